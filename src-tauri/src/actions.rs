@@ -0,0 +1,363 @@
+use crate::models::{Action, ActionParam};
+use serde_json::{json, Map, Value};
+use specta::datatype::{
+    DataType, EnumRepr, EnumType, EnumVariants, Field, GenericType, LiteralType, PrimitiveType,
+    StructFields, StructType,
+};
+use specta::{SpectaID, TypeCollection};
+use std::path::Path;
+use tauri_specta::{ExportContext, LanguageExt};
+
+// Every command the app registers, read from the same tauri-specta builder
+// that generates the frontend bindings, so a new command is listed as soon as
+// it's registered. Built once at startup and kept as managed state.
+pub struct Actions(Vec<Action>);
+
+impl Actions {
+    pub fn list(&self) -> Vec<Action> {
+        self.0.clone()
+    }
+}
+
+pub fn collect(builder: &tauri_specta::Builder) -> Actions {
+    let actions = builder
+        .export_str(Registry)
+        .and_then(|json| serde_json::from_str(&json).map_err(std::io::Error::other));
+    match actions {
+        Ok(actions) => Actions(actions),
+        Err(e) => {
+            log::warn!("Failed to build the action registry: {}", e);
+            Actions(Vec::new())
+        }
+    }
+}
+
+// A bindings "language" rendering the commands as a JSON list of actions
+struct Registry;
+
+impl LanguageExt for Registry {
+    type Error = std::io::Error;
+
+    fn render(&self, cfg: &ExportContext) -> Result<String, Self::Error> {
+        let mut actions: Vec<Action> = cfg
+            .commands
+            .iter()
+            .map(|function| Action {
+                id: function.name().to_string(),
+                title: title(function.name()),
+                params: function
+                    .args()
+                    .map(|(name, ty)| ActionParam {
+                        name: lower_camel_case(name),
+                        required: !matches!(ty, DataType::Nullable(_)),
+                        schema: Schemas::new(&cfg.type_map).schema(ty),
+                    })
+                    .collect(),
+            })
+            .collect();
+        actions.sort_by(|a, b| a.id.cmp(&b.id));
+        serde_json::to_string(&actions).map_err(std::io::Error::other)
+    }
+
+    fn format(&self, _path: &Path) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+// "create_note" -> "Create note"
+fn title(command: &str) -> String {
+    let words = command.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
+
+// Arguments are passed to invoke() in camelCase, e.g. `folder_id` as folderId
+fn lower_camel_case(name: &str) -> String {
+    let mut words = name.split('_').filter(|w| !w.is_empty());
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+// Turns specta types into JSON Schema. Named types are written inline with
+// their name as the title; one that contains itself is only named the second
+// time, so the schema stays finite.
+struct Schemas<'a> {
+    types: &'a TypeCollection,
+    expanding: Vec<SpectaID>,
+    // The generic arguments of the named type being written, as schemas
+    generics: Vec<(GenericType, Value)>,
+}
+
+impl<'a> Schemas<'a> {
+    fn new(types: &'a TypeCollection) -> Self {
+        Schemas {
+            types,
+            expanding: Vec::new(),
+            generics: Vec::new(),
+        }
+    }
+
+    fn schema(&mut self, ty: &DataType) -> Value {
+        match ty {
+            DataType::Any | DataType::Unknown => json!({}),
+            DataType::Primitive(primitive) => primitive_schema(primitive),
+            DataType::Literal(literal) => literal_schema(literal),
+            DataType::List(list) => {
+                let mut schema = json!({ "type": "array", "items": self.schema(list.ty()) });
+                if let Some(length) = list.length() {
+                    schema["minItems"] = json!(length);
+                    schema["maxItems"] = json!(length);
+                }
+                if list.unique() {
+                    schema["uniqueItems"] = json!(true);
+                }
+                schema
+            }
+            DataType::Map(map) => {
+                json!({ "type": "object", "additionalProperties": self.schema(map.value_ty()) })
+            }
+            DataType::Nullable(inner) => {
+                json!({ "anyOf": [self.schema(inner), { "type": "null" }] })
+            }
+            DataType::Struct(st) => self.struct_schema(st),
+            DataType::Enum(en) => self.enum_schema(en),
+            DataType::Tuple(tuple) => match tuple.elements().as_slice() {
+                [] => json!({ "type": "null" }),
+                elements => self.tuple_schema(elements),
+            },
+            DataType::Reference(reference) => {
+                let types = self.types;
+                let Some(named) = types.get(reference.sid()) else {
+                    return json!({ "title": reference.name() });
+                };
+                if self.expanding.contains(&reference.sid()) {
+                    return json!({ "title": named.name() });
+                }
+                // Arguments are resolved where the reference is made
+                let generics = reference
+                    .generics()
+                    .iter()
+                    .map(|(generic, ty)| (generic.clone(), self.schema(ty)))
+                    .collect();
+                let outer = std::mem::replace(&mut self.generics, generics);
+                self.expanding.push(reference.sid());
+                let mut schema = self.schema(&named.inner);
+                self.expanding.pop();
+                self.generics = outer;
+                if let Some(fields) = schema.as_object_mut() {
+                    fields.insert("title".into(), json!(named.name()));
+                }
+                schema
+            }
+            DataType::Generic(generic) => self
+                .generics
+                .iter()
+                .find(|(g, _)| g == generic)
+                .map(|(_, schema)| schema.clone())
+                .unwrap_or_else(|| json!({})),
+        }
+    }
+
+    fn tuple_schema(&mut self, elements: &[DataType]) -> Value {
+        let items: Vec<Value> = elements.iter().map(|ty| self.schema(ty)).collect();
+        json!({
+            "type": "array",
+            "prefixItems": items,
+            "minItems": elements.len(),
+            "maxItems": elements.len(),
+        })
+    }
+
+    // An object of the named fields, `tag` set to `variant` when given
+    fn object_schema<'f>(
+        &mut self,
+        fields: impl IntoIterator<Item = (&'f str, &'f Field)>,
+        tag: Option<(&str, &str)>,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut flattened = Vec::new();
+        if let Some((tag, variant)) = tag {
+            properties.insert(tag.into(), json!({ "const": variant }));
+            required.push(json!(tag));
+        }
+        for (name, field) in fields {
+            let Some(ty) = field.ty() else {
+                continue;
+            };
+            if field.flatten() {
+                flattened.push(self.schema(ty));
+                continue;
+            }
+            properties.insert(name.into(), self.schema(ty));
+            if !field.optional() {
+                required.push(json!(name));
+            }
+        }
+
+        let object = json!({ "type": "object", "properties": properties, "required": required });
+        if flattened.is_empty() {
+            object
+        } else {
+            flattened.insert(0, object);
+            json!({ "allOf": flattened })
+        }
+    }
+
+    fn struct_schema(&mut self, st: &StructType) -> Value {
+        match st.fields() {
+            StructFields::Unit => json!({ "type": "null" }),
+            StructFields::Unnamed(unnamed) => {
+                let types: Vec<DataType> = unnamed
+                    .fields()
+                    .iter()
+                    .filter_map(|f| f.ty().cloned())
+                    .collect();
+                match types.as_slice() {
+                    [ty] => self.schema(ty),
+                    types => self.tuple_schema(types),
+                }
+            }
+            StructFields::Named(named) => {
+                let tag = st.tag().map(|tag| (tag.as_ref(), st.name().as_ref()));
+                let fields = named
+                    .fields()
+                    .iter()
+                    .map(|(name, field)| (name.as_ref(), field));
+                self.object_schema(fields, tag)
+            }
+        }
+    }
+
+    fn enum_schema(&mut self, en: &EnumType) -> Value {
+        let variants: Vec<_> = en.variants().iter().filter(|(_, v)| !v.skip()).collect();
+
+        // Plain choices, like the text enums, are a list of strings
+        let all_unit = variants
+            .iter()
+            .all(|(_, v)| matches!(v.inner(), EnumVariants::Unit));
+        if all_unit && matches!(en.repr(), EnumRepr::External) {
+            let names: Vec<&str> = variants.iter().map(|(name, _)| name.as_ref()).collect();
+            return json!({ "type": "string", "enum": names });
+        }
+
+        let schemas: Vec<Value> = variants
+            .iter()
+            .map(|(name, variant)| self.variant_schema(en.repr(), name, variant.inner()))
+            .collect();
+        json!({ "oneOf": schemas })
+    }
+
+    fn variant_schema(&mut self, repr: &EnumRepr, name: &str, inner: &EnumVariants) -> Value {
+        let content = match inner {
+            EnumVariants::Unit => None,
+            EnumVariants::Named(named) => {
+                let fields = named
+                    .fields()
+                    .iter()
+                    .map(|(name, field)| (name.as_ref(), field));
+                // Internally tagged, the tag sits among the variant's fields
+                if let EnumRepr::Internal { tag } = repr {
+                    return self.object_schema(fields, Some((tag.as_ref(), name)));
+                }
+                Some(self.object_schema(fields, None))
+            }
+            EnumVariants::Unnamed(unnamed) => {
+                let types: Vec<DataType> = unnamed
+                    .fields()
+                    .iter()
+                    .filter_map(|f| f.ty().cloned())
+                    .collect();
+                Some(match types.as_slice() {
+                    [ty] => self.schema(ty),
+                    types => self.tuple_schema(types),
+                })
+            }
+        };
+
+        match (repr, content) {
+            (EnumRepr::Untagged, None) => json!({ "type": "null" }),
+            (EnumRepr::Untagged, Some(content)) => content,
+            (EnumRepr::External, None) => json!({ "const": name }),
+            (EnumRepr::External, Some(content)) => json!({
+                "type": "object",
+                "properties": { name: content },
+                "required": [name],
+            }),
+            (EnumRepr::Internal { tag }, None) => json!({
+                "type": "object",
+                "properties": { tag.to_string(): { "const": name } },
+                "required": [tag],
+            }),
+            (EnumRepr::Internal { tag }, Some(content)) => json!({
+                "allOf": [
+                    {
+                        "type": "object",
+                        "properties": { tag.to_string(): { "const": name } },
+                        "required": [tag],
+                    },
+                    content,
+                ],
+            }),
+            (EnumRepr::Adjacent { tag, content: key }, content) => {
+                let mut properties = Map::new();
+                properties.insert(tag.to_string(), json!({ "const": name }));
+                let mut required = vec![json!(tag)];
+                if let Some(content) = content {
+                    properties.insert(key.to_string(), content);
+                    required.push(json!(key));
+                }
+                json!({ "type": "object", "properties": properties, "required": required })
+            }
+        }
+    }
+}
+
+fn primitive_schema(primitive: &PrimitiveType) -> Value {
+    match primitive {
+        PrimitiveType::i8
+        | PrimitiveType::i16
+        | PrimitiveType::i32
+        | PrimitiveType::i64
+        | PrimitiveType::i128
+        | PrimitiveType::isize => json!({ "type": "integer" }),
+        PrimitiveType::u8
+        | PrimitiveType::u16
+        | PrimitiveType::u32
+        | PrimitiveType::u64
+        | PrimitiveType::u128
+        | PrimitiveType::usize => json!({ "type": "integer", "minimum": 0 }),
+        PrimitiveType::f32 | PrimitiveType::f64 => json!({ "type": "number" }),
+        PrimitiveType::bool => json!({ "type": "boolean" }),
+        PrimitiveType::char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        PrimitiveType::String => json!({ "type": "string" }),
+    }
+}
+
+fn literal_schema(literal: &LiteralType) -> Value {
+    match literal {
+        LiteralType::i8(v) => json!({ "const": v }),
+        LiteralType::i16(v) => json!({ "const": v }),
+        LiteralType::i32(v) => json!({ "const": v }),
+        LiteralType::u8(v) => json!({ "const": v }),
+        LiteralType::u16(v) => json!({ "const": v }),
+        LiteralType::u32(v) => json!({ "const": v }),
+        LiteralType::f32(v) => json!({ "const": v }),
+        LiteralType::f64(v) => json!({ "const": v }),
+        LiteralType::bool(v) => json!({ "const": v }),
+        LiteralType::String(v) => json!({ "const": v }),
+        LiteralType::char(v) => json!({ "const": v.to_string() }),
+        LiteralType::None => json!({ "type": "null" }),
+        _ => json!({}),
+    }
+}
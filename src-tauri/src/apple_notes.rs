@@ -0,0 +1,537 @@
+use crate::attachments;
+use crate::commands::{insert_note, store_attachment};
+use crate::db::Database;
+use crate::folders;
+use crate::formats::{Format, Importer};
+use crate::import;
+use crate::jobs::JobContext;
+use crate::models::{FormatTarget, ImportSummary, NoteCreate};
+use crate::render;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use md5::{Digest, Md5};
+use regex::{Captures, Regex};
+use rusqlite::Connection;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+use url::Url;
+
+// Apple Notes as exported by tools like Exporter: a folder per Apple Notes
+// folder, holding a file per note (HTML or Markdown, with its attachments
+// alongside) or ENEX files. Notes are converted to Markdown and the folders
+// recreated under the folder picked for the import; an ENEX file's notes go
+// in a folder named after it.
+pub struct AppleNotes;
+
+impl Importer for AppleNotes {
+    fn format(&self) -> Format {
+        Format {
+            id: "apple_notes",
+            name: "Apple Notes export",
+            target: FormatTarget::Folder,
+            extensions: &[],
+            preview: true,
+        }
+    }
+
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        folder_id: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        let db = ctx.app().state::<Database>();
+        for root in paths {
+            let export = scan(root);
+            for (source, problem) in &export.problems {
+                ctx.item_failed(source, problem);
+            }
+
+            let total = export.notes.len();
+            for (index, note) in export.notes.iter().enumerate() {
+                ctx.checkpoint()?;
+                let imported = {
+                    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+                    import_note(&tx, &db.data_dir, note, folder_id)
+                        .and_then(|created| tx.commit().map(|_| created).map_err(|e| e.to_string()))
+                };
+                match imported {
+                    Ok(true) => ctx.created(),
+                    Ok(false) => ctx.skipped(),
+                    Err(e) => ctx.item_failed(&note.source, &e),
+                }
+                ctx.progress(index + 1, total, &note.title);
+            }
+        }
+        Ok(None)
+    }
+
+    fn preview(
+        &self,
+        conn: &Connection,
+        paths: &[PathBuf],
+        folder_id: Option<&str>,
+    ) -> Result<ImportSummary, String> {
+        let mut summary = ImportSummary::default();
+        for root in paths {
+            let export = scan(root);
+            let problems = export.problems.iter();
+            summary
+                .problems
+                .extend(problems.map(|(source, problem)| format!("{}: {}", source, problem)));
+
+            let mut new_folders: HashSet<&[String]> = HashSet::new();
+            for note in &export.notes {
+                let duplicate = match find_folder(conn, folder_id, &note.folder)? {
+                    Ok(parent) => import::imported_before(
+                        conn,
+                        parent.as_deref(),
+                        &note.title,
+                        &note.created_at,
+                    )?,
+                    // Everything from the first missing folder down is new
+                    Err(missing) => {
+                        new_folders
+                            .extend((missing..note.folder.len()).map(|i| &note.folder[..=i]));
+                        false
+                    }
+                };
+                if duplicate {
+                    summary.duplicates += 1;
+                } else {
+                    summary.notes += 1;
+                    summary.attachments += note.attachments.len() as u32;
+                }
+            }
+            summary.folders += new_folders.len() as u32;
+        }
+        Ok(summary)
+    }
+}
+
+// ============ Reading ============
+
+struct Export {
+    notes: Vec<ExportedNote>,
+    // Files that couldn't be read, and why
+    problems: Vec<(String, String)>,
+}
+
+struct ExportedNote {
+    // Where the note came from within the export, for reports
+    source: String,
+    // Folder names below the export's top, e.g. ["Work", "Projects"]
+    folder: Vec<String>,
+    title: String,
+    body: Body,
+    tags: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    // What the body's links and images point at, by the reference as written
+    attachments: HashMap<String, Attached>,
+}
+
+enum Body {
+    Html(String),
+    Markdown(String),
+}
+
+enum Attached {
+    File(PathBuf),
+    Data { mime_type: String, bytes: Vec<u8> },
+}
+
+// `src="..."` and `href="..."`
+fn html_ref() -> &'static Regex {
+    static HTML_REF: OnceLock<Regex> = OnceLock::new();
+    HTML_REF.get_or_init(|| {
+        Regex::new(r#"(?i)\b(?:src|href)\s*=\s*"([^"]*)""#).expect("valid html ref regex")
+    })
+}
+
+// `](target)` and `](<target>)`, whose target can have spaces
+fn markdown_ref() -> &'static Regex {
+    static MARKDOWN_REF: OnceLock<Regex> = OnceLock::new();
+    MARKDOWN_REF.get_or_init(|| {
+        Regex::new(r"\]\(\s*(?:<([^<>\n]+)>|([^)\s]+))").expect("valid markdown ref regex")
+    })
+}
+
+// ENEX's reference to a resource by its MD5 hash
+fn en_media() -> &'static Regex {
+    static EN_MEDIA: OnceLock<Regex> = OnceLock::new();
+    EN_MEDIA.get_or_init(|| {
+        Regex::new(r"(?is)<en-media\b([^>]*?)/?>(?:\s*</en-media>)?").expect("valid en-media regex")
+    })
+}
+
+// The reference a match of html_ref or markdown_ref found
+fn reference<'t>(found: &Captures<'t>) -> &'t str {
+    found
+        .iter()
+        .skip(1)
+        .flatten()
+        .next()
+        .map_or("", |group| group.as_str())
+}
+
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE
+        .get_or_init(|| Regex::new(r#"([\w-]+)\s*=\s*"([^"]*)""#).expect("valid attribute regex"));
+    attribute
+        .captures_iter(attributes)
+        .find(|found| found[1].eq_ignore_ascii_case(name))
+        .map(|found| found[2].to_string())
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Hidden files and symlinks are skipped, as are files that aren't notes,
+// like the attachments the notes link to
+fn scan(root: &Path) -> Export {
+    let mut export = Export {
+        notes: Vec::new(),
+        problems: Vec::new(),
+    };
+    match root.canonicalize() {
+        Ok(root) => walk(&root, &root, &[], &mut export),
+        Err(e) => export
+            .problems
+            .push((root.to_string_lossy().into_owned(), e.to_string())),
+    }
+    export
+}
+
+fn walk(root: &Path, dir: &Path, folder: &[String], export: &mut Export) {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            export.problems.push((relative(root, dir), e.to_string()));
+            return;
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // A symlink could lead outside the export, or round in a loop
+        if name.starts_with('.') || path.is_symlink() {
+            continue;
+        }
+        if path.is_dir() {
+            let mut subfolder = folder.to_vec();
+            subfolder.push(name);
+            walk(root, &path, &subfolder, export);
+            continue;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let read = match extension.as_str() {
+            "html" | "htm" => read_note_file(root, &path, folder, false).map(|note| vec![note]),
+            "md" | "markdown" | "txt" => {
+                read_note_file(root, &path, folder, true).map(|note| vec![note])
+            }
+            "enex" => read_enex(root, &path, folder),
+            _ => continue,
+        };
+        match read {
+            Ok(notes) => export.notes.extend(notes),
+            Err(e) => export.problems.push((relative(root, &path), e)),
+        }
+    }
+}
+
+// Exporters give the files the note's dates
+fn file_times(path: &Path) -> (DateTime<Utc>, DateTime<Utc>) {
+    let metadata = fs::metadata(path).ok();
+    let updated_at = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(Utc::now);
+    let created_at = metadata
+        .and_then(|m| m.created().ok())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or(updated_at);
+    (created_at, updated_at)
+}
+
+fn read_note_file(
+    root: &Path,
+    path: &Path,
+    folder: &[String],
+    markdown: bool,
+) -> Result<ExportedNote, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let dir = path.parent().unwrap_or(root);
+    let references = if markdown { markdown_ref() } else { html_ref() };
+    let attachments = references
+        .captures_iter(&text)
+        .filter_map(|found| {
+            let reference = reference(&found).to_string();
+            resolve(root, dir, &reference).map(|attached| (reference, attached))
+        })
+        .collect();
+
+    let (created_at, updated_at) = file_times(path);
+    Ok(ExportedNote {
+        source: relative(root, path),
+        folder: folder.to_vec(),
+        title: import::note_title(path),
+        body: if markdown {
+            Body::Markdown(text)
+        } else {
+            Body::Html(text)
+        },
+        tags: Vec::new(),
+        created_at,
+        updated_at,
+        attachments,
+    })
+}
+
+// A data URI, or a file inside the export; links elsewhere are left as they are
+fn resolve(root: &Path, dir: &Path, reference: &str) -> Option<Attached> {
+    if let Some(data) = reference.strip_prefix("data:") {
+        let (header, payload) = data.split_once(',')?;
+        let mime_type = header.strip_suffix(";base64")?;
+        let bytes = BASE64.decode(payload.trim()).ok()?;
+        return Some(Attached::Data {
+            mime_type: match mime_type {
+                "" => "application/octet-stream".to_string(),
+                mime_type => mime_type.to_string(),
+            },
+            bytes,
+        });
+    }
+
+    // Relative and percent-encoded, e.g. `attachments/My%20Image.png`
+    let url = Url::from_directory_path(dir).ok()?.join(reference).ok()?;
+    if url.scheme() != "file" {
+        return None;
+    }
+    let path = url.to_file_path().ok()?.canonicalize().ok()?;
+    (path.starts_with(root) && path.is_file()).then_some(Attached::File(path))
+}
+
+fn enex_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+// Resources are matched to the note's en-media elements by MD5 hash and put
+// in their place as images or links
+fn read_enex(root: &Path, path: &Path, folder: &[String]) -> Result<Vec<ExportedNote>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document =
+        roxmltree::Document::parse_with_options(&text, options).map_err(|e| e.to_string())?;
+
+    let mut folder = folder.to_vec();
+    folder.push(import::note_title(path));
+    let source = relative(root, path);
+    let (file_created, file_updated) = file_times(path);
+
+    let mut notes = Vec::new();
+    for note in document
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("note"))
+    {
+        let field = |name: &str| {
+            note.children()
+                .find(|n| n.has_tag_name(name))
+                .and_then(|n| n.text())
+                .unwrap_or_default()
+        };
+
+        let mut attachments = HashMap::new();
+        let mut file_names = HashMap::new();
+        for resource in note.children().filter(|n| n.has_tag_name("resource")) {
+            let child = |name: &str| resource.descendants().find(|n| n.has_tag_name(name));
+            let Some(data) = child("data").and_then(|n| n.text()) else {
+                continue;
+            };
+            let Ok(bytes) = BASE64.decode(data.split_whitespace().collect::<String>()) else {
+                continue;
+            };
+            let hash = format!("{:x}", Md5::digest(&bytes));
+            if let Some(file_name) = child("file-name").and_then(|n| n.text()) {
+                file_names.insert(hash.clone(), file_name.to_string());
+            }
+            let mime_type = child("mime")
+                .and_then(|n| n.text())
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            attachments.insert(hash, Attached::Data { mime_type, bytes });
+        }
+
+        let html = en_media().replace_all(field("content"), |found: &Captures| {
+            let Some(hash) = attribute(&found[1], "hash") else {
+                return String::new();
+            };
+            let mime_type = attribute(&found[1], "type").unwrap_or_default();
+            if mime_type.starts_with("image/") {
+                format!("<img src=\"{}\">", hash)
+            } else {
+                let name = file_names.get(&hash).map_or("Attachment", String::as_str);
+                format!("<a href=\"{}\">{}</a>", hash, render::escape_html(name))
+            }
+        });
+
+        let created_at = enex_time(field("created")).unwrap_or(file_created);
+        notes.push(ExportedNote {
+            source: source.clone(),
+            folder: folder.clone(),
+            title: match field("title").trim() {
+                "" => "Untitled".to_string(),
+                title => title.to_string(),
+            },
+            body: Body::Html(html.into_owned()),
+            tags: note
+                .children()
+                .filter(|n| n.has_tag_name("tag"))
+                .filter_map(|n| n.text())
+                .map(String::from)
+                .collect(),
+            created_at,
+            updated_at: enex_time(field("updated")).unwrap_or(file_updated.max(created_at)),
+            attachments,
+        });
+    }
+    Ok(notes)
+}
+
+// ============ Importing ============
+
+// Ok with the folder's id, None being the top level, or Err with the index
+// of the first folder along the way that doesn't exist yet
+fn find_folder(
+    conn: &Connection,
+    folder_id: Option<&str>,
+    path: &[String],
+) -> Result<Result<Option<String>, usize>, String> {
+    let mut current = folder_id.map(String::from);
+    for (index, name) in path.iter().enumerate() {
+        match folders::child(conn, current.as_deref(), name)? {
+            Some(folder) => current = Some(folder.id),
+            None => return Ok(Err(index)),
+        }
+    }
+    Ok(Ok(current))
+}
+
+fn ensure_folder(
+    conn: &Connection,
+    folder_id: Option<&str>,
+    path: &[String],
+) -> Result<Option<String>, String> {
+    let mut current = folder_id.map(String::from);
+    for name in path {
+        current = Some(folders::child_or_create(conn, current.as_deref(), name)?.id);
+    }
+    Ok(current)
+}
+
+// Returns false when the note is already there from an earlier import
+fn import_note(
+    conn: &Connection,
+    data_dir: &Path,
+    note: &ExportedNote,
+    folder_id: Option<&str>,
+) -> Result<bool, String> {
+    let parent = ensure_folder(conn, folder_id, &note.folder)?;
+    if import::imported_before(conn, parent.as_deref(), &note.title, &note.created_at)? {
+        return Ok(false);
+    }
+
+    let created = insert_note(
+        conn,
+        NoteCreate {
+            title: Some(note.title.clone()),
+            content: None,
+            folder_id: parent,
+            tags: Some(note.tags.clone()),
+            aliases: None,
+        },
+    )?;
+
+    let mut urls: HashMap<&str, String> = HashMap::new();
+    for (reference, attached) in &note.attachments {
+        let (mime_type, bytes): (String, Cow<[u8]>) = match attached {
+            Attached::File(path) => {
+                let extension = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or_default();
+                let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                (
+                    attachments::mime_for_extension(extension).to_string(),
+                    bytes.into(),
+                )
+            }
+            Attached::Data { mime_type, bytes } => (mime_type.clone(), bytes.into()),
+        };
+        let attachment = store_attachment(
+            conn,
+            data_dir,
+            created.id.clone(),
+            attachments::kind_for_mime(&mime_type),
+            mime_type,
+            &bytes,
+            None,
+        )?;
+        urls.insert(
+            reference,
+            format!("attachment://localhost/{}", attachment.id),
+        );
+    }
+
+    let content = match &note.body {
+        Body::Html(html) => render::html_to_markdown(&link_attachments(html_ref(), html, &urls)),
+        Body::Markdown(markdown) => link_attachments(markdown_ref(), markdown, &urls).into_owned(),
+    };
+    import::finish_note(
+        conn,
+        &created.id,
+        &content,
+        &note.created_at,
+        &note.updated_at,
+    )?;
+    Ok(true)
+}
+
+// Points the references to stored attachments at them
+fn link_attachments<'a>(
+    references: &Regex,
+    text: &'a str,
+    urls: &HashMap<&str, String>,
+) -> Cow<'a, str> {
+    references.replace_all(text, |found: &Captures| match urls.get(reference(found)) {
+        Some(url) => found[0].replacen(reference(found), url, 1),
+        None => found[0].to_string(),
+    })
+}
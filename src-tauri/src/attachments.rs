@@ -0,0 +1,283 @@
+use crate::db::Database;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager};
+
+pub const ATTACHMENTS_DIR: &str = "attachments";
+const MAX_RANGE_CHUNK: u64 = 4 * 1024 * 1024;
+
+pub fn attachments_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(ATTACHMENTS_DIR)
+}
+
+pub fn attachment_path(data_dir: &Path, file_name: &str) -> PathBuf {
+    attachments_dir(data_dir).join(file_name)
+}
+
+pub fn write_blob(data_dir: &Path, file_name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    let dir = attachments_dir(data_dir);
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(file_name);
+    fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+pub fn remove_blob(data_dir: &Path, file_name: &str) -> std::io::Result<()> {
+    match fs::remove_file(attachment_path(data_dir, file_name)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Blob for `bytes`, shared with any attachment that already has the same
+// content. Returns the file name and whether a new blob was written.
+pub fn store_blob(
+    conn: &Connection,
+    data_dir: &Path,
+    hash: &str,
+    extension: &str,
+    bytes: &[u8],
+) -> Result<(String, bool), String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT file_name FROM attachments WHERE content_hash = ?1 LIMIT 1",
+            params![hash],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(file_name) = existing {
+        if attachment_path(data_dir, &file_name).exists() {
+            return Ok((file_name, false));
+        }
+    }
+
+    let file_name = format!("{}.{}", hash, extension);
+    let written = !attachment_path(data_dir, &file_name).exists();
+    if written {
+        write_blob(data_dir, &file_name, bytes).map_err(|e| e.to_string())?;
+    }
+    Ok((file_name, written))
+}
+
+// Removes each blob no attachment row points at any more. Call after the rows
+// are gone; blobs are shared between attachments with the same content.
+// Returns how many bytes were removed from disk.
+pub fn release_blobs(conn: &Connection, data_dir: &Path, file_names: &[String]) -> Result<i64, String> {
+    let mut removed = 0;
+    for file_name in file_names {
+        let referenced: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM attachments WHERE file_name = ?1)",
+                params![file_name],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !referenced {
+            let path = attachment_path(data_dir, file_name);
+            let size = fs::metadata(&path).map(|m| m.len() as i64).unwrap_or(0);
+            remove_blob(data_dir, file_name).map_err(|e| e.to_string())?;
+            removed += size;
+        }
+    }
+    Ok(removed)
+}
+
+pub fn extension_for_mime(mime_type: &str) -> &'static str {
+    match mime_type.split(';').next().unwrap_or_default().trim() {
+        "audio/webm" => "webm",
+        "audio/ogg" => "ogg",
+        "audio/mp4" | "audio/x-m4a" => "m4a",
+        "audio/mpeg" => "mp3",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "video/mp4" => "mp4",
+        _ => "bin",
+    }
+}
+
+// Reads the duration straight from a RIFF/WAVE header. Compressed formats
+// (webm/ogg/m4a) need a demuxer, so their duration comes from the recorder.
+pub fn wav_duration_ms(bytes: &[u8]) -> Option<i64> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut offset = 12;
+    let mut byte_rate: Option<u32> = None;
+
+    while offset + 8 <= bytes.len() {
+        let chunk_id = &bytes[offset..offset + 4];
+        let chunk_size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8;
+
+        if chunk_id == b"fmt " && body + 12 <= bytes.len() {
+            byte_rate = Some(u32::from_le_bytes(bytes[body + 8..body + 12].try_into().ok()?));
+        } else if chunk_id == b"data" {
+            let rate = byte_rate.filter(|r| *r > 0)?;
+            let data_len = chunk_size.min(bytes.len() - body) as i64;
+            return Some(data_len * 1000 / rate as i64);
+        }
+
+        // Chunks are word aligned
+        offset = body + chunk_size + (chunk_size & 1);
+    }
+
+    None
+}
+
+// Handler for the `attachment://` URI scheme. The path is the attachment id,
+// e.g. `attachment://localhost/att_...` (`http://attachment.localhost/...` on Windows).
+// Honors `Range` headers so media elements can seek without loading the whole file.
+pub fn serve(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    // The database failed to open and the recovery window is up
+    let Some(db) = app.try_state::<Database>() else {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let id = request.uri().path().trim_start_matches('/');
+
+    let row: Option<(String, String)> = db.conn.lock().ok().and_then(|conn| {
+        conn.query_row(
+            "SELECT file_name, mime_type FROM attachments WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok()
+    });
+
+    let Some((file_name, mime_type)) = row else {
+        return error_response(StatusCode::NOT_FOUND);
+    };
+
+    let Ok(mut file) = File::open(attachment_path(&db.data_dir, &file_name)) else {
+        return error_response(StatusCode::NOT_FOUND);
+    };
+    let Ok(len) = file.metadata().map(|m| m.len()) else {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+
+    let range_header = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+
+    let Some(range_header) = range_header else {
+        let mut bytes = Vec::with_capacity(len as usize);
+        if file.read_to_end(&mut bytes).is_err() {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, mime_type)
+            .header(header::CONTENT_LENGTH, bytes.len())
+            .header(header::ACCEPT_RANGES, "bytes")
+            .body(bytes)
+            .unwrap_or_else(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR));
+    };
+
+    let Some((start, end)) = parse_range(range_header, len) else {
+        return Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new())
+            .unwrap_or_else(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR));
+    };
+
+    let mut bytes = Vec::with_capacity((end - start + 1) as usize);
+    let read = file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| file.by_ref().take(end - start + 1).read_to_end(&mut bytes));
+    if read.is_err() {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, mime_type)
+        .header(header::CONTENT_LENGTH, bytes.len())
+        .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .body(bytes)
+        .unwrap_or_else(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+// Parses a single `bytes=` range into inclusive offsets. Open-ended ranges are
+// capped at MAX_RANGE_CHUNK; the client asks for the rest as playback advances.
+fn parse_range(value: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = value.strip_prefix("bytes=")?.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len.checked_sub(1)?)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, start.saturating_add(MAX_RANGE_CHUNK - 1).min(len.checked_sub(1)?))
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            (start, end.min(len.checked_sub(1)?))
+        }
+    };
+
+    if start > end || start >= len {
+        return None;
+    }
+    Some((start, end))
+}
+
+pub fn mime_for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "webm" => "audio/webm",
+        "ogg" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "txt" | "md" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+// Attachment kind stored for a MIME type
+pub fn kind_for_mime(mime_type: &str) -> &'static str {
+    match mime_type.split('/').next().unwrap_or_default() {
+        "image" => "image",
+        "audio" => "audio",
+        "video" => "video",
+        _ => "file",
+    }
+}
+
+fn error_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
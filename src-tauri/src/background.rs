@@ -0,0 +1,74 @@
+use crate::models::{ActivityLevel, BackgroundActivity, BackgroundSubsystem};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// How often a paused subsystem looks to see whether it may carry on
+const RESUME_CHECK: Duration = Duration::from_secs(5);
+
+// How much runs in the background, e.g. turned down on battery. Only kept
+// for the session; every launch starts at full activity.
+pub struct Activity {
+    level: Mutex<ActivityLevel>,
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Activity {
+            level: Mutex::new(ActivityLevel::Full),
+        }
+    }
+}
+
+impl Activity {
+    pub fn level(&self) -> ActivityLevel {
+        self.level
+            .lock()
+            .map(|level| *level)
+            .unwrap_or(ActivityLevel::Full)
+    }
+
+    pub fn status(&self) -> BackgroundActivity {
+        let level = self.level();
+        BackgroundActivity {
+            level,
+            paused: paused_at(level),
+        }
+    }
+
+    pub fn set_level(&self, level: ActivityLevel) {
+        if let Ok(mut current) = self.level.lock() {
+            *current = level;
+        }
+    }
+}
+
+fn paused_at(level: ActivityLevel) -> Vec<BackgroundSubsystem> {
+    use BackgroundSubsystem::*;
+    match level {
+        ActivityLevel::Full => Vec::new(),
+        ActivityLevel::Reduced => vec![Feeds, Email, LanSync, SystemSearch, Maintenance],
+        ActivityLevel::Paused => vec![
+            Feeds,
+            Email,
+            LanSync,
+            SystemSearch,
+            Mirror,
+            WatchedFolders,
+            Reminders,
+            Maintenance,
+        ],
+    }
+}
+
+pub fn is_paused(app: &AppHandle, subsystem: BackgroundSubsystem) -> bool {
+    paused_at(app.state::<Activity>().level()).contains(&subsystem)
+}
+
+// Called at the top of each background loop, so a subsystem picks up again
+// within a few seconds of being resumed rather than after its own interval
+pub async fn wait_while_paused(app: &AppHandle, subsystem: BackgroundSubsystem) {
+    while is_paused(app, subsystem) {
+        tokio::time::sleep(RESUME_CHECK).await;
+    }
+}
@@ -0,0 +1,140 @@
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use url::Url;
+
+pub struct Article {
+    pub title: String,
+    pub markdown: String,
+}
+
+pub async fn fetch_article(url: &str) -> Result<Article, String> {
+    let base = Url::parse(url).map_err(|e| e.to_string())?;
+
+    let html = reqwest::Client::new()
+        .get(base.clone())
+        .header(reqwest::header::USER_AGENT, "Mozilla/5.0 (compatible; Voyena)")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(extract_article(&html, &base))
+}
+
+// Readability-style extraction: every paragraph scores its parent (and half
+// its grandparent) by text length, discounted by link density. The highest
+// scoring container is taken as the article body.
+pub fn extract_article(html: &str, base: &Url) -> Article {
+    let document = Html::parse_document(html);
+
+    let title = meta_content(&document, "meta[property='og:title']")
+        .or_else(|| first_text(&document, "title"))
+        .or_else(|| first_text(&document, "h1"))
+        .unwrap_or_else(|| base.to_string());
+
+    let body_html = preferred_container(&document)
+        .or_else(|| best_scored_container(&document))
+        .map(|el| el.inner_html())
+        .unwrap_or_default();
+
+    let markdown = absolutize_links(&html2md::parse_html(&strip_chrome(&body_html)), base);
+
+    Article {
+        title: title.trim().to_string(),
+        markdown: markdown.trim().to_string(),
+    }
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("valid selector")
+}
+
+fn meta_content(document: &Html, css: &str) -> Option<String> {
+    document
+        .select(&selector(css))
+        .next()
+        .and_then(|el| el.value().attr("content"))
+        .map(|c| c.to_string())
+        .filter(|c| !c.trim().is_empty())
+}
+
+fn first_text(document: &Html, css: &str) -> Option<String> {
+    document
+        .select(&selector(css))
+        .next()
+        .map(|el| el.text().collect::<String>())
+        .filter(|t| !t.trim().is_empty())
+}
+
+// Pages that mark up their content semantically don't need scoring
+fn preferred_container(document: &Html) -> Option<ElementRef<'_>> {
+    document
+        .select(&selector("article, [itemprop='articleBody'], main"))
+        .find(|el| text_len(el) > 500)
+}
+
+fn best_scored_container(document: &Html) -> Option<ElementRef<'_>> {
+    let mut scores: HashMap<_, f64> = HashMap::new();
+
+    for paragraph in document.select(&selector("p, pre, blockquote")) {
+        let length = text_len(&paragraph);
+        if length < 25 {
+            continue;
+        }
+        let score = 1.0 + (length as f64 / 100.0).min(3.0) + length as f64 / 1000.0;
+
+        if let Some(parent) = paragraph.parent().and_then(ElementRef::wrap) {
+            *scores.entry(parent.id()).or_default() += score;
+            if let Some(grandparent) = parent.parent().and_then(ElementRef::wrap) {
+                *scores.entry(grandparent.id()).or_default() += score / 2.0;
+            }
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let el = document.tree.get(id).and_then(ElementRef::wrap)?;
+            Some((el, score * (1.0 - link_density(&el))))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(el, _)| el)
+}
+
+fn text_len(el: &ElementRef) -> usize {
+    el.text().map(|t| t.trim().len()).sum()
+}
+
+fn link_density(el: &ElementRef) -> f64 {
+    let total = text_len(el);
+    if total == 0 {
+        return 1.0;
+    }
+    let links: usize = el.select(&selector("a")).map(|a| text_len(&a)).sum();
+    links as f64 / total as f64
+}
+
+// Drops navigation, scripts and forms that sit inside the chosen container
+fn strip_chrome(html: &str) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"(?is)<(script|style|nav|aside|footer|form|noscript|iframe)\b.*?</(script|style|nav|aside|footer|form|noscript|iframe)>")
+            .expect("valid chrome regex")
+    });
+    re.replace_all(html, "").into_owned()
+}
+
+fn absolutize_links(markdown: &str, base: &Url) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"\]\(([^)\s]+)").expect("valid link regex"));
+    re.replace_all(markdown, |caps: &Captures| match base.join(&caps[1]) {
+        Ok(url) => format!("]({}", url),
+        Err(_) => caps[0].to_string(),
+    })
+    .into_owned()
+}
@@ -0,0 +1,191 @@
+#[cfg(desktop)]
+use crate::daily_notes;
+#[cfg(desktop)]
+use crate::db::Database;
+#[cfg(desktop)]
+use crate::locale;
+use crate::models::ClipboardCaptureSettings;
+#[cfg(desktop)]
+use crate::models::ClipboardCaptured;
+#[cfg(desktop)]
+use crate::render;
+#[cfg(desktop)]
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+#[cfg(desktop)]
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+pub const CLIPBOARD_CAPTURE_SETTING: &str = "clipboard_capture";
+
+pub const CAPTURED_EVENT: &str = "clipboard:captured";
+
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+D";
+
+pub fn load_settings(conn: &Connection) -> Result<ClipboardCaptureSettings, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![CLIPBOARD_CAPTURE_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        None => Ok(ClipboardCaptureSettings {
+            enabled: false,
+            shortcut: DEFAULT_SHORTCUT.to_string(),
+        }),
+    }
+}
+
+pub fn save_settings(conn: &Connection, settings: &ClipboardCaptureSettings) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            CLIPBOARD_CAPTURE_SETTING,
+            serde_json::to_string(settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Replaces whatever shortcut was registered before. Fails on a shortcut that
+// doesn't parse or that another app already holds.
+#[cfg(desktop)]
+pub fn register(app: &AppHandle, settings: &ClipboardCaptureSettings) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    if !settings.enabled {
+        return Ok(());
+    }
+    shortcuts
+        .on_shortcut(settings.shortcut.as_str(), |app, _, event| {
+            if event.state() == ShortcutState::Pressed {
+                let app = app.clone();
+                tauri::async_runtime::spawn_blocking(move || capture(&app));
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(mobile)]
+pub fn register(
+    _app: &tauri::AppHandle,
+    _settings: &ClipboardCaptureSettings,
+) -> Result<(), String> {
+    Err("Clipboard capture isn't available on this device".into())
+}
+
+// At startup, once the database is open
+#[cfg(desktop)]
+pub fn register_saved(app: &AppHandle) {
+    let db = app.state::<Database>();
+    let settings = db
+        .conn
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| load_settings(&conn));
+    if let Err(e) = settings.and_then(|settings| register(app, &settings)) {
+        log::warn!("Failed to register the clipboard shortcut: {}", e);
+    }
+}
+
+#[cfg(mobile)]
+pub fn register_saved(_app: &tauri::AppHandle) {}
+
+// Runs off the main thread for each press. The frontend confirms a capture
+// with a notification when the clipboard:captured event arrives.
+#[cfg(desktop)]
+fn capture(app: &AppHandle) {
+    // Asked first, while the app the text came from is still in front
+    let source_app = frontmost_app();
+    match append_clipboard(app, source_app) {
+        Ok(Some(captured)) => {
+            if let Err(e) = app.emit(CAPTURED_EVENT, captured) {
+                log::warn!("Failed to emit {}: {}", CAPTURED_EVENT, e);
+            }
+        }
+        Ok(None) => log::info!("Nothing on the clipboard to capture"),
+        Err(e) => log::warn!("Failed to capture the clipboard: {}", e),
+    }
+}
+
+#[cfg(desktop)]
+fn append_clipboard(
+    app: &AppHandle,
+    source_app: Option<String>,
+) -> Result<Option<ClipboardCaptured>, String> {
+    let Some(text) = read_clipboard()? else {
+        return Ok(None);
+    };
+
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = Local::now();
+    let time = locale::format_time(now.time(), locale::load(&conn).clock);
+    let heading = match &source_app {
+        Some(name) => format!("**{}** · {}", time, name),
+        None => format!("**{}**", time),
+    };
+
+    let note = daily_notes::for_date(&conn, now.date_naive())?;
+    let note = daily_notes::append(&conn, &note, &format!("{}\n\n{}", heading, text))?;
+    Ok(Some(ClipboardCaptured { note, source_app }))
+}
+
+// Rich text comes in as Markdown, like a paste; None when there's no text
+#[cfg(desktop)]
+fn read_clipboard() -> Result<Option<String>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    if let Ok(html) = clipboard.get().html() {
+        let markdown = render::html_to_markdown(&html);
+        if !markdown.is_empty() {
+            return Ok(Some(markdown));
+        }
+    }
+    match clipboard.get_text() {
+        Ok(text) if !text.trim().is_empty() => Ok(Some(text.trim().to_string())),
+        Ok(_) | Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// The name of the app in front, from the tools the OS ships; None where
+// there's no such tool, e.g. on Windows and Wayland
+#[cfg(target_os = "macos")]
+fn frontmost_app() -> Option<String> {
+    let front = output("lsappinfo", &["front"])?;
+    // "LSDisplayName"="Safari"
+    let info = output("lsappinfo", &["info", "-only", "name", front.trim()])?;
+    let name = info.rsplit('=').next()?.trim().trim_matches('"');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(all(desktop, unix, not(target_os = "macos")))]
+fn frontmost_app() -> Option<String> {
+    let class = output("xdotool", &["getactivewindow", "getwindowclassname"])?;
+    let class = class.trim();
+    (!class.is_empty()).then(|| class.to_string())
+}
+
+#[cfg(windows)]
+fn frontmost_app() -> Option<String> {
+    None
+}
+
+#[cfg(all(desktop, unix))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
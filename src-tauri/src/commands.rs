@@ -1,30 +1,131 @@
+use crate::actions::Actions;
+use crate::attachments;
+use crate::background::Activity;
+use crate::capture;
+use crate::clipboard_capture;
+use crate::comments;
+use crate::crdt;
+use crate::daily_notes;
+use crate::date_parse;
 use crate::db::Database;
+use crate::deep_links::LaunchLink;
+use crate::dependencies;
+use crate::email;
+use crate::embeds;
+use crate::export;
+use crate::error::AppError;
+use crate::feeds;
+use crate::folder_watch;
+use crate::folders;
+use crate::formats;
+use crate::goals;
+use crate::front_matter;
+use crate::history;
+use crate::holidays;
+use crate::integrity;
+use crate::jobs::{self, JobSpec};
+use crate::lan::{self, LanPeers};
+use crate::links;
+use crate::locale;
+use crate::map_formats;
+use crate::map_image;
+use crate::metadata;
+use crate::mirror;
+use crate::note_sort;
+use crate::outline;
 use crate::models::*;
-use chrono::Utc;
-use rusqlite::params;
-use tauri::State;
+use crate::palette;
+use crate::partial_update;
+use crate::platform;
+use crate::profiling;
+use crate::projects;
+use crate::publish;
+use crate::queries;
+use crate::quick_add;
+use crate::recovery;
+use crate::recurrence;
+use crate::render;
+use crate::review;
+use crate::revisions;
+use crate::sanitize;
+use crate::search_index;
+use crate::scripting::{self, Hook};
+use crate::search_query;
+use crate::seed;
+use crate::share;
+use crate::share_target;
+use crate::snippets;
+use crate::startup::{self, Recovery};
+use crate::storage;
+use crate::sync;
+use crate::system_search;
+use crate::timestamps;
+use crate::today;
+use crate::trash;
+use crate::validation;
+use crate::write_behind::WriteBehind;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 use uuid::Uuid;
 
 // ============ Notes Commands ============
 
+// With `recursive`, notes in every subfolder of `folder_id` are included too.
+// A `sort` given is remembered for the folder; without one the folder's last
+// choice is used.
 #[tauri::command]
-pub fn get_notes(db: State<Database>, folder_id: Option<String>) -> Result<Vec<Note>, String> {
+#[specta::specta]
+pub fn get_notes(
+    db: State<Database>,
+    folder_id: Option<String>,
+    recursive: Option<bool>,
+    sort: Option<NoteSortChoice>,
+) -> Result<Vec<Note>, String> {
+    let mut span = profiling::span("get_notes");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    let mut stmt = if folder_id.is_some() {
-        conn.prepare(
-            "SELECT id, title, content, folder_id, tags, is_pinned, created_at, updated_at, deleted_at
+    let choice = match sort {
+        Some(choice) => {
+            note_sort::save(&conn, folder_id.as_deref(), &choice)?;
+            choice
+        }
+        None => note_sort::load(&conn, folder_id.as_deref())?,
+    };
+    let order = note_sort::order_by(&choice);
+
+    let mut stmt = match (&folder_id, recursive.unwrap_or(false)) {
+        (Some(_), true) => conn.prepare_cached(&format!(
+            "WITH RECURSIVE subfolders(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT f.id FROM folders f JOIN subfolders s ON f.parent_id = s.id
+             )
+             SELECT {}
+             FROM notes
+             WHERE folder_id IN (SELECT id FROM subfolders) AND deleted_at IS NULL
+             ORDER BY {}",
+            queries::NOTE_COLUMNS,
+            order
+        )),
+        (Some(_), false) => conn.prepare_cached(&format!(
+            "SELECT {}
              FROM notes
              WHERE folder_id = ?1 AND deleted_at IS NULL
-             ORDER BY is_pinned DESC, updated_at DESC",
-        )
-    } else {
-        conn.prepare(
-            "SELECT id, title, content, folder_id, tags, is_pinned, created_at, updated_at, deleted_at
+             ORDER BY {}",
+            queries::NOTE_COLUMNS,
+            order
+        )),
+        (None, _) => conn.prepare_cached(&format!(
+            "SELECT {}
              FROM notes
              WHERE deleted_at IS NULL
-             ORDER BY is_pinned DESC, updated_at DESC",
-        )
+             ORDER BY {}",
+            queries::NOTE_COLUMNS,
+            order
+        )),
     }
     .map_err(|e| e.to_string())?;
 
@@ -36,18 +137,30 @@ pub fn get_notes(db: State<Database>, folder_id: Option<String>) -> Result<Vec<N
     .map_err(|e| e.to_string())?;
 
     let notes: Vec<Note> = rows.filter_map(|r| r.ok()).collect();
+    span.rows(notes.len());
     Ok(notes)
 }
 
+// The order get_notes uses for the folder when no sort is given
 #[tauri::command]
+#[specta::specta]
+pub fn get_note_sort(
+    db: State<Database>,
+    folder_id: Option<String>,
+) -> Result<NoteSortChoice, String> {
+    let _span = profiling::span("get_note_sort");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    note_sort::load(&conn, folder_id.as_deref())
+}
+
+#[tauri::command]
+#[specta::specta]
 pub fn get_note(db: State<Database>, id: String) -> Result<Option<Note>, String> {
+    let _span = profiling::span("get_note");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare(
-            "SELECT id, title, content, folder_id, tags, is_pinned, created_at, updated_at, deleted_at
-             FROM notes WHERE id = ?1",
-        )
+        .prepare_cached(&format!("SELECT {} FROM notes WHERE id = ?1", queries::NOTE_COLUMNS))
         .map_err(|e| e.to_string())?;
 
     let note = stmt.query_row(params![id], row_to_note).ok();
@@ -55,26 +168,52 @@ pub fn get_note(db: State<Database>, id: String) -> Result<Option<Note>, String>
 }
 
 #[tauri::command]
-pub fn create_note(db: State<Database>, data: NoteCreate) -> Result<Note, String> {
+#[specta::specta]
+pub fn create_note(
+    app: AppHandle,
+    db: State<Database>,
+    data: NoteCreate,
+) -> Result<Note, AppError> {
+    let _span = profiling::span("create_note");
+    validation::note_create(&data)?;
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    let note = insert_note(&conn, data)?;
+    scripting::dispatch(&app, Hook::NoteCreated, &note);
+    Ok(note)
+}
+
+pub(crate) fn insert_note(conn: &rusqlite::Connection, data: NoteCreate) -> Result<Note, String> {
+    let now = timestamps::now();
     let id = format!("note_{}", Uuid::new_v4());
 
+    let content = sanitize::sanitize_content(&data.content.unwrap_or_default());
+    // Untitled notes are named from their content so lists don't show blank rows
+    let title = match data.title {
+        Some(title) if !title.trim().is_empty() => title,
+        _ => render::derive_note_title(&content),
+    };
+
     let note = Note {
         id: id.clone(),
-        title: data.title.unwrap_or_default(),
-        content: data.content.unwrap_or_default(),
+        title,
+        content,
         folder_id: data.folder_id,
         tags: data.tags.unwrap_or_default(),
+        aliases: data.aliases.unwrap_or_default(),
         is_pinned: false,
-        created_at: now.clone(),
-        updated_at: now.clone(),
+        created_at: now,
+        updated_at: now,
         deleted_at: None,
+        published_url: None,
+        // Detected as the note is indexed
+        language: None,
     };
 
     conn.execute(
-        "INSERT INTO notes (id, title, content, folder_id, tags, is_pinned, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO notes (id, title, content, folder_id, tags, is_pinned, created_at, updated_at,
+                            aliases)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
             note.id,
             note.title,
@@ -82,92 +221,209 @@ pub fn create_note(db: State<Database>, data: NoteCreate) -> Result<Note, String
             note.folder_id,
             serde_json::to_string(&note.tags).unwrap_or_default(),
             note.is_pinned as i32,
-            note.created_at,
-            note.updated_at,
+            timestamps::format(&note.created_at),
+            timestamps::format(&note.updated_at),
+            serde_json::to_string(&note.aliases).unwrap_or_default(),
         ],
     )
     .map_err(|e| e.to_string())?;
+    search_index::note_changed(conn, &note.id);
 
-    Ok(note)
+    load_note(conn, &note.id)
 }
 
 #[tauri::command]
-pub fn update_note(db: State<Database>, id: String, data: NoteUpdate) -> Result<Note, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-
-    // Get current note
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, content, folder_id, tags, is_pinned, created_at, updated_at, deleted_at
-             FROM notes WHERE id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+#[specta::specta]
+pub fn update_note(
+    app: AppHandle,
+    db: State<Database>,
+    id: String,
+    data: NoteUpdate,
+) -> Result<Note, AppError> {
+    let _span = profiling::span("update_note");
+    validation::note_update(&data)?;
+    let content_changed = data.content.is_some();
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+
+    // A rename can rewrite links in other notes; it all lands together
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Only a rename or new content needs the old title; everything else is
+    // written blind
+    let stored_title = if data.title.is_some() || data.content.is_some() {
+        tx.query_row("SELECT title FROM notes WHERE id = ?1", params![id], |row| {
+            row.get::<_, String>(0)
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+    } else {
+        None
+    };
 
-    let current: Note = stmt
-        .query_row(params![id], row_to_note)
-        .map_err(|e| e.to_string())?;
+    // A note left untitled is named from its content, as on create
+    let mut title = data.title;
+    let untitled = title
+        .as_deref()
+        .or(stored_title.as_deref())
+        .is_some_and(|title| title.trim().is_empty());
+    if untitled {
+        title = Some(match data.content.as_deref() {
+            Some(content) => render::derive_note_title(content),
+            None => {
+                let content: String = tx
+                    .query_row("SELECT content FROM notes WHERE id = ?1", params![id], |row| {
+                        row.get(0)
+                    })
+                    .optional()
+                    .map_err(|e| e.to_string())?
+                    .unwrap_or_default();
+                render::derive_note_title(&content)
+            }
+        });
+    }
 
-    let updated = Note {
-        id: current.id,
-        title: data.title.unwrap_or(current.title),
-        content: data.content.unwrap_or(current.content),
-        folder_id: data.folder_id.or(current.folder_id),
-        tags: data.tags.unwrap_or(current.tags),
-        is_pinned: data.is_pinned.unwrap_or(current.is_pinned),
-        created_at: current.created_at,
-        updated_at: now,
-        deleted_at: current.deleted_at,
+    // Nothing can link to an empty title, so naming an untitled note isn't a
+    // rename
+    let old_title = match &title {
+        Some(title) => stored_title.filter(|old| old != title && !old.trim().is_empty()),
+        None => None,
     };
+    // Like move_notes_to_folder, a move drops the note's manual position
+    let moved = !matches!(data.folder_id, Patch::Undefined);
+
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("title", title)
+        .set("content", data.content.map(|c| sanitize::sanitize_content(&c)))
+        .set("folder_id", data.folder_id.into_change())
+        .set("tags", data.tags.map(|tags| serde_json::to_string(&tags).unwrap_or_default()))
+        .set(
+            "aliases",
+            data.aliases.map(|aliases| serde_json::to_string(&aliases).unwrap_or_default()),
+        )
+        .set("is_pinned", data.is_pinned.map(|pinned| pinned as i32))
+        .set("position", moved.then_some(None::<i64>))
+        .set_value("updated_at", now.clone());
+    if !changes.execute(&tx, "notes", &id)? {
+        return Err("Note not found".into());
+    }
+    // Indexed first so the note comes back with its language
+    search_index::note_changed(&tx, &id);
+    let updated = load_note(&tx, &id)?;
 
-    conn.execute(
-        "UPDATE notes SET title = ?1, content = ?2, folder_id = ?3, tags = ?4, is_pinned = ?5, updated_at = ?6
-         WHERE id = ?7",
-        params![
-            updated.title,
-            updated.content,
-            updated.folder_id,
-            serde_json::to_string(&updated.tags).unwrap_or_default(),
-            updated.is_pinned as i32,
-            updated.updated_at,
-            updated.id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+    if content_changed {
+        crdt::record_edit(&tx, &updated.id, &updated.content, &now)?;
+    }
+    if let Some(old_title) = old_title {
+        links::record_rename(&tx, &updated.id, &old_title, &now)?;
+        if links::load_settings(&tx)?.update_on_rename {
+            links::follow_rename(&tx, &updated.id, &old_title, &updated.title, &now)?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    scripting::dispatch(&app, Hook::NoteUpdated, &updated);
 
     Ok(updated)
 }
 
+// Deletes a note for good with everything attached to it. Returns the bytes
+// of attachment files removed from disk.
+// Deletes the note's rows. Returns its attachments' blob names, to pass to
+// `attachments::release_blobs` once the deletion is committed, so a failed
+// commit can't leave rows pointing at removed files.
+pub fn purge_note(conn: &rusqlite::Connection, id: &str) -> Result<Vec<String>, String> {
+    // Attachment rows go with the note; their blobs only once unreferenced
+    let file_names: Vec<String> = conn
+        .prepare("SELECT file_name FROM attachments WHERE note_id = ?1")
+        .and_then(|mut stmt| stmt.query_map(params![id], |row| row.get(0))?.collect())
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM attachments WHERE note_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM note_stats WHERE note_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    metadata::remove_all(conn, EntityKind::Note, id)?;
+    projects::unlink_all(conn, ProjectItemKind::Note, id)?;
+    comments::remove_all(conn, id)?;
+    crdt::remove(conn, id)?;
+    links::forget(conn, id)?;
+    revisions::remove_all(conn, id)?;
+    conn.execute("DELETE FROM notes WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(file_names)
+}
+
 #[tauri::command]
-pub fn delete_note(db: State<Database>, id: String, hard: Option<bool>) -> Result<(), String> {
+#[specta::specta]
+pub fn delete_note(
+    app: AppHandle,
+    db: State<Database>,
+    id: String,
+    hard: Option<bool>,
+) -> Result<(), String> {
+    let _span = profiling::span("delete_note");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     if hard.unwrap_or(false) {
-        conn.execute("DELETE FROM notes WHERE id = ?1", params![id])
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let file_names = purge_note(&tx, &id)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        attachments::release_blobs(&conn, &db.data_dir, &file_names)?;
     } else {
-        let now = Utc::now().to_rfc3339();
-        conn.execute(
-            "UPDATE notes SET deleted_at = ?1 WHERE id = ?2",
-            params![now, id],
-        )
+        trash::trash_note(&conn, &id, &timestamps::format(&Utc::now()))?;
     }
-    .map_err(|e| e.to_string())?;
+    search_index::note_removed(&conn, &id);
+    scripting::dispatch(&app, Hook::NoteDeleted, &id);
 
     Ok(())
 }
 
+// Without an `action`, a note whose folder has been deleted stays in the
+// trash and comes back with status folder_missing, for the user to choose
+// between recreating the folder, the top level or another folder
+#[tauri::command]
+#[specta::specta]
+pub fn restore_note(
+    db: State<Database>,
+    id: String,
+    action: Option<RestoreFolderAction>,
+    folder_id: Option<String>,
+) -> Result<NoteRestore, String> {
+    let _span = profiling::span("restore_note");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    trash::restore_note(&conn, &id, action, folder_id.as_deref())
+}
+
+// A trashed note's title, the start of its text, when it was deleted and
+// where it was filed, without loading the whole note
+#[tauri::command]
+#[specta::specta]
+pub fn get_deleted_note_preview(
+    db: State<Database>,
+    id: String,
+) -> Result<DeletedNotePreview, String> {
+    let _span = profiling::span("get_deleted_note_preview");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    trash::preview_note(&conn, &id)
+}
+
 #[tauri::command]
+#[specta::specta]
 pub fn move_notes_to_folder(
     db: State<Database>,
     note_ids: Vec<String>,
     folder_id: Option<String>,
 ) -> Result<(), String> {
+    let _span = profiling::span("move_notes_to_folder");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    let now = timestamps::format(&Utc::now());
 
+    // A note's place in the old folder's manual order means nothing in the
+    // new one
     for id in note_ids {
         conn.execute(
-            "UPDATE notes SET folder_id = ?1, updated_at = ?2 WHERE id = ?3",
+            "UPDATE notes SET folder_id = ?1, updated_at = ?2, position = NULL WHERE id = ?3",
             params![folder_id, now, id],
         )
         .map_err(|e| e.to_string())?;
@@ -176,53 +432,232 @@ pub fn move_notes_to_folder(
     Ok(())
 }
 
-// ============ Folders Commands ============
+// Sets the manual order: the notes in `note_ids` come in that order when a
+// folder is sorted by hand
+#[tauri::command]
+#[specta::specta]
+pub fn reorder_notes(db: State<Database>, note_ids: Vec<String>) -> Result<(), String> {
+    let _span = profiling::span("reorder_notes");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    note_sort::reorder(&conn, &note_ids)
+}
+
+// The note's YAML front matter as typed properties
+#[tauri::command]
+#[specta::specta]
+pub fn get_note_properties(db: State<Database>, id: String) -> Result<Vec<NoteProperty>, String> {
+    let _span = profiling::span("get_note_properties");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &id)?;
+    Ok(front_matter::properties(&note.content))
+}
+
+// Sets a property to `value` (JSON text) by rewriting the front matter block,
+// or removes it when `value` is None. Returns the properties afterwards.
+#[tauri::command]
+#[specta::specta]
+pub fn set_note_property(
+    db: State<Database>,
+    id: String,
+    key: String,
+    value: Option<String>,
+) -> Result<Vec<NoteProperty>, AppError> {
+    let _span = profiling::span("set_note_property");
+    let key = key.trim();
+    validation::note_property(key, value.as_deref())?;
+    let value = value
+        .map(|v| serde_json::from_str::<serde_json::Value>(&v))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &id)?;
+    let content = front_matter::set_property(&note.content, key, value.as_ref());
+    let now = timestamps::format(&Utc::now());
+    conn.execute(
+        "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+        params![content, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    crdt::record_edit(&conn, &id, &content, &now)?;
+    search_index::note_changed(&conn, &id);
+
+    Ok(front_matter::properties(&content))
+}
+
+// ============ Note Comment Commands ============
+
+// Margin comments, placed where their quoted text is in the note now
+#[tauri::command]
+#[specta::specta]
+pub fn get_note_comments(db: State<Database>, note_id: String) -> Result<Vec<NoteComment>, String> {
+    let _span = profiling::span("get_note_comments");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &note_id)?;
+    comments::list(&conn, &note_id, &note.content)
+}
+
+// Comments on `offset..offset + length` of the content as it is now; a zero
+// length leaves it at a single point
+#[tauri::command]
+#[specta::specta]
+pub fn add_note_comment(
+    db: State<Database>,
+    note_id: String,
+    data: NoteCommentCreate,
+) -> Result<NoteComment, AppError> {
+    let _span = profiling::span("add_note_comment");
+    validation::note_comment_body(&data.body)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &note_id)
+        .map_err(|_| AppError::validation("note_id", "note not found"))?;
+    let Some(quote) = comments::quote(&note.content, data.offset, data.length) else {
+        return Err(AppError::validation("offset", "is past the end of the note"));
+    };
+    Ok(comments::insert(&conn, &note_id, quote, data, &timestamps::now())?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_note_comment(
+    db: State<Database>,
+    id: String,
+    data: NoteCommentUpdate,
+) -> Result<NoteComment, AppError> {
+    let _span = profiling::span("update_note_comment");
+    if let Some(body) = &data.body {
+        validation::note_comment_body(body)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(current) = comments::load(&conn, &id)? else {
+        return Err(AppError::validation("id", "comment not found"));
+    };
+    let note = load_note(&conn, &current.note_id)?;
+    let now = timestamps::now();
+    Ok(comments::update(&conn, current, data, &note.content, &now)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_note_comment(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_note_comment");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    comments::delete(&conn, &id)
+}
+
+// ============ Search Commands ============
+
+// Relative dates in `before:`/`after:` read numeric dates per the locale
+fn parse_search(conn: &rusqlite::Connection, text: &str) -> Result<search_query::Query, String> {
+    let day_first = locale::load(conn).date_order == DateOrder::Dmy;
+    search_query::parse(text, Local::now().naive_local(), day_first)
+}
+
+// Full-text search over titles, tags and note text, best matches first.
+// Also takes filters, e.g. `tag:project folder:"Work" before:2024-01-01
+// is:pinned "exact phrase" -draft`; see search_query.rs.
+#[tauri::command]
+#[specta::specta]
+pub fn search_notes(
+    db: State<Database>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<SearchHit>, String> {
+    let mut span = profiling::span("search_notes");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let parsed = parse_search(&conn, &query)?;
+    let hits = search_index::search(&conn, &parsed, limit.unwrap_or(50).min(500))?;
+    span.rows(hits.len());
+    Ok(hits)
+}
+
+// Call when a note is opened in the editor; frequently opened notes rank
+// higher in search
+#[tauri::command]
+#[specta::specta]
+pub fn record_note_open(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("record_note_open");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    search_index::record_open(&conn, &id)
+}
 
 #[tauri::command]
-pub fn get_folders(db: State<Database>) -> Result<Vec<Folder>, String> {
+#[specta::specta]
+pub fn get_search_ranking(db: State<Database>) -> Result<SearchRanking, String> {
+    let _span = profiling::span("get_search_ranking");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(search_index::ranking(&conn))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_search_ranking(db: State<Database>, weights: SearchRanking) -> Result<(), AppError> {
+    let _span = profiling::span("set_search_ranking");
+    validation::search_ranking(&weights)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(search_index::save_ranking(&conn, &weights)?)
+}
+
+fn row_to_smart_folder(row: &rusqlite::Row) -> rusqlite::Result<SmartFolder> {
+    Ok(SmartFolder {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        query: row.get("query")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_smart_folders(db: State<Database>) -> Result<Vec<SmartFolder>, String> {
+    let _span = profiling::span("get_smart_folders");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, name, parent_id, color, icon, created_at, updated_at
-             FROM folders
-             ORDER BY name ASC",
+            "SELECT id, name, query, created_at, updated_at
+             FROM smart_folders ORDER BY name COLLATE NOCASE",
         )
         .map_err(|e| e.to_string())?;
 
-    let rows = stmt.query_map([], row_to_folder).map_err(|e| e.to_string())?;
-
-    let folders: Vec<Folder> = rows.filter_map(|r| r.ok()).collect();
-    Ok(folders)
+    let rows = stmt
+        .query_map([], row_to_smart_folder)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
 #[tauri::command]
-pub fn create_folder(db: State<Database>, data: FolderCreate) -> Result<Folder, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let id = format!("folder_{}", Uuid::new_v4());
+#[specta::specta]
+pub fn create_smart_folder(
+    db: State<Database>,
+    data: SmartFolderCreate,
+) -> Result<SmartFolder, AppError> {
+    let _span = profiling::span("create_smart_folder");
+    validation::smart_folder_create(&data)?;
 
-    let folder = Folder {
-        id: id.clone(),
-        name: data.name,
-        parent_id: data.parent_id,
-        color: data.color,
-        icon: data.icon,
-        created_at: now.clone(),
-        updated_at: now.clone(),
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    parse_search(&conn, &data.query).map_err(|e| AppError::validation("query", e))?;
+
+    let now = timestamps::now();
+    let folder = SmartFolder {
+        id: format!("smart_{}", Uuid::new_v4()),
+        name: data.name.trim().to_string(),
+        query: data.query.trim().to_string(),
+        created_at: now,
+        updated_at: now,
     };
 
     conn.execute(
-        "INSERT INTO folders (id, name, parent_id, color, icon, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO smart_folders (id, name, query, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
         params![
             folder.id,
             folder.name,
-            folder.parent_id,
-            folder.color,
-            folder.icon,
-            folder.created_at,
-            folder.updated_at,
+            folder.query,
+            timestamps::format(&folder.created_at),
+            timestamps::format(&folder.updated_at),
         ],
     )
     .map_err(|e| e.to_string())?;
@@ -231,42 +666,42 @@ pub fn create_folder(db: State<Database>, data: FolderCreate) -> Result<Folder,
 }
 
 #[tauri::command]
-pub fn update_folder(db: State<Database>, id: String, data: FolderUpdate) -> Result<Folder, String> {
+#[specta::specta]
+pub fn update_smart_folder(
+    db: State<Database>,
+    id: String,
+    data: SmartFolderUpdate,
+) -> Result<SmartFolder, AppError> {
+    let _span = profiling::span("update_smart_folder");
+    validation::smart_folder_update(&data)?;
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    if let Some(query) = &data.query {
+        parse_search(&conn, query).map_err(|e| AppError::validation("query", e))?;
+    }
 
-    // Get current folder
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, parent_id, color, icon, created_at, updated_at
-             FROM folders WHERE id = ?1",
+    let current = conn
+        .query_row(
+            "SELECT id, name, query, created_at, updated_at FROM smart_folders WHERE id = ?1",
+            params![id],
+            row_to_smart_folder,
         )
         .map_err(|e| e.to_string())?;
 
-    let current: Folder = stmt
-        .query_row(params![id], row_to_folder)
-        .map_err(|e| e.to_string())?;
-
-    let updated = Folder {
-        id: current.id,
-        name: data.name.unwrap_or(current.name),
-        parent_id: data.parent_id.or(current.parent_id),
-        color: data.color.or(current.color),
-        icon: data.icon.or(current.icon),
-        created_at: current.created_at,
-        updated_at: now,
+    let updated = SmartFolder {
+        name: data.name.map(|n| n.trim().to_string()).unwrap_or(current.name),
+        query: data.query.map(|q| q.trim().to_string()).unwrap_or(current.query),
+        updated_at: timestamps::now(),
+        ..current
     };
 
     conn.execute(
-        "UPDATE folders SET name = ?1, parent_id = ?2, color = ?3, icon = ?4, updated_at = ?5
-         WHERE id = ?6",
+        "UPDATE smart_folders SET name = ?1, query = ?2, updated_at = ?3 WHERE id = ?4",
         params![
             updated.name,
-            updated.parent_id,
-            updated.color,
-            updated.icon,
-            updated.updated_at,
-            updated.id,
+            updated.query,
+            timestamps::format(&updated.updated_at),
+            updated.id
         ],
     )
     .map_err(|e| e.to_string())?;
@@ -275,897 +710,5385 @@ pub fn update_folder(db: State<Database>, id: String, data: FolderUpdate) -> Res
 }
 
 #[tauri::command]
-pub fn delete_folder(db: State<Database>, id: String) -> Result<(), String> {
+#[specta::specta]
+pub fn delete_smart_folder(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_smart_folder");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    // Move notes in this folder to no folder
-    conn.execute(
-        "UPDATE notes SET folder_id = NULL WHERE folder_id = ?1",
-        params![id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Delete the folder
-    conn.execute("DELETE FROM folders WHERE id = ?1", params![id])
+    conn.execute("DELETE FROM smart_folders WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
-// ============ Settings Commands ============
-
+// Runs the saved query; relative dates resolve against today
 #[tauri::command]
-pub fn get_setting(db: State<Database>, key: String) -> Result<Option<String>, String> {
+#[specta::specta]
+pub fn get_smart_folder_notes(
+    db: State<Database>,
+    id: String,
+    limit: Option<u32>,
+) -> Result<Vec<SearchHit>, String> {
+    let mut span = profiling::span("get_smart_folder_notes");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    let mut stmt = conn
-        .prepare("SELECT value FROM settings WHERE key = ?1")
+    let query: String = conn
+        .query_row(
+            "SELECT query FROM smart_folders WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
         .map_err(|e| e.to_string())?;
 
-    let value: Option<String> = stmt.query_row(params![key], |row| row.get(0)).ok();
-    Ok(value)
+    let parsed = parse_search(&conn, &query)?;
+    let hits = search_index::search(&conn, &parsed, limit.unwrap_or(200).min(500))?;
+    span.rows(hits.len());
+    Ok(hits)
 }
 
+// Notes whose [[wikilinks]] point at this one
 #[tauri::command]
-pub fn set_setting(db: State<Database>, key: String, value: String) -> Result<(), String> {
+#[specta::specta]
+pub fn get_backlinks(db: State<Database>, note_id: String) -> Result<Vec<Note>, String> {
+    let _span = profiling::span("get_backlinks");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-        params![key, value],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+    search_index::backlinks(&conn, &note_id)
 }
 
-// ============ Helper Functions ============
-
-fn row_to_note(row: &rusqlite::Row) -> rusqlite::Result<Note> {
-    let tags_str: String = row.get(4)?;
-    let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
-    let is_pinned: i32 = row.get(5)?;
-
-    Ok(Note {
-        id: row.get(0)?,
-        title: row.get(1)?,
-        content: row.get(2)?,
-        folder_id: row.get(3)?,
-        tags,
-        is_pinned: is_pinned != 0,
-        created_at: row.get(6)?,
-        updated_at: row.get(7)?,
-        deleted_at: row.get(8)?,
-    })
+// The note's heading tree for the table of contents panel
+#[tauri::command]
+#[specta::specta]
+pub fn get_note_outline(db: State<Database>, id: String) -> Result<Vec<OutlineHeading>, String> {
+    let _span = profiling::span("get_note_outline");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &id)?;
+    Ok(outline::outline(&note.content))
 }
 
-fn row_to_folder(row: &rusqlite::Row) -> rusqlite::Result<Folder> {
-    Ok(Folder {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        parent_id: row.get(2)?,
-        color: row.get(3)?,
-        icon: row.get(4)?,
-        created_at: row.get(5)?,
-        updated_at: row.get(6)?,
-    })
+// Fragments for the note's ![[note#heading]] embeds, nested ones included,
+// so the editor can show them without a call per embed
+#[tauri::command]
+#[specta::specta]
+pub fn resolve_embeds(db: State<Database>, note_id: String) -> Result<Vec<Embed>, String> {
+    let _span = profiling::span("resolve_embeds");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    embeds::resolve(&conn, &note_id)
 }
 
-fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<Event> {
-    let tags_str: String = row.get(13)?;
-    let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
-    let reminders_str: String = row.get(19)?;
-    let reminders: Vec<EventReminder> = serde_json::from_str(&reminders_str).unwrap_or_default();
-    let has_scheduled_time: i32 = row.get(6)?;
-    let show_on_calendar: i32 = row.get(14)?;
-    let is_all_day: i32 = row.get(15)?;
-    let is_recurring: i32 = row.get(16)?;
-
-    Ok(Event {
-        id: row.get(0)?,
-        title: row.get(1)?,
-        description: row.get(2)?,
-        event_type: row.get(3)?,
-        start_time: row.get(4)?,
-        end_time: row.get(5)?,
-        has_scheduled_time: has_scheduled_time != 0,
-        time_mode: row.get(7)?,
-        duration_minutes: row.get(8)?,
-        location: row.get(9)?,
-        category: row.get(10)?,
-        color: row.get(11)?,
-        priority: row.get(12)?,
-        tags,
-        show_on_calendar: show_on_calendar != 0,
-        is_all_day: is_all_day != 0,
-        is_recurring: is_recurring != 0,
-        recurring_pattern: row.get(17)?,
-        status: row.get(18)?,
-        reminders,
-        notes: row.get(20)?,
-        created_at: row.get(21)?,
-        updated_at: row.get(22)?,
-        deleted_at: row.get(23)?,
-    })
+// Notes a [[wikilink]] being typed could mean, matched on title or alias
+#[tauri::command]
+#[specta::specta]
+pub fn resolve_note_by_title_or_alias(
+    db: State<Database>,
+    text: String,
+    limit: Option<u32>,
+) -> Result<Vec<LinkTarget>, String> {
+    let _span = profiling::span("resolve_note_by_title_or_alias");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    links::resolve(&conn, &text, limit.unwrap_or(20).min(100) as usize)
 }
 
-// ============ Events Commands ============
-
 #[tauri::command]
-pub fn get_events(db: State<Database>) -> Result<Vec<Event>, String> {
+#[specta::specta]
+pub fn get_link_settings(db: State<Database>) -> Result<LinkSettings, String> {
+    let _span = profiling::span("get_link_settings");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    links::load_settings(&conn)
+}
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, event_type, start_time, end_time, has_scheduled_time,
-                    time_mode, duration_minutes, location, category, color, priority, tags,
-                    show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
-                    reminders, notes, created_at, updated_at, deleted_at
-             FROM events
-             WHERE deleted_at IS NULL
-             ORDER BY start_time ASC",
-        )
-        .map_err(|e| e.to_string())?;
-
-    let rows = stmt.query_map([], row_to_event).map_err(|e| e.to_string())?;
-    let events: Vec<Event> = rows.filter_map(|r| r.ok()).collect();
-    Ok(events)
+#[tauri::command]
+#[specta::specta]
+pub fn set_link_settings(db: State<Database>, settings: LinkSettings) -> Result<(), String> {
+    let _span = profiling::span("set_link_settings");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    links::save_settings(&conn, &settings)
 }
 
+// Snapshots taken before automatic edits to the note, newest first
 #[tauri::command]
-pub fn get_event(db: State<Database>, id: String) -> Result<Option<Event>, String> {
+#[specta::specta]
+pub fn get_note_revisions(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<NoteRevision>, String> {
+    let _span = profiling::span("get_note_revisions");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    revisions::list(&conn, &note_id)
+}
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, event_type, start_time, end_time, has_scheduled_time,
-                    time_mode, duration_minutes, location, category, color, priority, tags,
-                    show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
-                    reminders, notes, created_at, updated_at, deleted_at
-             FROM events WHERE id = ?1",
+// Wikilinks in one note, or every note, whose target is missing, trashed or
+// was renamed. With `repair`, links to a renamed note get its current title.
+#[tauri::command]
+#[specta::specta]
+pub fn check_note_links(
+    db: State<Database>,
+    note_id: Option<String>,
+    repair: Option<bool>,
+) -> Result<Vec<BrokenLink>, String> {
+    let _span = profiling::span("check_note_links");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if !repair.unwrap_or(false) {
+        return links::check(&conn, note_id.as_deref());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let broken = links::repair(&tx, note_id.as_deref(), &timestamps::format(&Utc::now()))?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(broken)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_note_bookmarks(db: State<Database>, note_id: String) -> Result<Vec<NoteBookmark>, String> {
+    let _span = profiling::span("get_note_bookmarks");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    search_index::bookmarks_for(&conn, &note_id)
+}
+
+// Reindexes every note in the background, e.g. after a large import or if
+// search results look stale. Returns the job id; progress arrives on
+// `job:progress`.
+#[tauri::command]
+#[specta::specta]
+pub fn rebuild_search_index(app: AppHandle) -> Result<String, String> {
+    let _span = profiling::span("rebuild_search_index");
+    jobs::enqueue(&app, JobSpec::RebuildSearchIndex)
+}
+
+// ============ Folders Commands ============
+
+// Used when a list query leaves out the limit, and the most one can ask for
+const DEFAULT_PAGE_SIZE: u32 = 500;
+const MAX_PAGE_SIZE: u32 = 1000;
+
+fn page_bounds(query: &ListQuery) -> (u32, u32) {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    (limit, query.offset.unwrap_or(0))
+}
+
+// Names sort A to Z and dates newest first unless the query says otherwise.
+// The id is a tie-breaker so pages don't overlap.
+fn order_by(query: &ListQuery, default: ListSort, name_column: &str) -> String {
+    let sort = query.sort.unwrap_or(default);
+    let descending = query.descending.unwrap_or(sort != ListSort::Name);
+    let column = match sort {
+        ListSort::Name => name_column,
+        ListSort::Created => "created_at",
+        ListSort::Updated => "updated_at",
+    };
+    format!(
+        "{} COLLATE NOCASE {}, id",
+        column,
+        if descending { "DESC" } else { "ASC" }
+    )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_folders(db: State<Database>, query: Option<ListQuery>) -> Result<Page<Folder>, String> {
+    let mut span = profiling::span("get_folders");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let query = query.unwrap_or_default();
+    let (limit, offset) = page_bounds(&query);
+
+    const FILTER: &str = "?1 IS NULL OR instr(lower(name), lower(?1)) > 0";
+
+    let total: u32 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM folders WHERE {}", FILTER),
+            params![query.search],
+            |row| row.get(0),
         )
         .map_err(|e| e.to_string())?;
 
-    let event = stmt.query_row(params![id], row_to_event).ok();
-    Ok(event)
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, name, parent_id, color, icon, created_at, updated_at
+             FROM folders
+             WHERE {}
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            FILTER,
+            order_by(&query, ListSort::Name, "name")
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![query.search, limit, offset], row_to_folder)
+        .map_err(|e| e.to_string())?;
+
+    let folders: Vec<Folder> = rows.filter_map(|r| r.ok()).collect();
+    span.rows(folders.len());
+    Ok(Page { items: folders, total })
 }
 
 #[tauri::command]
-pub fn create_event(db: State<Database>, data: EventCreate) -> Result<Event, String> {
+#[specta::specta]
+pub fn create_folder(db: State<Database>, data: FolderCreate) -> Result<Folder, AppError> {
+    let _span = profiling::span("create_folder");
+    validation::folder_create(&data)?;
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let id = format!("event_{}", Uuid::new_v4());
+    Ok(insert_folder(&conn, data)?)
+}
+
+pub(crate) fn insert_folder(
+    conn: &rusqlite::Connection,
+    data: FolderCreate,
+) -> Result<Folder, String> {
+    let now = timestamps::now();
+    let id = format!("folder_{}", Uuid::new_v4());
 
-    let event = Event {
+    let folder = Folder {
         id: id.clone(),
-        title: data.title,
-        description: data.description,
-        event_type: None,
-        start_time: data.start_time.clone(),
-        end_time: data.end_time,
-        has_scheduled_time: data.start_time.is_some(),
-        time_mode: data.time_mode.unwrap_or_else(|| "at_time".to_string()),
-        duration_minutes: data.duration_minutes,
-        location: data.location,
-        category: data.category.or(Some("personal".to_string())),
+        name: data.name,
+        parent_id: data.parent_id,
         color: data.color,
-        priority: data.priority.or(Some("medium".to_string())),
-        tags: data.tags.unwrap_or_default(),
-        show_on_calendar: data.show_on_calendar.unwrap_or(true),
-        is_all_day: data.is_all_day.unwrap_or(false),
-        is_recurring: data.is_recurring.unwrap_or(false),
-        recurring_pattern: data.recurring_pattern,
-        status: Some("pending".to_string()),
-        reminders: data.reminders.unwrap_or_default(),
-        notes: None,
-        created_at: now.clone(),
-        updated_at: now.clone(),
-        deleted_at: None,
+        icon: data.icon,
+        created_at: now,
+        updated_at: now,
     };
 
     conn.execute(
-        "INSERT INTO events (id, title, description, event_type, start_time, end_time, has_scheduled_time,
-                            time_mode, duration_minutes, location, category, color, priority, tags,
-                            show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
-                            reminders, notes, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+        "INSERT INTO folders (id, name, parent_id, color, icon, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![
-            event.id,
-            event.title,
-            event.description,
-            event.event_type,
-            event.start_time,
-            event.end_time,
-            event.has_scheduled_time as i32,
-            event.time_mode,
-            event.duration_minutes,
-            event.location,
-            event.category,
-            event.color,
-            event.priority,
-            serde_json::to_string(&event.tags).unwrap_or_default(),
-            event.show_on_calendar as i32,
-            event.is_all_day as i32,
-            event.is_recurring as i32,
-            event.recurring_pattern,
-            event.status,
-            serde_json::to_string(&event.reminders).unwrap_or_default(),
-            event.notes,
-            event.created_at,
-            event.updated_at,
+            folder.id,
+            folder.name,
+            folder.parent_id,
+            folder.color,
+            folder.icon,
+            timestamps::format(&folder.created_at),
+            timestamps::format(&folder.updated_at),
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(event)
+    Ok(folder)
+}
+
+// Breadcrumbs: the folder's ancestors from the top level down, ending with
+// the folder itself
+#[tauri::command]
+#[specta::specta]
+pub fn get_folder_path(db: State<Database>, id: String) -> Result<Vec<Folder>, String> {
+    let _span = profiling::span("get_folder_path");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let path = folders::ancestors(&conn, &id)?;
+    if path.is_empty() {
+        return Err(format!("Folder {} not found", id));
+    }
+    Ok(path)
 }
 
+// Looks up a folder by a path like "Work/Projects/Alpha". Missing folders
+// along the way are created unless `create` is false, in which case a
+// missing folder gives None.
 #[tauri::command]
-pub fn update_event(db: State<Database>, id: String, data: EventUpdate) -> Result<Event, String> {
+#[specta::specta]
+pub fn resolve_folder_path(
+    db: State<Database>,
+    path: String,
+    create: Option<bool>,
+) -> Result<Option<Folder>, AppError> {
+    let _span = profiling::span("resolve_folder_path");
+    validation::folder_path(&path)?;
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    Ok(folders::resolve_path(&conn, &path, create.unwrap_or(true))?)
+}
 
-    // Get current event
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, event_type, start_time, end_time, has_scheduled_time,
-                    time_mode, duration_minutes, location, category, color, priority, tags,
-                    show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
-                    reminders, notes, created_at, updated_at, deleted_at
-             FROM events WHERE id = ?1",
+#[tauri::command]
+#[specta::specta]
+pub fn update_folder(db: State<Database>, id: String, data: FolderUpdate) -> Result<Folder, AppError> {
+    let _span = profiling::span("update_folder");
+    validation::folder_update(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+
+    if let Some(parent_id) = data.parent_id.value() {
+        if folders::is_within(&conn, parent_id, &id)? {
+            return Err(AppError::validation(
+                "parent_id",
+                "cannot move a folder into itself or one of its subfolders",
+            ));
+        }
+    }
+
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("name", data.name)
+        .set("parent_id", data.parent_id.into_change())
+        .set("color", data.color.into_change())
+        .set("icon", data.icon.into_change())
+        .set_value("updated_at", now);
+    if !changes.execute(&conn, "folders", &id)? {
+        return Err("Folder not found".into());
+    }
+
+    Ok(conn
+        .query_row(
+            "SELECT id, name, parent_id, color, icon, created_at, updated_at
+             FROM folders WHERE id = ?1",
+            params![id],
+            row_to_folder,
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?)
+}
 
-    let current: Event = stmt
-        .query_row(params![id], row_to_event)
-        .map_err(|e| e.to_string())?;
+// Moves a folder under `new_parent_id`, or to the top level with None. A
+// folder of the same name already there is a conflict unless `merge` is
+// set, in which case the two are merged and the surviving folder returned.
+#[tauri::command]
+#[specta::specta]
+pub fn move_folder(
+    db: State<Database>,
+    id: String,
+    new_parent_id: Option<String>,
+    merge: Option<bool>,
+) -> Result<Folder, AppError> {
+    let _span = profiling::span("move_folder");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+
+    let mut folder = tx
+        .query_row(
+            "SELECT id, name, parent_id, color, icon, created_at, updated_at
+             FROM folders WHERE id = ?1",
+            params![id],
+            row_to_folder,
+        )
+        .map_err(|_| AppError::validation("id", "folder not found"))?;
+
+    if let Some(parent_id) = &new_parent_id {
+        if folders::ancestors(&tx, parent_id)?.is_empty() {
+            return Err(AppError::validation("new_parent_id", "folder not found"));
+        }
+        if folders::is_within(&tx, parent_id, &id)? {
+            return Err(AppError::validation(
+                "new_parent_id",
+                "cannot move a folder into itself or one of its subfolders",
+            ));
+        }
+    }
+    if folder.parent_id == new_parent_id {
+        return Ok(folder);
+    }
 
-    let updated = Event {
-        id: current.id,
-        title: data.title.unwrap_or(current.title),
-        description: data.description.or(current.description),
-        event_type: current.event_type,
-        start_time: data.start_time.or(current.start_time),
-        end_time: data.end_time.or(current.end_time),
-        has_scheduled_time: current.has_scheduled_time,
-        time_mode: data.time_mode.unwrap_or(current.time_mode),
-        duration_minutes: data.duration_minutes.or(current.duration_minutes),
-        location: data.location.or(current.location),
-        category: data.category.or(current.category),
-        color: data.color.or(current.color),
-        priority: data.priority.or(current.priority),
-        tags: data.tags.unwrap_or(current.tags),
-        show_on_calendar: data.show_on_calendar.unwrap_or(current.show_on_calendar),
-        is_all_day: data.is_all_day.unwrap_or(current.is_all_day),
-        is_recurring: data.is_recurring.unwrap_or(current.is_recurring),
-        recurring_pattern: data.recurring_pattern.or(current.recurring_pattern),
-        status: data.status.or(current.status),
-        reminders: data.reminders.unwrap_or(current.reminders),
-        notes: current.notes,
-        created_at: current.created_at,
-        updated_at: now,
-        deleted_at: current.deleted_at,
-    };
+    if let Some(existing) = folders::child(&tx, new_parent_id.as_deref(), &folder.name)? {
+        if !merge.unwrap_or(false) {
+            return Err(AppError::conflict(
+                format!("A folder named \"{}\" already exists there", existing.name),
+                existing.id,
+            ));
+        }
+        folders::merge_into(&tx, &folder, &existing, &timestamps::format(&now))?;
+        tx.commit().map_err(|e| e.to_string())?;
+        return Ok(existing);
+    }
 
-    conn.execute(
-        "UPDATE events SET title = ?1, description = ?2, start_time = ?3, end_time = ?4,
-                          time_mode = ?5, duration_minutes = ?6, location = ?7, category = ?8,
-                          color = ?9, priority = ?10, tags = ?11, show_on_calendar = ?12,
-                          is_all_day = ?13, is_recurring = ?14, recurring_pattern = ?15,
-                          status = ?16, reminders = ?17, updated_at = ?18
-         WHERE id = ?19",
+    folder.parent_id = new_parent_id;
+    folder.updated_at = now;
+    tx.execute(
+        "UPDATE folders SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
         params![
-            updated.title,
-            updated.description,
-            updated.start_time,
-            updated.end_time,
-            updated.time_mode,
-            updated.duration_minutes,
-            updated.location,
-            updated.category,
-            updated.color,
-            updated.priority,
-            serde_json::to_string(&updated.tags).unwrap_or_default(),
-            updated.show_on_calendar as i32,
-            updated.is_all_day as i32,
-            updated.is_recurring as i32,
-            updated.recurring_pattern,
-            updated.status,
-            serde_json::to_string(&updated.reminders).unwrap_or_default(),
-            updated.updated_at,
-            updated.id,
+            folder.parent_id,
+            timestamps::format(&folder.updated_at),
+            folder.id
         ],
     )
     .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-    Ok(updated)
+    Ok(folder)
 }
 
 #[tauri::command]
-pub fn delete_event(db: State<Database>, id: String, hard: Option<bool>) -> Result<(), String> {
+#[specta::specta]
+pub fn delete_folder(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_folder");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    if hard.unwrap_or(false) {
-        conn.execute("DELETE FROM events WHERE id = ?1", params![id])
-    } else {
-        let now = Utc::now().to_rfc3339();
-        conn.execute(
-            "UPDATE events SET deleted_at = ?1 WHERE id = ?2",
-            params![now, id],
-        )
-    }
+    // Move notes in this folder to no folder
+    conn.execute(
+        "UPDATE notes SET folder_id = NULL WHERE folder_id = ?1",
+        params![id],
+    )
     .map_err(|e| e.to_string())?;
 
+    // Delete the folder
+    metadata::remove_all(&conn, EntityKind::Folder, &id)?;
+    conn.execute("DELETE FROM folders WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
-// ============ Brain Map Commands ============
+// ============ Metadata Commands ============
 
-fn row_to_brain_map(row: &rusqlite::Row) -> rusqlite::Result<BrainMap> {
-    Ok(BrainMap {
-        id: row.get(0)?,
-        title: row.get(1)?,
-        description: row.get(2)?,
-        center_node_id: row.get(3)?,
-        center_node_text: row.get(4)?,
-        viewport_x: row.get(5)?,
-        viewport_y: row.get(6)?,
-        viewport_zoom: row.get(7)?,
-        theme: row.get(8)?,
-        created_at: row.get(9)?,
-        updated_at: row.get(10)?,
-        deleted_at: row.get(11)?,
-    })
+// Custom fields of a note, event or folder, sorted by key
+#[tauri::command]
+#[specta::specta]
+pub fn get_metadata(
+    db: State<Database>,
+    entity_type: EntityKind,
+    entity_id: String,
+) -> Result<Vec<MetadataEntry>, String> {
+    let _span = profiling::span("get_metadata");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    metadata::get(&conn, entity_type, &entity_id)
 }
 
-fn row_to_brain_map_node(row: &rusqlite::Row) -> rusqlite::Result<BrainMapNode> {
-    let is_collapsed: i32 = row.get(14)?;
-    Ok(BrainMapNode {
-        id: row.get(0)?,
-        brain_map_id: row.get(1)?,
-        parent_node_id: row.get(2)?,
-        label: row.get(3)?,
-        description: row.get(4)?,
-        x: row.get(5)?,
-        y: row.get(6)?,
-        color: row.get(7)?,
-        shape: row.get(8)?,
-        size: row.get(9)?,
-        icon: row.get(10)?,
-        linked_note_id: row.get(11)?,
-        linked_folder_id: row.get(12)?,
-        linked_event_id: row.get(13)?,
-        is_collapsed: is_collapsed != 0,
-        layer: row.get(15)?,
-        created_at: row.get(16)?,
-        updated_at: row.get(17)?,
-    })
-}
+// Sets one field to `value` (JSON text), or removes it when `value` is None
+#[tauri::command]
+#[specta::specta]
+pub fn set_metadata(
+    db: State<Database>,
+    entity_type: EntityKind,
+    entity_id: String,
+    key: String,
+    value: Option<String>,
+) -> Result<Vec<MetadataEntry>, AppError> {
+    let _span = profiling::span("set_metadata");
+    let key = key.trim();
+    validation::metadata(key, value.as_deref())?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if !metadata::entity_exists(&conn, entity_type, &entity_id)? {
+        return Err(AppError::validation(
+            "entity_id",
+            format!("no {} with this id", entity_type.as_str()),
+        ));
+    }
 
-fn row_to_brain_map_connection(row: &rusqlite::Row) -> rusqlite::Result<BrainMapConnection> {
-    let animated: i32 = row.get(6)?;
-    Ok(BrainMapConnection {
-        id: row.get(0)?,
-        brain_map_id: row.get(1)?,
-        source_node_id: row.get(2)?,
-        target_node_id: row.get(3)?,
-        label: row.get(4)?,
-        color: row.get(5)?,
-        style: row.get(7)?,
-        animated: animated != 0,
-        created_at: row.get(8)?,
-    })
+    let now = timestamps::format(&Utc::now());
+    metadata::set(&conn, entity_type, &entity_id, key, value.as_deref(), &now)?;
+    Ok(metadata::get(&conn, entity_type, &entity_id)?)
 }
 
+// ============ Palette Commands ============
+
+// Colors offered by the folder, event and map node pickers
 #[tauri::command]
-pub fn get_brain_maps(db: State<Database>) -> Result<Vec<BrainMap>, String> {
+#[specta::specta]
+pub fn get_color_palette(db: State<Database>) -> Result<Vec<PaletteColor>, String> {
+    let _span = profiling::span("get_color_palette");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(palette::colors(&conn))
+}
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, center_node_id, center_node_text,
-                    viewport_x, viewport_y, viewport_zoom, theme,
-                    created_at, updated_at, deleted_at
-             FROM brain_maps
-             WHERE deleted_at IS NULL
-             ORDER BY updated_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
+// Adds a custom color and returns the whole palette
+#[tauri::command]
+#[specta::specta]
+pub fn add_palette_color(
+    db: State<Database>,
+    name: String,
+    hex: String,
+) -> Result<Vec<PaletteColor>, AppError> {
+    let _span = profiling::span("add_palette_color");
+    validation::palette_color(&name, &hex)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let hex = hex.to_ascii_lowercase();
+    if palette::colors(&conn).iter().any(|c| c.hex.eq_ignore_ascii_case(&hex)) {
+        return Err(AppError::validation("hex", "is already in the palette"));
+    }
 
-    let rows = stmt.query_map([], row_to_brain_map).map_err(|e| e.to_string())?;
-    let brain_maps: Vec<BrainMap> = rows.filter_map(|r| r.ok()).collect();
-    Ok(brain_maps)
+    let mut custom = palette::custom_colors(&conn);
+    custom.push(PaletteColor {
+        name: name.trim().to_string(),
+        hex,
+        custom: true,
+    });
+    palette::save_custom_colors(&conn, &custom)?;
+    Ok(palette::colors(&conn))
 }
 
+// Built-in colors can't be removed; colors already in use are left as they are
 #[tauri::command]
-pub fn get_brain_map(db: State<Database>, id: String) -> Result<Option<BrainMapWithData>, String> {
+#[specta::specta]
+pub fn remove_palette_color(db: State<Database>, hex: String) -> Result<Vec<PaletteColor>, String> {
+    let _span = profiling::span("remove_palette_color");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut custom = palette::custom_colors(&conn);
+    custom.retain(|c| !c.hex.eq_ignore_ascii_case(&hex));
+    palette::save_custom_colors(&conn, &custom)?;
+    Ok(palette::colors(&conn))
+}
 
-    // Get brain map
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, center_node_id, center_node_text,
-                    viewport_x, viewport_y, viewport_zoom, theme,
-                    created_at, updated_at, deleted_at
-             FROM brain_maps WHERE id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+#[specta::specta]
+pub fn get_icon_catalog(db: State<Database>) -> Result<Vec<CatalogIcon>, String> {
+    let _span = profiling::span("get_icon_catalog");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(palette::icons(&conn))
+}
 
-    let brain_map: Option<BrainMap> = stmt.query_row(params![id], row_to_brain_map).ok();
+// Adds a custom icon and returns the whole catalog
+#[tauri::command]
+#[specta::specta]
+pub fn add_catalog_icon(
+    db: State<Database>,
+    id: String,
+    label: String,
+    glyph: String,
+) -> Result<Vec<CatalogIcon>, AppError> {
+    let _span = profiling::span("add_catalog_icon");
+    validation::catalog_icon(&id, &label, &glyph)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if palette::icons(&conn).iter().any(|i| i.id == id) {
+        return Err(AppError::validation("id", "is already in the catalog"));
+    }
 
-    if let Some(bm) = brain_map {
-        // Get nodes
-        let mut node_stmt = conn
-            .prepare(
-                "SELECT id, brain_map_id, parent_node_id, label, description,
-                        x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
-                        linked_event_id, is_collapsed, layer, created_at, updated_at
-                 FROM brain_map_nodes WHERE brain_map_id = ?1
-                 ORDER BY layer ASC, created_at ASC",
-            )
-            .map_err(|e| e.to_string())?;
+    let mut custom = palette::custom_icons(&conn);
+    custom.push(CatalogIcon {
+        id,
+        label: label.trim().to_string(),
+        glyph: glyph.trim().to_string(),
+        custom: true,
+    });
+    palette::save_custom_icons(&conn, &custom)?;
+    Ok(palette::icons(&conn))
+}
 
-        let node_rows = node_stmt
-            .query_map(params![id], row_to_brain_map_node)
-            .map_err(|e| e.to_string())?;
-        let nodes: Vec<BrainMapNode> = node_rows.filter_map(|r| r.ok()).collect();
+// Built-in icons can't be removed; folders and nodes using the icon keep its id
+#[tauri::command]
+#[specta::specta]
+pub fn remove_catalog_icon(db: State<Database>, id: String) -> Result<Vec<CatalogIcon>, String> {
+    let _span = profiling::span("remove_catalog_icon");
+    if palette::is_builtin_icon(&id) {
+        return Err(format!("{} is a built-in icon", id));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut custom = palette::custom_icons(&conn);
+    custom.retain(|i| i.id != id);
+    palette::save_custom_icons(&conn, &custom)?;
+    Ok(palette::icons(&conn))
+}
 
-        // Get connections
-        let mut conn_stmt = conn
-            .prepare(
-                "SELECT id, brain_map_id, source_node_id, target_node_id, label, color, animated, style, created_at
-                 FROM brain_map_connections WHERE brain_map_id = ?1",
-            )
-            .map_err(|e| e.to_string())?;
+// ============ Settings Commands ============
 
-        let conn_rows = conn_stmt
-            .query_map(params![id], row_to_brain_map_connection)
-            .map_err(|e| e.to_string())?;
-        let connections: Vec<BrainMapConnection> = conn_rows.filter_map(|r| r.ok()).collect();
+// Settings with their own commands, which check what gets stored and keep
+// anything secret out of the webview; the generic commands refuse them
+const MANAGED_SETTINGS: &[&str] = &[mirror::MIRROR_SETTING, publish::PUBLISH_TARGET_SETTING];
 
-        Ok(Some(BrainMapWithData {
-            brain_map: bm,
-            nodes,
-            connections,
-        }))
-    } else {
-        Ok(None)
+#[tauri::command]
+#[specta::specta]
+pub fn get_setting(db: State<Database>, key: String) -> Result<Option<String>, String> {
+    let _span = profiling::span("get_setting");
+    if MANAGED_SETTINGS.contains(&key.as_str()) {
+        return Err(format!("The {} setting has its own command", key));
     }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT value FROM settings WHERE key = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let value: Option<String> = stmt.query_row(params![key], |row| row.get(0)).ok();
+    Ok(value)
 }
 
 #[tauri::command]
-pub fn create_brain_map(db: State<Database>, data: BrainMapCreate) -> Result<BrainMapWithData, String> {
+#[specta::specta]
+pub fn set_setting(db: State<Database>, key: String, value: String) -> Result<(), String> {
+    let _span = profiling::span("set_setting");
+    if MANAGED_SETTINGS.contains(&key.as_str()) {
+        return Err(format!("The {} setting has its own command", key));
+    }
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let map_id = format!("brainmap_{}", Uuid::new_v4());
-    let center_node_id = format!("node_{}", Uuid::new_v4());
-
-    let brain_map = BrainMap {
-        id: map_id.clone(),
-        title: data.title.unwrap_or_else(|| "Untitled Map".to_string()),
-        description: data.description,
-        center_node_id: Some(center_node_id.clone()),
-        center_node_text: data.center_node_text.clone().unwrap_or_else(|| "Central Idea".to_string()),
-        viewport_x: 0.0,
-        viewport_y: 0.0,
-        viewport_zoom: 1.0,
-        theme: data.theme,
-        created_at: now.clone(),
-        updated_at: now.clone(),
-        deleted_at: None,
-    };
 
-    // Insert brain map
     conn.execute(
-        "INSERT INTO brain_maps (id, title, description, center_node_id, center_node_text,
-                                 viewport_x, viewport_y, viewport_zoom, theme, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-        params![
-            brain_map.id,
-            brain_map.title,
-            brain_map.description,
-            brain_map.center_node_id,
-            brain_map.center_node_text,
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// ============ Helper Functions ============
+
+pub(crate) fn row_to_note(row: &rusqlite::Row) -> rusqlite::Result<Note> {
+    let tags_str: String = row.get("tags")?;
+    let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+    let is_pinned: i32 = row.get("is_pinned")?;
+    let aliases_str: String = row.get("aliases")?;
+    let aliases: Vec<String> = serde_json::from_str(&aliases_str).unwrap_or_default();
+
+    Ok(Note {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        content: row.get("content")?,
+        folder_id: row.get("folder_id")?,
+        tags,
+        aliases,
+        is_pinned: is_pinned != 0,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+        deleted_at: timestamps::get_opt(row, "deleted_at")?,
+        published_url: row.get("published_url")?,
+        language: row.get("language")?,
+    })
+}
+
+pub(crate) fn row_to_folder(row: &rusqlite::Row) -> rusqlite::Result<Folder> {
+    Ok(Folder {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        parent_id: row.get("parent_id")?,
+        color: row.get("color")?,
+        icon: row.get("icon")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+pub(crate) fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<Event> {
+    let tags_str: String = row.get("tags")?;
+    let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+    let reminders_str: String = row.get("reminders")?;
+    let reminders: Vec<EventReminder> = serde_json::from_str(&reminders_str).unwrap_or_default();
+    let has_scheduled_time: i32 = row.get("has_scheduled_time")?;
+    let show_on_calendar: i32 = row.get("show_on_calendar")?;
+    let is_all_day: i32 = row.get("is_all_day")?;
+    let is_recurring: i32 = row.get("is_recurring")?;
+    let start_time = timestamps::column(row.get("start_time")?);
+    let travel_minutes: Option<i32> = row.get("travel_minutes")?;
+
+    Ok(Event {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        event_type: row.get("event_type")?,
+        end_time: timestamps::column(row.get("end_time")?),
+        has_scheduled_time: has_scheduled_time != 0,
+        time_mode: row.get("time_mode")?,
+        duration_minutes: row.get("duration_minutes")?,
+        location: row.get("location")?,
+        category: row.get("category")?,
+        color: row.get("color")?,
+        priority: row.get("priority")?,
+        tags,
+        show_on_calendar: show_on_calendar != 0,
+        is_all_day: is_all_day != 0,
+        is_recurring: is_recurring != 0,
+        recurring_pattern: row.get("recurring_pattern")?,
+        status: row.get("status")?,
+        reminders,
+        notes: row.get("notes")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+        deleted_at: timestamps::get_opt(row, "deleted_at")?,
+        travel_minutes,
+        leave_by: leave_by(start_time.as_ref(), travel_minutes),
+        start_time,
+    })
+}
+
+// When to set off for an event `travel_minutes` away
+fn leave_by(
+    start_time: Option<&DateTime<Utc>>,
+    travel_minutes: Option<i32>,
+) -> Option<DateTime<Utc>> {
+    let minutes = travel_minutes.filter(|m| *m > 0)?;
+    Some(*start_time? - Duration::minutes(minutes as i64))
+}
+
+// ============ Events Commands ============
+
+fn load_event(conn: &rusqlite::Connection, id: &str) -> Result<Event, String> {
+    conn.prepare_cached(&format!("SELECT {} FROM events WHERE id = ?1", queries::EVENT_COLUMNS))
+        .and_then(|mut stmt| stmt.query_row(params![id], row_to_event))
+        .map_err(|_| "Event not found".to_string())
+}
+
+pub(crate) fn insert_event(conn: &rusqlite::Connection, event: &Event) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO events (id, title, description, event_type, start_time, end_time, has_scheduled_time,
+                            time_mode, duration_minutes, location, category, color, priority, tags,
+                            show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
+                            reminders, notes, created_at, updated_at, travel_minutes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+        params![
+            event.id,
+            event.title,
+            event.description,
+            event.event_type,
+            event.start_time.as_ref().map(timestamps::format),
+            event.end_time.as_ref().map(timestamps::format),
+            event.has_scheduled_time as i32,
+            event.time_mode,
+            event.duration_minutes,
+            event.location,
+            event.category,
+            event.color,
+            event.priority,
+            serde_json::to_string(&event.tags).unwrap_or_default(),
+            event.show_on_calendar as i32,
+            event.is_all_day as i32,
+            event.is_recurring as i32,
+            event.recurring_pattern,
+            event.status,
+            serde_json::to_string(&event.reminders).unwrap_or_default(),
+            event.notes,
+            timestamps::format(&event.created_at),
+            timestamps::format(&event.updated_at),
+            event.travel_minutes,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_events(db: State<Database>) -> Result<Vec<Event>, String> {
+    let mut span = profiling::span("get_events");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {}
+             FROM events
+             WHERE deleted_at IS NULL
+             ORDER BY start_time ASC",
+            queries::EVENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([], row_to_event).map_err(|e| e.to_string())?;
+    let events: Vec<Event> = rows.filter_map(|r| r.ok()).collect();
+    span.rows(events.len());
+    Ok(events)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_events_by_type(
+    db: State<Database>,
+    event_type: EventType,
+) -> Result<Vec<Event>, String> {
+    let mut span = profiling::span("get_events_by_type");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {}
+             FROM events
+             WHERE event_type = ?1 AND deleted_at IS NULL
+             ORDER BY start_time ASC",
+            queries::EVENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![event_type], row_to_event)
+        .map_err(|e| e.to_string())?;
+    let events: Vec<Event> = rows.filter_map(|r| r.ok()).collect();
+    span.rows(events.len());
+    Ok(events)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_event(db: State<Database>, id: String) -> Result<Option<Event>, String> {
+    let _span = profiling::span("get_event");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare_cached(&format!("SELECT {} FROM events WHERE id = ?1", queries::EVENT_COLUMNS))
+        .map_err(|e| e.to_string())?;
+
+    let event = stmt.query_row(params![id], row_to_event).ok();
+    Ok(event)
+}
+
+// An unsaved event from create data, with the same defaults as create_event
+pub(crate) fn new_event(data: EventCreate) -> Event {
+    let now = timestamps::now();
+    Event {
+        id: format!("event_{}", Uuid::new_v4()),
+        title: data.title,
+        description: data.description,
+        event_type: data.event_type.or(Some(EventType::Event)),
+        start_time: data.start_time,
+        end_time: data.end_time,
+        has_scheduled_time: data.start_time.is_some(),
+        time_mode: data.time_mode.unwrap_or(TimeMode::AtTime),
+        duration_minutes: data.duration_minutes,
+        location: data.location,
+        category: data.category.or(Some("personal".to_string())),
+        color: data.color,
+        priority: data.priority.or(Some(Priority::Medium)),
+        tags: data.tags.unwrap_or_default(),
+        show_on_calendar: data.show_on_calendar.unwrap_or(true),
+        is_all_day: data.is_all_day.unwrap_or(false),
+        is_recurring: data.is_recurring.unwrap_or(false),
+        recurring_pattern: data.recurring_pattern,
+        status: Some(EventStatus::Pending),
+        reminders: data.reminders.unwrap_or_default(),
+        notes: data.notes,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+        travel_minutes: None,
+        leave_by: None,
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_event(
+    app: AppHandle,
+    db: State<Database>,
+    data: EventCreate,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("create_event");
+    validation::event_create(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let event = new_event(data);
+    insert_event(&conn, &event)?;
+    scripting::dispatch(&app, Hook::EventCreated, &event);
+
+    Ok(event)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_event(
+    app: AppHandle,
+    db: State<Database>,
+    id: String,
+    data: EventUpdate,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("update_event");
+    validation::event_update(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+
+    // Derived, like in create_event: an event is on the calendar once it has a start
+    let has_scheduled_time = match data.start_time {
+        Patch::Undefined => None,
+        Patch::Null => Some(0),
+        Patch::Value(_) => Some(1),
+    };
+
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("title", data.title)
+        .set("description", data.description.into_change())
+        .set("event_type", data.event_type)
+        .set("start_time", data.start_time.map(|t| timestamps::format(&t)).into_change())
+        .set("has_scheduled_time", has_scheduled_time)
+        .set("end_time", data.end_time.map(|t| timestamps::format(&t)).into_change())
+        .set("time_mode", data.time_mode)
+        .set("duration_minutes", data.duration_minutes.into_change())
+        .set("location", data.location.into_change())
+        .set("category", data.category.into_change())
+        .set("color", data.color.into_change())
+        .set("priority", data.priority)
+        .set("tags", data.tags.map(|tags| serde_json::to_string(&tags).unwrap_or_default()))
+        .set("show_on_calendar", data.show_on_calendar.map(|show| show as i32))
+        .set("is_all_day", data.is_all_day.map(|all_day| all_day as i32))
+        .set("is_recurring", data.is_recurring.map(|recurring| recurring as i32))
+        .set("recurring_pattern", data.recurring_pattern.into_change())
+        .set("status", data.status)
+        .set(
+            "reminders",
+            data.reminders.map(|reminders| serde_json::to_string(&reminders).unwrap_or_default()),
+        )
+        .set("notes", data.notes.into_change())
+        .set_value("updated_at", now);
+    if !changes.execute(&conn, "events", &id)? {
+        return Err("Event not found".into());
+    }
+
+    let event = load_event(&conn, &id)?;
+    scripting::dispatch(&app, Hook::EventUpdated, &event);
+    Ok(event)
+}
+
+pub fn purge_event(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    metadata::remove_all(conn, EntityKind::Event, id)?;
+    recurrence::remove(conn, id)?;
+    dependencies::remove_all(conn, id)?;
+    projects::unlink_all(conn, ProjectItemKind::Event, id)?;
+    goals::unlink_all(conn, id)?;
+    conn.execute("DELETE FROM events WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_event(db: State<Database>, id: String, hard: Option<bool>) -> Result<(), String> {
+    let _span = profiling::span("delete_event");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    if hard.unwrap_or(false) {
+        purge_event(&conn, &id)?;
+    } else {
+        let now = timestamps::format(&Utc::now());
+        conn.execute(
+            "UPDATE events SET deleted_at = ?1 WHERE id = ?2",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// A copy of `source` starting at `new_start`, keeping its duration, reminders,
+// tags and category. The copy starts out pending.
+fn copy_event(source: Event, new_start: DateTime<Utc>) -> Event {
+    let duration = match (source.start_time, source.end_time) {
+        (Some(old_start), Some(old_end)) => Some(old_end - old_start),
+        _ => source
+            .duration_minutes
+            .map(|minutes| Duration::minutes(minutes as i64)),
+    };
+    let end_time = match duration {
+        Some(duration) if source.end_time.is_some() => Some(new_start + duration),
+        _ => None,
+    };
+
+    let now = timestamps::now();
+    Event {
+        id: format!("event_{}", Uuid::new_v4()),
+        start_time: Some(new_start),
+        end_time,
+        has_scheduled_time: true,
+        status: Some(EventStatus::Pending),
+        notes: None,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+        leave_by: leave_by(Some(&new_start), source.travel_minutes),
+        ..source
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn duplicate_event(
+    db: State<Database>,
+    id: String,
+    new_start: DateTime<Utc>,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("duplicate_event");
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let event = copy_event(load_event(&conn, &id)?, new_start);
+    insert_event(&conn, &event)?;
+
+    Ok(event)
+}
+
+// Logs another occurrence of the most recently created event in `category`,
+// starting now. None when the category has no events yet.
+#[tauri::command]
+#[specta::specta]
+pub fn create_event_like_last(db: State<Database>, category: String) -> Result<Option<Event>, AppError> {
+    let _span = profiling::span("create_event_like_last");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let last = conn
+        .query_row(
+            &format!(
+                "SELECT {}
+                 FROM events
+                 WHERE category = ?1 AND deleted_at IS NULL
+                 ORDER BY created_at DESC LIMIT 1",
+                queries::EVENT_COLUMNS
+            ),
+            params![category],
+            row_to_event,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(last) = last else {
+        return Ok(None);
+    };
+
+    let event = copy_event(last, Utc::now());
+    insert_event(&conn, &event)?;
+
+    Ok(Some(event))
+}
+
+// Moves each event's start by whatever `delta_for` returns for it, and its
+// end by the same amount, in one transaction. Events without a start time
+// are left as they are.
+fn reschedule_events<F>(
+    conn: &mut rusqlite::Connection,
+    ids: &[String],
+    delta_for: F,
+) -> Result<Vec<Event>, String>
+where
+    F: Fn(&DateTime<Utc>) -> Option<Duration>,
+{
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+
+    let mut events = Vec::new();
+    for id in ids {
+        let mut event = load_event(&tx, id)?;
+        let Some(start) = event.start_time else {
+            continue;
+        };
+        let Some(delta) = delta_for(&start) else {
+            continue;
+        };
+
+        event.start_time = Some(start + delta);
+        event.end_time = event.end_time.map(|end| end + delta);
+        event.leave_by = event.leave_by.map(|leave_by| leave_by + delta);
+        event.updated_at = now;
+        tx.execute(
+            "UPDATE events SET start_time = ?1, end_time = ?2, updated_at = ?3 WHERE id = ?4",
+            params![
+                event.start_time.as_ref().map(timestamps::format),
+                event.end_time.as_ref().map(timestamps::format),
+                timestamps::format(&event.updated_at),
+                event.id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        events.push(event);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(events)
+}
+
+// Returns the events that moved
+#[tauri::command]
+#[specta::specta]
+pub fn shift_events(db: State<Database>, ids: Vec<String>, delta_minutes: i32) -> Result<Vec<Event>, String> {
+    let _span = profiling::span("shift_events");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let delta = Duration::minutes(delta_minutes as i64);
+    reschedule_events(&mut conn, &ids, |_| Some(delta))
+}
+
+// `date` is a calendar day like 2024-05-01. Each event keeps its time of day
+// and duration. Returns the events that moved.
+#[tauri::command]
+#[specta::specta]
+pub fn move_events_to_date(
+    db: State<Database>,
+    ids: Vec<String>,
+    date: String,
+) -> Result<Vec<Event>, AppError> {
+    let _span = profiling::span("move_events_to_date");
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| AppError::validation("date", "must be a date like 2024-05-01"))?;
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let events = reschedule_events(&mut conn, &ids, |start| {
+        // Same local time of day on the new date, even across a DST change
+        let local = timestamps::local(start);
+        let moved = Local
+            .from_local_datetime(&date.and_time(local.time()))
+            .earliest()?;
+        Some(moved.with_timezone(&Utc) - *start)
+    })?;
+    Ok(events)
+}
+
+fn priority_rank(priority: Option<Priority>) -> u8 {
+    match priority {
+        Some(Priority::High) => 0,
+        Some(Priority::Medium) | None => 1,
+        Some(Priority::Low) => 2,
+    }
+}
+
+// The planner backlog: events without a time yet. Sorted by priority (then
+// newest first) unless the filter asks for creation order.
+#[tauri::command]
+#[specta::specta]
+pub fn get_unscheduled_events(
+    db: State<Database>,
+    filters: Option<UnscheduledFilter>,
+) -> Result<Vec<Event>, String> {
+    let mut span = profiling::span("get_unscheduled_events");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let filters = filters.unwrap_or_default();
+
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {}
+             FROM events
+             WHERE has_scheduled_time = 0 AND deleted_at IS NULL
+             ORDER BY created_at DESC",
+            queries::EVENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_event).map_err(|e| e.to_string())?;
+
+    // Filtered here rather than in SQL so legacy spellings ("urgent", "done")
+    // match through the enums
+    let search = filters.search.as_deref().map(str::to_lowercase);
+    let mut events: Vec<Event> = rows
+        .filter_map(|r| r.ok())
+        .filter(|e| filters.category.is_none() || e.category == filters.category)
+        .filter(|e| filters.priority.is_none() || e.priority == filters.priority)
+        .filter(|e| match &search {
+            Some(search) => e.title.to_lowercase().contains(search),
+            None => true,
+        })
+        .filter(|e| {
+            filters.include_finished.unwrap_or(false)
+                || !matches!(
+                    e.status,
+                    Some(EventStatus::Completed | EventStatus::Cancelled | EventStatus::Skipped)
+                )
+        })
+        .collect();
+
+    // The sort is stable, so equal priorities stay newest first
+    if filters.sort.unwrap_or(BacklogSort::Priority) == BacklogSort::Priority {
+        events.sort_by_key(|e| priority_rank(e.priority));
+    }
+
+    span.rows(events.len());
+    Ok(events)
+}
+
+// Gives a backlog event its time and puts it on the calendar in one update
+#[tauri::command]
+#[specta::specta]
+pub fn schedule_event(
+    db: State<Database>,
+    id: String,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("schedule_event");
+    validation::event_schedule(&start, end.as_ref())?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let changed = conn
+        .execute(
+            "UPDATE events SET start_time = ?1, end_time = ?2, has_scheduled_time = 1, updated_at = ?3
+             WHERE id = ?4 AND deleted_at IS NULL",
+            params![
+                timestamps::format(&start),
+                end.as_ref().map(timestamps::format),
+                timestamps::format(&Utc::now()),
+                id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Event not found".into());
+    }
+
+    Ok(load_event(&conn, &id)?)
+}
+
+// Sets or clears (`None`) the travel buffer; the event's leave_by follows it
+#[tauri::command]
+#[specta::specta]
+pub fn set_event_travel_time(
+    db: State<Database>,
+    id: String,
+    travel_minutes: Option<i32>,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("set_event_travel_time");
+    validation::travel_minutes(travel_minutes)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let changed = conn
+        .execute(
+            "UPDATE events SET travel_minutes = ?1, updated_at = ?2
+             WHERE id = ?3 AND deleted_at IS NULL",
+            params![travel_minutes, timestamps::format(&Utc::now()), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Event not found".into());
+    }
+
+    Ok(load_event(&conn, &id)?)
+}
+
+// ============ Locale Commands ============
+
+// Longest range get_agenda will group
+const MAX_AGENDA_DAYS: i64 = 366;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_locale_settings(db: State<Database>) -> Result<LocaleSettings, String> {
+    let _span = profiling::span("get_locale_settings");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(locale::load(&conn))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_locale_settings(db: State<Database>, settings: LocaleSettings) -> Result<(), String> {
+    let _span = profiling::span("set_locale_settings");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            locale::LOCALE_SETTING,
+            serde_json::to_string(&settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// The week containing `date`, starting on the configured first day
+#[tauri::command]
+#[specta::specta]
+pub fn get_week_range(db: State<Database>, date: String) -> Result<WeekRange, AppError> {
+    let _span = profiling::span("get_week_range");
+    let date = date_arg("date", &date)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = locale::load(&conn);
+
+    let start = locale::week_start(date, settings.week_start);
+    let (year, week_number) = locale::week_number(date, settings.week_start);
+    Ok(WeekRange {
+        start: start.format("%Y-%m-%d").to_string(),
+        end: (start + Duration::days(6)).format("%Y-%m-%d").to_string(),
+        year,
+        week_number,
+    })
+}
+
+// Today's events, due tasks, habits, edited notes and reminders in one call
+#[tauri::command]
+#[specta::specta]
+pub fn get_today_view(db: State<Database>) -> Result<TodayView, String> {
+    let _span = profiling::span("get_today_view");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    today::build(&conn, Local::now().date_naive())
+}
+
+// Scheduled events from `start` to `end` (inclusive) grouped by the local day
+// they start on. Days without events are left out.
+#[tauri::command]
+#[specta::specta]
+pub fn get_agenda(
+    db: State<Database>,
+    start: String,
+    end: String,
+) -> Result<Vec<AgendaDay>, AppError> {
+    let _span = profiling::span("get_agenda");
+    let start = date_arg("start", &start)?;
+    let end = date_arg("end", &end)?;
+    if end < start {
+        return Err(AppError::validation("end", "must not be before start"));
+    }
+    if (end - start).num_days() >= MAX_AGENDA_DAYS {
+        return Err(AppError::validation("end", "range must be at most a year"));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = locale::load(&conn);
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {}
+             FROM events
+             WHERE deleted_at IS NULL AND start_time IS NOT NULL AND show_on_calendar = 1
+             ORDER BY start_time ASC",
+            queries::EVENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let events = stmt
+        .query_map([], row_to_event)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok());
+
+    let mut days: Vec<AgendaDay> = Vec::new();
+    for event in events {
+        let Some(day) = event
+            .start_time
+            .as_ref()
+            .map(timestamps::local_date)
+            .filter(|d| *d >= start && *d <= end)
+        else {
+            continue;
+        };
+        let entry = AgendaEntry {
+            time_label: event
+                .start_time
+                .as_ref()
+                .filter(|_| !event.is_all_day)
+                .map(timestamps::local)
+                .map(|t| t.time())
+                .map(|t| locale::format_time(t, settings.clock)),
+            event,
+        };
+        let date = day.format("%Y-%m-%d").to_string();
+        match days.iter_mut().find(|d| d.date == date) {
+            Some(agenda_day) => agenda_day.entries.push(entry),
+            None => days.push(AgendaDay {
+                date,
+                label: format!(
+                    "{} {}",
+                    day.format("%A"),
+                    locale::format_date(day, settings.date_order)
+                ),
+                week_number: locale::week_number(day, settings.week_start).1,
+                entries: vec![entry],
+            }),
+        }
+    }
+
+    days.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok(days)
+}
+
+// Queues writing scheduled events to an .ics file the user picks, optionally
+// only those starting between `start` and `end` (YYYY-MM-DD, inclusive).
+// Returns the job id, or None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_events_ics(
+    app: AppHandle,
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let _span = profiling::span("export_events_ics");
+    // Checked now so a bad date fails the call instead of the job
+    for (field, value) in [("start", &start), ("end", &end)] {
+        if let Some(value) = value {
+            date_arg(field, value)?;
+        }
+    }
+
+    let file_name = format!("voyena-events-{}.ics", Utc::now().format("%Y-%m-%d"));
+    let Some(path) = pick_save_path(&app, &file_name, "iCalendar", &["ics"])? else {
+        return Ok(None);
+    };
+    Ok(Some(jobs::enqueue(&app, JobSpec::EventsIcs { path, start, end })?))
+}
+
+// Queues rendering the folder's notes, subfolders included, as linked HTML
+// pages with an index.html into a directory the user picks. Returns the job
+// id, or None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_folder_site(
+    app: AppHandle,
+    db: State<'_, Database>,
+    folder_id: String,
+) -> Result<Option<String>, AppError> {
+    let _span = profiling::span("export_folder_site");
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM folders WHERE id = ?1)",
+                params![folder_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(AppError::validation("folder_id", "Folder not found"));
+        }
+    }
+
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .set_title("Export folder as a website")
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let root = picked.into_path().map_err(|e| e.to_string())?;
+    Ok(Some(jobs::enqueue(&app, JobSpec::FolderSite { folder_id, root })?))
+}
+
+// ============ Project Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_projects(db: State<Database>) -> Result<Vec<Project>, String> {
+    let _span = profiling::span("get_projects");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    projects::list(&conn)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_project(db: State<Database>, data: ProjectCreate) -> Result<Project, AppError> {
+    let _span = profiling::span("create_project");
+    validation::project_create(&data)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(projects::insert(&conn, data, &timestamps::now())?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_project(
+    db: State<Database>,
+    id: String,
+    data: ProjectUpdate,
+) -> Result<Project, AppError> {
+    let _span = profiling::span("update_project");
+    validation::project_update(&data)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(current) = projects::load(&conn, &id)? else {
+        return Err(AppError::validation("id", "project not found"));
+    };
+    Ok(projects::update(&conn, current, data, &timestamps::now())?)
+}
+
+// Unlinks everything first; the notes, events and maps stay
+#[tauri::command]
+#[specta::specta]
+pub fn delete_project(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_project");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    projects::delete(&conn, &id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn link_project_item(
+    db: State<Database>,
+    project_id: String,
+    item_type: ProjectItemKind,
+    item_id: String,
+) -> Result<(), AppError> {
+    let _span = profiling::span("link_project_item");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if projects::load(&conn, &project_id)?.is_none() {
+        return Err(AppError::validation("project_id", "project not found"));
+    }
+    if !projects::item_exists(&conn, item_type, &item_id)? {
+        return Err(AppError::validation("item_id", "not found"));
+    }
+    let now = timestamps::format(&Utc::now());
+    Ok(projects::link(&conn, &project_id, item_type, &item_id, &now)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unlink_project_item(
+    db: State<Database>,
+    project_id: String,
+    item_type: ProjectItemKind,
+    item_id: String,
+) -> Result<(), String> {
+    let _span = profiling::span("unlink_project_item");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    projects::unlink(&conn, &project_id, item_type, &item_id)
+}
+
+// Linked items with task progress and the most recently changed items
+#[tauri::command]
+#[specta::specta]
+pub fn get_project_overview(db: State<Database>, id: String) -> Result<ProjectOverview, String> {
+    let _span = profiling::span("get_project_overview");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let project = projects::load(&conn, &id)?.ok_or_else(|| format!("Project {} not found", id))?;
+    projects::overview(&conn, project, Local::now().date_naive())
+}
+
+// ============ Goal Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_goals(db: State<Database>) -> Result<Vec<Goal>, String> {
+    let _span = profiling::span("get_goals");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    goals::list(&conn)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_goal(db: State<Database>, data: GoalCreate) -> Result<Goal, AppError> {
+    let _span = profiling::span("create_goal");
+    validation::goal_create(&data)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(goals::insert(&conn, data, &timestamps::now())?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_goal(db: State<Database>, id: String, data: GoalUpdate) -> Result<Goal, AppError> {
+    let _span = profiling::span("update_goal");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(current) = goals::load(&conn, &id)? else {
+        return Err(AppError::validation("id", "goal not found"));
+    };
+    validation::goal_update(&current, &data)?;
+    Ok(goals::update(&conn, current, data, &timestamps::now())?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_goal(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_goal");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    goals::delete(&conn, &id)
+}
+
+// Links a task or habit; its completions count towards the goal
+#[tauri::command]
+#[specta::specta]
+pub fn link_goal_item(
+    db: State<Database>,
+    goal_id: String,
+    event_id: String,
+) -> Result<(), AppError> {
+    let _span = profiling::span("link_goal_item");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if goals::load(&conn, &goal_id)?.is_none() {
+        return Err(AppError::validation("goal_id", "goal not found"));
+    }
+    if !projects::item_exists(&conn, ProjectItemKind::Event, &event_id)? {
+        return Err(AppError::validation("event_id", "task not found"));
+    }
+    Ok(goals::link(&conn, &goal_id, &event_id, &timestamps::format(&Utc::now()))?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unlink_goal_item(
+    db: State<Database>,
+    goal_id: String,
+    event_id: String,
+) -> Result<(), String> {
+    let _span = profiling::span("unlink_goal_item");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    goals::unlink(&conn, &goal_id, &event_id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_goal_progress(db: State<Database>, id: String) -> Result<GoalProgress, String> {
+    let _span = profiling::span("get_goal_progress");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let goal = goals::load(&conn, &id)?.ok_or_else(|| format!("Goal {} not found", id))?;
+    goals::progress(&conn, goal, Local::now().date_naive())
+}
+
+// ============ Date Parsing Commands ============
+
+// Resolves "next friday 3pm", "in 2 weeks", "5/6" and the like. `reference`
+// defaults to now; `locale` (e.g. "en-GB") decides whether numeric dates are
+// day or month first, falling back to the date order setting. None when the
+// expression isn't understood.
+#[tauri::command]
+#[specta::specta]
+pub fn parse_date_expression(
+    db: State<Database>,
+    text: String,
+    reference: Option<String>,
+    locale: Option<String>,
+) -> Result<Option<ParsedDate>, AppError> {
+    let _span = profiling::span("parse_date_expression");
+    let reference = match reference {
+        Some(value) => match DateTime::parse_from_rfc3339(&value) {
+            Ok(time) => time.with_timezone(&Local).naive_local(),
+            Err(_) => validation::parse_timestamp(&value)
+                .ok_or_else(|| AppError::validation("reference", "must be an RFC 3339 timestamp"))?,
+        },
+        None => Local::now().naive_local(),
+    };
+    let day_first = match locale {
+        Some(locale) => date_parse::day_first(&locale),
+        None => {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            locale::load(&conn).date_order == DateOrder::Dmy
+        }
+    };
+
+    let Some(resolved) = date_parse::resolve(&text, reference, day_first) else {
+        return Ok(None);
+    };
+    let at = resolved.date.and_time(resolved.time.unwrap_or_default());
+    Ok(Some(ParsedDate {
+        date: resolved.date.format("%Y-%m-%d").to_string(),
+        time: resolved.time.map(|t| t.format("%H:%M").to_string()),
+        timestamp: at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }))
+}
+
+// ============ Inbox Commands ============
+
+fn row_to_inbox_item(row: &rusqlite::Row) -> rusqlite::Result<InboxItem> {
+    let tags_str: String = row.get("tags")?;
+    let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+
+    Ok(InboxItem {
+        id: row.get("id")?,
+        text: row.get("text")?,
+        title: row.get("title")?,
+        tags,
+        due_date: row.get("due_date")?,
+        priority: row.get("priority")?,
+        folder_path: row.get("folder_path")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+// Files an entry as a task: on its due date as an all-day item, otherwise
+// unscheduled in the backlog
+fn file_as_task(
+    conn: &rusqlite::Connection,
+    title: String,
+    tags: Vec<String>,
+    due_date: Option<&str>,
+    priority: Option<Priority>,
+) -> Result<Event, String> {
+    let event = new_event(EventCreate {
+        title,
+        description: None,
+        event_type: Some(EventType::Task),
+        start_time: due_date
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .map(locale::day_start_utc),
+        end_time: None,
+        time_mode: Some(if due_date.is_some() { TimeMode::AllDay } else { TimeMode::Todo }),
+        duration_minutes: None,
+        location: None,
+        category: Some("todo".to_string()),
+        color: None,
+        priority,
+        tags: Some(tags),
+        show_on_calendar: None,
+        is_all_day: Some(due_date.is_some()),
+        is_recurring: None,
+        recurring_pattern: None,
+        reminders: None,
+        notes: None,
+    });
+    insert_event(conn, &event)?;
+    Ok(event)
+}
+
+pub(crate) fn insert_inbox_item(
+    conn: &rusqlite::Connection,
+    item: &InboxItem,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO inbox_items (id, text, title, tags, due_date, priority, folder_path,
+                                  created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            item.id,
+            item.text,
+            item.title,
+            serde_json::to_string(&item.tags).unwrap_or_default(),
+            item.due_date,
+            item.priority,
+            item.folder_path,
+            timestamps::format(&item.created_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// `#tag`, `@date`, `!priority` and `/Folder/Path` tokens are parsed out of
+// `text`; the folder applies when the entry is filed as a note
+#[tauri::command]
+#[specta::specta]
+pub fn quick_add(db: State<Database>, text: String) -> Result<QuickAddResult, AppError> {
+    let _span = profiling::span("quick_add");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let day_first = locale::load(&conn).date_order == DateOrder::Dmy;
+    let parsed = quick_add::parse(&text, Local::now().naive_local(), day_first);
+    validation::quick_add(&parsed.title, &parsed.tags)?;
+    if let Some(path) = &parsed.folder_path {
+        validation::folder_path(path)?;
+    }
+
+    let due_date = parsed.due.map(|d| d.format("%Y-%m-%d").to_string());
+    if due_date.is_some() {
+        let event = file_as_task(
+            &conn,
+            parsed.title,
+            parsed.tags,
+            due_date.as_deref(),
+            parsed.priority,
+        )?;
+        return Ok(QuickAddResult {
+            inbox_item: None,
+            event: Some(event),
+        });
+    }
+
+    let item = InboxItem {
+        id: format!("inbox_{}", Uuid::new_v4()),
+        text: text.trim().to_string(),
+        title: parsed.title,
+        tags: parsed.tags,
+        due_date,
+        priority: parsed.priority,
+        folder_path: parsed.folder_path,
+        created_at: timestamps::now(),
+    };
+    insert_inbox_item(&conn, &item)?;
+
+    Ok(QuickAddResult {
+        inbox_item: Some(item),
+        event: None,
+    })
+}
+
+// Untriaged items, oldest first
+#[tauri::command]
+#[specta::specta]
+pub fn get_inbox_items(db: State<Database>) -> Result<Vec<InboxItem>, String> {
+    let _span = profiling::span("get_inbox_items");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, text, title, tags, due_date, priority, folder_path, created_at
+             FROM inbox_items
+             WHERE triaged_at IS NULL
+             ORDER BY created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let items = stmt
+        .query_map([], row_to_inbox_item)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(items)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn triage_inbox_item(
+    db: State<Database>,
+    id: String,
+    action: TriageAction,
+) -> Result<TriageResult, AppError> {
+    let _span = profiling::span("triage_inbox_item");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let item = conn
+        .query_row(
+            "SELECT id, text, title, tags, due_date, priority, folder_path, created_at
+             FROM inbox_items WHERE id = ?1 AND triaged_at IS NULL",
+            params![id],
+            row_to_inbox_item,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Inbox item not found")?;
+
+    let mut result = TriageResult {
+        note: None,
+        event: None,
+    };
+    match action {
+        TriageAction::Note => {
+            let folder = match item.folder_path.as_deref() {
+                Some(path) => folders::resolve_path(&conn, path, true)?,
+                None => None,
+            };
+            result.note = Some(insert_note(
+                &conn,
+                NoteCreate {
+                    title: Some(item.title),
+                    content: None,
+                    folder_id: folder.map(|f| f.id),
+                    tags: Some(item.tags),
+                    aliases: None,
+                },
+            )?);
+        }
+        TriageAction::Task => {
+            result.event = Some(file_as_task(
+                &conn,
+                item.title,
+                item.tags,
+                item.due_date.as_deref(),
+                item.priority,
+            )?);
+        }
+        TriageAction::Dismiss => {}
+    }
+
+    let filed_id = result
+        .note
+        .as_ref()
+        .map(|n| n.id.clone())
+        .or(result.event.as_ref().map(|e| e.id.clone()));
+    conn.execute(
+        "UPDATE inbox_items SET triaged_at = ?1, filed_id = ?2 WHERE id = ?3",
+        params![timestamps::format(&Utc::now()), filed_id, item.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+// A YYYY-MM-DD command argument
+fn date_arg(field: &str, value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::validation(field, "must be a date like 2024-05-01"))
+}
+
+// ============ Recurring Task Commands ============
+
+const DEFAULT_HISTORY_DAYS: i64 = 90;
+
+// Makes a task recurring with `rule`, or a one-off again with None. Past
+// completions are kept either way.
+#[tauri::command]
+#[specta::specta]
+pub fn set_task_recurrence(
+    db: State<Database>,
+    id: String,
+    rule: Option<RecurrenceRule>,
+) -> Result<Event, AppError> {
+    let _span = profiling::span("set_task_recurrence");
+    if let Some(rule) = &rule {
+        validation::recurrence_rule(rule)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    load_event(&conn, &id)?;
+    recurrence::save_rule(&conn, &id, rule.as_ref(), &timestamps::format(&Utc::now()))?;
+    Ok(load_event(&conn, &id)?)
+}
+
+// Occurrences from `start` to `end` (YYYY-MM-DD, inclusive), by default the
+// last 90 days up to today, with streaks and totals
+#[tauri::command]
+#[specta::specta]
+pub fn get_task_history(
+    db: State<Database>,
+    id: String,
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<TaskHistory, AppError> {
+    let _span = profiling::span("get_task_history");
+    let today = Local::now().date_naive();
+    let end = end.map(|e| date_arg("end", &e)).transpose()?.unwrap_or(today);
+    let start = match start {
+        Some(start) => date_arg("start", &start)?,
+        None => end - Duration::days(DEFAULT_HISTORY_DAYS - 1),
+    };
+    if end < start {
+        return Err(AppError::validation("end", "must not be before start"));
+    }
+    if (end - start).num_days() >= MAX_AGENDA_DAYS {
+        return Err(AppError::validation("end", "range must be at most a year"));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let event = load_event(&conn, &id)?;
+    let Some(rule) = recurrence::rule(&conn, &event)? else {
+        return Err(AppError::validation("id", "is not a recurring task"));
+    };
+    Ok(recurrence::history(&conn, &event, rule, start, end, today)?)
+}
+
+fn set_occurrence_completed(
+    db: State<Database>,
+    id: String,
+    date: String,
+    completed: bool,
+) -> Result<TaskOccurrence, AppError> {
+    let date = date_arg("date", &date)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let event = load_event(&conn, &id)?;
+    let Some(rule) = recurrence::rule(&conn, &event)? else {
+        return Err(AppError::validation("id", "is not a recurring task"));
+    };
+    if !recurrence::occurs_on(&rule, recurrence::anchor(&event), date) {
+        return Err(AppError::validation("date", "is not an occurrence of this task"));
+    }
+
+    let now = timestamps::format(&Utc::now());
+    recurrence::set_completed(&conn, &id, date, completed, &now)?;
+    Ok(recurrence::occurrence(&conn, &id, date, Local::now().date_naive())?)
+}
+
+// Records one occurrence as done; the task itself stays open for the next
+#[tauri::command]
+#[specta::specta]
+pub fn complete_task_occurrence(
+    db: State<Database>,
+    id: String,
+    date: String,
+) -> Result<TaskOccurrence, AppError> {
+    let _span = profiling::span("complete_task_occurrence");
+    set_occurrence_completed(db, id, date, true)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn reopen_task_occurrence(
+    db: State<Database>,
+    id: String,
+    date: String,
+) -> Result<TaskOccurrence, AppError> {
+    let _span = profiling::span("reopen_task_occurrence");
+    set_occurrence_completed(db, id, date, false)
+}
+
+// ============ Task Dependency Commands ============
+
+// `task_id` can't be started before `depends_on_id` is done
+#[tauri::command]
+#[specta::specta]
+pub fn add_task_dependency(
+    db: State<Database>,
+    task_id: String,
+    depends_on_id: String,
+) -> Result<(), AppError> {
+    let _span = profiling::span("add_task_dependency");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    load_event(&conn, &task_id).map_err(|_| AppError::validation("task_id", "task not found"))?;
+    load_event(&conn, &depends_on_id)
+        .map_err(|_| AppError::validation("depends_on_id", "task not found"))?;
+    if dependencies::would_cycle(&conn, &task_id, &depends_on_id)? {
+        return Err(AppError::validation(
+            "depends_on_id",
+            "already depends on this task, directly or through others",
+        ));
+    }
+    dependencies::add(&conn, &task_id, &depends_on_id, &timestamps::format(&Utc::now()))?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_task_dependency(
+    db: State<Database>,
+    task_id: String,
+    depends_on_id: String,
+) -> Result<(), String> {
+    let _span = profiling::span("remove_task_dependency");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    dependencies::remove(&conn, &task_id, &depends_on_id)
+}
+
+// Open tasks linked to `project` (all of them when None) in an order that
+// respects their dependencies. Cycles are reported rather than failing the
+// plan.
+#[tauri::command]
+#[specta::specta]
+pub fn get_task_plan(db: State<Database>, project: Option<String>) -> Result<TaskPlan, String> {
+    let _span = profiling::span("get_task_plan");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut condition = format!("{} AND {}", today::IS_TASK, today::IS_OPEN);
+    let mut args = Vec::new();
+    if let Some(project) = project.as_deref() {
+        condition = format!("{} AND {}", condition, projects::linked(ProjectItemKind::Event));
+        args.push(project);
+    }
+    let tasks = today::query_events(&conn, &condition, &args)?;
+    dependencies::plan(&conn, tasks)
+}
+
+// ============ Productivity Commands ============
+
+const BUSIEST_DAYS_LIMIT: u32 = 5;
+
+// Hours an event blocks out: its start-to-end span, else its duration
+const SCHEDULED_HOURS_SQL: &str = "CASE
+    WHEN is_all_day != 0 OR status = 'cancelled' THEN 0
+    WHEN end_time IS NOT NULL AND julianday(end_time) > julianday(start_time)
+        THEN (julianday(end_time) - julianday(start_time)) * 24
+    WHEN duration_minutes > 0 THEN duration_minutes / 60.0
+    ELSE 0
+END";
+
+// Everything is aggregated in SQL; `start` and `end` are YYYY-MM-DD, inclusive
+#[tauri::command]
+#[specta::specta]
+pub fn get_productivity_metrics(
+    db: State<Database>,
+    start: String,
+    end: String,
+) -> Result<ProductivityMetrics, AppError> {
+    let _span = profiling::span("get_productivity_metrics");
+    let start_date = date_arg("start", &start)?;
+    let end_date = date_arg("end", &end)?;
+    if end_date < start_date {
+        return Err(AppError::validation("end", "must not be before start"));
+    }
+    let days = (end_date - start_date).num_days() + 1;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (events_total, events_completed, events_cancelled, events_missed, scheduled_hours) = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(status = 'completed'), 0),
+                        COALESCE(SUM(status = 'cancelled'), 0),
+                        COALESCE(SUM(status = 'missed'), 0),
+                        TOTAL({})
+                 FROM events
+                 WHERE deleted_at IS NULL AND date(start_time, 'localtime') BETWEEN ?1 AND ?2",
+                SCHEDULED_HOURS_SQL
+            ),
+            params![start, end],
+            |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT date(start_time, 'localtime') AS day, COUNT(*), TOTAL({}) AS hours
+             FROM events
+             WHERE deleted_at IS NULL AND status IS NOT 'cancelled'
+               AND day BETWEEN ?1 AND ?2
+             GROUP BY day
+             ORDER BY COUNT(*) DESC, hours DESC, day ASC
+             LIMIT ?3",
+            SCHEDULED_HOURS_SQL
+        ))
+        .map_err(|e| e.to_string())?;
+    let busiest_days = stmt
+        .query_map(params![start, end, BUSIEST_DAYS_LIMIT], |row| {
+            Ok(BusyDay {
+                date: row.get(0)?,
+                event_count: row.get(1)?,
+                scheduled_hours: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let notes_created: u32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM notes
+             WHERE deleted_at IS NULL AND date(created_at) BETWEEN ?1 AND ?2",
+            params![start, end],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(ProductivityMetrics {
+        events_total,
+        events_completed,
+        events_cancelled,
+        events_missed,
+        busiest_days,
+        average_scheduled_hours: scheduled_hours / days as f64,
+        notes_created,
+    })
+}
+
+// ============ Holiday Commands ============
+
+// Longest range get_holidays will expand
+const MAX_HOLIDAY_RANGE_DAYS: i64 = 3660;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_holiday_regions(db: State<Database>) -> Result<Vec<HolidayRegion>, String> {
+    let _span = profiling::span("get_holiday_regions");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let selected = holidays::load_selected(&conn)?;
+    Ok(holidays::regions(&selected))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_holiday_regions(db: State<Database>, codes: Vec<String>) -> Result<(), AppError> {
+    let _span = profiling::span("set_holiday_regions");
+    let codes = holidays::canonical_codes(&codes).map_err(|code| {
+        AppError::validation("codes", format!("no holiday calendar for '{}'", code))
+    })?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            holidays::HOLIDAY_REGIONS_SETTING,
+            serde_json::to_string(&codes).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// Holidays of the selected regions from `start` to `end` (YYYY-MM-DD, inclusive)
+#[tauri::command]
+#[specta::specta]
+pub fn get_holidays(
+    db: State<Database>,
+    start: String,
+    end: String,
+) -> Result<Vec<Holiday>, AppError> {
+    let _span = profiling::span("get_holidays");
+    let start = date_arg("start", &start)?;
+    let end = date_arg("end", &end)?;
+    if end < start {
+        return Err(AppError::validation("end", "must not be before start"));
+    }
+    if (end - start).num_days() > MAX_HOLIDAY_RANGE_DAYS {
+        return Err(AppError::validation("end", "range must be at most ten years"));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let selected = holidays::load_selected(&conn)?;
+    Ok(holidays::between(&selected, start, end))
+}
+
+// ============ Review Commands ============
+
+// Writes the week's review into the Reviews folder, creating the folder on
+// first use. Regenerating a week replaces its earlier review.
+#[tauri::command]
+#[specta::specta]
+pub fn generate_weekly_review(db: State<Database>, week: String) -> Result<Note, AppError> {
+    let _span = profiling::span("generate_weekly_review");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = locale::load(&conn);
+    let start = review::week_start(&week, &settings).ok_or_else(|| {
+        AppError::validation("week", "must be a week like 2024-W18 or a date like 2024-05-01")
+    })?;
+
+    let content = review::compose(&conn, start, &settings)?;
+    let title = review::title(start, &settings);
+
+    let folder_id = folders::resolve_path(&conn, review::REVIEWS_FOLDER, true)?
+        .map(|folder| folder.id)
+        .ok_or("Reviews folder could not be created")?;
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM notes WHERE folder_id = ?1 AND title = ?2 AND deleted_at IS NULL",
+            params![folder_id, title],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(id) = existing else {
+        return Ok(insert_note(
+            &conn,
+            NoteCreate {
+                title: Some(title),
+                content: Some(content),
+                folder_id: Some(folder_id),
+                tags: Some(vec!["review".to_string()]),
+                aliases: None,
+            },
+        )?);
+    };
+
+    conn.execute(
+        "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+        params![sanitize::sanitize_content(&content), timestamps::format(&Utc::now()), id],
+    )
+    .map_err(|e| e.to_string())?;
+    search_index::note_changed(&conn, &id);
+    let note = conn
+        .query_row(
+            &format!("SELECT {} FROM notes WHERE id = ?1", queries::NOTE_COLUMNS),
+            params![id],
+            row_to_note,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(note)
+}
+
+// ============ Brain Map Commands ============
+
+pub(crate) fn row_to_brain_map(row: &rusqlite::Row) -> rusqlite::Result<BrainMap> {
+    Ok(BrainMap {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        center_node_id: row.get("center_node_id")?,
+        center_node_text: row.get("center_node_text")?,
+        viewport_x: row.get("viewport_x")?,
+        viewport_y: row.get("viewport_y")?,
+        viewport_zoom: row.get("viewport_zoom")?,
+        theme: row.get("theme")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+        deleted_at: timestamps::get_opt(row, "deleted_at")?,
+    })
+}
+
+pub(crate) fn row_to_brain_map_node(row: &rusqlite::Row) -> rusqlite::Result<BrainMapNode> {
+    let is_collapsed: i32 = row.get("is_collapsed")?;
+    Ok(BrainMapNode {
+        id: row.get("id")?,
+        brain_map_id: row.get("brain_map_id")?,
+        parent_node_id: row.get("parent_node_id")?,
+        label: row.get("label")?,
+        description: row.get("description")?,
+        x: row.get("x")?,
+        y: row.get("y")?,
+        color: row.get("color")?,
+        shape: row.get("shape")?,
+        size: row.get("size")?,
+        icon: row.get("icon")?,
+        linked_note_id: row.get("linked_note_id")?,
+        linked_folder_id: row.get("linked_folder_id")?,
+        linked_event_id: row.get("linked_event_id")?,
+        is_collapsed: is_collapsed != 0,
+        layer: row.get("layer")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+pub(crate) fn row_to_brain_map_connection(row: &rusqlite::Row) -> rusqlite::Result<BrainMapConnection> {
+    let animated: i32 = row.get("animated")?;
+    Ok(BrainMapConnection {
+        id: row.get("id")?,
+        brain_map_id: row.get("brain_map_id")?,
+        source_node_id: row.get("source_node_id")?,
+        target_node_id: row.get("target_node_id")?,
+        label: row.get("label")?,
+        color: row.get("color")?,
+        style: row.get("style")?,
+        animated: animated != 0,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+// Searches titles and descriptions. Node and connection counts leave out
+// anything in the trash.
+#[tauri::command]
+#[specta::specta]
+pub fn get_brain_maps(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    query: Option<ListQuery>,
+) -> Result<Page<BrainMapSummary>, String> {
+    let mut span = profiling::span("get_brain_maps");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush_viewports(&conn)?;
+    let query = query.unwrap_or_default();
+    let (limit, offset) = page_bounds(&query);
+
+    const FILTER: &str = "deleted_at IS NULL
+         AND (?1 IS NULL OR instr(lower(title), lower(?1)) > 0
+              OR instr(lower(coalesce(description, '')), lower(?1)) > 0)";
+
+    let total: u32 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM brain_maps WHERE {}", FILTER),
+            params![query.search],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, title, description, center_node_id, center_node_text,
+                    viewport_x, viewport_y, viewport_zoom, theme,
+                    created_at, updated_at, deleted_at,
+                    (SELECT COUNT(*) FROM brain_map_nodes n
+                     WHERE n.brain_map_id = brain_maps.id AND n.deleted_at IS NULL) AS node_count,
+                    (SELECT COUNT(*) FROM brain_map_connections c
+                     WHERE c.brain_map_id = brain_maps.id AND c.deleted_at IS NULL)
+                        AS connection_count
+             FROM brain_maps
+             WHERE {}
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            FILTER,
+            order_by(&query, ListSort::Updated, "title")
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![query.search, limit, offset], |row| {
+            Ok(BrainMapSummary {
+                brain_map: row_to_brain_map(row)?,
+                node_count: row.get("node_count")?,
+                connection_count: row.get("connection_count")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let brain_maps: Vec<BrainMapSummary> = rows.filter_map(|r| r.ok()).collect();
+    span.rows(brain_maps.len());
+    Ok(Page { items: brain_maps, total })
+}
+
+fn load_brain_map_data(conn: &rusqlite::Connection, id: &str) -> Result<Option<BrainMapWithData>, String> {
+    // Get brain map
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, description, center_node_id, center_node_text,
+                    viewport_x, viewport_y, viewport_zoom, theme,
+                    created_at, updated_at, deleted_at
+             FROM brain_maps WHERE id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let Some(brain_map) = stmt.query_row(params![id], row_to_brain_map).ok() else {
+        return Ok(None);
+    };
+
+    // Get nodes
+    let mut node_stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM brain_map_nodes WHERE brain_map_id = ?1 AND deleted_at IS NULL
+             ORDER BY layer ASC, created_at ASC",
+            queries::NODE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let node_rows = node_stmt
+        .query_map(params![id], row_to_brain_map_node)
+        .map_err(|e| e.to_string())?;
+    let nodes: Vec<BrainMapNode> = node_rows.filter_map(|r| r.ok()).collect();
+
+    // Get connections
+    let mut conn_stmt = conn
+        .prepare(
+            "SELECT id, brain_map_id, source_node_id, target_node_id, label, color, animated, style, created_at
+             FROM brain_map_connections WHERE brain_map_id = ?1 AND deleted_at IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let conn_rows = conn_stmt
+        .query_map(params![id], row_to_brain_map_connection)
+        .map_err(|e| e.to_string())?;
+    let connections: Vec<BrainMapConnection> = conn_rows.filter_map(|r| r.ok()).collect();
+
+    Ok(Some(BrainMapWithData {
+        brain_map,
+        nodes,
+        connections,
+    }))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_brain_map(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+) -> Result<Option<BrainMapWithData>, String> {
+    let mut span = profiling::span("get_brain_map");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush(&conn)?;
+
+    let data = load_brain_map_data(&conn, &id)?;
+    if let Some(data) = &data {
+        span.rows(data.nodes.len() + data.connections.len());
+    }
+    Ok(data)
+}
+
+// Like get_brain_map, but leaves out everything below a collapsed node.
+// `hidden_descendants` has the number of nodes hidden under each collapsed
+// node that is itself visible.
+#[tauri::command]
+#[specta::specta]
+pub fn get_brain_map_visible(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+) -> Result<Option<BrainMapVisibleData>, String> {
+    let mut span = profiling::span("get_brain_map_visible");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush(&conn)?;
+
+    let Some(data) = load_brain_map_data(&conn, &id)? else {
+        return Ok(None);
+    };
+
+    let ids: HashSet<&str> = data.nodes.iter().map(|n| n.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&BrainMapNode>> = HashMap::new();
+    let mut stack = Vec::new();
+    for node in &data.nodes {
+        match node.parent_node_id.as_deref().filter(|p| ids.contains(p)) {
+            Some(parent) => children.entry(parent).or_default().push(node),
+            None => stack.push(node),
+        }
+    }
+
+    // Walk down from the roots, stopping at collapsed nodes. `seen` guards
+    // against parent cycles.
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut hidden_descendants = HashMap::new();
+    while let Some(node) = stack.pop() {
+        if !seen.insert(node.id.as_str()) {
+            continue;
+        }
+        let below = children.get(node.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+        if !node.is_collapsed {
+            stack.extend(below);
+            continue;
+        }
+
+        let mut hidden = 0;
+        let mut pending: Vec<&BrainMapNode> = below.to_vec();
+        let mut counted: HashSet<&str> = HashSet::new();
+        while let Some(child) = pending.pop() {
+            if child.id != node.id && counted.insert(child.id.as_str()) {
+                hidden += 1;
+                pending.extend(children.get(child.id.as_str()).into_iter().flatten());
+            }
+        }
+        hidden_descendants.insert(node.id.clone(), hidden);
+    }
+
+    let nodes: Vec<BrainMapNode> = data
+        .nodes
+        .iter()
+        .filter(|n| seen.contains(n.id.as_str()))
+        .cloned()
+        .collect();
+    let connections: Vec<BrainMapConnection> = data
+        .connections
+        .iter()
+        .filter(|c| seen.contains(c.source_node_id.as_str()) && seen.contains(c.target_node_id.as_str()))
+        .cloned()
+        .collect();
+    span.rows(nodes.len() + connections.len());
+
+    Ok(Some(BrainMapVisibleData {
+        brain_map: data.brain_map,
+        nodes,
+        connections,
+        hidden_descendants,
+    }))
+}
+
+// How many nodes get_brain_map_stats lists as most connected
+const MOST_CONNECTED_LIMIT: usize = 5;
+
+// Degree counts parent and child links as well as custom connections
+#[tauri::command]
+#[specta::specta]
+pub fn get_brain_map_stats(db: State<Database>, id: String) -> Result<Option<BrainMapStats>, String> {
+    let _span = profiling::span("get_brain_map_stats");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let Some(data) = load_brain_map_data(&conn, &id)? else {
+        return Ok(None);
+    };
+
+    let ids: HashSet<&str> = data.nodes.iter().map(|n| n.id.as_str()).collect();
+    let mut degree: HashMap<&str, u32> = HashMap::new();
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut stack = Vec::new();
+    for node in &data.nodes {
+        match node.parent_node_id.as_deref().filter(|p| ids.contains(p)) {
+            Some(parent) => {
+                children.entry(parent).or_default().push(node.id.as_str());
+                *degree.entry(parent).or_default() += 1;
+                *degree.entry(node.id.as_str()).or_default() += 1;
+            }
+            None => stack.push((node.id.as_str(), 0)),
+        }
+    }
+
+    let mut connection_count = 0;
+    for connection in &data.connections {
+        let source = connection.source_node_id.as_str();
+        let target = connection.target_node_id.as_str();
+        if ids.contains(source) && ids.contains(target) {
+            connection_count += 1;
+            *degree.entry(source).or_default() += 1;
+            *degree.entry(target).or_default() += 1;
+        }
+    }
+
+    // `seen` guards against parent cycles, which never reach a root anyway
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut max_depth = 0;
+    while let Some((node_id, depth)) = stack.pop() {
+        if !seen.insert(node_id) {
+            continue;
+        }
+        max_depth = max_depth.max(depth);
+        for child in children.get(node_id).into_iter().flatten() {
+            stack.push((child, depth + 1));
+        }
+    }
+
+    let node_degree = |node: &BrainMapNode| NodeDegree {
+        node_id: node.id.clone(),
+        label: node.label.clone(),
+        degree: degree.get(node.id.as_str()).copied().unwrap_or(0),
+    };
+    let center = data.brain_map.center_node_id.as_deref();
+    let orphan_nodes = data
+        .nodes
+        .iter()
+        .filter(|n| Some(n.id.as_str()) != center && !degree.contains_key(n.id.as_str()))
+        .map(node_degree)
+        .collect();
+    let mut most_connected: Vec<NodeDegree> = data
+        .nodes
+        .iter()
+        .map(node_degree)
+        .filter(|n| n.degree > 0)
+        .collect();
+    most_connected.sort_by_key(|n| std::cmp::Reverse(n.degree));
+    most_connected.truncate(MOST_CONNECTED_LIMIT);
+
+    Ok(Some(BrainMapStats {
+        node_count: data.nodes.len() as u32,
+        connection_count,
+        max_depth,
+        orphan_nodes,
+        most_connected,
+    }))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_brain_map(db: State<Database>, data: BrainMapCreate) -> Result<BrainMapWithData, AppError> {
+    let _span = profiling::span("create_brain_map");
+    validation::brain_map_create(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+    let map_id = format!("brainmap_{}", Uuid::new_v4());
+    let center_node_id = format!("node_{}", Uuid::new_v4());
+
+    let brain_map = BrainMap {
+        id: map_id.clone(),
+        title: data.title.unwrap_or_else(|| "Untitled Map".to_string()),
+        description: data.description,
+        center_node_id: Some(center_node_id.clone()),
+        center_node_text: data.center_node_text.clone().unwrap_or_else(|| "Central Idea".to_string()),
+        viewport_x: 0.0,
+        viewport_y: 0.0,
+        viewport_zoom: 1.0,
+        theme: data.theme,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+
+    // Insert brain map
+    conn.execute(
+        "INSERT INTO brain_maps (id, title, description, center_node_id, center_node_text,
+                                 viewport_x, viewport_y, viewport_zoom, theme, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            brain_map.id,
+            brain_map.title,
+            brain_map.description,
+            brain_map.center_node_id,
+            brain_map.center_node_text,
             brain_map.viewport_x,
             brain_map.viewport_y,
             brain_map.viewport_zoom,
             brain_map.theme,
-            brain_map.created_at,
-            brain_map.updated_at,
+            timestamps::format(&brain_map.created_at),
+            timestamps::format(&brain_map.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Create center node
+    let center_node = BrainMapNode {
+        id: center_node_id.clone(),
+        brain_map_id: map_id.clone(),
+        parent_node_id: None,
+        label: data.center_node_text.unwrap_or_else(|| "Central Idea".to_string()),
+        description: None,
+        x: 0.0,
+        y: 0.0,
+        color: Some("#6366f1".to_string()),
+        shape: Some(NodeShape::Circle),
+        size: Some(NodeSize::Large),
+        icon: None,
+        linked_note_id: None,
+        linked_folder_id: None,
+        linked_event_id: None,
+        is_collapsed: false,
+        layer: 0,
+        created_at: now,
+        updated_at: now,
+    };
+
+    conn.execute(
+        "INSERT INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, description,
+                                      x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
+                                      linked_event_id, is_collapsed, layer, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            center_node.id,
+            center_node.brain_map_id,
+            center_node.parent_node_id,
+            center_node.label,
+            center_node.description,
+            center_node.x,
+            center_node.y,
+            center_node.color,
+            center_node.shape,
+            center_node.size,
+            center_node.icon,
+            center_node.linked_note_id,
+            center_node.linked_folder_id,
+            center_node.linked_event_id,
+            center_node.is_collapsed as i32,
+            center_node.layer,
+            timestamps::format(&center_node.created_at),
+            timestamps::format(&center_node.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(BrainMapWithData {
+        brain_map,
+        nodes: vec![center_node],
+        connections: vec![],
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_brain_map(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+    data: BrainMapUpdate,
+) -> Result<BrainMap, AppError> {
+    let _span = profiling::span("update_brain_map");
+    validation::brain_map_update(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush_viewports(&conn)?;
+    let now = timestamps::format(&Utc::now());
+
+    let select = "SELECT id, title, description, center_node_id, center_node_text,
+                         viewport_x, viewport_y, viewport_zoom, theme,
+                         created_at, updated_at, deleted_at
+                  FROM brain_maps WHERE id = ?1";
+    let current: BrainMap = conn
+        .query_row(select, params![id], row_to_brain_map)
+        .map_err(|e| e.to_string())?;
+
+    // The viewport isn't content: updated_at orders the map list and decides
+    // what sync sends, so panning or zooming alone leaves it as it was
+    let patched = |patch: &Patch<String>, current: &Option<String>| match patch {
+        Patch::Undefined => false,
+        Patch::Null => current.is_some(),
+        Patch::Value(value) => current.as_ref() != Some(value),
+    };
+    let content_changed = data.title.as_ref().is_some_and(|t| *t != current.title)
+        || patched(&data.description, &current.description)
+        || patched(&data.center_node_id, &current.center_node_id)
+        || data
+            .center_node_text
+            .as_ref()
+            .is_some_and(|t| *t != current.center_node_text)
+        || patched(&data.theme, &current.theme);
+
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("title", data.title)
+        .set("description", data.description.into_change())
+        .set("center_node_id", data.center_node_id.into_change())
+        .set("center_node_text", data.center_node_text)
+        .set("viewport_x", data.viewport_x)
+        .set("viewport_y", data.viewport_y)
+        .set("viewport_zoom", data.viewport_zoom)
+        .set("theme", data.theme.into_change())
+        .set_value(
+            "updated_at",
+            if content_changed {
+                now
+            } else {
+                timestamps::format(&current.updated_at)
+            },
+        );
+    changes.execute(&conn, "brain_maps", &id)?;
+
+    Ok(conn
+        .query_row(select, params![id], row_to_brain_map)
+        .map_err(|e| e.to_string())?)
+}
+
+// For panning and zooming, which call this continuously. The viewport is held
+// and written once it settles, and doesn't count as a change to the map.
+#[tauri::command]
+#[specta::specta]
+pub fn update_brain_map_viewport(
+    app: AppHandle,
+    write_behind: State<WriteBehind>,
+    id: String,
+    viewport: BrainMapViewport,
+) -> Result<(), AppError> {
+    let _span = profiling::span("update_brain_map_viewport");
+    validation::viewport(&viewport)?;
+    write_behind.hold_viewport(&app, id, viewport)?;
+    Ok(())
+}
+
+pub fn purge_brain_map(conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    for sql in [
+        "DELETE FROM brain_map_connections WHERE brain_map_id = ?1",
+        "DELETE FROM brain_map_nodes WHERE brain_map_id = ?1",
+        "DELETE FROM brain_map_ops WHERE brain_map_id = ?1",
+        "DELETE FROM brain_maps WHERE id = ?1",
+    ] {
+        conn.execute(sql, params![id]).map_err(|e| e.to_string())?;
+    }
+    projects::unlink_all(conn, ProjectItemKind::BrainMap, id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_brain_map(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+    hard: Option<bool>,
+) -> Result<(), String> {
+    let _span = profiling::span("delete_brain_map");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Children are handled explicitly since foreign keys aren't enforced. A
+    // soft delete stamps them with the map's deleted_at, which is how
+    // restore_brain_map tells them from anything trashed separately.
+    if hard.unwrap_or(false) {
+        purge_brain_map(&tx, &id)?;
+    } else {
+        let now = timestamps::format(&Utc::now());
+        for sql in [
+            "UPDATE brain_map_connections SET deleted_at = ?1 WHERE brain_map_id = ?2 AND deleted_at IS NULL",
+            "UPDATE brain_map_nodes SET deleted_at = ?1 WHERE brain_map_id = ?2 AND deleted_at IS NULL",
+            "UPDATE brain_maps SET deleted_at = ?1 WHERE id = ?2",
+        ] {
+            tx.execute(sql, params![now, id]).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Brings a trashed map back together with the nodes and connections that were
+// trashed with it.
+#[tauri::command]
+#[specta::specta]
+pub fn restore_brain_map(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("restore_brain_map");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let deleted_at: Option<String> = tx
+        .query_row(
+            "SELECT deleted_at FROM brain_maps WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let Some(deleted_at) = deleted_at else {
+        return Ok(());
+    };
+
+    tx.execute(
+        "UPDATE brain_map_nodes SET deleted_at = NULL WHERE brain_map_id = ?1 AND deleted_at = ?2",
+        params![id, deleted_at],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE brain_map_connections SET deleted_at = NULL WHERE brain_map_id = ?1 AND deleted_at = ?2",
+        params![id, deleted_at],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE brain_maps SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&Utc::now()), id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// `format` is svg, png, graphml or xmind. Images are rendered from the stored
+// node positions rather than the canvas, so the zoom and scroll the map was
+// left at don't matter. Returns the saved path, or None if cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_brain_map_image(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+    format: String,
+) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_brain_map_image");
+    let data = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        load_brain_map_data(&conn, &id)?.ok_or("Brain map not found")?
+    };
+
+    let (bytes, filter_name) = match format.as_str() {
+        "svg" => (map_image::render_svg(&data).into_bytes(), "SVG image"),
+        "png" => (map_image::rasterize_png(&map_image::render_svg(&data))?, "PNG image"),
+        "graphml" => (map_formats::graphml(&data).into_bytes(), "GraphML"),
+        "xmind" => (map_formats::xmind(&data)?, "XMind"),
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+
+    let Some(path) = pick_save_path(
+        &app,
+        &export::file_name(&data.brain_map.title, &format),
+        filter_name,
+        &[format.as_str()],
+    )?
+    else {
+        return Ok(None);
+    };
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+// ============ Brain Map Node Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_brain_map_node(db: State<Database>, data: BrainMapNodeCreate) -> Result<BrainMapNode, AppError> {
+    let _span = profiling::span("create_brain_map_node");
+    validation::node_create(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+    let id = format!("node_{}", Uuid::new_v4());
+
+    // Calculate layer based on parent
+    let layer = if let Some(ref parent_id) = data.parent_node_id {
+        let mut stmt = conn
+            .prepare("SELECT layer FROM brain_map_nodes WHERE id = ?1")
+            .map_err(|e| e.to_string())?;
+        let parent_layer: i32 = stmt.query_row(params![parent_id], |row| row.get(0)).unwrap_or(0);
+        parent_layer + 1
+    } else {
+        1
+    };
+
+    let node = BrainMapNode {
+        id: id.clone(),
+        brain_map_id: data.brain_map_id,
+        parent_node_id: data.parent_node_id,
+        label: data.label,
+        description: data.description,
+        x: data.x.unwrap_or(0.0),
+        y: data.y.unwrap_or(0.0),
+        color: data.color,
+        shape: data.shape.or(Some(NodeShape::Circle)),
+        size: data.size.or(Some(NodeSize::Medium)),
+        icon: data.icon,
+        linked_note_id: data.linked_note_id,
+        linked_folder_id: data.linked_folder_id,
+        linked_event_id: data.linked_event_id,
+        is_collapsed: false,
+        layer,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let op = history::record(
+        &conn,
+        &node.brain_map_id,
+        "create_node",
+        vec![node.id.clone()],
+        Vec::new(),
+    )?;
+
+    conn.execute(
+        "INSERT INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, description,
+                                      x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
+                                      linked_event_id, is_collapsed, layer, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            node.id,
+            node.brain_map_id,
+            node.parent_node_id,
+            node.label,
+            node.description,
+            node.x,
+            node.y,
+            node.color,
+            node.shape,
+            node.size,
+            node.icon,
+            node.linked_note_id,
+            node.linked_folder_id,
+            node.linked_event_id,
+            node.is_collapsed as i32,
+            node.layer,
+            timestamps::format(&node.created_at),
+            timestamps::format(&node.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Update brain map's updated_at
+    conn.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&now), node.brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    op.finish(&conn)?;
+
+    Ok(node)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_brain_map_node(
+    app: AppHandle,
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+    data: BrainMapNodeUpdate,
+) -> Result<BrainMapNode, AppError> {
+    let _span = profiling::span("update_brain_map_node");
+    validation::node_update(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush_positions(&conn)?;
+    let now = timestamps::now();
+
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {} FROM brain_map_nodes WHERE id = ?1",
+            queries::NODE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let current: BrainMapNode = stmt
+        .query_row(params![id], row_to_brain_map_node)
+        .map_err(|e| e.to_string())?;
+
+    let previous_note_id = current.linked_note_id.clone();
+    let updated = BrainMapNode {
+        id: current.id,
+        brain_map_id: current.brain_map_id.clone(),
+        parent_node_id: data.parent_node_id.apply(current.parent_node_id),
+        label: data.label.unwrap_or(current.label),
+        description: data.description.apply(current.description),
+        x: data.x.unwrap_or(current.x),
+        y: data.y.unwrap_or(current.y),
+        color: data.color.apply(current.color),
+        shape: data.shape.apply(current.shape),
+        size: data.size.apply(current.size),
+        icon: data.icon.apply(current.icon),
+        linked_note_id: data.linked_note_id.apply(current.linked_note_id),
+        linked_folder_id: data.linked_folder_id.apply(current.linked_folder_id),
+        linked_event_id: data.linked_event_id.apply(current.linked_event_id),
+        is_collapsed: data.is_collapsed.unwrap_or(current.is_collapsed),
+        layer: current.layer,
+        created_at: current.created_at,
+        updated_at: now,
+    };
+
+    let op = history::record(
+        &conn,
+        &updated.brain_map_id,
+        "update_node",
+        vec![updated.id.clone()],
+        Vec::new(),
+    )?;
+
+    conn.execute(
+        "UPDATE brain_map_nodes SET parent_node_id = ?1, label = ?2, description = ?3,
+                                   x = ?4, y = ?5, color = ?6, shape = ?7, size = ?8, icon = ?9,
+                                   linked_note_id = ?10, linked_folder_id = ?11, linked_event_id = ?12,
+                                   is_collapsed = ?13, updated_at = ?14
+         WHERE id = ?15",
+        params![
+            updated.parent_node_id,
+            updated.label,
+            updated.description,
+            updated.x,
+            updated.y,
+            updated.color,
+            updated.shape,
+            updated.size,
+            updated.icon,
+            updated.linked_note_id,
+            updated.linked_folder_id,
+            updated.linked_event_id,
+            updated.is_collapsed as i32,
+            timestamps::format(&updated.updated_at),
+            updated.id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Update brain map's updated_at
+    conn.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&now), updated.brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    op.finish(&conn)?;
+
+    if updated.linked_note_id != previous_note_id {
+        for note_id in previous_note_id.iter().chain(updated.linked_note_id.iter()) {
+            emit_note_links_changed(&app, note_id, &updated.id, &updated.brain_map_id);
+        }
+    }
+
+    Ok(updated)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_brain_map_node(
+    app: AppHandle,
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    id: String,
+) -> Result<(), String> {
+    let _span = profiling::span("delete_brain_map_node");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush_positions(&conn)?;
+    let now = timestamps::format(&Utc::now());
+
+    // Get brain_map_id and linked note before deleting
+    let (brain_map_id, linked_note_id): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT brain_map_id, linked_note_id FROM brain_map_nodes WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap_or((None, None));
+
+    let op = match &brain_map_id {
+        Some(bm_id) => Some(history::record_node_delete(&conn, bm_id, &id)?),
+        None => None,
+    };
+
+    // Foreign keys aren't enforced, so do what the schema's ON DELETE clauses say
+    conn.execute("DELETE FROM brain_map_nodes WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM brain_map_connections WHERE source_node_id = ?1 OR target_node_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE brain_map_nodes SET parent_node_id = NULL WHERE parent_node_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Update brain map's updated_at
+    if let Some(bm_id) = &brain_map_id {
+        conn.execute(
+            "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+            params![now, bm_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(op) = op {
+        op.finish(&conn)?;
+    }
+
+    if let (Some(note_id), Some(bm_id)) = (linked_note_id, brain_map_id) {
+        emit_note_links_changed(&app, &note_id, &id, &bm_id);
+    }
+
+    Ok(())
+}
+
+// Called on every frame of a drag. Positions are held and only the last one
+// per node is written once the drag pauses, as one undoable step per map.
+#[tauri::command]
+#[specta::specta]
+pub fn update_node_positions(
+    app: AppHandle,
+    write_behind: State<WriteBehind>,
+    updates: Vec<(String, f64, f64)>,
+) -> Result<(), String> {
+    let _span = profiling::span("update_node_positions");
+    write_behind.hold_positions(&app, updates)
+}
+
+// ============ Note Link Commands ============
+
+pub const NOTE_LINKS_EVENT: &str = "note:links-changed";
+
+fn emit_note_links_changed(app: &AppHandle, note_id: &str, node_id: &str, brain_map_id: &str) {
+    let payload = NoteLinksChanged {
+        note_id: note_id.to_string(),
+        node_id: node_id.to_string(),
+        brain_map_id: brain_map_id.to_string(),
+    };
+    if let Err(e) = app.emit(NOTE_LINKS_EVENT, payload) {
+        log::warn!("Failed to emit note link change: {}", e);
+    }
+}
+
+// The maps a note appears in, for the editor's "appears in" list. Nodes and
+// maps in the trash are left out.
+#[tauri::command]
+#[specta::specta]
+pub fn get_nodes_linked_to_note(db: State<Database>, note_id: String) -> Result<Vec<NoteNodeLink>, String> {
+    let _span = profiling::span("get_nodes_linked_to_note");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.label, m.id, m.title
+             FROM brain_map_nodes n JOIN brain_maps m ON m.id = n.brain_map_id
+             WHERE n.linked_note_id = ?1 AND n.deleted_at IS NULL AND m.deleted_at IS NULL
+             ORDER BY m.updated_at DESC, n.layer ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let links = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteNodeLink {
+                node_id: row.get(0)?,
+                node_label: row.get(1)?,
+                brain_map_id: row.get(2)?,
+                brain_map_title: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(links.filter_map(|r| r.ok()).collect())
+}
+
+// Points the node at the note, replacing whatever note it linked to before
+#[tauri::command]
+#[specta::specta]
+pub fn link_note_to_node(
+    app: AppHandle,
+    db: State<Database>,
+    note_id: String,
+    node_id: String,
+) -> Result<BrainMapNode, String> {
+    let _span = profiling::span("link_note_to_node");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    load_note(&conn, &note_id)?;
+
+    let mut node = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM brain_map_nodes WHERE id = ?1 AND deleted_at IS NULL",
+                queries::NODE_COLUMNS
+            ),
+            params![node_id],
+            row_to_brain_map_node,
+        )
+        .map_err(|_| "Brain map node not found".to_string())?;
+    if node.linked_note_id.as_deref() == Some(note_id.as_str()) {
+        return Ok(node);
+    }
+
+    let previous_note_id = node.linked_note_id.replace(note_id.clone());
+    node.updated_at = timestamps::now();
+
+    let op = history::record(
+        &conn,
+        &node.brain_map_id,
+        "link_note",
+        vec![node.id.clone()],
+        Vec::new(),
+    )?;
+    conn.execute(
+        "UPDATE brain_map_nodes SET linked_note_id = ?1, updated_at = ?2 WHERE id = ?3",
+        params![note_id, timestamps::format(&node.updated_at), node.id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&node.updated_at), node.brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+    op.finish(&conn)?;
+
+    for id in previous_note_id.iter().chain([&note_id]) {
+        emit_note_links_changed(&app, id, &node.id, &node.brain_map_id);
+    }
+
+    Ok(node)
+}
+
+// ============ Brain Map Connection Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_brain_map_connection(
+    db: State<Database>,
+    data: BrainMapConnectionCreate,
+) -> Result<BrainMapConnection, AppError> {
+    let _span = profiling::span("create_brain_map_connection");
+    validation::connection_create(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+    let id = format!("conn_{}", Uuid::new_v4());
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM brain_map_connections
+             WHERE brain_map_id = ?1 AND source_node_id = ?2 AND target_node_id = ?3",
+            params![data.brain_map_id, data.source_node_id, data.target_node_id],
+            |row| row.get(0),
+        )
+        .ok();
+    if let Some(existing_id) = existing {
+        return Err(AppError::conflict("These nodes are already connected", existing_id));
+    }
+
+    let connection = BrainMapConnection {
+        id: id.clone(),
+        brain_map_id: data.brain_map_id.clone(),
+        source_node_id: data.source_node_id,
+        target_node_id: data.target_node_id,
+        label: data.label,
+        color: data.color,
+        style: data.style.or(Some("solid".to_string())),
+        animated: data.animated.unwrap_or(false),
+        created_at: now,
+    };
+
+    let op = history::record(
+        &conn,
+        &connection.brain_map_id,
+        "create_connection",
+        Vec::new(),
+        vec![connection.id.clone()],
+    )?;
+
+    conn.execute(
+        "INSERT INTO brain_map_connections (id, brain_map_id, source_node_id, target_node_id, label, color, style, animated, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            connection.id,
+            connection.brain_map_id,
+            connection.source_node_id,
+            connection.target_node_id,
+            connection.label,
+            connection.color,
+            connection.style,
+            connection.animated as i32,
+            timestamps::format(&connection.created_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Update brain map's updated_at
+    conn.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&now), data.brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    op.finish(&conn)?;
+
+    Ok(connection)
+}
+
+// Connections in either direction between two nodes
+#[tauri::command]
+#[specta::specta]
+pub fn get_connections_between(db: State<Database>, a: String, b: String) -> Result<Vec<BrainMapConnection>, String> {
+    let _span = profiling::span("get_connections_between");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, brain_map_id, source_node_id, target_node_id, label, color, animated, style, created_at
+             FROM brain_map_connections
+             WHERE ((source_node_id = ?1 AND target_node_id = ?2)
+                OR (source_node_id = ?2 AND target_node_id = ?1))
+               AND deleted_at IS NULL
+             ORDER BY created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![a, b], row_to_brain_map_connection)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_brain_map_connection(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_brain_map_connection");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let brain_map_id: Option<String> = conn
+        .query_row(
+            "SELECT brain_map_id FROM brain_map_connections WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .ok();
+    let op = match &brain_map_id {
+        Some(bm_id) => {
+            let ids = vec![id.clone()];
+            Some(history::record(&conn, bm_id, "delete_connection", Vec::new(), ids)?)
+        }
+        None => None,
+    };
+
+    conn.execute("DELETE FROM brain_map_connections WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if let Some(op) = op {
+        op.finish(&conn)?;
+    }
+
+    Ok(())
+}
+
+// ============ Brain Map History Commands ============
+
+// Reverts the latest node/connection edit on the map. None when there is
+// nothing left to undo.
+#[tauri::command]
+#[specta::specta]
+pub fn undo_brain_map_op(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    map_id: String,
+) -> Result<Option<BrainMapOpApplied>, String> {
+    let _span = profiling::span("undo_brain_map_op");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    // A drag still being held is the step to undo
+    write_behind.flush_positions(&conn)?;
+    history::undo(&mut conn, &map_id)
+}
+
+// Reapplies the most recently undone edit. None when there is nothing to redo.
+#[tauri::command]
+#[specta::specta]
+pub fn redo_brain_map_op(
+    db: State<Database>,
+    write_behind: State<WriteBehind>,
+    map_id: String,
+) -> Result<Option<BrainMapOpApplied>, String> {
+    let _span = profiling::span("redo_brain_map_op");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    write_behind.flush_positions(&conn)?;
+    history::redo(&mut conn, &map_id)
+}
+
+// ============ Style Preset Commands ============
+
+fn row_to_style_preset(row: &rusqlite::Row) -> rusqlite::Result<StylePreset> {
+    Ok(StylePreset {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        color: row.get("color")?,
+        shape: row.get("shape")?,
+        size: row.get("size")?,
+        icon: row.get("icon")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_style_presets(db: State<Database>) -> Result<Vec<StylePreset>, String> {
+    let _span = profiling::span("get_style_presets");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, color, shape, size, icon, created_at
+             FROM style_presets ORDER BY name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], row_to_style_preset)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_style_preset(db: State<Database>, data: StylePresetCreate) -> Result<StylePreset, AppError> {
+    let _span = profiling::span("create_style_preset");
+    validation::style_preset_create(&data)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let preset = StylePreset {
+        id: format!("style_{}", Uuid::new_v4()),
+        name: data.name.trim().to_string(),
+        color: data.color,
+        shape: data.shape,
+        size: data.size,
+        icon: data.icon,
+        created_at: timestamps::now(),
+    };
+
+    conn.execute(
+        "INSERT INTO style_presets (id, name, color, shape, size, icon, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            preset.id,
+            preset.name,
+            preset.color,
+            preset.shape,
+            preset.size,
+            preset.icon,
+            timestamps::format(&preset.created_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_style_preset(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_style_preset");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM style_presets WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// Restyles `node_id` and every node below it in one transaction. Returns the
+// updated nodes.
+#[tauri::command]
+#[specta::specta]
+pub fn apply_style_to_subtree(
+    db: State<Database>,
+    node_id: String,
+    style: NodeStyle,
+) -> Result<Vec<BrainMapNode>, AppError> {
+    let _span = profiling::span("apply_style_to_subtree");
+    validation::node_style(&style)?;
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+
+    // UNION (not UNION ALL) stops at nodes already visited, so a parent cycle
+    // can't recurse forever
+    const SUBTREE: &str = "WITH RECURSIVE subtree(id) AS (
+             SELECT ?1
+             UNION
+             SELECT n.id FROM brain_map_nodes n JOIN subtree s ON n.parent_node_id = s.id
+             WHERE n.deleted_at IS NULL
+         )";
+
+    let (brain_map_id, ids): (Option<String>, Vec<String>) = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "{} SELECT n.id, n.brain_map_id
+                    FROM brain_map_nodes n JOIN subtree s ON s.id = n.id",
+                SUBTREE
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![node_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let rows: Vec<(String, String)> = rows.filter_map(|r| r.ok()).collect();
+        (rows.first().map(|(_, map)| map.clone()), rows.into_iter().map(|(id, _)| id).collect())
+    };
+    let Some(brain_map_id) = brain_map_id else {
+        return Ok(Vec::new());
+    };
+    let op = history::record(&tx, &brain_map_id, "restyle_subtree", ids, Vec::new())?;
+
+    tx.execute(
+        &format!(
+            "{} UPDATE brain_map_nodes
+                SET color = COALESCE(?2, color), shape = COALESCE(?3, shape),
+                    size = COALESCE(?4, size), icon = COALESCE(?5, icon), updated_at = ?6
+              WHERE id IN (SELECT id FROM subtree)",
+            SUBTREE
+        ),
+        params![node_id, style.color, style.shape, style.size, style.icon, now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let nodes: Vec<BrainMapNode> = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "{} SELECT {}
+                    FROM brain_map_nodes WHERE id IN (SELECT id FROM subtree)",
+                SUBTREE,
+                queries::NODE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![node_id], row_to_brain_map_node)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    tx.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![now, brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+    op.finish(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(nodes)
+}
+
+// ============ Attachment Commands ============
+
+pub(crate) fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get("id")?,
+        note_id: row.get("note_id")?,
+        kind: row.get("kind")?,
+        file_name: row.get("file_name")?,
+        mime_type: row.get("mime_type")?,
+        size_bytes: row.get("size_bytes")?,
+        duration_ms: row.get("duration_ms")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+pub(crate) fn store_attachment(
+    conn: &rusqlite::Connection,
+    data_dir: &std::path::Path,
+    note_id: String,
+    kind: &str,
+    mime_type: String,
+    bytes: &[u8],
+    duration_ms: Option<i64>,
+) -> Result<Attachment, String> {
+    let now = timestamps::now();
+    let id = format!("att_{}", Uuid::new_v4());
+
+    let note_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM notes WHERE id = ?1)",
+            params![note_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !note_exists {
+        return Err(format!("Note {} not found", note_id));
+    }
+
+    let hash = attachments::content_hash(bytes);
+    let (file_name, written) = attachments::store_blob(
+        conn,
+        data_dir,
+        &hash,
+        attachments::extension_for_mime(&mime_type),
+        bytes,
+    )?;
+
+    let attachment = Attachment {
+        id,
+        note_id,
+        kind: kind.to_string(),
+        file_name,
+        mime_type,
+        size_bytes: bytes.len() as i64,
+        duration_ms,
+        created_at: now,
+    };
+
+    let inserted = conn.execute(
+        "INSERT INTO attachments (id, note_id, kind, file_name, mime_type, size_bytes, duration_ms,
+                                  created_at, content_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            attachment.id,
+            attachment.note_id,
+            attachment.kind,
+            attachment.file_name,
+            attachment.mime_type,
+            attachment.size_bytes,
+            attachment.duration_ms,
+            timestamps::format(&attachment.created_at),
+            hash,
+        ],
+    );
+
+    if let Err(e) = inserted {
+        // Don't leave an unreferenced blob behind
+        if written {
+            let _ = attachments::remove_blob(data_dir, &attachment.file_name);
+        }
+        return Err(e.to_string());
+    }
+
+    Ok(attachment)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn save_audio_memo(
+    db: State<Database>,
+    note_id: String,
+    bytes: Vec<u8>,
+    mime_type: Option<String>,
+    duration_ms: Option<i64>,
+) -> Result<Attachment, String> {
+    let _span = profiling::span("save_audio_memo");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    store_attachment(
+        &conn,
+        &db.data_dir,
+        note_id,
+        "audio",
+        mime_type.unwrap_or_else(|| "audio/webm".to_string()),
+        &bytes,
+        duration_ms.or_else(|| attachments::wav_duration_ms(&bytes)),
+    )
+}
+
+// Attaches a file the user picks in a native dialog; the webview never names
+// the path. Returns None if the dialog, or the size warning, was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_attachment(
+    app: AppHandle,
+    db: State<'_, Database>,
+    note_id: String,
+) -> Result<Option<Attachment>, AppError> {
+    let _span = profiling::span("add_attachment");
+    let Some(file) = app
+        .dialog()
+        .file()
+        .set_title("Attach a file")
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+    // Read the file on the Rust side so large images/audio never cross the IPC bridge
+    let path = file.into_path().map_err(|e| e.to_string())?;
+
+    // Files over the warning threshold need the user to confirm
+    let size = std::fs::metadata(&path).map_err(|e| e.to_string())?.len() as i64;
+    let limit = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        storage::load_settings(&conn)?.attachment_warning_bytes
+    };
+    if let Some(limit) = limit.filter(|limit| size > *limit) {
+        let confirmed = app
+            .dialog()
+            .message(format!(
+                "This file is {}, over the {} attachment size warning. Attach it anyway?",
+                storage::format_megabytes(size),
+                storage::format_megabytes(limit),
+            ))
+            .title("Large attachment")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancel)
+            .blocking_show();
+        if !confirmed {
+            return Ok(None);
+        }
+    }
+
+    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let mime_type = attachments::mime_for_extension(
+        path.extension().and_then(|e| e.to_str()).unwrap_or_default(),
+    );
+    let kind = attachments::kind_for_mime(mime_type);
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let attachment =
+        store_attachment(&conn, &db.data_dir, note_id, kind, mime_type.to_string(), &bytes, None)?;
+    Ok(Some(attachment))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_attachments(db: State<Database>, note_id: String) -> Result<Vec<Attachment>, String> {
+    let _span = profiling::span("get_attachments");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, note_id, kind, file_name, mime_type, size_bytes, duration_ms, created_at
+             FROM attachments
+             WHERE note_id = ?1
+             ORDER BY created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![note_id], row_to_attachment)
+        .map_err(|e| e.to_string())?;
+    let attachments: Vec<Attachment> = rows.filter_map(|r| r.ok()).collect();
+    Ok(attachments)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_audio_memos(db: State<Database>, note_id: String) -> Result<Vec<Attachment>, String> {
+    let _span = profiling::span("get_audio_memos");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, note_id, kind, file_name, mime_type, size_bytes, duration_ms, created_at
+             FROM attachments
+             WHERE note_id = ?1 AND kind = 'audio'
+             ORDER BY created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![note_id], row_to_attachment)
+        .map_err(|e| e.to_string())?;
+    let memos: Vec<Attachment> = rows.filter_map(|r| r.ok()).collect();
+    Ok(memos)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_attachment(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_attachment");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let file_name: Option<String> = conn
+        .query_row(
+            "SELECT file_name FROM attachments WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .ok();
+
+    conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    // The blob may still back other attachments with the same content
+    if let Some(file_name) = file_name {
+        attachments::release_blobs(&conn, &db.data_dir, &[file_name])?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_attachment_dedup_stats(db: State<Database>) -> Result<AttachmentDedupStats, String> {
+    let _span = profiling::span("get_attachment_dedup_stats");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Attachments sharing a blob share its file name
+    let (attachments, blobs, logical_bytes, stored_bytes): (i64, i64, i64, i64) = conn
+        .query_row(
+            "SELECT COALESCE(SUM(refs), 0), COUNT(*), COALESCE(SUM(size_bytes * refs), 0),
+                    COALESCE(SUM(size_bytes), 0)
+             FROM (SELECT COUNT(*) AS refs, MAX(size_bytes) AS size_bytes
+                   FROM attachments GROUP BY file_name)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(AttachmentDedupStats {
+        attachments,
+        blobs,
+        logical_bytes,
+        stored_bytes,
+        saved_bytes: logical_bytes - stored_bytes,
+    })
+}
+
+// What the database, attachments and backups take up on disk, by note and folder
+#[tauri::command]
+#[specta::specta]
+pub fn get_storage_usage(db: State<Database>) -> Result<StorageUsage, String> {
+    let _span = profiling::span("get_storage_usage");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    storage::usage(&conn, &db.data_dir)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_storage_settings(
+    db: State<Database>,
+    settings: StorageSettings,
+) -> Result<(), AppError> {
+    let _span = profiling::span("set_storage_settings");
+    if settings.attachment_warning_bytes.is_some_and(|bytes| bytes <= 0) {
+        return Err(AppError::validation(
+            "attachment_warning_bytes",
+            "Must be greater than zero",
+        ));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    storage::save_settings(&conn, &settings)?;
+    Ok(())
+}
+
+// ============ Sharing Commands ============
+
+// Renders a note to a standalone page with attachments inlined as data URIs.
+fn render_note_page(conn: &rusqlite::Connection, data_dir: &std::path::Path, note: &Note) -> String {
+    let body = share::inline_attachments(&render::content_to_html(&note.content), |attachment_id| {
+        let (file_name, mime_type): (String, String) = conn
+            .query_row(
+                "SELECT file_name, mime_type FROM attachments WHERE id = ?1",
+                params![attachment_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok()?;
+        let bytes = std::fs::read(attachments::attachment_path(data_dir, &file_name)).ok()?;
+        Some((mime_type, bytes))
+    });
+
+    render::html_document(&note.title, &body)
+}
+
+fn load_note(conn: &rusqlite::Connection, id: &str) -> Result<Note, String> {
+    conn.prepare_cached(&format!("SELECT {} FROM notes WHERE id = ?1", queries::NOTE_COLUMNS))
+        .and_then(|mut stmt| stmt.query_row(params![id], row_to_note))
+        .map_err(|e| e.to_string())
+}
+
+// Returns the saved path, or None if the user cancelled the dialog.
+#[tauri::command]
+#[specta::specta]
+pub async fn share_note_bundle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+    password: Option<String>,
+) -> Result<Option<String>, String> {
+    let _span = profiling::span("share_note_bundle");
+    let (note, page) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let note = load_note(&conn, &id)?;
+        let page = render_note_page(&conn, &db.data_dir, &note);
+        (note, page)
+    };
+
+    let page = match password.filter(|p| !p.is_empty()) {
+        Some(password) => share::password_protect(&note.title, &page, &password)?,
+        None => page,
+    };
+
+    let Some(path) = pick_save_path(&app, &export::file_name(&note.title, "html"), "HTML", &["html"])?
+    else {
+        return Ok(None);
+    };
+    std::fs::write(&path, page).map_err(|e| e.to_string())?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+fn load_publish_target(
+    conn: &rusqlite::Connection,
+) -> Result<(PublishTarget, Option<String>), String> {
+    let target = publish::load_target(conn)?.ok_or("Publishing is not configured")?;
+    Ok((target, publish::load_secret()?))
+}
+
+// The configured target, without its secret
+#[tauri::command]
+#[specta::specta]
+pub fn get_publish_target(db: State<Database>) -> Result<Option<PublishTarget>, String> {
+    let _span = profiling::span("get_publish_target");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    publish::load_target(&conn)
+}
+
+// `secret` is the POST bearer token or the S3 secret access key, stored in the
+// OS keychain; None keeps the stored one and "" removes it
+#[tauri::command]
+#[specta::specta]
+pub fn set_publish_target(
+    db: State<Database>,
+    target: PublishTarget,
+    secret: Option<String>,
+) -> Result<(), String> {
+    let _span = profiling::span("set_publish_target");
+    if let Some(secret) = secret {
+        publish::store_secret(&secret)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    publish::save_target(&conn, &target)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn publish_note(db: State<'_, Database>, id: String) -> Result<Note, String> {
+    let _span = profiling::span("publish_note");
+    // Never hold the connection across the upload
+    let (note, target, page) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let target = load_publish_target(&conn)?;
+        let note = load_note(&conn, &id)?;
+        let page = render_note_page(&conn, &db.data_dir, &note);
+        (note, target, page)
+    };
+    let (target, secret) = target;
+
+    let key = publish::object_key(&note.id);
+    let url = publish::upload(&target, secret.as_deref(), &key, page).await?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE notes SET published_url = ?1 WHERE id = ?2",
+        params![url, note.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(Note {
+        published_url: Some(url),
+        ..note
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn unpublish_note(db: State<'_, Database>, id: String) -> Result<Note, String> {
+    let _span = profiling::span("unpublish_note");
+    let (note, (target, secret)) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (load_note(&conn, &id)?, load_publish_target(&conn)?)
+    };
+
+    if note.published_url.is_none() {
+        return Ok(note);
+    }
+
+    publish::remove(&target, secret.as_deref(), &publish::object_key(&note.id)).await?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE notes SET published_url = NULL WHERE id = ?1",
+        params![note.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(Note {
+        published_url: None,
+        ..note
+    })
+}
+
+// ============ Email Capture Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_email_capture_config(db: State<Database>) -> Result<Option<EmailCaptureConfig>, String> {
+    let _span = profiling::span("get_email_capture_config");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    email::load_config(&conn)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_email_capture_config(
+    db: State<Database>,
+    config: EmailCaptureConfig,
+    password: Option<String>,
+) -> Result<(), String> {
+    let _span = profiling::span("set_email_capture_config");
+    if let Some(password) = password {
+        email::store_password(&config, &password)?;
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            email::EMAIL_CAPTURE_SETTING,
+            serde_json::to_string(&config).map_err(|e| e.to_string())?,
         ],
     )
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn poll_email_inbox(db: State<'_, Database>) -> Result<EmailPollResult, String> {
+    let _span = profiling::span("poll_email_inbox");
+    email::poll(&db).await
+}
+
+// ============ Feed Commands ============
+
+fn row_to_feed(row: &rusqlite::Row) -> rusqlite::Result<Feed> {
+    Ok(Feed {
+        id: row.get("id")?,
+        url: row.get("url")?,
+        title: row.get("title")?,
+        site_url: row.get("site_url")?,
+        last_fetched_at: timestamps::get_opt(row, "last_fetched_at")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+fn row_to_feed_item(row: &rusqlite::Row) -> rusqlite::Result<FeedItem> {
+    let is_read: i32 = row.get("is_read")?;
+    Ok(FeedItem {
+        id: row.get("id")?,
+        feed_id: row.get("feed_id")?,
+        guid: row.get("guid")?,
+        title: row.get("title")?,
+        url: row.get("url")?,
+        summary: row.get("summary")?,
+        content: row.get("content")?,
+        published_at: timestamps::get_opt(row, "published_at")?,
+        is_read: is_read != 0,
+        note_id: row.get("note_id")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_feeds(db: State<Database>) -> Result<Vec<Feed>, String> {
+    let _span = profiling::span("get_feeds");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, url, title, site_url, last_fetched_at, created_at
+             FROM feeds
+             ORDER BY title COLLATE NOCASE ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([], row_to_feed).map_err(|e| e.to_string())?;
+    let feeds: Vec<Feed> = rows.filter_map(|r| r.ok()).collect();
+    Ok(feeds)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_feed(db: State<'_, Database>, url: String) -> Result<Feed, String> {
+    let _span = profiling::span("add_feed");
+    let id = format!("feed_{}", Uuid::new_v4());
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO feeds (id, url, created_at) VALUES (?1, ?2, ?3)",
+            params![id, url, timestamps::format(&Utc::now())],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // Validate the subscription right away; a URL that isn't a feed is rejected
+    if let Err(e) = feeds::refresh_feed(&db, &id).await {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM feeds WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        return Err(e);
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT id, url, title, site_url, last_fetched_at, created_at FROM feeds WHERE id = ?1",
+        params![id],
+        row_to_feed,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_feed(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_feed");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM feed_items WHERE feed_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM feeds WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_feed_items(
+    db: State<Database>,
+    feed_id: Option<String>,
+    unread_only: Option<bool>,
+) -> Result<Vec<FeedItem>, String> {
+    let _span = profiling::span("get_feed_items");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, feed_id, guid, title, url, summary, content, published_at, is_read, note_id, created_at
+             FROM feed_items
+             WHERE (?1 IS NULL OR feed_id = ?1) AND (?2 = 0 OR is_read = 0)
+             ORDER BY COALESCE(published_at, created_at) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(
+            params![feed_id, unread_only.unwrap_or(false) as i32],
+            row_to_feed_item,
+        )
+        .map_err(|e| e.to_string())?;
+    let items: Vec<FeedItem> = rows.filter_map(|r| r.ok()).collect();
+    Ok(items)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn mark_feed_item_read(db: State<Database>, id: String, is_read: bool) -> Result<(), String> {
+    let _span = profiling::span("mark_feed_item_read");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE feed_items SET is_read = ?1 WHERE id = ?2",
+        params![is_read as i32, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_feeds(db: State<'_, Database>) -> Result<usize, String> {
+    let _span = profiling::span("refresh_feeds");
+    feeds::refresh_all(&db).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn save_feed_item_as_note(
+    db: State<Database>,
+    id: String,
+    folder_id: Option<String>,
+) -> Result<Note, String> {
+    let _span = profiling::span("save_feed_item_as_note");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let item: FeedItem = conn
+        .query_row(
+            "SELECT id, feed_id, guid, title, url, summary, content, published_at, is_read, note_id, created_at
+             FROM feed_items WHERE id = ?1",
+            params![id],
+            row_to_feed_item,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut content = item.content.or(item.summary).unwrap_or_default();
+    if let Some(url) = &item.url {
+        content.push_str(&format!(
+            "<p>Source: <a href=\"{url}\">{url}</a></p>",
+            url = render::escape_html(url)
+        ));
+    }
+
+    let note = insert_note(
+        &conn,
+        NoteCreate {
+            title: Some(item.title),
+            content: Some(content),
+            folder_id,
+            tags: Some(vec!["feed".to_string()]),
+            aliases: None,
+        },
+    )?;
+
+    conn.execute(
+        "UPDATE feed_items SET note_id = ?1, is_read = 1 WHERE id = ?2",
+        params![note.id, item.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(note)
+}
+
+// ============ Web Capture Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub async fn capture_url(
+    db: State<'_, Database>,
+    url: String,
+    tags: Option<Vec<String>>,
+    folder_id: Option<String>,
+) -> Result<Note, String> {
+    let _span = profiling::span("capture_url");
+    let article = capture::fetch_article(&url).await?;
+
+    let mut tags = tags.unwrap_or_default();
+    if !tags.iter().any(|t| t == "read-later") {
+        tags.push("read-later".to_string());
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    insert_note(
+        &conn,
+        NoteCreate {
+            title: Some(article.title),
+            content: Some(format!("> Source: <{}>\n\n{}\n", url, article.markdown)),
+            folder_id,
+            tags: Some(tags),
+            aliases: None,
+        },
+    )
+}
+
+// Pasted rich text as Markdown, the same on every platform whatever the
+// webview's clipboard hands over
+#[tauri::command]
+#[specta::specta]
+pub fn convert_html_to_markdown(html: String) -> String {
+    let _span = profiling::span("convert_html_to_markdown");
+    render::html_to_markdown(&html)
+}
+
+// ============ Recovery Code Commands ============
+
+// Replaces any existing codes. The plaintext codes are returned exactly once
+// for the user to print; only salted hashes are stored.
+#[tauri::command]
+#[specta::specta]
+pub fn rotate_recovery_codes(db: State<Database>) -> Result<Vec<String>, String> {
+    let _span = profiling::span("rotate_recovery_codes");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute("DELETE FROM recovery_codes", [])
+        .map_err(|e| e.to_string())?;
+
+    let mut codes = Vec::with_capacity(recovery::CODE_COUNT);
+    for _ in 0..recovery::CODE_COUNT {
+        let code = recovery::generate_code();
+        let salt = recovery::generate_salt();
+        tx.execute(
+            "INSERT INTO recovery_codes (id, code_hash, salt, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                format!("recovery_{}", Uuid::new_v4()),
+                recovery::hash_code(&salt, &code),
+                salt,
+                now,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        codes.push(code);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(codes)
+}
+
+// Checks a code against the unused set and burns it on success.
+#[tauri::command]
+#[specta::specta]
+pub fn verify_recovery_code(db: State<Database>, code: String) -> Result<bool, String> {
+    let _span = profiling::span("verify_recovery_code");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, code_hash, salt FROM recovery_codes WHERE used_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let candidates: Vec<(String, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let matched = candidates
+        .into_iter()
+        .find(|(_, hash, salt)| recovery::hash_code(salt, &code) == *hash);
+
+    match matched {
+        Some((id, _, _)) => {
+            conn.execute(
+                "UPDATE recovery_codes SET used_at = ?1 WHERE id = ?2",
+                params![timestamps::format(&Utc::now()), id],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_recovery_code_status(db: State<Database>) -> Result<RecoveryCodeStatus, String> {
+    let _span = profiling::span("get_recovery_code_status");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE used_at IS NULL), MIN(created_at) FROM recovery_codes",
+        [],
+        |row| {
+            Ok(RecoveryCodeStatus {
+                total: row.get(0)?,
+                remaining: row.get(1)?,
+                created_at: timestamps::get_opt(row, 2)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
 
-    // Create center node
-    let center_node = BrainMapNode {
-        id: center_node_id.clone(),
-        brain_map_id: map_id.clone(),
-        parent_node_id: None,
-        label: data.center_node_text.unwrap_or_else(|| "Central Idea".to_string()),
-        description: None,
-        x: 0.0,
-        y: 0.0,
-        color: Some("#6366f1".to_string()),
-        shape: Some("circle".to_string()),
-        size: Some("large".to_string()),
-        icon: None,
-        linked_note_id: None,
-        linked_folder_id: None,
-        linked_event_id: None,
-        is_collapsed: false,
-        layer: 0,
-        created_at: now.clone(),
-        updated_at: now.clone(),
+// ============ File Commands ============
+//
+// The webview has no filesystem access of its own. Every read or write of a
+// user-chosen path goes through a native dialog opened here, so a compromised
+// frontend can't name arbitrary paths.
+
+fn pick_save_path(
+    app: &AppHandle,
+    file_name: &str,
+    filter_name: &str,
+    extensions: &[&str],
+) -> Result<Option<PathBuf>, String> {
+    app.dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(filter_name, extensions)
+        .blocking_save_file()
+        .map(|path| path.into_path().map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// `format` is one of txt, md or html. Returns the saved path, or None if cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_note_to_file(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+    format: String,
+) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_note_to_file");
+    let (note, contents) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let note = load_note(&conn, &id)?;
+        let contents = match format.as_str() {
+            "txt" => format!("{}\n\n{}\n", note.title, render::content_to_plain_text(&note.content)),
+            "md" => {
+                render::markdown_document(&note.title, &render::content_to_markdown(&note.content))
+            }
+            "html" => render_note_page(&conn, &db.data_dir, &note),
+            other => return Err(format!("Unsupported export format: {}", other)),
+        };
+        (note, contents)
+    };
+
+    let Some(path) = pick_save_path(
+        &app,
+        &export::file_name(&note.title, &format),
+        &format.to_uppercase(),
+        &[format.as_str()],
+    )?
+    else {
+        return Ok(None);
+    };
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+// Queues importing .md, .txt and .html files picked by the user, one note
+// per file. Returns the job id, or None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_notes_from_files(
+    app: AppHandle,
+    folder_id: Option<String>,
+) -> Result<Option<String>, String> {
+    let _span = profiling::span("import_notes_from_files");
+    let Some(files) = app
+        .dialog()
+        .file()
+        .add_filter("Notes", &["md", "markdown", "txt", "html", "htm"])
+        .blocking_pick_files()
+    else {
+        return Ok(None);
+    };
+    let paths = files
+        .into_iter()
+        .map(|file| file.into_path().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    jobs::enqueue(&app, JobSpec::NoteFiles { paths, folder_id }).map(Some)
+}
+
+// Writes a consistent copy of the live database to a user-chosen file.
+#[tauri::command]
+#[specta::specta]
+pub async fn backup_database(app: AppHandle, db: State<'_, Database>) -> Result<Option<String>, String> {
+    let _span = profiling::span("backup_database");
+    let file_name = format!("voyena-backup-{}.db", Utc::now().format("%Y-%m-%d"));
+    let Some(path) = pick_save_path(&app, &file_name, "SQLite database", &["db"])? else {
+        return Ok(None);
+    };
+
+    // VACUUM INTO refuses to overwrite, and the dialog already confirmed replacing
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+        .map_err(|e| e.to_string())?;
+    storage::record_backup(&conn, &path, &timestamps::format(&Utc::now()))?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+// ============ Job Commands ============
+//
+// Whole-vault exports run as queued background jobs: each command returns the
+// job id (or None if the dialog was cancelled) and the work reports through
+// `job:progress` events until it completes, fails or is cancelled, ending
+// with a report of what was created, updated, skipped or failed.
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_vault_archive(app: AppHandle) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_vault_archive");
+    let file_name = format!("voyena-vault-{}.zip", Utc::now().format("%Y-%m-%d"));
+    let Some(path) = pick_save_path(&app, &file_name, "Zip archive", &["zip"])? else {
+        return Ok(None);
+    };
+
+    jobs::enqueue(&app, JobSpec::VaultArchive { path }).map(Some)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_markdown_folder(app: AppHandle) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_markdown_folder");
+    let Some(folder) = app
+        .dialog()
+        .file()
+        .set_title("Export notes as Markdown")
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let root = folder.into_path().map_err(|e| e.to_string())?;
+
+    jobs::enqueue(&app, JobSpec::MarkdownFolder { root }).map(Some)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_enex(app: AppHandle) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_enex");
+    let file_name = format!("voyena-{}.enex", Utc::now().format("%Y-%m-%d"));
+    let Some(path) = pick_save_path(&app, &file_name, "Evernote export", &["enex"])? else {
+        return Ok(None);
+    };
+
+    jobs::enqueue(&app, JobSpec::Enex { path }).map(Some)
+}
+
+fn pick_import_paths(
+    app: &AppHandle,
+    info: &formats::Format,
+) -> Result<Option<Vec<PathBuf>>, String> {
+    let dialog = app.dialog().file().set_title(format!("Import {}", info.name));
+    let picked = match info.target {
+        FormatTarget::File => dialog
+            .add_filter(info.name, info.extensions)
+            .blocking_pick_files(),
+        FormatTarget::Folder => dialog.blocking_pick_folder().map(|folder| vec![folder]),
+    };
+    picked
+        .map(|picked| {
+            picked
+                .into_iter()
+                .map(|file| file.into_path().map_err(|e| e.to_string()))
+                .collect()
+        })
+        .transpose()
+}
+
+// Queues importing with a registered format (see list_supported_formats),
+// after the user picks the files or folder to import. Returns the job id, or
+// None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_format(
+    app: AppHandle,
+    format: String,
+    folder_id: Option<String>,
+) -> Result<Option<String>, String> {
+    let _span = profiling::span("import_format");
+    let info = formats::importer(&format)?.format();
+    let Some(paths) = pick_import_paths(&app, &info)? else {
+        return Ok(None);
+    };
+
+    let spec = JobSpec::Import {
+        format,
+        paths,
+        folder_id,
+    };
+    jobs::enqueue(&app, spec).map(Some)
+}
+
+// Like import_format, but only works out what the import would create, for
+// the user to check before confirm_import queues it. For formats listed with
+// `preview`; None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn preview_import(
+    app: AppHandle,
+    db: State<'_, Database>,
+    previews: State<'_, formats::Previews>,
+    format: String,
+    folder_id: Option<String>,
+) -> Result<Option<ImportPreview>, String> {
+    let _span = profiling::span("preview_import");
+    let importer = formats::importer(&format)?;
+    let Some(paths) = pick_import_paths(&app, &importer.format())? else {
+        return Ok(None);
+    };
+
+    let summary = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        importer.preview(&conn, &paths, folder_id.as_deref())?
+    };
+    let id = previews.add(formats::PendingImport {
+        format: format.clone(),
+        paths,
+        folder_id,
+    })?;
+    Ok(Some(ImportPreview {
+        id,
+        format,
+        summary,
+    }))
+}
+
+// Queues a previewed import. Returns the job id.
+#[tauri::command]
+#[specta::specta]
+pub fn confirm_import(
+    app: AppHandle,
+    previews: State<formats::Previews>,
+    id: String,
+) -> Result<String, String> {
+    let _span = profiling::span("confirm_import");
+    let pending = previews.take(&id)?;
+    let spec = JobSpec::Import {
+        format: pending.format,
+        paths: pending.paths,
+        folder_id: pending.folder_id,
     };
+    jobs::enqueue(&app, spec)
+}
+
+// Queues exporting the vault with a registered format, after the user picks
+// where to write it
+#[tauri::command]
+#[specta::specta]
+pub async fn export_format(app: AppHandle, format: String) -> Result<Option<String>, String> {
+    let _span = profiling::span("export_format");
+    let info = formats::exporter(&format)?.format();
+    let path = match info.target {
+        FormatTarget::File => {
+            let extension = info.extensions.first().copied().unwrap_or_default();
+            let file_name = format!("voyena-{}.{}", Utc::now().format("%Y-%m-%d"), extension);
+            pick_save_path(&app, &file_name, info.name, info.extensions)?
+        }
+        FormatTarget::Folder => app
+            .dialog()
+            .file()
+            .set_title(format!("Export as {}", info.name))
+            .blocking_pick_folder()
+            .map(|folder| folder.into_path().map_err(|e| e.to_string()))
+            .transpose()?,
+    };
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    jobs::enqueue(&app, JobSpec::Export { format, path }).map(Some)
+}
+
+// Every registered import and export format, for the import and export dialogs
+#[tauri::command]
+#[specta::specta]
+pub fn list_supported_formats() -> Vec<SupportedFormat> {
+    let _span = profiling::span("list_supported_formats");
+    formats::supported()
+}
+
+// Most recent first, including finished jobs from the last two weeks
+#[tauri::command]
+#[specta::specta]
+pub fn list_jobs(db: State<Database>, limit: Option<u32>) -> Result<Vec<Job>, String> {
+    let _span = profiling::span("list_jobs");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    jobs::list(&conn, limit.unwrap_or(50))
+}
+
+// Returns false if the job had already finished.
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_job(app: AppHandle, id: String) -> Result<bool, String> {
+    let _span = profiling::span("cancel_job");
+    jobs::cancel(&app, &id)
+}
+
+// ============ Sync Commands ============
+
+// Merges another device's version of changed notes, events and snippets.
+// Notes and events both devices edited since they last synced get a conflict
+// copy instead of being overwritten, unless only the text clashed and the
+// note's document was sent.
+#[tauri::command]
+#[specta::specta]
+pub fn merge_remote_changes(
+    db: State<Database>,
+    changes: RemoteChanges,
+) -> Result<SyncReport, String> {
+    let _span = profiling::span("merge_remote_changes");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let report = sync::apply(&tx, changes, &timestamps::now())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+// The note's content document, base64 encoded, to send along with the note
+// in RemoteChanges.note_docs
+#[tauri::command]
+#[specta::specta]
+pub fn get_note_crdt(db: State<Database>, note_id: String) -> Result<NoteDoc, String> {
+    let _span = profiling::span("get_note_crdt");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let note = load_note(&conn, &note_id)?;
+    let doc = crdt::export(&conn, &note.id, &note.content, &timestamps::format(&Utc::now()))?;
+    Ok(NoteDoc { note_id, doc })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_conflict_copies(db: State<Database>) -> Result<Vec<ConflictCopy>, String> {
+    let _span = profiling::span("list_conflict_copies");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    sync::conflict_copies(&conn)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn resolve_conflict_copy(
+    db: State<Database>,
+    id: String,
+    resolution: ConflictResolution,
+) -> Result<(), String> {
+    let _span = profiling::span("resolve_conflict_copy");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    sync::resolve(&tx, &id, resolution, &timestamps::now())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// ============ LAN Sync Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_lan_sync_config(db: State<Database>) -> Result<Option<LanSyncConfig>, String> {
+    let _span = profiling::span("get_lan_sync_config");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    lan::load_config(&conn)
+}
 
+// The service picks up changes within half a minute
+#[tauri::command]
+#[specta::specta]
+pub fn set_lan_sync_config(
+    db: State<Database>,
+    config: LanSyncConfig,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let _span = profiling::span("set_lan_sync_config");
+    if config.enabled {
+        platform::require(platform::capabilities().lan_sync, "LAN sync")?;
+    }
+    if config.device_name.trim().is_empty() {
+        return Err("Device name is required".into());
+    }
+    if let Some(passphrase) = passphrase {
+        lan::store_passphrase(&passphrase)?;
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute(
-        "INSERT INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, description,
-                                      x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
-                                      linked_event_id, is_collapsed, layer, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
         params![
-            center_node.id,
-            center_node.brain_map_id,
-            center_node.parent_node_id,
-            center_node.label,
-            center_node.description,
-            center_node.x,
-            center_node.y,
-            center_node.color,
-            center_node.shape,
-            center_node.size,
-            center_node.icon,
-            center_node.linked_note_id,
-            center_node.linked_folder_id,
-            center_node.linked_event_id,
-            center_node.is_collapsed as i32,
-            center_node.layer,
-            center_node.created_at,
-            center_node.updated_at,
+            lan::LAN_SYNC_SETTING,
+            serde_json::to_string(&config).map_err(|e| e.to_string())?,
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(BrainMapWithData {
-        brain_map,
-        nodes: vec![center_node],
-        connections: vec![],
-    })
+    Ok(())
+}
+
+// Devices with LAN sync on that are reachable right now
+#[tauri::command]
+#[specta::specta]
+pub fn get_lan_peers(
+    db: State<Database>,
+    peers: State<LanPeers>,
+) -> Result<Vec<LanPeer>, String> {
+    let _span = profiling::span("get_lan_peers");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    peers.list(&conn)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_with_lan_peer(
+    db: State<'_, Database>,
+    peers: State<'_, LanPeers>,
+    device_id: String,
+) -> Result<LanSyncResult, String> {
+    let _span = profiling::span("sync_with_lan_peer");
+    lan::sync_with(&db, &peers, &device_id).await
+}
+
+// ============ Markdown Mirror Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_mirror_config(db: State<Database>) -> Result<Option<MirrorConfig>, String> {
+    let _span = profiling::span("get_mirror_config");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    mirror::load_config(&conn)
+}
+
+// Notes are written within a few seconds of their last edit. Turning the
+// mirror on needs a directory from pick_mirror_directory first.
+#[tauri::command]
+#[specta::specta]
+pub fn set_mirror_config(db: State<Database>, enabled: bool) -> Result<(), AppError> {
+    let _span = profiling::span("set_mirror_config");
+    if enabled {
+        platform::require(platform::capabilities().markdown_mirror, "The Markdown mirror")?;
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let directory = mirror::load_config(&conn)?
+        .map(|config| config.directory)
+        .unwrap_or_default();
+    if enabled && !std::path::Path::new(&directory).is_dir() {
+        return Err(AppError::validation("directory", "Choose an existing directory"));
+    }
+    mirror::save_config(&conn, &MirrorConfig { enabled, directory })?;
+    Ok(())
+}
+
+// Points the mirror at a directory the user picks, which then gets every note
+// written afresh. Returns the new config, or None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn pick_mirror_directory(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<Option<MirrorConfig>, String> {
+    let _span = profiling::span("pick_mirror_directory");
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .set_title("Mirror notes to a directory")
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let directory = picked.into_path().map_err(|e| e.to_string())?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = MirrorConfig {
+        enabled: mirror::load_config(&conn)?.is_some_and(|config| config.enabled),
+        directory: directory.to_string_lossy().into_owned(),
+    };
+    mirror::save_config(&conn, &config)?;
+    Ok(Some(config))
+}
+
+// ============ Watched Folder Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_watched_folders(db: State<Database>) -> Result<Vec<WatchedFolder>, String> {
+    let _span = profiling::span("get_watched_folders");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    folder_watch::list(&conn)
+}
+
+// Syncs the folder with a directory the user picks. Notes already in the
+// folder are written to the directory and files already in the directory
+// become notes. Returns None if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn watch_folder(
+    app: AppHandle,
+    db: State<'_, Database>,
+    folder_id: String,
+) -> Result<Option<WatchedFolder>, AppError> {
+    let _span = profiling::span("watch_folder");
+    platform::require(platform::capabilities().watched_folders, "Watched folders")?;
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .set_title("Sync folder with a directory")
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let directory = picked.into_path().map_err(|e| e.to_string())?;
+    let directory = directory.to_string_lossy().into_owned();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM folders WHERE id = ?1)",
+            params![folder_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(AppError::validation("folder_id", "Folder not found"));
+    }
+    let taken = folder_watch::list(&conn)?
+        .into_iter()
+        .any(|watched| watched.directory == directory && watched.folder_id != folder_id);
+    if taken {
+        return Err(AppError::validation(
+            "directory",
+            "Another folder is already synced with this directory",
+        ));
+    }
+
+    let watched = folder_watch::add(&conn, &folder_id, &directory, &timestamps::now())?;
+    Ok(Some(watched))
+}
+
+// Stops syncing; the notes and the files both stay
+#[tauri::command]
+#[specta::specta]
+pub fn unwatch_folder(db: State<Database>, folder_id: String) -> Result<(), String> {
+    let _span = profiling::span("unwatch_folder");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    folder_watch::remove(&conn, &folder_id)
+}
+
+// ============ System Search Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_system_search_settings(db: State<Database>) -> Result<SystemSearchSettings, String> {
+    let _span = profiling::span("get_system_search_settings");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    system_search::load_settings(&conn)
+}
+
+// Notes are indexed within half a minute; turning it off takes every note
+// back out of the index
+#[tauri::command]
+#[specta::specta]
+pub fn set_system_search_settings(
+    db: State<Database>,
+    settings: SystemSearchSettings,
+) -> Result<(), String> {
+    let _span = profiling::span("set_system_search_settings");
+    if settings.enabled {
+        platform::require(platform::capabilities().system_search, "System search")?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    system_search::save_settings(&conn, &settings)
+}
+
+// The note a voyena://note/{id} link launched the app with, once; links
+// opened later arrive as the note:open event
+#[tauri::command]
+#[specta::specta]
+pub fn take_launch_note_link(launch: State<LaunchLink>) -> Option<String> {
+    let _span = profiling::span("take_launch_note_link");
+    launch.take()
+}
+
+// ============ Share Target Commands ============
+
+// Content shared from another app. Files, named by the token from a
+// share:files event, always become a note with the files attached; text and
+// links go to the inbox unless a note is asked for.
+#[tauri::command]
+#[specta::specta]
+pub fn receive_shared_content(
+    db: State<Database>,
+    pending: State<share_target::PendingFiles>,
+    payload: SharedContent,
+) -> Result<ShareResult, AppError> {
+    let _span = profiling::span("receive_shared_content");
+    validation::shared_content(&payload)?;
+    let paths = match &payload.files {
+        Some(token) => pending.take(token)?,
+        None => Vec::new(),
+    };
+    let files = share_target::read_files(&paths)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(share_target::receive(&conn, &db.data_dir, &payload, files)?)
+}
+
+// Shared files not saved yet, e.g. ones that arrived at launch
+#[tauri::command]
+#[specta::specta]
+pub fn get_pending_shares(
+    pending: State<share_target::PendingFiles>,
+) -> Result<Vec<PendingShare>, String> {
+    let _span = profiling::span("get_pending_shares");
+    pending.list()
+}
+
+// Adds or removes "Send to Voyena" on the Windows Explorer file menu
+#[tauri::command]
+#[specta::specta]
+pub fn set_share_menu(enabled: bool) -> Result<(), String> {
+    let _span = profiling::span("set_share_menu");
+    share_target::set_menu(enabled)
+}
+
+// ============ Clipboard Capture Commands ============
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_clipboard_capture_settings(
+    db: State<Database>,
+) -> Result<ClipboardCaptureSettings, String> {
+    let _span = profiling::span("get_clipboard_capture_settings");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    clipboard_capture::load_settings(&conn)
+}
+
+// The shortcut is registered before the settings are saved, so one that
+// doesn't parse or is taken by another app is refused
+#[tauri::command]
+#[specta::specta]
+pub fn set_clipboard_capture_settings(
+    app: AppHandle,
+    db: State<Database>,
+    settings: ClipboardCaptureSettings,
+) -> Result<(), String> {
+    let _span = profiling::span("set_clipboard_capture_settings");
+    platform::require(platform::capabilities().clipboard_capture, "Clipboard capture")?;
+    clipboard_capture::register(&app, &settings)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    clipboard_capture::save_settings(&conn, &settings)
+}
+
+// The day's note, created if there isn't one yet; today when no date is given
+#[tauri::command]
+#[specta::specta]
+pub fn get_daily_note(db: State<Database>, date: Option<NaiveDate>) -> Result<Note, String> {
+    let _span = profiling::span("get_daily_note");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    daily_notes::for_date(&conn, date.unwrap_or_else(|| Local::now().date_naive()))
 }
 
+// ============ Snippet Commands ============
+
 #[tauri::command]
-pub fn update_brain_map(db: State<Database>, id: String, data: BrainMapUpdate) -> Result<BrainMap, String> {
+#[specta::specta]
+pub fn get_snippets(db: State<Database>) -> Result<Vec<Snippet>, String> {
+    let _span = profiling::span("get_snippets");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    snippets::list(&conn)
+}
 
-    // Get current
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, title, description, center_node_id, center_node_text,
-                    viewport_x, viewport_y, viewport_zoom, theme,
-                    created_at, updated_at, deleted_at
-             FROM brain_maps WHERE id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+fn ensure_trigger_free(
+    conn: &rusqlite::Connection,
+    trigger: &str,
+    id: Option<&str>,
+) -> Result<(), AppError> {
+    if let Some(existing_id) = snippets::trigger_owner(conn, trigger, id)? {
+        return Err(AppError::conflict(
+            format!("Another snippet already uses \"{}\"", trigger),
+            existing_id,
+        ));
+    }
+    Ok(())
+}
 
-    let current: BrainMap = stmt
-        .query_row(params![id], row_to_brain_map)
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+#[specta::specta]
+pub fn create_snippet(db: State<Database>, data: SnippetCreate) -> Result<Snippet, AppError> {
+    let _span = profiling::span("create_snippet");
+    validation::snippet_create(&data)?;
 
-    let updated = BrainMap {
-        id: current.id,
-        title: data.title.unwrap_or(current.title),
-        description: data.description.or(current.description),
-        center_node_id: data.center_node_id.or(current.center_node_id),
-        center_node_text: data.center_node_text.unwrap_or(current.center_node_text),
-        viewport_x: data.viewport_x.unwrap_or(current.viewport_x),
-        viewport_y: data.viewport_y.unwrap_or(current.viewport_y),
-        viewport_zoom: data.viewport_zoom.unwrap_or(current.viewport_zoom),
-        theme: data.theme.or(current.theme),
-        created_at: current.created_at,
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let trigger = data.trigger.trim().to_string();
+    ensure_trigger_free(&conn, &trigger, None)?;
+
+    let now = timestamps::now();
+    let snippet = Snippet {
+        id: format!("snippet_{}", Uuid::new_v4()),
+        trigger,
+        name: data.name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        content: data.content,
+        created_at: now,
         updated_at: now,
-        deleted_at: current.deleted_at,
+        deleted_at: None,
     };
+    snippets::write(&conn, &snippet)?;
 
-    conn.execute(
-        "UPDATE brain_maps SET title = ?1, description = ?2, center_node_id = ?3, center_node_text = ?4,
-                              viewport_x = ?5, viewport_y = ?6, viewport_zoom = ?7, theme = ?8, updated_at = ?9
-         WHERE id = ?10",
-        params![
-            updated.title,
-            updated.description,
-            updated.center_node_id,
-            updated.center_node_text,
-            updated.viewport_x,
-            updated.viewport_y,
-            updated.viewport_zoom,
-            updated.theme,
-            updated.updated_at,
-            updated.id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(updated)
+    Ok(snippet)
 }
 
 #[tauri::command]
-pub fn delete_brain_map(db: State<Database>, id: String, hard: Option<bool>) -> Result<(), String> {
+#[specta::specta]
+pub fn update_snippet(
+    db: State<Database>,
+    id: String,
+    data: SnippetUpdate,
+) -> Result<Snippet, AppError> {
+    let _span = profiling::span("update_snippet");
+    validation::snippet_update(&data)?;
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let trigger = data.trigger.map(|t| t.trim().to_string());
+    if let Some(trigger) = &trigger {
+        ensure_trigger_free(&conn, trigger, Some(&id))?;
+    }
 
-    if hard.unwrap_or(false) {
-        // Hard delete - cascade handled by foreign keys
-        conn.execute("DELETE FROM brain_maps WHERE id = ?1", params![id])
-    } else {
-        let now = Utc::now().to_rfc3339();
-        conn.execute(
-            "UPDATE brain_maps SET deleted_at = ?1 WHERE id = ?2",
-            params![now, id],
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("trigger", trigger)
+        .set(
+            "name",
+            data.name
+                .map(|n| n.trim().to_string())
+                .into_change()
+                .map(|n| n.filter(|n| !n.is_empty())),
         )
+        .set("content", data.content)
+        .set_value("updated_at", timestamps::format(&Utc::now()));
+    if !changes.execute(&conn, "snippets", &id)? {
+        return Err("Snippet not found".into());
     }
-    .map_err(|e| e.to_string())?;
 
+    Ok(snippets::load(&conn, &id)?.ok_or("Snippet not found")?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_snippet(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_snippet");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if !snippets::delete(&conn, &id, &timestamps::format(&Utc::now()))? {
+        return Err("Snippet not found".into());
+    }
     Ok(())
 }
 
-// ============ Brain Map Node Commands ============
+// The text to insert for a trigger the user typed; None when no snippet has it
+#[tauri::command]
+#[specta::specta]
+pub fn expand_snippet(db: State<Database>, trigger: String) -> Result<Option<String>, String> {
+    let _span = profiling::span("expand_snippet");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    snippets::expand(&conn, &trigger)
+}
+
+// ============ Script Commands ============
 
 #[tauri::command]
-pub fn create_brain_map_node(db: State<Database>, data: BrainMapNodeCreate) -> Result<BrainMapNode, String> {
+#[specta::specta]
+pub fn get_scripts(db: State<Database>) -> Result<Vec<Script>, String> {
+    let _span = profiling::span("get_scripts");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let id = format!("node_{}", Uuid::new_v4());
+    scripting::list(&conn)
+}
 
-    // Calculate layer based on parent
-    let layer = if let Some(ref parent_id) = data.parent_node_id {
-        let mut stmt = conn
-            .prepare("SELECT layer FROM brain_map_nodes WHERE id = ?1")
-            .map_err(|e| e.to_string())?;
-        let parent_layer: i32 = stmt.query_row(params![parent_id], |row| row.get(0)).unwrap_or(0);
-        parent_layer + 1
-    } else {
-        1
-    };
+#[tauri::command]
+#[specta::specta]
+pub fn create_script(db: State<Database>, data: ScriptCreate) -> Result<Script, AppError> {
+    let _span = profiling::span("create_script");
+    validation::script_create(&data)?;
+    scripting::check(&data.source).map_err(|e| AppError::validation("source", e))?;
 
-    let node = BrainMapNode {
-        id: id.clone(),
-        brain_map_id: data.brain_map_id,
-        parent_node_id: data.parent_node_id,
-        label: data.label,
-        description: data.description,
-        x: data.x.unwrap_or(0.0),
-        y: data.y.unwrap_or(0.0),
-        color: data.color,
-        shape: data.shape.or(Some("circle".to_string())),
-        size: data.size.or(Some("medium".to_string())),
-        icon: data.icon,
-        linked_note_id: data.linked_note_id,
-        linked_folder_id: data.linked_folder_id,
-        linked_event_id: data.linked_event_id,
-        is_collapsed: false,
-        layer,
-        created_at: now.clone(),
-        updated_at: now.clone(),
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::now();
+    let script = Script {
+        id: format!("script_{}", Uuid::new_v4()),
+        name: data.name.trim().to_string(),
+        source: data.source,
+        enabled: data.enabled.unwrap_or(true),
+        last_run_at: None,
+        last_error: None,
+        created_at: now,
+        updated_at: now,
     };
 
     conn.execute(
-        "INSERT INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, description,
-                                      x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
-                                      linked_event_id, is_collapsed, layer, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        "INSERT INTO scripts (id, name, source, enabled, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
-            node.id,
-            node.brain_map_id,
-            node.parent_node_id,
-            node.label,
-            node.description,
-            node.x,
-            node.y,
-            node.color,
-            node.shape,
-            node.size,
-            node.icon,
-            node.linked_note_id,
-            node.linked_folder_id,
-            node.linked_event_id,
-            node.is_collapsed as i32,
-            node.layer,
-            node.created_at,
-            node.updated_at,
+            script.id,
+            script.name,
+            script.source,
+            script.enabled as i32,
+            timestamps::format(&script.created_at),
+            timestamps::format(&script.updated_at),
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    // Update brain map's updated_at
-    conn.execute(
-        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
-        params![now, node.brain_map_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(node)
+    Ok(script)
 }
 
+// New source clears the last run's error, which was about the old source
 #[tauri::command]
-pub fn update_brain_map_node(db: State<Database>, id: String, data: BrainMapNodeUpdate) -> Result<BrainMapNode, String> {
+#[specta::specta]
+pub fn update_script(
+    db: State<Database>,
+    id: String,
+    data: ScriptUpdate,
+) -> Result<Script, AppError> {
+    let _span = profiling::span("update_script");
+    validation::script_update(&data)?;
+    if let Some(source) = &data.source {
+        scripting::check(source).map_err(|e| AppError::validation("source", e))?;
+    }
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    let mut changes = partial_update::Changes::default();
+    changes
+        .set("name", data.name.map(|n| n.trim().to_string()))
+        .set("last_error", data.source.as_ref().map(|_| None::<String>))
+        .set("source", data.source)
+        .set("enabled", data.enabled.map(|enabled| enabled as i32))
+        .set_value("updated_at", timestamps::format(&Utc::now()));
+    if !changes.execute(&conn, "scripts", &id)? {
+        return Err("Script not found".into());
+    }
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, brain_map_id, parent_node_id, label, description,
-                    x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
-                    linked_event_id, is_collapsed, layer, created_at, updated_at
-             FROM brain_map_nodes WHERE id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+    Ok(scripting::load(&conn, &id)?.ok_or("Script not found")?)
+}
 
-    let current: BrainMapNode = stmt
-        .query_row(params![id], row_to_brain_map_node)
+#[tauri::command]
+#[specta::specta]
+pub fn delete_script(db: State<Database>, id: String) -> Result<(), String> {
+    let _span = profiling::span("delete_script");
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM scripts WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let updated = BrainMapNode {
-        id: current.id,
-        brain_map_id: current.brain_map_id.clone(),
-        parent_node_id: data.parent_node_id.or(current.parent_node_id),
-        label: data.label.unwrap_or(current.label),
-        description: data.description.or(current.description),
-        x: data.x.unwrap_or(current.x),
-        y: data.y.unwrap_or(current.y),
-        color: data.color.or(current.color),
-        shape: data.shape.or(current.shape),
-        size: data.size.or(current.size),
-        icon: data.icon.or(current.icon),
-        linked_note_id: data.linked_note_id.or(current.linked_note_id),
-        linked_folder_id: data.linked_folder_id.or(current.linked_folder_id),
-        linked_event_id: data.linked_event_id.or(current.linked_event_id),
-        is_collapsed: data.is_collapsed.unwrap_or(current.is_collapsed),
-        layer: current.layer,
-        created_at: current.created_at,
-        updated_at: now.clone(),
-    };
+// ============ Action Registry Commands ============
 
-    conn.execute(
-        "UPDATE brain_map_nodes SET parent_node_id = ?1, label = ?2, description = ?3,
-                                   x = ?4, y = ?5, color = ?6, shape = ?7, size = ?8, icon = ?9,
-                                   linked_note_id = ?10, linked_folder_id = ?11, linked_event_id = ?12,
-                                   is_collapsed = ?13, updated_at = ?14
-         WHERE id = ?15",
-        params![
-            updated.parent_node_id,
-            updated.label,
-            updated.description,
-            updated.x,
-            updated.y,
-            updated.color,
-            updated.shape,
-            updated.size,
-            updated.icon,
-            updated.linked_note_id,
-            updated.linked_folder_id,
-            updated.linked_event_id,
-            updated.is_collapsed as i32,
-            updated.updated_at,
-            updated.id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+// Every command the frontend can invoke and the arguments it takes, for the
+// command palette and automation rules
+#[tauri::command]
+#[specta::specta]
+pub fn list_actions(actions: State<Actions>) -> Vec<Action> {
+    let _span = profiling::span("list_actions");
+    actions.list()
+}
 
-    // Update brain map's updated_at
-    conn.execute(
-        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
-        params![now, updated.brain_map_id],
-    )
-    .map_err(|e| e.to_string())?;
+// ============ Platform Commands ============
 
-    Ok(updated)
+#[tauri::command]
+#[specta::specta]
+pub fn get_platform_capabilities() -> PlatformCapabilities {
+    let _span = profiling::span("get_platform_capabilities");
+    platform::capabilities()
 }
 
+// ============ Background Activity Commands ============
+
 #[tauri::command]
-pub fn delete_brain_map_node(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+#[specta::specta]
+pub fn get_background_activity(activity: State<Activity>) -> BackgroundActivity {
+    let _span = profiling::span("get_background_activity");
+    activity.status()
+}
 
-    // Get brain_map_id before deleting
-    let brain_map_id: Option<String> = conn
-        .query_row(
-            "SELECT brain_map_id FROM brain_map_nodes WHERE id = ?1",
-            params![id],
-            |row| row.get(0),
-        )
-        .ok();
+// Turns background work down, e.g. on battery, or back up. Paused
+// subsystems carry on within a few seconds of being resumed; the level goes
+// back to full on the next launch.
+#[tauri::command]
+#[specta::specta]
+pub fn set_background_activity(
+    activity: State<Activity>,
+    level: ActivityLevel,
+) -> BackgroundActivity {
+    let _span = profiling::span("set_background_activity");
+    activity.set_level(level);
+    activity.status()
+}
 
-    // Delete node (cascades to connections due to FK)
-    conn.execute("DELETE FROM brain_map_nodes WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
+// ============ Startup Recovery Commands ============
+//
+// Used by the recovery window that opens instead of the app when the
+// database can't be opened at startup.
 
-    // Update brain map's updated_at
-    if let Some(bm_id) = brain_map_id {
-        conn.execute(
-            "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
-            params![now, bm_id],
-        )
-        .map_err(|e| e.to_string())?;
-    }
+#[tauri::command]
+#[specta::specta]
+pub fn get_startup_problem(recovery: State<Recovery>) -> Option<StartupProblem> {
+    let _span = profiling::span("get_startup_problem");
+    recovery.problem()
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn open_database_read_only(app: AppHandle) -> Result<(), String> {
+    let _span = profiling::span("open_database_read_only");
+    startup::open_read_only(&app)
 }
 
+// `path` is a backup from the problem's list, or None to pick a file. The app
+// restarts on the restored database; cancelling the dialog does nothing.
 #[tauri::command]
-pub fn update_node_positions(
+#[specta::specta]
+pub async fn restore_database_backup(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    let _span = profiling::span("restore_database_backup");
+    let path = match path {
+        Some(path) => startup::listed_backup(&app, &path)?,
+        None => {
+            let picked = app
+                .dialog()
+                .file()
+                .add_filter("SQLite database", &["db"])
+                .blocking_pick_file();
+            match picked {
+                Some(file) => file.into_path().map_err(|e| e.to_string())?,
+                None => return Ok(()),
+            }
+        }
+    };
+    startup::restore(&app, &path)?;
+    app.restart()
+}
+
+// Tries again, e.g. once whatever held the database lock has let go
+#[tauri::command]
+#[specta::specta]
+pub fn restart_app(app: AppHandle) {
+    let _span = profiling::span("restart_app");
+    app.restart()
+}
+
+// ============ Maintenance Commands ============
+
+// Finds nodes and connections left pointing at rows that no longer exist and,
+// unless `dry_run` is set, fixes them. The report counts either way.
+#[tauri::command]
+#[specta::specta]
+pub fn repair_brain_maps(db: State<Database>, dry_run: Option<bool>) -> Result<BrainMapRepairReport, String> {
+    let _span = profiling::span("repair_brain_maps");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    integrity::repair_brain_maps(&mut conn, dry_run.unwrap_or(false))
+}
+
+// Deletes for good the notes, events and maps trashed more than
+// `older_than_days` ago, with their revisions and attachments
+#[tauri::command]
+#[specta::specta]
+pub fn purge_deleted_items(
     db: State<Database>,
-    updates: Vec<(String, f64, f64)>,
-) -> Result<(), String> {
+    older_than_days: u32,
+) -> Result<PurgeReport, String> {
+    let _span = profiling::span("purge_deleted_items");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    trash::purge(&mut conn, &db.data_dir, older_than_days)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_trash_settings(db: State<Database>) -> Result<TrashSettings, String> {
+    let _span = profiling::span("get_trash_settings");
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
+    trash::load_settings(&conn)
+}
 
-    for (id, x, y) in updates {
-        conn.execute(
-            "UPDATE brain_map_nodes SET x = ?1, y = ?2, updated_at = ?3 WHERE id = ?4",
-            params![x, y, now, id],
-        )
-        .map_err(|e| e.to_string())?;
+// Once set, the maintenance scheduler purges anything trashed longer ago
+#[tauri::command]
+#[specta::specta]
+pub fn set_trash_settings(db: State<Database>, settings: TrashSettings) -> Result<(), AppError> {
+    let _span = profiling::span("set_trash_settings");
+    if settings.retention_days == Some(0) {
+        return Err(AppError::validation("retention_days", "Must be at least one day"));
     }
 
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    trash::save_settings(&conn, &settings)?;
     Ok(())
 }
 
-// ============ Brain Map Connection Commands ============
+// ============ Developer Commands ============
 
+// Fills the database with a reproducible demo workspace for UI and
+// performance work. Refused in release builds.
 #[tauri::command]
-pub fn create_brain_map_connection(
-    db: State<Database>,
-    data: BrainMapConnectionCreate,
-) -> Result<BrainMapConnection, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = Utc::now().to_rfc3339();
-    let id = format!("conn_{}", Uuid::new_v4());
+#[specta::specta]
+pub fn seed_demo_data(db: State<Database>, scale: Option<u32>) -> Result<SeedSummary, String> {
+    let _span = profiling::span("seed_demo_data");
+    if !cfg!(debug_assertions) {
+        return Err("Demo data can only be generated in debug builds".to_string());
+    }
 
-    let connection = BrainMapConnection {
-        id: id.clone(),
-        brain_map_id: data.brain_map_id.clone(),
-        source_node_id: data.source_node_id,
-        target_node_id: data.target_node_id,
-        label: data.label,
-        color: data.color,
-        style: data.style.or(Some("solid".to_string())),
-        animated: data.animated.unwrap_or(false),
-        created_at: now.clone(),
-    };
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    seed::seed(&mut conn, scale.unwrap_or(1))
+}
 
-    conn.execute(
-        "INSERT INTO brain_map_connections (id, brain_map_id, source_node_id, target_node_id, label, color, style, animated, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        params![
-            connection.id,
-            connection.brain_map_id,
-            connection.source_node_id,
-            connection.target_node_id,
-            connection.label,
-            connection.color,
-            connection.style,
-            connection.animated as i32,
-            connection.created_at,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+#[tauri::command]
+#[specta::specta]
+pub fn set_profiling_enabled(db: State<Database>, enabled: bool) -> Result<(), String> {
+    let _span = profiling::span("set_profiling_enabled");
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    // Update brain map's updated_at
     conn.execute(
-        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
-        params![now, data.brain_map_id],
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![profiling::PROFILING_SETTING, enabled.to_string()],
     )
     .map_err(|e| e.to_string())?;
+    profiling::set_enabled(&mut conn, enabled);
 
-    Ok(connection)
+    Ok(())
 }
 
+// Slowest commands and queries recorded since profiling was switched on
 #[tauri::command]
-pub fn delete_brain_map_connection(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    conn.execute("DELETE FROM brain_map_connections WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+#[specta::specta]
+pub fn get_performance_report(limit: Option<u32>) -> PerformanceReport {
+    let _span = profiling::span("get_performance_report");
+    profiling::report(limit.unwrap_or(20) as usize)
 }
@@ -0,0 +1,183 @@
+use crate::models::{NoteComment, NoteCommentCreate, NoteCommentUpdate};
+use crate::timestamps;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+const SELECT_COMMENT: &str =
+    "SELECT id, note_id, anchor_offset, anchor_length, quote, body, resolved, created_at, updated_at
+     FROM note_comments";
+
+fn row_to_comment(row: &rusqlite::Row) -> rusqlite::Result<NoteComment> {
+    Ok(NoteComment {
+        id: row.get("id")?,
+        note_id: row.get("note_id")?,
+        offset: row.get("anchor_offset")?,
+        length: row.get("anchor_length")?,
+        quote: row.get("quote")?,
+        body: row.get("body")?,
+        resolved: row.get("resolved")?,
+        detached: false,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+fn units(text: &str) -> Vec<u16> {
+    text.encode_utf16().collect()
+}
+
+// The text at `offset..offset + length`, or None past the end
+pub fn quote(content: &str, offset: u32, length: u32) -> Option<String> {
+    let units = units(content);
+    let (start, end) = (offset as usize, offset as usize + length as usize);
+    units.get(start..end).map(String::from_utf16_lossy)
+}
+
+// Where `quote` is now: still at `offset`, or the copy nearest to it
+fn locate(content: &[u16], quote: &[u16], offset: usize) -> Option<usize> {
+    if quote.is_empty() {
+        return Some(offset.min(content.len()));
+    }
+    if content.get(offset..offset + quote.len()) == Some(quote) {
+        return Some(offset);
+    }
+    content
+        .windows(quote.len())
+        .enumerate()
+        .filter(|(_, window)| *window == quote)
+        .map(|(start, _)| start)
+        .min_by_key(|start| start.abs_diff(offset))
+}
+
+// Moves the comment to where its quoted text is in `content` now and saves
+// the new position, so the next search starts from there
+fn follow(conn: &Connection, comment: &mut NoteComment, content: &[u16]) -> Result<(), String> {
+    let Some(offset) = locate(content, &units(&comment.quote), comment.offset as usize) else {
+        comment.detached = true;
+        return Ok(());
+    };
+    if offset as u32 != comment.offset {
+        comment.offset = offset as u32;
+        conn.execute(
+            "UPDATE note_comments SET anchor_offset = ?1 WHERE id = ?2",
+            params![comment.offset, comment.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Comments on the note in the order they appear in `content`
+pub fn list(conn: &Connection, note_id: &str, content: &str) -> Result<Vec<NoteComment>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE note_id = ?1 ORDER BY anchor_offset, created_at",
+            SELECT_COMMENT
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut comments = stmt
+        .query_map(params![note_id], row_to_comment)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let content = units(content);
+    for comment in &mut comments {
+        follow(conn, comment, &content)?;
+    }
+    comments.sort_by_key(|c| c.offset);
+    Ok(comments)
+}
+
+pub fn load(conn: &Connection, id: &str) -> Result<Option<NoteComment>, String> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_COMMENT),
+        params![id],
+        row_to_comment,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn insert(
+    conn: &Connection,
+    note_id: &str,
+    quote: String,
+    data: NoteCommentCreate,
+    now: &DateTime<Utc>,
+) -> Result<NoteComment, String> {
+    let comment = NoteComment {
+        id: format!("comment_{}", Uuid::new_v4()),
+        note_id: note_id.to_string(),
+        offset: data.offset,
+        length: data.length,
+        quote,
+        body: data.body,
+        resolved: false,
+        detached: false,
+        created_at: *now,
+        updated_at: *now,
+    };
+    conn.execute(
+        "INSERT INTO note_comments
+             (id, note_id, anchor_offset, anchor_length, quote, body, resolved, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            comment.id,
+            comment.note_id,
+            comment.offset,
+            comment.length,
+            comment.quote,
+            comment.body,
+            comment.resolved,
+            timestamps::format(&comment.created_at),
+            timestamps::format(&comment.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(comment)
+}
+
+pub fn update(
+    conn: &Connection,
+    current: NoteComment,
+    data: NoteCommentUpdate,
+    content: &str,
+    now: &DateTime<Utc>,
+) -> Result<NoteComment, String> {
+    let mut comment = NoteComment {
+        body: data.body.unwrap_or(current.body),
+        resolved: data.resolved.unwrap_or(current.resolved),
+        updated_at: *now,
+        ..current
+    };
+    conn.execute(
+        "UPDATE note_comments SET body = ?1, resolved = ?2, updated_at = ?3 WHERE id = ?4",
+        params![
+            comment.body,
+            comment.resolved,
+            timestamps::format(&comment.updated_at),
+            comment.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    follow(conn, &mut comment, &units(content))?;
+    Ok(comment)
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM note_comments WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Drops the comments of a note that's being deleted for good
+pub fn remove_all(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM note_comments WHERE note_id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
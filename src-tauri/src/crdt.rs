@@ -0,0 +1,154 @@
+use automerge::transaction::{CommitOptions, Transactable};
+use automerge::{ActorId, AutoCommit, ObjId, ObjType, ReadDoc, ROOT};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+const CONTENT: &str = "content";
+
+// Every device builds the same first change for a note, so their documents
+// share a root and merge instead of holding two copies of the text
+fn genesis(note_id: &str) -> Result<AutoCommit, String> {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(note_id.as_bytes()));
+    doc.put_object(ROOT, CONTENT, ObjType::Text)
+        .map_err(|e| e.to_string())?;
+    doc.commit_with(CommitOptions::default().with_time(0));
+    Ok(doc)
+}
+
+// The text a note had before it got a document. Devices seeding the same
+// text make the same change.
+fn seed(note_id: &str, content: &str) -> Result<AutoCommit, String> {
+    let mut doc = genesis(note_id)?;
+    if content.is_empty() {
+        return Ok(doc);
+    }
+    let hash = Sha256::digest(format!("{}\n{}", note_id, content).as_bytes());
+    doc.set_actor(ActorId::from(&hash[..16]));
+    let text = text_obj(&doc)?;
+    doc.splice_text(&text, 0, 0, content)
+        .map_err(|e| e.to_string())?;
+    doc.commit_with(CommitOptions::default().with_time(0));
+    Ok(doc)
+}
+
+fn text_obj(doc: &AutoCommit) -> Result<ObjId, String> {
+    match doc.get(ROOT, CONTENT).map_err(|e| e.to_string())? {
+        Some((_, id)) => Ok(id),
+        None => Err("Note document has no content".into()),
+    }
+}
+
+fn text(doc: &AutoCommit) -> Result<String, String> {
+    doc.text(&text_obj(doc)?).map_err(|e| e.to_string())
+}
+
+fn decode(doc: &str) -> Result<AutoCommit, String> {
+    let bytes = BASE64.decode(doc).map_err(|e| e.to_string())?;
+    AutoCommit::load(&bytes).map_err(|e| e.to_string())
+}
+
+fn load(conn: &Connection, note_id: &str) -> Result<Option<AutoCommit>, String> {
+    let bytes: Option<Vec<u8>> = conn
+        .query_row(
+            "SELECT doc FROM note_crdt WHERE note_id = ?1",
+            params![note_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    bytes
+        .map(|bytes| AutoCommit::load(&bytes).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+fn save(conn: &Connection, note_id: &str, doc: &mut AutoCommit, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO note_crdt (note_id, doc, updated_at) VALUES (?1, ?2, ?3)",
+        params![note_id, doc.save(), now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The note's document with `content` as its latest edit. Writes that didn't
+// go through `record_edit` are caught up here as one diff.
+fn current(conn: &Connection, note_id: &str, content: &str) -> Result<AutoCommit, String> {
+    let Some(mut doc) = load(conn, note_id)? else {
+        return seed(note_id, content);
+    };
+    if text(&doc)? != content {
+        doc.set_actor(ActorId::random());
+        let text = text_obj(&doc)?;
+        doc.update_text(&text, content).map_err(|e| e.to_string())?;
+        doc.commit();
+    }
+    Ok(doc)
+}
+
+pub fn record_edit(
+    conn: &Connection,
+    note_id: &str,
+    content: &str,
+    now: &str,
+) -> Result<(), String> {
+    let mut doc = current(conn, note_id, content)?;
+    save(conn, note_id, &mut doc, now)
+}
+
+// The note's document for sending to another device
+pub fn export(
+    conn: &Connection,
+    note_id: &str,
+    content: &str,
+    now: &str,
+) -> Result<String, String> {
+    let mut doc = current(conn, note_id, content)?;
+    save(conn, note_id, &mut doc, now)?;
+    Ok(BASE64.encode(doc.save()))
+}
+
+// True if the two only share the empty first change, i.e. each device
+// seeded different text and merging would put both copies side by side
+fn unrelated(local: &mut AutoCommit, remote: &mut AutoCommit) -> bool {
+    let hashes = |doc: &mut AutoCommit| -> HashSet<_> {
+        doc.get_changes(&[]).iter().map(|c| c.hash()).collect()
+    };
+    let (local, remote) = (hashes(local), hashes(remote));
+    local.len() > 1 && remote.len() > 1 && local.intersection(&remote).count() <= 1
+}
+
+// Merges another device's document into the note's and returns the merged
+// text. `local` is the note's content here, None if it's new. Returns None
+// when the histories are unrelated; the other document is adopted so the
+// next edit builds on it, and the caller decides the text.
+pub fn merge(
+    conn: &Connection,
+    note_id: &str,
+    local: Option<&str>,
+    remote: &str,
+    now: &str,
+) -> Result<Option<String>, String> {
+    let mut remote = decode(remote)?;
+    let Some(local) = local else {
+        save(conn, note_id, &mut remote, now)?;
+        return text(&remote).map(Some);
+    };
+    let mut doc = current(conn, note_id, local)?;
+    if unrelated(&mut doc, &mut remote) {
+        save(conn, note_id, &mut remote, now)?;
+        return Ok(None);
+    }
+    doc.merge(&mut remote).map_err(|e| e.to_string())?;
+    save(conn, note_id, &mut doc, now)?;
+    text(&doc).map(Some)
+}
+
+// Drops the document of a note that's being deleted for good
+pub fn remove(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM note_crdt WHERE note_id = ?1", params![note_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::commands::{insert_note, row_to_note};
+use crate::crdt;
+use crate::locale;
+use crate::models::{Note, NoteCreate};
+use crate::queries;
+use crate::render;
+use crate::sanitize;
+use crate::search_index;
+use crate::timestamps;
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+// The note for `date`, created the first time it's needed and titled with
+// the date as the locale writes it. A trashed one is replaced by a new note.
+pub fn for_date(conn: &Connection, date: NaiveDate) -> Result<Note, String> {
+    let key = date.format("%Y-%m-%d").to_string();
+    let existing = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM notes
+                 WHERE id = (SELECT note_id FROM daily_notes WHERE date = ?1)
+                   AND deleted_at IS NULL",
+                queries::NOTE_COLUMNS
+            ),
+            params![key],
+            row_to_note,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(note) = existing {
+        return Ok(note);
+    }
+
+    let title = locale::format_date(date, locale::load(conn).date_order);
+    let note = insert_note(
+        conn,
+        NoteCreate {
+            title: Some(title),
+            content: None,
+            folder_id: None,
+            tags: None,
+            aliases: None,
+        },
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO daily_notes (date, note_id) VALUES (?1, ?2)",
+        params![key, note.id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(note)
+}
+
+// Adds a Markdown block to the end of a note, rendered to HTML when the note
+// is editor HTML
+pub fn append(conn: &Connection, note: &Note, markdown: &str) -> Result<Note, String> {
+    let content = if render::looks_like_html(&note.content) {
+        let block = sanitize::sanitize_html(&render::markdown_to_html(markdown));
+        format!("{}{}", note.content, block)
+    } else if note.content.trim().is_empty() {
+        format!("{}\n", markdown)
+    } else {
+        format!("{}\n\n{}\n", note.content.trim_end(), markdown)
+    };
+
+    let now = timestamps::format(&Utc::now());
+    conn.execute(
+        "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+        params![content, now, note.id],
+    )
+    .map_err(|e| e.to_string())?;
+    crdt::record_edit(conn, &note.id, &content, &now)?;
+    search_index::note_changed(conn, &note.id);
+
+    conn.query_row(
+        &format!("SELECT {} FROM notes WHERE id = ?1", queries::NOTE_COLUMNS),
+        params![note.id],
+        row_to_note,
+    )
+    .map_err(|e| e.to_string())
+}
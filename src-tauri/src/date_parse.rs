@@ -0,0 +1,344 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+// What an expression resolved to; `time` is None for date-only expressions
+// like "next friday"
+pub struct Resolved {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month(word: &str) -> Option<u32> {
+    let month = match word {
+        "jan" | "january" => 1,
+        "feb" | "february" => 2,
+        "mar" | "march" => 3,
+        "apr" | "april" => 4,
+        "may" => 5,
+        "jun" | "june" => 6,
+        "jul" | "july" => 7,
+        "aug" | "august" => 8,
+        "sep" | "sept" | "september" => 9,
+        "oct" | "october" => 10,
+        "nov" | "november" => 11,
+        "dec" | "december" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn count(word: &str) -> Option<u32> {
+    let n = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        _ => return word.parse().ok(),
+    };
+    Some(n)
+}
+
+// "5", "5th", "22nd"
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn year(word: &str) -> Option<i32> {
+    match word.len() {
+        4 => word.parse().ok(),
+        2 => word.parse::<i32>().ok().map(|y| 2000 + y),
+        _ => None,
+    }
+}
+
+// "3pm", "3:30pm", "15:00", or a bare hour followed by "am"/"pm"
+fn clock(word: &str, next: Option<&str>) -> Option<(NaiveTime, usize)> {
+    let (body, meridiem, used) = if let Some(body) = word.strip_suffix("am") {
+        (body, Some(false), 1)
+    } else if let Some(body) = word.strip_suffix("pm") {
+        (body, Some(true), 1)
+    } else {
+        match next {
+            Some("am") => (word, Some(false), 2),
+            Some("pm") => (word, Some(true), 2),
+            _ => (word, None, 1),
+        }
+    };
+
+    let (hour, minute) = match body.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm after it
+        None if meridiem.is_some() => (body.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+    Some((NaiveTime::from_hms_opt(hour, minute, 0)?, used))
+}
+
+fn named_time(word: &str) -> Option<NaiveTime> {
+    let hour = match word {
+        "midnight" => 0,
+        "morning" => 9,
+        "noon" | "midday" => 12,
+        "afternoon" => 15,
+        "evening" => 19,
+        "tonight" | "night" => 20,
+        _ => return None,
+    };
+    NaiveTime::from_hms_opt(hour, 0, 0)
+}
+
+// Upcoming `day`, today included unless `strict`
+fn upcoming(from: NaiveDate, day: Weekday, strict: bool) -> NaiveDate {
+    let mut ahead = (day.num_days_from_monday() + 7 - from.weekday().num_days_from_monday()) % 7;
+    if strict && ahead == 0 {
+        ahead = 7;
+    }
+    from + Duration::days(ahead as i64)
+}
+
+fn previous(from: NaiveDate, day: Weekday) -> NaiveDate {
+    let back = (from.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+    from - Duration::days(if back == 0 { 7 } else { back } as i64)
+}
+
+fn offset(from: NaiveDateTime, n: i64, unit: &str) -> Option<NaiveDateTime> {
+    let months = |m: i64| -> Option<NaiveDateTime> {
+        if m >= 0 {
+            from.checked_add_months(Months::new(m as u32))
+        } else {
+            from.checked_sub_months(Months::new((-m) as u32))
+        }
+    };
+    match unit.trim_end_matches('s') {
+        "min" | "minute" => Some(from + Duration::minutes(n)),
+        "hr" | "hour" => Some(from + Duration::hours(n)),
+        "day" => Some(from + Duration::days(n)),
+        "week" | "wk" => Some(from + Duration::weeks(n)),
+        "month" => months(n),
+        "year" | "yr" => months(n * 12),
+        _ => None,
+    }
+}
+
+// Numeric dates: 5/6, 5/6/2025, 5.6.25. `day_first` decides between
+// day/month and month/day.
+fn numeric_date(word: &str, reference: NaiveDate, day_first: bool) -> Option<(NaiveDate, bool)> {
+    let parts: Vec<&str> = word.split(['/', '.']).collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let a: u32 = parts[0].parse().ok()?;
+    let b: u32 = parts[1].parse().ok()?;
+    let (day, month) = if day_first { (a, b) } else { (b, a) };
+    match parts.get(2) {
+        Some(y) => Some((NaiveDate::from_ymd_opt(year(y)?, month, day)?, true)),
+        None => Some((
+            NaiveDate::from_ymd_opt(reference.year(), month, day)?,
+            false,
+        )),
+    }
+}
+
+// Month-name dates: "may 5", "may 5th 2025", "5 may", "5th of may"
+fn month_date(words: &[&str], reference: NaiveDate) -> Option<(NaiveDate, bool, usize)> {
+    let (month, day, mut used) = if let Some(m) = month(words[0]) {
+        (m, day_of_month(words.get(1)?)?, 2)
+    } else {
+        let day = day_of_month(words[0])?;
+        match words.get(1).copied() {
+            Some("of") => (month(words.get(2)?)?, day, 3),
+            Some(word) => (month(word)?, day, 2),
+            None => return None,
+        }
+    };
+
+    let explicit_year = words
+        .get(used)
+        .filter(|w| w.len() == 4)
+        .and_then(|w| year(w));
+    if explicit_year.is_some() {
+        used += 1;
+    }
+    let year = explicit_year.unwrap_or(reference.year());
+    Some((
+        NaiveDate::from_ymd_opt(year, month, day)?,
+        explicit_year.is_some(),
+        used,
+    ))
+}
+
+// Regions that write numeric dates month first (5/6 is May 6th)
+const MONTH_FIRST_REGIONS: &[&str] = &["US", "PH", "FM", "MH", "PW", "BZ"];
+
+// Whether `locale` (a BCP 47 tag like "en-GB") writes the day before the
+// month. A bare language tag counts as day first except for English.
+pub fn day_first(locale: &str) -> bool {
+    let mut parts = locale.split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    match parts.find(|p| p.len() == 2) {
+        Some(region) => !MONTH_FIRST_REGIONS.contains(&region.to_ascii_uppercase().as_str()),
+        None => !language.eq_ignore_ascii_case("en"),
+    }
+}
+
+const FILLERS: &[&str] = &["at", "on", "the", "of", "by", "due", "for"];
+
+// Resolves expressions like "next friday 3pm", "tomorrow at noon", "in 2
+// weeks" or "may 5th" against `reference`. Every word has to be understood;
+// anything else gives None rather than a guess.
+pub fn resolve(text: &str, reference: NaiveDateTime, day_first: bool) -> Option<Resolved> {
+    let lowered = text.to_lowercase().replace(',', " ");
+    let words: Vec<&str> = lowered.split_whitespace().collect();
+    let today = reference.date();
+
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    // Year-less dates that already passed this year move to next year
+    let mut roll_year = false;
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let next = words.get(i + 1).copied();
+
+        if let Some(t) = named_time(word) {
+            if word == "tonight" {
+                date = Some(today);
+            }
+            time = Some(t);
+            i += 1;
+        } else if let Some((t, used)) = clock(word, next) {
+            time = Some(t);
+            i += used;
+        } else if word == "at" && next.and_then(|w| w.parse::<u32>().ok()).is_some() {
+            // "at 3" reads as the afternoon for small hours
+            let hour: u32 = next?.parse().ok()?;
+            let hour = if (1..=7).contains(&hour) {
+                hour + 12
+            } else {
+                hour
+            };
+            time = Some(NaiveTime::from_hms_opt(hour, 0, 0)?);
+            i += 2;
+        } else if FILLERS.contains(&word) {
+            i += 1;
+        } else if matches!(word, "today" | "tod") {
+            date = Some(today);
+            i += 1;
+        } else if matches!(word, "tomorrow" | "tmr" | "tmrw") {
+            date = Some(today + Duration::days(1));
+            i += 1;
+        } else if word == "yesterday" {
+            date = Some(today - Duration::days(1));
+            i += 1;
+        } else if word == "day" && next == Some("after") && words.get(i + 2) == Some(&"tomorrow") {
+            date = Some(today + Duration::days(2));
+            i += 3;
+        } else if let Some(day) = weekday(word) {
+            date = Some(upcoming(today, day, false));
+            i += 1;
+        } else if matches!(word, "next" | "this" | "last") {
+            let target = next?;
+            let resolved = if let Some(day) = weekday(target) {
+                match word {
+                    "this" => upcoming(today, day, false),
+                    "next" => upcoming(today, day, true),
+                    _ => previous(today, day),
+                }
+            } else {
+                let n = match word {
+                    "this" => 0,
+                    "next" => 1,
+                    _ => -1,
+                };
+                offset(reference, n, target)?.date()
+            };
+            date = Some(resolved);
+            i += 2;
+        } else if word == "in" || count(word).is_some() {
+            // "in 3 days", "2 weeks ago", "5 days from now"
+            let start = if word == "in" { i + 1 } else { i };
+            let n = count(words.get(start)?)? as i64;
+            let unit = words.get(start + 1)?;
+            // `used` counts words from `i`
+            let (sign, used) = match (word, words.get(start + 2).copied()) {
+                ("in", _) => (1, 3),
+                (_, Some("ago")) => (-1, 3),
+                (_, Some("later")) => (1, 3),
+                (_, Some("from")) if words.get(start + 3) == Some(&"now") => (1, 4),
+                _ => {
+                    // Not a relative offset; maybe a day of the month ("5 may")
+                    let (d, explicit, used) = month_date(&words[i..], today)?;
+                    date = Some(d);
+                    roll_year = !explicit;
+                    i += used;
+                    continue;
+                }
+            };
+            let moved = offset(reference, sign * n, unit)?;
+            date = Some(moved.date());
+            if unit.starts_with("min") || unit.starts_with("h") {
+                time = Some(moved.time());
+            }
+            i += used;
+        } else if let Ok(d) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+            date = Some(d);
+            i += 1;
+        } else if let Some((d, explicit)) = numeric_date(word, today, day_first) {
+            date = Some(d);
+            roll_year = !explicit;
+            i += 1;
+        } else if let Some((d, explicit, used)) = month_date(&words[i..], today) {
+            date = Some(d);
+            roll_year = !explicit;
+            i += used;
+        } else {
+            return None;
+        }
+    }
+
+    if date.is_none() && time.is_none() {
+        return None;
+    }
+
+    let mut date = match date {
+        Some(d) => d,
+        // A bare time that already passed today means tomorrow
+        None if time.is_some_and(|t| t <= reference.time()) => today + Duration::days(1),
+        None => today,
+    };
+    if roll_year && date < today {
+        date = date.with_year(date.year() + 1).unwrap_or(date);
+    }
+
+    Some(Resolved { date, time })
+}
@@ -1,30 +1,173 @@
-use rusqlite::{Connection, Result as SqliteResult};
+use crate::models::{EventStatus, EventType, NodeShape, NodeSize, Priority, TimeMode};
+use crate::timestamps;
+use chrono::Utc;
+use rusqlite::{params, Connection, OpenFlags, Result as SqliteResult};
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 use tauri::AppHandle;
 use tauri::Manager;
 
+pub const DB_FILE: &str = "voyena.db";
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Statements kept prepared by prepare_cached. The default of 16 is fewer than
+// the queries a single screen of the app runs.
+const STATEMENT_CACHE: usize = 64;
+
+// What timestamps::format writes, as a GLOB pattern
+const CANONICAL_TIMESTAMP: &str =
+    "[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]T[0-9][0-9]:[0-9][0-9]:[0-9][0-9].[0-9][0-9][0-9]Z";
+
+// Every column holding a time a record was created, changed or acted on.
+// Some are compared with others (a sync base with a note's updated_at), so
+// they all have to be in the same format.
+const RECORD_TIMESTAMPS: &[(&str, &str)] = &[
+    ("folders", "created_at"),
+    ("folders", "updated_at"),
+    ("notes", "created_at"),
+    ("notes", "updated_at"),
+    ("notes", "deleted_at"),
+    ("events", "created_at"),
+    ("events", "updated_at"),
+    ("events", "deleted_at"),
+    ("brain_maps", "created_at"),
+    ("brain_maps", "updated_at"),
+    ("brain_maps", "deleted_at"),
+    ("brain_map_nodes", "created_at"),
+    ("brain_map_nodes", "updated_at"),
+    ("brain_map_nodes", "deleted_at"),
+    ("brain_map_connections", "created_at"),
+    ("brain_map_connections", "deleted_at"),
+    ("brain_map_ops", "created_at"),
+    ("style_presets", "created_at"),
+    ("attachments", "created_at"),
+    ("email_imports", "imported_at"),
+    ("feeds", "last_fetched_at"),
+    ("feeds", "created_at"),
+    ("feed_items", "published_at"),
+    ("feed_items", "created_at"),
+    ("recovery_codes", "used_at"),
+    ("recovery_codes", "created_at"),
+    ("jobs", "run_after"),
+    ("jobs", "created_at"),
+    ("jobs", "updated_at"),
+    ("reminder_log", "fire_at"),
+    ("reminder_log", "fired_at"),
+    ("inbox_items", "created_at"),
+    ("inbox_items", "triaged_at"),
+    ("note_stats", "last_opened_at"),
+    ("smart_folders", "created_at"),
+    ("smart_folders", "updated_at"),
+    ("entity_metadata", "updated_at"),
+    ("task_completions", "completed_at"),
+    ("task_dependencies", "created_at"),
+    ("projects", "created_at"),
+    ("projects", "updated_at"),
+    ("project_items", "added_at"),
+    ("goals", "created_at"),
+    ("goals", "updated_at"),
+    ("goal_items", "added_at"),
+    ("note_comments", "created_at"),
+    ("note_comments", "updated_at"),
+    ("sync_state", "base_updated_at"),
+    ("conflict_copies", "created_at"),
+    ("note_crdt", "updated_at"),
+    ("lan_peers", "last_sent_at"),
+    ("lan_peers", "last_synced_at"),
+    ("backups", "created_at"),
+    ("note_renames", "renamed_at"),
+    ("note_revisions", "created_at"),
+    ("mirror_files", "updated_at"),
+    ("watched_folders", "created_at"),
+    ("watched_files", "note_updated_at"),
+    ("system_search_files", "updated_at"),
+    ("snippets", "created_at"),
+    ("snippets", "updated_at"),
+    ("snippets", "deleted_at"),
+    ("scripts", "last_run_at"),
+    ("scripts", "created_at"),
+    ("scripts", "updated_at"),
+];
+
 pub struct Database {
-    pub conn: Mutex<Connection>,
+    pub conn: SharedConnection,
+    pub data_dir: PathBuf,
+}
+
+// The connection behind a mutex that recovers when a command panics while
+// holding it, rather than leaving every later command failing on the
+// poisoned lock
+pub struct SharedConnection(Mutex<Connection>);
+
+impl SharedConnection {
+    fn new(conn: Connection) -> Self {
+        SharedConnection(Mutex::new(conn))
+    }
+
+    // Never fails; returns a Result so callers treat it like any other lock
+    pub fn lock(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        let guard = match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                log::error!("Recovering the database connection after a panic");
+                self.0.clear_poison();
+                let guard = poisoned.into_inner();
+                // The panic may have left a transaction open; whatever it
+                // wrote is undone
+                if !guard.is_autocommit() {
+                    if let Err(e) = guard.execute_batch("ROLLBACK") {
+                        log::error!("Failed to roll back after a panic: {}", e);
+                    }
+                }
+                guard
+            }
+        };
+        Ok(guard)
+    }
 }
 
 impl Database {
-    pub fn new(app_handle: &AppHandle) -> SqliteResult<Self> {
+    // The app data directory, created if it's missing
+    pub fn data_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
         let app_dir = app_handle
             .path()
             .app_data_dir()
-            .expect("Failed to get app data directory");
-
-        std::fs::create_dir_all(&app_dir).expect("Failed to create app data directory");
+            .map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+        Ok(app_dir)
+    }
 
-        let db_path: PathBuf = app_dir.join("voyena.db");
-        let conn = Connection::open(&db_path)?;
+    pub fn open(app_dir: PathBuf) -> SqliteResult<Self> {
+        let db_path: PathBuf = app_dir.join(DB_FILE);
+        let mut conn = Connection::open(&db_path)?;
+        // Another process holding a write lock is waited out for a while
+        // before the start is treated as failed
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
 
         // Initialize schema
         Self::init_schema(&conn)?;
+        crate::profiling::init(&mut conn);
 
         Ok(Self {
-            conn: Mutex::new(conn),
+            conn: SharedConnection::new(conn),
+            data_dir: app_dir,
+        })
+    }
+
+    // Safe mode after a failed start: the schema is left alone and every
+    // write fails
+    pub fn open_read_only(app_dir: PathBuf) -> SqliteResult<Self> {
+        let mut conn =
+            Connection::open_with_flags(app_dir.join(DB_FILE), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
+        crate::profiling::init(&mut conn);
+
+        Ok(Self {
+            conn: SharedConnection::new(conn),
+            data_dir: app_dir,
         })
     }
 
@@ -127,6 +270,7 @@ impl Database {
                 layer INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
+                deleted_at TEXT,
                 FOREIGN KEY (brain_map_id) REFERENCES brain_maps(id) ON DELETE CASCADE,
                 FOREIGN KEY (parent_node_id) REFERENCES brain_map_nodes(id) ON DELETE SET NULL,
                 FOREIGN KEY (linked_note_id) REFERENCES notes(id) ON DELETE SET NULL,
@@ -145,11 +289,389 @@ impl Database {
                 style TEXT DEFAULT 'solid',
                 animated INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
+                deleted_at TEXT,
                 FOREIGN KEY (brain_map_id) REFERENCES brain_maps(id) ON DELETE CASCADE,
                 FOREIGN KEY (source_node_id) REFERENCES brain_map_nodes(id) ON DELETE CASCADE,
                 FOREIGN KEY (target_node_id) REFERENCES brain_map_nodes(id) ON DELETE CASCADE
             );
 
+            -- Undo log of node/connection edits (see history.rs)
+            CREATE TABLE IF NOT EXISTS brain_map_ops (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                brain_map_id TEXT NOT NULL,
+                label TEXT NOT NULL,
+                footprint TEXT NOT NULL,
+                state_before TEXT NOT NULL,
+                state_after TEXT NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );
+
+            -- Named node styles for the brain map style picker
+            CREATE TABLE IF NOT EXISTS style_presets (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                color TEXT,
+                shape TEXT,
+                size TEXT,
+                icon TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            -- Attachments table (blobs live in the app data dir, see attachments.rs)
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL,
+                kind TEXT NOT NULL DEFAULT 'file',
+                file_name TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                duration_ms INTEGER,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+            );
+
+            -- Emails already turned into notes by the IMAP capture
+            CREATE TABLE IF NOT EXISTS email_imports (
+                message_id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL,
+                imported_at TEXT NOT NULL
+            );
+
+            -- Subscribed RSS/Atom feeds
+            CREATE TABLE IF NOT EXISTS feeds (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT,
+                site_url TEXT,
+                last_fetched_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            -- Fetched feed entries (read-later list)
+            CREATE TABLE IF NOT EXISTS feed_items (
+                id TEXT PRIMARY KEY,
+                feed_id TEXT NOT NULL,
+                guid TEXT NOT NULL,
+                title TEXT NOT NULL,
+                url TEXT,
+                summary TEXT,
+                content TEXT,
+                published_at TEXT,
+                is_read INTEGER NOT NULL DEFAULT 0,
+                note_id TEXT,
+                created_at TEXT NOT NULL,
+                UNIQUE (feed_id, guid),
+                FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE,
+                FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE SET NULL
+            );
+
+            -- Hashed single-use recovery codes for encrypted content
+            CREATE TABLE IF NOT EXISTS recovery_codes (
+                id TEXT PRIMARY KEY,
+                code_hash TEXT NOT NULL,
+                salt TEXT NOT NULL,
+                used_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            -- Background jobs; `spec` is the JSON job description
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                spec TEXT NOT NULL,
+                state TEXT NOT NULL DEFAULT 'queued',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                done INTEGER NOT NULL DEFAULT 0,
+                total INTEGER NOT NULL DEFAULT 0,
+                message TEXT,
+                output TEXT,
+                error TEXT,
+                run_after TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Reminders already delivered, so each fires once even across restarts
+            CREATE TABLE IF NOT EXISTS reminder_log (
+                event_id TEXT NOT NULL,
+                reminder_key TEXT NOT NULL,
+                fire_at TEXT NOT NULL,
+                fired_at TEXT NOT NULL,
+                PRIMARY KEY (event_id, reminder_key, fire_at)
+            );
+
+            -- Quick-add entries; triaged_at is set once filed or dismissed
+            CREATE TABLE IF NOT EXISTS inbox_items (
+                id TEXT PRIMARY KEY,
+                text TEXT NOT NULL,
+                title TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                due_date TEXT,
+                priority TEXT,
+                filed_id TEXT,
+                created_at TEXT NOT NULL,
+                triaged_at TEXT
+            );
+
+            -- Search index, kept current by search_index.rs on every note write
+            CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+                note_id UNINDEXED,
+                title,
+                body,
+                tags,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            -- Wikilinks by lowercased target (a note title or id) for backlinks
+            CREATE TABLE IF NOT EXISTS note_links (
+                source_note_id TEXT NOT NULL,
+                target TEXT NOT NULL,
+                PRIMARY KEY (source_note_id, target)
+            );
+
+            -- Web links found in notes
+            CREATE TABLE IF NOT EXISTS note_bookmarks (
+                note_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                label TEXT,
+                PRIMARY KEY (note_id, url)
+            );
+
+            -- How often each note is opened, for search ranking. Kept apart
+            -- from notes so opening one doesn't touch updated_at.
+            CREATE TABLE IF NOT EXISTS note_stats (
+                note_id TEXT PRIMARY KEY,
+                open_count INTEGER NOT NULL DEFAULT 0,
+                last_opened_at TEXT
+            );
+
+            -- Saved searches (smart folders)
+            CREATE TABLE IF NOT EXISTS smart_folders (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Custom fields on notes, events and folders; values are JSON
+            CREATE TABLE IF NOT EXISTS entity_metadata (
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (entity_type, entity_id, key)
+            );
+
+            -- Recurrence rules of recurring tasks; occurrence dates are computed
+            CREATE TABLE IF NOT EXISTS task_recurrence (
+                event_id TEXT PRIMARY KEY,
+                frequency TEXT NOT NULL,
+                interval INTEGER NOT NULL DEFAULT 1,
+                weekdays TEXT NOT NULL DEFAULT '[]',
+                until_date TEXT
+            );
+
+            -- Completed occurrences of recurring tasks, by local date
+            CREATE TABLE IF NOT EXISTS task_completions (
+                event_id TEXT NOT NULL,
+                occurrence_date TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                PRIMARY KEY (event_id, occurrence_date)
+            );
+
+            -- task_id can't start before depends_on_id is done
+            CREATE TABLE IF NOT EXISTS task_dependencies (
+                task_id TEXT NOT NULL,
+                depends_on_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (task_id, depends_on_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS projects (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                color TEXT,
+                status TEXT NOT NULL DEFAULT 'active',
+                due_date TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Notes, events (tasks included) and brain maps linked to a project
+            CREATE TABLE IF NOT EXISTS project_items (
+                project_id TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                added_at TEXT NOT NULL,
+                PRIMARY KEY (project_id, item_type, item_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS goals (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                metric TEXT NOT NULL DEFAULT 'completion',
+                target INTEGER,
+                due_date TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Tasks and habits whose completions count towards a goal
+            CREATE TABLE IF NOT EXISTS goal_items (
+                goal_id TEXT NOT NULL,
+                event_id TEXT NOT NULL,
+                added_at TEXT NOT NULL,
+                PRIMARY KEY (goal_id, event_id)
+            );
+
+            -- Margin comments on notes; the range is in UTF-16 units of the content
+            CREATE TABLE IF NOT EXISTS note_comments (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL,
+                anchor_offset INTEGER NOT NULL,
+                anchor_length INTEGER NOT NULL,
+                quote TEXT NOT NULL,
+                body TEXT NOT NULL,
+                resolved INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Last updated_at of each note/event this device and another agreed on
+            CREATE TABLE IF NOT EXISTS sync_state (
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                base_updated_at TEXT NOT NULL,
+                PRIMARY KEY (entity_type, entity_id)
+            );
+
+            -- Copies holding another device's version of an item both edited
+            CREATE TABLE IF NOT EXISTS conflict_copies (
+                id TEXT PRIMARY KEY,
+                entity_type TEXT NOT NULL,
+                original_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                device TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            -- Automerge document holding each note's content and its edit history
+            CREATE TABLE IF NOT EXISTS note_crdt (
+                note_id TEXT PRIMARY KEY,
+                doc BLOB NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- Devices synced with over the local network
+            CREATE TABLE IF NOT EXISTS lan_peers (
+                device_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                last_sent_at TEXT,
+                last_synced_at TEXT
+            );
+
+            -- Backups written from the app, for the storage report
+            CREATE TABLE IF NOT EXISTS backups (
+                path TEXT PRIMARY KEY,
+                created_at TEXT NOT NULL
+            );
+
+            -- Titles notes had before being renamed, lowercased, for repairing links
+            CREATE TABLE IF NOT EXISTS note_renames (
+                note_id TEXT NOT NULL,
+                old_title TEXT NOT NULL,
+                renamed_at TEXT NOT NULL,
+                PRIMARY KEY (note_id, old_title)
+            );
+
+            -- Note snapshots taken before automatic edits, newest kept
+            CREATE TABLE IF NOT EXISTS note_revisions (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                note_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            -- Files the Markdown mirror wrote, relative to its directory, and
+            -- the note version each holds
+            CREATE TABLE IF NOT EXISTS mirror_files (
+                note_id TEXT PRIMARY KEY,
+                directory TEXT NOT NULL,
+                path TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- App folders kept in two-way sync with a directory of Markdown files
+            CREATE TABLE IF NOT EXISTS watched_folders (
+                folder_id TEXT PRIMARY KEY,
+                directory TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            -- Which file in a watched directory each note is, as of the last
+            -- sync: the note version written or read and the file's mtime
+            CREATE TABLE IF NOT EXISTS watched_files (
+                note_id TEXT PRIMARY KEY,
+                folder_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                note_updated_at TEXT NOT NULL,
+                file_modified_at INTEGER NOT NULL
+            );
+
+            -- Notes with a page in the system search directory, and the
+            -- version each page shows
+            CREATE TABLE IF NOT EXISTS system_search_files (
+                note_id TEXT PRIMARY KEY,
+                updated_at TEXT NOT NULL
+            );
+
+            -- The note kept for each day (YYYY-MM-DD), e.g. for clipboard
+            -- captures
+            CREATE TABLE IF NOT EXISTS daily_notes (
+                date TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL
+            );
+
+            -- The note list order last chosen in each folder; '' is the list
+            -- of all notes
+            CREATE TABLE IF NOT EXISTS note_sort_choices (
+                folder_id TEXT PRIMARY KEY,
+                sort TEXT NOT NULL,
+                descending INTEGER NOT NULL
+            );
+
+            -- Blocks of text typed by their trigger, e.g. ";sig". Deleted ones
+            -- keep a row with deleted_at set so the deletion syncs.
+            CREATE TABLE IF NOT EXISTS snippets (
+                id TEXT PRIMARY KEY,
+                trigger TEXT NOT NULL,
+                name TEXT,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT
+            );
+
+            -- User scripts, run when notes and events change; see scripting.rs
+            -- for the handlers they can define
+            CREATE TABLE IF NOT EXISTS scripts (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                source TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                last_run_at TEXT,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
             -- Indexes for performance
             CREATE INDEX IF NOT EXISTS idx_notes_folder ON notes(folder_id);
             CREATE INDEX IF NOT EXISTS idx_notes_updated ON notes(updated_at DESC);
@@ -161,6 +683,14 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_brain_map_nodes_map ON brain_map_nodes(brain_map_id);
             CREATE INDEX IF NOT EXISTS idx_brain_map_nodes_parent ON brain_map_nodes(parent_node_id);
             CREATE INDEX IF NOT EXISTS idx_brain_map_connections_map ON brain_map_connections(brain_map_id);
+            CREATE INDEX IF NOT EXISTS idx_brain_map_ops_map ON brain_map_ops(brain_map_id, seq);
+            CREATE INDEX IF NOT EXISTS idx_attachments_note ON attachments(note_id);
+            CREATE INDEX IF NOT EXISTS idx_feed_items_feed ON feed_items(feed_id, published_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_jobs_state ON jobs(state, run_after);
+            CREATE INDEX IF NOT EXISTS idx_note_links_target ON note_links(target);
+            CREATE INDEX IF NOT EXISTS idx_note_comments_note ON note_comments(note_id);
+            CREATE INDEX IF NOT EXISTS idx_note_revisions_note ON note_revisions(note_id, seq);
+            CREATE INDEX IF NOT EXISTS idx_snippets_trigger ON snippets(trigger COLLATE NOCASE);
             "#,
         )?;
 
@@ -172,15 +702,230 @@ impl Database {
 
     fn run_migrations(conn: &Connection) -> SqliteResult<()> {
         // Migration: Add linked_event_id column to brain_map_nodes if it doesn't exist
+        Self::add_column_if_missing(
+            conn,
+            "brain_map_nodes",
+            "linked_event_id",
+            "TEXT REFERENCES events(id) ON DELETE SET NULL",
+        )?;
+
+        // Migration: Public URL of a published note
+        Self::add_column_if_missing(conn, "notes", "published_url", "TEXT")?;
+
+        // Migration: Nodes and connections are trashed along with their map
+        Self::add_column_if_missing(conn, "brain_map_nodes", "deleted_at", "TEXT")?;
+        Self::add_column_if_missing(conn, "brain_map_connections", "deleted_at", "TEXT")?;
+
+        // Migration: Travel buffer before an event, for "time to leave" reminders
+        Self::add_column_if_missing(conn, "events", "travel_minutes", "INTEGER")?;
+
+        // Migration: Quick-add entries can name a folder ("/Work/Projects")
+        Self::add_column_if_missing(conn, "inbox_items", "folder_path", "TEXT")?;
+
+        // Migration: Attachment blobs are shared by content hash. Older rows
+        // keep their own blob and a NULL hash.
+        Self::add_column_if_missing(conn, "attachments", "content_hash", "TEXT")?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_attachments_hash ON attachments(content_hash);
+             CREATE INDEX IF NOT EXISTS idx_attachments_file ON attachments(file_name);",
+        )?;
+
+        // Migration: Finished jobs keep a report of what they did (JSON)
+        Self::add_column_if_missing(conn, "jobs", "report", "TEXT")?;
+
+        // Migration: Other names a note goes by, a JSON array like tags
+        Self::add_column_if_missing(conn, "notes", "aliases", "TEXT NOT NULL DEFAULT '[]'")?;
+
+        // Migration: Watched files remember the content hash last synced, so
+        // an edit is told apart from a bare mtime change. Older rows get ''.
+        Self::add_column_if_missing(
+            conn,
+            "watched_files",
+            "file_hash",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+
+        // Migration: Trashed notes remember the path of the folder they were
+        // in, so restoring one can find or recreate it if it's gone since
+        Self::add_column_if_missing(conn, "notes", "deleted_folder_path", "TEXT")?;
+
+        // Migration: Notes can be put in order by hand; ones never placed
+        // have a NULL position. Each way a folder can be sorted gets an index
+        // in its default direction, so get_notes reads a folder in order
+        // rather than sorting it.
+        Self::add_column_if_missing(conn, "notes", "position", "INTEGER")?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_notes_folder_updated
+                 ON notes(folder_id, is_pinned DESC, updated_at DESC);
+             CREATE INDEX IF NOT EXISTS idx_notes_folder_created
+                 ON notes(folder_id, is_pinned DESC, created_at DESC);
+             CREATE INDEX IF NOT EXISTS idx_notes_folder_title
+                 ON notes(folder_id, is_pinned DESC, title COLLATE NOCASE);
+             CREATE INDEX IF NOT EXISTS idx_notes_folder_size
+                 ON notes(folder_id, is_pinned DESC, length(content) DESC);
+             CREATE INDEX IF NOT EXISTS idx_notes_folder_position
+                 ON notes(folder_id, is_pinned DESC, position);",
+        )?;
+
+        // Migration: The language a note is written in, detected whenever it's
+        // indexed for search; existing notes get theirs from the rebuild
+        Self::add_column_if_missing(conn, "notes", "language", "TEXT")?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_notes_language ON notes(language)",
+        )?;
+
+        // Migration: Rewrite legacy enum spellings to their canonical form so
+        // rows read back into the typed models; anything unrecognizable gets
+        // the column default.
+        Self::normalize_column(conn, "events", "time_mode", "at_time", |v| {
+            v.parse::<TimeMode>().ok().map(|m| m.as_str())
+        })?;
+        Self::normalize_column(conn, "events", "priority", "medium", |v| {
+            v.parse::<Priority>().ok().map(|p| p.as_str())
+        })?;
+        Self::normalize_column(conn, "events", "status", "pending", |v| {
+            v.parse::<EventStatus>().ok().map(|s| s.as_str())
+        })?;
+        Self::normalize_column(conn, "events", "event_type", "event", |v| {
+            v.parse::<EventType>().ok().map(|t| t.as_str())
+        })?;
+        Self::normalize_column(conn, "brain_map_nodes", "shape", "circle", |v| {
+            v.parse::<NodeShape>().ok().map(|s| s.as_str())
+        })?;
+        Self::normalize_column(conn, "brain_map_nodes", "size", "medium", |v| {
+            v.parse::<NodeSize>().ok().map(|s| s.as_str())
+        })?;
+
+        // Migration: Event times were stored as whatever the client sent,
+        // often local times without an offset, so ORDER BY and date ranges
+        // compared mixed formats. They're rewritten as UTC RFC 3339; values
+        // that don't parse as a time at all are cleared.
+        Self::normalize_timestamps(conn, "events", "start_time")?;
+        Self::normalize_timestamps(conn, "events", "end_time")?;
+
+        // Migration: Record timestamps were written with to_rfc3339, in
+        // nanoseconds with a +00:00 offset, and read back as plain strings.
+        // They're typed now, so they're rewritten in the same stored format
+        // as event times.
+        for (table, column) in RECORD_TIMESTAMPS {
+            Self::normalize_timestamps(conn, table, column)?;
+        }
+
+        // Migration: update_event used to leave has_scheduled_time alone, so
+        // events given a start time later never showed up on the calendar
+        conn.execute(
+            "UPDATE events SET has_scheduled_time = 1
+             WHERE has_scheduled_time = 0 AND start_time IS NOT NULL",
+            [],
+        )?;
+
+        // Migration: One connection per direction between two nodes. Stacked
+        // duplicates from before the constraint keep their oldest copy.
+        conn.execute_batch(
+            "DELETE FROM brain_map_connections WHERE rowid NOT IN (
+                 SELECT MIN(rowid) FROM brain_map_connections
+                 GROUP BY brain_map_id, source_node_id, target_node_id
+             );
+             CREATE UNIQUE INDEX IF NOT EXISTS idx_brain_map_connections_unique
+                 ON brain_map_connections(brain_map_id, source_node_id, target_node_id);",
+        )?;
+
+        Ok(())
+    }
+
+    fn normalize_column(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        fallback: &str,
+        canonical: fn(&str) -> Option<&'static str>,
+    ) -> SqliteResult<()> {
+        let values: Vec<String> = conn
+            .prepare(&format!(
+                "SELECT DISTINCT {} FROM {} WHERE {} IS NOT NULL",
+                column, table, column
+            ))?
+            .query_map([], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for value in values {
+            let normalized = canonical(&value).unwrap_or(fallback);
+            if normalized != value {
+                conn.execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, column),
+                    params![normalized, value],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn normalize_timestamps(conn: &Connection, table: &str, column: &str) -> SqliteResult<()> {
+        // Rows already in the stored format are skipped, so this only does
+        // work once
+        let values: Vec<String> = conn
+            .prepare(&format!(
+                "SELECT DISTINCT {0} FROM {1}
+                 WHERE {0} IS NOT NULL AND {0} NOT GLOB '{2}'",
+                column, table, CANONICAL_TIMESTAMP
+            ))?
+            .query_map([], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        // A NOT NULL column can't be cleared, so it gets the current time
+        let required: bool = conn.query_row(
+            "SELECT \"notnull\" FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        for value in values {
+            let normalized = match timestamps::parse(&value) {
+                Some(time) => Some(timestamps::format(&time)),
+                None if required => {
+                    log::warn!(
+                        "Replacing unreadable {}.{} value {:?}",
+                        table,
+                        column,
+                        value
+                    );
+                    Some(timestamps::format(&Utc::now()))
+                }
+                None => {
+                    log::warn!("Clearing unreadable {}.{} value {:?}", table, column, value);
+                    None
+                }
+            };
+            conn.execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, column),
+                params![normalized, value],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn add_column_if_missing(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> SqliteResult<()> {
         let columns: Vec<String> = conn
-            .prepare("PRAGMA table_info(brain_map_nodes)")?
+            .prepare(&format!("PRAGMA table_info({})", table))?
             .query_map([], |row| row.get::<_, String>(1))?
             .filter_map(|r| r.ok())
             .collect();
 
-        if !columns.contains(&"linked_event_id".to_string()) {
+        if !columns.iter().any(|c| c == column) {
             conn.execute(
-                "ALTER TABLE brain_map_nodes ADD COLUMN linked_event_id TEXT REFERENCES events(id) ON DELETE SET NULL",
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
                 [],
             )?;
         }
@@ -0,0 +1,104 @@
+use crate::share_target;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+pub const OPEN_NOTE_EVENT: &str = "note:open";
+
+// A note link the app was launched with, held until the frontend is ready
+// to ask for it since the event would arrive before it listens
+#[derive(Default)]
+pub struct LaunchLink {
+    note_id: Mutex<Option<String>>,
+}
+
+impl LaunchLink {
+    fn set(&self, note_id: Option<String>) {
+        if let Ok(mut current) = self.note_id.lock() {
+            *current = note_id;
+        }
+    }
+
+    // Only handed out once
+    pub fn take(&self) -> Option<String> {
+        self.note_id.lock().ok()?.take()
+    }
+}
+
+// `voyena://note/{id}`
+pub fn note_url(note_id: &str) -> String {
+    format!("voyena://note/{}", note_id)
+}
+
+fn note_id(url: &Url) -> Option<String> {
+    if url.scheme() != "voyena" || url.host_str() != Some("note") {
+        return None;
+    }
+    let id = url.path().trim_matches('/');
+    (!id.is_empty() && !id.contains('/')).then(|| id.to_string())
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn is_share(url: &Url) -> bool {
+    url.scheme() == "voyena" && url.host_str() == Some("share")
+}
+
+fn open(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        if is_share(&url) {
+            focus_main_window(app);
+            share_target::handle(app, share_target::from_url(&url));
+            continue;
+        }
+        let Some(note_id) = note_id(&url) else {
+            log::warn!("Ignoring unrecognized link {}", url);
+            continue;
+        };
+        focus_main_window(app);
+        if let Err(e) = app.emit(OPEN_NOTE_EVENT, note_id) {
+            log::warn!("Failed to emit {}: {}", OPEN_NOTE_EVENT, e);
+        }
+    }
+}
+
+// Links opened while the app runs arrive as the note:open event; shared
+// content, at launch or later, is saved as it comes in
+pub fn listen(app: &AppHandle) {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(files) = share_target::files_from_args(&args) {
+        share_target::offer_files(app, files);
+    }
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            app.state::<LaunchLink>().set(urls.iter().find_map(note_id));
+            for url in urls.iter().filter(|url| is_share(url)) {
+                share_target::handle(app, share_target::from_url(url));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to read the launch link: {}", e),
+    }
+
+    let handle = app.clone();
+    app.deep_link()
+        .on_open_url(move |event| open(&handle, event.urls()));
+}
+
+// A second launch, e.g. from a link on Windows or Linux, brings this
+// instance forward instead; the link itself reaches `listen`, files sent
+// from the Explorer menu are handled here
+#[cfg(desktop)]
+pub fn second_instance(app: &AppHandle, args: Vec<String>, _cwd: String) {
+    focus_main_window(app);
+    if let Some(files) = share_target::files_from_args(&args) {
+        share_target::offer_files(app, files);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::models::{Event, EventStatus, PlannedTask, Priority, TaskPlan};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+pub fn add(conn: &Connection, task_id: &str, depends_on_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_at)
+         VALUES (?1, ?2, ?3)",
+        params![task_id, depends_on_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn remove(conn: &Connection, task_id: &str, depends_on_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+        params![task_id, depends_on_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Drops every dependency to or from a task that's being deleted for good
+pub fn remove_all(conn: &Connection, event_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 OR depends_on_id = ?1",
+        params![event_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Task id to the ids it depends on, in the order they were added
+fn edges(conn: &Connection) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT task_id, depends_on_id FROM task_dependencies ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (task_id, depends_on_id) = row.map_err(|e| e.to_string())?;
+        edges.entry(task_id).or_default().push(depends_on_id);
+    }
+    Ok(edges)
+}
+
+// Everything `id` waits on, directly or through other tasks
+fn reachable(edges: &HashMap<String, Vec<String>>, id: &str) -> HashSet<String> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(next) = stack.pop() {
+        for dep in edges.get(next).into_iter().flatten() {
+            if seen.insert(dep.clone()) {
+                stack.push(dep);
+            }
+        }
+    }
+    seen
+}
+
+// True if making `task_id` wait on `depends_on_id` would close a loop
+pub fn would_cycle(conn: &Connection, task_id: &str, depends_on_id: &str) -> Result<bool, String> {
+    Ok(task_id == depends_on_id || reachable(&edges(conn)?, depends_on_id).contains(task_id))
+}
+
+// Ids of live tasks that aren't done yet
+fn unfinished(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM events WHERE deleted_at IS NULL
+             AND (status IS NULL OR status IN ('pending', 'in_progress'))",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// Among tasks that are ready at the same time: higher priority first, then
+// the earliest start, then the oldest
+fn sort_key(task: &Event) -> (Reverse<u8>, bool, Option<DateTime<Utc>>, DateTime<Utc>) {
+    let priority = match task.priority {
+        Some(Priority::High) => 3,
+        Some(Priority::Medium) => 2,
+        Some(Priority::Low) => 1,
+        None => 0,
+    };
+    (
+        Reverse(priority),
+        task.start_time.is_none(),
+        task.start_time,
+        task.created_at,
+    )
+}
+
+fn is_open(task: &Event) -> bool {
+    matches!(
+        task.status,
+        None | Some(EventStatus::Pending) | Some(EventStatus::InProgress)
+    )
+}
+
+// Orders `tasks` so each comes after the ones it depends on. Tasks left over
+// are in a cycle or wait on one; they go last with no step.
+pub fn plan(conn: &Connection, tasks: Vec<Event>) -> Result<TaskPlan, String> {
+    let edges = edges(conn)?;
+    let unfinished = unfinished(conn)?;
+    let tasks: Vec<Event> = tasks.into_iter().filter(is_open).collect();
+    let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+
+    // Dependencies between tasks in the plan that still hold them back
+    let waits_on = |id: &str| -> Vec<&str> {
+        edges
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|dep| ids.contains(dep) && unfinished.contains(*dep))
+            .collect()
+    };
+
+    let mut steps: HashMap<&str, u32> = HashMap::new();
+    let mut order: Vec<usize> = Vec::new();
+    let mut remaining: Vec<usize> = (0..tasks.len()).collect();
+    remaining.sort_by(|a, b| sort_key(&tasks[*a]).cmp(&sort_key(&tasks[*b])));
+
+    while let Some(position) = remaining.iter().position(|i| {
+        waits_on(&tasks[*i].id)
+            .iter()
+            .all(|d| steps.contains_key(d))
+    }) {
+        let index = remaining.remove(position);
+        let id = tasks[index].id.as_str();
+        let step = waits_on(id).iter().map(|d| steps[d] + 1).max().unwrap_or(0);
+        steps.insert(id, step);
+        order.push(index);
+    }
+
+    // A leftover task is in a cycle if it waits on itself; tasks that wait
+    // on each other share one
+    let left: HashMap<&str, HashSet<String>> = remaining
+        .iter()
+        .map(|i| (tasks[*i].id.as_str(), reachable(&edges, &tasks[*i].id)))
+        .collect();
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    let mut in_cycle: HashSet<String> = HashSet::new();
+    for id in remaining.iter().map(|i| tasks[*i].id.as_str()) {
+        if in_cycle.contains(id) || !left[id].contains(id) {
+            continue;
+        }
+        let cycle: Vec<String> = remaining
+            .iter()
+            .map(|i| tasks[*i].id.clone())
+            .filter(|other| left[id].contains(other) && left[other.as_str()].contains(id))
+            .collect();
+        in_cycle.extend(cycle.iter().cloned());
+        cycles.push(cycle);
+    }
+    order.extend(remaining.iter().copied());
+
+    let planned = order
+        .into_iter()
+        .map(|index| {
+            let task = &tasks[index];
+            let depends_on = edges.get(&task.id).cloned().unwrap_or_default();
+            let blocked_by = depends_on
+                .iter()
+                .filter(|dep| unfinished.contains(*dep))
+                .cloned()
+                .collect();
+            PlannedTask {
+                depends_on,
+                blocked_by,
+                step: steps.get(task.id.as_str()).copied(),
+                in_cycle: in_cycle.contains(&task.id),
+                task: task.clone(),
+            }
+        })
+        .collect();
+
+    Ok(TaskPlan {
+        tasks: planned,
+        cycles,
+    })
+}
@@ -0,0 +1,339 @@
+use crate::background;
+use crate::commands::{insert_note, store_attachment};
+use crate::db::Database;
+use crate::models::{BackgroundSubsystem, EmailCaptureConfig, EmailPollResult, NoteCreate};
+use crate::render;
+use crate::timestamps;
+use chrono::Utc;
+use mail_parser::{MessageParser, MimeHeaders};
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+pub const EMAIL_CAPTURE_SETTING: &str = "email_capture";
+pub const KEYRING_SERVICE: &str = "com.voyena.app";
+const DEFAULT_POLL_MINUTES: u64 = 15;
+
+// ============ Configuration ============
+
+pub fn load_config(conn: &Connection) -> Result<Option<EmailCaptureConfig>, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![EMAIL_CAPTURE_SETTING],
+            |row| row.get(0),
+        )
+        .ok();
+
+    value
+        .map(|v| serde_json::from_str(&v).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// The IMAP password never touches the database; it lives in the OS keychain.
+fn keyring_entry(config: &EmailCaptureConfig) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(
+        KEYRING_SERVICE,
+        &format!("imap:{}@{}", config.username, config.host),
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn store_password(config: &EmailCaptureConfig, password: &str) -> Result<(), String> {
+    keyring_entry(config)?
+        .set_password(password)
+        .map_err(|e| e.to_string())
+}
+
+fn load_password(config: &EmailCaptureConfig) -> Result<String, String> {
+    keyring_entry(config)?
+        .get_password()
+        .map_err(|e| format!("No IMAP password in keychain: {}", e))
+}
+
+// ============ Minimal IMAP client ============
+
+struct ImapResponse {
+    lines: Vec<String>,
+    literals: Vec<Vec<u8>>,
+}
+
+struct ImapSession {
+    stream: BufReader<TlsStream<TcpStream>>,
+    next_tag: u32,
+}
+
+impl ImapSession {
+    async fn connect(host: &str, port: u16) -> Result<Self, String> {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+        let config = ClientConfig::builder_with_provider(Arc::new(
+            tokio_rustls::rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+        let server_name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+        let tcp = TcpStream::connect((host, port))
+            .await
+            .map_err(|e| e.to_string())?;
+        let tls = TlsConnector::from(Arc::new(config))
+            .connect(server_name, tcp)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut session = Self {
+            stream: BufReader::new(tls),
+            next_tag: 1,
+        };
+
+        // Server greeting
+        let mut greeting = String::new();
+        session
+            .stream
+            .read_line(&mut greeting)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !greeting.starts_with("* OK") {
+            return Err(format!("Unexpected IMAP greeting: {}", greeting.trim_end()));
+        }
+
+        Ok(session)
+    }
+
+    async fn command(&mut self, command: &str) -> Result<ImapResponse, String> {
+        let tag = format!("v{}", self.next_tag);
+        self.next_tag += 1;
+
+        self.stream
+            .get_mut()
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut response = ImapResponse {
+            lines: Vec::new(),
+            literals: Vec::new(),
+        };
+
+        loop {
+            let mut raw = Vec::new();
+            let read = self
+                .stream
+                .read_until(b'\n', &mut raw)
+                .await
+                .map_err(|e| e.to_string())?;
+            if read == 0 {
+                return Err("IMAP connection closed".to_string());
+            }
+            let line = String::from_utf8_lossy(&raw).trim_end().to_string();
+
+            if let Some(status) = line.strip_prefix(&format!("{} ", tag)) {
+                if status.starts_with("OK") {
+                    return Ok(response);
+                }
+                return Err(format!("IMAP error: {}", status));
+            }
+
+            // `{n}` at the end of a line announces an n-byte literal
+            if let Some(size) = literal_size(&line) {
+                let mut literal = vec![0u8; size];
+                self.stream
+                    .read_exact(&mut literal)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                response.literals.push(literal);
+            }
+            response.lines.push(line);
+        }
+    }
+}
+
+fn literal_size(line: &str) -> Option<usize> {
+    let open = line.rfind('{')?;
+    line.strip_suffix('}')?.get(open + 1..)?.parse().ok()
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// ============ Import ============
+
+// Turns one raw RFC 822 message into a note. Returns false when the message
+// was already imported (e.g. the \Seen flag was cleared on another client).
+fn import_message(
+    conn: &Connection,
+    data_dir: &Path,
+    config: &EmailCaptureConfig,
+    raw: &[u8],
+) -> Result<bool, String> {
+    let message = MessageParser::default()
+        .parse(raw)
+        .ok_or("Unparseable email")?;
+
+    let message_id = message
+        .message_id()
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| {
+            let digest = Sha256::digest(raw);
+            digest.iter().map(|b| format!("{:02x}", b)).collect()
+        });
+
+    // The note, its attachments and the email_imports row go in together, so
+    // a failure part way leaves nothing for the next poll to duplicate
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let already_imported: bool = tx
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM email_imports WHERE message_id = ?1)",
+            params![message_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if already_imported {
+        return Ok(false);
+    }
+
+    let content = match message.body_html(0) {
+        Some(html) => html.into_owned(),
+        None => render::markdown_to_html(&message.body_text(0).unwrap_or_default()),
+    };
+
+    let note = insert_note(
+        &tx,
+        NoteCreate {
+            title: Some(message.subject().unwrap_or("(no subject)").to_string()),
+            content: Some(content),
+            folder_id: config.folder_id.clone(),
+            tags: Some(vec!["email".to_string()]),
+            aliases: None,
+        },
+    )?;
+
+    for part in message.attachments() {
+        let mime_type = part
+            .content_type()
+            .map(|ct| match ct.subtype() {
+                Some(sub) => format!("{}/{}", ct.ctype(), sub),
+                None => ct.ctype().to_string(),
+            })
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let kind = match mime_type.split('/').next() {
+            Some(k @ ("image" | "audio" | "video")) => k.to_string(),
+            _ => "file".to_string(),
+        };
+
+        store_attachment(
+            &tx,
+            data_dir,
+            note.id.clone(),
+            &kind,
+            mime_type,
+            part.contents(),
+            None,
+        )?;
+    }
+
+    tx.execute(
+        "INSERT INTO email_imports (message_id, note_id, imported_at) VALUES (?1, ?2, ?3)",
+        params![message_id, note.id, timestamps::format(&Utc::now())],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+pub async fn poll(db: &Database) -> Result<EmailPollResult, String> {
+    let config = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        load_config(&conn)?.ok_or("Email capture is not configured")?
+    };
+    let password = load_password(&config)?;
+
+    let mut session = ImapSession::connect(&config.host, config.port.unwrap_or(993)).await?;
+    session
+        .command(&format!("LOGIN {} {}", quote(&config.username), quote(&password)))
+        .await?;
+    session
+        .command(&format!("SELECT {}", quote(&config.mailbox)))
+        .await?;
+
+    let search = session.command("UID SEARCH UNSEEN").await?;
+    let uids: Vec<u32> = search
+        .lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("* SEARCH"))
+        .flat_map(|rest| rest.split_whitespace().filter_map(|n| n.parse().ok()))
+        .collect();
+
+    let mut result = EmailPollResult::default();
+    for uid in uids {
+        let fetched = session
+            .command(&format!("UID FETCH {} BODY.PEEK[]", uid))
+            .await?;
+        let Some(raw) = fetched.literals.into_iter().next() else {
+            continue;
+        };
+
+        let imported = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            import_message(&conn, &db.data_dir, &config, &raw)
+        };
+        match imported {
+            Ok(true) => result.imported += 1,
+            Ok(false) => result.skipped += 1,
+            Err(e) => {
+                // Leave it unseen so the next poll retries
+                log::warn!("Failed to import email uid {}: {}", uid, e);
+                result.failed += 1;
+                continue;
+            }
+        }
+
+        session
+            .command(&format!("UID STORE {} +FLAGS (\\Seen)", uid))
+            .await?;
+    }
+
+    let _ = session.command("LOGOUT").await;
+
+    Ok(result)
+}
+
+pub fn spawn_poller(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Email).await;
+            let config = {
+                let db = app.state::<Database>();
+                let conn = db.conn.lock().ok();
+                conn.and_then(|c| load_config(&c).ok().flatten())
+            };
+
+            let minutes = match config.filter(|c| c.enabled) {
+                Some(config) => {
+                    if let Err(e) = poll(&app.state::<Database>()).await {
+                        log::warn!("Email capture poll failed: {}", e);
+                    }
+                    config.poll_interval_minutes.unwrap_or(DEFAULT_POLL_MINUTES)
+                }
+                None => DEFAULT_POLL_MINUTES,
+            };
+
+            tokio::time::sleep(Duration::from_secs(minutes.max(1) * 60)).await;
+        }
+    });
+}
@@ -0,0 +1,143 @@
+use crate::links;
+use crate::models::Embed;
+use crate::outline;
+use crate::render;
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Embeds inside embeds are followed this deep
+const MAX_DEPTH: usize = 8;
+
+// `![[target`, an optional `#heading`, then any `|label`
+fn embed() -> &'static Regex {
+    static EMBED: OnceLock<Regex> = OnceLock::new();
+    EMBED.get_or_init(|| {
+        Regex::new(r"!\[\[([^\[\]|#]+)(?:#([^\[\]|]*))?(?:\|[^\[\]]*)?\]\]")
+            .expect("valid embed regex")
+    })
+}
+
+// From the heading up to the next heading of the same or a higher level
+fn section(html: &str, heading: &str) -> Option<String> {
+    let wanted = outline::slug(heading);
+    let headings: Vec<(usize, u8, String)> = outline::html_headings(html)
+        .into_iter()
+        .map(|(level, text, start)| (start, level, outline::slug(&text)))
+        .collect();
+
+    let index = headings.iter().position(|(_, _, s)| *s == wanted)?;
+    let (start, level, _) = headings[index];
+    let end = headings[index + 1..]
+        .iter()
+        .find(|(_, l, _)| *l <= level)
+        .map_or(html.len(), |(position, _, _)| *position);
+    Some(html[start..end].trim().to_string())
+}
+
+struct Resolver<'a> {
+    conn: &'a Connection,
+    names: HashMap<String, String>,
+    listed: HashSet<(String, String)>,
+    embeds: Vec<Embed>,
+}
+
+impl Resolver<'_> {
+    fn content(&self, note_id: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row(
+                "SELECT content FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+                params![note_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    // `path` is the note sections being shown around `html`, outermost first
+    fn collect(
+        &mut self,
+        source_note_id: &str,
+        html: &str,
+        path: &mut Vec<(String, Option<String>)>,
+    ) -> Result<(), String> {
+        for captures in embed().captures_iter(html) {
+            let name = links::target_text(&captures[1], true);
+            let heading = captures
+                .get(2)
+                .map(|h| links::target_text(h.as_str(), true))
+                .filter(|h| !h.is_empty());
+            let target = match &heading {
+                Some(heading) => format!("{}#{}", name, heading),
+                None => name.clone(),
+            };
+            if !self
+                .listed
+                .insert((source_note_id.to_string(), target.clone()))
+            {
+                continue;
+            }
+
+            let note_id = self.names.get(&links::key(&name)).cloned();
+            let part = (
+                note_id.clone().unwrap_or_default(),
+                heading.as_deref().map(outline::slug),
+            );
+            // A whole note contains all its sections, so it can't be shown
+            // inside one of them or vice versa
+            let cyclic = note_id.is_some()
+                && path.iter().any(|(id, h)| {
+                    *id == part.0 && (h.is_none() || part.1.is_none() || *h == part.1)
+                });
+
+            let mut fragment = None;
+            if let (Some(id), false) = (&note_id, cyclic) {
+                if let Some(content) = self.content(id)? {
+                    let html = render::content_to_html(&content);
+                    fragment = match &heading {
+                        Some(heading) => section(&html, heading),
+                        None => Some(html),
+                    };
+                }
+            }
+
+            self.embeds.push(Embed {
+                source_note_id: source_note_id.to_string(),
+                target,
+                note_id: note_id.clone(),
+                heading,
+                html: fragment.clone(),
+                cyclic,
+            });
+
+            if let (Some(id), Some(fragment)) = (note_id, fragment) {
+                if path.len() < MAX_DEPTH {
+                    path.push(part);
+                    self.collect(&id, &fragment, path)?;
+                    path.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Every embed the note shows, including those inside embedded fragments,
+// each listed once per note it appears in
+pub fn resolve(conn: &Connection, note_id: &str) -> Result<Vec<Embed>, String> {
+    let mut resolver = Resolver {
+        conn,
+        names: links::live_names(conn)?,
+        listed: HashSet::new(),
+        embeds: Vec::new(),
+    };
+    let Some(content) = resolver.content(note_id)? else {
+        return Err("Note not found".into());
+    };
+
+    let html = render::content_to_html(&content);
+    let mut path = vec![(note_id.to_string(), None)];
+    resolver.collect(note_id, &html, &mut path)?;
+    Ok(resolver.embeds)
+}
@@ -0,0 +1,48 @@
+use serde::Serialize;
+use specta::Type;
+
+// Error returned by commands that need to tell the frontend more than a
+// message. Serialized as `{ kind, message, ... }` so callers can branch on
+// `kind` and still show `message` directly.
+#[derive(Debug, thiserror::Error, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    #[error("{field}: {message}")]
+    Validation { field: String, message: String },
+    // The row would duplicate `existing_id`
+    #[error("{message}")]
+    Conflict { message: String, existing_id: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl AppError {
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>, existing_id: String) -> Self {
+        AppError::Conflict {
+            message: message.into(),
+            existing_id,
+        }
+    }
+}
+
+// Lets existing `.map_err(|e| e.to_string())?` call sites keep working
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal {
+            message: message.to_string(),
+        }
+    }
+}
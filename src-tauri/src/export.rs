@@ -0,0 +1,739 @@
+use crate::attachments;
+use crate::commands::{row_to_attachment, row_to_folder, row_to_note};
+use crate::db::Database;
+use crate::front_matter;
+use crate::ics;
+use crate::jobs::JobContext;
+use crate::links;
+use crate::locale;
+use crate::models::{Attachment, Event, Folder, Note};
+use crate::outline;
+use crate::queries;
+use crate::render;
+use crate::share;
+use crate::timestamps;
+use crate::today;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use rusqlite::Connection;
+use scraper::Html;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const ATTACHMENTS_FOLDER: &str = "attachments";
+
+// Strips characters that aren't allowed in file names on some platform
+pub fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect();
+    let stem = stem.trim().trim_matches('.');
+    if stem.is_empty() {
+        "Untitled".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+pub fn file_name(title: &str, extension: &str) -> String {
+    format!("{}.{}", file_stem(title), extension)
+}
+
+// ============ Vault snapshot ============
+
+// Everything an export needs, read in one go so the database lock is only
+// held while loading and not while files are written
+#[derive(Serialize)]
+pub struct Vault {
+    pub folders: Vec<Folder>,
+    pub notes: Vec<Note>,
+    pub attachments: Vec<Attachment>,
+}
+
+pub fn load_vault(conn: &Connection) -> Result<Vault, String> {
+    let folders = conn
+        .prepare(
+            "SELECT id, name, parent_id, color, icon, created_at, updated_at
+             FROM folders ORDER BY name",
+        )
+        .and_then(|mut stmt| stmt.query_map([], row_to_folder)?.collect())
+        .map_err(|e| e.to_string())?;
+
+    let notes = conn
+        .prepare(&format!(
+            "SELECT {} FROM notes WHERE deleted_at IS NULL ORDER BY created_at",
+            queries::NOTE_COLUMNS
+        ))
+        .and_then(|mut stmt| stmt.query_map([], row_to_note)?.collect())
+        .map_err(|e| e.to_string())?;
+
+    let attachments = conn
+        .prepare(
+            "SELECT a.id, a.note_id, a.kind, a.file_name, a.mime_type, a.size_bytes, a.duration_ms,
+                    a.created_at
+             FROM attachments a JOIN notes n ON n.id = a.note_id
+             WHERE n.deleted_at IS NULL ORDER BY a.created_at",
+        )
+        .and_then(|mut stmt| stmt.query_map([], row_to_attachment)?.collect())
+        .map_err(|e| e.to_string())?;
+
+    Ok(Vault {
+        folders,
+        notes,
+        attachments,
+    })
+}
+
+fn load_from(ctx: &JobContext) -> Result<(Vault, PathBuf), String> {
+    let db = ctx.app().state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok((load_vault(&conn)?, db.data_dir.clone()))
+}
+
+// Relative directory of every folder, e.g. "Work/Projects"
+pub fn folder_paths(folders: &[Folder]) -> HashMap<&str, String> {
+    let by_id: HashMap<&str, &Folder> = folders.iter().map(|f| (f.id.as_str(), f)).collect();
+
+    by_id
+        .keys()
+        .map(|id| {
+            let mut parts = Vec::new();
+            let mut seen = HashSet::new();
+            let mut current = by_id.get(id).copied();
+            // `seen` guards against parent cycles left by older versions
+            while let Some(folder) = current.filter(|f| seen.insert(f.id.as_str())) {
+                parts.push(file_stem(&folder.name));
+                current = folder
+                    .parent_id
+                    .as_deref()
+                    .and_then(|p| by_id.get(p).copied());
+            }
+            parts.reverse();
+            (*id, parts.join("/"))
+        })
+        .collect()
+}
+
+// ============ Folder-tree writers ============
+
+// Destination of a folder-tree export: a directory on disk or a zip archive.
+// Paths are relative and '/'-separated.
+trait Sink {
+    fn write(&mut self, path: &str, bytes: &[u8]) -> Result<(), String>;
+    fn copy(&mut self, path: &str, source: &Path) -> Result<(), String>;
+    // Whether writing `path` replaces a file from an earlier export
+    fn exists(&self, path: &str) -> bool;
+}
+
+struct DirSink {
+    root: PathBuf,
+}
+
+impl DirSink {
+    fn path(&self, path: &str) -> PathBuf {
+        path.split('/')
+            .fold(self.root.clone(), |dir, part| dir.join(part))
+    }
+
+    fn target(&self, path: &str) -> Result<PathBuf, String> {
+        let target = self.path(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        Ok(target)
+    }
+}
+
+impl Sink for DirSink {
+    fn write(&mut self, path: &str, bytes: &[u8]) -> Result<(), String> {
+        fs::write(self.target(path)?, bytes).map_err(|e| e.to_string())
+    }
+
+    fn copy(&mut self, path: &str, source: &Path) -> Result<(), String> {
+        fs::copy(source, self.target(path)?)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.path(path).exists()
+    }
+}
+
+struct ZipSink {
+    zip: ZipWriter<BufWriter<File>>,
+}
+
+impl Sink for ZipSink {
+    fn write(&mut self, path: &str, bytes: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip
+            .start_file(path, options)
+            .map_err(|e| e.to_string())?;
+        self.zip.write_all(bytes).map_err(|e| e.to_string())
+    }
+
+    // Attachments are mostly media that's already compressed, so store as-is
+    fn copy(&mut self, path: &str, source: &Path) -> Result<(), String> {
+        let mut file = File::open(source).map_err(|e| e.to_string())?;
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(true);
+        self.zip
+            .start_file(path, options)
+            .map_err(|e| e.to_string())?;
+        std::io::copy(&mut file, &mut self.zip).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // Every archive is written from scratch
+    fn exists(&self, _path: &str) -> bool {
+        false
+    }
+}
+
+// One Markdown file per note, laid out like the folder tree, with attachments
+// collected in a top-level folder the notes link to relatively.
+fn write_tree(
+    ctx: &JobContext,
+    vault: &Vault,
+    data_dir: &Path,
+    sink: &mut dyn Sink,
+) -> Result<(), String> {
+    let folders = folder_paths(&vault.folders);
+    let attachment_files: HashMap<&str, &str> = vault
+        .attachments
+        .iter()
+        .map(|a| (a.id.as_str(), a.file_name.as_str()))
+        .collect();
+
+    let total = vault.notes.len() + vault.attachments.len();
+    let mut used = HashSet::new();
+
+    for (index, note) in vault.notes.iter().enumerate() {
+        ctx.checkpoint()?;
+
+        let dir = note
+            .folder_id
+            .as_deref()
+            .and_then(|id| folders.get(id))
+            .filter(|dir| !dir.is_empty());
+        let prefix = dir.map(|d| format!("{}/", d)).unwrap_or_default();
+        let up = "../".repeat(prefix.matches('/').count());
+
+        let stem = file_stem(&note.title);
+        let mut path = format!("{}{}.md", prefix, stem);
+        let mut n = 2;
+        while !used.insert(path.to_lowercase()) {
+            path = format!("{}{} ({}).md", prefix, stem, n);
+            n += 1;
+        }
+
+        let html = share::replace_attachment_urls(&render::content_to_html(&note.content), |id| {
+            attachment_files
+                .get(id)
+                .map(|file| format!("{}{}/{}", up, ATTACHMENTS_FOLDER, file))
+        });
+        let markdown = format!(
+            "{}{}",
+            front_matter::header(&note.content),
+            render::content_to_markdown(&html)
+        );
+        let markdown = render::markdown_document(&note.title, &markdown);
+        let replacing = sink.exists(&path);
+        sink.write(&path, markdown.as_bytes())?;
+        if replacing {
+            ctx.updated();
+        } else {
+            ctx.created();
+        }
+
+        ctx.progress(index + 1, total, &note.title);
+    }
+
+    // Attachments with the same content share one blob; copy it once
+    let mut copied = HashSet::new();
+    for (index, attachment) in vault.attachments.iter().enumerate() {
+        ctx.checkpoint()?;
+
+        let source = attachments::attachment_path(data_dir, &attachment.file_name);
+        let first = copied.insert(attachment.file_name.as_str());
+        let path = format!("{}/{}", ATTACHMENTS_FOLDER, attachment.file_name);
+        if !first {
+            ctx.skipped();
+        } else if source.exists() {
+            let replacing = sink.exists(&path);
+            sink.copy(&path, &source)?;
+            if replacing {
+                ctx.updated();
+            } else {
+                ctx.created();
+            }
+        } else {
+            log::warn!("Skipping missing attachment blob {}", attachment.file_name);
+            ctx.item_failed(&attachment.file_name, "file is missing");
+        }
+
+        ctx.progress(vault.notes.len() + index + 1, total, &attachment.file_name);
+    }
+
+    Ok(())
+}
+
+// Writes to a sibling temp file and only moves it into place once complete,
+// so a failed or cancelled export never leaves a truncated file behind.
+pub fn write_atomically<F>(path: &Path, write: F) -> Result<(), String>
+where
+    F: FnOnce(File) -> Result<(), String>,
+{
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let file = File::create(&partial).map_err(|e| e.to_string())?;
+    let result = write(file).and_then(|_| fs::rename(&partial, path).map_err(|e| e.to_string()));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+// ============ Export jobs ============
+
+// Markdown tree plus vault.json with the raw records, zipped
+pub fn vault_archive(ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+    let (vault, data_dir) = load_from(ctx)?;
+
+    write_atomically(path, |file| {
+        let mut sink = ZipSink {
+            zip: ZipWriter::new(BufWriter::new(file)),
+        };
+        let manifest = serde_json::to_vec_pretty(&vault).map_err(|e| e.to_string())?;
+        sink.write("vault.json", &manifest)?;
+        write_tree(ctx, &vault, &data_dir, &mut sink)?;
+
+        sink.zip
+            .finish()
+            .and_then(|mut writer| writer.flush().map_err(Into::into))
+            .map_err(|e| e.to_string())
+    })?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+// Files already written stay in place if the job is cancelled midway
+pub fn markdown_folder(ctx: &JobContext, root: &Path) -> Result<Option<String>, String> {
+    let (vault, data_dir) = load_from(ctx)?;
+
+    let mut sink = DirSink {
+        root: root.to_path_buf(),
+    };
+    write_tree(ctx, &vault, &data_dir, &mut sink)?;
+
+    Ok(Some(root.to_string_lossy().into_owned()))
+}
+
+fn enex_date(time: &DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// ENML content goes in CDATA, which can't contain its own terminator
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+// Evernote export format. Attachments become base64 resources; the HTML
+// references to them are replaced by their file names since ENML only links
+// resources by MD5 hash.
+pub fn enex(ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+    let (vault, data_dir) = load_from(ctx)?;
+
+    let mut by_note: HashMap<&str, Vec<&Attachment>> = HashMap::new();
+    for attachment in &vault.attachments {
+        by_note
+            .entry(attachment.note_id.as_str())
+            .or_default()
+            .push(attachment);
+    }
+    let total = vault.notes.len();
+
+    write_atomically(path, |file| {
+        let mut out = BufWriter::new(file);
+        let io = |e: std::io::Error| e.to_string();
+
+        write!(
+            out,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE en-export SYSTEM \"http://xml.evernote.com/pub/evernote-export4.dtd\">\n\
+             <en-export export-date=\"{}\" application=\"Voyena\" version=\"1.0\">\n",
+            Utc::now().format("%Y%m%dT%H%M%SZ")
+        )
+        .map_err(io)?;
+
+        for (index, note) in vault.notes.iter().enumerate() {
+            ctx.checkpoint()?;
+
+            let note_attachments = by_note.get(note.id.as_str()).cloned().unwrap_or_default();
+            let html =
+                share::replace_attachment_urls(&render::content_to_html(&note.content), |id| {
+                    note_attachments
+                        .iter()
+                        .find(|a| a.id == id)
+                        .map(|a| a.file_name.clone())
+                });
+            let enml = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\
+                 <!DOCTYPE en-note SYSTEM \"http://xml.evernote.com/pub/enml2.dtd\">\
+                 <en-note>{}</en-note>",
+                html
+            );
+
+            write!(
+                out,
+                "<note><title>{}</title><content>{}</content><created>{}</created><updated>{}</updated>",
+                render::escape_html(&note.title),
+                cdata(&enml),
+                enex_date(&note.created_at),
+                enex_date(&note.updated_at)
+            )
+            .map_err(io)?;
+            for tag in &note.tags {
+                write!(out, "<tag>{}</tag>", render::escape_html(tag)).map_err(io)?;
+            }
+
+            for attachment in note_attachments {
+                let source = attachments::attachment_path(&data_dir, &attachment.file_name);
+                let Ok(bytes) = fs::read(&source) else {
+                    log::warn!("Skipping missing attachment blob {}", attachment.file_name);
+                    ctx.item_failed(&attachment.file_name, "file is missing");
+                    continue;
+                };
+                write!(
+                    out,
+                    "<resource><data encoding=\"base64\">{}</data><mime>{}</mime>\
+                     <resource-attributes><file-name>{}</file-name></resource-attributes></resource>",
+                    BASE64.encode(bytes),
+                    render::escape_html(&attachment.mime_type),
+                    render::escape_html(&attachment.file_name)
+                )
+                .map_err(io)?;
+            }
+
+            writeln!(out, "</note>").map_err(io)?;
+            ctx.created();
+            ctx.progress(index + 1, total, &note.title);
+        }
+
+        writeln!(out, "</en-export>").map_err(io)?;
+        out.flush().map_err(io)
+    })?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+fn day(field: &str, value: Option<&str>) -> Result<Option<NaiveDate>, String> {
+    value
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+        .transpose()
+        .map_err(|_| format!("{} must be a YYYY-MM-DD date", field))
+}
+
+// Scheduled events as one calendar file, optionally only those starting
+// between `start` and `end` (YYYY-MM-DD, inclusive)
+pub fn events_ics(
+    ctx: &JobContext,
+    path: &Path,
+    start: Option<&str>,
+    end: Option<&str>,
+) -> Result<Option<String>, String> {
+    let from = day("start", start)?.unwrap_or(NaiveDate::MIN);
+    let to = day("end", end)?.unwrap_or(NaiveDate::MAX);
+    let (events, week_start) = {
+        let db = ctx.app().state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let events = today::query_events(&conn, "start_time IS NOT NULL", &[])?;
+        (events, locale::load(&conn).week_start)
+    };
+    let events: Vec<Event> = events
+        .into_iter()
+        .filter(|event| {
+            let day = event.start_time.as_ref().map(timestamps::local_date);
+            day.is_some_and(|d| d >= from && d <= to)
+        })
+        .collect();
+
+    let total = events.len();
+    ctx.progress(0, total, "Writing calendar");
+    ctx.checkpoint()?;
+    write_atomically(path, |mut file| {
+        file.write_all(ics::calendar(&events, week_start).as_bytes())
+            .map_err(|e| e.to_string())
+    })?;
+    for _ in &events {
+        ctx.created();
+    }
+    ctx.progress(total, total, "Writing calendar");
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+// ============ Folder website ============
+
+// `[[target#heading|label]]`, embeds included
+fn site_link() -> &'static Regex {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    LINK.get_or_init(|| {
+        Regex::new(r"!?\[\[([^\[\]|#]+)(?:#([^\[\]|]*))?(?:\|([^\[\]]*))?\]\]")
+            .expect("valid wikilink regex")
+    })
+}
+
+fn heading_open() -> &'static Regex {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    HEADING.get_or_init(|| {
+        Regex::new(r"(?is)<h([1-6])((?:\s[^>]*)?)>(.*?)</h[1-6]>").expect("valid heading regex")
+    })
+}
+
+// Heading ids from their text, so `[[Page#Heading]]` links land on them
+fn anchor_headings(html: &str) -> String {
+    heading_open()
+        .replace_all(html, |c: &regex::Captures| {
+            if c[2].contains("id=") {
+                return c[0].to_string();
+            }
+            let text: String = Html::parse_fragment(&c[3]).root_element().text().collect();
+            format!(
+                "<h{level}{attrs} id=\"{id}\">{inner}</h{level}>",
+                level = &c[1],
+                attrs = &c[2],
+                id = outline::slug(&text),
+                inner = &c[3]
+            )
+        })
+        .into_owned()
+}
+
+// Percent-encodes a relative '/'-separated path for an href
+fn href(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+// Wikilinks to pages in the site become relative links; links to notes
+// outside it keep their text only
+fn link_pages(html: &str, pages: &HashMap<String, String>, up: &str) -> String {
+    site_link()
+        .replace_all(html, |c: &regex::Captures| {
+            let heading = c
+                .get(2)
+                .map(|h| h.as_str().trim())
+                .filter(|h| !h.is_empty());
+            let label = match (c.get(3), heading) {
+                (Some(label), _) => label.as_str().trim().to_string(),
+                (None, Some(heading)) => format!("{} › {}", c[1].trim(), heading),
+                (None, None) => c[1].trim().to_string(),
+            };
+            let target = links::key(&links::target_text(&c[1], true));
+            match pages.get(&target) {
+                Some(page) => {
+                    let anchor = heading
+                        .map(|h| format!("#{}", outline::slug(&links::target_text(h, true))))
+                        .unwrap_or_default();
+                    format!("<a href=\"{}{}{}\">{}</a>", up, href(page), anchor, label)
+                }
+                None => format!("<span class=\"missing-link\">{}</span>", label),
+            }
+        })
+        .into_owned()
+}
+
+// Every note in the folder and its subfolders as an HTML page, laid out like
+// the folder tree, with an index.html listing them
+pub fn folder_site(
+    ctx: &JobContext,
+    folder_id: &str,
+    root: &Path,
+) -> Result<Option<String>, String> {
+    let (vault, data_dir) = load_from(ctx)?;
+    let Some(folder) = vault.folders.iter().find(|f| f.id == folder_id) else {
+        return Err("Folder not found".into());
+    };
+
+    // Directories relative to the exported folder
+    let paths = folder_paths(&vault.folders);
+    let base = paths.get(folder_id).cloned().unwrap_or_default();
+    let dirs: HashMap<&str, String> = paths
+        .iter()
+        .filter_map(|(id, path)| {
+            if *id == folder_id {
+                Some((*id, String::new()))
+            } else {
+                path.strip_prefix(&format!("{}/", base))
+                    .map(|rest| (*id, rest.to_string()))
+            }
+        })
+        .collect();
+
+    let notes: Vec<(&Note, String)> = vault
+        .notes
+        .iter()
+        .filter_map(|note| {
+            let dir = dirs.get(note.folder_id.as_deref()?)?;
+            Some((note, dir.clone()))
+        })
+        .collect();
+
+    // Page of every note, and every name a wikilink can reach it by
+    let mut used = HashSet::from(["index.html".to_string()]);
+    let mut pages: Vec<(&Note, String)> = Vec::new();
+    for (note, dir) in &notes {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir)
+        };
+        let stem = file_stem(&note.title);
+        let mut path = format!("{}{}.html", prefix, stem);
+        let mut n = 2;
+        while !used.insert(path.to_lowercase()) {
+            path = format!("{}{} ({}).html", prefix, stem, n);
+            n += 1;
+        }
+        pages.push((note, path));
+    }
+    let mut by_name: HashMap<String, String> = HashMap::new();
+    for (note, path) in &pages {
+        for alias in &note.aliases {
+            by_name.insert(links::key(alias), path.clone());
+        }
+    }
+    for (note, path) in &pages {
+        by_name.insert(links::key(&note.title), path.clone());
+        by_name.insert(links::key(&note.id), path.clone());
+    }
+
+    let exported: HashSet<&str> = pages.iter().map(|(note, _)| note.id.as_str()).collect();
+    let site_attachments: Vec<&Attachment> = vault
+        .attachments
+        .iter()
+        .filter(|a| exported.contains(a.note_id.as_str()))
+        .collect();
+    let attachment_files: HashMap<&str, &str> = site_attachments
+        .iter()
+        .map(|a| (a.id.as_str(), a.file_name.as_str()))
+        .collect();
+
+    let mut sink = DirSink {
+        root: root.to_path_buf(),
+    };
+    let total = pages.len() + site_attachments.len() + 1;
+
+    for (index, (note, path)) in pages.iter().enumerate() {
+        ctx.checkpoint()?;
+
+        let up = "../".repeat(path.matches('/').count());
+        let html = share::replace_attachment_urls(&render::content_to_html(&note.content), |id| {
+            attachment_files
+                .get(id)
+                .map(|file| format!("{}{}/{}", up, ATTACHMENTS_FOLDER, file))
+        });
+        let body = format!(
+            "<nav><a href=\"{}index.html\">{}</a></nav>\n{}",
+            up,
+            render::escape_html(&folder.name),
+            anchor_headings(&link_pages(&html, &by_name, &up))
+        );
+        let replacing = sink.exists(path);
+        sink.write(path, render::html_document(&note.title, &body).as_bytes())?;
+        if replacing {
+            ctx.updated();
+        } else {
+            ctx.created();
+        }
+
+        ctx.progress(index + 1, total, &note.title);
+    }
+
+    let mut copied = HashSet::new();
+    for (index, attachment) in site_attachments.iter().enumerate() {
+        ctx.checkpoint()?;
+
+        let source = attachments::attachment_path(&data_dir, &attachment.file_name);
+        let path = format!("{}/{}", ATTACHMENTS_FOLDER, attachment.file_name);
+        if !copied.insert(attachment.file_name.as_str()) {
+            ctx.skipped();
+        } else if source.exists() {
+            let replacing = sink.exists(&path);
+            sink.copy(&path, &source)?;
+            if replacing {
+                ctx.updated();
+            } else {
+                ctx.created();
+            }
+        } else {
+            ctx.item_failed(&attachment.file_name, "file is missing");
+        }
+
+        ctx.progress(pages.len() + index + 1, total, &attachment.file_name);
+    }
+
+    // Index grouped by subfolder, the exported folder's own notes first
+    let mut sections: Vec<(&str, Vec<&(&Note, String)>)> = Vec::new();
+    let mut ordered: Vec<&(&Note, String)> = pages.iter().collect();
+    ordered.sort_by(|a, b| {
+        let dir = |path: &str| {
+            path.rsplit_once('/')
+                .map_or("", |(dir, _)| dir)
+                .to_lowercase()
+        };
+        (dir(&a.1), a.0.title.to_lowercase()).cmp(&(dir(&b.1), b.0.title.to_lowercase()))
+    });
+    for page in ordered {
+        let dir = page.1.rsplit_once('/').map_or("", |(dir, _)| dir);
+        match sections.last_mut() {
+            Some((current, list)) if *current == dir => list.push(page),
+            _ => sections.push((dir, vec![page])),
+        }
+    }
+    let mut index_body = String::new();
+    for (dir, list) in sections {
+        if !dir.is_empty() {
+            index_body.push_str(&format!("<h2>{}</h2>\n", render::escape_html(dir)));
+        }
+        index_body.push_str("<ul>\n");
+        for (note, path) in list {
+            index_body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                href(path),
+                render::escape_html(&note.title)
+            ));
+        }
+        index_body.push_str("</ul>\n");
+    }
+    sink.write(
+        "index.html",
+        render::html_document(&folder.name, &index_body).as_bytes(),
+    )?;
+    ctx.progress(total, total, "index.html");
+
+    Ok(Some(root.join("index.html").to_string_lossy().into_owned()))
+}
@@ -0,0 +1,150 @@
+use crate::background;
+use crate::db::Database;
+use crate::models::BackgroundSubsystem;
+use crate::timestamps;
+use chrono::Utc;
+use rusqlite::params;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+const REFRESH_INTERVAL_MINUTES: u64 = 30;
+
+pub struct FetchedFeed {
+    pub title: Option<String>,
+    pub site_url: Option<String>,
+    pub items: Vec<FetchedItem>,
+}
+
+pub struct FetchedItem {
+    pub guid: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub summary: Option<String>,
+    pub content: Option<String>,
+    pub published_at: Option<String>,
+}
+
+// Downloads and parses an RSS or Atom document (feed-rs detects the format).
+pub async fn fetch(url: &str) -> Result<FetchedFeed, String> {
+    let bytes = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let feed = feed_rs::parser::parse(bytes.as_ref()).map_err(|e| e.to_string())?;
+
+    let items = feed
+        .entries
+        .into_iter()
+        .map(|entry| FetchedItem {
+            guid: entry.id,
+            title: entry
+                .title
+                .map(|t| t.content)
+                .unwrap_or_else(|| "Untitled".to_string()),
+            url: entry.links.first().map(|l| l.href.clone()),
+            summary: entry.summary.map(|t| t.content),
+            content: entry.content.and_then(|c| c.body),
+            published_at: entry
+                .published
+                .or(entry.updated)
+                .map(|d| timestamps::format(&d)),
+        })
+        .collect();
+
+    Ok(FetchedFeed {
+        title: feed.title.map(|t| t.content),
+        site_url: feed.links.first().map(|l| l.href.clone()),
+        items,
+    })
+}
+
+// Fetches one feed and stores entries not seen before. Returns the number of new items.
+pub async fn refresh_feed(db: &Database, feed_id: &str) -> Result<usize, String> {
+    let url: String = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT url FROM feeds WHERE id = ?1",
+            params![feed_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?
+    };
+
+    let fetched = fetch(&url).await?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+    let mut added = 0;
+
+    for item in fetched.items {
+        added += conn
+            .execute(
+                "INSERT OR IGNORE INTO feed_items (id, feed_id, guid, title, url, summary, content,
+                                                   published_at, is_read, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0, ?9)",
+                params![
+                    format!("feeditem_{}", Uuid::new_v4()),
+                    feed_id,
+                    item.guid,
+                    item.title,
+                    item.url,
+                    item.summary,
+                    item.content,
+                    item.published_at,
+                    now,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    conn.execute(
+        "UPDATE feeds SET title = COALESCE(?1, title), site_url = COALESCE(?2, site_url),
+                          last_fetched_at = ?3
+         WHERE id = ?4",
+        params![fetched.title, fetched.site_url, now, feed_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(added)
+}
+
+pub async fn refresh_all(db: &Database) -> Result<usize, String> {
+    let feed_ids: Vec<String> = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT id FROM feeds")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let mut added = 0;
+    for feed_id in feed_ids {
+        // One broken feed shouldn't stop the others
+        match refresh_feed(db, &feed_id).await {
+            Ok(count) => added += count,
+            Err(e) => log::warn!("Failed to refresh feed {}: {}", feed_id, e),
+        }
+    }
+
+    Ok(added)
+}
+
+pub fn spawn_fetcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Feeds).await;
+            if let Err(e) = refresh_all(&app.state::<Database>()).await {
+                log::warn!("Feed refresh failed: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(REFRESH_INTERVAL_MINUTES * 60)).await;
+        }
+    });
+}
@@ -0,0 +1,814 @@
+use crate::attachments;
+use crate::background;
+use crate::commands::{insert_note, row_to_note};
+use crate::db::Database;
+use crate::export;
+use crate::links;
+use crate::models::{BackgroundSubsystem, Note, NoteCreate, WatchedFolder};
+use crate::queries;
+use crate::render;
+use crate::search_index;
+use crate::sync;
+use crate::timestamps;
+use crate::trash;
+use chrono::{DateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+// In-app edits are written out on this schedule; external edits as soon as
+// the watcher reports them
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// Lets an editor finish saving before the file is read
+const SETTLE: Duration = Duration::from_millis(500);
+
+pub fn list(conn: &Connection) -> Result<Vec<WatchedFolder>, String> {
+    let mut stmt = conn
+        .prepare("SELECT folder_id, directory, created_at FROM watched_folders ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(WatchedFolder {
+                folder_id: row.get(0)?,
+                directory: row.get(1)?,
+                created_at: timestamps::get(row, 2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// Pointing a folder at another directory starts its sync over
+pub fn add(
+    conn: &Connection,
+    folder_id: &str,
+    directory: &str,
+    now: &DateTime<Utc>,
+) -> Result<WatchedFolder, String> {
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT directory FROM watched_folders WHERE folder_id = ?1",
+            params![folder_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if current.as_deref() != Some(directory) {
+        remove(conn, folder_id)?;
+    }
+
+    conn.execute(
+        "INSERT OR REPLACE INTO watched_folders (folder_id, directory, created_at)
+         VALUES (?1, ?2, ?3)",
+        params![folder_id, directory, timestamps::format(now)],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(WatchedFolder {
+        folder_id: folder_id.to_string(),
+        directory: directory.to_string(),
+        created_at: *now,
+    })
+}
+
+// Notes and files are both left as they are
+pub fn remove(conn: &Connection, folder_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM watched_files WHERE folder_id = ?1",
+        params![folder_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM watched_folders WHERE folder_id = ?1",
+        params![folder_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ============ Sync ============
+
+// A file counts as edited when its content hash changes; the mtime only says
+// whether it's worth reading
+struct Record {
+    path: String,
+    note_updated_at: String,
+    file_modified_at: i64,
+    file_hash: String,
+}
+
+struct LiveNote {
+    id: String,
+    title: String,
+    content: String,
+    updated_at: String,
+}
+
+struct DiskFile {
+    name: String,
+    modified_at: i64,
+}
+
+enum Step {
+    // The note's Markdown goes to `path`. `replaces` is the file it takes
+    // the place of and the hash that file had when planned.
+    Write {
+        note_id: String,
+        markdown: String,
+        note_updated_at: String,
+        path: String,
+        replaces: Option<(String, String)>,
+    },
+    // `path` holds the note as of `note_updated_at`
+    Written {
+        note_id: String,
+        note_updated_at: String,
+        path: String,
+        modified_at: i64,
+        hash: String,
+    },
+    // The file's text goes into the note, or a new one when `note_id` is
+    // None. `seen_updated_at` is the note version the decision was made on.
+    Read {
+        note_id: Option<String>,
+        seen_updated_at: Option<String>,
+        title: Option<String>,
+        path: String,
+        text: String,
+        modified_at: i64,
+    },
+    // Both the note and its file were edited since the last sync. The
+    // file's text becomes a conflict copy and the note is written over it
+    // on the next pass.
+    Conflict {
+        note_id: String,
+        seen_updated_at: String,
+        synced_updated_at: String,
+        path: String,
+        text: String,
+        modified_at: i64,
+    },
+    // The file was deleted outside the app
+    Trash {
+        note_id: String,
+        seen_updated_at: String,
+    },
+    // The note left the folder; `path` is removed with it when given
+    Forget {
+        note_id: String,
+        path: Option<String>,
+    },
+}
+
+fn modified_at(path: &Path) -> Result<i64, String> {
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| e.to_string())?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64))
+}
+
+fn stem(path: &str) -> String {
+    path.strip_suffix(".md")
+        .or_else(|| path.strip_suffix(".MD"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+// Whether `path` is the file `unique_path` would give a note titled `stem`
+fn named_for(path: &str, stem: &str) -> bool {
+    let (path, stem) = (path.to_lowercase(), stem.to_lowercase());
+    path == format!("{}.md", stem)
+        || (path.starts_with(&format!("{} (", stem)) && path.ends_with(").md"))
+}
+
+fn unique_path(taken: &mut HashSet<String>, title: &str) -> String {
+    let stem = export::file_stem(title);
+    let mut path = format!("{}.md", stem);
+    let mut n = 2;
+    while !taken.insert(path.to_lowercase()) {
+        path = format!("{} ({}).md", stem, n);
+        n += 1;
+    }
+    path
+}
+
+// Markdown files directly in the directory, by lowercased name
+fn scan(directory: &Path) -> Result<HashMap<String, DiskFile>, String> {
+    let mut files = HashMap::new();
+    for entry in fs::read_dir(directory).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+        if !is_file || name.starts_with('.') || !name.to_lowercase().ends_with(".md") {
+            continue;
+        }
+        files.insert(
+            name.to_lowercase(),
+            DiskFile {
+                modified_at: modified_at(&entry.path())?,
+                name,
+            },
+        );
+    }
+    Ok(files)
+}
+
+fn read(directory: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(directory.join(name))
+        .map_err(|e| log::warn!("Failed to read {}: {}", name, e))
+        .ok()
+}
+
+// Steps for a note whose file is still where it was last synced
+fn tracked(
+    directory: &Path,
+    note: &LiveNote,
+    markdown: String,
+    record: &Record,
+    file: DiskFile,
+    taken: &mut HashSet<String>,
+) -> Option<Step> {
+    let touched = file.modified_at != record.file_modified_at;
+    // Rows from before hashes were kept fall back to the mtime
+    let (text, edited) = if touched || record.file_hash.is_empty() {
+        let text = read(directory, &file.name)?;
+        let edited = if record.file_hash.is_empty() {
+            touched
+        } else {
+            attachments::content_hash(text.as_bytes()) != record.file_hash
+        };
+        (Some(text), edited)
+    } else {
+        (None, false)
+    };
+    let note_changed = note.updated_at != record.note_updated_at;
+
+    match (edited, note_changed, text) {
+        (true, true, Some(text)) => Some(Step::Conflict {
+            note_id: note.id.clone(),
+            seen_updated_at: note.updated_at.clone(),
+            synced_updated_at: record.note_updated_at.clone(),
+            path: file.name,
+            text,
+            modified_at: file.modified_at,
+        }),
+        (true, false, Some(text)) => Some(Step::Read {
+            note_id: Some(note.id.clone()),
+            seen_updated_at: Some(note.updated_at.clone()),
+            title: None,
+            path: file.name,
+            text,
+            modified_at: file.modified_at,
+        }),
+        (false, true, text) => {
+            // Renamed in the app
+            let path = if named_for(&file.name, &export::file_stem(&note.title)) {
+                file.name.clone()
+            } else {
+                unique_path(taken, &note.title)
+            };
+            let hash = match text {
+                Some(text) => attachments::content_hash(text.as_bytes()),
+                None => record.file_hash.clone(),
+            };
+            Some(Step::Write {
+                note_id: note.id.clone(),
+                markdown,
+                note_updated_at: note.updated_at.clone(),
+                path,
+                replaces: Some((file.name, hash)),
+            })
+        }
+        // Same content under a new mtime, e.g. touched or copied back
+        (false, false, Some(text)) => Some(Step::Written {
+            note_id: note.id.clone(),
+            note_updated_at: record.note_updated_at.clone(),
+            path: file.name,
+            modified_at: file.modified_at,
+            hash: attachments::content_hash(text.as_bytes()),
+        }),
+        _ => None,
+    }
+}
+
+fn plan(
+    directory: &Path,
+    records: &HashMap<String, Record>,
+    notes: &[LiveNote],
+) -> Result<Vec<Step>, String> {
+    let mut files = scan(directory)?;
+    let mut taken: HashSet<String> = files.keys().cloned().collect();
+    taken.extend(records.values().map(|r| r.path.to_lowercase()));
+    let mut steps = Vec::new();
+
+    let live: HashSet<&str> = notes.iter().map(|n| n.id.as_str()).collect();
+    for (note_id, record) in records.iter().filter(|(id, _)| !live.contains(id.as_str())) {
+        // A file edited since is kept, and read in below as a new note
+        let key = record.path.to_lowercase();
+        let unchanged = files
+            .get(&key)
+            .is_some_and(|f| f.modified_at == record.file_modified_at);
+        if unchanged {
+            files.remove(&key);
+        }
+        steps.push(Step::Forget {
+            note_id: note_id.clone(),
+            path: unchanged.then(|| record.path.clone()),
+        });
+    }
+
+    let mut missing = Vec::new();
+    for note in notes {
+        let markdown = render::content_to_markdown(&note.content);
+        let Some(record) = records.get(&note.id) else {
+            // The note is new here. A file of the same name and text, e.g.
+            // from an earlier sync of the directory, is taken as its own.
+            let key = format!("{}.md", export::file_stem(&note.title)).to_lowercase();
+            let same = files.get(&key).is_some_and(|file| {
+                read(directory, &file.name).is_some_and(|text| text == markdown)
+            });
+            if let Some(file) = same.then(|| files.remove(&key)).flatten() {
+                steps.push(Step::Written {
+                    note_id: note.id.clone(),
+                    note_updated_at: note.updated_at.clone(),
+                    path: file.name,
+                    modified_at: file.modified_at,
+                    hash: attachments::content_hash(markdown.as_bytes()),
+                });
+            } else {
+                steps.push(Step::Write {
+                    note_id: note.id.clone(),
+                    path: unique_path(&mut taken, &note.title),
+                    markdown,
+                    note_updated_at: note.updated_at.clone(),
+                    replaces: None,
+                });
+            }
+            continue;
+        };
+
+        match files.remove(&record.path.to_lowercase()) {
+            Some(file) => {
+                steps.extend(tracked(directory, note, markdown, record, file, &mut taken));
+            }
+            // Deleted outside the app, but the note has changed since
+            None if note.updated_at != record.note_updated_at => steps.push(Step::Write {
+                note_id: note.id.clone(),
+                markdown,
+                note_updated_at: note.updated_at.clone(),
+                path: record.path.clone(),
+                replaces: None,
+            }),
+            None => missing.push((note, markdown)),
+        }
+    }
+
+    let mut untracked: Vec<(DiskFile, String)> = files
+        .into_values()
+        .filter_map(|file| read(directory, &file.name).map(|text| (file, text)))
+        .collect();
+
+    // A file that went missing while another with its exact text appeared
+    // was renamed outside the app
+    for (note, markdown) in missing {
+        match untracked.iter().position(|(_, text)| *text == markdown) {
+            Some(index) => {
+                let (file, text) = untracked.swap_remove(index);
+                steps.push(Step::Read {
+                    note_id: Some(note.id.clone()),
+                    seen_updated_at: Some(note.updated_at.clone()),
+                    title: Some(stem(&file.name)),
+                    path: file.name,
+                    text,
+                    modified_at: file.modified_at,
+                });
+            }
+            None => steps.push(Step::Trash {
+                note_id: note.id.clone(),
+                seen_updated_at: note.updated_at.clone(),
+            }),
+        }
+    }
+
+    for (file, text) in untracked {
+        steps.push(Step::Read {
+            note_id: None,
+            seen_updated_at: None,
+            title: Some(stem(&file.name)),
+            path: file.name,
+            text,
+            modified_at: file.modified_at,
+        });
+    }
+
+    Ok(steps)
+}
+
+// Whether the file still has the content it was planned on: the hash given,
+// or no file at all for None
+fn still_as_planned(path: &Path, hash: Option<&str>) -> Result<bool, String> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(hash == Some(attachments::content_hash(&bytes).as_str())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(hash.is_none()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn remove_file(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+// The file side of a step, done without the database lock. A file edited
+// since the plan was made is never overwritten or removed; the step fails
+// and the next pass sees the edit.
+fn touch_disk(directory: &Path, step: Step) -> Result<Step, String> {
+    match step {
+        Step::Write {
+            note_id,
+            markdown,
+            note_updated_at,
+            path,
+            replaces,
+        } => {
+            let target = directory.join(&path);
+            let replaced = replaces.as_ref().filter(|(old, _)| *old == path);
+            if !still_as_planned(&target, replaced.map(|(_, hash)| hash.as_str()))? {
+                return Err(format!("{} changed on disk", path));
+            }
+            if let Some((old, hash)) = replaces.as_ref().filter(|(old, _)| *old != path) {
+                if !still_as_planned(&directory.join(old), Some(hash))? {
+                    return Err(format!("{} changed on disk", old));
+                }
+            }
+
+            export::write_atomically(&target, |mut file| {
+                file.write_all(markdown.as_bytes())
+                    .map_err(|e| e.to_string())
+            })?;
+            if let Some((old, _)) = replaces.filter(|(old, _)| *old != path) {
+                remove_file(&directory.join(old))?;
+            }
+            Ok(Step::Written {
+                note_id,
+                note_updated_at,
+                modified_at: modified_at(&target)?,
+                hash: attachments::content_hash(markdown.as_bytes()),
+                path,
+            })
+        }
+        Step::Forget {
+            note_id,
+            path: Some(path),
+        } => {
+            remove_file(&directory.join(&path))?;
+            Ok(Step::Forget {
+                note_id,
+                path: Some(path),
+            })
+        }
+        step => Ok(step),
+    }
+}
+
+fn record(
+    conn: &Connection,
+    folder_id: &str,
+    note_id: &str,
+    path: &str,
+    note_updated_at: &str,
+    modified_at: i64,
+    hash: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO watched_files
+             (note_id, folder_id, path, note_updated_at, file_modified_at, file_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![note_id, folder_id, path, note_updated_at, modified_at, hash],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn unrecord(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM watched_files WHERE note_id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Whether the note is still the version a step was planned on; if not the
+// step is dropped and the next pass looks again
+fn unchanged_since(conn: &Connection, note_id: &str, seen: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM notes WHERE id = ?1 AND updated_at = ?2 AND deleted_at IS NULL",
+        params![note_id, seen],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|e| e.to_string())
+}
+
+// The database side of a step. Run inside a transaction.
+fn apply(conn: &Connection, folder_id: &str, step: Step, now: &str) -> Result<(), String> {
+    match step {
+        Step::Written {
+            note_id,
+            note_updated_at,
+            path,
+            modified_at,
+            hash,
+        } => record(
+            conn,
+            folder_id,
+            &note_id,
+            &path,
+            &note_updated_at,
+            modified_at,
+            &hash,
+        ),
+        Step::Read {
+            note_id: None,
+            title,
+            path,
+            text,
+            modified_at,
+            ..
+        } => {
+            let hash = attachments::content_hash(text.as_bytes());
+            let note = insert_note(
+                conn,
+                NoteCreate {
+                    title,
+                    content: Some(text),
+                    folder_id: Some(folder_id.to_string()),
+                    tags: None,
+                    aliases: None,
+                },
+            )?;
+            record(
+                conn,
+                folder_id,
+                &note.id,
+                &path,
+                &timestamps::format(&note.updated_at),
+                modified_at,
+                &hash,
+            )
+        }
+        Step::Read {
+            note_id: Some(note_id),
+            seen_updated_at,
+            title,
+            path,
+            text,
+            modified_at,
+        } => {
+            if !unchanged_since(
+                conn,
+                &note_id,
+                seen_updated_at.as_deref().unwrap_or_default(),
+            )? {
+                return Ok(());
+            }
+            if let Some(title) = title {
+                let old_title: String = conn
+                    .query_row(
+                        "SELECT title FROM notes WHERE id = ?1",
+                        params![note_id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| e.to_string())?;
+                links::record_rename(conn, &note_id, &old_title, now)?;
+                conn.execute(
+                    "UPDATE notes SET title = ?1 WHERE id = ?2",
+                    params![title, note_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            links::save_content(conn, &note_id, &text, now)?;
+            let hash = attachments::content_hash(text.as_bytes());
+            record(conn, folder_id, &note_id, &path, now, modified_at, &hash)
+        }
+        Step::Conflict {
+            note_id,
+            seen_updated_at,
+            synced_updated_at,
+            path,
+            text,
+            modified_at,
+        } => {
+            if !unchanged_since(conn, &note_id, &seen_updated_at)? {
+                return Ok(());
+            }
+            let note = conn
+                .query_row(
+                    &format!("SELECT {} FROM notes WHERE id = ?1", queries::NOTE_COLUMNS),
+                    params![note_id],
+                    row_to_note,
+                )
+                .map_err(|e| e.to_string())?;
+            let file_version = Note {
+                content: text.clone(),
+                ..note
+            };
+            sync::note_copy(conn, &file_version, &path, &timestamps::now())?;
+
+            // The file is now in step with the note as it was, so the next
+            // pass writes the note's edits out over it
+            let hash = attachments::content_hash(text.as_bytes());
+            record(
+                conn,
+                folder_id,
+                &note_id,
+                &path,
+                &synced_updated_at,
+                modified_at,
+                &hash,
+            )
+        }
+        Step::Trash {
+            note_id,
+            seen_updated_at,
+        } => {
+            if !unchanged_since(conn, &note_id, &seen_updated_at)? {
+                return Ok(());
+            }
+            trash::trash_note(conn, &note_id, now)?;
+            search_index::note_removed(conn, &note_id);
+            unrecord(conn, &note_id)
+        }
+        Step::Forget { note_id, .. } => unrecord(conn, &note_id),
+        // Turned into Written before this point
+        Step::Write { .. } => Ok(()),
+    }
+}
+
+fn load(
+    conn: &Connection,
+    folder_id: &str,
+) -> Result<(HashMap<String, Record>, Vec<LiveNote>), String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT note_id, path, note_updated_at, file_modified_at, file_hash
+             FROM watched_files WHERE folder_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let records = stmt
+        .query_map(params![folder_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Record {
+                    path: row.get(1)?,
+                    note_updated_at: row.get(2)?,
+                    file_modified_at: row.get(3)?,
+                    file_hash: row.get(4)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, content, updated_at FROM notes
+             WHERE folder_id = ?1 AND deleted_at IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let notes = stmt
+        .query_map(params![folder_id], |row| {
+            Ok(LiveNote {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok((records, notes))
+}
+
+fn sync(app: &AppHandle, watched: &WatchedFolder) -> Result<(), String> {
+    let directory = Path::new(&watched.directory);
+    // A missing directory, e.g. on an unplugged drive, mustn't read as every
+    // file having been deleted
+    if !directory.is_dir() {
+        return Ok(());
+    }
+
+    let db = app.state::<Database>();
+    let (records, notes) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM folders WHERE id = ?1)",
+                params![watched.folder_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        // The folder was deleted in the app; the files stay
+        if !exists {
+            return remove(&conn, &watched.folder_id);
+        }
+        load(&conn, &watched.folder_id)?
+    };
+
+    let steps: Vec<Step> = plan(directory, &records, &notes)?
+        .into_iter()
+        .filter_map(|step| {
+            touch_disk(directory, step)
+                .map_err(|e| log::warn!("Failed to sync {}: {}", watched.directory, e))
+                .ok()
+        })
+        .collect();
+    if steps.is_empty() {
+        return Ok(());
+    }
+
+    let now = timestamps::format(&Utc::now());
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for step in steps {
+        apply(&tx, &watched.folder_id, step, &now)?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn watch(directories: &[String], changed: &Arc<Notify>) -> Option<RecommendedWatcher> {
+    if directories.is_empty() {
+        return None;
+    }
+    let changed = changed.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if event.is_ok() {
+            changed.notify_one();
+        }
+    })
+    .map_err(|e| log::warn!("Failed to start the folder watcher: {}", e))
+    .ok()?;
+
+    // A directory that can't be watched is still synced on the poll
+    for directory in directories {
+        if let Err(e) = watcher.watch(Path::new(directory), RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch {}: {}", directory, e);
+        }
+    }
+    Some(watcher)
+}
+
+pub fn spawn_service(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let changed = Arc::new(Notify::new());
+        let mut watching: Vec<String> = Vec::new();
+        let mut _watcher: Option<RecommendedWatcher> = None;
+
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::WatchedFolders).await;
+            let folders = {
+                let db = app.state::<Database>();
+                let conn = db.conn.lock();
+                conn.map_err(|e| e.to_string()).and_then(|conn| list(&conn))
+            };
+            let folders = folders.unwrap_or_else(|e| {
+                log::warn!("Failed to load watched folders: {}", e);
+                Vec::new()
+            });
+
+            let directories: Vec<String> = folders.iter().map(|f| f.directory.clone()).collect();
+            if directories != watching {
+                _watcher = watch(&directories, &changed);
+                watching = directories;
+            }
+
+            for watched in &folders {
+                if let Err(e) = sync(&app, watched) {
+                    log::warn!("Failed to sync {}: {}", watched.directory, e);
+                }
+            }
+
+            tokio::select! {
+                _ = changed.notified() => tokio::time::sleep(SETTLE).await,
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+}
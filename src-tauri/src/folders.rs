@@ -0,0 +1,165 @@
+use crate::commands::{insert_folder, row_to_folder};
+use crate::metadata;
+use crate::models::{EntityKind, Folder, FolderCreate};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+
+const SELECT_FOLDER: &str =
+    "SELECT id, name, parent_id, color, icon, created_at, updated_at FROM folders";
+
+// "Work/Projects/Alpha" as folder names; blank segments are skipped
+pub fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Root first, ending with the folder itself. Empty if the folder doesn't
+// exist; stops early at a parent cycle left by older versions.
+pub fn ancestors(conn: &Connection, id: &str) -> Result<Vec<Folder>, String> {
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = Some(id.to_string());
+
+    while let Some(id) = next.filter(|id| seen.insert(id.clone())) {
+        let folder = conn
+            .query_row(
+                &format!("{} WHERE id = ?1", SELECT_FOLDER),
+                params![id],
+                row_to_folder,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(folder) = folder else {
+            break;
+        };
+        next = folder.parent_id.clone();
+        chain.push(folder);
+    }
+
+    chain.reverse();
+    Ok(chain)
+}
+
+// Names match case-insensitively; the oldest wins if siblings share a name
+pub fn child(
+    conn: &Connection,
+    parent_id: Option<&str>,
+    name: &str,
+) -> Result<Option<Folder>, String> {
+    conn.query_row(
+        &format!(
+            "{} WHERE parent_id IS ?1 AND name = ?2 COLLATE NOCASE
+             ORDER BY created_at LIMIT 1",
+            SELECT_FOLDER
+        ),
+        params![parent_id, name],
+        row_to_folder,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// The child with that name, created if there isn't one
+pub fn child_or_create(
+    conn: &Connection,
+    parent_id: Option<&str>,
+    name: &str,
+) -> Result<Folder, String> {
+    if let Some(folder) = child(conn, parent_id, name)? {
+        return Ok(folder);
+    }
+    insert_folder(
+        conn,
+        FolderCreate {
+            name: name.to_string(),
+            parent_id: parent_id.map(String::from),
+            color: None,
+            icon: None,
+        },
+    )
+}
+
+// The folder at `path`, counted from the top level. With `create`, missing
+// folders along the way are created; otherwise a missing one gives None.
+pub fn resolve_path(conn: &Connection, path: &str, create: bool) -> Result<Option<Folder>, String> {
+    let mut current: Option<Folder> = None;
+
+    for name in path_segments(path) {
+        let parent_id = current.as_ref().map(|f| f.id.clone());
+        current = match child(conn, parent_id.as_deref(), name)? {
+            Some(folder) => Some(folder),
+            None if create => Some(insert_folder(
+                conn,
+                FolderCreate {
+                    name: name.to_string(),
+                    parent_id,
+                    color: None,
+                    icon: None,
+                },
+            )?),
+            None => return Ok(None),
+        };
+    }
+
+    Ok(current)
+}
+
+// True if `id` is `ancestor` or sits somewhere below it
+pub fn is_within(conn: &Connection, id: &str, ancestor: &str) -> Result<bool, String> {
+    Ok(ancestors(conn, id)?.iter().any(|f| f.id == ancestor))
+}
+
+// Folds `source` into `target`: notes, linked map nodes and custom fields
+// move over, subfolders whose name is already taken in `target` are merged
+// the same way, the rest are reparented. `source` is deleted afterwards.
+pub fn merge_into(
+    conn: &Connection,
+    source: &Folder,
+    target: &Folder,
+    now: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE notes SET folder_id = ?1 WHERE folder_id = ?2",
+        params![target.id, source.id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE brain_map_nodes SET linked_folder_id = ?1 WHERE linked_folder_id = ?2",
+        params![target.id, source.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let children = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE parent_id = ?1 ORDER BY created_at",
+                SELECT_FOLDER
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![source.id], row_to_folder)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    for folder in children {
+        match child(conn, Some(&target.id), &folder.name)? {
+            Some(existing) => merge_into(conn, &folder, &existing, now)?,
+            None => {
+                conn.execute(
+                    "UPDATE folders SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![target.id, now, folder.id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    metadata::merge(conn, EntityKind::Folder, &source.id, &target.id)?;
+    conn.execute("DELETE FROM folders WHERE id = ?1", params![source.id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
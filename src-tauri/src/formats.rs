@@ -0,0 +1,241 @@
+use crate::apple_notes;
+use crate::export;
+use crate::import;
+use crate::jex;
+use crate::jobs::JobContext;
+use crate::models::{FormatDirection, FormatTarget, ImportSummary, SupportedFormat};
+use crate::task_csv;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// ============ Registry ============
+//
+// Import and export formats run as background jobs through import_format and
+// export_format. A new format is a module with a type implementing Importer
+// or Exporter, added to the list below; the dialogs pick it up from
+// list_supported_formats without further changes. Importers that can work
+// out what they'd create beforehand also get a dry run through
+// preview_import, which confirm_import then queues.
+
+static IMPORTERS: &[&dyn Importer] = &[
+    &NoteFiles,
+    &jex::Jex,
+    &apple_notes::AppleNotes,
+    &task_csv::Todoist,
+    &task_csv::TickTick,
+];
+
+static EXPORTERS: &[&dyn Exporter] = &[&VaultArchive, &MarkdownFolder, &Enex, &EventsIcs];
+
+// What the dialogs show for a format, and what they let the user pick
+pub struct Format {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub target: FormatTarget,
+    pub extensions: &'static [&'static str],
+    // Whether the importer implements preview
+    pub preview: bool,
+}
+
+impl Format {
+    fn supported(&self, direction: FormatDirection) -> SupportedFormat {
+        SupportedFormat {
+            id: self.id.to_string(),
+            name: self.name.to_string(),
+            direction,
+            target: self.target,
+            extensions: self.extensions.iter().map(|e| e.to_string()).collect(),
+            preview: self.preview,
+        }
+    }
+}
+
+pub trait Importer: Sync {
+    fn format(&self) -> Format;
+
+    // `paths` are the picked files, or the one picked folder. Runs as a job,
+    // so it must be safe to run again from the start after an interruption.
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        folder_id: Option<&str>,
+    ) -> Result<Option<String>, String>;
+
+    // What import would do with the same paths, without writing anything
+    fn preview(
+        &self,
+        _conn: &Connection,
+        _paths: &[PathBuf],
+        _folder_id: Option<&str>,
+    ) -> Result<ImportSummary, String> {
+        Err(format!("{} imports have no preview", self.format().name))
+    }
+}
+
+pub trait Exporter: Sync {
+    fn format(&self) -> Format;
+
+    // `path` is the file to write, or the folder to write into. Returns the
+    // job's output, if any, like the other jobs.
+    fn export(&self, ctx: &JobContext, path: &Path) -> Result<Option<String>, String>;
+}
+
+pub fn importer(id: &str) -> Result<&'static dyn Importer, String> {
+    IMPORTERS
+        .iter()
+        .copied()
+        .find(|importer| importer.format().id == id)
+        .ok_or_else(|| format!("Unsupported import format: {}", id))
+}
+
+pub fn exporter(id: &str) -> Result<&'static dyn Exporter, String> {
+    EXPORTERS
+        .iter()
+        .copied()
+        .find(|exporter| exporter.format().id == id)
+        .ok_or_else(|| format!("Unsupported export format: {}", id))
+}
+
+// Importers first, each list in registry order
+pub fn supported() -> Vec<SupportedFormat> {
+    let imports = IMPORTERS
+        .iter()
+        .map(|importer| importer.format().supported(FormatDirection::Import));
+    let exports = EXPORTERS
+        .iter()
+        .map(|exporter| exporter.format().supported(FormatDirection::Export));
+    imports.chain(exports).collect()
+}
+
+// Previewed imports waiting for confirm_import, by preview id. Managed as app
+// state; the paths were picked in a native dialog, so the frontend only ever
+// hands back the id.
+#[derive(Default)]
+pub struct Previews(Mutex<HashMap<String, PendingImport>>);
+
+pub struct PendingImport {
+    pub format: String,
+    pub paths: Vec<PathBuf>,
+    pub folder_id: Option<String>,
+}
+
+impl Previews {
+    pub fn add(&self, pending: PendingImport) -> Result<String, String> {
+        let id = format!("preview_{}", Uuid::new_v4());
+        self.0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(id.clone(), pending);
+        Ok(id)
+    }
+
+    pub fn take(&self, id: &str) -> Result<PendingImport, String> {
+        self.0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(id)
+            .ok_or_else(|| "Import preview not found".to_string())
+    }
+}
+
+// ============ Built-in formats ============
+
+struct NoteFiles;
+
+impl Importer for NoteFiles {
+    fn format(&self) -> Format {
+        Format {
+            id: "markdown",
+            name: "Markdown, text or HTML files",
+            target: FormatTarget::File,
+            extensions: &["md", "markdown", "txt", "html", "htm"],
+            preview: false,
+        }
+    }
+
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        folder_id: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        import::note_files(ctx, paths, folder_id)
+    }
+}
+
+struct VaultArchive;
+
+impl Exporter for VaultArchive {
+    fn format(&self) -> Format {
+        Format {
+            id: "vault_archive",
+            name: "Vault archive",
+            target: FormatTarget::File,
+            extensions: &["zip"],
+            preview: false,
+        }
+    }
+
+    fn export(&self, ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+        export::vault_archive(ctx, path)
+    }
+}
+
+struct MarkdownFolder;
+
+impl Exporter for MarkdownFolder {
+    fn format(&self) -> Format {
+        Format {
+            id: "markdown",
+            name: "Markdown folder",
+            target: FormatTarget::Folder,
+            extensions: &[],
+            preview: false,
+        }
+    }
+
+    fn export(&self, ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+        export::markdown_folder(ctx, path)
+    }
+}
+
+struct Enex;
+
+impl Exporter for Enex {
+    fn format(&self) -> Format {
+        Format {
+            id: "enex",
+            name: "Evernote export",
+            target: FormatTarget::File,
+            extensions: &["enex"],
+            preview: false,
+        }
+    }
+
+    fn export(&self, ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+        export::enex(ctx, path)
+    }
+}
+
+struct EventsIcs;
+
+impl Exporter for EventsIcs {
+    fn format(&self) -> Format {
+        Format {
+            id: "ics",
+            name: "Calendar (iCalendar)",
+            target: FormatTarget::File,
+            extensions: &["ics"],
+            preview: false,
+        }
+    }
+
+    // Every scheduled event; export_events_ics takes a date range
+    fn export(&self, ctx: &JobContext, path: &Path) -> Result<Option<String>, String> {
+        export::events_ics(ctx, path, None, None)
+    }
+}
@@ -0,0 +1,262 @@
+use crate::models::{NoteProperty, PropertyKind};
+use crate::validation;
+use chrono::NaiveDate;
+use serde_json::Value;
+
+// The YAML between the opening and closing `---` lines (without them), and
+// the content after it. Content without a closed block has no front matter.
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let rest = content.strip_prefix("---").and_then(|rest| {
+        rest.strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+    });
+    let Some(rest) = rest else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+pub fn body(content: &str) -> &str {
+    split(content).1
+}
+
+// The block as stored, delimiters included; empty without front matter
+pub fn header(content: &str) -> &str {
+    &content[..content.len() - body(content).len()]
+}
+
+fn join(yaml: &str, body: &str) -> String {
+    if yaml.trim().is_empty() {
+        return body.to_string();
+    }
+    let newline = if yaml.ends_with('\n') { "" } else { "\n" };
+    format!("---\n{}{}---\n{}", yaml, newline, body)
+}
+
+// A top-level `key: value` entry, with the lines it spans
+struct Entry<'a> {
+    key: String,
+    value: &'a str,
+    nested: Vec<&'a str>,
+    lines: std::ops::Range<usize>,
+}
+
+fn entries(yaml: &str) -> Vec<Entry<'_>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (index, line) in yaml.lines().enumerate() {
+        let continues = line.starts_with([' ', '\t', '-']) || line.trim().is_empty();
+        match entries.last_mut() {
+            Some(entry) if continues => {
+                if !line.trim().is_empty() {
+                    entry.nested.push(line);
+                }
+                entry.lines.end = index + 1;
+                continue;
+            }
+            _ => {}
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        entries.push(Entry {
+            key: unquote(key.trim()).to_string(),
+            value: value.trim(),
+            nested: Vec::new(),
+            lines: index..index + 1,
+        });
+    }
+    entries
+}
+
+fn unquote(text: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    text
+}
+
+// A trailing ` # comment` outside quotes
+fn strip_comment(text: &str) -> &str {
+    if text.starts_with(['"', '\'']) {
+        return text;
+    }
+    match text.find(" #") {
+        Some(index) => text[..index].trim_end(),
+        None => text,
+    }
+}
+
+// Splits `a, "b, c", d` on the commas outside quotes
+fn flow_items(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, ch) in text.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(q), c) if c == q => quote = None,
+            (None, ',') => {
+                items.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+fn scalar(text: &str) -> Value {
+    let text = strip_comment(text.trim());
+    if text.starts_with('"') && text.ends_with('"') && text.len() >= 2 {
+        return serde_json::from_str(text)
+            .unwrap_or_else(|_| Value::String(unquote(text).to_string()));
+    }
+    if text.starts_with('\'') && text.ends_with('\'') && text.len() >= 2 {
+        return Value::String(unquote(text).replace("''", "'"));
+    }
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = text.parse::<i64>() {
+        return Value::from(n);
+    }
+    if let Some(n) = text
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .and_then(serde_json::Number::from_f64)
+    {
+        return Value::Number(n);
+    }
+    Value::String(text.to_string())
+}
+
+fn value(entry: &Entry) -> Value {
+    let inline = strip_comment(entry.value);
+    if let Some(items) = inline.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return Value::Array(flow_items(items).into_iter().map(scalar).collect());
+    }
+    if !inline.is_empty() || entry.nested.is_empty() {
+        return scalar(inline);
+    }
+
+    let items: Option<Vec<Value>> = entry
+        .nested
+        .iter()
+        .map(|line| line.trim_start().strip_prefix('-').map(scalar))
+        .collect();
+    match items {
+        Some(items) => Value::Array(items),
+        // Nested maps aren't properties; they're shown as the YAML text
+        None => Value::String(entry.nested.join("\n")),
+    }
+}
+
+fn kind(value: &Value) -> PropertyKind {
+    match value {
+        Value::Bool(_) => PropertyKind::Checkbox,
+        Value::Number(_) => PropertyKind::Number,
+        Value::Array(_) => PropertyKind::List,
+        Value::String(s) if NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() => PropertyKind::Date,
+        Value::String(s) if validation::parse_timestamp(s).is_some() => PropertyKind::DateTime,
+        _ => PropertyKind::Text,
+    }
+}
+
+// The note's front matter entries in the order they're written
+pub fn properties(content: &str) -> Vec<NoteProperty> {
+    let Some(yaml) = split(content).0 else {
+        return Vec::new();
+    };
+    entries(yaml)
+        .iter()
+        .map(|entry| {
+            let value = value(entry);
+            NoteProperty {
+                key: entry.key.clone(),
+                kind: kind(&value),
+                value: value.to_string(),
+            }
+        })
+        .collect()
+}
+
+// Strings that would read back as something else, or that YAML would choke
+// on, are written double-quoted (JSON string syntax is valid YAML)
+fn yaml_string(text: &str) -> String {
+    let special = text.is_empty()
+        || text != text.trim()
+        || text.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || text.contains([':', '#', ',', '[', ']', '\n', '\r', '\t'])
+        || scalar(text) != Value::String(text.to_string());
+    if special {
+        Value::String(text.to_string()).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn yaml_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => yaml_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match item {
+                    Value::Null => "null".to_string(),
+                    item => yaml_value(item),
+                })
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+// Content with `key` set to `value`, or removed when None. Other entries,
+// comments and the body are kept as written.
+pub fn set_property(content: &str, key: &str, value: Option<&Value>) -> String {
+    let (yaml, body) = split(content);
+    let yaml = yaml.unwrap_or("");
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    let line = value.map(|v| {
+        let text = yaml_value(v);
+        if text.is_empty() {
+            format!("{}:", key)
+        } else {
+            format!("{}: {}", key, text)
+        }
+    });
+
+    match entries(yaml).into_iter().find(|entry| entry.key == key) {
+        Some(entry) => {
+            lines.splice(entry.lines, line);
+        }
+        None => lines.extend(line),
+    }
+
+    let mut yaml = lines.join("\n");
+    if !yaml.is_empty() {
+        yaml.push('\n');
+    }
+    join(&yaml, body)
+}
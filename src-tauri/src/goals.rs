@@ -0,0 +1,234 @@
+use crate::commands::row_to_event;
+use crate::models::{
+    Event, EventStatus, Goal, GoalCreate, GoalItemProgress, GoalMetric, GoalProgress, GoalUpdate,
+    OccurrenceStatus,
+};
+use crate::queries;
+use crate::recurrence;
+use crate::timestamps;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+const SELECT_GOAL: &str =
+    "SELECT id, title, description, metric, target, due_date, created_at, updated_at FROM goals";
+
+fn row_to_goal(row: &rusqlite::Row) -> rusqlite::Result<Goal> {
+    Ok(Goal {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        metric: row.get("metric")?,
+        target: row.get("target")?,
+        due_date: row.get("due_date")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+pub fn load(conn: &Connection, id: &str) -> Result<Option<Goal>, String> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_GOAL),
+        params![id],
+        row_to_goal,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Soonest due first; goals without a due date last
+pub fn list(conn: &Connection) -> Result<Vec<Goal>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} ORDER BY due_date IS NULL, due_date, created_at",
+            SELECT_GOAL
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_goal).map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub fn insert(conn: &Connection, data: GoalCreate, now: &DateTime<Utc>) -> Result<Goal, String> {
+    let goal = Goal {
+        id: format!("goal_{}", Uuid::new_v4()),
+        title: data.title,
+        description: data.description,
+        metric: data.metric.unwrap_or(GoalMetric::Completion),
+        target: data.target,
+        due_date: data.due_date,
+        created_at: *now,
+        updated_at: *now,
+    };
+    conn.execute(
+        "INSERT INTO goals (id, title, description, metric, target, due_date, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            goal.id,
+            goal.title,
+            goal.description,
+            goal.metric,
+            goal.target,
+            goal.due_date,
+            timestamps::format(&goal.created_at),
+            timestamps::format(&goal.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(goal)
+}
+
+pub fn update(
+    conn: &Connection,
+    current: Goal,
+    data: GoalUpdate,
+    now: &DateTime<Utc>,
+) -> Result<Goal, String> {
+    let goal = Goal {
+        title: data.title.unwrap_or(current.title),
+        description: data.description.or(current.description),
+        metric: data.metric.unwrap_or(current.metric),
+        target: data.target.or(current.target),
+        due_date: data.due_date.or(current.due_date),
+        updated_at: *now,
+        ..current
+    };
+    conn.execute(
+        "UPDATE goals SET title = ?1, description = ?2, metric = ?3, target = ?4,
+                due_date = ?5, updated_at = ?6
+         WHERE id = ?7",
+        params![
+            goal.title,
+            goal.description,
+            goal.metric,
+            goal.target,
+            goal.due_date,
+            timestamps::format(&goal.updated_at),
+            goal.id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(goal)
+}
+
+// The linked tasks are left alone
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM goal_items WHERE goal_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM goals WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn link(conn: &Connection, goal_id: &str, event_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO goal_items (goal_id, event_id, added_at) VALUES (?1, ?2, ?3)",
+        params![goal_id, event_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn unlink(conn: &Connection, goal_id: &str, event_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM goal_items WHERE goal_id = ?1 AND event_id = ?2",
+        params![goal_id, event_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Drops a task that's being deleted for good from every goal
+pub fn unlink_all(conn: &Connection, event_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM goal_items WHERE event_id = ?1",
+        params![event_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Linked tasks that aren't in the trash, in the order they were linked
+fn linked_tasks(conn: &Connection, goal_id: &str) -> Result<Vec<Event>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM goal_items g JOIN events e ON e.id = g.event_id
+             WHERE g.goal_id = ?1 AND e.deleted_at IS NULL
+             ORDER BY g.added_at",
+            queries::qualified(queries::EVENT_COLUMNS, "e")
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![goal_id], row_to_event)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn item_progress(
+    conn: &Connection,
+    task: Event,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> Result<GoalItemProgress, String> {
+    let Some(rule) = recurrence::rule(conn, &task)? else {
+        let done = u32::from(task.status == Some(EventStatus::Completed));
+        let due = u32::from(task.status != Some(EventStatus::Cancelled));
+        return Ok(GoalItemProgress {
+            task,
+            recurring: false,
+            done,
+            due,
+        });
+    };
+
+    let (done, due) = if from > to {
+        (0, 0)
+    } else {
+        let history = recurrence::history(conn, &task, rule, from, to, today)?;
+        let done = history
+            .occurrences
+            .iter()
+            .filter(|o| o.status == OccurrenceStatus::Completed)
+            .count();
+        (done as u32, history.occurrences.len() as u32)
+    };
+    Ok(GoalItemProgress {
+        task,
+        recurring: true,
+        done,
+        due,
+    })
+}
+
+pub fn progress(conn: &Connection, goal: Goal, today: NaiveDate) -> Result<GoalProgress, String> {
+    let due_date = goal
+        .due_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    let from = timestamps::local_date(&goal.created_at);
+    let to = due_date.map_or(today, |due| due.min(today));
+
+    let items = linked_tasks(conn, &goal.id)?
+        .into_iter()
+        .map(|task| item_progress(conn, task, from, to, today))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let current: u32 = items.iter().map(|i| i.done).sum();
+    let target = match goal.metric {
+        GoalMetric::Completion => items.iter().map(|i| i.due).sum(),
+        GoalMetric::Count => goal.target.unwrap_or(0),
+    };
+    let percent = (current * 100)
+        .checked_div(target)
+        .map_or(0, |percent| percent.min(100));
+
+    Ok(GoalProgress {
+        days_left: due_date.map(|due| (due - today).num_days()),
+        goal,
+        items,
+        current,
+        target,
+        percent,
+    })
+}
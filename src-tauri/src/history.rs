@@ -0,0 +1,296 @@
+use crate::commands::{row_to_brain_map_connection, row_to_brain_map_node};
+use crate::models::{BrainMapConnection, BrainMapNode, BrainMapOpApplied};
+use crate::queries;
+use crate::timestamps;
+use chrono::Utc;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+// Oldest operations beyond this many per map are dropped
+const MAX_OPS_PER_MAP: i64 = 200;
+
+// The rows an operation touched, as they were at one point in time. An id
+// missing from `nodes`/`connections` means the row didn't exist then.
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    nodes: Vec<BrainMapNode>,
+    connections: Vec<BrainMapConnection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Footprint {
+    node_ids: Vec<String>,
+    connection_ids: Vec<String>,
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn snapshot(conn: &Connection, footprint: &Footprint) -> Result<Snapshot, String> {
+    let mut snapshot = Snapshot::default();
+
+    if !footprint.node_ids.is_empty() {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM brain_map_nodes WHERE id IN ({}) AND deleted_at IS NULL",
+                queries::NODE_COLUMNS,
+                placeholders(footprint.node_ids.len())
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params_from_iter(&footprint.node_ids), row_to_brain_map_node)
+            .map_err(|e| e.to_string())?;
+        snapshot.nodes = rows.filter_map(|r| r.ok()).collect();
+    }
+
+    if !footprint.connection_ids.is_empty() {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, brain_map_id, source_node_id, target_node_id, label, color, animated, style, created_at
+                 FROM brain_map_connections WHERE id IN ({}) AND deleted_at IS NULL",
+                placeholders(footprint.connection_ids.len())
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params_from_iter(&footprint.connection_ids),
+                row_to_brain_map_connection,
+            )
+            .map_err(|e| e.to_string())?;
+        snapshot.connections = rows.filter_map(|r| r.ok()).collect();
+    }
+
+    Ok(snapshot)
+}
+
+// Captures the rows an edit is about to touch. Call `finish` once the edit is
+// done to log it as one undoable step.
+pub struct Recorder {
+    brain_map_id: String,
+    label: &'static str,
+    footprint: Footprint,
+    before: Snapshot,
+}
+
+pub fn record(
+    conn: &Connection,
+    brain_map_id: &str,
+    label: &'static str,
+    node_ids: Vec<String>,
+    connection_ids: Vec<String>,
+) -> Result<Recorder, String> {
+    let footprint = Footprint {
+        node_ids,
+        connection_ids,
+    };
+    Ok(Recorder {
+        brain_map_id: brain_map_id.to_string(),
+        label,
+        before: snapshot(conn, &footprint)?,
+        footprint,
+    })
+}
+
+// Everything deleting a node changes: the node, its children (which lose
+// their parent) and its connections
+pub fn record_node_delete(
+    conn: &Connection,
+    brain_map_id: &str,
+    node_id: &str,
+) -> Result<Recorder, String> {
+    let ids = |sql: &str| -> Result<Vec<String>, String> {
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![node_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        Ok(rows.filter_map(|r| r.ok()).collect())
+    };
+
+    let mut node_ids = ids("SELECT id FROM brain_map_nodes WHERE parent_node_id = ?1")?;
+    node_ids.push(node_id.to_string());
+    let connection_ids = ids(
+        "SELECT id FROM brain_map_connections WHERE source_node_id = ?1 OR target_node_id = ?1",
+    )?;
+
+    record(conn, brain_map_id, "delete_node", node_ids, connection_ids)
+}
+
+impl Recorder {
+    pub fn finish(self, conn: &Connection) -> Result<(), String> {
+        let after = snapshot(conn, &self.footprint)?;
+
+        // A new edit makes anything previously undone unreachable
+        conn.execute(
+            "DELETE FROM brain_map_ops WHERE brain_map_id = ?1 AND undone = 1",
+            params![self.brain_map_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "INSERT INTO brain_map_ops (brain_map_id, label, footprint, state_before, state_after,
+                                        undone, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)",
+            params![
+                self.brain_map_id,
+                self.label,
+                serde_json::to_string(&self.footprint).map_err(|e| e.to_string())?,
+                serde_json::to_string(&self.before).map_err(|e| e.to_string())?,
+                serde_json::to_string(&after).map_err(|e| e.to_string())?,
+                timestamps::format(&Utc::now()),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "DELETE FROM brain_map_ops WHERE brain_map_id = ?1 AND seq <= (
+                 SELECT seq FROM brain_map_ops WHERE brain_map_id = ?1
+                 ORDER BY seq DESC LIMIT 1 OFFSET ?2
+             )",
+            params![self.brain_map_id, MAX_OPS_PER_MAP],
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+// ============ Undo / redo ============
+
+// Puts every row the operation touched back the way `state` has it
+fn restore(conn: &Connection, footprint: &Footprint, state: &Snapshot) -> Result<(), String> {
+    for id in &footprint.node_ids {
+        let result = match state.nodes.iter().find(|n| &n.id == id) {
+            Some(node) => conn.execute(
+                "INSERT OR REPLACE INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, description,
+                                                         x, y, color, shape, size, icon, linked_note_id,
+                                                         linked_folder_id, linked_event_id, is_collapsed, layer,
+                                                         created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+                params![
+                    node.id,
+                    node.brain_map_id,
+                    node.parent_node_id,
+                    node.label,
+                    node.description,
+                    node.x,
+                    node.y,
+                    node.color,
+                    node.shape,
+                    node.size,
+                    node.icon,
+                    node.linked_note_id,
+                    node.linked_folder_id,
+                    node.linked_event_id,
+                    node.is_collapsed as i32,
+                    node.layer,
+                    timestamps::format(&node.created_at),
+                    timestamps::format(&node.updated_at),
+                ],
+            ),
+            None => conn.execute("DELETE FROM brain_map_nodes WHERE id = ?1", params![id]),
+        };
+        result.map_err(|e| e.to_string())?;
+    }
+
+    for id in &footprint.connection_ids {
+        let result = match state.connections.iter().find(|c| &c.id == id) {
+            Some(connection) => conn.execute(
+                "INSERT OR REPLACE INTO brain_map_connections (id, brain_map_id, source_node_id, target_node_id,
+                                                               label, color, style, animated, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    connection.id,
+                    connection.brain_map_id,
+                    connection.source_node_id,
+                    connection.target_node_id,
+                    connection.label,
+                    connection.color,
+                    connection.style,
+                    connection.animated as i32,
+                    timestamps::format(&connection.created_at),
+                ],
+            ),
+            None => conn.execute("DELETE FROM brain_map_connections WHERE id = ?1", params![id]),
+        };
+        result.map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Undo rewinds the newest applied operation to its `state_before`; redo
+// replays the oldest undone one to its `state_after`.
+fn step(
+    conn: &mut Connection,
+    brain_map_id: &str,
+    undo: bool,
+) -> Result<Option<BrainMapOpApplied>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let sql = if undo {
+        "SELECT seq, label, footprint, state_before FROM brain_map_ops
+         WHERE brain_map_id = ?1 AND undone = 0 ORDER BY seq DESC LIMIT 1"
+    } else {
+        "SELECT seq, label, footprint, state_after FROM brain_map_ops
+         WHERE brain_map_id = ?1 AND undone = 1 ORDER BY seq ASC LIMIT 1"
+    };
+    let op: Option<(i64, String, String, String)> = tx
+        .query_row(sql, params![brain_map_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((seq, label, footprint, state)) = op else {
+        return Ok(None);
+    };
+
+    let footprint: Footprint = serde_json::from_str(&footprint).map_err(|e| e.to_string())?;
+    let state: Snapshot = serde_json::from_str(&state).map_err(|e| e.to_string())?;
+    restore(&tx, &footprint, &state)?;
+
+    tx.execute(
+        "UPDATE brain_map_ops SET undone = ?1 WHERE seq = ?2",
+        params![undo as i32, seq],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE brain_maps SET updated_at = ?1 WHERE id = ?2",
+        params![timestamps::format(&Utc::now()), brain_map_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let removed_node_ids = footprint
+        .node_ids
+        .into_iter()
+        .filter(|id| !state.nodes.iter().any(|n| &n.id == id))
+        .collect();
+    let removed_connection_ids = footprint
+        .connection_ids
+        .into_iter()
+        .filter(|id| !state.connections.iter().any(|c| &c.id == id))
+        .collect();
+
+    Ok(Some(BrainMapOpApplied {
+        label,
+        nodes: state.nodes,
+        connections: state.connections,
+        removed_node_ids,
+        removed_connection_ids,
+    }))
+}
+
+pub fn undo(
+    conn: &mut Connection,
+    brain_map_id: &str,
+) -> Result<Option<BrainMapOpApplied>, String> {
+    step(conn, brain_map_id, true)
+}
+
+pub fn redo(
+    conn: &mut Connection,
+    brain_map_id: &str,
+) -> Result<Option<BrainMapOpApplied>, String> {
+    step(conn, brain_map_id, false)
+}
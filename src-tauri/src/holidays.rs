@@ -0,0 +1,307 @@
+use crate::models::{Holiday, HolidayRegion};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+
+pub const HOLIDAY_REGIONS_SETTING: &str = "holiday_regions";
+
+// How a holiday's date is found in a given year
+enum Rule {
+    Fixed(u32, u32),
+    // nth weekday of the month; -1 is the last one
+    Nth(u32, Weekday, i32),
+    // Last weekday on or before the given day
+    OnOrBefore(u32, u32, Weekday),
+    // Days after Easter Sunday (negative for before)
+    Easter(i64),
+}
+
+struct Dataset {
+    code: &'static str,
+    name: &'static str,
+    // A subdivision gets its country's holidays plus its own
+    parent: Option<&'static str>,
+    holidays: &'static [(&'static str, Rule)],
+}
+
+// Public holidays as written into law. Dates are the nominal ones; substitute
+// days for holidays that fall on a weekend aren't added.
+const DATASETS: &[Dataset] = &[
+    Dataset {
+        code: "US",
+        name: "United States",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Martin Luther King Jr. Day", Rule::Nth(1, Weekday::Mon, 3)),
+            ("Washington's Birthday", Rule::Nth(2, Weekday::Mon, 3)),
+            ("Memorial Day", Rule::Nth(5, Weekday::Mon, -1)),
+            ("Juneteenth", Rule::Fixed(6, 19)),
+            ("Independence Day", Rule::Fixed(7, 4)),
+            ("Labor Day", Rule::Nth(9, Weekday::Mon, 1)),
+            ("Columbus Day", Rule::Nth(10, Weekday::Mon, 2)),
+            ("Veterans Day", Rule::Fixed(11, 11)),
+            ("Thanksgiving Day", Rule::Nth(11, Weekday::Thu, 4)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+        ],
+    },
+    Dataset {
+        code: "CA",
+        name: "Canada",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Good Friday", Rule::Easter(-2)),
+            ("Victoria Day", Rule::OnOrBefore(5, 24, Weekday::Mon)),
+            ("Canada Day", Rule::Fixed(7, 1)),
+            ("Labour Day", Rule::Nth(9, Weekday::Mon, 1)),
+            (
+                "National Day for Truth and Reconciliation",
+                Rule::Fixed(9, 30),
+            ),
+            ("Thanksgiving", Rule::Nth(10, Weekday::Mon, 2)),
+            ("Remembrance Day", Rule::Fixed(11, 11)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("Boxing Day", Rule::Fixed(12, 26)),
+        ],
+    },
+    Dataset {
+        code: "GB",
+        name: "United Kingdom (England and Wales)",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Good Friday", Rule::Easter(-2)),
+            ("Easter Monday", Rule::Easter(1)),
+            ("Early May Bank Holiday", Rule::Nth(5, Weekday::Mon, 1)),
+            ("Spring Bank Holiday", Rule::Nth(5, Weekday::Mon, -1)),
+            ("Summer Bank Holiday", Rule::Nth(8, Weekday::Mon, -1)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("Boxing Day", Rule::Fixed(12, 26)),
+        ],
+    },
+    Dataset {
+        code: "GB-SCT",
+        name: "United Kingdom (Scotland)",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("2nd January", Rule::Fixed(1, 2)),
+            ("Good Friday", Rule::Easter(-2)),
+            ("Early May Bank Holiday", Rule::Nth(5, Weekday::Mon, 1)),
+            ("Spring Bank Holiday", Rule::Nth(5, Weekday::Mon, -1)),
+            ("Summer Bank Holiday", Rule::Nth(8, Weekday::Mon, 1)),
+            ("St Andrew's Day", Rule::Fixed(11, 30)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("Boxing Day", Rule::Fixed(12, 26)),
+        ],
+    },
+    Dataset {
+        code: "GB-NIR",
+        name: "United Kingdom (Northern Ireland)",
+        parent: Some("GB"),
+        holidays: &[
+            ("St Patrick's Day", Rule::Fixed(3, 17)),
+            ("Battle of the Boyne", Rule::Fixed(7, 12)),
+        ],
+    },
+    Dataset {
+        code: "DE",
+        name: "Germany",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Good Friday", Rule::Easter(-2)),
+            ("Easter Monday", Rule::Easter(1)),
+            ("Labour Day", Rule::Fixed(5, 1)),
+            ("Ascension Day", Rule::Easter(39)),
+            ("Whit Monday", Rule::Easter(50)),
+            ("German Unity Day", Rule::Fixed(10, 3)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("St Stephen's Day", Rule::Fixed(12, 26)),
+        ],
+    },
+    Dataset {
+        code: "DE-BY",
+        name: "Germany (Bavaria)",
+        parent: Some("DE"),
+        holidays: &[
+            ("Epiphany", Rule::Fixed(1, 6)),
+            ("Corpus Christi", Rule::Easter(60)),
+            ("All Saints' Day", Rule::Fixed(11, 1)),
+        ],
+    },
+    Dataset {
+        code: "FR",
+        name: "France",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Easter Monday", Rule::Easter(1)),
+            ("Labour Day", Rule::Fixed(5, 1)),
+            ("Victory in Europe Day", Rule::Fixed(5, 8)),
+            ("Ascension Day", Rule::Easter(39)),
+            ("Whit Monday", Rule::Easter(50)),
+            ("Bastille Day", Rule::Fixed(7, 14)),
+            ("Assumption of Mary", Rule::Fixed(8, 15)),
+            ("All Saints' Day", Rule::Fixed(11, 1)),
+            ("Armistice Day", Rule::Fixed(11, 11)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+        ],
+    },
+    Dataset {
+        code: "NL",
+        name: "Netherlands",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Easter Sunday", Rule::Easter(0)),
+            ("Easter Monday", Rule::Easter(1)),
+            ("King's Day", Rule::Fixed(4, 27)),
+            ("Liberation Day", Rule::Fixed(5, 5)),
+            ("Ascension Day", Rule::Easter(39)),
+            ("Whit Sunday", Rule::Easter(49)),
+            ("Whit Monday", Rule::Easter(50)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("Second Day of Christmas", Rule::Fixed(12, 26)),
+        ],
+    },
+    Dataset {
+        code: "AU",
+        name: "Australia",
+        parent: None,
+        holidays: &[
+            ("New Year's Day", Rule::Fixed(1, 1)),
+            ("Australia Day", Rule::Fixed(1, 26)),
+            ("Good Friday", Rule::Easter(-2)),
+            ("Easter Monday", Rule::Easter(1)),
+            ("Anzac Day", Rule::Fixed(4, 25)),
+            ("Christmas Day", Rule::Fixed(12, 25)),
+            ("Boxing Day", Rule::Fixed(12, 26)),
+        ],
+    },
+];
+
+fn dataset(code: &str) -> Option<&'static Dataset> {
+    DATASETS.iter().find(|d| d.code.eq_ignore_ascii_case(code))
+}
+
+// Anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+fn date_in(rule: &Rule, year: i32) -> Option<NaiveDate> {
+    match *rule {
+        Rule::Fixed(month, day) => NaiveDate::from_ymd_opt(year, month, day),
+        Rule::Nth(month, weekday, n) if n > 0 => {
+            NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+        }
+        Rule::Nth(month, weekday, _) => {
+            let (next_year, next_month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+            let last = NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()?;
+            let back =
+                (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            Some(last - Duration::days(back as i64))
+        }
+        Rule::OnOrBefore(month, day, weekday) => {
+            let limit = NaiveDate::from_ymd_opt(year, month, day)?;
+            let back =
+                (limit.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            Some(limit - Duration::days(back as i64))
+        }
+        Rule::Easter(offset) => Some(easter_sunday(year)? + Duration::days(offset)),
+    }
+}
+
+pub fn regions(selected: &[String]) -> Vec<HolidayRegion> {
+    DATASETS
+        .iter()
+        .map(|d| HolidayRegion {
+            code: d.code.to_string(),
+            name: d.name.to_string(),
+            selected: selected.iter().any(|s| s.eq_ignore_ascii_case(d.code)),
+        })
+        .collect()
+}
+
+// Canonical codes for `codes`, or the first one there's no dataset for
+pub fn canonical_codes(codes: &[String]) -> Result<Vec<String>, String> {
+    let mut canonical = Vec::new();
+    for code in codes {
+        let dataset = dataset(code.trim()).ok_or_else(|| code.clone())?;
+        if !canonical.iter().any(|c| c == dataset.code) {
+            canonical.push(dataset.code.to_string());
+        }
+    }
+    Ok(canonical)
+}
+
+pub fn load_selected(conn: &Connection) -> Result<Vec<String>, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![HOLIDAY_REGIONS_SETTING],
+            |row| row.get(0),
+        )
+        .ok();
+
+    value
+        .map(|v| serde_json::from_str(&v).map_err(|e| e.to_string()))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+// Holidays of the selected regions between `start` and `end` inclusive, by
+// date. A holiday shared by several regions is listed once, under the first.
+pub fn between(selected: &[String], start: NaiveDate, end: NaiveDate) -> Vec<Holiday> {
+    let mut seen = HashSet::new();
+    let mut holidays = Vec::new();
+
+    for code in selected {
+        let Some(region) = dataset(code) else {
+            continue;
+        };
+        let parent = region.parent.and_then(dataset);
+        let rules = parent
+            .into_iter()
+            .chain(Some(region))
+            .flat_map(|d| d.holidays.iter());
+
+        for (name, rule) in rules {
+            for year in start.year()..=end.year() {
+                let Some(date) = date_in(rule, year).filter(|d| *d >= start && *d <= end) else {
+                    continue;
+                };
+                if seen.insert((date, *name)) {
+                    holidays.push(Holiday {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        name: name.to_string(),
+                        region: region.code.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    holidays.sort_by(|a, b| a.date.cmp(&b.date));
+    holidays
+}
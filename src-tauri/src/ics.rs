@@ -0,0 +1,124 @@
+use crate::models::{Event, EventStatus, WeekStart};
+use crate::timestamps;
+use chrono::{DateTime, Duration, Utc};
+
+// RFC 5545 TEXT escaping
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Content lines are folded at 75 octets, continuing with a leading space
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn utc(time: &DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn wkst(start: WeekStart) -> &'static str {
+    match start {
+        WeekStart::Monday => "MO",
+        WeekStart::Sunday => "SU",
+        WeekStart::Saturday => "SA",
+    }
+}
+
+fn vevent(out: &mut String, event: &Event, start: &DateTime<Utc>, week_start: WeekStart) {
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}@voyena", event.id));
+    push_line(out, &format!("DTSTAMP:{}", utc(&event.updated_at)));
+
+    if event.is_all_day {
+        // All-day events fall on the user's local days
+        let first = timestamps::local_date(start);
+        let last = event
+            .end_time
+            .as_ref()
+            .map(timestamps::local_date)
+            .unwrap_or(first)
+            .max(first);
+        push_line(
+            out,
+            &format!("DTSTART;VALUE=DATE:{}", first.format("%Y%m%d")),
+        );
+        // DTEND is exclusive for all-day events
+        let end = last + Duration::days(1);
+        push_line(out, &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+    } else {
+        push_line(out, &format!("DTSTART:{}", utc(start)));
+        match event.end_time.as_ref().map(utc) {
+            Some(dtend) => push_line(out, &format!("DTEND:{}", dtend)),
+            None => {
+                if let Some(minutes) = event.duration_minutes.filter(|m| *m > 0) {
+                    push_line(out, &format!("DURATION:PT{}M", minutes));
+                }
+            }
+        }
+    }
+
+    if event.is_recurring {
+        let freq = match event.recurring_pattern.as_deref() {
+            Some("daily") => Some("DAILY"),
+            Some("weekly") => Some("WEEKLY"),
+            Some("monthly") => Some("MONTHLY"),
+            Some("yearly") => Some("YEARLY"),
+            _ => None,
+        };
+        if let Some(freq) = freq {
+            push_line(
+                out,
+                &format!("RRULE:FREQ={};WKST={}", freq, wkst(week_start)),
+            );
+        }
+    }
+
+    push_line(out, &format!("SUMMARY:{}", escape(&event.title)));
+    if let Some(description) = event.description.as_deref().filter(|d| !d.is_empty()) {
+        push_line(out, &format!("DESCRIPTION:{}", escape(description)));
+    }
+    if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+        push_line(out, &format!("LOCATION:{}", escape(location)));
+    }
+    if !event.tags.is_empty() {
+        let tags: Vec<String> = event.tags.iter().map(|t| escape(t)).collect();
+        push_line(out, &format!("CATEGORIES:{}", tags.join(",")));
+    }
+    let status = match event.status {
+        Some(EventStatus::Cancelled) => "CANCELLED",
+        _ => "CONFIRMED",
+    };
+    push_line(out, &format!("STATUS:{}", status));
+    push_line(out, "END:VEVENT");
+}
+
+// Scheduled events as an iCalendar feed. Weekly recurrences carry the
+// locale's first day of the week (WKST) so other calendars expand them the
+// same way.
+pub fn calendar(events: &[Event], week_start: WeekStart) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//Voyena//Voyena Calendar//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    for event in events {
+        if let Some(start) = event.start_time.as_ref() {
+            vevent(&mut out, event, start, week_start);
+        }
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
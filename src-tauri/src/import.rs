@@ -0,0 +1,143 @@
+use crate::commands::insert_note;
+use crate::db::Database;
+use crate::jobs::JobContext;
+use crate::models::NoteCreate;
+use crate::render;
+use crate::sanitize;
+use crate::search_index;
+use crate::timestamps;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+// Note content from a .md, .txt or .html file
+fn note_content(path: &Path, text: String) -> String {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => text,
+        "txt" => text
+            .split("\n\n")
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| format!("<p>{}</p>", render::escape_html(p.trim())))
+            .collect(),
+        _ => render::markdown_to_html(&text),
+    }
+}
+
+pub fn note_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+// A live note in the folder with the same title and content, e.g. from a run
+// of the job cut short by the app closing
+fn already_imported(
+    conn: &Connection,
+    folder_id: Option<&str>,
+    title: &str,
+    content: &str,
+) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM notes
+         WHERE deleted_at IS NULL AND folder_id IS ?1 AND title = ?2 AND content = ?3",
+        params![folder_id, title, content],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|e| e.to_string())
+}
+
+// For importers that keep the source's timestamps: a live note in the
+// folder with the same title and creation time, i.e. from an earlier run
+pub fn imported_before(
+    conn: &Connection,
+    folder_id: Option<&str>,
+    title: &str,
+    created_at: &DateTime<Utc>,
+) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM notes WHERE deleted_at IS NULL AND folder_id IS ?1
+                                          AND title = ?2 AND created_at = ?3)",
+        params![folder_id, title, timestamps::format(created_at)],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Fills in a note created empty so its attachments had somewhere to go,
+// with the content linking to them and the source's timestamps
+pub fn finish_note(
+    conn: &Connection,
+    note_id: &str,
+    content: &str,
+    created_at: &DateTime<Utc>,
+    updated_at: &DateTime<Utc>,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE notes SET content = ?1, created_at = ?2, updated_at = ?3 WHERE id = ?4",
+        params![
+            sanitize::sanitize_content(content),
+            timestamps::format(created_at),
+            timestamps::format(updated_at),
+            note_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    search_index::note_changed(conn, note_id);
+    Ok(())
+}
+
+// One note per file. Files that can't be read are reported and the rest
+// still imported.
+pub fn note_files(
+    ctx: &JobContext,
+    paths: &[PathBuf],
+    folder_id: Option<&str>,
+) -> Result<Option<String>, String> {
+    let db = ctx.app().state::<Database>();
+    let total = paths.len();
+
+    for (index, path) in paths.iter().enumerate() {
+        ctx.checkpoint()?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let title = note_title(path);
+                let content = sanitize::sanitize_content(&note_content(path, text));
+                let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                if already_imported(&conn, folder_id, &title, &content)? {
+                    ctx.skipped();
+                } else {
+                    insert_note(
+                        &conn,
+                        NoteCreate {
+                            title: Some(title),
+                            content: Some(content),
+                            folder_id: folder_id.map(String::from),
+                            tags: None,
+                            aliases: None,
+                        },
+                    )?;
+                    ctx.created();
+                }
+            }
+            Err(e) => ctx.item_failed(&name, &e.to_string()),
+        }
+
+        ctx.progress(index + 1, total, &name);
+    }
+
+    Ok(None)
+}
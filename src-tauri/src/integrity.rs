@@ -0,0 +1,107 @@
+use crate::models::BrainMapRepairReport;
+use rusqlite::Connection;
+
+// Foreign keys aren't enforced on the connection, so the ON DELETE clauses in
+// the schema never ran. These checks catch what they would have handled.
+
+const ORPHANED_NODES: &str = "brain_map_id NOT IN (SELECT id FROM brain_maps)";
+
+const DANGLING_CONNECTIONS: &str = "brain_map_id NOT IN (SELECT id FROM brain_maps)
+     OR source_node_id NOT IN (SELECT id FROM brain_map_nodes)
+     OR target_node_id NOT IN (SELECT id FROM brain_map_nodes)";
+
+const MISSING_PARENTS: &str =
+    "parent_node_id IS NOT NULL AND parent_node_id NOT IN (SELECT id FROM brain_map_nodes)";
+
+// Children of a missing parent are reattached to the map's center node, or
+// become roots if the center is gone too
+const REATTACH_TO_CENTER: &str = "UPDATE brain_map_nodes SET parent_node_id = (
+         SELECT m.center_node_id FROM brain_maps m
+         WHERE m.id = brain_map_nodes.brain_map_id
+           AND m.center_node_id <> brain_map_nodes.id
+           AND m.center_node_id IN (SELECT id FROM brain_map_nodes)
+     )";
+
+fn broken_link(column: &str, table: &str) -> String {
+    format!(
+        "{0} IS NOT NULL AND {0} NOT IN (SELECT id FROM {1})",
+        column, table
+    )
+}
+
+// Counts the rows of `table` matching `condition`, or applies `action` to them
+fn check(
+    conn: &Connection,
+    repair: bool,
+    table: &str,
+    condition: &str,
+    action: &str,
+) -> Result<u32, String> {
+    let changed = if repair {
+        conn.execute(&format!("{} WHERE {}", action, condition), [])
+    } else {
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE {}", table, condition),
+            [],
+            |row| row.get(0),
+        )
+    };
+    changed.map(|n| n as u32).map_err(|e| e.to_string())
+}
+
+pub fn repair_brain_maps(
+    conn: &mut Connection,
+    dry_run: bool,
+) -> Result<BrainMapRepairReport, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let repair = !dry_run;
+
+    let report = BrainMapRepairReport {
+        orphaned_nodes: check(
+            &tx,
+            repair,
+            "brain_map_nodes",
+            ORPHANED_NODES,
+            "DELETE FROM brain_map_nodes",
+        )?,
+        dangling_connections: check(
+            &tx,
+            repair,
+            "brain_map_connections",
+            DANGLING_CONNECTIONS,
+            "DELETE FROM brain_map_connections",
+        )?,
+        missing_parents: check(
+            &tx,
+            repair,
+            "brain_map_nodes",
+            MISSING_PARENTS,
+            REATTACH_TO_CENTER,
+        )?,
+        broken_note_links: check(
+            &tx,
+            repair,
+            "brain_map_nodes",
+            &broken_link("linked_note_id", "notes"),
+            "UPDATE brain_map_nodes SET linked_note_id = NULL",
+        )?,
+        broken_folder_links: check(
+            &tx,
+            repair,
+            "brain_map_nodes",
+            &broken_link("linked_folder_id", "folders"),
+            "UPDATE brain_map_nodes SET linked_folder_id = NULL",
+        )?,
+        broken_event_links: check(
+            &tx,
+            repair,
+            "brain_map_nodes",
+            &broken_link("linked_event_id", "events"),
+            "UPDATE brain_map_nodes SET linked_event_id = NULL",
+        )?,
+        repaired: repair,
+    };
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
@@ -0,0 +1,471 @@
+use crate::attachments;
+use crate::commands::{insert_event, insert_note, new_event, store_attachment};
+use crate::db::Database;
+use crate::folders;
+use crate::formats::{Format, Importer};
+use crate::import;
+use crate::jobs::JobContext;
+use crate::models::{EventCreate, EventStatus, EventType, FormatTarget, NoteCreate, TimeMode};
+use crate::render;
+use crate::timestamps;
+use chrono::{DateTime, TimeZone, Utc};
+use regex::{Captures, Regex};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+// Joplin's item types, from the type_ property
+const TYPE_NOTE: &str = "1";
+const TYPE_FOLDER: &str = "2";
+const TYPE_RESOURCE: &str = "4";
+const TYPE_TAG: &str = "5";
+const TYPE_NOTE_TAG: &str = "6";
+
+// Joplin export (.jex): an uncompressed tar with one text file per item,
+// named by the item's id, and each resource's bytes in resources/. Notebooks
+// become folders, under the folder picked for the import; notes keep their
+// tags, resources and timestamps; to-dos become tasks.
+pub struct Jex;
+
+impl Importer for Jex {
+    fn format(&self) -> Format {
+        Format {
+            id: "jex",
+            name: "Joplin export",
+            target: FormatTarget::File,
+            extensions: &["jex"],
+            preview: false,
+        }
+    }
+
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        folder_id: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match read_archive(path) {
+                Ok(archive) => import_archive(ctx, &archive, folder_id)?,
+                Err(e) => ctx.item_failed(&name, &e),
+            }
+        }
+        Ok(None)
+    }
+}
+
+// ============ Reading ============
+
+// An item's properties, and for notes the title and body above them
+struct Item {
+    title: String,
+    body: String,
+    props: HashMap<String, String>,
+}
+
+impl Item {
+    fn prop(&self, key: &str) -> &str {
+        self.props.get(key).map(String::as_str).unwrap_or_default()
+    }
+
+    fn id(&self) -> &str {
+        self.prop("id")
+    }
+
+    // The time the user sees in Joplin, which can be edited, before the
+    // time the item was written
+    fn time(&self, user_key: &str, key: &str) -> Option<DateTime<Utc>> {
+        timestamp(self.prop(user_key)).or_else(|| timestamp(self.prop(key)))
+    }
+}
+
+// Properties are `key: value` lines at the end, after a blank line; above
+// them the first line is the title and the body starts on the third. Values
+// have their line breaks escaped.
+fn parse_item(text: &str) -> Option<Item> {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    let mut props = HashMap::new();
+    while let Some(line) = lines.pop() {
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim().replace("\\n", "\n").replace("\\r", "\r");
+        props.insert(key.trim().to_string(), value);
+    }
+    props.get("type_")?;
+
+    let title = lines
+        .first()
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default();
+    let body = lines
+        .get(2..)
+        .map(|body| body.join("\n"))
+        .unwrap_or_default();
+    Some(Item { title, body, props })
+}
+
+// ISO 8601 in exports, milliseconds since the epoch in older ones; empty
+// or 0 when unset
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    match value.parse::<i64>() {
+        Ok(millis) if millis > 0 => Utc.timestamp_millis_opt(millis).single(),
+        _ => None,
+    }
+}
+
+struct Archive {
+    items: Vec<Item>,
+    // Resource bytes by resource id
+    resources: HashMap<String, Vec<u8>>,
+}
+
+fn read_archive(path: &Path) -> Result<Archive, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut tar = tar::Archive::new(file);
+    let mut archive = Archive {
+        items: Vec::new(),
+        resources: HashMap::new(),
+    };
+
+    for entry in tar.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = entry.path().map_err(|e| e.to_string())?.into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+
+        let stem = entry_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let in_resources = entry_path
+            .parent()
+            .is_some_and(|parent| parent.ends_with("resources"));
+        if in_resources {
+            archive.resources.insert(stem, bytes);
+        } else if entry_path.extension().is_some_and(|e| e == "md") {
+            if let Some(item) = parse_item(&String::from_utf8_lossy(&bytes)) {
+                archive.items.push(item);
+            }
+        }
+    }
+
+    if archive.items.is_empty() {
+        return Err("Not a Joplin export".to_string());
+    }
+    Ok(archive)
+}
+
+// ============ Importing ============
+
+// `[label](:/id)`, Joplin's link to a note or resource
+fn item_link() -> &'static Regex {
+    static ITEM_LINK: OnceLock<Regex> = OnceLock::new();
+    ITEM_LINK.get_or_init(|| {
+        Regex::new(r"\[([^\[\]]*)\]\(:/([0-9a-fA-F]{32})\)").expect("valid item link regex")
+    })
+}
+
+// `:/id` anywhere, e.g. an HTML note's `src=":/id"`
+fn item_ref() -> &'static Regex {
+    static ITEM_REF: OnceLock<Regex> = OnceLock::new();
+    ITEM_REF.get_or_init(|| Regex::new(r":/([0-9a-fA-F]{32})").expect("valid item ref regex"))
+}
+
+// Notebooks in parent-first order become folders. One whose name is already
+// taken among its new siblings is merged into that folder, so importing the
+// same export twice doesn't duplicate the tree.
+fn import_folders(
+    conn: &Connection,
+    archive: &Archive,
+    folder_id: Option<&str>,
+) -> Result<HashMap<String, String>, String> {
+    let notebooks: HashMap<&str, &Item> = archive
+        .items
+        .iter()
+        .filter(|item| item.prop("type_") == TYPE_FOLDER)
+        .map(|item| (item.id(), item))
+        .collect();
+
+    let mut created: HashMap<String, String> = HashMap::new();
+    for id in notebooks.keys() {
+        // Walk up to the top, a notebook already made, or a parent cycle,
+        // whose rest then goes at the top
+        let mut chain = vec![*id];
+        loop {
+            let parent = notebooks[chain[chain.len() - 1]].prop("parent_id");
+            if !notebooks.contains_key(parent)
+                || created.contains_key(parent)
+                || chain.contains(&parent)
+            {
+                break;
+            }
+            chain.push(parent);
+        }
+
+        for id in chain.into_iter().rev() {
+            if created.contains_key(id) {
+                continue;
+            }
+            let notebook = notebooks[id];
+            let parent_id = created
+                .get(notebook.prop("parent_id"))
+                .map(String::as_str)
+                .or(folder_id);
+            let name = match notebook.title.as_str() {
+                "" => "Untitled",
+                title => title,
+            };
+            let folder = folders::child_or_create(conn, parent_id, name)?;
+            created.insert(id.to_string(), folder.id);
+        }
+    }
+    Ok(created)
+}
+
+fn import_archive(
+    ctx: &JobContext,
+    archive: &Archive,
+    folder_id: Option<&str>,
+) -> Result<(), String> {
+    let db = ctx.app().state::<Database>();
+    let folders = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        import_folders(&conn, archive, folder_id)?
+    };
+
+    let of_type = |type_| {
+        archive
+            .items
+            .iter()
+            .filter(move |i| i.prop("type_") == type_)
+    };
+    let tag_names: HashMap<&str, &str> = of_type(TYPE_TAG)
+        .map(|tag| (tag.id(), tag.title.as_str()))
+        .collect();
+    let mut tags: HashMap<&str, Vec<String>> = HashMap::new();
+    for note_tag in of_type(TYPE_NOTE_TAG) {
+        if let Some(name) = tag_names.get(note_tag.prop("tag_id")) {
+            tags.entry(note_tag.prop("note_id"))
+                .or_default()
+                .push(name.to_string());
+        }
+    }
+    let resources: HashMap<&str, &Item> = of_type(TYPE_RESOURCE).map(|r| (r.id(), r)).collect();
+    let notes: Vec<&Item> = of_type(TYPE_NOTE).collect();
+    let titles: HashMap<&str, &str> = notes.iter().map(|n| (n.id(), n.title.as_str())).collect();
+
+    let links = Links {
+        data_dir: &db.data_dir,
+        archive,
+        resources: &resources,
+        titles: &titles,
+    };
+
+    let total = notes.len();
+    for (index, note) in notes.into_iter().enumerate() {
+        ctx.checkpoint()?;
+        let title = match note.title.trim() {
+            "" => "Untitled",
+            title => title,
+        };
+        let imported = if note.prop("encryption_applied") == "1" {
+            Err("Encrypted in Joplin; decrypt it there and export again".to_string())
+        } else {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            let note_tags = tags.remove(note.id()).unwrap_or_default();
+            let folder_id = folders
+                .get(note.prop("parent_id"))
+                .map(String::as_str)
+                .or(folder_id);
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            let imported = if note.prop("is_todo") == "1" {
+                import_todo(&tx, note, title, note_tags)
+            } else {
+                import_note(&tx, note, title, folder_id, note_tags, &links)
+            };
+            imported.and_then(|created| tx.commit().map(|_| created).map_err(|e| e.to_string()))
+        };
+
+        match imported {
+            Ok(true) => ctx.created(),
+            Ok(false) => ctx.skipped(),
+            Err(e) => ctx.item_failed(title, &e),
+        }
+        ctx.progress(index + 1, total, title);
+    }
+    Ok(())
+}
+
+// What a note's `:/id` links can point at
+struct Links<'a> {
+    data_dir: &'a Path,
+    archive: &'a Archive,
+    resources: &'a HashMap<&'a str, &'a Item>,
+    titles: &'a HashMap<&'a str, &'a str>,
+}
+
+// Returns false when the note is already there from an earlier import
+fn import_note(
+    conn: &Connection,
+    item: &Item,
+    title: &str,
+    folder_id: Option<&str>,
+    tags: Vec<String>,
+    links: &Links,
+) -> Result<bool, String> {
+    let created_at = item
+        .time("user_created_time", "created_time")
+        .unwrap_or_else(Utc::now);
+    let updated_at = item
+        .time("user_updated_time", "updated_time")
+        .unwrap_or(created_at);
+    if import::imported_before(conn, folder_id, title, &created_at)? {
+        return Ok(false);
+    }
+
+    let note = insert_note(
+        conn,
+        NoteCreate {
+            title: Some(title.to_string()),
+            content: None,
+            folder_id: folder_id.map(String::from),
+            tags: Some(tags),
+            aliases: None,
+        },
+    )?;
+
+    // Resources the body uses become the note's attachments
+    let mut attached: HashMap<String, String> = HashMap::new();
+    for found in item_ref().captures_iter(&item.body) {
+        let id = &found[1];
+        if attached.contains_key(id) {
+            continue;
+        }
+        let (Some(resource), Some(bytes)) =
+            (links.resources.get(id), links.archive.resources.get(id))
+        else {
+            continue;
+        };
+        let mime_type = match resource.prop("mime") {
+            "" => attachments::mime_for_extension(resource.prop("file_extension")).to_string(),
+            mime => mime.to_string(),
+        };
+        let attachment = store_attachment(
+            conn,
+            links.data_dir,
+            note.id.clone(),
+            attachments::kind_for_mime(&mime_type),
+            mime_type,
+            bytes,
+            None,
+        )?;
+        attached.insert(id.to_string(), attachment.id);
+    }
+
+    let html = item.prop("markup_language") == "2";
+    // Links to other notes become wikilinks by title
+    let body = if html {
+        item.body.clone()
+    } else {
+        item_link()
+            .replace_all(&item.body, |link: &Captures| {
+                match links.titles.get(&link[2]) {
+                    Some(title) if !title.contains(['[', ']', '|', '#']) => match link[1].trim() {
+                        "" => format!("[[{}]]", title),
+                        label => format!("[[{}|{}]]", title, label),
+                    },
+                    _ => link[0].to_string(),
+                }
+            })
+            .into_owned()
+    };
+    let body = item_ref().replace_all(&body, |found: &Captures| match attached.get(&found[1]) {
+        Some(id) => format!("attachment://localhost/{}", id),
+        None => found[0].to_string(),
+    });
+    let content = if html {
+        body.into_owned()
+    } else {
+        render::markdown_to_html(&body)
+    };
+
+    import::finish_note(conn, &note.id, &content, &created_at, &updated_at)?;
+    Ok(true)
+}
+
+// A to-do becomes a task, due when the to-do had an alarm; its body is kept
+// as the task's notes
+fn import_todo(
+    conn: &Connection,
+    item: &Item,
+    title: &str,
+    tags: Vec<String>,
+) -> Result<bool, String> {
+    let created_at = item
+        .time("user_created_time", "created_time")
+        .unwrap_or_else(Utc::now);
+    let updated_at = item
+        .time("user_updated_time", "updated_time")
+        .unwrap_or(created_at);
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM events WHERE deleted_at IS NULL
+                                               AND title = ?1 AND created_at = ?2)",
+            params![title, timestamps::format(&created_at)],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Ok(false);
+    }
+
+    let due = timestamp(item.prop("todo_due"));
+    let mut event = new_event(EventCreate {
+        title: title.to_string(),
+        description: None,
+        event_type: Some(EventType::Task),
+        start_time: due,
+        end_time: None,
+        time_mode: Some(if due.is_some() {
+            TimeMode::AtTime
+        } else {
+            TimeMode::Todo
+        }),
+        duration_minutes: None,
+        location: None,
+        category: None,
+        color: None,
+        priority: None,
+        tags: Some(tags),
+        show_on_calendar: Some(due.is_some()),
+        is_all_day: None,
+        is_recurring: None,
+        recurring_pattern: None,
+        reminders: None,
+        notes: Some(item.body.trim().to_string()).filter(|body| !body.is_empty()),
+    });
+    if timestamp(item.prop("todo_completed")).is_some() {
+        event.status = Some(EventStatus::Completed);
+    }
+    event.created_at = created_at;
+    event.updated_at = updated_at;
+    insert_event(conn, &event)?;
+    Ok(true)
+}
@@ -0,0 +1,587 @@
+use crate::db::Database;
+use crate::export;
+use crate::formats;
+use crate::import;
+use crate::models::{Job, JobProgress, JobReport, JobState};
+use crate::search_index;
+use crate::timestamps;
+use chrono::{Duration as ChronoDuration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+pub const PROGRESS_EVENT: &str = "job:progress";
+
+// Running updates closer together than this are dropped; state changes never are
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// Attempts include runs cut short by the app closing, so a job that keeps
+// crashing the app is eventually given up on
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY_SECONDS: i64 = 30;
+
+// The worker also wakes this often to pick up retries whose delay has passed
+const IDLE_POLL: Duration = Duration::from_secs(30);
+
+const KEEP_FINISHED_DAYS: i64 = 14;
+
+const CANCELLED: &str = "Job cancelled";
+
+// What a job does, stored as JSON so queued and interrupted jobs survive a restart
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum JobSpec {
+    #[serde(rename = "export_vault_archive")]
+    VaultArchive { path: PathBuf },
+    #[serde(rename = "export_markdown_folder")]
+    MarkdownFolder { root: PathBuf },
+    #[serde(rename = "export_enex")]
+    Enex { path: PathBuf },
+    // `start` and `end` are YYYY-MM-DD, both inclusive
+    #[serde(rename = "export_events_ics")]
+    EventsIcs {
+        path: PathBuf,
+        start: Option<String>,
+        end: Option<String>,
+    },
+    #[serde(rename = "export_folder_site")]
+    FolderSite { folder_id: String, root: PathBuf },
+    #[serde(rename = "import_note_files")]
+    NoteFiles {
+        paths: Vec<PathBuf>,
+        folder_id: Option<String>,
+    },
+    #[serde(rename = "rebuild_search_index")]
+    RebuildSearchIndex,
+    // `format` is the id of a registered importer or exporter
+    #[serde(rename = "import_format")]
+    Import {
+        format: String,
+        paths: Vec<PathBuf>,
+        folder_id: Option<String>,
+    },
+    #[serde(rename = "export_format")]
+    Export { format: String, path: PathBuf },
+}
+
+impl JobSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobSpec::VaultArchive { .. } => "export_vault_archive",
+            JobSpec::MarkdownFolder { .. } => "export_markdown_folder",
+            JobSpec::Enex { .. } => "export_enex",
+            JobSpec::EventsIcs { .. } => "export_events_ics",
+            JobSpec::FolderSite { .. } => "export_folder_site",
+            JobSpec::NoteFiles { .. } => "import_note_files",
+            JobSpec::RebuildSearchIndex => "rebuild_search_index",
+            JobSpec::Import { .. } => "import_format",
+            JobSpec::Export { .. } => "export_format",
+        }
+    }
+
+    // Must be safe to run again from the start after an interruption
+    fn run(&self, ctx: &JobContext) -> Result<Option<String>, String> {
+        match self {
+            JobSpec::VaultArchive { path } => export::vault_archive(ctx, path),
+            JobSpec::MarkdownFolder { root } => export::markdown_folder(ctx, root),
+            JobSpec::Enex { path } => export::enex(ctx, path),
+            JobSpec::EventsIcs { path, start, end } => {
+                export::events_ics(ctx, path, start.as_deref(), end.as_deref())
+            }
+            JobSpec::FolderSite { folder_id, root } => export::folder_site(ctx, folder_id, root),
+            JobSpec::NoteFiles { paths, folder_id } => {
+                import::note_files(ctx, paths, folder_id.as_deref())
+            }
+            JobSpec::RebuildSearchIndex => search_index::rebuild(ctx),
+            JobSpec::Import {
+                format,
+                paths,
+                folder_id,
+            } => formats::importer(format)?.import(ctx, paths, folder_id.as_deref()),
+            JobSpec::Export { format, path } => formats::exporter(format)?.export(ctx, path),
+        }
+    }
+}
+
+// Managed as app state next to the database
+#[derive(Default)]
+pub struct Jobs {
+    // Cancel flag of each job currently running
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    wake: Notify,
+}
+
+impl Jobs {
+    fn register(&self, id: &str) -> Result<Arc<AtomicBool>, String> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.running
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(id.to_string(), flag.clone());
+        Ok(flag)
+    }
+
+    fn finish(&self, id: &str) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(id);
+        }
+    }
+}
+
+fn emit(app: &AppHandle, progress: JobProgress) {
+    if let Err(e) = app.emit(PROGRESS_EVENT, progress) {
+        log::warn!("Failed to emit job progress: {}", e);
+    }
+}
+
+// Handed to the job body for reporting progress and noticing cancellation
+pub struct JobContext {
+    id: String,
+    kind: String,
+    app: AppHandle,
+    cancel: Arc<AtomicBool>,
+    done: Cell<u32>,
+    total: Cell<u32>,
+    last_emit: Cell<Option<Instant>>,
+    report: RefCell<JobReport>,
+}
+
+impl JobContext {
+    pub fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    // Throttled like the events, and also written to the jobs row so
+    // list_jobs shows where a job got to
+    pub fn progress(&self, done: usize, total: usize, message: &str) {
+        self.done.set(done as u32);
+        self.total.set(total as u32);
+
+        let due = match self.last_emit.get() {
+            Some(last) => last.elapsed() >= PROGRESS_INTERVAL,
+            None => true,
+        };
+        if !due && done < total {
+            return;
+        }
+        self.last_emit.set(Some(Instant::now()));
+
+        let db = self.app.state::<Database>();
+        if let Ok(conn) = db.conn.lock() {
+            let _ = conn.execute(
+                "UPDATE jobs SET done = ?1, total = ?2, message = ?3, updated_at = ?4 WHERE id = ?5",
+                params![
+                    done as u32,
+                    total as u32,
+                    message,
+                    timestamps::format(&Utc::now()),
+                    self.id
+                ],
+            );
+        }
+        self.emit(
+            JobState::Running,
+            Some(message.to_string()),
+            None,
+            None,
+            None,
+        );
+    }
+
+    // Tallies for the report the job finishes with
+    pub fn created(&self) {
+        self.report.borrow_mut().created += 1;
+    }
+
+    pub fn updated(&self) {
+        self.report.borrow_mut().updated += 1;
+    }
+
+    pub fn skipped(&self) {
+        self.report.borrow_mut().skipped += 1;
+    }
+
+    // An item that couldn't be processed; the job carries on with the rest
+    pub fn item_failed(&self, item: &str, error: &str) {
+        self.report
+            .borrow_mut()
+            .errors
+            .push(format!("{}: {}", item, error));
+    }
+
+    // Call between items; turns a cancel_job request into an early return
+    pub fn checkpoint(&self) -> Result<(), String> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED.to_string());
+        }
+        Ok(())
+    }
+
+    fn emit(
+        &self,
+        state: JobState,
+        message: Option<String>,
+        output: Option<String>,
+        error: Option<String>,
+        report: Option<JobReport>,
+    ) {
+        emit(
+            &self.app,
+            JobProgress {
+                id: self.id.clone(),
+                kind: self.kind.clone(),
+                state,
+                done: self.done.get(),
+                total: self.total.get(),
+                message,
+                output,
+                error,
+                report,
+            },
+        );
+    }
+}
+
+// ============ Queue ============
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<Job> {
+    Ok(Job {
+        id: row.get("id")?,
+        kind: row.get("kind")?,
+        state: row.get("state")?,
+        attempts: row.get("attempts")?,
+        done: row.get("done")?,
+        total: row.get("total")?,
+        message: row.get("message")?,
+        output: row.get("output")?,
+        error: row.get("error")?,
+        report: row
+            .get::<_, Option<String>>("report")?
+            .and_then(|report| serde_json::from_str(&report).ok()),
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+pub fn list(conn: &Connection, limit: u32) -> Result<Vec<Job>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, kind, state, attempts, done, total, message, output, error, created_at,
+                    updated_at, report
+             FROM jobs ORDER BY created_at DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![limit], row_to_job)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// Queues the job and returns its id; the worker picks it up right away if idle
+pub fn enqueue(app: &AppHandle, spec: JobSpec) -> Result<String, String> {
+    let id = format!("job_{}", Uuid::new_v4());
+    let now = timestamps::format(&Utc::now());
+    let json = serde_json::to_string(&spec).map_err(|e| e.to_string())?;
+
+    {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO jobs (id, kind, spec, state, run_after, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?5)",
+            params![id, spec.kind(), json, JobState::Queued, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    emit(
+        app,
+        JobProgress {
+            id: id.clone(),
+            kind: spec.kind().to_string(),
+            state: JobState::Queued,
+            done: 0,
+            total: 0,
+            message: None,
+            output: None,
+            error: None,
+            report: None,
+        },
+    );
+    app.state::<Jobs>().wake.notify_one();
+
+    Ok(id)
+}
+
+// Queued jobs are cancelled on the spot; running ones stop at their next
+// checkpoint. Returns false if the job had already finished.
+pub fn cancel(app: &AppHandle, id: &str) -> Result<bool, String> {
+    if let Some(flag) = app
+        .state::<Jobs>()
+        .running
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(id)
+    {
+        flag.store(true, Ordering::Relaxed);
+        return Ok(true);
+    }
+
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let cancelled = conn
+        .query_row(
+            "UPDATE jobs SET state = ?1, updated_at = ?2 WHERE id = ?3 AND state = ?4
+             RETURNING kind, done, total",
+            params![
+                JobState::Cancelled,
+                timestamps::format(&Utc::now()),
+                id,
+                JobState::Queued
+            ],
+            |row| {
+                Ok(JobProgress {
+                    id: id.to_string(),
+                    kind: row.get(0)?,
+                    state: JobState::Cancelled,
+                    done: row.get(1)?,
+                    total: row.get(2)?,
+                    message: None,
+                    output: None,
+                    error: None,
+                    report: None,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    Ok(match cancelled {
+        Some(progress) => {
+            emit(app, progress);
+            true
+        }
+        None => false,
+    })
+}
+
+// Jobs still marked running were cut off by the app closing: put them back
+// in the queue, unless they've used up their attempts. Old finished jobs are
+// dropped here too.
+fn recover(conn: &Connection) -> Result<(), String> {
+    let now = Utc::now();
+
+    conn.execute(
+        "UPDATE jobs SET state = ?1, error = 'Interrupted too many times', updated_at = ?2
+         WHERE state = ?3 AND attempts >= ?4",
+        params![
+            JobState::Failed,
+            timestamps::format(&now),
+            JobState::Running,
+            MAX_ATTEMPTS
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE jobs SET state = ?1, updated_at = ?2 WHERE state = ?3",
+        params![
+            JobState::Queued,
+            timestamps::format(&now),
+            JobState::Running
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let cutoff = timestamps::format(&(now - ChronoDuration::days(KEEP_FINISHED_DAYS)));
+    conn.execute(
+        "DELETE FROM jobs WHERE state IN (?1, ?2, ?3) AND updated_at < ?4",
+        params![
+            JobState::Completed,
+            JobState::Failed,
+            JobState::Cancelled,
+            cutoff
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+struct Claimed {
+    id: String,
+    kind: String,
+    spec: Result<JobSpec, String>,
+    attempts: u32,
+}
+
+// Marks the oldest due job as running
+fn claim_next(conn: &Connection) -> Result<Option<Claimed>, String> {
+    let now = timestamps::format(&Utc::now());
+
+    let next = conn
+        .query_row(
+            "SELECT id, kind, spec, attempts FROM jobs
+             WHERE state = ?1 AND run_after <= ?2
+             ORDER BY created_at LIMIT 1",
+            params![JobState::Queued, now],
+            |row| {
+                let spec: String = row.get(2)?;
+                Ok(Claimed {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    spec: serde_json::from_str(&spec).map_err(|e| e.to_string()),
+                    attempts: row.get::<_, u32>(3)? + 1,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(job) = &next {
+        conn.execute(
+            "UPDATE jobs SET state = ?1, attempts = ?2, done = 0, total = 0, message = NULL,
+                             updated_at = ?3
+             WHERE id = ?4",
+            params![JobState::Running, job.attempts, now, job.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(next)
+}
+
+// Runs one claimed job to completion on the current (blocking) thread and
+// records the outcome: done, cancelled, queued again for a retry, or failed.
+fn execute(app: AppHandle, job: Claimed) {
+    let Ok(cancel) = app.state::<Jobs>().register(&job.id) else {
+        return;
+    };
+    let ctx = JobContext {
+        id: job.id,
+        kind: job.kind,
+        app,
+        cancel,
+        done: Cell::new(0),
+        total: Cell::new(0),
+        last_emit: Cell::new(None),
+        report: RefCell::new(JobReport::default()),
+    };
+    ctx.emit(JobState::Running, None, None, None, None);
+
+    let result = job.spec.and_then(|spec| spec.run(&ctx));
+    ctx.app.state::<Jobs>().finish(&ctx.id);
+
+    let now = Utc::now();
+    let (state, output, error, run_after) = match result {
+        Ok(output) => (JobState::Completed, output, None, now),
+        Err(_) if ctx.cancel.load(Ordering::Relaxed) => (JobState::Cancelled, None, None, now),
+        Err(e) if job.attempts < MAX_ATTEMPTS => {
+            log::warn!("Job {} ({}) failed, will retry: {}", ctx.id, ctx.kind, e);
+            let delay = RETRY_DELAY_SECONDS << (job.attempts - 1);
+            (
+                JobState::Queued,
+                None,
+                Some(e),
+                now + ChronoDuration::seconds(delay),
+            )
+        }
+        Err(e) => {
+            log::warn!("Job {} ({}) failed: {}", ctx.id, ctx.kind, e);
+            (JobState::Failed, None, Some(e), now)
+        }
+    };
+
+    // Kept for cancelled and failed runs too, showing how far they got
+    let report = ctx.report.take();
+    let db = ctx.app.state::<Database>();
+    if let Ok(conn) = db.conn.lock() {
+        if let Err(e) = conn.execute(
+            "UPDATE jobs SET state = ?1, output = ?2, error = ?3, report = ?4, run_after = ?5,
+                             updated_at = ?6
+             WHERE id = ?7",
+            params![
+                state,
+                output,
+                error,
+                serde_json::to_string(&report).ok(),
+                timestamps::format(&run_after),
+                timestamps::format(&now),
+                ctx.id
+            ],
+        ) {
+            log::warn!("Failed to record outcome of job {}: {}", ctx.id, e);
+        }
+    }
+
+    ctx.emit(state, None, output, error, Some(report));
+}
+
+// A panic skips execute's bookkeeping, so the job is failed here instead of
+// sitting in the running state until the next launch
+fn abandon(app: &AppHandle, id: &str) {
+    app.state::<Jobs>().finish(id);
+
+    let db = app.state::<Database>();
+    let Ok(conn) = db.conn.lock() else {
+        return;
+    };
+    let _ = conn.execute(
+        "UPDATE jobs SET state = ?1, error = 'Job panicked', updated_at = ?2 WHERE id = ?3",
+        params![JobState::Failed, timestamps::format(&Utc::now()), id],
+    );
+}
+
+// One job at a time, in the order they were queued
+pub fn spawn_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        {
+            let db = app.state::<Database>();
+            let recovered = db
+                .conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| recover(&conn));
+            if let Err(e) = recovered {
+                log::warn!("Failed to recover interrupted jobs: {}", e);
+            }
+        }
+
+        loop {
+            let next = {
+                let db = app.state::<Database>();
+                let conn = db.conn.lock().map_err(|e| e.to_string());
+                conn.and_then(|conn| claim_next(&conn))
+            };
+
+            match next {
+                Ok(Some(job)) => {
+                    let id = job.id.clone();
+                    let handle = app.clone();
+                    if let Err(e) =
+                        tauri::async_runtime::spawn_blocking(move || execute(handle, job)).await
+                    {
+                        log::warn!("Job {} panicked: {}", id, e);
+                        abandon(&app, &id);
+                    }
+                }
+                Ok(None) => {
+                    let _ =
+                        tokio::time::timeout(IDLE_POLL, app.state::<Jobs>().wake.notified()).await;
+                }
+                Err(e) => {
+                    log::warn!("Failed to claim next job: {}", e);
+                    tokio::time::sleep(IDLE_POLL).await;
+                }
+            }
+        }
+    });
+}
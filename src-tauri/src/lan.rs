@@ -0,0 +1,643 @@
+use crate::background;
+use crate::db::Database;
+use crate::email::KEYRING_SERVICE;
+use crate::models::{
+    BackgroundSubsystem, LanPeer, LanSyncConfig, LanSyncResult, RemoteChanges, SyncReport,
+};
+use crate::sync;
+use crate::timestamps;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use mdns_sd::{Receiver, ServiceDaemon, ServiceEvent, ServiceInfo};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+pub const LAN_SYNC_SETTING: &str = "lan_sync";
+pub const RECEIVED_EVENT: &str = "lan-sync:received";
+const DEVICE_ID_SETTING: &str = "lan_device_id";
+const SALT_SETTING: &str = "lan_salt";
+const SERVICE_TYPE: &str = "_voyena-sync._tcp.local.";
+const PBKDF2_ITERATIONS: u32 = 250_000;
+const MAX_MESSAGE_BYTES: usize = 256 * 1024 * 1024;
+// Until the handshake is done the peer hasn't shown it knows the passphrase,
+// so it only gets to send a greeting and a signature
+const MAX_HANDSHAKE_BYTES: usize = 4 * 1024;
+const MAX_HANDSHAKES: usize = 4;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(15);
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const EXCHANGE_TIMEOUT: Duration = Duration::from_secs(300);
+
+// ============ Configuration ============
+
+pub fn load_config(conn: &Connection) -> Result<Option<LanSyncConfig>, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![LAN_SYNC_SETTING],
+            |row| row.get(0),
+        )
+        .ok();
+
+    value
+        .map(|v| serde_json::from_str(&v).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// Made up the first time it's needed and kept, so peers recognise this
+// device after a rename
+fn device_id(conn: &Connection) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![DEVICE_ID_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    let id = format!("device_{}", Uuid::new_v4());
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+        params![DEVICE_ID_SETTING, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+// Random bytes made up once per install and mixed into the key, so guesses at
+// the passphrase can't be worked out ahead of time for every device at once
+fn salt(conn: &Connection) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![SALT_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(salt) = existing {
+        return Ok(salt);
+    }
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    let salt = BASE64.encode(bytes);
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+        params![SALT_SETTING, salt],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(salt)
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, "lan-sync").map_err(|e| e.to_string())
+}
+
+pub fn store_passphrase(passphrase: &str) -> Result<(), String> {
+    keyring_entry()?
+        .set_password(passphrase)
+        .map_err(|e| e.to_string())
+}
+
+fn load_passphrase() -> Result<String, String> {
+    keyring_entry()?
+        .get_password()
+        .map_err(|e| format!("No LAN sync passphrase in keychain: {}", e))
+}
+
+// From the passphrase and both devices' salts. Slow on purpose, so it runs
+// off the async threads.
+async fn derive_key(passphrase: &str, client: &Hello, server: &Hello) -> Result<[u8; 32], String> {
+    let passphrase = passphrase.to_string();
+    let salt = [client.salt.as_bytes(), server.salt.as_bytes()].concat();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, PBKDF2_ITERATIONS, &mut key);
+        key
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ============ Peers ============
+
+// Devices mDNS currently sees, managed as app state
+#[derive(Default)]
+pub struct LanPeers {
+    found: Mutex<HashMap<String, LanPeer>>,
+}
+
+impl LanPeers {
+    fn get(&self, device_id: &str) -> Option<LanPeer> {
+        self.found.lock().ok()?.get(device_id).cloned()
+    }
+
+    fn insert(&self, peer: LanPeer) {
+        if let Ok(mut found) = self.found.lock() {
+            found.insert(peer.device_id.clone(), peer);
+        }
+    }
+
+    fn remove(&self, device_id: &str) {
+        if let Ok(mut found) = self.found.lock() {
+            found.remove(device_id);
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut found) = self.found.lock() {
+            found.clear();
+        }
+    }
+
+    // By name, with when each was last synced
+    pub fn list(&self, conn: &Connection) -> Result<Vec<LanPeer>, String> {
+        let mut peers: Vec<LanPeer> = self
+            .found
+            .lock()
+            .map_err(|e| e.to_string())?
+            .values()
+            .cloned()
+            .collect();
+        for peer in &mut peers {
+            peer.last_synced_at = conn
+                .query_row(
+                    "SELECT last_synced_at FROM lan_peers WHERE device_id = ?1",
+                    params![peer.device_id],
+                    |row| timestamps::get_opt(row, 0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?
+                .flatten();
+        }
+        peers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(peers)
+    }
+}
+
+// Keeps the peer list in step with mDNS until the daemon shuts down
+fn watch(app: &AppHandle, events: Receiver<ServiceEvent>, own_id: &str) {
+    let peers = app.state::<LanPeers>();
+    while let Ok(event) = events.recv() {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let Some(id) = info.get_property_val_str("id").filter(|id| *id != own_id) else {
+                    continue;
+                };
+                // IPv4 first; link-local IPv6 would need a scope
+                let Some(ip) = info.get_addresses().iter().min_by_key(|ip| ip.is_ipv6()) else {
+                    continue;
+                };
+                peers.insert(LanPeer {
+                    device_id: id.to_string(),
+                    name: info.get_property_val_str("name").unwrap_or(id).to_string(),
+                    address: SocketAddr::new(*ip, info.get_port()).to_string(),
+                    last_synced_at: None,
+                });
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                if let Some(id) = fullname.strip_suffix(&format!(".{}", SERVICE_TYPE)) {
+                    peers.remove(id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// ============ Channel ============
+
+// Length-prefixed JSON messages. After the handshake each one is sealed with
+// AES-256-GCM under a key only holders of the passphrase can derive.
+struct Channel {
+    stream: TcpStream,
+    cipher: Option<Aes256Gcm>,
+    // 0 on the connecting side, 1 on the listening side; keeps the two
+    // directions' nonces apart
+    side: u8,
+    sent: u64,
+    received: u64,
+}
+
+fn nonce(side: u8, counter: u64) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[0] = side;
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+impl Channel {
+    fn new(stream: TcpStream, side: u8) -> Self {
+        Self {
+            stream,
+            cipher: None,
+            side,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    async fn send<T: Serialize>(&mut self, message: &T) -> Result<(), String> {
+        let mut bytes = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        if let Some(cipher) = &self.cipher {
+            let nonce = nonce(self.side, self.sent);
+            self.sent += 1;
+            bytes = cipher
+                .encrypt(Nonce::from_slice(&nonce), bytes.as_slice())
+                .map_err(|e| e.to_string())?;
+        }
+        self.stream
+            .write_u32(bytes.len() as u32)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.stream
+            .write_all(&bytes)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn recv<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+        let len = self.stream.read_u32().await.map_err(|e| e.to_string())? as usize;
+        let max = match self.cipher {
+            Some(_) => MAX_MESSAGE_BYTES,
+            None => MAX_HANDSHAKE_BYTES,
+        };
+        if len > max {
+            return Err("Message from peer is too large".into());
+        }
+        let mut bytes = vec![0u8; len];
+        self.stream
+            .read_exact(&mut bytes)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(cipher) = &self.cipher {
+            let nonce = nonce(1 - self.side, self.received);
+            self.received += 1;
+            bytes = cipher
+                .decrypt(Nonce::from_slice(&nonce), bytes.as_slice())
+                .map_err(|_| "Message from peer failed to decrypt")?;
+        }
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Hello {
+    device_id: String,
+    name: String,
+    nonce: String,
+    salt: String,
+}
+
+fn hello(conn: &Connection, config: &LanSyncConfig) -> Result<Hello, String> {
+    let mut nonce = [0u8; 16];
+    OsRng.fill_bytes(&mut nonce);
+    Ok(Hello {
+        device_id: device_id(conn)?,
+        name: config.device_name.clone(),
+        nonce: BASE64.encode(nonce),
+        salt: salt(conn)?,
+    })
+}
+
+// Keyed on the passphrase and bound to this connection's nonces, so it
+// can't be replayed
+fn proof(key: &[u8], label: &str, client: &Hello, server: &Hello) -> Result<Hmac<Sha256>, String> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).map_err(|e| e.to_string())?;
+    mac.update(label.as_bytes());
+    mac.update(client.nonce.as_bytes());
+    mac.update(server.nonce.as_bytes());
+    Ok(mac)
+}
+
+fn sign(key: &[u8], label: &str, client: &Hello, server: &Hello) -> Result<String, String> {
+    Ok(BASE64.encode(proof(key, label, client, server)?.finalize().into_bytes()))
+}
+
+fn check(
+    key: &[u8],
+    label: &str,
+    client: &Hello,
+    server: &Hello,
+    signature: &str,
+) -> Result<(), String> {
+    let signature = BASE64.decode(signature).map_err(|e| e.to_string())?;
+    proof(key, label, client, server)?
+        .verify_slice(&signature)
+        .map_err(|_| "The other device has a different sync passphrase".to_string())
+}
+
+fn seal(channel: &mut Channel, key: &[u8], client: &Hello, server: &Hello) -> Result<(), String> {
+    let session = proof(key, "session", client, server)?
+        .finalize()
+        .into_bytes();
+    channel.cipher = Some(Aes256Gcm::new_from_slice(&session).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+// Both sides prove they know the passphrase before anything else is sent.
+// The connecting side goes first, so a device that merely connects gets
+// nothing back it could test passphrase guesses against.
+async fn open(stream: TcpStream, passphrase: &str, me: Hello) -> Result<(Channel, Hello), String> {
+    let mut channel = Channel::new(stream, 0);
+    channel.send(&me).await?;
+    let peer: Hello = channel.recv().await?;
+    let key = derive_key(passphrase, &me, &peer).await?;
+    channel.send(&sign(&key, "client", &me, &peer)?).await?;
+    let signature: String = channel.recv().await?;
+    check(&key, "server", &me, &peer, &signature)?;
+    seal(&mut channel, &key, &me, &peer)?;
+    Ok((channel, peer))
+}
+
+async fn accept(
+    stream: TcpStream,
+    passphrase: &str,
+    me: Hello,
+) -> Result<(Channel, Hello), String> {
+    let mut channel = Channel::new(stream, 1);
+    let peer: Hello = channel.recv().await?;
+    channel.send(&me).await?;
+    let key = derive_key(passphrase, &peer, &me).await?;
+    let signature: String = channel.recv().await?;
+    check(&key, "client", &peer, &me, &signature)?;
+    channel.send(&sign(&key, "server", &peer, &me)?).await?;
+    seal(&mut channel, &key, &peer, &me)?;
+    Ok((channel, peer))
+}
+
+// ============ Exchange ============
+
+// Everything changed since the peer last confirmed it merged our changes
+fn outgoing(
+    db: &Database,
+    config: &LanSyncConfig,
+    peer_id: &str,
+    now: &str,
+) -> Result<RemoteChanges, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let since: Option<String> = conn
+        .query_row(
+            "SELECT last_sent_at FROM lan_peers WHERE device_id = ?1",
+            params![peer_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    sync::changes_since(&conn, &config.device_name, since.as_deref(), now)
+}
+
+fn incoming(
+    db: &Database,
+    peer: &Hello,
+    changes: RemoteChanges,
+    now: &DateTime<Utc>,
+) -> Result<SyncReport, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let report = sync::apply(&tx, changes, now)?;
+    tx.execute(
+        "INSERT INTO lan_peers (device_id, name, last_synced_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(device_id) DO UPDATE SET name = ?2, last_synced_at = ?3",
+        params![peer.device_id, peer.name, timestamps::format(now)],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+fn mark_sent(db: &Database, peer_id: &str, at: &str) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE lan_peers SET last_sent_at = ?1 WHERE device_id = ?2",
+        params![at, peer_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn count(changes: &RemoteChanges) -> u32 {
+    (changes.notes.len() + changes.events.len() + changes.snippets.len()) as u32
+}
+
+// Sends this device's changes to a peer found on the network and merges the
+// ones it sends back
+pub async fn sync_with(
+    db: &Database,
+    peers: &LanPeers,
+    device_id: &str,
+) -> Result<LanSyncResult, String> {
+    let (config, me) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let config = load_config(&conn)?
+            .filter(|c| c.enabled)
+            .ok_or("LAN sync is not enabled")?;
+        let me = hello(&conn, &config)?;
+        (config, me)
+    };
+    let peer = peers
+        .get(device_id)
+        .ok_or("That device isn't on the network right now")?;
+    let passphrase = load_passphrase()?;
+
+    let exchange = async {
+        let stream = TcpStream::connect(&peer.address)
+            .await
+            .map_err(|e| e.to_string())?;
+        let (mut channel, remote) = open(stream, &passphrase, me).await?;
+        if remote.device_id != peer.device_id {
+            return Err("A different device answered at that address".to_string());
+        }
+
+        let now = timestamps::format(&Utc::now());
+        let changes = outgoing(db, &config, &remote.device_id, &now)?;
+        let sent = count(&changes);
+        channel.send(&changes).await?;
+        let theirs: RemoteChanges = channel.recv().await?;
+        let received = incoming(db, &remote, theirs, &timestamps::now())?;
+        // The peer merged ours before replying
+        mark_sent(db, &remote.device_id, &now)?;
+        channel.send(&received).await?;
+
+        Ok(LanSyncResult {
+            peer: remote.name,
+            sent,
+            received,
+        })
+    };
+    tokio::time::timeout(EXCHANGE_TIMEOUT, exchange)
+        .await
+        .map_err(|_| "LAN sync timed out".to_string())?
+}
+
+// The other half of `sync_with`, for a peer that connected to us. `permit`
+// is one of the handshake slots, given back once the peer has proved itself.
+async fn answer(
+    db: &Database,
+    stream: TcpStream,
+    passphrase: &str,
+    config: &LanSyncConfig,
+    permit: OwnedSemaphorePermit,
+) -> Result<LanSyncResult, String> {
+    let me = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        hello(&conn, config)?
+    };
+    let (mut channel, remote) =
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, accept(stream, passphrase, me))
+            .await
+            .map_err(|_| "LAN sync handshake timed out".to_string())??;
+    drop(permit);
+
+    let theirs: RemoteChanges = channel.recv().await?;
+    let received = incoming(db, &remote, theirs, &timestamps::now())?;
+    let now = timestamps::format(&Utc::now());
+    let changes = outgoing(db, config, &remote.device_id, &now)?;
+    let sent = count(&changes);
+    channel.send(&changes).await?;
+    // Only counts as sent once the peer reports it merged them
+    let _: SyncReport = channel.recv().await?;
+    mark_sent(db, &remote.device_id, &now)?;
+
+    Ok(LanSyncResult {
+        peer: remote.name,
+        sent,
+        received,
+    })
+}
+
+async fn serve(
+    app: AppHandle,
+    stream: TcpStream,
+    passphrase: Arc<str>,
+    config: LanSyncConfig,
+    permit: OwnedSemaphorePermit,
+) {
+    let db = app.state::<Database>();
+    let answer = answer(&db, stream, &passphrase, &config, permit);
+    let answered = tokio::time::timeout(EXCHANGE_TIMEOUT, answer)
+        .await
+        .unwrap_or_else(|_| Err("LAN sync timed out".to_string()));
+    match answered {
+        Ok(result) => {
+            if let Err(e) = app.emit(RECEIVED_EVENT, result) {
+                log::warn!("Failed to emit LAN sync result: {}", e);
+            }
+        }
+        Err(e) => log::warn!("LAN sync from a peer failed: {}", e),
+    }
+}
+
+// ============ Service ============
+
+// The config and passphrase when LAN sync is on and set up, and background
+// activity isn't turned down; a running service stops once this changes
+fn enabled(app: &AppHandle) -> Option<(LanSyncConfig, String)> {
+    if background::is_paused(app, BackgroundSubsystem::LanSync) {
+        return None;
+    }
+    let config = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().ok()?;
+        load_config(&conn).ok().flatten()?
+    };
+    if !config.enabled {
+        return None;
+    }
+    Some((config, load_passphrase().ok()?))
+}
+
+// Listens and advertises this device until LAN sync is turned off or
+// reconfigured
+async fn run(app: &AppHandle, config: &LanSyncConfig, passphrase: &str) -> Result<(), String> {
+    let shared: Arc<str> = Arc::from(passphrase);
+    let handshakes = Arc::new(Semaphore::new(MAX_HANDSHAKES));
+    let device_id = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        device_id(&conn)?
+    };
+    let listener = TcpListener::bind(("0.0.0.0", config.port.unwrap_or(0)))
+        .await
+        .map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let mdns = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let properties = [
+        ("id", device_id.as_str()),
+        ("name", config.device_name.as_str()),
+    ];
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &device_id,
+        &format!("{}.local.", device_id),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+    mdns.register(service).map_err(|e| e.to_string())?;
+    let events = mdns.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    let watcher = app.clone();
+    std::thread::spawn(move || watch(&watcher, events, &device_id));
+
+    let current = Some((config.clone(), passphrase.to_string()));
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                // Connections past the limit are dropped unanswered
+                Ok((stream, address)) => match handshakes.clone().try_acquire_owned() {
+                    Ok(permit) => {
+                        let task =
+                            serve(app.clone(), stream, shared.clone(), config.clone(), permit);
+                        tauri::async_runtime::spawn(task);
+                    }
+                    Err(_) => log::warn!("Too many LAN sync handshakes; turned away {}", address),
+                },
+                Err(e) => break Err(e.to_string()),
+            },
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {
+                if enabled(app) != current {
+                    break Ok(());
+                }
+            }
+        }
+    };
+    let _ = mdns.shutdown();
+    result
+}
+
+pub fn spawn_service(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::LanSync).await;
+            if let Some((config, passphrase)) = enabled(&app) {
+                if let Err(e) = run(&app, &config, &passphrase).await {
+                    log::warn!("LAN sync stopped: {}", e);
+                }
+                app.state::<LanPeers>().clear();
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
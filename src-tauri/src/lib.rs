@@ -1,20 +1,403 @@
+mod actions;
+mod apple_notes;
+mod attachments;
+mod background;
+mod capture;
+mod clipboard_capture;
 mod commands;
+mod comments;
+mod crdt;
+mod daily_notes;
+mod date_parse;
 mod db;
+mod deep_links;
+mod dependencies;
+mod email;
+mod embeds;
+mod error;
+mod export;
+mod feeds;
+mod folder_watch;
+mod folders;
+mod formats;
+mod front_matter;
+mod goals;
+mod history;
+mod holidays;
+mod ics;
+mod import;
+mod integrity;
+mod jex;
+mod jobs;
+mod lan;
+mod language;
+mod links;
+mod locale;
+mod maintenance;
+mod map_formats;
+mod map_image;
+mod metadata;
+mod mirror;
 mod models;
+mod note_sort;
+mod outline;
+mod palette;
+mod panics;
+mod partial_update;
+mod platform;
+mod profiling;
+mod projects;
+mod publish;
+mod queries;
+mod quick_add;
+mod recovery;
+mod recurrence;
+mod reminders;
+mod render;
+mod review;
+mod revisions;
+mod sanitize;
+mod scripting;
+mod search_index;
+mod search_query;
+mod seed;
+mod share;
+mod share_target;
+mod snippets;
+mod startup;
+mod storage;
+mod sync;
+mod system_search;
+mod task_csv;
+mod timestamps;
+mod today;
+mod trash;
+mod validation;
+mod write_behind;
 
-use db::Database;
-use tauri::Manager;
+#[cfg(debug_assertions)]
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use tauri::{Manager, RunEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri_specta::Builder::<tauri::Wry>::new().commands(tauri_specta::collect_commands![
+        // Notes
+        commands::get_notes,
+        commands::get_note_sort,
+        commands::get_note,
+        commands::create_note,
+        commands::update_note,
+        commands::delete_note,
+        commands::restore_note,
+        commands::get_deleted_note_preview,
+        commands::move_notes_to_folder,
+        commands::reorder_notes,
+        commands::get_note_properties,
+        commands::set_note_property,
+        // Note comments
+        commands::get_note_comments,
+        commands::add_note_comment,
+        commands::update_note_comment,
+        commands::delete_note_comment,
+        // Search
+        commands::search_notes,
+        commands::get_backlinks,
+        commands::check_note_links,
+        commands::resolve_note_by_title_or_alias,
+        commands::resolve_embeds,
+        commands::get_note_outline,
+        commands::get_link_settings,
+        commands::set_link_settings,
+        commands::get_note_revisions,
+        commands::get_note_bookmarks,
+        commands::rebuild_search_index,
+        commands::record_note_open,
+        commands::get_search_ranking,
+        commands::set_search_ranking,
+        commands::get_smart_folders,
+        commands::create_smart_folder,
+        commands::update_smart_folder,
+        commands::delete_smart_folder,
+        commands::get_smart_folder_notes,
+        // Folders
+        commands::get_folders,
+        commands::create_folder,
+        commands::update_folder,
+        commands::move_folder,
+        commands::delete_folder,
+        commands::get_folder_path,
+        commands::resolve_folder_path,
+        // Events
+        commands::get_events,
+        commands::get_event,
+        commands::get_events_by_type,
+        commands::create_event,
+        commands::update_event,
+        commands::delete_event,
+        commands::duplicate_event,
+        commands::create_event_like_last,
+        commands::shift_events,
+        commands::move_events_to_date,
+        commands::get_unscheduled_events,
+        commands::schedule_event,
+        commands::set_event_travel_time,
+        // Locale
+        commands::get_locale_settings,
+        commands::set_locale_settings,
+        commands::get_week_range,
+        commands::get_agenda,
+        commands::get_today_view,
+        // Recurring tasks
+        commands::set_task_recurrence,
+        commands::get_task_history,
+        commands::complete_task_occurrence,
+        commands::reopen_task_occurrence,
+        // Task dependencies
+        commands::add_task_dependency,
+        commands::remove_task_dependency,
+        commands::get_task_plan,
+        // Projects
+        commands::get_projects,
+        commands::create_project,
+        commands::update_project,
+        commands::delete_project,
+        commands::link_project_item,
+        commands::unlink_project_item,
+        commands::get_project_overview,
+        // Goals
+        commands::get_goals,
+        commands::create_goal,
+        commands::update_goal,
+        commands::delete_goal,
+        commands::link_goal_item,
+        commands::unlink_goal_item,
+        commands::get_goal_progress,
+        commands::export_events_ics,
+        commands::export_folder_site,
+        // Date parsing
+        commands::parse_date_expression,
+        // Inbox
+        commands::quick_add,
+        commands::get_inbox_items,
+        commands::triage_inbox_item,
+        // Productivity
+        commands::get_productivity_metrics,
+        // Holidays
+        commands::get_holiday_regions,
+        commands::set_holiday_regions,
+        commands::get_holidays,
+        // Reviews
+        commands::generate_weekly_review,
+        // Brain Maps
+        commands::get_brain_maps,
+        commands::get_brain_map,
+        commands::get_brain_map_visible,
+        commands::get_brain_map_stats,
+        commands::create_brain_map,
+        commands::update_brain_map,
+        commands::update_brain_map_viewport,
+        commands::delete_brain_map,
+        commands::restore_brain_map,
+        commands::export_brain_map_image,
+        commands::create_brain_map_node,
+        commands::update_brain_map_node,
+        commands::delete_brain_map_node,
+        commands::update_node_positions,
+        commands::get_nodes_linked_to_note,
+        commands::link_note_to_node,
+        commands::create_brain_map_connection,
+        commands::get_connections_between,
+        commands::delete_brain_map_connection,
+        commands::undo_brain_map_op,
+        commands::redo_brain_map_op,
+        // Style presets
+        commands::get_style_presets,
+        commands::create_style_preset,
+        commands::delete_style_preset,
+        commands::apply_style_to_subtree,
+        // Metadata
+        commands::get_metadata,
+        commands::set_metadata,
+        // Palette
+        commands::get_color_palette,
+        commands::add_palette_color,
+        commands::remove_palette_color,
+        commands::get_icon_catalog,
+        commands::add_catalog_icon,
+        commands::remove_catalog_icon,
+        // Settings
+        commands::get_setting,
+        commands::set_setting,
+        // Attachments
+        commands::add_attachment,
+        commands::get_attachments,
+        commands::save_audio_memo,
+        commands::get_audio_memos,
+        commands::delete_attachment,
+        commands::get_attachment_dedup_stats,
+        commands::get_storage_usage,
+        commands::set_storage_settings,
+        // Sharing
+        commands::share_note_bundle,
+        commands::publish_note,
+        commands::unpublish_note,
+        commands::get_publish_target,
+        commands::set_publish_target,
+        // Email capture
+        commands::get_email_capture_config,
+        commands::set_email_capture_config,
+        commands::poll_email_inbox,
+        // Feeds
+        commands::get_feeds,
+        commands::add_feed,
+        commands::delete_feed,
+        commands::get_feed_items,
+        commands::mark_feed_item_read,
+        commands::refresh_feeds,
+        commands::save_feed_item_as_note,
+        // Web capture
+        commands::capture_url,
+        commands::convert_html_to_markdown,
+        // Files
+        commands::export_note_to_file,
+        commands::import_notes_from_files,
+        commands::backup_database,
+        // Jobs
+        commands::export_vault_archive,
+        commands::export_markdown_folder,
+        commands::export_enex,
+        commands::import_format,
+        commands::preview_import,
+        commands::confirm_import,
+        commands::export_format,
+        commands::list_supported_formats,
+        commands::list_jobs,
+        commands::cancel_job,
+        // Recovery codes
+        commands::rotate_recovery_codes,
+        commands::verify_recovery_code,
+        commands::get_recovery_code_status,
+        // Sync
+        commands::merge_remote_changes,
+        commands::list_conflict_copies,
+        commands::get_note_crdt,
+        commands::resolve_conflict_copy,
+        // LAN Sync
+        commands::get_lan_sync_config,
+        commands::set_lan_sync_config,
+        commands::get_lan_peers,
+        commands::sync_with_lan_peer,
+        // Markdown Mirror
+        commands::get_mirror_config,
+        commands::set_mirror_config,
+        commands::pick_mirror_directory,
+        // Watched Folders
+        commands::get_watched_folders,
+        commands::watch_folder,
+        commands::unwatch_folder,
+        // System Search
+        commands::get_system_search_settings,
+        commands::set_system_search_settings,
+        commands::take_launch_note_link,
+        // Share Target
+        commands::receive_shared_content,
+        commands::get_pending_shares,
+        commands::set_share_menu,
+        // Clipboard capture
+        commands::get_clipboard_capture_settings,
+        commands::set_clipboard_capture_settings,
+        commands::get_daily_note,
+        // Snippets
+        commands::get_snippets,
+        commands::create_snippet,
+        commands::update_snippet,
+        commands::delete_snippet,
+        commands::expand_snippet,
+        // Scripts
+        commands::get_scripts,
+        commands::create_script,
+        commands::update_script,
+        commands::delete_script,
+        // Action Registry
+        commands::list_actions,
+        // Platform
+        commands::get_platform_capabilities,
+        // Background Activity
+        commands::get_background_activity,
+        commands::set_background_activity,
+        // Startup Recovery
+        commands::get_startup_problem,
+        commands::open_database_read_only,
+        commands::restore_database_backup,
+        commands::restart_app,
+        // Maintenance
+        commands::repair_brain_maps,
+        commands::purge_deleted_items,
+        commands::get_trash_settings,
+        commands::set_trash_settings,
+        // Developer
+        commands::seed_demo_data,
+        commands::set_profiling_enabled,
+        commands::get_performance_report,
+    ])
+    // Only reach the frontend as event payloads, e.g. `job:progress`
+    .typ::<models::ClipboardCaptured>()
+    .typ::<models::JobProgress>()
+    .typ::<models::LanSyncResult>()
+    .typ::<models::NoteLinksChanged>()
+    .typ::<models::PanicReport>()
+    .typ::<models::ReminderDue>();
+
+    // Keep the frontend bindings in step with the command signatures
+    #[cfg(debug_assertions)]
+    builder
+        .export(
+            Typescript::default().bigint(BigIntExportBehavior::Number),
+            "../src/bindings.ts",
+        )
+        .expect("Failed to export TypeScript bindings");
+
+    // Derived from the same command list, so it can't fall out of step
+    let app_builder = tauri::Builder::default().manage(actions::collect(&builder));
+    // Registered first so a second launch hands over before starting up
+    #[cfg(desktop)]
+    let app_builder =
+        app_builder.plugin(tauri_plugin_single_instance::init(deep_links::second_instance));
+    // Clipboard capture registers its shortcut once the database is open
+    #[cfg(desktop)]
+    let app_builder = app_builder.plugin(tauri_plugin_global_shortcut::Builder::new().build());
+    // Reminders are delivered by the OS on mobile
+    #[cfg(mobile)]
+    let app_builder = app_builder.plugin(tauri_plugin_notification::init());
+
+    app_builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
+        .register_uri_scheme_protocol("attachment", |ctx, request| {
+            attachments::serve(ctx.app_handle(), &request)
+        })
         .setup(|app| {
-            // Initialize database
-            let db = Database::new(app.handle())
-                .expect("Failed to initialize database");
-            app.manage(db);
+            panics::install_hook(app.handle().clone());
+            app.manage(jobs::Jobs::default());
+            app.manage(formats::Previews::default());
+            app.manage(share_target::PendingFiles::default());
+            app.manage(lan::LanPeers::default());
+            app.manage(deep_links::LaunchLink::default());
+            app.manage(background::Activity::default());
+            app.manage(startup::Recovery::default());
+            app.manage(write_behind::WriteBehind::default());
+
+            // Initialize database; if it can't be opened the recovery window
+            // takes over and nothing else starts
+            match startup::open(app.handle()) {
+                Ok(db) => {
+                    app.manage(db);
+                    deep_links::listen(app.handle());
+                    platform::spawn_services(app.handle());
+                }
+                Err(problem) => startup::show(app.handle(), problem)?,
+            }
 
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -25,41 +408,21 @@ pub fn run() {
             }
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            // Notes
-            commands::get_notes,
-            commands::get_note,
-            commands::create_note,
-            commands::update_note,
-            commands::delete_note,
-            commands::move_notes_to_folder,
-            // Folders
-            commands::get_folders,
-            commands::create_folder,
-            commands::update_folder,
-            commands::delete_folder,
-            // Events
-            commands::get_events,
-            commands::get_event,
-            commands::create_event,
-            commands::update_event,
-            commands::delete_event,
-            // Brain Maps
-            commands::get_brain_maps,
-            commands::get_brain_map,
-            commands::create_brain_map,
-            commands::update_brain_map,
-            commands::delete_brain_map,
-            commands::create_brain_map_node,
-            commands::update_brain_map_node,
-            commands::delete_brain_map_node,
-            commands::update_node_positions,
-            commands::create_brain_map_connection,
-            commands::delete_brain_map_connection,
-            // Settings
-            commands::get_setting,
-            commands::set_setting,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(builder.invoke_handler())
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Held writes, like the end of a drag, are saved before exiting
+            if let RunEvent::Exit = event {
+                if let Some(db) = app.try_state::<db::Database>() {
+                    let result = db
+                        .conn
+                        .lock()
+                        .and_then(|conn| app.state::<write_behind::WriteBehind>().flush(&conn));
+                    if let Err(e) = result {
+                        log::warn!("Failed to save pending writes: {}", e);
+                    }
+                }
+            }
+        });
 }
@@ -0,0 +1,497 @@
+use crate::crdt;
+use crate::models::{BrokenLink, BrokenLinkReason, LinkSettings, LinkTarget};
+use crate::render;
+use crate::revisions;
+use crate::sanitize;
+use crate::search_index;
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use scraper::Html;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+pub const LINK_SETTING: &str = "links";
+
+// `[[target` then any `#heading` or `|label` up to the closing brackets
+fn wikilink() -> &'static Regex {
+    static WIKILINK: OnceLock<Regex> = OnceLock::new();
+    WIKILINK.get_or_init(|| {
+        Regex::new(r"\[\[([^\[\]|#]+)([^\[\]]*)\]\]").expect("valid wikilink regex")
+    })
+}
+
+// The target as the reader sees it; editor HTML escapes entities
+pub fn target_text(raw: &str, html: bool) -> String {
+    if html {
+        Html::parse_fragment(raw)
+            .root_element()
+            .text()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    } else {
+        raw.trim().to_string()
+    }
+}
+
+fn linkable(title: &str) -> bool {
+    !title.trim().is_empty() && !title.contains(['[', ']', '|', '#'])
+}
+
+pub fn key(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+// Links follow renames unless turned off
+pub fn load_settings(conn: &Connection) -> Result<LinkSettings, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![LINK_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        None => Ok(LinkSettings {
+            update_on_rename: true,
+        }),
+    }
+}
+
+pub fn save_settings(conn: &Connection, settings: &LinkSettings) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            LINK_SETTING,
+            serde_json::to_string(settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn record_rename(
+    conn: &Connection,
+    note_id: &str,
+    old_title: &str,
+    now: &str,
+) -> Result<(), String> {
+    let old_title = key(old_title);
+    if old_title.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO note_renames (note_id, old_title, renamed_at) VALUES (?1, ?2, ?3)",
+        params![note_id, old_title, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn forget(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM note_renames WHERE note_id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Every lowercased title and id a link can point at, loaded once per check
+#[derive(Default)]
+struct Targets {
+    live: HashSet<String>,
+    trashed: HashMap<String, (String, String)>,
+    renamed: HashMap<String, (String, String)>,
+}
+
+impl Targets {
+    fn load(conn: &Connection) -> Result<Self, String> {
+        let mut targets = Targets::default();
+
+        let mut stmt = conn
+            .prepare("SELECT id, title, aliases, deleted_at IS NOT NULL FROM notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for (id, title, aliases, trashed) in rows.filter_map(|r| r.ok()) {
+            let aliases: Vec<String> = serde_json::from_str(&aliases).unwrap_or_default();
+            let keys = [key(&id), key(&title)]
+                .into_iter()
+                .chain(aliases.iter().map(|alias| key(alias)));
+            targets.add(keys, trashed, &id, &title);
+        }
+
+        // Events are only linked by id; titles repeat too often
+        let mut stmt = conn
+            .prepare("SELECT id, title, deleted_at IS NOT NULL FROM events")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get(2)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for (id, title, trashed) in rows.filter_map(|r| r.ok()) {
+            targets.add([key(&id)], trashed, &id, &title);
+        }
+
+        // Oldest first so the latest rename wins a shared former title
+        let mut stmt = conn
+            .prepare(
+                "SELECT r.old_title, n.id, n.title
+                 FROM note_renames r JOIN notes n ON n.id = r.note_id
+                 WHERE n.deleted_at IS NULL
+                 ORDER BY r.renamed_at",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for (old_title, id, title) in rows.filter_map(|r| r.ok()) {
+            targets.renamed.insert(old_title, (id, title));
+        }
+
+        Ok(targets)
+    }
+
+    fn add(
+        &mut self,
+        keys: impl IntoIterator<Item = String>,
+        trashed: bool,
+        id: &str,
+        title: &str,
+    ) {
+        for k in keys.into_iter().filter(|k| !k.is_empty()) {
+            if trashed {
+                self.trashed.insert(k, (id.to_string(), title.to_string()));
+            } else {
+                self.live.insert(k);
+            }
+        }
+    }
+
+    // None when the link works
+    fn resolve(&self, key: &str) -> Option<(BrokenLinkReason, Option<&(String, String)>)> {
+        if self.live.contains(key) {
+            None
+        } else if let Some(target) = self.renamed.get(key) {
+            Some((BrokenLinkReason::Renamed, Some(target)))
+        } else if let Some(target) = self.trashed.get(key) {
+            Some((BrokenLinkReason::Trashed, Some(target)))
+        } else {
+            Some((BrokenLinkReason::Missing, None))
+        }
+    }
+}
+
+// Broken wikilinks in one live note, or in all of them. Each target is
+// listed once per note.
+pub fn check(conn: &Connection, note_id: Option<&str>) -> Result<Vec<BrokenLink>, String> {
+    let targets = Targets::load(conn)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, content FROM notes
+             WHERE deleted_at IS NULL AND (?1 IS NULL OR id = ?1)
+             ORDER BY title COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let notes: Vec<(String, String, String)> = stmt
+        .query_map(params![note_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut broken = Vec::new();
+    for (source_id, source_title, content) in notes {
+        let html = render::looks_like_html(&content);
+        let mut seen = HashSet::new();
+        for captures in wikilink().captures_iter(&content) {
+            let target = target_text(&captures[1], html);
+            let target_key = key(&target);
+            if target_key.is_empty() || !seen.insert(target_key.clone()) {
+                continue;
+            }
+            let Some((reason, meant)) = targets.resolve(&target_key) else {
+                continue;
+            };
+            broken.push(BrokenLink {
+                source_note_id: source_id.clone(),
+                source_title: source_title.clone(),
+                target,
+                reason,
+                target_id: meant.map(|(id, _)| id.clone()),
+                target_title: meant.map(|(_, title)| title.clone()),
+                repaired: false,
+            });
+        }
+    }
+    Ok(broken)
+}
+
+// Points links at `renames` (lowercased old target to new title) at the new
+// title, keeping any heading or label. None if nothing changed.
+pub fn retarget(content: &str, renames: &HashMap<String, String>) -> Option<String> {
+    let html = render::looks_like_html(content);
+    let mut changed = false;
+    let rewritten = wikilink().replace_all(content, |captures: &regex::Captures| {
+        match renames.get(&key(&target_text(&captures[1], html))) {
+            Some(title) => {
+                changed = true;
+                let title = if html {
+                    render::escape_html(title)
+                } else {
+                    title.clone()
+                };
+                format!("[[{}{}]]", title, &captures[2])
+            }
+            None => captures[0].to_string(),
+        }
+    });
+    changed.then(|| rewritten.into_owned())
+}
+
+// Saves new content for a note, e.g. after its links were retargeted, with
+// the edit recorded for sync and search. The content is sanitized first, as
+// it may come from outside the app; returns what was saved.
+pub fn save_content(
+    conn: &Connection,
+    note_id: &str,
+    content: &str,
+    now: &str,
+) -> Result<String, String> {
+    let content = sanitize::sanitize_content(content);
+    conn.execute(
+        "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+        params![content, now, note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crdt::record_edit(conn, note_id, &content, now)?;
+    search_index::note_changed(conn, note_id);
+    Ok(content)
+}
+
+// `check`, then links to a renamed note are pointed at its current title.
+// Run inside a transaction.
+pub fn repair(
+    conn: &Connection,
+    note_id: Option<&str>,
+    now: &str,
+) -> Result<Vec<BrokenLink>, String> {
+    let mut broken = check(conn, note_id)?;
+
+    // A title with brackets, `|` or `#` can't be written as a link target
+    let mut renames: HashMap<String, HashMap<String, String>> = HashMap::new();
+    for link in &mut broken {
+        let Some(title) = &link.target_title else {
+            continue;
+        };
+        if link.reason == BrokenLinkReason::Renamed && linkable(title) {
+            renames
+                .entry(link.source_note_id.clone())
+                .or_default()
+                .insert(key(&link.target), title.clone());
+            link.repaired = true;
+        }
+    }
+
+    for (source_id, renames) in &renames {
+        let content: String = conn
+            .query_row(
+                "SELECT content FROM notes WHERE id = ?1",
+                params![source_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if let Some(content) = retarget(&content, renames) {
+            save_content(conn, source_id, &content, now)?;
+        }
+    }
+
+    Ok(broken)
+}
+
+// Points other notes' links to `old_title` at `new_title`, found through the
+// backlinks index. Each note changed gets a revision first. Skipped when
+// another live note still goes by the old title, since those links may mean it.
+// Returns how many notes changed; run inside the rename's transaction.
+pub fn follow_rename(
+    conn: &Connection,
+    note_id: &str,
+    old_title: &str,
+    new_title: &str,
+    now: &str,
+) -> Result<usize, String> {
+    let old_key = key(old_title);
+    if old_key.is_empty() || !linkable(new_title) {
+        return Ok(0);
+    }
+    let mut stmt = conn
+        .prepare("SELECT title, aliases FROM notes WHERE id != ?1 AND deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let still_used = stmt
+        .query_map(params![note_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .any(|(title, aliases)| {
+            let aliases: Vec<String> = serde_json::from_str(&aliases).unwrap_or_default();
+            key(&title) == old_key || aliases.iter().any(|alias| key(alias) == old_key)
+        });
+    if still_used {
+        return Ok(0);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.content
+             FROM note_links l JOIN notes n ON n.id = l.source_note_id
+             WHERE l.target = ?1 AND n.id != ?2 AND n.deleted_at IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let sources: Vec<(String, String)> = stmt
+        .query_map(params![old_key, note_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let renames = HashMap::from([(old_key, new_title.trim().to_string())]);
+    let reason = format!(
+        "Links to \"{}\" renamed to \"{}\"",
+        old_title.trim(),
+        new_title.trim()
+    );
+    let mut changed = 0;
+    for (source_id, content) in sources {
+        if let Some(content) = retarget(&content, &renames) {
+            revisions::snapshot(conn, &source_id, &reason, now)?;
+            save_content(conn, &source_id, &content, now)?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+// Lowercased ids, titles and aliases of live notes, to the note's id. An id
+// beats a title, which beats an alias; among equals the latest edit wins.
+pub fn live_names(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, aliases FROM notes WHERE deleted_at IS NULL
+             ORDER BY updated_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let notes: Vec<(String, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut names = HashMap::new();
+    for (id, _, aliases) in &notes {
+        let aliases: Vec<String> = serde_json::from_str(aliases).unwrap_or_default();
+        for alias in aliases {
+            names.insert(key(&alias), id.clone());
+        }
+    }
+    for (id, title, _) in &notes {
+        names.insert(key(title), id.clone());
+    }
+    for (id, _, _) in &notes {
+        names.insert(key(id), id.clone());
+    }
+    names.remove("");
+    Ok(names)
+}
+
+// Live notes whose title or an alias matches `text`: whole-name matches,
+// then names starting with it, then names containing it
+pub fn resolve(conn: &Connection, text: &str, limit: usize) -> Result<Vec<LinkTarget>, String> {
+    let wanted = key(text);
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, aliases FROM notes WHERE deleted_at IS NULL
+             ORDER BY updated_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let rank = |name: &str| {
+        let name = key(name);
+        if name == wanted {
+            Some(0)
+        } else if name.starts_with(&wanted) {
+            Some(1)
+        } else if name.contains(&wanted) {
+            Some(2)
+        } else {
+            None
+        }
+    };
+
+    let mut found: Vec<(u8, LinkTarget)> = Vec::new();
+    for (id, title, aliases) in rows.filter_map(|r| r.ok()) {
+        let aliases: Vec<String> = serde_json::from_str(&aliases).unwrap_or_default();
+        // The title wins unless an alias matches better
+        let best = std::iter::once((None, rank(&title)))
+            .chain(aliases.into_iter().map(|alias| {
+                let rank = rank(&alias);
+                (Some(alias), rank)
+            }))
+            .filter_map(|(alias, rank)| Some((rank?, alias)))
+            .min_by_key(|(rank, _)| *rank);
+        if let Some((rank, alias)) = best {
+            found.push((
+                rank,
+                LinkTarget {
+                    note_id: id,
+                    title,
+                    alias,
+                    exact: rank == 0,
+                },
+            ));
+        }
+    }
+
+    // Stable, so equal ranks stay most recently edited first
+    found.sort_by_key(|(rank, _)| *rank);
+    Ok(found
+        .into_iter()
+        .take(limit)
+        .map(|(_, target)| target)
+        .collect())
+}
@@ -0,0 +1,100 @@
+use crate::models::{ClockFormat, DateOrder, LocaleSettings, WeekStart};
+use crate::validation;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use rusqlite::{params, Connection};
+
+pub const LOCALE_SETTING: &str = "locale";
+
+// Stored settings, or the defaults (Monday weeks, 24-hour clock, ISO dates)
+pub fn load(conn: &Connection) -> LocaleSettings {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![LOCALE_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| serde_json::from_str(&value).ok())
+    .unwrap_or_default()
+}
+
+fn first_weekday(start: WeekStart) -> Weekday {
+    match start {
+        WeekStart::Monday => Weekday::Mon,
+        WeekStart::Sunday => Weekday::Sun,
+        WeekStart::Saturday => Weekday::Sat,
+    }
+}
+
+pub fn week_start(date: NaiveDate, start: WeekStart) -> NaiveDate {
+    let first = first_weekday(start).num_days_from_monday();
+    let back = (date.weekday().num_days_from_monday() + 7 - first) % 7;
+    date - Duration::days(back as i64)
+}
+
+// (year, week) for `date`. Monday weeks are ISO 8601 weeks. Otherwise week 1
+// is the one containing January 1st, and a week belongs to the year it ends in.
+pub fn week_number(date: NaiveDate, start: WeekStart) -> (i32, u32) {
+    if start == WeekStart::Monday {
+        let week = date.iso_week();
+        return (week.year(), week.week());
+    }
+    let this_week = week_start(date, start);
+    let year = (this_week + Duration::days(6)).year();
+    let first_week = NaiveDate::from_ymd_opt(year, 1, 1)
+        .map(|jan1| week_start(jan1, start))
+        .unwrap_or(this_week);
+    (year, ((this_week - first_week).num_days() / 7) as u32 + 1)
+}
+
+// First day of the given week of `year`, as counted by week_number
+pub fn week_from_number(year: i32, week: u32, start: WeekStart) -> Option<NaiveDate> {
+    if start == WeekStart::Monday {
+        return NaiveDate::from_isoywd_opt(year, week, Weekday::Mon);
+    }
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1)?;
+    let first = week_start(jan1, start) + Duration::weeks(week.checked_sub(1)? as i64);
+    Some(first).filter(|d| week_number(*d, start) == (year, week))
+}
+
+pub fn format_date(date: NaiveDate, order: DateOrder) -> String {
+    let pattern = match order {
+        DateOrder::Ymd => "%Y-%m-%d",
+        DateOrder::Dmy => "%d/%m/%Y",
+        DateOrder::Mdy => "%m/%d/%Y",
+    };
+    date.format(pattern).to_string()
+}
+
+pub fn format_time(time: NaiveTime, clock: ClockFormat) -> String {
+    let pattern = match clock {
+        ClockFormat::TwentyFourHour => "%H:%M",
+        ClockFormat::TwelveHour => "%-I:%M %p",
+    };
+    time.format(pattern).to_string()
+}
+
+// A stored timestamp as the user's wall-clock time; naive timestamps are
+// already local
+pub fn local_date_time(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+    validation::parse_timestamp(value)
+}
+
+pub fn local_date(value: &str) -> Option<NaiveDate> {
+    local_date_time(value).map(|time| time.date())
+}
+
+// Local midnight at the start of `date`, in UTC like stored timestamps
+pub fn day_start_utc(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
@@ -0,0 +1,42 @@
+use crate::background;
+use crate::db::Database;
+use crate::models::BackgroundSubsystem;
+use crate::trash;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// Housekeeping has no deadline, so a few runs a day is plenty
+const RUN_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+fn purge_trash(app: &AppHandle) -> Result<(), String> {
+    let db = app.state::<Database>();
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(days) = trash::load_settings(&conn)?.retention_days else {
+        return Ok(());
+    };
+
+    let report = trash::purge(&mut conn, &db.data_dir, days)?;
+    let purged = report.notes + report.events + report.brain_maps;
+    if purged > 0 {
+        log::info!(
+            "Purged {} trashed items older than {} days ({} bytes)",
+            purged,
+            days,
+            report.bytes_reclaimed
+        );
+    }
+    Ok(())
+}
+
+// Runs the periodic maintenance tasks, the first time at launch
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Maintenance).await;
+            if let Err(e) = purge_trash(&app) {
+                log::warn!("Purging the trash failed: {}", e);
+            }
+            tokio::time::sleep(RUN_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,289 @@
+use crate::models::{BrainMapNode, BrainMapWithData, NodeShape};
+use crate::render::escape_html;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+// ============ GraphML ============
+
+// Plain data keys for Gephi and friends, plus the yFiles graphics yEd draws from
+const GRAPHML_KEYS: &str = r#"<key id="label" for="node" attr.name="label" attr.type="string"/>
+<key id="description" for="node" attr.name="description" attr.type="string"/>
+<key id="color" for="node" attr.name="color" attr.type="string"/>
+<key id="shape" for="node" attr.name="shape" attr.type="string"/>
+<key id="x" for="node" attr.name="x" attr.type="double"/>
+<key id="y" for="node" attr.name="y" attr.type="double"/>
+<key id="edge_kind" for="edge" attr.name="kind" attr.type="string"/>
+<key id="edge_label" for="edge" attr.name="label" attr.type="string"/>
+<key id="edge_color" for="edge" attr.name="color" attr.type="string"/>
+<key id="node_graphics" for="node" yfiles.type="nodegraphics"/>
+<key id="edge_graphics" for="edge" yfiles.type="edgegraphics"/>
+"#;
+
+const DEFAULT_NODE_COLOR: &str = "#5A5A5A";
+const DEFAULT_EDGE_COLOR: &str = "#8A8A8A";
+
+fn yed_shape(shape: Option<NodeShape>) -> &'static str {
+    match shape.unwrap_or(NodeShape::Circle) {
+        NodeShape::Circle => "ellipse",
+        NodeShape::Rectangle => "rectangle",
+        NodeShape::Diamond => "diamond",
+        NodeShape::Hexagon => "hexagon",
+        NodeShape::Pill => "roundrectangle",
+    }
+}
+
+fn data(out: &mut String, key: &str, value: &str) {
+    let _ = write!(out, r#"<data key="{}">{}</data>"#, key, escape_html(value));
+}
+
+fn graphml_edge(
+    out: &mut String,
+    id: &str,
+    source: &str,
+    target: &str,
+    kind: &str,
+    label: Option<&str>,
+    color: &str,
+) {
+    let _ = write!(
+        out,
+        r#"<edge id="{}" source="{}" target="{}">"#,
+        escape_html(id),
+        escape_html(source),
+        escape_html(target)
+    );
+    data(out, "edge_kind", kind);
+    if let Some(label) = label {
+        data(out, "edge_label", label);
+    }
+    data(out, "edge_color", color);
+    let _ = write!(
+        out,
+        r#"<data key="edge_graphics"><y:PolyLineEdge><y:LineStyle color="{}" type="{}" width="1.0"/><y:Arrows source="none" target="{}"/>"#,
+        escape_html(color),
+        if kind == "parent" { "line" } else { "dashed" },
+        if kind == "parent" { "none" } else { "standard" }
+    );
+    if let Some(label) = label {
+        let _ = write!(out, "<y:EdgeLabel>{}</y:EdgeLabel>", escape_html(label));
+    }
+    out.push_str("</y:PolyLineEdge></data></edge>\n");
+}
+
+// Parent links become edges of kind "parent", custom connections edges of kind
+// "connection", so the hierarchy can be told apart after import
+pub fn graphml(map: &BrainMapWithData) -> String {
+    let ids: HashSet<&str> = map.nodes.iter().map(|n| n.id.as_str()).collect();
+
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:y="http://www.yworks.com/xml/graphml">
+"#,
+    );
+    out.push_str(GRAPHML_KEYS);
+    let _ = writeln!(
+        out,
+        r#"<graph id="{}" edgedefault="directed">"#,
+        escape_html(&map.brain_map.id)
+    );
+
+    for node in &map.nodes {
+        let color = node.color.as_deref().unwrap_or(DEFAULT_NODE_COLOR);
+        let _ = write!(out, r#"<node id="{}">"#, escape_html(&node.id));
+        data(&mut out, "label", &node.label);
+        if let Some(description) = &node.description {
+            data(&mut out, "description", description);
+        }
+        data(&mut out, "color", color);
+        if let Some(shape) = node.shape {
+            data(&mut out, "shape", shape.as_str());
+        }
+        data(&mut out, "x", &node.x.to_string());
+        data(&mut out, "y", &node.y.to_string());
+        let _ = write!(
+            out,
+            r#"<data key="node_graphics"><y:ShapeNode><y:Geometry x="{:.2}" y="{:.2}" width="120.0" height="50.0"/><y:Fill color="{}" transparent="false"/><y:NodeLabel>{}</y:NodeLabel><y:Shape type="{}"/></y:ShapeNode></data>"#,
+            node.x - 60.0,
+            node.y - 25.0,
+            escape_html(color),
+            escape_html(&node.label),
+            yed_shape(node.shape)
+        );
+        out.push_str("</node>\n");
+    }
+
+    for node in &map.nodes {
+        let Some(parent) = node.parent_node_id.as_deref().filter(|id| ids.contains(id)) else {
+            continue;
+        };
+        graphml_edge(
+            &mut out,
+            &format!("parent-{}", node.id),
+            parent,
+            &node.id,
+            "parent",
+            None,
+            node.color.as_deref().unwrap_or(DEFAULT_NODE_COLOR),
+        );
+    }
+
+    for connection in &map.connections {
+        if !ids.contains(connection.source_node_id.as_str())
+            || !ids.contains(connection.target_node_id.as_str())
+        {
+            continue;
+        }
+        graphml_edge(
+            &mut out,
+            &connection.id,
+            &connection.source_node_id,
+            &connection.target_node_id,
+            "connection",
+            connection.label.as_deref(),
+            connection.color.as_deref().unwrap_or(DEFAULT_EDGE_COLOR),
+        );
+    }
+
+    out.push_str("</graph>\n</graphml>\n");
+    out
+}
+
+// ============ XMind ============
+
+fn topic(
+    node: &BrainMapNode,
+    children: &HashMap<&str, Vec<&BrainMapNode>>,
+    seen: &mut HashSet<String>,
+) -> Value {
+    seen.insert(node.id.clone());
+
+    let mut attached = Vec::new();
+    for child in children.get(node.id.as_str()).into_iter().flatten() {
+        if !seen.contains(&child.id) {
+            attached.push(topic(child, children, seen));
+        }
+    }
+
+    let mut topic = json!({
+        "id": node.id,
+        "class": "topic",
+        "title": node.label,
+    });
+    if !attached.is_empty() {
+        topic["children"] = json!({ "attached": attached });
+    }
+    if let Some(color) = &node.color {
+        topic["style"] = json!({ "properties": { "svg:fill": color } });
+    }
+    if let Some(description) = &node.description {
+        topic["notes"] = json!({ "plain": { "content": description } });
+    }
+    topic
+}
+
+// XMind wants a single root topic. The map's center node is used when it has
+// one; other top-level nodes hang off it as floating topics. Without a center
+// node, a topic named after the map holds every top-level node.
+fn sheet(map: &BrainMapWithData) -> Value {
+    let ids: HashSet<&str> = map.nodes.iter().map(|n| n.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&BrainMapNode>> = HashMap::new();
+    let mut roots = Vec::new();
+    for node in &map.nodes {
+        match node.parent_node_id.as_deref().filter(|id| ids.contains(id)) {
+            Some(parent) => children.entry(parent).or_default().push(node),
+            None => roots.push(node),
+        }
+    }
+
+    let center = map
+        .brain_map
+        .center_node_id
+        .as_deref()
+        .and_then(|id| map.nodes.iter().find(|n| n.id == id));
+
+    let mut seen = HashSet::new();
+    let mut root = match center {
+        Some(center) => topic(center, &children, &mut seen),
+        None => json!({
+            "id": format!("root-{}", map.brain_map.id),
+            "class": "topic",
+            "title": map.brain_map.title,
+        }),
+    };
+
+    // Nodes whose parent chain loops never reach a root, so they're added
+    // as floating topics too
+    let mut others: Vec<Value> = Vec::new();
+    for node in roots.into_iter().chain(map.nodes.iter()) {
+        if !seen.contains(&node.id) {
+            others.push(topic(node, &children, &mut seen));
+        }
+    }
+    if !others.is_empty() {
+        let slot = if center.is_some() {
+            "detached"
+        } else {
+            "attached"
+        };
+        root["children"][slot] = Value::Array(others);
+    }
+    root["structureClass"] = json!("org.xmind.ui.map.unbalanced");
+
+    let relationships: Vec<Value> = map
+        .connections
+        .iter()
+        .filter(|c| {
+            ids.contains(c.source_node_id.as_str()) && ids.contains(c.target_node_id.as_str())
+        })
+        .map(|c| {
+            let mut relationship = json!({
+                "id": c.id,
+                "end1Id": c.source_node_id,
+                "end2Id": c.target_node_id,
+            });
+            if let Some(label) = &c.label {
+                relationship["title"] = json!(label);
+            }
+            if let Some(color) = &c.color {
+                relationship["style"] = json!({ "properties": { "line-color": color } });
+            }
+            relationship
+        })
+        .collect();
+
+    json!({
+        "id": map.brain_map.id,
+        "class": "sheet",
+        "title": map.brain_map.title,
+        "rootTopic": root,
+        "relationships": relationships,
+    })
+}
+
+// The JSON-based package XMind has used since XMind Zen
+pub fn xmind(map: &BrainMapWithData) -> Result<Vec<u8>, String> {
+    let content = serde_json::to_vec(&json!([sheet(map)])).map_err(|e| e.to_string())?;
+    let metadata = serde_json::to_vec(&json!({ "creator": { "name": "Voyena" } }))
+        .map_err(|e| e.to_string())?;
+    let manifest = serde_json::to_vec(&json!({
+        "file-entries": { "content.json": {}, "metadata.json": {} }
+    }))
+    .map_err(|e| e.to_string())?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, bytes) in [
+        ("content.json", content),
+        ("metadata.json", metadata),
+        ("manifest.json", manifest),
+    ] {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+    }
+    let cursor = zip.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
@@ -0,0 +1,334 @@
+use crate::models::{BrainMapNode, BrainMapWithData, NodeShape, NodeSize};
+use crate::render::escape_html;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+// Mirrors PALETTE in BrainMapCanvas.tsx
+const BG: &str = "#0a0a0a";
+const SURFACE: &str = "#141414";
+const ELEVATED: &str = "#1a1a1a";
+const TEXT: &str = "#F5F5F0";
+const SECONDARY: &str = "#8A8A8A";
+const MUTED: &str = "#5A5A5A";
+const DIVIDER: &str = "rgba(255, 255, 255, 0.08)";
+
+const FONT_FAMILY: &str = "-apple-system, 'Segoe UI', Helvetica, Arial, sans-serif";
+
+// Room around the outermost nodes, enough for the center node's aura
+const PADDING: f64 = 60.0;
+
+// PNGs are rendered at twice the map's size, but never past this many pixels a side
+const PNG_SCALE: f32 = 2.0;
+const PNG_MAX_SIDE: f32 = 8192.0;
+
+// Mirrors SIZE_CONFIG in BrainMapCanvas.tsx: radius, font size, stroke width
+fn size_config(size: Option<NodeSize>) -> (f64, f64, f64) {
+    match size.unwrap_or(NodeSize::Medium) {
+        NodeSize::Small => (28.0, 11.0, 1.0),
+        NodeSize::Medium => (42.0, 13.0, 1.5),
+        NodeSize::Large => (58.0, 15.0, 2.0),
+        NodeSize::Xl => (80.0, 18.0, 2.5),
+    }
+}
+
+// Half width and half height of the node's shape
+fn extent(shape: NodeShape, radius: f64) -> (f64, f64) {
+    match shape {
+        NodeShape::Rectangle => (radius * 1.6, radius * 1.1),
+        NodeShape::Pill => (radius * 1.8, radius * 0.7),
+        NodeShape::Circle | NodeShape::Diamond | NodeShape::Hexagon => (radius, radius),
+    }
+}
+
+// Same paths as getShapePath, centered on the origin. None for circles.
+fn shape_path(shape: NodeShape, radius: f64) -> Option<String> {
+    let r = radius;
+    match shape {
+        NodeShape::Circle => None,
+        NodeShape::Diamond => Some(format!("M 0 {} L {} 0 L 0 {} L {} 0 Z", -r, r, r, -r)),
+        NodeShape::Rectangle => {
+            let (w, h) = extent(shape, r);
+            Some(format!(
+                "M {} {} L {} {} L {} {} L {} {} Z",
+                -w, -h, w, -h, w, h, -w, h
+            ))
+        }
+        NodeShape::Hexagon => {
+            let points: Vec<String> = (0..6)
+                .map(|i| {
+                    let angle = (i as f64 * 60.0 - 30.0) * PI / 180.0;
+                    let command = if i == 0 { "M" } else { "L" };
+                    format!("{} {:.2} {:.2}", command, angle.cos() * r, angle.sin() * r)
+                })
+                .collect();
+            Some(format!("{} Z", points.join(" ")))
+        }
+        NodeShape::Pill => {
+            let (w, h) = extent(shape, r);
+            Some(format!(
+                "M {0} {1} L {2} {1} A {3} {3} 0 0 1 {2} {3} L {0} {3} A {3} {3} 0 0 1 {0} {1} Z",
+                -w + h,
+                -h,
+                w - h,
+                h
+            ))
+        }
+    }
+}
+
+// Labels are cut the same way the canvas cuts them
+fn short_label(label: &str) -> String {
+    if label.chars().count() > 16 {
+        format!("{}…", label.chars().take(14).collect::<String>())
+    } else {
+        label.to_string()
+    }
+}
+
+// The slightly bent edge the canvas draws between two nodes
+fn edge_path(from: &BrainMapNode, to: &BrainMapNode) -> Option<String> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let dist = (dx * dx + dy * dy).sqrt();
+    if dist < 1.0 {
+        return None;
+    }
+
+    let offset = (dist * 0.1).min(30.0);
+    let cx = (from.x + to.x) / 2.0 - dy / dist * offset;
+    let cy = (from.y + to.y) / 2.0 + dx / dist * offset;
+    Some(format!(
+        "M {:.2} {:.2} Q {:.2} {:.2} {:.2} {:.2}",
+        from.x, from.y, cx, cy, to.x, to.y
+    ))
+}
+
+fn write_edge(out: &mut String, path: &str, color: &str, opacity: f64) {
+    let _ = write!(
+        out,
+        r#"<path d="{0}" fill="none" stroke="{1}" stroke-width="1.5" stroke-opacity="{2}" stroke-linecap="round"/><path d="{0}" fill="none" stroke="{3}" stroke-width="0.5" stroke-opacity="{4}" stroke-linecap="round"/>"#,
+        path,
+        escape_html(color),
+        0.6 * opacity,
+        TEXT,
+        0.3 * opacity
+    );
+}
+
+fn write_node(out: &mut String, node: &BrainMapNode, is_center: bool, child_count: usize) {
+    let shape = node.shape.unwrap_or(NodeShape::Circle);
+    let (radius, font_size, stroke_width) = size_config(node.size);
+    let color = escape_html(node.color.as_deref().unwrap_or(MUTED));
+
+    let _ = write!(
+        out,
+        r#"<g transform="translate({:.2}, {:.2})">"#,
+        node.x, node.y
+    );
+
+    if is_center {
+        let _ = write!(
+            out,
+            r#"<circle r="{}" fill="{2}" opacity="0.04"/><circle r="{}" fill="{2}" opacity="0.08"/>"#,
+            radius + 35.0,
+            radius + 20.0,
+            color
+        );
+    }
+
+    match shape_path(shape, radius) {
+        None => {
+            let _ = write!(
+                out,
+                r#"<circle r="{0}" fill="url(#node-fill)" stroke="{1}" stroke-width="{2}" filter="url(#node-shadow)"/><circle r="{3}" fill="none" stroke="{4}" stroke-width="2" opacity="0.6"/><ellipse cx="{5:.2}" cy="{6:.2}" rx="{7:.2}" ry="{8:.2}" fill="{9}" opacity="0.08"/>"#,
+                radius,
+                DIVIDER,
+                stroke_width,
+                radius - 3.0,
+                color,
+                -radius * 0.2,
+                -radius * 0.3,
+                radius * 0.4,
+                radius * 0.2,
+                TEXT
+            );
+        }
+        Some(path) => {
+            let _ = write!(
+                out,
+                r#"<path d="{0}" fill="url(#node-fill)" stroke="{1}" stroke-width="{2}" filter="url(#node-shadow)"/><path d="{0}" fill="none" stroke="{3}" stroke-width="2" opacity="0.5" transform="scale(0.92)"/>"#,
+                path, DIVIDER, stroke_width, color
+            );
+        }
+    }
+
+    let _ = write!(
+        out,
+        r#"<text text-anchor="middle" dy="0.35em" font-size="{}" font-weight="{}" fill="{}" letter-spacing="0.3">{}</text>"#,
+        font_size,
+        if is_center { 500 } else { 300 },
+        TEXT,
+        escape_html(&short_label(&node.label))
+    );
+
+    if node.linked_note_id.is_some()
+        || node.linked_folder_id.is_some()
+        || node.linked_event_id.is_some()
+    {
+        let _ = write!(
+            out,
+            r#"<g transform="translate({:.2}, {:.2})"><circle r="8" fill="{}" stroke="{}" stroke-width="1"/><text text-anchor="middle" dy="0.35em" font-size="9" fill="{}">{}</text></g>"#,
+            radius * 0.6,
+            -radius * 0.6,
+            SURFACE,
+            DIVIDER,
+            TEXT,
+            if node.linked_event_id.is_some() {
+                "◉"
+            } else {
+                "↗"
+            }
+        );
+    }
+
+    if child_count > 0 {
+        let _ = write!(
+            out,
+            r#"<g transform="translate({:.2}, {:.2})"><circle r="10" fill="{}" stroke="{}" stroke-width="1"/><text text-anchor="middle" dy="0.35em" font-size="9" fill="{}" font-weight="500">{}</text></g>"#,
+            radius * 0.65,
+            radius * 0.55,
+            SURFACE,
+            DIVIDER,
+            SECONDARY,
+            child_count
+        );
+    }
+
+    out.push_str("</g>");
+}
+
+// Draws the whole map from its stored positions, framed to fit every node,
+// so the result doesn't depend on where the canvas happens to be scrolled
+pub fn render_svg(data: &BrainMapWithData) -> String {
+    let nodes: HashMap<&str, &BrainMapNode> =
+        data.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    let mut children: HashMap<&str, usize> = HashMap::new();
+    for node in &data.nodes {
+        if let Some(parent) = node.parent_node_id.as_deref() {
+            *children.entry(parent).or_default() += 1;
+        }
+    }
+
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for node in &data.nodes {
+        let (radius, _, _) = size_config(node.size);
+        let (w, h) = extent(node.shape.unwrap_or(NodeShape::Circle), radius);
+        min_x = min_x.min(node.x - w);
+        min_y = min_y.min(node.y - h);
+        max_x = max_x.max(node.x + w);
+        max_y = max_y.max(node.y + h);
+    }
+    if data.nodes.is_empty() {
+        (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+    let (x, y) = (min_x - PADDING, min_y - PADDING);
+    let (width, height) = (max_x - min_x + PADDING * 2.0, max_y - min_y + PADDING * 2.0);
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0:.0}" height="{1:.0}" viewBox="{2:.2} {3:.2} {0:.2} {1:.2}" font-family="{4}">"#,
+        width, height, x, y, FONT_FAMILY
+    );
+    let _ = write!(
+        out,
+        r##"<defs><filter id="node-shadow" x="-50%" y="-50%" width="200%" height="200%"><feDropShadow dx="0" dy="6" stdDeviation="8" flood-color="#000" flood-opacity="0.35"/></filter><radialGradient id="node-fill" cx="35%" cy="25%" r="70%"><stop offset="0%" stop-color="{}"/><stop offset="70%" stop-color="{}"/><stop offset="100%" stop-color="{}"/></radialGradient></defs>"##,
+        ELEVATED, SURFACE, BG
+    );
+    let _ = write!(
+        out,
+        r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"/>"#,
+        x, y, width, height, BG
+    );
+
+    // Parent links first, then custom connections, then nodes on top
+    for node in &data.nodes {
+        let Some(parent) = node.parent_node_id.as_deref().and_then(|id| nodes.get(id)) else {
+            continue;
+        };
+        if let Some(path) = edge_path(parent, node) {
+            out.push_str(r#"<g opacity="0.6">"#);
+            write_edge(&mut out, &path, node.color.as_deref().unwrap_or(MUTED), 0.5);
+            out.push_str("</g>");
+        }
+    }
+
+    for connection in &data.connections {
+        let (Some(source), Some(target)) = (
+            nodes.get(connection.source_node_id.as_str()),
+            nodes.get(connection.target_node_id.as_str()),
+        ) else {
+            continue;
+        };
+        let Some(path) = edge_path(source, target) else {
+            continue;
+        };
+        write_edge(
+            &mut out,
+            &path,
+            connection.color.as_deref().unwrap_or(SECONDARY),
+            1.0,
+        );
+        if let Some(label) = &connection.label {
+            let _ = write!(
+                out,
+                r#"<text x="{:.2}" y="{:.2}" text-anchor="middle" font-size="11" fill="{}" font-weight="300" letter-spacing="0.5">{}</text>"#,
+                (source.x + target.x) / 2.0,
+                (source.y + target.y) / 2.0 - 12.0,
+                SECONDARY,
+                escape_html(label)
+            );
+        }
+    }
+
+    let center = data.brain_map.center_node_id.as_deref();
+    let mut ordered: Vec<&BrainMapNode> = data.nodes.iter().collect();
+    ordered.sort_by_key(|n| n.layer);
+    for node in ordered {
+        let child_count = children.get(node.id.as_str()).copied().unwrap_or(0);
+        write_node(
+            &mut out,
+            node,
+            center == Some(node.id.as_str()),
+            child_count,
+        );
+    }
+
+    out.push_str("</svg>");
+    out
+}
+
+pub fn rasterize_png(svg: &str) -> Result<Vec<u8>, String> {
+    let mut options = Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = Tree::from_str(svg, &options).map_err(|e| e.to_string())?;
+
+    let size = tree.size();
+    let scale = PNG_SCALE.min(PNG_MAX_SIDE / size.width().max(size.height()));
+    let mut pixmap = Pixmap::new(
+        (size.width() * scale).ceil() as u32,
+        (size.height() * scale).ceil() as u32,
+    )
+    .ok_or("Brain map is too large to render")?;
+
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
@@ -0,0 +1,90 @@
+use crate::models::{EntityKind, MetadataEntry};
+use crate::timestamps;
+use rusqlite::{params, Connection, OptionalExtension};
+
+fn table(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Note => "notes",
+        EntityKind::Event => "events",
+        EntityKind::Folder => "folders",
+        EntityKind::Snippet => "snippets",
+    }
+}
+
+// Trashed notes and events still count, so their fields survive a restore
+pub fn entity_exists(conn: &Connection, kind: EntityKind, id: &str) -> Result<bool, String> {
+    conn.query_row(
+        &format!("SELECT 1 FROM {} WHERE id = ?1", table(kind)),
+        params![id],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|e| e.to_string())
+}
+
+pub fn get(conn: &Connection, kind: EntityKind, id: &str) -> Result<Vec<MetadataEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT key, value, updated_at FROM entity_metadata
+             WHERE entity_type = ?1 AND entity_id = ?2
+             ORDER BY key",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![kind.as_str(), id], |row| {
+            Ok(MetadataEntry {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                updated_at: timestamps::get(row, 2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// Stores `value` under `key`, replacing what was there; None removes the key
+pub fn set(
+    conn: &Connection,
+    kind: EntityKind,
+    id: &str,
+    key: &str,
+    value: Option<&str>,
+    now: &str,
+) -> Result<(), String> {
+    match value {
+        Some(value) => conn.execute(
+            "INSERT OR REPLACE INTO entity_metadata (entity_type, entity_id, key, value, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![kind.as_str(), id, key, value, now],
+        ),
+        None => conn.execute(
+            "DELETE FROM entity_metadata WHERE entity_type = ?1 AND entity_id = ?2 AND key = ?3",
+            params![kind.as_str(), id, key],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Moves `from`'s fields over to `into`, keeping `into`'s value where both
+// have the same key
+pub fn merge(conn: &Connection, kind: EntityKind, from: &str, into: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE OR IGNORE entity_metadata SET entity_id = ?3
+         WHERE entity_type = ?1 AND entity_id = ?2",
+        params![kind.as_str(), from, into],
+    )
+    .map_err(|e| e.to_string())?;
+    remove_all(conn, kind, from)
+}
+
+// Drops every field of an entity that's being deleted for good
+pub fn remove_all(conn: &Connection, kind: EntityKind, id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM entity_metadata WHERE entity_type = ?1 AND entity_id = ?2",
+        params![kind.as_str(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
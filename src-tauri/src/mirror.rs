@@ -0,0 +1,288 @@
+use crate::background;
+use crate::commands::row_to_folder;
+use crate::db::Database;
+use crate::export;
+use crate::models::{BackgroundSubsystem, Folder, MirrorConfig};
+use crate::render;
+use crate::timestamps;
+use chrono::{Duration as ChronoDuration, Utc};
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+pub const MIRROR_SETTING: &str = "markdown_mirror";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// A note is written once it's gone this long without an edit, so typing
+// doesn't rewrite the file on every keystroke
+const QUIET_SECONDS: i64 = 3;
+
+// Notes handled per pass; the database is only locked while reading and
+// recording a batch, not while its files are written
+const BATCH: i64 = 100;
+
+pub fn load_config(conn: &Connection) -> Result<Option<MirrorConfig>, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![MIRROR_SETTING],
+            |row| row.get(0),
+        )
+        .ok();
+
+    value
+        .map(|v| serde_json::from_str(&v).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// The writer picks up changes on its next pass
+pub fn save_config(conn: &Connection, config: &MirrorConfig) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            MIRROR_SETTING,
+            serde_json::to_string(config).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// A note whose file is out of date. `path` is where it goes, None if the
+// note was trashed or deleted and its file should go.
+struct Change {
+    note_id: String,
+    title: String,
+    content: String,
+    updated_at: String,
+    old_path: Option<String>,
+    path: Option<String>,
+}
+
+fn file_path(directory: &Path, path: &str) -> PathBuf {
+    path.split('/')
+        .fold(directory.to_path_buf(), |dir, part| dir.join(part))
+}
+
+fn pending(conn: &Connection, directory: &str) -> Result<Vec<Change>, String> {
+    // The mirror was pointed somewhere else; everything is written afresh
+    conn.execute(
+        "DELETE FROM mirror_files WHERE directory != ?1",
+        params![directory],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let quiet_before = timestamps::format(&(Utc::now() - ChronoDuration::seconds(QUIET_SECONDS)));
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.title, n.content, n.folder_id, n.updated_at, n.deleted_at IS NOT NULL,
+                    m.path
+             FROM notes n LEFT JOIN mirror_files m ON m.note_id = n.id
+             WHERE n.updated_at <= ?1
+               AND ((n.deleted_at IS NULL AND (m.updated_at IS NULL OR m.updated_at != n.updated_at))
+                    OR (n.deleted_at IS NOT NULL AND m.path IS NOT NULL))
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    // Each with its folder and whether it's in the trash
+    let rows: Vec<(Change, Option<String>, bool)> = stmt
+        .query_map(params![quiet_before, BATCH], |row| {
+            let change = Change {
+                note_id: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                updated_at: row.get(4)?,
+                old_path: row.get(6)?,
+                path: None,
+            };
+            Ok((change, row.get(3)?, row.get(5)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let folders: Vec<Folder> = conn
+        .prepare("SELECT id, name, parent_id, color, icon, created_at, updated_at FROM folders")
+        .and_then(|mut stmt| stmt.query_map([], row_to_folder)?.collect())
+        .map_err(|e| e.to_string())?;
+    let folder_paths = export::folder_paths(&folders);
+
+    // Paths kept by notes that aren't changing, so two notes with the same
+    // title don't overwrite each other's file
+    let changing: HashSet<&str> = rows
+        .iter()
+        .map(|(change, _, _)| change.note_id.as_str())
+        .collect();
+    let mut taken: HashSet<String> = conn
+        .prepare("SELECT note_id, path FROM mirror_files")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|(note_id, _)| !changing.contains(note_id.as_str()))
+        .map(|(_, path)| path.to_lowercase())
+        .collect();
+
+    let mut changes = Vec::new();
+    for (mut change, folder_id, trashed) in rows {
+        if !trashed {
+            let prefix = folder_id
+                .as_deref()
+                .and_then(|id| folder_paths.get(id))
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| format!("{}/", dir))
+                .unwrap_or_default();
+            let stem = export::file_stem(&change.title);
+            let mut path = format!("{}{}.md", prefix, stem);
+            let mut n = 2;
+            while !taken.insert(path.to_lowercase()) {
+                path = format!("{}{} ({}).md", prefix, stem, n);
+                n += 1;
+            }
+            change.path = Some(path);
+        }
+        changes.push(change);
+    }
+
+    // Notes deleted for good
+    let mut stmt = conn
+        .prepare(
+            "SELECT note_id, path FROM mirror_files WHERE note_id NOT IN (SELECT id FROM notes)",
+        )
+        .map_err(|e| e.to_string())?;
+    let gone = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok());
+    for (note_id, path) in gone {
+        changes.push(Change {
+            note_id,
+            title: String::new(),
+            content: String::new(),
+            updated_at: String::new(),
+            old_path: Some(path),
+            path: None,
+        });
+    }
+
+    Ok(changes)
+}
+
+fn remove_file(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn write(directory: &Path, change: &Change) -> Result<(), String> {
+    if let Some(path) = &change.path {
+        let target = file_path(directory, path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let markdown =
+            render::markdown_document(&change.title, &render::content_to_markdown(&change.content));
+        export::write_atomically(&target, |mut file| {
+            file.write_all(markdown.as_bytes())
+                .map_err(|e| e.to_string())
+        })?;
+    }
+    if let Some(old_path) = change
+        .old_path
+        .as_ref()
+        .filter(|old| change.path.as_ref() != Some(old))
+    {
+        remove_file(&file_path(directory, old_path))?;
+    }
+    Ok(())
+}
+
+fn record(conn: &Connection, directory: &str, change: &Change) -> Result<(), String> {
+    match &change.path {
+        Some(path) => conn.execute(
+            "INSERT OR REPLACE INTO mirror_files (note_id, directory, path, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![change.note_id, directory, path, change.updated_at],
+        ),
+        None => conn.execute(
+            "DELETE FROM mirror_files WHERE note_id = ?1",
+            params![change.note_id],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Brings one batch of files up to date; returns how many it wrote
+fn pass(app: &AppHandle, directory: &str) -> Result<usize, String> {
+    let db = app.state::<Database>();
+    let changes = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        pending(&conn, directory)?
+    };
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    // A note that fails to write is left unrecorded and retried next pass
+    let root = Path::new(directory);
+    let written: Vec<&Change> = changes
+        .iter()
+        .filter(|change| match write(root, change) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Failed to mirror note {}: {}", change.note_id, e);
+                false
+            }
+        })
+        .collect();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    for change in &written {
+        record(&conn, directory, change)?;
+    }
+    Ok(written.len())
+}
+
+fn enabled(app: &AppHandle) -> Option<String> {
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().ok()?;
+    let config = load_config(&conn).ok().flatten()?;
+    // Nothing is written while the directory is missing, e.g. on an
+    // unplugged drive
+    (config.enabled && Path::new(&config.directory).is_dir()).then_some(config.directory)
+}
+
+pub fn spawn_writer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Mirror).await;
+            if let Some(directory) = enabled(&app) {
+                // Keep going while there's a backlog, e.g. the first mirror
+                loop {
+                    match pass(&app, &directory) {
+                        Ok(written) if written as i64 >= BATCH => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            log::warn!("Markdown mirror failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
@@ -1,47 +1,318 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use specta::Type;
+use std::collections::HashMap;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Declares an enum stored as TEXT. Parsing is lenient about values written by
+// older versions (case, spaces/dashes, and the listed legacy spellings) but
+// rejects anything unknown.
+macro_rules! text_enum {
+    ($name:ident { $($variant:ident => $value:literal $(| $alias:literal)*),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Type)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                let normalized = value.trim().to_ascii_lowercase().replace([' ', '-'], "_");
+                match normalized.as_str() {
+                    $($value $(| $alias)* => Ok($name::$variant),)+
+                    _ => Err(format!(
+                        "unknown {} '{}', expected one of: {}",
+                        stringify!($name),
+                        value,
+                        [$($value),+].join(", ")
+                    )),
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?
+                    .parse()
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl ToSql for $name {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                Ok(ToSqlOutput::from(self.as_str()))
+            }
+        }
+
+        impl FromSql for $name {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                value
+                    .as_str()?
+                    .parse()
+                    .map_err(|e: String| FromSqlError::Other(e.into()))
+            }
+        }
+    };
+}
+
+text_enum!(Priority {
+    Low => "low",
+    Medium => "medium" | "normal",
+    High => "high" | "urgent",
+});
+
+text_enum!(EventStatus {
+    Pending => "pending" | "todo" | "open",
+    InProgress => "in_progress" | "inprogress" | "started",
+    Completed => "completed" | "complete" | "done",
+    Cancelled => "cancelled" | "canceled",
+    Missed => "missed",
+    Skipped => "skipped",
+});
+
+// What kind of thing an event is, so the UI can treat tasks, meetings,
+// reminders and deadlines differently
+text_enum!(EventType {
+    Event => "event" | "appointment",
+    Task => "task" | "todo",
+    Meeting => "meeting" | "call",
+    Reminder => "reminder",
+    Deadline => "deadline" | "due",
+});
+
+text_enum!(TimeMode {
+    Todo => "todo",
+    AtTime => "at_time" | "attime" | "timed",
+    AllDay => "all_day" | "allday",
+    Morning => "morning",
+    Day => "day" | "afternoon",
+    Evening => "evening" | "night",
+    Anytime => "anytime" | "any_time",
+});
+
+text_enum!(NodeShape {
+    Circle => "circle" | "ellipse",
+    Rectangle => "rectangle" | "rect" | "square",
+    Diamond => "diamond",
+    Hexagon => "hexagon",
+    Pill => "pill" | "rounded",
+});
+
+text_enum!(NodeSize {
+    Small => "small" | "sm",
+    Medium => "medium" | "md",
+    Large => "large" | "lg",
+    Xl => "xl" | "extra_large" | "xlarge",
+});
+
+text_enum!(ListSort {
+    Name => "name" | "title",
+    Created => "created" | "created_at",
+    Updated => "updated" | "updated_at",
+});
+
+text_enum!(BacklogSort {
+    Priority => "priority",
+    Created => "created" | "created_at",
+});
+
+text_enum!(FormatDirection {
+    Import => "import",
+    Export => "export",
+});
+
+text_enum!(FormatTarget {
+    File => "file",
+    Folder => "folder",
+});
+
+text_enum!(JobState {
+    Queued => "queued" | "pending",
+    Running => "running",
+    Completed => "completed" | "done",
+    Failed => "failed" | "error",
+    Cancelled => "cancelled" | "canceled",
+});
+
+// Paging, search and sort for list commands. Everything is optional; a
+// missing limit falls back to the command's default page size.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ListQuery {
+    pub search: Option<String>,
+    pub sort: Option<ListSort>,
+    pub descending: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+// One page of a list, with the number of matching rows across all pages
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u32,
+}
+
+// An update field that can be cleared as well as set. A missing key leaves
+// the stored value alone and an explicit null clears it, which a plain
+// Option can't tell apart. Fields need #[serde(default)] to be left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Undefined,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    // The new value when set, otherwise None
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Patch::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Undefined => Patch::Undefined,
+            Patch::Null => Patch::Null,
+            Patch::Value(value) => Patch::Value(f(value)),
+        }
+    }
+
+    // What to store in place of `current`
+    pub fn apply(self, current: Option<T>) -> Option<T> {
+        match self {
+            Patch::Undefined => current,
+            Patch::Null => None,
+            Patch::Value(value) => Some(value),
+        }
+    }
+
+    // None when the column is left alone, Some(None) when it's cleared
+    pub fn into_change(self) -> Option<Option<T>> {
+        match self {
+            Patch::Undefined => None,
+            Patch::Null => Some(None),
+            Patch::Value(value) => Some(Some(value)),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Patch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value().serialize(serializer)
+    }
+}
+
+// Only called for keys that are present, so the rest stay Undefined
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => Patch::Value(value),
+            None => Patch::Null,
+        })
+    }
+}
+
+impl<T: Type> Type for Patch<T> {
+    fn inline(
+        type_map: &mut specta::TypeCollection,
+        generics: specta::Generics,
+    ) -> specta::datatype::DataType {
+        Option::<T>::inline(type_map, generics)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Note {
     pub id: String,
     pub title: String,
     pub content: String,
     pub folder_id: Option<String>,
     pub tags: Vec<String>,
+    // Other names the note goes by in [[wikilinks]] and search
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub is_pinned: bool,
-    pub created_at: String,
-    pub updated_at: String,
-    pub deleted_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub published_url: Option<String>,
+    // ISO 639-1 code of the language the note is written in, for the editor's
+    // spellcheck and the lang: search filter; None until it can be told
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct NoteCreate {
     pub title: Option<String>,
     pub content: Option<String>,
     pub folder_id: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub aliases: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct NoteUpdate {
     pub title: Option<String>,
     pub content: Option<String>,
-    pub folder_id: Option<String>,
+    #[serde(default)]
+    pub folder_id: Patch<String>,
     pub tags: Option<Vec<String>>,
+    pub aliases: Option<Vec<String>>,
     pub is_pinned: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+text_enum!(NoteSort {
+    Updated => "updated" | "updated_at",
+    Created => "created" | "created_at",
+    Title => "title" | "name",
+    // Length of the content
+    Size => "size" | "length",
+    // The order set with reorder_notes
+    Manual => "manual" | "custom",
+});
+
+// How a note list is ordered; pinned notes always come first. `descending`
+// defaults to A–Z for titles and manual order and newest or largest first
+// otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub struct NoteSortChoice {
+    pub sort: NoteSort,
+    pub descending: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Folder {
     pub id: String,
     pub name: String,
     pub parent_id: Option<String>,
     pub color: Option<String>,
     pub icon: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct FolderCreate {
     pub name: String,
     pub parent_id: Option<String>,
@@ -49,17 +320,21 @@ pub struct FolderCreate {
     pub icon: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct FolderUpdate {
     pub name: Option<String>,
-    pub parent_id: Option<String>,
-    pub color: Option<String>,
-    pub icon: Option<String>,
+    // Null moves the folder to the top level
+    #[serde(default)]
+    pub parent_id: Patch<String>,
+    #[serde(default)]
+    pub color: Patch<String>,
+    #[serde(default)]
+    pub icon: Patch<String>,
 }
 
 // ============ Event Models ============
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct EventReminder {
     pub id: String,
     pub minutes_before: i32,
@@ -67,78 +342,106 @@ pub struct EventReminder {
     pub reminder_type: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Event {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
-    pub event_type: Option<String>,
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
+    pub event_type: Option<EventType>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
     pub has_scheduled_time: bool,
-    pub time_mode: String,
+    pub time_mode: TimeMode,
     pub duration_minutes: Option<i32>,
     pub location: Option<String>,
     pub category: Option<String>,
     pub color: Option<String>,
-    pub priority: Option<String>,
+    pub priority: Option<Priority>,
     pub tags: Vec<String>,
     pub show_on_calendar: bool,
     pub is_all_day: bool,
     pub is_recurring: bool,
     pub recurring_pattern: Option<String>,
-    pub status: Option<String>,
+    pub status: Option<EventStatus>,
     pub reminders: Vec<EventReminder>,
     pub notes: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub deleted_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    // Minutes needed to get to the event's location
+    pub travel_minutes: Option<i32>,
+    // start_time minus travel_minutes; not stored
+    pub leave_by: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct EventCreate {
     pub title: String,
     pub description: Option<String>,
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
-    pub time_mode: Option<String>,
+    pub event_type: Option<EventType>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub time_mode: Option<TimeMode>,
     pub duration_minutes: Option<i32>,
     pub location: Option<String>,
     pub category: Option<String>,
     pub color: Option<String>,
-    pub priority: Option<String>,
+    pub priority: Option<Priority>,
     pub tags: Option<Vec<String>>,
     pub show_on_calendar: Option<bool>,
     pub is_all_day: Option<bool>,
     pub is_recurring: Option<bool>,
     pub recurring_pattern: Option<String>,
     pub reminders: Option<Vec<EventReminder>>,
+    pub notes: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct EventUpdate {
     pub title: Option<String>,
-    pub description: Option<String>,
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
-    pub time_mode: Option<String>,
-    pub duration_minutes: Option<i32>,
-    pub location: Option<String>,
-    pub category: Option<String>,
-    pub color: Option<String>,
-    pub priority: Option<String>,
+    #[serde(default)]
+    pub description: Patch<String>,
+    pub event_type: Option<EventType>,
+    #[serde(default)]
+    pub start_time: Patch<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Patch<DateTime<Utc>>,
+    pub time_mode: Option<TimeMode>,
+    #[serde(default)]
+    pub duration_minutes: Patch<i32>,
+    #[serde(default)]
+    pub location: Patch<String>,
+    #[serde(default)]
+    pub category: Patch<String>,
+    #[serde(default)]
+    pub color: Patch<String>,
+    pub priority: Option<Priority>,
     pub tags: Option<Vec<String>>,
     pub show_on_calendar: Option<bool>,
     pub is_all_day: Option<bool>,
     pub is_recurring: Option<bool>,
-    pub recurring_pattern: Option<String>,
-    pub status: Option<String>,
+    #[serde(default)]
+    pub recurring_pattern: Patch<String>,
+    pub status: Option<EventStatus>,
     pub reminders: Option<Vec<EventReminder>>,
+    #[serde(default)]
+    pub notes: Patch<String>,
+}
+
+// Filters for get_unscheduled_events. Finished events (completed, cancelled or
+// skipped) are left out unless `include_finished` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct UnscheduledFilter {
+    pub category: Option<String>,
+    pub priority: Option<Priority>,
+    pub search: Option<String>,
+    pub include_finished: Option<bool>,
+    pub sort: Option<BacklogSort>,
 }
 
 // ============ Brain Map Models ============
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMap {
     pub id: String,
     pub title: String,
@@ -149,12 +452,12 @@ pub struct BrainMap {
     pub viewport_y: f64,
     pub viewport_zoom: f64,
     pub theme: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub deleted_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapCreate {
     pub title: Option<String>,
     pub description: Option<String>,
@@ -162,19 +465,29 @@ pub struct BrainMapCreate {
     pub theme: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapUpdate {
     pub title: Option<String>,
-    pub description: Option<String>,
-    pub center_node_id: Option<String>,
+    #[serde(default)]
+    pub description: Patch<String>,
+    #[serde(default)]
+    pub center_node_id: Patch<String>,
     pub center_node_text: Option<String>,
     pub viewport_x: Option<f64>,
     pub viewport_y: Option<f64>,
     pub viewport_zoom: Option<f64>,
-    pub theme: Option<String>,
+    #[serde(default)]
+    pub theme: Patch<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+pub struct BrainMapViewport {
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapNode {
     pub id: String,
     pub brain_map_id: String,
@@ -184,19 +497,19 @@ pub struct BrainMapNode {
     pub x: f64,
     pub y: f64,
     pub color: Option<String>,
-    pub shape: Option<String>,
-    pub size: Option<String>,
+    pub shape: Option<NodeShape>,
+    pub size: Option<NodeSize>,
     pub icon: Option<String>,
     pub linked_note_id: Option<String>,
     pub linked_folder_id: Option<String>,
     pub linked_event_id: Option<String>,
     pub is_collapsed: bool,
     pub layer: i32,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapNodeCreate {
     pub brain_map_id: String,
     pub parent_node_id: Option<String>,
@@ -205,32 +518,41 @@ pub struct BrainMapNodeCreate {
     pub x: Option<f64>,
     pub y: Option<f64>,
     pub color: Option<String>,
-    pub shape: Option<String>,
-    pub size: Option<String>,
+    pub shape: Option<NodeShape>,
+    pub size: Option<NodeSize>,
     pub icon: Option<String>,
     pub linked_note_id: Option<String>,
     pub linked_folder_id: Option<String>,
     pub linked_event_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapNodeUpdate {
-    pub parent_node_id: Option<String>,
+    #[serde(default)]
+    pub parent_node_id: Patch<String>,
     pub label: Option<String>,
-    pub description: Option<String>,
+    #[serde(default)]
+    pub description: Patch<String>,
     pub x: Option<f64>,
     pub y: Option<f64>,
-    pub color: Option<String>,
-    pub shape: Option<String>,
-    pub size: Option<String>,
-    pub icon: Option<String>,
-    pub linked_note_id: Option<String>,
-    pub linked_folder_id: Option<String>,
-    pub linked_event_id: Option<String>,
+    #[serde(default)]
+    pub color: Patch<String>,
+    #[serde(default)]
+    pub shape: Patch<NodeShape>,
+    #[serde(default)]
+    pub size: Patch<NodeSize>,
+    #[serde(default)]
+    pub icon: Patch<String>,
+    #[serde(default)]
+    pub linked_note_id: Patch<String>,
+    #[serde(default)]
+    pub linked_folder_id: Patch<String>,
+    #[serde(default)]
+    pub linked_event_id: Patch<String>,
     pub is_collapsed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapConnection {
     pub id: String,
     pub brain_map_id: String,
@@ -240,10 +562,10 @@ pub struct BrainMapConnection {
     pub color: Option<String>,
     pub style: Option<String>,
     pub animated: bool,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapConnectionCreate {
     pub brain_map_id: String,
     pub source_node_id: String,
@@ -254,9 +576,1420 @@ pub struct BrainMapConnectionCreate {
     pub animated: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BrainMapWithData {
     pub brain_map: BrainMap,
     pub nodes: Vec<BrainMapNode>,
     pub connections: Vec<BrainMapConnection>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BrainMapSummary {
+    pub brain_map: BrainMap,
+    pub node_count: u32,
+    pub connection_count: u32,
+}
+
+// What undo/redo changed: the rows as they are now, and the ids of rows the
+// step removed
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BrainMapOpApplied {
+    pub label: String,
+    pub nodes: Vec<BrainMapNode>,
+    pub connections: Vec<BrainMapConnection>,
+    pub removed_node_ids: Vec<String>,
+    pub removed_connection_ids: Vec<String>,
+}
+
+// A node that links to a note, with the map it appears in
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteNodeLink {
+    pub node_id: String,
+    pub node_label: String,
+    pub brain_map_id: String,
+    pub brain_map_title: String,
+}
+
+// Payload of the note:links-changed event, sent for the note a node was
+// linked to or unlinked from
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteLinksChanged {
+    pub note_id: String,
+    pub node_id: String,
+    pub brain_map_id: String,
+}
+
+// Visual properties applied together to a node or a whole branch. Fields left
+// out are kept as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct NodeStyle {
+    pub color: Option<String>,
+    pub shape: Option<NodeShape>,
+    pub size: Option<NodeSize>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StylePreset {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub shape: Option<NodeShape>,
+    pub size: Option<NodeSize>,
+    pub icon: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StylePresetCreate {
+    pub name: String,
+    pub color: Option<String>,
+    pub shape: Option<NodeShape>,
+    pub size: Option<NodeSize>,
+    pub icon: Option<String>,
+}
+
+// Only what's visible with collapsed branches folded away
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BrainMapVisibleData {
+    pub brain_map: BrainMap,
+    pub nodes: Vec<BrainMapNode>,
+    pub connections: Vec<BrainMapConnection>,
+    // Collapsed node id -> number of nodes hidden beneath it
+    pub hidden_descendants: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NodeDegree {
+    pub node_id: String,
+    pub label: String,
+    pub degree: u32,
+}
+
+// Structure overview of one map. Depth counts parent links from a top-level
+// node (depth 0). Orphans are nodes other than the center with no parent,
+// children or connections.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BrainMapStats {
+    pub node_count: u32,
+    pub connection_count: u32,
+    pub max_depth: u32,
+    pub orphan_nodes: Vec<NodeDegree>,
+    pub most_connected: Vec<NodeDegree>,
+}
+
+// Counts of broken references found by repair_brain_maps; `repaired` is false
+// for a dry run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct BrainMapRepairReport {
+    pub orphaned_nodes: u32,
+    pub missing_parents: u32,
+    pub dangling_connections: u32,
+    pub broken_note_links: u32,
+    pub broken_folder_links: u32,
+    pub broken_event_links: u32,
+    pub repaired: bool,
+}
+
+// ============ Attachment Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Attachment {
+    pub id: String,
+    pub note_id: String,
+    pub kind: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub duration_ms: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Attachments are stored once per distinct content; `saved_bytes` is what
+// storing every copy separately would have added
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AttachmentDedupStats {
+    pub attachments: i64,
+    pub blobs: i64,
+    pub logical_bytes: i64,
+    pub stored_bytes: i64,
+    pub saved_bytes: i64,
+}
+
+// `attachment_warning_bytes` is None when the warning is turned off
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StorageSettings {
+    pub attachment_warning_bytes: Option<i64>,
+}
+
+// Attachment bytes count every note a shared blob is attached to
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteStorage {
+    pub note_id: String,
+    pub title: String,
+    pub folder_id: Option<String>,
+    pub attachments: i64,
+    pub bytes: i64,
+    pub in_trash: bool,
+}
+
+// `folder_id` None is the notes outside any folder
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct FolderStorage {
+    pub folder_id: Option<String>,
+    pub name: String,
+    pub notes: i64,
+    pub bytes: i64,
+}
+
+// A backup written from the app that's still on disk
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BackupFile {
+    pub path: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// Bytes on disk: the database with its write-ahead log, the attachment
+// store, and backups. `notes` is the largest first, capped; `folders` covers
+// every folder with attachments.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StorageUsage {
+    pub database_bytes: i64,
+    pub attachments_bytes: i64,
+    pub backups_bytes: i64,
+    pub backups: Vec<BackupFile>,
+    pub notes: Vec<NoteStorage>,
+    pub folders: Vec<FolderStorage>,
+    pub settings: StorageSettings,
+}
+
+// ============ Search Models ============
+
+// `snippet` is plain text with matches wrapped in <mark>; empty when the
+// search had only filters
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SearchHit {
+    pub note: Note,
+    pub snippet: String,
+    // Combined ranking score; 0 for filter-only searches
+    pub score: f64,
+}
+
+// How search results are ordered. Weights are relative to each other; zero
+// turns a signal off.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SearchRanking {
+    pub relevance: f64,
+    pub recency: f64,
+    pub pinned: f64,
+    pub frequency: f64,
+    // Age at which a note's recency boost has halved
+    pub recency_half_life_days: f64,
+}
+
+impl Default for SearchRanking {
+    fn default() -> Self {
+        Self {
+            relevance: 1.0,
+            recency: 0.3,
+            pinned: 0.2,
+            frequency: 0.2,
+            recency_half_life_days: 30.0,
+        }
+    }
+}
+
+// A saved search shown alongside folders; `query` uses the search_notes syntax
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SmartFolder {
+    pub id: String,
+    pub name: String,
+    pub query: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SmartFolderCreate {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SmartFolderUpdate {
+    pub name: Option<String>,
+    pub query: Option<String>,
+}
+
+// A web link found in a note; `label` is the link text when it differs from the URL
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteBookmark {
+    pub note_id: String,
+    pub url: String,
+    pub label: Option<String>,
+}
+
+// ============ Locale Models ============
+
+text_enum!(WeekStart {
+    Monday => "monday" | "mon",
+    Sunday => "sunday" | "sun",
+    Saturday => "saturday" | "sat",
+});
+
+text_enum!(ClockFormat {
+    TwentyFourHour => "24h" | "24",
+    TwelveHour => "12h" | "12",
+});
+
+// Order of day, month and year in numeric dates
+text_enum!(DateOrder {
+    Ymd => "ymd" | "iso",
+    Dmy => "dmy",
+    Mdy => "mdy" | "us",
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LocaleSettings {
+    pub week_start: WeekStart,
+    pub clock: ClockFormat,
+    pub date_order: DateOrder,
+}
+
+impl Default for LocaleSettings {
+    fn default() -> Self {
+        Self {
+            week_start: WeekStart::Monday,
+            clock: ClockFormat::TwentyFourHour,
+            date_order: DateOrder::Ymd,
+        }
+    }
+}
+
+// A week as the locale counts it; `start` and `end` are YYYY-MM-DD
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct WeekRange {
+    pub start: String,
+    pub end: String,
+    pub year: i32,
+    pub week_number: u32,
+}
+
+// One day of the agenda, with its heading already formatted for the locale
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AgendaDay {
+    // YYYY-MM-DD
+    pub date: String,
+    pub label: String,
+    pub week_number: u32,
+    pub entries: Vec<AgendaEntry>,
+}
+
+// `time_label` is the start time on the configured clock; None for all-day events
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AgendaEntry {
+    pub event: Event,
+    pub time_label: Option<String>,
+}
+
+// ============ Date Parsing Models ============
+
+// A resolved date expression. `timestamp` is the offset-less local form the
+// calendar editor uses, at midnight when no time was given.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ParsedDate {
+    // YYYY-MM-DD
+    pub date: String,
+    // HH:MM
+    pub time: Option<String>,
+    pub timestamp: String,
+}
+
+// ============ Inbox Models ============
+
+// A quick-add entry waiting for triage. `text` is what was typed; title,
+// tags, due date, priority and folder were parsed out of it.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct InboxItem {
+    pub id: String,
+    pub text: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    // YYYY-MM-DD
+    pub due_date: Option<String>,
+    pub priority: Option<Priority>,
+    // e.g. "Work/Projects", used when the item is filed as a note
+    pub folder_path: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+text_enum!(TriageAction {
+    Note => "note",
+    Task => "task" | "todo",
+    Dismiss => "dismiss" | "delete" | "discard",
+});
+
+// Exactly one of `inbox_item` and `event` is set: dated entries are filed as
+// tasks right away, everything else waits in the inbox
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct QuickAddResult {
+    pub inbox_item: Option<InboxItem>,
+    pub event: Option<Event>,
+}
+
+// What a triaged item became; both are None when it was dismissed
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TriageResult {
+    pub note: Option<Note>,
+    pub event: Option<Event>,
+}
+
+// ============ Productivity Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BusyDay {
+    // YYYY-MM-DD
+    pub date: String,
+    pub event_count: u32,
+    pub scheduled_hours: f64,
+}
+
+// Totals for the events starting and notes created within a date range.
+// Scheduled hours leave out all-day and cancelled events.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProductivityMetrics {
+    pub events_total: u32,
+    pub events_completed: u32,
+    pub events_cancelled: u32,
+    pub events_missed: u32,
+    pub busiest_days: Vec<BusyDay>,
+    pub average_scheduled_hours: f64,
+    pub notes_created: u32,
+}
+
+// ============ Holiday Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Holiday {
+    // YYYY-MM-DD
+    pub date: String,
+    pub name: String,
+    pub region: String,
+}
+
+// A bundled holiday calendar; `selected` when it's shown on the calendar
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct HolidayRegion {
+    pub code: String,
+    pub name: String,
+    pub selected: bool,
+}
+
+// ============ Publish Models ============
+
+// Where publish_note uploads pages. The POST bearer token or the S3 secret
+// access key is kept in the OS keychain, not here.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PublishTarget {
+    // Simple HTTP target: receives the page as a POST body and answers with
+    // `{ "url": "..." }` (or a Location header). Unpublish sends DELETE.
+    Post { endpoint: String },
+    // S3-compatible bucket (AWS, R2, MinIO, ...) addressed path-style.
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        public_base_url: Option<String>,
+    },
+}
+
+// ============ Email Capture Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct EmailCaptureConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub mailbox: String,
+    pub folder_id: Option<String>,
+    pub poll_interval_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct EmailPollResult {
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+// ============ Feed Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Feed {
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub site_url: Option<String>,
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct FeedItem {
+    pub id: String,
+    pub feed_id: String,
+    pub guid: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub summary: Option<String>,
+    pub content: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub is_read: bool,
+    pub note_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ============ Recovery Code Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RecoveryCodeStatus {
+    pub total: i64,
+    pub remaining: i64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// ============ Demo Data Models ============
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct SeedSummary {
+    pub folders: u32,
+    pub notes: u32,
+    pub events: u32,
+    pub nodes: u32,
+    pub connections: u32,
+}
+
+// ============ Profiling Models ============
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct CommandStats {
+    pub name: String,
+    pub calls: u32,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub avg_rows: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct QueryStats {
+    pub sql: String,
+    pub calls: u32,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PerformanceReport {
+    pub enabled: bool,
+    pub commands: Vec<CommandStats>,
+    pub queries: Vec<QueryStats>,
+}
+
+// ============ Job Models ============
+
+// What an import or export did with each item. `errors` names the items
+// that failed without stopping the job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct JobReport {
+    pub created: u32,
+    pub updated: u32,
+    pub skipped: u32,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub attempts: u32,
+    pub done: u32,
+    pub total: u32,
+    pub message: Option<String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub report: Option<JobReport>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+text_enum!(ReminderKind {
+    Reminder => "reminder",
+    Leave => "leave",
+});
+
+// Payload of the `reminder:due` event. `fire_at` is when it was due; a
+// "leave" reminder fires at the event's leave_by time.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ReminderDue {
+    pub event_id: String,
+    pub title: String,
+    pub kind: ReminderKind,
+    pub fire_at: DateTime<Utc>,
+    pub start_time: String,
+    pub location: Option<String>,
+}
+
+// Payload of the `job:progress` event. `report` is set once the job ends.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct JobProgress {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub done: u32,
+    pub total: u32,
+    pub message: Option<String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub report: Option<JobReport>,
+}
+
+// A color offered by pickers for folders, events and map nodes. `custom`
+// marks the user's own additions, which can be removed again.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PaletteColor {
+    pub name: String,
+    pub hex: String,
+    #[serde(default)]
+    pub custom: bool,
+}
+
+// An icon that can be set on folders and map nodes. `id` is what gets
+// stored; `glyph` is what's shown.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct CatalogIcon {
+    pub id: String,
+    pub label: String,
+    pub glyph: String,
+    #[serde(default)]
+    pub custom: bool,
+}
+
+// What a metadata entry is attached to
+text_enum!(EntityKind {
+    Note => "note",
+    Event => "event",
+    Folder => "folder",
+    Snippet => "snippet",
+});
+
+// One custom field. `value` is JSON text, so it can hold a string, number,
+// list or object.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct MetadataEntry {
+    pub key: String,
+    pub value: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// How a front matter value reads: `2024-05-01` is a date, `[a, b]` a list
+text_enum!(PropertyKind {
+    Text => "text",
+    Number => "number",
+    Checkbox => "checkbox",
+    Date => "date",
+    DateTime => "date_time",
+    List => "list",
+});
+
+// One entry of a note's YAML front matter. `value` is JSON text.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteProperty {
+    pub key: String,
+    pub kind: PropertyKind,
+    pub value: String,
+}
+
+// Everything the "today" screen shows, for the local day `date` (YYYY-MM-DD)
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TodayView {
+    pub date: String,
+    // Calendar events starting today, in start order
+    pub events: Vec<Event>,
+    // Open tasks due today or overdue
+    pub tasks: Vec<Event>,
+    // Recurring tasks with an occurrence today not yet completed
+    pub habits: Vec<Event>,
+    pub notes: Vec<Note>,
+    // Reminders and leave alerts going off today, fired or not
+    pub reminders: Vec<ReminderDue>,
+}
+
+text_enum!(RecurrenceFrequency {
+    Daily => "daily",
+    Weekly => "weekly",
+    Monthly => "monthly",
+    Yearly => "yearly",
+});
+
+text_enum!(DayOfWeek {
+    Monday => "mon" | "monday",
+    Tuesday => "tue" | "tuesday",
+    Wednesday => "wed" | "wednesday",
+    Thursday => "thu" | "thursday",
+    Friday => "fri" | "friday",
+    Saturday => "sat" | "saturday",
+    Sunday => "sun" | "sunday",
+});
+
+fn one() -> u32 {
+    1
+}
+
+// When a recurring task comes due, counted from the day it starts on.
+// `interval` is in units of `frequency` (every 2 weeks); `weekdays` only
+// applies to weekly rules and defaults to the start day. `until` is the last
+// possible date (YYYY-MM-DD).
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    #[serde(default = "one")]
+    pub interval: u32,
+    #[serde(default)]
+    pub weekdays: Vec<DayOfWeek>,
+    pub until: Option<String>,
+}
+
+text_enum!(OccurrenceStatus {
+    Completed => "completed",
+    Missed => "missed",
+    Pending => "pending",
+});
+
+// One due date of a recurring task. Past dates that weren't completed are
+// missed; today and later are pending.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TaskOccurrence {
+    pub date: String,
+    pub status: OccurrenceStatus,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// A recurring task's occurrences in the requested range. Streaks and totals
+// cover everything from the first occurrence up to today; an open occurrence
+// today doesn't break the current streak.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TaskHistory {
+    pub event_id: String,
+    pub rule: RecurrenceRule,
+    pub occurrences: Vec<TaskOccurrence>,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub completed: u32,
+    pub missed: u32,
+}
+
+// An open task in dependency order. `step` counts the unfinished tasks
+// ahead of it in the chain (0 can start now) and is None for tasks that
+// can't be ordered because they're in, or wait on, a cycle. `blocked_by`
+// lists unfinished dependencies, in the plan or not.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PlannedTask {
+    pub task: Event,
+    pub depends_on: Vec<String>,
+    pub blocked_by: Vec<String>,
+    pub step: Option<u32>,
+    pub in_cycle: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TaskPlan {
+    pub tasks: Vec<PlannedTask>,
+    // Task ids of each dependency cycle found
+    pub cycles: Vec<Vec<String>>,
+}
+
+// ============ Project Models ============
+
+text_enum!(ProjectStatus {
+    Active => "active",
+    OnHold => "on_hold" | "paused",
+    Completed => "completed" | "done",
+    Archived => "archived",
+});
+
+// What can be linked to a project; tasks are events
+text_enum!(ProjectItemKind {
+    Note => "note",
+    Event => "event",
+    BrainMap => "brain_map" | "map",
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub status: ProjectStatus,
+    // YYYY-MM-DD
+    pub due_date: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProjectCreate {
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub due_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProjectUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub status: Option<ProjectStatus>,
+    pub due_date: Option<String>,
+}
+
+// Task counts leave out cancelled tasks. `progress` is the share of them
+// completed, 0-100.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProjectStats {
+    pub tasks_total: u32,
+    pub tasks_completed: u32,
+    pub tasks_overdue: u32,
+    pub upcoming_events: u32,
+    pub notes: u32,
+    pub brain_maps: u32,
+    pub progress: u32,
+}
+
+// A linked item, by when it last changed
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProjectActivity {
+    pub kind: ProjectItemKind,
+    pub id: String,
+    pub title: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Everything linked to a project that isn't in the trash
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProjectOverview {
+    pub project: Project,
+    pub notes: Vec<Note>,
+    pub events: Vec<Event>,
+    pub tasks: Vec<Event>,
+    pub brain_maps: Vec<BrainMap>,
+    pub stats: ProjectStats,
+    pub recent_activity: Vec<ProjectActivity>,
+}
+
+// ============ Goal Models ============
+
+// How a goal's progress is measured. `completion` is the share of linked
+// tasks done and habit occurrences completed so far; `count` adds those up
+// against the goal's target.
+text_enum!(GoalMetric {
+    Completion => "completion" | "percent",
+    Count => "count",
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Goal {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub metric: GoalMetric,
+    // Required for `count` goals
+    pub target: Option<u32>,
+    // YYYY-MM-DD
+    pub due_date: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct GoalCreate {
+    pub title: String,
+    pub description: Option<String>,
+    pub metric: Option<GoalMetric>,
+    pub target: Option<u32>,
+    pub due_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct GoalUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub metric: Option<GoalMetric>,
+    pub target: Option<u32>,
+    pub due_date: Option<String>,
+}
+
+// One linked task or habit. A one-off task counts once; a habit counts each
+// occurrence from the day the goal was set until today or its due date.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct GoalItemProgress {
+    pub task: Event,
+    pub recurring: bool,
+    pub done: u32,
+    pub due: u32,
+}
+
+// `percent` is `current` over `target`, capped at 100
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub items: Vec<GoalItemProgress>,
+    pub current: u32,
+    pub target: u32,
+    pub percent: u32,
+    // Negative once the due date has passed
+    pub days_left: Option<i64>,
+}
+
+// ============ Note Comment Models ============
+
+// A margin comment on part of a note. `offset` and `length` are in UTF-16
+// code units of the stored content, like JS string indices. `quote` is the
+// text the comment was left on; when edits move it the comment follows, and
+// if it's gone the comment is `detached` and keeps its last position.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteComment {
+    pub id: String,
+    pub note_id: String,
+    pub offset: u32,
+    pub length: u32,
+    pub quote: String,
+    pub body: String,
+    pub resolved: bool,
+    pub detached: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteCommentCreate {
+    pub offset: u32,
+    pub length: u32,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteCommentUpdate {
+    pub body: Option<String>,
+    pub resolved: Option<bool>,
+}
+
+// ============ Sync Models ============
+
+// Records another device changed, as it has them. Items carry their own
+// updated_at; `device` names the sender in conflict copies.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RemoteChanges {
+    pub device: String,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub note_docs: Vec<NoteDoc>,
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+// A note's content CRDT, base64 encoded. Sent with changed notes so both
+// devices' edits merge character by character.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteDoc {
+    pub note_id: String,
+    pub doc: String,
+}
+
+// A note or event created because this device and another both changed it
+// since they last synced. `id` is the copy, holding the other device's
+// version; `title` is that version's title without the conflict label.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ConflictCopy {
+    pub id: String,
+    pub kind: EntityKind,
+    pub original_id: String,
+    pub title: String,
+    pub device: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SyncReport {
+    pub applied: u32,
+    pub unchanged: u32,
+    pub conflicts: Vec<ConflictCopy>,
+}
+
+// Which version to keep: `original` trashes the copy, `copy` moves its
+// version into the original and trashes it, `both` leaves them as separate
+// items
+text_enum!(ConflictResolution {
+    Original => "original" | "keep_original",
+    Copy => "copy" | "keep_copy",
+    Both => "both" | "keep_both",
+});
+
+// ============ LAN Sync Models ============
+
+// Syncing straight with this user's other devices on the local network.
+// Devices find each other over mDNS and only talk to ones that know the same
+// passphrase, which is kept in the OS keychain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct LanSyncConfig {
+    pub enabled: bool,
+    pub device_name: String,
+    // Listening port; any free one when None
+    pub port: Option<u16>,
+}
+
+// A device found on the network
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LanPeer {
+    pub device_id: String,
+    pub name: String,
+    pub address: String,
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LanSyncResult {
+    pub peer: String,
+    // Notes and events sent to the peer
+    pub sent: u32,
+    // What merging the peer's changes did here
+    pub received: SyncReport,
+}
+
+// ============ Link Check Models ============
+
+// Why a wikilink doesn't reach a live note or event: nothing by that name or
+// id, the target is in the trash, or it names a note by a title it had before
+// being renamed
+text_enum!(BrokenLinkReason {
+    Missing => "missing",
+    Trashed => "trashed",
+    Renamed => "renamed",
+});
+
+// `target` is the link as written. `target_id`/`target_title` are the note or
+// event it most likely meant, when there is one; `repaired` once the link was
+// rewritten to the renamed note's current title.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BrokenLink {
+    pub source_note_id: String,
+    pub source_title: String,
+    pub target: String,
+    pub reason: BrokenLinkReason,
+    pub target_id: Option<String>,
+    pub target_title: Option<String>,
+    pub repaired: bool,
+}
+
+// `update_on_rename` rewrites [[wikilinks]] in other notes when a note's
+// title changes
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LinkSettings {
+    pub update_on_rename: bool,
+}
+
+// A note as it was before a change made on the user's behalf, e.g. its
+// links being updated after another note was renamed
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteRevision {
+    pub seq: i64,
+    pub note_id: String,
+    pub title: String,
+    pub content: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// A note a wikilink can point at. `alias` is the alias that matched when the
+// title didn't; `exact` when the whole name matched rather than part of it.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LinkTarget {
+    pub note_id: String,
+    pub title: String,
+    pub alias: Option<String>,
+    pub exact: bool,
+}
+
+// What `![[target#heading]]` in `source_note_id` shows, with `target` as
+// written. `html` is None when the note or heading doesn't exist, or when
+// showing it would embed something already being shown (`cyclic`).
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Embed {
+    pub source_note_id: String,
+    pub target: String,
+    pub note_id: Option<String>,
+    pub heading: Option<String>,
+    pub html: Option<String>,
+    pub cyclic: bool,
+}
+
+// ============ Outline Models ============
+
+// A heading with the ones nested under it. `offset` is where the heading
+// starts in the note content and `end` where its section stops, in UTF-16
+// code units like JavaScript string indices.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct OutlineHeading {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+    pub offset: u32,
+    pub end: u32,
+    pub children: Vec<OutlineHeading>,
+}
+
+// ============ Markdown Mirror Models ============
+
+// Keeps a Markdown copy of every live note in `directory`, laid out like the
+// folder tree, so the notes survive losing the database
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct MirrorConfig {
+    pub enabled: bool,
+    pub directory: String,
+}
+
+// ============ Watched Folder Models ============
+
+// Notes in `folder_id` and the .md files directly inside `directory` are kept
+// in step both ways
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct WatchedFolder {
+    pub folder_id: String,
+    pub directory: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// ============ System Search Models ============
+
+// Whether notes are handed to Spotlight / Windows Search, each result
+// opening the note through a voyena://note/{id} link
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SystemSearchSettings {
+    pub enabled: bool,
+}
+
+// ============ Share Target Models ============
+
+text_enum!(ShareDestination {
+    Note => "note",
+    Inbox => "inbox",
+});
+
+// Something another app handed over, e.g. from the Explorer context menu or
+// a voyena://share link. `files` is the token of a PendingShare, never paths.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SharedContent {
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub url: Option<String>,
+    pub files: Option<String>,
+    // Files always make a note; otherwise the inbox unless asked for a note
+    pub destination: Option<ShareDestination>,
+}
+
+// Files shared from the OS, held until receive_shared_content saves them.
+// Also the payload of the share:files event.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PendingShare {
+    pub token: String,
+    pub file_names: Vec<String>,
+}
+
+// Exactly one is set. Also the payload of the share:received event.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ShareResult {
+    pub note: Option<Note>,
+    pub inbox_item: Option<InboxItem>,
+}
+
+// ============ Clipboard Capture Models ============
+
+// A global shortcut that appends the clipboard to today's daily note.
+// `shortcut` is e.g. "CommandOrControl+Shift+D".
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ClipboardCaptureSettings {
+    pub enabled: bool,
+    pub shortcut: String,
+}
+
+// Payload of the clipboard:captured event. `source_app` is the app that was
+// in front when the shortcut was pressed, where the OS can tell.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ClipboardCaptured {
+    pub note: Note,
+    pub source_app: Option<String>,
+}
+
+// ============ Platform Models ============
+
+// Which subsystems this build runs, so the settings screens can hide the rest
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PlatformCapabilities {
+    pub mobile: bool,
+    pub markdown_mirror: bool,
+    pub watched_folders: bool,
+    pub system_search: bool,
+    pub lan_sync: bool,
+    // "Send to Voyena" on the Explorer menu
+    pub share_menu: bool,
+    // A global shortcut appends the clipboard to the daily note
+    pub clipboard_capture: bool,
+    // Reminders are scheduled as OS notifications rather than fired by the
+    // app as reminder:due events
+    pub scheduled_notifications: bool,
+}
+
+// ============ Background Activity Models ============
+
+text_enum!(ActivityLevel {
+    Full => "full",
+    // Nothing that reaches the network or builds indexes
+    Reduced => "reduced" | "low_power",
+    // Only work the user starts, e.g. imports and exports
+    Paused => "paused",
+});
+
+text_enum!(BackgroundSubsystem {
+    Feeds => "feeds",
+    Email => "email",
+    LanSync => "lan_sync",
+    SystemSearch => "system_search",
+    Mirror => "mirror",
+    WatchedFolders => "watched_folders",
+    Reminders => "reminders",
+    Maintenance => "maintenance",
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BackgroundActivity {
+    pub level: ActivityLevel,
+    pub paused: Vec<BackgroundSubsystem>,
+}
+
+// ============ Startup Recovery Models ============
+
+text_enum!(StartupProblemKind {
+    Corrupt => "corrupt",
+    // Held by another process, e.g. a second copy of the app
+    Locked => "locked",
+    // Anything else, like a missing permission or a full disk
+    Unavailable => "unavailable",
+});
+
+// Why the database couldn't be opened at startup, for the recovery window
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StartupProblem {
+    pub kind: StartupProblemKind,
+    pub message: String,
+    pub database_path: String,
+    // Backups recorded in the database, if it could still be read
+    pub backups: Vec<BackupFile>,
+    // Set once the user chose to carry on with the database read-only
+    pub read_only: bool,
+}
+
+// ============ Diagnostics Models ============
+
+// Payload of the app:panic event
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PanicReport {
+    pub message: String,
+    // file:line:column
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// ============ Trash Models ============
+
+// How long trashed notes, events and maps are kept before the maintenance
+// scheduler deletes them for good; None keeps them until emptied by hand
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TrashSettings {
+    pub retention_days: Option<u32>,
+}
+
+text_enum!(RestoreStatus {
+    Restored => "restored",
+    // The note's folder is gone and nothing was restored; call again with a
+    // RestoreFolderAction
+    FolderMissing => "folder_missing",
+});
+
+text_enum!(RestoreFolderAction {
+    // Create the folders along the original path again
+    Recreate => "recreate",
+    Root => "root",
+    // Into the folder given as `folder_id`
+    Folder => "folder",
+});
+
+// `folder_path` is where the note was filed when it was trashed, e.g.
+// "Work/Projects", for offering the choices when its folder is missing
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct NoteRestore {
+    pub status: RestoreStatus,
+    pub note: Note,
+    pub folder_path: Option<String>,
+}
+
+// A trashed note as the trash list shows it. `snippet` is the start of its
+// text; `folder_path` is where it was filed, e.g. "Work/Projects".
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct DeletedNotePreview {
+    pub id: String,
+    pub title: String,
+    pub snippet: String,
+    pub deleted_at: DateTime<Utc>,
+    pub folder_path: Option<String>,
+}
+
+// What a purge deleted. `bytes_reclaimed` is note and revision text plus
+// attachment files no other note uses; the database file itself only
+// shrinks once SQLite reuses or vacuums the freed pages.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PurgeReport {
+    pub notes: i64,
+    pub events: i64,
+    pub brain_maps: i64,
+    pub revisions: i64,
+    pub attachments: i64,
+    pub bytes_reclaimed: i64,
+}
+
+// ============ Snippet Models ============
+
+// A block of text stored centrally and typed by its trigger, e.g. ";sig" for
+// a signature. `name` is an optional label for the snippet list.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Snippet {
+    pub id: String,
+    pub trigger: String,
+    pub name: Option<String>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SnippetCreate {
+    pub trigger: String,
+    pub name: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SnippetUpdate {
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub name: Patch<String>,
+    pub content: Option<String>,
+}
+
+// ============ Format Registry Models ============
+
+// An import or export format as the import and export dialogs offer it. A
+// format that works both ways is listed once for each direction, under the
+// same id. `target` is what the dialog picks: files (one to save, any number
+// to import) or a folder; `extensions` are without the dot, empty for folders.
+// Imports with `preview` can be dry-run with preview_import first.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SupportedFormat {
+    pub id: String,
+    pub name: String,
+    pub direction: FormatDirection,
+    pub target: FormatTarget,
+    pub extensions: Vec<String>,
+    pub preview: bool,
+}
+
+// What an import would do. `duplicates` are notes already there from an
+// earlier import, which it skips; `problems` are files it can't read.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ImportSummary {
+    pub folders: u32,
+    pub notes: u32,
+    pub attachments: u32,
+    pub duplicates: u32,
+    pub problems: Vec<String>,
+}
+
+// A dry run waiting to be confirmed; pass `id` to confirm_import
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ImportPreview {
+    pub id: String,
+    pub format: String,
+    pub summary: ImportSummary,
+}
+
+// ============ Script Models ============
+
+// A user script. It subscribes to data events by defining handlers like
+// `fn on_note_created(note)`, and can call create_note(title, content),
+// tag_note(id, tag) and schedule_event(title, when). `last_error` is from
+// the last run, None when it went through.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Script {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScriptCreate {
+    pub name: String,
+    pub source: String,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScriptUpdate {
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+// ============ Action Registry Models ============
+
+// A backend command as the command palette and automation rules offer it.
+// `id` is the command name passed to invoke().
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Action {
+    pub id: String,
+    pub title: String,
+    pub params: Vec<ActionParam>,
+}
+
+// `name` is the argument's key in invoke()'s arguments and `schema` the JSON
+// Schema its value must match. Arguments that aren't required can be null.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ActionParam {
+    pub name: String,
+    pub required: bool,
+    pub schema: serde_json::Value,
+}
@@ -0,0 +1,85 @@
+use crate::models::{NoteSort, NoteSortChoice};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const DEFAULT: NoteSortChoice = NoteSortChoice {
+    sort: NoteSort::Updated,
+    descending: None,
+};
+
+// Choices are kept per folder; '' is the list of all notes
+fn key(folder_id: Option<&str>) -> &str {
+    folder_id.unwrap_or("")
+}
+
+fn descending(choice: &NoteSortChoice) -> bool {
+    choice
+        .descending
+        .unwrap_or(!matches!(choice.sort, NoteSort::Title | NoteSort::Manual))
+}
+
+// The folder's last choice, with `descending` filled in
+pub fn load(conn: &Connection, folder_id: Option<&str>) -> Result<NoteSortChoice, String> {
+    let saved = conn
+        .query_row(
+            "SELECT sort, descending FROM note_sort_choices WHERE folder_id = ?1",
+            params![key(folder_id)],
+            |row| {
+                Ok(NoteSortChoice {
+                    sort: row.get("sort")?,
+                    descending: Some(row.get("descending")?),
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let choice = saved.unwrap_or(DEFAULT);
+    Ok(NoteSortChoice {
+        descending: Some(descending(&choice)),
+        ..choice
+    })
+}
+
+pub fn save(
+    conn: &Connection,
+    folder_id: Option<&str>,
+    choice: &NoteSortChoice,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO note_sort_choices (folder_id, sort, descending)
+         VALUES (?1, ?2, ?3)",
+        params![key(folder_id), choice.sort, descending(choice)],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// An ORDER BY for notes. Pinned notes lead, and ties, like notes never
+// placed by hand, go most recently edited first.
+pub fn order_by(choice: &NoteSortChoice) -> String {
+    let column = match choice.sort {
+        NoteSort::Updated => "updated_at",
+        NoteSort::Created => "created_at",
+        NoteSort::Title => "title COLLATE NOCASE",
+        NoteSort::Size => "length(content)",
+        NoteSort::Manual => "position",
+    };
+    format!(
+        "is_pinned DESC, {} {}, updated_at DESC",
+        column,
+        if descending(choice) { "DESC" } else { "ASC" }
+    )
+}
+
+// Numbers the notes in the order given. The order isn't part of a note's
+// content, so updated_at is left alone.
+pub fn reorder(conn: &Connection, note_ids: &[String]) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (position, id) in note_ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE notes SET position = ?1 WHERE id = ?2",
+            params![position as i64, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
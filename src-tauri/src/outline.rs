@@ -0,0 +1,124 @@
+use crate::front_matter;
+use crate::models::OutlineHeading;
+use crate::render;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use scraper::Html;
+use std::sync::OnceLock;
+
+// "My Heading" and "my-heading" both name the same section
+pub fn slug(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+fn heading_tag() -> &'static Regex {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    HEADING.get_or_init(|| {
+        Regex::new(r"(?is)<h([1-6])[^>]*>(.*?)</h[1-6]>").expect("valid heading regex")
+    })
+}
+
+// Level, text and byte offset of each heading in editor HTML
+pub fn html_headings(html: &str) -> Vec<(u8, String, usize)> {
+    heading_tag()
+        .captures_iter(html)
+        .map(|c| {
+            let text: String = Html::parse_fragment(&c[2]).root_element().text().collect();
+            let start = c.get(0).map_or(0, |m| m.start());
+            (c[1].parse().unwrap_or(6), text.trim().to_string(), start)
+        })
+        .collect()
+}
+
+// Headings in code blocks and HTML blocks aren't headings
+fn markdown_headings(markdown: &str) -> Vec<(u8, String, usize)> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut headings = Vec::new();
+    let mut current: Option<(u8, String, usize)> = None;
+    for (event, range) in Parser::new_ext(markdown, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((level as u8, String::new(), range.start));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading, _)) = current.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text, start)) = current.take() {
+                    headings.push((level, text.trim().to_string(), start));
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+fn nest(flat: &[OutlineHeading], next: &mut usize, parent_level: u8) -> Vec<OutlineHeading> {
+    let mut nested = Vec::new();
+    while *next < flat.len() && flat[*next].level > parent_level {
+        let mut heading = flat[*next].clone();
+        *next += 1;
+        heading.children = nest(flat, next, heading.level);
+        nested.push(heading);
+    }
+    nested
+}
+
+// The note's headings as a tree. A section ends at the next heading of the
+// same or a higher level.
+pub fn outline(content: &str) -> Vec<OutlineHeading> {
+    let body = front_matter::body(content);
+    let base = content.len() - body.len();
+    let headings = if render::looks_like_html(body) {
+        html_headings(body)
+    } else {
+        markdown_headings(body)
+    };
+
+    // Byte offsets to UTF-16 in one pass; headings come in document order
+    let mut units = 0usize;
+    let mut counted = 0usize;
+    let mut utf16 = |byte: usize| -> u32 {
+        units += content[counted..byte].encode_utf16().count();
+        counted = byte;
+        units as u32
+    };
+    let offsets: Vec<u32> = headings
+        .iter()
+        .map(|(_, _, start)| utf16(base + start))
+        .collect();
+    let total = utf16(content.len());
+
+    let flat: Vec<OutlineHeading> = headings
+        .iter()
+        .enumerate()
+        .map(|(index, (level, text, _))| {
+            let end = headings[index + 1..]
+                .iter()
+                .zip(&offsets[index + 1..])
+                .find(|((l, _, _), _)| l <= level)
+                .map_or(total, |(_, offset)| *offset);
+            OutlineHeading {
+                level: *level,
+                text: text.clone(),
+                slug: slug(text),
+                offset: offsets[index],
+                end,
+                children: Vec::new(),
+            }
+        })
+        .collect();
+
+    nest(&flat, &mut 0, 0)
+}
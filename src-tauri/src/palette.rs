@@ -0,0 +1,127 @@
+use crate::models::{CatalogIcon, PaletteColor};
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub const CUSTOM_COLORS_SETTING: &str = "custom_colors";
+pub const CUSTOM_ICONS_SETTING: &str = "custom_icons";
+
+// (name, hex), in picker order
+const COLORS: &[(&str, &str)] = &[
+    ("Indigo", "#6366f1"),
+    ("Violet", "#8b5cf6"),
+    ("Pink", "#ec4899"),
+    ("Rose", "#f43f5e"),
+    ("Orange", "#f97316"),
+    ("Yellow", "#eab308"),
+    ("Green", "#22c55e"),
+    ("Teal", "#14b8a6"),
+    ("Cyan", "#06b6d4"),
+    ("Blue", "#3b82f6"),
+    ("Slate", "#64748b"),
+];
+
+// (id, label, glyph)
+const ICONS: &[(&str, &str, &str)] = &[
+    ("folder", "Folder", "📁"),
+    ("note", "Note", "📝"),
+    ("book", "Book", "📚"),
+    ("brain", "Brain", "🧠"),
+    ("idea", "Idea", "💡"),
+    ("star", "Star", "⭐"),
+    ("heart", "Heart", "❤️"),
+    ("flag", "Flag", "🚩"),
+    ("pin", "Pin", "📌"),
+    ("calendar", "Calendar", "📅"),
+    ("clock", "Clock", "⏰"),
+    ("check", "Check", "✅"),
+    ("target", "Target", "🎯"),
+    ("rocket", "Rocket", "🚀"),
+    ("briefcase", "Work", "💼"),
+    ("home", "Home", "🏠"),
+    ("person", "Person", "👤"),
+    ("people", "People", "👥"),
+    ("travel", "Travel", "✈️"),
+    ("money", "Money", "💰"),
+    ("health", "Health", "🩺"),
+    ("fitness", "Fitness", "🏃"),
+    ("music", "Music", "🎵"),
+    ("code", "Code", "💻"),
+    ("link", "Link", "🔗"),
+    ("archive", "Archive", "🗄️"),
+];
+
+fn load<T: DeserializeOwned>(conn: &Connection, key: &str) -> Vec<T> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| serde_json::from_str(&value).ok())
+    .unwrap_or_default()
+}
+
+fn save<T: Serialize>(conn: &Connection, key: &str, items: &[T]) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            key,
+            serde_json::to_string(items).map_err(|e| e.to_string())?
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Built-in colors first, then the user's additions in the order they were added
+pub fn colors(conn: &Connection) -> Vec<PaletteColor> {
+    let builtin = COLORS.iter().map(|(name, hex)| PaletteColor {
+        name: name.to_string(),
+        hex: hex.to_string(),
+        custom: false,
+    });
+    builtin.chain(custom_colors(conn)).collect()
+}
+
+pub fn custom_colors(conn: &Connection) -> Vec<PaletteColor> {
+    load::<PaletteColor>(conn, CUSTOM_COLORS_SETTING)
+        .into_iter()
+        .map(|color| PaletteColor {
+            custom: true,
+            ..color
+        })
+        .collect()
+}
+
+pub fn save_custom_colors(conn: &Connection, colors: &[PaletteColor]) -> Result<(), String> {
+    save(conn, CUSTOM_COLORS_SETTING, colors)
+}
+
+pub fn icons(conn: &Connection) -> Vec<CatalogIcon> {
+    let builtin = ICONS.iter().map(|(id, label, glyph)| CatalogIcon {
+        id: id.to_string(),
+        label: label.to_string(),
+        glyph: glyph.to_string(),
+        custom: false,
+    });
+    builtin.chain(custom_icons(conn)).collect()
+}
+
+pub fn custom_icons(conn: &Connection) -> Vec<CatalogIcon> {
+    load::<CatalogIcon>(conn, CUSTOM_ICONS_SETTING)
+        .into_iter()
+        .map(|icon| CatalogIcon {
+            custom: true,
+            ..icon
+        })
+        .collect()
+}
+
+pub fn save_custom_icons(conn: &Connection, icons: &[CatalogIcon]) -> Result<(), String> {
+    save(conn, CUSTOM_ICONS_SETTING, icons)
+}
+
+pub fn is_builtin_icon(id: &str) -> bool {
+    ICONS.iter().any(|(builtin, _, _)| *builtin == id)
+}
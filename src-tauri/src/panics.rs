@@ -0,0 +1,39 @@
+use crate::models::PanicReport;
+use crate::timestamps;
+use tauri::{AppHandle, Emitter};
+
+pub const PANIC_EVENT: &str = "app:panic";
+
+// Logs every panic with where it happened and reports it to the frontend as
+// the app:panic event, so a command that panicked shows up as an error
+// rather than a call that never answers. The default hook still runs after.
+pub fn install_hook(app: AppHandle) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let report = PanicReport {
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(String::from),
+            occurred_at: timestamps::now(),
+        };
+
+        log::error!(
+            "Panic in {} at {}: {}",
+            report.thread.as_deref().unwrap_or("an unnamed thread"),
+            report.location.as_deref().unwrap_or("an unknown location"),
+            report.message
+        );
+        if let Err(e) = app.emit(PANIC_EVENT, report) {
+            log::error!("Failed to emit {}: {}", PANIC_EVENT, e);
+        }
+        default_hook(info);
+    }));
+}
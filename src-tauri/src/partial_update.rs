@@ -0,0 +1,62 @@
+use rusqlite::types::ToSql;
+use rusqlite::{params_from_iter, Connection};
+
+// The columns an update actually provides. Only those are written, so a field
+// another writer changed since this one was sent isn't put back to an older
+// value.
+#[derive(Default)]
+pub struct Changes {
+    columns: Vec<&'static str>,
+    values: Vec<Box<dyn ToSql>>,
+}
+
+impl Changes {
+    // Leaves the column alone when `value` is None
+    pub fn set<T: ToSql + 'static>(&mut self, column: &'static str, value: Option<T>) -> &mut Self {
+        if let Some(value) = value {
+            self.set_value(column, value);
+        }
+        self
+    }
+
+    // Writes the column whatever `value` is; an Option of None clears it
+    pub fn set_value<T: ToSql + 'static>(&mut self, column: &'static str, value: T) -> &mut Self {
+        self.columns.push(column);
+        self.values.push(Box::new(value));
+        self
+    }
+
+    // False when there's no row with that id. Callers set at least
+    // updated_at, so there's always a column to write.
+    pub fn execute(
+        &self,
+        conn: &Connection,
+        table: &'static str,
+        id: &str,
+    ) -> Result<bool, String> {
+        let assignments: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+            .collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE id = ?{}",
+            table,
+            assignments.join(", "),
+            self.columns.len() + 1
+        );
+        let values = self
+            .values
+            .iter()
+            .map(|value| value.as_ref())
+            .chain(std::iter::once(&id as &dyn ToSql));
+        // The same few fields are edited over and over, so the statements
+        // for them stay cached
+        let changed = conn
+            .prepare_cached(&sql)
+            .and_then(|mut stmt| stmt.execute(params_from_iter(values)))
+            .map_err(|e| e.to_string())?;
+        Ok(changed > 0)
+    }
+}
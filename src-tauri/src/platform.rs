@@ -0,0 +1,77 @@
+use crate::clipboard_capture;
+use crate::email;
+use crate::feeds;
+use crate::folder_watch;
+use crate::jobs;
+use crate::lan;
+use crate::maintenance;
+use crate::mirror;
+use crate::models::PlatformCapabilities;
+use crate::reminders;
+use crate::search_index;
+use crate::system_search;
+use tauri::AppHandle;
+
+// What this build can run. Mobile apps are suspended soon after leaving the
+// foreground and only reach files the user picks one at a time, so the
+// subsystems that watch or write directories or serve the network stay off
+// there, and reminders are handed to the OS to deliver instead.
+pub fn capabilities() -> PlatformCapabilities {
+    let desktop = cfg!(desktop);
+    PlatformCapabilities {
+        mobile: !desktop,
+        markdown_mirror: desktop,
+        watched_folders: desktop,
+        system_search: desktop,
+        lan_sync: desktop,
+        share_menu: cfg!(windows),
+        clipboard_capture: desktop,
+        scheduled_notifications: !desktop,
+    }
+}
+
+// For the commands that turn a subsystem on
+pub fn require(available: bool, feature: &str) -> Result<(), String> {
+    if !available {
+        return Err(format!("{} isn't available on this device", feature));
+    }
+    Ok(())
+}
+
+// Starts the background subsystems this build can run
+pub fn spawn_services(app: &AppHandle) {
+    let capabilities = capabilities();
+
+    // Background IMAP poller (idle unless email capture is enabled)
+    email::spawn_poller(app.clone());
+    feeds::spawn_fetcher(app.clone());
+    // Resumes jobs interrupted by the last shutdown
+    jobs::spawn_worker(app.clone());
+    // Idle unless a trash retention period is set
+    maintenance::spawn_scheduler(app.clone());
+    if let Err(e) = search_index::ensure_current(app) {
+        log::warn!("Failed to check the search index: {}", e);
+    }
+    // Fires reminders itself on desktop and schedules them with the OS on
+    // mobile
+    reminders::spawn_scheduler(app.clone());
+    // Idle unless LAN sync is enabled
+    if capabilities.lan_sync {
+        lan::spawn_service(app.clone());
+    }
+    // Idle unless the Markdown mirror is enabled
+    if capabilities.markdown_mirror {
+        mirror::spawn_writer(app.clone());
+    }
+    if capabilities.watched_folders {
+        folder_watch::spawn_service(app.clone());
+    }
+    // Idle unless system search indexing is enabled
+    if capabilities.system_search {
+        system_search::spawn_indexer(app.clone());
+    }
+    // Nothing is registered unless clipboard capture is enabled
+    if capabilities.clipboard_capture {
+        clipboard_capture::register_saved(app);
+    }
+}
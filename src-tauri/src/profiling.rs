@@ -0,0 +1,189 @@
+use crate::models::{CommandStats, PerformanceReport, QueryStats};
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Settings key; profiling is off unless this is "true"
+pub const PROFILING_SETTING: &str = "profiling_enabled";
+
+// Samples kept per ring buffer; the oldest are dropped first
+const CAPACITY: usize = 1000;
+
+// Process-wide because SQLite's profile hook is a plain fn pointer
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COMMANDS: Mutex<VecDeque<Sample>> = Mutex::new(VecDeque::new());
+static QUERIES: Mutex<VecDeque<Sample>> = Mutex::new(VecDeque::new());
+
+struct Sample {
+    name: String,
+    duration: Duration,
+    rows: Option<usize>,
+}
+
+fn push(buffer: &Mutex<VecDeque<Sample>>, sample: Sample) {
+    if let Ok(mut samples) = buffer.lock() {
+        if samples.len() == CAPACITY {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
+
+fn record_query(sql: &str, duration: Duration) {
+    push(
+        &QUERIES,
+        Sample {
+            name: sql.split_whitespace().collect::<Vec<_>>().join(" "),
+            duration,
+            rows: None,
+        },
+    );
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Turning profiling on starts from empty buffers
+pub fn set_enabled(conn: &mut Connection, enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    conn.profile(if enabled { Some(record_query) } else { None });
+
+    if enabled {
+        for buffer in [&COMMANDS, &QUERIES] {
+            if let Ok(mut samples) = buffer.lock() {
+                samples.clear();
+            }
+        }
+    }
+}
+
+// Applies the persisted setting to a freshly opened connection
+pub fn init(conn: &mut Connection) {
+    let enabled = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![PROFILING_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .is_ok_and(|value| value == "true");
+
+    if enabled {
+        set_enabled(conn, true);
+    }
+}
+
+// Times a command from creation until drop. A no-op while profiling is off.
+pub struct Span {
+    name: &'static str,
+    start: Option<Instant>,
+    rows: Option<usize>,
+}
+
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        start: is_enabled().then(Instant::now),
+        rows: None,
+    }
+}
+
+impl Span {
+    pub fn rows(&mut self, count: usize) {
+        self.rows = Some(count);
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            push(
+                &COMMANDS,
+                Sample {
+                    name: self.name.to_string(),
+                    duration: start.elapsed(),
+                    rows: self.rows,
+                },
+            );
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[derive(Default)]
+struct Aggregate {
+    calls: u32,
+    total: Duration,
+    max: Duration,
+    rows: usize,
+    row_samples: usize,
+}
+
+impl Aggregate {
+    fn avg_ms(&self) -> f64 {
+        millis(self.total) / self.calls as f64
+    }
+
+    fn avg_rows(&self) -> Option<u32> {
+        (self.row_samples > 0).then(|| (self.rows / self.row_samples) as u32)
+    }
+}
+
+// Groups samples by name, slowest (by max duration) first
+fn aggregate(buffer: &Mutex<VecDeque<Sample>>) -> Vec<(String, Aggregate)> {
+    let Ok(samples) = buffer.lock() else {
+        return Vec::new();
+    };
+
+    let mut by_name: HashMap<String, Aggregate> = HashMap::new();
+    for sample in samples.iter() {
+        let entry = by_name.entry(sample.name.clone()).or_default();
+        entry.calls += 1;
+        entry.total += sample.duration;
+        entry.max = entry.max.max(sample.duration);
+        if let Some(rows) = sample.rows {
+            entry.rows += rows;
+            entry.row_samples += 1;
+        }
+    }
+
+    let mut stats: Vec<_> = by_name.into_iter().collect();
+    stats.sort_by_key(|(_, agg)| std::cmp::Reverse(agg.max));
+    stats
+}
+
+pub fn report(limit: usize) -> PerformanceReport {
+    let commands = aggregate(&COMMANDS)
+        .into_iter()
+        .take(limit)
+        .map(|(name, agg)| CommandStats {
+            avg_ms: agg.avg_ms(),
+            max_ms: millis(agg.max),
+            avg_rows: agg.avg_rows(),
+            calls: agg.calls,
+            name,
+        })
+        .collect();
+
+    let queries = aggregate(&QUERIES)
+        .into_iter()
+        .take(limit)
+        .map(|(sql, agg)| QueryStats {
+            avg_ms: agg.avg_ms(),
+            max_ms: millis(agg.max),
+            calls: agg.calls,
+            sql,
+        })
+        .collect();
+
+    PerformanceReport {
+        enabled: is_enabled(),
+        commands,
+        queries,
+    }
+}
@@ -0,0 +1,357 @@
+use crate::commands::{row_to_brain_map, row_to_note};
+use crate::models::{
+    BrainMap, Event, EventStatus, Note, Project, ProjectActivity, ProjectCreate, ProjectItemKind,
+    ProjectOverview, ProjectStats, ProjectStatus, ProjectUpdate,
+};
+use crate::queries;
+use crate::timestamps;
+use crate::today;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+const SELECT_PROJECT: &str =
+    "SELECT id, name, description, color, status, due_date, created_at, updated_at FROM projects";
+
+const RECENT_ACTIVITY_LIMIT: usize = 10;
+
+fn row_to_project(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        description: row.get("description")?,
+        color: row.get("color")?,
+        status: row.get("status")?,
+        due_date: row.get("due_date")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+fn table(kind: ProjectItemKind) -> &'static str {
+    match kind {
+        ProjectItemKind::Note => "notes",
+        ProjectItemKind::Event => "events",
+        ProjectItemKind::BrainMap => "brain_maps",
+    }
+}
+
+// `id IN (...)` for items of `kind` linked to the project bound to ?1
+pub fn linked(kind: ProjectItemKind) -> String {
+    format!(
+        "id IN (SELECT item_id FROM project_items WHERE project_id = ?1 AND item_type = '{}')",
+        kind.as_str()
+    )
+}
+
+pub fn load(conn: &Connection, id: &str) -> Result<Option<Project>, String> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_PROJECT),
+        params![id],
+        row_to_project,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// A project by name, ignoring case
+pub fn named(conn: &Connection, name: &str) -> Result<Option<Project>, String> {
+    conn.query_row(
+        &format!(
+            "{} WHERE name = ?1 COLLATE NOCASE ORDER BY created_at",
+            SELECT_PROJECT
+        ),
+        params![name],
+        row_to_project,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Active and on-hold projects first, then by name
+pub fn list(conn: &Connection) -> Result<Vec<Project>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} ORDER BY status IN ('completed', 'archived'), name COLLATE NOCASE",
+            SELECT_PROJECT
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_project)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub fn insert(
+    conn: &Connection,
+    data: ProjectCreate,
+    now: &DateTime<Utc>,
+) -> Result<Project, String> {
+    let project = Project {
+        id: format!("project_{}", Uuid::new_v4()),
+        name: data.name,
+        description: data.description,
+        color: data.color,
+        status: ProjectStatus::Active,
+        due_date: data.due_date,
+        created_at: *now,
+        updated_at: *now,
+    };
+    conn.execute(
+        "INSERT INTO projects (id, name, description, color, status, due_date, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            project.id,
+            project.name,
+            project.description,
+            project.color,
+            project.status,
+            project.due_date,
+            timestamps::format(&project.created_at),
+            timestamps::format(&project.updated_at),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(project)
+}
+
+pub fn update(
+    conn: &Connection,
+    current: Project,
+    data: ProjectUpdate,
+    now: &DateTime<Utc>,
+) -> Result<Project, String> {
+    let project = Project {
+        name: data.name.unwrap_or(current.name),
+        description: data.description.or(current.description),
+        color: data.color.or(current.color),
+        status: data.status.unwrap_or(current.status),
+        due_date: data.due_date.or(current.due_date),
+        updated_at: *now,
+        ..current
+    };
+    conn.execute(
+        "UPDATE projects SET name = ?1, description = ?2, color = ?3, status = ?4,
+                due_date = ?5, updated_at = ?6
+         WHERE id = ?7",
+        params![
+            project.name,
+            project.description,
+            project.color,
+            project.status,
+            project.due_date,
+            timestamps::format(&project.updated_at),
+            project.id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(project)
+}
+
+// The linked items themselves are left alone
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM project_items WHERE project_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM projects WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Only items that aren't in the trash can be linked
+pub fn item_exists(conn: &Connection, kind: ProjectItemKind, id: &str) -> Result<bool, String> {
+    conn.query_row(
+        &format!(
+            "SELECT 1 FROM {} WHERE id = ?1 AND deleted_at IS NULL",
+            table(kind)
+        ),
+        params![id],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|e| e.to_string())
+}
+
+pub fn link(
+    conn: &Connection,
+    project_id: &str,
+    kind: ProjectItemKind,
+    item_id: &str,
+    now: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO project_items (project_id, item_type, item_id, added_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![project_id, kind, item_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn unlink(
+    conn: &Connection,
+    project_id: &str,
+    kind: ProjectItemKind,
+    item_id: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM project_items WHERE project_id = ?1 AND item_type = ?2 AND item_id = ?3",
+        params![project_id, kind, item_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Drops an item that's being deleted for good from every project
+pub fn unlink_all(conn: &Connection, kind: ProjectItemKind, item_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM project_items WHERE item_type = ?1 AND item_id = ?2",
+        params![kind, item_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn linked_notes(conn: &Connection, project_id: &str) -> Result<Vec<Note>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM notes WHERE deleted_at IS NULL AND {}
+             ORDER BY updated_at DESC",
+            queries::NOTE_COLUMNS,
+            linked(ProjectItemKind::Note)
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], row_to_note)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn linked_brain_maps(conn: &Connection, project_id: &str) -> Result<Vec<BrainMap>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, title, description, center_node_id, center_node_text,
+                    viewport_x, viewport_y, viewport_zoom, theme,
+                    created_at, updated_at, deleted_at
+             FROM brain_maps WHERE deleted_at IS NULL AND {}
+             ORDER BY updated_at DESC",
+            linked(ProjectItemKind::BrainMap)
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], row_to_brain_map)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn local_day(event: &Event) -> Option<NaiveDate> {
+    event.start_time.as_ref().map(timestamps::local_date)
+}
+
+fn stats(
+    notes: &[Note],
+    events: &[Event],
+    tasks: &[Event],
+    brain_maps: &[BrainMap],
+    today: NaiveDate,
+) -> ProjectStats {
+    let tasks: Vec<&Event> = tasks
+        .iter()
+        .filter(|t| t.status != Some(EventStatus::Cancelled))
+        .collect();
+    let completed = tasks
+        .iter()
+        .filter(|t| t.status == Some(EventStatus::Completed))
+        .count() as u32;
+    let overdue = tasks
+        .iter()
+        .filter(|t| {
+            matches!(
+                t.status,
+                None | Some(EventStatus::Pending | EventStatus::InProgress)
+            )
+        })
+        .filter(|t| local_day(t).is_some_and(|d| d < today))
+        .count() as u32;
+    let total = tasks.len() as u32;
+
+    ProjectStats {
+        tasks_total: total,
+        tasks_completed: completed,
+        tasks_overdue: overdue,
+        upcoming_events: events
+            .iter()
+            .filter(|e| local_day(e).is_some_and(|d| d >= today))
+            .count() as u32,
+        notes: notes.len() as u32,
+        brain_maps: brain_maps.len() as u32,
+        progress: (completed * 100).checked_div(total).unwrap_or(0),
+    }
+}
+
+fn recent_activity(
+    notes: &[Note],
+    events: &[Event],
+    tasks: &[Event],
+    brain_maps: &[BrainMap],
+) -> Vec<ProjectActivity> {
+    let activity = |kind, id: &str, title: &str, updated_at: &DateTime<Utc>| ProjectActivity {
+        kind,
+        id: id.to_string(),
+        title: title.to_string(),
+        updated_at: *updated_at,
+    };
+    let mut items: Vec<ProjectActivity> = notes
+        .iter()
+        .map(|n| activity(ProjectItemKind::Note, &n.id, &n.title, &n.updated_at))
+        .chain(
+            events
+                .iter()
+                .chain(tasks)
+                .map(|e| activity(ProjectItemKind::Event, &e.id, &e.title, &e.updated_at)),
+        )
+        .chain(
+            brain_maps
+                .iter()
+                .map(|m| activity(ProjectItemKind::BrainMap, &m.id, &m.title, &m.updated_at)),
+        )
+        .collect();
+    items.sort_by_key(|item| Reverse(item.updated_at));
+    items.truncate(RECENT_ACTIVITY_LIMIT);
+    items
+}
+
+pub fn overview(
+    conn: &Connection,
+    project: Project,
+    today: NaiveDate,
+) -> Result<ProjectOverview, String> {
+    let notes = linked_notes(conn, &project.id)?;
+    let brain_maps = linked_brain_maps(conn, &project.id)?;
+    let events_linked = linked(ProjectItemKind::Event);
+    let events = today::query_events(
+        conn,
+        &format!("NOT {} AND {}", today::IS_TASK, events_linked),
+        &[&project.id],
+    )?;
+    let tasks = today::query_events(
+        conn,
+        &format!("{} AND {}", today::IS_TASK, events_linked),
+        &[&project.id],
+    )?;
+
+    Ok(ProjectOverview {
+        stats: stats(&notes, &events, &tasks, &brain_maps, today),
+        recent_activity: recent_activity(&notes, &events, &tasks, &brain_maps),
+        project,
+        notes,
+        events,
+        tasks,
+        brain_maps,
+    })
+}
@@ -0,0 +1,270 @@
+use crate::email::KEYRING_SERVICE;
+use crate::models::PublishTarget;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+// Settings key holding the JSON-encoded PublishTarget. Publishing stays
+// disabled until the user configures one.
+pub const PUBLISH_TARGET_SETTING: &str = "publish_target";
+
+// Fields older versions kept in the setting, moved to the keychain on load
+const LEGACY_SECRET_FIELDS: &[&str] = &["token", "secret_access_key"];
+
+// ============ Configuration ============
+
+pub fn load_target(conn: &Connection) -> Result<Option<PublishTarget>, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![PUBLISH_TARGET_SETTING],
+            |row| row.get(0),
+        )
+        .ok();
+    let Some(value) = value else {
+        return Ok(None);
+    };
+
+    let mut value: serde_json::Value =
+        serde_json::from_str(&value).map_err(|e| format!("Invalid publish target: {}", e))?;
+    let legacy = value.as_object_mut().and_then(|fields| {
+        let secrets: Vec<serde_json::Value> = LEGACY_SECRET_FIELDS
+            .iter()
+            .filter_map(|field| fields.remove(*field))
+            .collect();
+        (!secrets.is_empty()).then_some(secrets)
+    });
+    let target: PublishTarget =
+        serde_json::from_value(value).map_err(|e| format!("Invalid publish target: {}", e))?;
+    if let Some(secrets) = legacy {
+        if let Some(secret) = secrets.iter().find_map(|secret| secret.as_str()) {
+            store_secret(secret)?;
+        }
+        save_target(conn, &target)?;
+    }
+    Ok(Some(target))
+}
+
+pub fn save_target(conn: &Connection, target: &PublishTarget) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            PUBLISH_TARGET_SETTING,
+            serde_json::to_string(target).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The POST bearer token or S3 secret access key never touches the database;
+// like the IMAP password, it lives in the OS keychain.
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, "publish").map_err(|e| e.to_string())
+}
+
+// An empty secret removes the stored one
+pub fn store_secret(secret: &str) -> Result<(), String> {
+    let entry = keyring_entry()?;
+    if !secret.is_empty() {
+        return entry.set_password(secret).map_err(|e| e.to_string());
+    }
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn load_secret() -> Result<Option<String>, String> {
+    match keyring_entry()?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// ============ Uploading ============
+
+#[derive(Deserialize)]
+struct PostResponse {
+    url: String,
+}
+
+pub fn object_key(note_id: &str) -> String {
+    format!("notes/{}.html", note_id)
+}
+
+pub async fn upload(
+    target: &PublishTarget,
+    secret: Option<&str>,
+    key: &str,
+    html: String,
+) -> Result<String, String> {
+    let client = reqwest::Client::new();
+
+    match target {
+        PublishTarget::Post { endpoint } => {
+            let mut request = client
+                .post(endpoint)
+                .header("Content-Type", "text/html; charset=utf-8")
+                .header("X-Voyena-Key", key)
+                .body(html);
+            if let Some(token) = secret {
+                request = request.bearer_auth(token);
+            }
+
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Publish endpoint returned {}", response.status()));
+            }
+
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+            match response.json::<PostResponse>().await {
+                Ok(body) => Ok(body.url),
+                Err(e) => location.ok_or_else(|| format!("Publish endpoint returned no URL: {}", e)),
+            }
+        }
+        PublishTarget::S3 { public_base_url, .. } => {
+            let (url, headers) = sign_s3_request(target, secret, "PUT", key, html.as_bytes())?;
+            let mut request = client
+                .put(&url)
+                .header("Content-Type", "text/html; charset=utf-8")
+                .body(html);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Bucket returned {}", response.status()));
+            }
+
+            Ok(match public_base_url {
+                Some(base) => format!("{}/{}", base.trim_end_matches('/'), key),
+                None => url,
+            })
+        }
+    }
+}
+
+pub async fn remove(target: &PublishTarget, secret: Option<&str>, key: &str) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    let response = match target {
+        PublishTarget::Post { endpoint } => {
+            let mut request = client
+                .delete(format!("{}/{}", endpoint.trim_end_matches('/'), key));
+            if let Some(token) = secret {
+                request = request.bearer_auth(token);
+            }
+            request.send().await
+        }
+        PublishTarget::S3 { .. } => {
+            let (url, headers) = sign_s3_request(target, secret, "DELETE", key, &[])?;
+            let mut request = client.delete(&url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            request.send().await
+        }
+    }
+    .map_err(|e| e.to_string())?;
+
+    // Already gone counts as unpublished
+    if response.status().is_success() || response.status() == reqwest::StatusCode::NOT_FOUND {
+        Ok(())
+    } else {
+        Err(format!("Unpublish returned {}", response.status()))
+    }
+}
+
+// AWS Signature Version 4 for a single-chunk, path-style S3 request.
+// Returns the request URL and the headers that must accompany it.
+fn sign_s3_request(
+    target: &PublishTarget,
+    secret_access_key: Option<&str>,
+    method: &str,
+    key: &str,
+    body: &[u8],
+) -> Result<(String, Vec<(String, String)>), String> {
+    let PublishTarget::S3 {
+        endpoint,
+        bucket,
+        region,
+        access_key_id,
+        ..
+    } = target
+    else {
+        return Err("Not an S3 target".to_string());
+    };
+    let secret_access_key =
+        secret_access_key.ok_or("No S3 secret access key in keychain".to_string())?;
+
+    let endpoint = endpoint.trim_end_matches('/');
+    let host = endpoint
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(endpoint)
+        .to_string();
+    let canonical_uri = format!("/{}/{}", bucket, key);
+    let url = format!("{}{}", endpoint, canonical_uri);
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date_stamp = now.format("%Y%m%d").to_string();
+    let payload_hash = to_hex(&Sha256::digest(body));
+
+    let canonical_headers = format!(
+        "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
+        host, payload_hash, amz_date
+    );
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, canonical_uri, canonical_headers, signed_headers, payload_hash
+    );
+
+    let scope = format!("{}/{}/s3/aws4_request", date_stamp, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        to_hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), date_stamp.as_bytes())?;
+    let k_region = hmac_sha256(&k_date, region.as_bytes())?;
+    let k_service = hmac_sha256(&k_region, b"s3")?;
+    let k_signing = hmac_sha256(&k_service, b"aws4_request")?;
+    let signature = to_hex(&hmac_sha256(&k_signing, string_to_sign.as_bytes())?);
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key_id, scope, signed_headers, signature
+    );
+
+    Ok((
+        url,
+        vec![
+            ("x-amz-date".to_string(), amz_date),
+            ("x-amz-content-sha256".to_string(), payload_hash),
+            ("Authorization".to_string(), authorization),
+        ],
+    ))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| e.to_string())?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
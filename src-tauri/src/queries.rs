@@ -0,0 +1,25 @@
+// Column lists for the tables read in many places, so every query reading a
+// row for the matching row_to_* in commands selects everything it needs
+
+pub const NOTE_COLUMNS: &str =
+    "id, title, content, folder_id, tags, is_pinned, created_at, updated_at,
+     deleted_at, published_url, aliases, language";
+
+pub const EVENT_COLUMNS: &str =
+    "id, title, description, event_type, start_time, end_time, has_scheduled_time,
+     time_mode, duration_minutes, location, category, color, priority, tags,
+     show_on_calendar, is_all_day, is_recurring, recurring_pattern, status,
+     reminders, notes, created_at, updated_at, deleted_at, travel_minutes";
+
+pub const NODE_COLUMNS: &str = "id, brain_map_id, parent_node_id, label, description,
+     x, y, color, shape, size, icon, linked_note_id, linked_folder_id,
+     linked_event_id, is_collapsed, layer, created_at, updated_at";
+
+// The same columns read through a table alias in a join, e.g. `n.id, n.title`
+pub fn qualified(columns: &str, alias: &str) -> String {
+    columns
+        .split(',')
+        .map(|column| format!("{}.{}", alias, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
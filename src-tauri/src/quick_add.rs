@@ -0,0 +1,84 @@
+use crate::date_parse;
+use crate::models::Priority;
+use chrono::{NaiveDate, NaiveDateTime};
+
+pub struct Parsed {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due: Option<NaiveDate>,
+    pub priority: Option<Priority>,
+    pub folder_path: Option<String>,
+}
+
+// `@today`, `@2024-05-01`, `@next-friday`; dashes stand in for spaces except
+// in ISO dates
+fn date(word: &str, reference: NaiveDateTime, day_first: bool) -> Option<NaiveDate> {
+    let expression = if NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok() {
+        word.to_string()
+    } else {
+        word.replace(['-', '_'], " ")
+    };
+    date_parse::resolve(&expression, reference, day_first).map(|r| r.date)
+}
+
+// `!high`, `!low` and friends, or `!1` (high) to `!3` (low)
+fn priority(word: &str) -> Option<Priority> {
+    match word {
+        "1" | "!!" => Some(Priority::High),
+        "2" | "!" => Some(Priority::Medium),
+        "3" => Some(Priority::Low),
+        _ => word.parse().ok(),
+    }
+}
+
+// `/Work/Projects`, with underscores standing in for spaces. Has to start
+// with a letter so "/2" or a lone "/" stay in the title.
+fn folder_path(word: &str) -> Option<String> {
+    if !word.chars().next().is_some_and(char::is_alphabetic) {
+        return None;
+    }
+    Some(word.trim_end_matches('/').replace('_', " "))
+}
+
+// Pulls `#tag`, `@date`, `!priority` and `/folder` tokens out of quick-add
+// text; the rest is the title. A token that doesn't parse stays in the title
+// as typed.
+pub fn parse(text: &str, reference: NaiveDateTime, day_first: bool) -> Parsed {
+    let mut parsed = Parsed {
+        title: String::new(),
+        tags: Vec::new(),
+        due: None,
+        priority: None,
+        folder_path: None,
+    };
+    let mut words = Vec::new();
+
+    for token in text.split_whitespace() {
+        let mut chars = token.chars();
+        let symbol = chars.next();
+        let rest = chars.as_str().trim_end_matches([',', '.', ';']);
+
+        let consumed = match symbol {
+            Some('#') if !rest.is_empty() => {
+                if !parsed.tags.iter().any(|t| t.eq_ignore_ascii_case(rest)) {
+                    parsed.tags.push(rest.to_string());
+                }
+                true
+            }
+            Some('@') => date(rest, reference, day_first)
+                .map(|d| parsed.due = Some(d))
+                .is_some(),
+            Some('!') => priority(rest).map(|p| parsed.priority = Some(p)).is_some(),
+            Some('/') => folder_path(rest)
+                .map(|p| parsed.folder_path = Some(p))
+                .is_some(),
+            _ => false,
+        };
+        if !consumed {
+            words.push(token);
+        }
+    }
+
+    parsed.title = words.join(" ");
+    parsed
+}
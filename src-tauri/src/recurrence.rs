@@ -0,0 +1,396 @@
+use crate::locale;
+use crate::models::{
+    DayOfWeek, Event, OccurrenceStatus, RecurrenceFrequency, RecurrenceRule, TaskHistory,
+    TaskOccurrence, WeekStart,
+};
+use crate::timestamps;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc, Weekday};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+fn weekday(day: DayOfWeek) -> Weekday {
+    match day {
+        DayOfWeek::Monday => Weekday::Mon,
+        DayOfWeek::Tuesday => Weekday::Tue,
+        DayOfWeek::Wednesday => Weekday::Wed,
+        DayOfWeek::Thursday => Weekday::Thu,
+        DayOfWeek::Friday => Weekday::Fri,
+        DayOfWeek::Saturday => Weekday::Sat,
+        DayOfWeek::Sunday => Weekday::Sun,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+// The local day a task's occurrences are counted from
+pub fn anchor(event: &Event) -> NaiveDate {
+    timestamps::local_date(event.start_time.as_ref().unwrap_or(&event.created_at))
+}
+
+// The stored rule, or one made from the older `recurring_pattern` field
+pub fn rule(conn: &Connection, event: &Event) -> Result<Option<RecurrenceRule>, String> {
+    let stored = conn
+        .query_row(
+            "SELECT frequency, interval, weekdays, until_date FROM task_recurrence
+             WHERE event_id = ?1",
+            params![event.id],
+            |row| {
+                let weekdays: String = row.get(2)?;
+                Ok(RecurrenceRule {
+                    frequency: row.get(0)?,
+                    interval: row.get(1)?,
+                    weekdays: serde_json::from_str(&weekdays).unwrap_or_default(),
+                    until: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if stored.is_some() {
+        return Ok(stored);
+    }
+
+    let legacy = event
+        .recurring_pattern
+        .as_deref()
+        .filter(|_| event.is_recurring)
+        .and_then(|pattern| pattern.parse().ok());
+    Ok(legacy.map(|frequency| RecurrenceRule {
+        frequency,
+        interval: 1,
+        weekdays: Vec::new(),
+        until: None,
+    }))
+}
+
+// An iCalendar RRULE ("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH"), as other apps
+// export them. A month day or month is taken to be the start date's, which
+// is what anchors the rule here; anything the rule can't express, like
+// COUNT or "the second Monday", gives None.
+pub fn from_rrule(value: &str) -> Option<RecurrenceRule> {
+    let value = value.trim();
+    let value = value
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+        .map_or(value, |_| &value[6..]);
+
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut weekdays = Vec::new();
+    let mut until = None;
+    for part in value.split(';').filter(|part| !part.trim().is_empty()) {
+        let (key, val) = part.split_once('=')?;
+        let val = val.trim();
+        match key.trim().to_ascii_uppercase().as_str() {
+            "FREQ" => frequency = Some(val.parse::<RecurrenceFrequency>().ok()?),
+            "INTERVAL" => interval = val.parse().ok().filter(|n| *n > 0)?,
+            "BYDAY" => {
+                for code in val.split(',') {
+                    weekdays.push(rrule_weekday(code.trim())?);
+                }
+            }
+            "UNTIL" => {
+                let date = val.get(..8)?;
+                until = Some(format_date(NaiveDate::parse_from_str(date, "%Y%m%d").ok()?));
+            }
+            "BYMONTHDAY" | "BYMONTH" if !val.contains(',') => {}
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+
+    let frequency = frequency?;
+    if !weekdays.is_empty() && frequency != RecurrenceFrequency::Weekly {
+        return None;
+    }
+    Some(RecurrenceRule {
+        frequency,
+        interval,
+        weekdays,
+        until,
+    })
+}
+
+fn rrule_weekday(code: &str) -> Option<DayOfWeek> {
+    Some(match code.to_ascii_uppercase().as_str() {
+        "MO" => DayOfWeek::Monday,
+        "TU" => DayOfWeek::Tuesday,
+        "WE" => DayOfWeek::Wednesday,
+        "TH" => DayOfWeek::Thursday,
+        "FR" => DayOfWeek::Friday,
+        "SA" => DayOfWeek::Saturday,
+        "SU" => DayOfWeek::Sunday,
+        _ => return None,
+    })
+}
+
+// Stores the rule and keeps the event's is_recurring / recurring_pattern in
+// step; None makes the task a one-off again. Completions are kept.
+pub fn save_rule(
+    conn: &Connection,
+    event_id: &str,
+    rule: Option<&RecurrenceRule>,
+    now: &str,
+) -> Result<(), String> {
+    match rule {
+        Some(rule) => {
+            let weekdays = serde_json::to_string(&rule.weekdays).map_err(|e| e.to_string())?;
+            conn.execute(
+                "INSERT OR REPLACE INTO task_recurrence
+                     (event_id, frequency, interval, weekdays, until_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    event_id,
+                    rule.frequency,
+                    rule.interval,
+                    weekdays,
+                    rule.until
+                ],
+            )
+        }
+        None => conn.execute(
+            "DELETE FROM task_recurrence WHERE event_id = ?1",
+            params![event_id],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE events SET is_recurring = ?1, recurring_pattern = ?2, updated_at = ?3
+         WHERE id = ?4",
+        params![
+            rule.is_some(),
+            rule.map(|r| r.frequency.as_str()),
+            now,
+            event_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Occurrence dates from `from` to `to` (inclusive), in order
+pub fn occurrences(
+    rule: &RecurrenceRule,
+    anchor: NaiveDate,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<NaiveDate> {
+    let end = match rule.until.as_deref().and_then(parse_date) {
+        Some(until) => to.min(until),
+        None => to,
+    };
+    let start = from.max(anchor);
+    let interval = rule.interval.max(1);
+    let mut dates = Vec::new();
+    if start > end {
+        return dates;
+    }
+
+    match rule.frequency {
+        RecurrenceFrequency::Daily => {
+            let step = interval as i64;
+            let skipped = ((start - anchor).num_days() + step - 1) / step;
+            let mut date = anchor + Duration::days(skipped * step);
+            while date <= end {
+                dates.push(date);
+                date += Duration::days(step);
+            }
+        }
+        RecurrenceFrequency::Weekly => {
+            let days: Vec<Weekday> = if rule.weekdays.is_empty() {
+                vec![anchor.weekday()]
+            } else {
+                rule.weekdays.iter().map(|d| weekday(*d)).collect()
+            };
+            let first_week = locale::week_start(anchor, WeekStart::Monday);
+            let mut date = start;
+            while date <= end {
+                let week = (date - first_week).num_days() / 7;
+                if week % interval as i64 == 0 && days.contains(&date.weekday()) {
+                    dates.push(date);
+                }
+                date += Duration::days(1);
+            }
+        }
+        RecurrenceFrequency::Monthly | RecurrenceFrequency::Yearly => {
+            let step = match rule.frequency {
+                RecurrenceFrequency::Yearly => interval * 12,
+                _ => interval,
+            };
+            // On the start's day of the month, or the last day of shorter months
+            let mut n = 0;
+            while let Some(date) = anchor
+                .checked_add_months(Months::new(n * step))
+                .filter(|d| *d <= end)
+            {
+                if date >= start {
+                    dates.push(date);
+                }
+                n += 1;
+            }
+        }
+    }
+    dates
+}
+
+pub fn occurs_on(rule: &RecurrenceRule, anchor: NaiveDate, date: NaiveDate) -> bool {
+    !occurrences(rule, anchor, date, date).is_empty()
+}
+
+// Completed occurrence dates, with when they were completed
+fn completions(
+    conn: &Connection,
+    event_id: &str,
+) -> Result<HashMap<NaiveDate, DateTime<Utc>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT occurrence_date, completed_at FROM task_completions WHERE event_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![event_id], |row| {
+            Ok((row.get::<_, String>(0)?, timestamps::get(row, 1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .filter_map(|r| r.ok())
+        .filter_map(|(date, completed_at)| Some((parse_date(&date)?, completed_at)))
+        .collect())
+}
+
+fn completed_at(
+    conn: &Connection,
+    event_id: &str,
+    date: NaiveDate,
+) -> Result<Option<DateTime<Utc>>, String> {
+    conn.query_row(
+        "SELECT completed_at FROM task_completions WHERE event_id = ?1 AND occurrence_date = ?2",
+        params![event_id, format_date(date)],
+        |row| timestamps::get(row, 0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn is_completed(conn: &Connection, event_id: &str, date: NaiveDate) -> Result<bool, String> {
+    Ok(completed_at(conn, event_id, date)?.is_some())
+}
+
+// Marks the occurrence on `date` done (or not); completing twice keeps the
+// first time
+pub fn set_completed(
+    conn: &Connection,
+    event_id: &str,
+    date: NaiveDate,
+    completed: bool,
+    now: &str,
+) -> Result<(), String> {
+    if completed {
+        conn.execute(
+            "INSERT OR IGNORE INTO task_completions (event_id, occurrence_date, completed_at)
+             VALUES (?1, ?2, ?3)",
+            params![event_id, format_date(date), now],
+        )
+    } else {
+        conn.execute(
+            "DELETE FROM task_completions WHERE event_id = ?1 AND occurrence_date = ?2",
+            params![event_id, format_date(date)],
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn occurrence(
+    conn: &Connection,
+    event_id: &str,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Result<TaskOccurrence, String> {
+    let completed_at = completed_at(conn, event_id, date)?;
+    Ok(TaskOccurrence {
+        date: format_date(date),
+        status: status(completed_at.is_some(), date, today),
+        completed_at,
+    })
+}
+
+fn status(completed: bool, date: NaiveDate, today: NaiveDate) -> OccurrenceStatus {
+    if completed {
+        OccurrenceStatus::Completed
+    } else if date < today {
+        OccurrenceStatus::Missed
+    } else {
+        OccurrenceStatus::Pending
+    }
+}
+
+pub fn history(
+    conn: &Connection,
+    event: &Event,
+    rule: RecurrenceRule,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> Result<TaskHistory, String> {
+    let anchor = anchor(event);
+    let mut done = completions(conn, &event.id)?;
+
+    let (mut current, mut longest, mut completed, mut missed) = (0, 0, 0, 0);
+    for date in occurrences(&rule, anchor, anchor, today) {
+        match status(done.contains_key(&date), date, today) {
+            OccurrenceStatus::Completed => {
+                completed += 1;
+                current += 1;
+                longest = longest.max(current);
+            }
+            OccurrenceStatus::Missed => {
+                missed += 1;
+                current = 0;
+            }
+            OccurrenceStatus::Pending => {}
+        }
+    }
+
+    let occurrences = occurrences(&rule, anchor, from, to)
+        .into_iter()
+        .map(|date| {
+            let completed_at = done.remove(&date);
+            TaskOccurrence {
+                date: format_date(date),
+                status: status(completed_at.is_some(), date, today),
+                completed_at,
+            }
+        })
+        .collect();
+
+    Ok(TaskHistory {
+        event_id: event.id.clone(),
+        rule,
+        occurrences,
+        current_streak: current,
+        longest_streak: longest,
+        completed,
+        missed,
+    })
+}
+
+// Drops the rule and history of a task that's being deleted for good
+pub fn remove(conn: &Connection, event_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM task_recurrence WHERE event_id = ?1",
+        params![event_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM task_completions WHERE event_id = ?1",
+        params![event_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use crate::background;
+use crate::db::Database;
+use crate::models::{BackgroundSubsystem, EventReminder, ReminderDue, ReminderKind};
+use crate::timestamps;
+use crate::validation;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use std::time::Duration;
+#[cfg(desktop)]
+use tauri::Emitter;
+use tauri::{AppHandle, Manager};
+
+pub const DUE_EVENT: &str = "reminder:due";
+
+const CHECK_INTERVAL_SECONDS: u64 = 30;
+// Reminders that came due while the app was closed are dropped once they're
+// older than this, rather than all firing at startup
+#[cfg(desktop)]
+const STALE_AFTER_MINUTES: i64 = 15;
+const LEAVE_KEY: &str = "leave";
+
+// Stored timestamps are either RFC 3339 or naive local times from the
+// date pickers
+fn instant(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let naive = validation::parse_timestamp(value)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+struct Upcoming {
+    id: String,
+    title: String,
+    start_time: String,
+    location: Option<String>,
+    reminders: Vec<EventReminder>,
+    travel_minutes: Option<i32>,
+}
+
+// Every notification and "time to leave" alert of an event, as (key, kind,
+// fire_at). Email reminders aren't delivered from here.
+fn fire_times(event: &Upcoming) -> Vec<(String, ReminderKind, DateTime<Utc>)> {
+    let Some(start) = instant(&event.start_time) else {
+        return Vec::new();
+    };
+
+    let mut times: Vec<_> = event
+        .reminders
+        .iter()
+        .filter(|r| r.reminder_type == "notification")
+        .map(|r| {
+            let fire_at = start - ChronoDuration::minutes(r.minutes_before as i64);
+            (r.id.clone(), ReminderKind::Reminder, fire_at)
+        })
+        .collect();
+    if let Some(minutes) = event.travel_minutes.filter(|m| *m > 0) {
+        let fire_at = start - ChronoDuration::minutes(minutes as i64);
+        times.push((LEAVE_KEY.to_string(), ReminderKind::Leave, fire_at));
+    }
+    times
+}
+
+// The reminders of a pending event whose time has come
+#[cfg(desktop)]
+fn due_times(event: &Upcoming, now: DateTime<Utc>) -> Vec<(String, ReminderKind, DateTime<Utc>)> {
+    let mut times = fire_times(event);
+    let stale = now - ChronoDuration::minutes(STALE_AFTER_MINUTES);
+    times.retain(|(_, _, fire_at)| *fire_at <= now && *fire_at > stale);
+    times
+}
+
+// Pending events with a start time, optionally only those starting on or
+// after the `since` date (compared on the stored date, so pass a day early)
+fn pending_events(conn: &Connection, since: Option<NaiveDate>) -> Result<Vec<Upcoming>, String> {
+    let since = since.map(|d| d.format("%Y-%m-%d").to_string());
+    let upcoming = conn
+        .prepare(
+            "SELECT id, title, start_time, location, reminders, travel_minutes
+             FROM events
+             WHERE deleted_at IS NULL AND start_time IS NOT NULL
+               AND (status IS NULL OR status IN ('pending', 'in_progress'))
+               AND (?1 IS NULL OR substr(start_time, 1, 10) >= ?1)",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![since], |row| {
+            let reminders: String = row.get(4)?;
+            Ok(Upcoming {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                start_time: row.get(2)?,
+                location: row.get(3)?,
+                reminders: serde_json::from_str(&reminders).unwrap_or_default(),
+                travel_minutes: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(upcoming)
+}
+
+fn reminder_due(event: &Upcoming, kind: ReminderKind, fire_at: DateTime<Utc>) -> ReminderDue {
+    ReminderDue {
+        event_id: event.id.clone(),
+        title: event.title.clone(),
+        kind,
+        fire_at,
+        start_time: event.start_time.clone(),
+        location: event.location.clone(),
+    }
+}
+
+// Reminders of pending events that fire from `start` up to `end`, whether or
+// not they've fired yet, in firing order
+pub fn firing_between(
+    conn: &Connection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<ReminderDue>, String> {
+    let since = start.date_naive() - ChronoDuration::days(1);
+    let mut reminders: Vec<ReminderDue> = Vec::new();
+    for event in &pending_events(conn, Some(since))? {
+        for (_, kind, fire_at) in fire_times(event) {
+            if fire_at >= start && fire_at < end {
+                reminders.push(reminder_due(event, kind, fire_at));
+            }
+        }
+    }
+    reminders.sort_by_key(|reminder| reminder.fire_at);
+    Ok(reminders)
+}
+
+// Collects the reminders due at `now` and records them as fired
+#[cfg(desktop)]
+fn take_due(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<ReminderDue>, String> {
+    let upcoming = pending_events(conn, None)?;
+    let fired_at = timestamps::format(&now);
+    let mut due = Vec::new();
+    for event in &upcoming {
+        for (key, kind, fire_at) in due_times(event, now) {
+            let inserted = conn
+                .execute(
+                    "INSERT OR IGNORE INTO reminder_log (event_id, reminder_key, fire_at, fired_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![event.id, key, timestamps::format(&fire_at), fired_at],
+                )
+                .map_err(|e| e.to_string())?;
+            if inserted == 0 {
+                continue;
+            }
+            due.push(reminder_due(event, kind, fire_at));
+        }
+    }
+
+    Ok(due)
+}
+
+#[cfg(desktop)]
+fn check(app: &AppHandle) -> Result<(), String> {
+    let due = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        take_due(&conn, Utc::now())?
+    };
+
+    for reminder in due {
+        if let Err(e) = app.emit(DUE_EVENT, reminder) {
+            log::warn!("Failed to emit reminder: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Fires regular reminders and "time to leave" alerts as `reminder:due`
+// events; the frontend shows the notification
+#[cfg(desktop)]
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Reminders).await;
+            if let Err(e) = check(&app) {
+                log::warn!("Reminder check failed: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECONDS)).await;
+        }
+    });
+}
+
+// Mobile apps don't run in the background to fire reminders, so the coming
+// week's are scheduled as OS notifications instead. The schedule is rebuilt
+// while the app is open whenever the events behind it change.
+#[cfg(mobile)]
+const SCHEDULE_AHEAD_DAYS: i64 = 7;
+
+#[cfg(mobile)]
+fn notification_body(reminder: &ReminderDue) -> String {
+    match reminder.kind {
+        ReminderKind::Leave => match &reminder.location {
+            Some(location) => format!("Time to leave for {}", location),
+            None => "Time to leave".to_string(),
+        },
+        ReminderKind::Reminder => match instant(&reminder.start_time) {
+            Some(start) => format!("Starts at {}", start.with_timezone(&Local).format("%H:%M")),
+            None => "Starting soon".to_string(),
+        },
+    }
+}
+
+#[cfg(mobile)]
+fn schedule(app: &AppHandle, scheduled: &mut Vec<(String, String)>) -> Result<(), String> {
+    use tauri_plugin_notification::{NotificationExt, PermissionState, Schedule};
+
+    let now = Utc::now();
+    let upcoming = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        firing_between(&conn, now, now + ChronoDuration::days(SCHEDULE_AHEAD_DAYS))?
+    };
+    let keys: Vec<(String, String)> = upcoming
+        .iter()
+        .map(|r| (r.event_id.clone(), r.fire_at.clone()))
+        .collect();
+    if keys == *scheduled {
+        return Ok(());
+    }
+
+    let notification = app.notification();
+    if notification.permission_state().map_err(|e| e.to_string())? != PermissionState::Granted
+        && notification
+            .request_permission()
+            .map_err(|e| e.to_string())?
+            != PermissionState::Granted
+    {
+        return Err("Notifications aren't allowed".into());
+    }
+
+    notification.cancel_all().map_err(|e| e.to_string())?;
+    for (index, reminder) in upcoming.iter().enumerate() {
+        let Some(fire_at) = DateTime::parse_from_rfc3339(&reminder.fire_at)
+            .ok()
+            .and_then(|t| time::OffsetDateTime::from_unix_timestamp(t.timestamp()).ok())
+        else {
+            continue;
+        };
+        notification
+            .builder()
+            .id(index as i32 + 1)
+            .title(&reminder.title)
+            .body(notification_body(reminder))
+            .schedule(Schedule::At {
+                date: fire_at,
+                repeating: false,
+                allow_while_idle: true,
+            })
+            .show()
+            .map_err(|e| e.to_string())?;
+    }
+    *scheduled = keys;
+    Ok(())
+}
+
+#[cfg(mobile)]
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut scheduled = Vec::new();
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::Reminders).await;
+            if let Err(e) = schedule(&app, &mut scheduled) {
+                log::warn!("Reminder scheduling failed: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECONDS)).await;
+        }
+    });
+}
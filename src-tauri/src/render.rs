@@ -0,0 +1,162 @@
+use crate::front_matter;
+use crate::sanitize;
+use pulldown_cmark::{html, Options, Parser};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::sync::OnceLock;
+
+// Note content is either editor HTML or Markdown (imports, quick capture),
+// optionally after a YAML front matter block.
+pub fn looks_like_html(content: &str) -> bool {
+    front_matter::body(content).trim_start().starts_with('<')
+}
+
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let parser = Parser::new_ext(markdown, options);
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, parser);
+    out
+}
+
+// Front matter isn't part of the rendered note
+pub fn content_to_html(content: &str) -> String {
+    let body = front_matter::body(content);
+    if looks_like_html(body) {
+        body.to_string()
+    } else {
+        markdown_to_html(body)
+    }
+}
+
+// Keeps the front matter block at the top
+pub fn content_to_markdown(content: &str) -> String {
+    if looks_like_html(content) {
+        let (header, body) = (front_matter::header(content), front_matter::body(content));
+        format!("{}{}", header, html2md::parse_html(body))
+    } else {
+        content.to_string()
+    }
+}
+
+// Pasted HTML from a browser or office app as Markdown. Scripts, styles and
+// clipboard markers are dropped first, as is the bold wrapper Google Docs puts
+// around a whole selection.
+pub fn html_to_markdown(html: &str) -> String {
+    static DOCS_WRAPPER: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES: OnceLock<Regex> = OnceLock::new();
+    let docs_wrapper = DOCS_WRAPPER.get_or_init(|| {
+        Regex::new(r#"(?is)<b\b[^>]*\bid="docs-internal-guid-[^"]*"[^>]*>(.*)</b>"#)
+            .expect("valid docs wrapper regex")
+    });
+    let blank_lines = BLANK_LINES.get_or_init(|| {
+        Regex::new(r"\n[ \t]*(\n[ \t]*)+\n").expect("valid blank lines regex")
+    });
+
+    let html = docs_wrapper.replace(html, "$1");
+    let markdown = html2md::parse_html(&sanitize::sanitize_html(&html));
+    blank_lines.replace_all(&markdown, "\n\n").trim().to_string()
+}
+
+// A Markdown file for a note: its front matter, if any, has to come before
+// the title heading
+pub fn markdown_document(title: &str, markdown: &str) -> String {
+    format!(
+        "{}# {}\n\n{}\n",
+        front_matter::header(markdown),
+        title,
+        front_matter::body(markdown)
+    )
+}
+
+// Text only, with block elements separated by blank lines like the editor's getText()
+pub fn content_to_plain_text(content: &str) -> String {
+    static BLOCK_END: OnceLock<Regex> = OnceLock::new();
+    let block_end = BLOCK_END.get_or_init(|| {
+        Regex::new(r"(?i)</(p|h[1-6]|li|blockquote|pre|div|tr)>|<br\s*/?>").expect("valid block regex")
+    });
+
+    let html = content_to_html(content);
+    let spaced = block_end.replace_all(&html, "$0\n\n");
+    let text: String = Html::parse_fragment(&spaced).root_element().text().collect();
+
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// Longest title taken from a note's content
+const DERIVED_TITLE_LEN: usize = 80;
+
+// A title for an untitled note: its first heading, else its first line of
+// text, cut at a word where it's too long. Empty when there's no text.
+pub fn derive_note_title(content: &str) -> String {
+    let headings = Selector::parse("h1, h2, h3, h4, h5, h6").expect("valid selector");
+    let document = Html::parse_fragment(&content_to_html(content));
+    let heading = document
+        .select(&headings)
+        .map(|heading| heading.text().collect::<Vec<_>>().join(" "))
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|text| !text.is_empty());
+    let line = heading.or_else(|| {
+        content_to_plain_text(content)
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .find(|line| !line.is_empty())
+    });
+    let Some(line) = line else {
+        return String::new();
+    };
+
+    if line.chars().count() <= DERIVED_TITLE_LEN {
+        return line;
+    }
+    let cut: String = line.chars().take(DERIVED_TITLE_LEN).collect();
+    // A single long word is cut where it is
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > cut.len() / 2 => &cut[..end],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const PAGE_STYLE: &str = r#"
+    body { max-width: 760px; margin: 40px auto; padding: 0 20px; font: 16px/1.6 -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; color: #1f2937; }
+    img { max-width: 100%; }
+    pre { background: #f3f4f6; padding: 12px; border-radius: 6px; overflow-x: auto; }
+    code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+    blockquote { border-left: 3px solid #d1d5db; margin-left: 0; padding-left: 16px; color: #4b5563; }
+    table { border-collapse: collapse; }
+    th, td { border: 1px solid #d1d5db; padding: 4px 8px; }
+"#;
+
+// Standalone page wrapping already-rendered body HTML.
+pub fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\n</body>\n</html>\n",
+        title = escape_html(title),
+        style = PAGE_STYLE,
+        body = body,
+    )
+}
@@ -0,0 +1,246 @@
+use crate::locale;
+use crate::models::LocaleSettings;
+use crate::validation;
+use chrono::{Duration, NaiveDate};
+use rusqlite::Connection;
+use std::fmt::Write as _;
+
+pub const REVIEWS_FOLDER: &str = "Reviews";
+
+// First day of the week `value` names, either a week number ("2024-W18")
+// or any date in it ("2024-05-01"), with weeks counted per the locale
+pub fn week_start(value: &str, settings: &LocaleSettings) -> Option<NaiveDate> {
+    let value = value.trim();
+    if let Some((year, week)) = value.split_once("-W") {
+        return locale::week_from_number(
+            year.parse().ok()?,
+            week.parse().ok()?,
+            settings.week_start,
+        );
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(locale::week_start(date, settings.week_start))
+}
+
+pub fn title(start: NaiveDate, settings: &LocaleSettings) -> String {
+    let (year, week) = locale::week_number(start, settings.week_start);
+    format!("Weekly Review {}-W{:02}", year, week)
+}
+
+fn minutes_between(start: &str, end: &str) -> Option<i64> {
+    let start = validation::parse_timestamp(start)?;
+    let end = validation::parse_timestamp(end)?;
+    Some((end - start).num_minutes()).filter(|m| *m > 0)
+}
+
+fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+struct WeekEvent {
+    title: String,
+    event_type: Option<String>,
+    time_mode: String,
+    day: NaiveDate,
+    minutes: Option<i64>,
+    is_all_day: bool,
+    status: Option<String>,
+}
+
+impl WeekEvent {
+    fn is_task(&self) -> bool {
+        self.event_type.as_deref() == Some("task") || self.time_mode == "todo"
+    }
+
+    fn is_completed(&self) -> bool {
+        self.status.as_deref() == Some("completed")
+    }
+
+    // Focus time counts completed work blocks; meetings, reminders and
+    // deadlines don't
+    fn focus_minutes(&self) -> i64 {
+        let counts = matches!(
+            self.event_type.as_deref(),
+            None | Some("event") | Some("task")
+        );
+        if counts && self.is_completed() && !self.is_all_day {
+            self.minutes.unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+fn week_events(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<WeekEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT title, event_type, time_mode, start_time, end_time, duration_minutes,
+                    is_all_day, status
+             FROM events
+             WHERE deleted_at IS NULL AND start_time IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let start_time: String = row.get(3)?;
+            let end_time: Option<String> = row.get(4)?;
+            let duration: Option<i32> = row.get(5)?;
+            let is_all_day: i32 = row.get(6)?;
+            let minutes = end_time
+                .as_deref()
+                .and_then(|end| minutes_between(&start_time, end))
+                .or(duration.map(|d| d as i64));
+            Ok((
+                start_time,
+                WeekEvent {
+                    title: row.get(0)?,
+                    event_type: row.get(1)?,
+                    time_mode: row.get(2)?,
+                    day: NaiveDate::MIN,
+                    minutes,
+                    is_all_day: is_all_day != 0,
+                    status: row.get(7)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut events: Vec<WeekEvent> = rows
+        .filter_map(|r| r.ok())
+        .filter_map(|(start_time, event)| {
+            let day = locale::local_date(&start_time).filter(|d| *d >= start && *d < end)?;
+            Some(WeekEvent { day, ..event })
+        })
+        .collect();
+    events.sort_by_key(|e| e.day);
+    Ok(events)
+}
+
+// Titles of notes created during the week, leaving out earlier reviews
+fn week_notes(conn: &Connection, start: NaiveDate, end: NaiveDate) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.title, n.created_at
+             FROM notes n LEFT JOIN folders f ON f.id = n.folder_id
+             WHERE n.deleted_at IS NULL AND (f.name IS NULL OR f.name != ?1)
+             ORDER BY n.created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([REVIEWS_FOLDER], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .filter_map(|r| r.ok())
+        .filter(|(_, created_at)| {
+            locale::local_date(created_at).is_some_and(|d| d >= start && d < end)
+        })
+        .map(|(title, _)| {
+            if title.trim().is_empty() {
+                "Untitled".to_string()
+            } else {
+                title
+            }
+        })
+        .collect())
+}
+
+// The review note's Markdown for the week starting on `start`
+pub fn compose(
+    conn: &Connection,
+    start: NaiveDate,
+    settings: &LocaleSettings,
+) -> Result<String, String> {
+    let end = start + Duration::days(7);
+    let events = week_events(conn, start, end)?;
+    let notes = week_notes(conn, start, end)?;
+
+    let completed: Vec<&WeekEvent> = events
+        .iter()
+        .filter(|e| e.is_completed() && !e.is_task())
+        .collect();
+    let tasks: Vec<&WeekEvent> = events.iter().filter(|e| e.is_task()).collect();
+    let tasks_done = tasks.iter().filter(|t| t.is_completed()).count();
+    let count_status = |status: &str| {
+        tasks
+            .iter()
+            .filter(|t| t.status.as_deref() == Some(status))
+            .count()
+    };
+    let focus: i64 = events.iter().map(WeekEvent::focus_minutes).sum();
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "_{} – {}_\n",
+        locale::format_date(start, settings.date_order),
+        locale::format_date(end - Duration::days(1), settings.date_order)
+    );
+
+    out.push_str("## Summary\n\n");
+    let _ = writeln!(out, "- Completed events: {}", completed.len());
+    let _ = writeln!(out, "- Focus time: {}", format_minutes(focus));
+    let _ = writeln!(out, "- New notes: {}", notes.len());
+    let _ = writeln!(
+        out,
+        "- Tasks: {} of {} done ({} missed, {} skipped, {} cancelled)",
+        tasks_done,
+        tasks.len(),
+        count_status("missed"),
+        count_status("skipped"),
+        count_status("cancelled")
+    );
+
+    if !completed.is_empty() {
+        out.push_str("\n## Completed\n\n");
+        for event in &completed {
+            let _ = write!(
+                out,
+                "- {} {} — {}",
+                event.day.format("%a"),
+                locale::format_date(event.day, settings.date_order),
+                event.title
+            );
+            if let Some(minutes) = event.minutes.filter(|_| !event.is_all_day) {
+                let _ = write!(out, " ({})", format_minutes(minutes));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !tasks.is_empty() {
+        out.push_str("\n## Tasks\n\n");
+        for task in &tasks {
+            let mark = if task.is_completed() { "x" } else { " " };
+            let _ = write!(out, "- [{}] {}", mark, task.title);
+            match task.status.as_deref() {
+                Some(status) if status != "completed" && status != "pending" => {
+                    let _ = write!(out, " ({})", status.replace('_', " "));
+                }
+                _ => {}
+            }
+            out.push('\n');
+        }
+    }
+
+    if !notes.is_empty() {
+        out.push_str("\n## New Notes\n\n");
+        for note in &notes {
+            let _ = writeln!(out, "- {}", note);
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,61 @@
+use crate::models::NoteRevision;
+use crate::timestamps;
+use rusqlite::{params, Connection};
+
+// Oldest revisions beyond this many per note are dropped
+const MAX_REVISIONS_PER_NOTE: i64 = 50;
+
+// Saves the note's title and content as they are now, before a change the
+// user didn't make themselves
+pub fn snapshot(conn: &Connection, note_id: &str, reason: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO note_revisions (note_id, title, content, reason, created_at)
+         SELECT id, title, content, ?2, ?3 FROM notes WHERE id = ?1",
+        params![note_id, reason, now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM note_revisions WHERE note_id = ?1 AND seq <= (
+             SELECT seq FROM note_revisions WHERE note_id = ?1
+             ORDER BY seq DESC LIMIT 1 OFFSET ?2
+         )",
+        params![note_id, MAX_REVISIONS_PER_NOTE],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// Newest first
+pub fn list(conn: &Connection, note_id: &str) -> Result<Vec<NoteRevision>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT seq, note_id, title, content, reason, created_at
+             FROM note_revisions WHERE note_id = ?1
+             ORDER BY seq DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteRevision {
+                seq: row.get(0)?,
+                note_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                reason: row.get(4)?,
+                created_at: timestamps::get(row, 5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+pub fn remove_all(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM note_revisions WHERE note_id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use ammonia::Builder;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+// Allow-list matching what the TipTap editor produces: formatting marks,
+// task lists, tables, colored text and highlights, links and images
+// (including `attachment://` sources). Scripts, event handlers and
+// unknown URL schemes are dropped.
+fn builder() -> &'static Builder<'static> {
+    static BUILDER: OnceLock<Builder<'static>> = OnceLock::new();
+    BUILDER.get_or_init(|| {
+        let mut builder = Builder::default();
+        builder
+            .add_tags(["input", "label", "colgroup", "col"])
+            .add_generic_attributes(["class", "style", "data-type", "data-checked", "data-color"])
+            .add_tag_attributes("input", ["type", "checked", "disabled"])
+            .add_tag_attributes("a", ["target"])
+            .add_tag_attributes("td", ["colwidth"])
+            .add_tag_attributes("th", ["colwidth"])
+            .add_tag_attributes("col", ["style"])
+            .add_url_schemes(["attachment"])
+            .filter_style_properties(HashSet::from([
+                "color",
+                "background-color",
+                "text-align",
+                "min-width",
+                "width",
+            ]))
+            .link_rel(Some("noopener noreferrer nofollow"));
+        builder
+    })
+}
+
+pub fn sanitize_html(html: &str) -> String {
+    builder().clean(html).to_string()
+}
+
+// Markdown content is left alone; it is rendered (and escaped) on display.
+// So is front matter, which is never rendered as HTML.
+pub fn sanitize_content(content: &str) -> String {
+    if crate::render::looks_like_html(content) {
+        let header = crate::front_matter::header(content);
+        format!("{}{}", header, sanitize_html(&content[header.len()..]))
+    } else {
+        content.to_string()
+    }
+}
@@ -0,0 +1,342 @@
+use crate::commands::{insert_event, insert_note, new_event, row_to_note};
+use crate::date_parse;
+use crate::db::Database;
+use crate::locale;
+use crate::models::{DateOrder, EventCreate, NoteCreate, Script, TimeMode};
+use crate::queries;
+use crate::search_index;
+use crate::timestamps;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tauri::{AppHandle, Manager};
+
+// Enough to loop over a note's fields, not to hang the app
+const MAX_OPERATIONS: u64 = 500_000;
+const MAX_CHANGES: usize = 20;
+
+// The data events scripts can subscribe to, by defining the handler, e.g.
+// `fn on_note_created(note) { ... }`. Handlers get the note or event as a
+// map, or for on_note_deleted its id.
+#[derive(Debug, Clone, Copy)]
+pub enum Hook {
+    NoteCreated,
+    NoteUpdated,
+    NoteDeleted,
+    EventCreated,
+    EventUpdated,
+}
+
+impl Hook {
+    fn handler(self) -> &'static str {
+        match self {
+            Hook::NoteCreated => "on_note_created",
+            Hook::NoteUpdated => "on_note_updated",
+            Hook::NoteDeleted => "on_note_deleted",
+            Hook::EventCreated => "on_event_created",
+            Hook::EventUpdated => "on_event_updated",
+        }
+    }
+}
+
+// A change a script asked for through the API. Nothing is written until the
+// handler returns without an error, and changes made for a script don't fire
+// hooks themselves, so scripts can't set each other off.
+enum Change {
+    CreateNote {
+        title: String,
+        content: String,
+    },
+    TagNote {
+        note_id: String,
+        tag: String,
+    },
+    ScheduleEvent {
+        title: String,
+        start: DateTime<Utc>,
+        all_day: bool,
+    },
+}
+
+type Changes = Rc<RefCell<Vec<Change>>>;
+
+fn request(changes: &Changes, change: Change) -> Result<(), Box<EvalAltResult>> {
+    let mut changes = changes.borrow_mut();
+    if changes.len() >= MAX_CHANGES {
+        return Err(format!("A script can make at most {} changes a run", MAX_CHANGES).into());
+    }
+    changes.push(change);
+    Ok(())
+}
+
+// "tomorrow 9am", "2024-05-01"; a day without a time is all day
+fn parse_when(when: &str, day_first: bool) -> Result<(DateTime<Utc>, bool), Box<EvalAltResult>> {
+    let resolved = date_parse::resolve(when, Local::now().naive_local(), day_first)
+        .ok_or_else(|| format!("Can't read \"{}\" as a date", when))?;
+    let Some(time) = resolved.time else {
+        return Ok((locale::day_start_utc(resolved.date), true));
+    };
+    let start = Local
+        .from_local_datetime(&resolved.date.and_time(time))
+        .earliest()
+        .ok_or_else(|| format!("{} doesn't exist in this time zone", when))?;
+    Ok((start.with_timezone(&Utc), false))
+}
+
+// No files, modules or eval, and limits on time and memory. The registered
+// functions are all a script can do to the user's data.
+fn engine(name: &str, changes: &Changes, day_first: bool) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .disable_symbol("eval");
+
+    let script = name.to_string();
+    engine.on_print(move |text| log::info!("Script {}: {}", script, text));
+    let script = name.to_string();
+    engine.on_debug(move |text, _, _| log::debug!("Script {}: {}", script, text));
+
+    let requested = changes.clone();
+    engine.register_fn("create_note", move |title: &str, content: &str| {
+        let change = Change::CreateNote {
+            title: title.to_string(),
+            content: content.to_string(),
+        };
+        request(&requested, change)
+    });
+    let requested = changes.clone();
+    engine.register_fn("tag_note", move |note_id: &str, tag: &str| {
+        let change = Change::TagNote {
+            note_id: note_id.to_string(),
+            tag: tag.to_string(),
+        };
+        request(&requested, change)
+    });
+    let requested = changes.clone();
+    engine.register_fn("schedule_event", move |title: &str, when: &str| {
+        let (start, all_day) = parse_when(when, day_first)?;
+        let change = Change::ScheduleEvent {
+            title: title.to_string(),
+            start,
+            all_day,
+        };
+        request(&requested, change)
+    });
+    engine
+}
+
+fn compile(engine: &Engine, source: &str) -> Result<AST, String> {
+    engine.compile(source).map_err(|e| e.to_string())
+}
+
+// For saving a script: it has to parse
+pub fn check(source: &str) -> Result<(), String> {
+    compile(&engine("", &Changes::default(), false), source).map(|_| ())
+}
+
+fn row_to_script(row: &rusqlite::Row) -> rusqlite::Result<Script> {
+    Ok(Script {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        source: row.get("source")?,
+        enabled: row.get::<_, i32>("enabled")? != 0,
+        last_run_at: timestamps::get_opt(row, "last_run_at")?,
+        last_error: row.get("last_error")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+    })
+}
+
+const COLUMNS: &str = "id, name, source, enabled, last_run_at, last_error, created_at, updated_at";
+
+fn query(conn: &Connection, filter: &str) -> Result<Vec<Script>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM scripts {} ORDER BY name COLLATE NOCASE",
+            COLUMNS, filter
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_script)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Script>, String> {
+    query(conn, "")
+}
+
+pub fn load(conn: &Connection, id: &str) -> Result<Option<Script>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM scripts WHERE id = ?1", COLUMNS),
+        params![id],
+        row_to_script,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Runs every enabled script's handler for the hook, off the calling thread.
+// A script that fails has the error kept as its last_error, for the scripts
+// screen; the change that fired the hook stands either way.
+pub fn dispatch<T: Serialize>(app: &AppHandle, hook: Hook, payload: &T) {
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::warn!("Failed to pass {} to scripts: {}", hook.handler(), e);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = run_all(&app, hook, &payload) {
+            log::warn!("Failed to run scripts for {}: {}", hook.handler(), e);
+        }
+    });
+}
+
+// Scripts run without the database lock; only their changes take it
+fn run_all(app: &AppHandle, hook: Hook, payload: &serde_json::Value) -> Result<(), String> {
+    let db = app.state::<Database>();
+    let (scripts, day_first) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let scripts = query(&conn, "WHERE enabled = 1")?;
+        (scripts, locale::load(&conn).date_order == DateOrder::Dmy)
+    };
+
+    for script in scripts {
+        let result = match run(&script, hook, payload, day_first) {
+            Ok(None) => continue,
+            Ok(Some(changes)) => Ok(changes),
+            Err(e) => Err(e),
+        };
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let outcome = result.and_then(|changes| apply(&conn, &changes));
+        conn.execute(
+            "UPDATE scripts SET last_run_at = ?1, last_error = ?2 WHERE id = ?3",
+            params![timestamps::format(&Utc::now()), outcome.err(), script.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// The changes the handler asked for; None when the script has no handler
+// for the hook
+fn run(
+    script: &Script,
+    hook: Hook,
+    payload: &serde_json::Value,
+    day_first: bool,
+) -> Result<Option<Vec<Change>>, String> {
+    let changes = Changes::default();
+    let engine = engine(&script.name, &changes, day_first);
+    let ast = compile(&engine, &script.source)?;
+    let handler = hook.handler();
+    if !ast
+        .iter_functions()
+        .any(|f| f.name == handler && f.params.len() == 1)
+    {
+        return Ok(None);
+    }
+
+    let argument = rhai::serde::to_dynamic(payload).map_err(|e| e.to_string())?;
+    engine
+        .call_fn::<Dynamic>(&mut Scope::new(), &ast, handler, (argument,))
+        .map_err(|e| e.to_string())?;
+    Ok(Some(changes.take()))
+}
+
+// All or nothing, like the handler that asked for them
+fn apply(conn: &Connection, changes: &[Change]) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for change in changes {
+        match change {
+            Change::CreateNote { title, content } => {
+                insert_note(
+                    &tx,
+                    NoteCreate {
+                        title: Some(title.clone()),
+                        content: Some(content.clone()),
+                        folder_id: None,
+                        tags: None,
+                        aliases: None,
+                    },
+                )?;
+            }
+            Change::TagNote { note_id, tag } => tag_note(&tx, note_id, tag)?,
+            Change::ScheduleEvent {
+                title,
+                start,
+                all_day,
+            } => {
+                let event = new_event(EventCreate {
+                    title: title.clone(),
+                    description: None,
+                    event_type: None,
+                    start_time: Some(*start),
+                    end_time: None,
+                    time_mode: all_day.then_some(TimeMode::AllDay),
+                    duration_minutes: None,
+                    location: None,
+                    category: None,
+                    color: None,
+                    priority: None,
+                    tags: None,
+                    show_on_calendar: None,
+                    is_all_day: Some(*all_day),
+                    is_recurring: None,
+                    recurring_pattern: None,
+                    reminders: None,
+                    notes: None,
+                });
+                insert_event(&tx, &event)?;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Adds the tag unless the note has it already
+fn tag_note(conn: &Connection, note_id: &str, tag: &str) -> Result<(), String> {
+    let note = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+                queries::NOTE_COLUMNS
+            ),
+            params![note_id],
+            row_to_note,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Note {} not found", note_id))?;
+
+    let tag = tag.trim().trim_start_matches('#');
+    if tag.is_empty() || note.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+        return Ok(());
+    }
+    let mut tags = note.tags;
+    tags.push(tag.to_string());
+    conn.execute(
+        "UPDATE notes SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+        params![
+            serde_json::to_string(&tags).unwrap_or_default(),
+            timestamps::format(&Utc::now()),
+            note_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    search_index::note_changed(conn, note_id);
+    Ok(())
+}
@@ -0,0 +1,452 @@
+use crate::commands::row_to_note;
+use crate::db::Database;
+use crate::jobs::{self, JobContext, JobSpec};
+use crate::language;
+use crate::models::{Note, NoteBookmark, SearchHit, SearchRanking};
+use crate::queries;
+use crate::render;
+use crate::search_query::{self, Query};
+use crate::timestamps;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use scraper::{Html, Selector};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+// Bump when what gets indexed changes; a stale index is rebuilt at startup
+const INDEX_VERSION: i64 = 3;
+const VERSION_SETTING: &str = "search_index_version";
+
+// Notes indexed per transaction during a rebuild
+const REBUILD_BATCH: usize = 200;
+
+// Target of each `[[wikilink]]`, `[[Title|label]]` or `![[id#heading]]`,
+// trimmed and lowercased
+fn wikilinks(text: &str) -> Vec<String> {
+    static WIKILINK: OnceLock<Regex> = OnceLock::new();
+    let wikilink = WIKILINK
+        .get_or_init(|| Regex::new(r"\[\[([^\[\]|#]+)[^\[\]]*\]\]").expect("valid wikilink regex"));
+
+    let mut targets: Vec<String> = wikilink
+        .captures_iter(text)
+        .map(|c| c[1].trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+// Web links in the note, with the link text when there is one
+fn bookmarks(html: &str, text: &str) -> Vec<(String, Option<String>)> {
+    static BARE_URL: OnceLock<Regex> = OnceLock::new();
+    let bare_url =
+        BARE_URL.get_or_init(|| Regex::new(r#"https?://[^\s<>"')\]]+"#).expect("valid url regex"));
+
+    let mut found: Vec<(String, Option<String>)> = Vec::new();
+    let anchors = Selector::parse("a[href]").expect("valid selector");
+    for anchor in Html::parse_fragment(html).select(&anchors) {
+        let Some(href) = anchor.value().attr("href") else {
+            continue;
+        };
+        if !(href.starts_with("http://") || href.starts_with("https://")) {
+            continue;
+        }
+        let label: String = anchor.text().collect::<String>().trim().to_string();
+        let label = Some(label).filter(|l| !l.is_empty() && l != href);
+        if !found.iter().any(|(url, _)| url == href) {
+            found.push((href.to_string(), label));
+        }
+    }
+    for url in bare_url.find_iter(text) {
+        let url = url.as_str().trim_end_matches(['.', ',', ';', ':']);
+        if !found.iter().any(|(u, _)| u == url) {
+            found.push((url.to_string(), None));
+        }
+    }
+    found
+}
+
+fn remove(conn: &Connection, note_id: &str) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM notes_fts WHERE note_id = ?1", params![note_id])?;
+    conn.execute(
+        "DELETE FROM note_links WHERE source_note_id = ?1",
+        params![note_id],
+    )?;
+    conn.execute(
+        "DELETE FROM note_bookmarks WHERE note_id = ?1",
+        params![note_id],
+    )?;
+    Ok(())
+}
+
+// Replaces everything indexed for the note; trashed or missing notes are
+// dropped from the index
+fn index(conn: &Connection, note_id: &str) -> rusqlite::Result<()> {
+    remove(conn, note_id)?;
+
+    let note = conn
+        .query_row(
+            "SELECT title, content, tags, aliases FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+            params![note_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((title, content, tags, aliases)) = note else {
+        return Ok(());
+    };
+
+    let html = render::content_to_html(&content);
+    let text = render::content_to_plain_text(&content);
+    let tags: Vec<String> = serde_json::from_str(&tags).unwrap_or_default();
+    let aliases: Vec<String> = serde_json::from_str(&aliases).unwrap_or_default();
+
+    // Derived like the rest of the index, so every writer of notes keeps it
+    // current; updated_at is left alone
+    let language = language::detect(&format!("{}\n{}", title, text));
+    conn.execute(
+        "UPDATE notes SET language = ?1 WHERE id = ?2",
+        params![language, note_id],
+    )?;
+
+    // Aliases weigh like the title
+    let titles = std::iter::once(title).chain(aliases).collect::<Vec<_>>().join("\n");
+    conn.execute(
+        "INSERT INTO notes_fts (note_id, title, body, tags) VALUES (?1, ?2, ?3, ?4)",
+        params![note_id, titles, text, tags.join(" ")],
+    )?;
+    for target in wikilinks(&text) {
+        conn.execute(
+            "INSERT OR IGNORE INTO note_links (source_note_id, target) VALUES (?1, ?2)",
+            params![note_id, target],
+        )?;
+    }
+    for (url, label) in bookmarks(&html, &text) {
+        conn.execute(
+            "INSERT OR IGNORE INTO note_bookmarks (note_id, url, label) VALUES (?1, ?2, ?3)",
+            params![note_id, url, label],
+        )?;
+    }
+    Ok(())
+}
+
+// Call after any write to a note. The index is derived data, so a failure
+// here is logged rather than failing the write; a rebuild repairs it.
+pub fn note_changed(conn: &Connection, note_id: &str) {
+    if let Err(e) = index(conn, note_id) {
+        log::warn!("Failed to index note {}: {}", note_id, e);
+    }
+}
+
+pub fn note_removed(conn: &Connection, note_id: &str) {
+    if let Err(e) = remove(conn, note_id) {
+        log::warn!("Failed to unindex note {}: {}", note_id, e);
+    }
+}
+
+// Queues a rebuild when the index was built by an older version (or never)
+pub fn ensure_current(app: &AppHandle) -> Result<(), String> {
+    let stale = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let version: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![VERSION_SETTING],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let queued: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM jobs WHERE kind = ?1 AND state IN ('queued', 'running'))",
+                params![JobSpec::RebuildSearchIndex.kind()],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        version.and_then(|v| v.parse::<i64>().ok()) != Some(INDEX_VERSION) && !queued
+    };
+
+    if stale {
+        jobs::enqueue(app, JobSpec::RebuildSearchIndex)?;
+    }
+    Ok(())
+}
+
+// Job body: clears the index and indexes every live note again in batches.
+// The lock is released between batches so the app stays usable; edits made
+// meanwhile are indexed as usual.
+pub fn rebuild(ctx: &JobContext) -> Result<Option<String>, String> {
+    let db = ctx.app().state::<Database>();
+
+    let ids: Vec<String> = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "DELETE FROM notes_fts; DELETE FROM note_links; DELETE FROM note_bookmarks;",
+        )
+        .map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT id FROM notes WHERE deleted_at IS NULL ORDER BY updated_at DESC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let total = ids.len();
+    ctx.progress(0, total, "Indexing notes");
+    for (batch, chunk) in ids.chunks(REBUILD_BATCH).enumerate() {
+        ctx.checkpoint()?;
+        {
+            let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            for id in chunk {
+                index(&tx, id).map_err(|e| e.to_string())?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+        let done = (batch * REBUILD_BATCH + chunk.len()).min(total);
+        ctx.progress(done, total, "Indexing notes");
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![VERSION_SETTING, INDEX_VERSION.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(None)
+}
+
+pub fn search(conn: &Connection, query: &Query, limit: u32) -> Result<Vec<SearchHit>, String> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut conditions = vec!["n.deleted_at IS NULL".to_string()];
+    let mut values: Vec<Value> = Vec::new();
+
+    let matched = search_query::match_expression(&query.terms, true);
+    if let Some(expression) = &matched {
+        conditions.push("notes_fts MATCH ?".to_string());
+        values.push(Value::Text(expression.clone()));
+    }
+    for term in &query.excluded_terms {
+        conditions.push(
+            "n.id NOT IN (SELECT note_id FROM notes_fts WHERE notes_fts MATCH ?)".to_string(),
+        );
+        values.push(Value::Text(
+            search_query::match_expression(std::slice::from_ref(term), false).unwrap_or_default(),
+        ));
+    }
+    for (filter, negated) in &query.filters {
+        let (sql, params) = search_query::filter_sql(filter);
+        // NULL (e.g. a note outside any folder) counts as not matching
+        conditions.push(if *negated {
+            format!("NOT COALESCE(({}), 0)", sql)
+        } else {
+            sql.to_string()
+        });
+        values.extend(params);
+    }
+    // Text matches are re-ranked with the boosts, so fetch more than asked for
+    let fetch = if matched.is_some() {
+        (limit * RANKING_POOL_FACTOR).max(RANKING_POOL_MIN)
+    } else {
+        limit
+    };
+    values.push(Value::Integer(fetch as i64));
+
+    // With search text, title matches weigh most, then tags, then the body.
+    // Filter-only searches list notes like get_notes does.
+    let sql = if matched.is_some() {
+        format!(
+            "SELECT {},
+                    snippet(notes_fts, 2, '<mark>', '</mark>', '…', 12) AS snippet,
+                    -bm25(notes_fts, 0.0, 10.0, 1.0, 4.0) AS relevance,
+                    COALESCE(s.open_count, 0) AS open_count
+             FROM notes_fts JOIN notes n ON n.id = notes_fts.note_id
+                  LEFT JOIN note_stats s ON s.note_id = n.id
+             WHERE {}
+             ORDER BY bm25(notes_fts, 0.0, 10.0, 1.0, 4.0)
+             LIMIT ?",
+            queries::qualified(queries::NOTE_COLUMNS, "n"),
+            conditions.join(" AND ")
+        )
+    } else {
+        format!(
+            "SELECT {}, '' AS snippet, 0.0 AS relevance, 0 AS open_count
+             FROM notes n
+             WHERE {}
+             ORDER BY n.is_pinned DESC, n.updated_at DESC
+             LIMIT ?",
+            queries::qualified(queries::NOTE_COLUMNS, "n"),
+            conditions.join(" AND ")
+        )
+    };
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            Ok((
+                SearchHit {
+                    note: row_to_note(row)?,
+                    snippet: row.get("snippet")?,
+                    score: 0.0,
+                },
+                row.get::<_, f64>("relevance")?,
+                row.get::<_, i64>("open_count")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let candidates: Vec<(SearchHit, f64, i64)> = rows.filter_map(|r| r.ok()).collect();
+
+    if matched.is_none() {
+        return Ok(candidates.into_iter().map(|(hit, _, _)| hit).collect());
+    }
+    let mut hits = rank(candidates, &ranking(conn), Utc::now());
+    hits.truncate(limit as usize);
+    Ok(hits)
+}
+
+// ============ Ranking ============
+
+const RANKING_SETTING: &str = "search_ranking";
+const RANKING_POOL_FACTOR: u32 = 4;
+const RANKING_POOL_MIN: u32 = 200;
+
+// Stored weights, or the defaults
+pub fn ranking(conn: &Connection) -> SearchRanking {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![RANKING_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| serde_json::from_str(&value).ok())
+    .unwrap_or_default()
+}
+
+pub fn save_ranking(conn: &Connection, weights: &SearchRanking) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            RANKING_SETTING,
+            serde_json::to_string(weights).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Each signal is scaled to 0..1 and weighted: relevance against the best
+// match, recency halving every `recency_half_life_days`, pinned as 0 or 1,
+// and opens on a log scale that flattens out for favourites.
+fn rank(
+    candidates: Vec<(SearchHit, f64, i64)>,
+    weights: &SearchRanking,
+    now: DateTime<Utc>,
+) -> Vec<SearchHit> {
+    let best = candidates
+        .iter()
+        .map(|(_, relevance, _)| *relevance)
+        .fold(0.0_f64, f64::max);
+
+    let mut hits: Vec<SearchHit> = candidates
+        .into_iter()
+        .map(|(mut hit, relevance, opens)| {
+            let relevance = if best > 0.0 { relevance / best } else { 0.0 };
+            let age_days = (now - hit.note.updated_at).num_seconds().max(0) as f64 / 86_400.0;
+            let recency = 0.5_f64.powf(age_days / weights.recency_half_life_days);
+            let pinned = if hit.note.is_pinned { 1.0 } else { 0.0 };
+            let opens = (opens.max(0) as f64).ln_1p();
+            let frequency = opens / (1.0 + opens);
+
+            hit.score = weights.relevance * relevance
+                + weights.recency * recency
+                + weights.pinned * pinned
+                + weights.frequency * frequency;
+            hit
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits
+}
+
+// Counted when a note is opened in the editor; feeds the frequency boost
+pub fn record_open(conn: &Connection, note_id: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO note_stats (note_id, open_count, last_opened_at) VALUES (?1, 1, ?2)
+         ON CONFLICT(note_id) DO UPDATE SET open_count = open_count + 1,
+                                            last_opened_at = excluded.last_opened_at",
+        params![note_id, timestamps::format(&Utc::now())],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Live notes with a wikilink to this one, by title, alias or id
+pub fn backlinks(conn: &Connection, note_id: &str) -> Result<Vec<Note>, String> {
+    let names: Option<(String, String)> = conn
+        .query_row(
+            "SELECT title, aliases FROM notes WHERE id = ?1",
+            params![note_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((title, aliases)) = names else {
+        return Ok(Vec::new());
+    };
+    let aliases: Vec<String> = serde_json::from_str(&aliases).unwrap_or_default();
+
+    let mut targets = vec![note_id.to_lowercase()];
+    targets.extend(
+        std::iter::once(title)
+            .chain(aliases)
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty()),
+    );
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT DISTINCT {}
+             FROM note_links l JOIN notes n ON n.id = l.source_note_id
+             WHERE l.target IN ({}) AND n.id != ? AND n.deleted_at IS NULL
+             ORDER BY n.updated_at DESC",
+            queries::qualified(queries::NOTE_COLUMNS, "n"),
+            vec!["?"; targets.len()].join(", ")
+        ))
+        .map_err(|e| e.to_string())?;
+    targets.push(note_id.to_string());
+    let rows = stmt
+        .query_map(params_from_iter(&targets), row_to_note)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+pub fn bookmarks_for(conn: &Connection, note_id: &str) -> Result<Vec<NoteBookmark>, String> {
+    let mut stmt = conn
+        .prepare("SELECT note_id, url, label FROM note_bookmarks WHERE note_id = ?1 ORDER BY rowid")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteBookmark {
+                note_id: row.get(0)?,
+                url: row.get(1)?,
+                label: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
@@ -0,0 +1,175 @@
+use crate::date_parse;
+use crate::locale;
+use crate::timestamps;
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::types::Value;
+
+pub enum Filter {
+    Tag(String),
+    // Matches the folder with this name and everything below it
+    Folder(String),
+    Before(NaiveDate),
+    After(NaiveDate),
+    Pinned,
+    Published,
+    // ISO 639-1 code, as detected for the note
+    Language(String),
+}
+
+// A parsed search. Plain words and "quoted phrases" go to the full-text
+// index; `key:value` filters become SQL conditions. A leading `-` negates
+// either.
+#[derive(Default)]
+pub struct Query {
+    pub terms: Vec<String>,
+    pub excluded_terms: Vec<String>,
+    pub filters: Vec<(Filter, bool)>,
+}
+
+impl Query {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.excluded_terms.is_empty() && self.filters.is_empty()
+    }
+}
+
+// Splits on whitespace, keeping "quoted runs" (also after `key:`) together.
+// Quotes are kept so callers can tell phrases from words.
+fn tokens(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                current.push(ch);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.strip_prefix('"').unwrap_or(value);
+    value.strip_suffix('"').unwrap_or(value)
+}
+
+fn date(
+    key: &str,
+    value: &str,
+    reference: NaiveDateTime,
+    day_first: bool,
+) -> Result<NaiveDate, String> {
+    date_parse::resolve(value, reference, day_first)
+        .map(|r| r.date)
+        .ok_or_else(|| format!("{}: unrecognized date \"{}\"", key, value))
+}
+
+// `before:2024-01-01`, `after:"last month"`, `tag:work`, `folder:"Work"`,
+// `is:pinned`, `is:published`, `lang:de`. Unknown keys are searched as plain
+// text.
+pub fn parse(input: &str, reference: NaiveDateTime, day_first: bool) -> Result<Query, String> {
+    let mut query = Query::default();
+
+    for token in tokens(input) {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+            _ => (false, token),
+        };
+
+        let filter = match token.split_once(':') {
+            Some((key, value)) if !key.starts_with('"') => {
+                let value = unquote(value).trim();
+                match key.to_ascii_lowercase().as_str() {
+                    "tag" if !value.is_empty() => Some(Filter::Tag(value.to_string())),
+                    "folder" if !value.is_empty() => Some(Filter::Folder(value.to_string())),
+                    "lang" | "language" if !value.is_empty() => {
+                        Some(Filter::Language(value.to_ascii_lowercase()))
+                    }
+                    "before" => Some(Filter::Before(date(key, value, reference, day_first)?)),
+                    "after" => Some(Filter::After(date(key, value, reference, day_first)?)),
+                    "is" => match value.to_ascii_lowercase().as_str() {
+                        "pinned" => Some(Filter::Pinned),
+                        "published" => Some(Filter::Published),
+                        other => return Err(format!("is: unknown value \"{}\"", other)),
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        match filter {
+            Some(filter) => query.filters.push((filter, negated)),
+            None => {
+                let text = unquote(&token).trim();
+                if text.is_empty() {
+                    continue;
+                }
+                if negated {
+                    query.excluded_terms.push(text.to_string());
+                } else {
+                    query.terms.push(text.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(query)
+}
+
+// Each term as an FTS5 string; the last positive word also matches as a
+// prefix so results show up while typing
+pub fn match_expression(terms: &[String], prefix_last: bool) -> Option<String> {
+    let mut parts: Vec<String> = terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    if prefix_last {
+        if let Some(last) = parts.last_mut() {
+            last.push('*');
+        }
+    }
+    Some(parts.join(" ")).filter(|e| !e.is_empty())
+}
+
+fn day_start(date: NaiveDate) -> String {
+    timestamps::format(&locale::day_start_utc(date))
+}
+
+// SQL condition on `notes n` for the filter, with its parameters in order.
+// Dates compare against creation time; `after` includes the day itself.
+pub fn filter_sql(filter: &Filter) -> (&'static str, Vec<Value>) {
+    match filter {
+        Filter::Tag(tag) => (
+            "EXISTS (SELECT 1 FROM json_each(n.tags) WHERE lower(json_each.value) = lower(?))",
+            vec![Value::Text(tag.clone())],
+        ),
+        Filter::Folder(name) => (
+            "n.folder_id IN (
+                 WITH RECURSIVE subfolders(id) AS (
+                     SELECT id FROM folders WHERE lower(name) = lower(?)
+                     UNION
+                     SELECT f.id FROM folders f JOIN subfolders s ON f.parent_id = s.id
+                 )
+                 SELECT id FROM subfolders
+             )",
+            vec![Value::Text(name.clone())],
+        ),
+        Filter::Before(date) => ("n.created_at < ?", vec![Value::Text(day_start(*date))]),
+        Filter::After(date) => ("n.created_at >= ?", vec![Value::Text(day_start(*date))]),
+        Filter::Pinned => ("n.is_pinned = 1", Vec::new()),
+        Filter::Published => ("n.published_url IS NOT NULL", Vec::new()),
+        Filter::Language(code) => ("n.language = ?", vec![Value::Text(code.clone())]),
+    }
+}
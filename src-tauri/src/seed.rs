@@ -0,0 +1,364 @@
+use crate::models::{EventStatus, NodeShape, NodeSize, Priority, SeedSummary, TimeMode};
+use crate::search_index;
+use crate::timestamps;
+use chrono::{Duration, DurationRound, Utc};
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+// Small xorshift generator with a fixed seed, so every run produces the same
+// demo workspace (handy when comparing timings before and after a change).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+}
+
+const FOLDERS: &[(&str, &str)] = &[
+    ("Work", "#3b82f6"),
+    ("Personal", "#22c55e"),
+    ("Research", "#8b5cf6"),
+    ("Travel", "#f97316"),
+    ("Reading", "#ec4899"),
+    ("Projects", "#14b8a6"),
+];
+
+const TOPICS: &[&str] = &[
+    "Quarterly planning",
+    "Team retro",
+    "Reading list",
+    "Trip to Lisbon",
+    "Garden layout",
+    "API redesign",
+    "Book notes",
+    "Recipe ideas",
+    "Interview prep",
+    "Budget review",
+    "Conference talk",
+    "Home office setup",
+    "Learning Rust",
+    "Weekly groceries",
+    "Product roadmap",
+];
+
+const SENTENCES: &[&str] = &[
+    "We agreed to revisit the scope once the first prototype is in front of users.",
+    "The main open question is how this interacts with offline mode.",
+    "Follow up with the design team about the empty states.",
+    "Numbers look better than last month, mostly thanks to fewer support tickets.",
+    "Keep the first version small and ship it behind a setting.",
+    "There is a good chapter on this in the book Sam recommended.",
+    "Remember to book tickets before prices go up in spring.",
+    "Performance is fine up to a few thousand items, then the list starts to stutter.",
+    "Write down what worked so we can repeat it next time.",
+    "Ask for feedback early rather than polishing in isolation.",
+];
+
+const TASKS: &[&str] = &[
+    "Draft outline",
+    "Send summary email",
+    "Review pull requests",
+    "Update the spreadsheet",
+    "Call the venue",
+    "Renew passport",
+];
+
+const EVENT_TITLES: &[&str] = &[
+    "Standup",
+    "1:1 with manager",
+    "Gym",
+    "Dentist",
+    "Project sync",
+    "Lunch with Alex",
+    "Pay rent",
+    "Write blog post",
+    "Yoga class",
+    "Sprint review",
+    "Call parents",
+    "Grocery run",
+];
+
+const MAP_TOPICS: &[&str] = &[
+    "Goals", "Health", "Career", "Learning", "Finance", "Family", "Travel", "Hobbies", "Ideas",
+    "Reading", "Projects", "Habits",
+];
+
+fn note_content(rng: &mut Rng) -> String {
+    let mut html = String::new();
+    for _ in 0..2 + rng.below(4) {
+        let sentences: Vec<&str> = (0..2 + rng.below(3)).map(|_| *rng.pick(SENTENCES)).collect();
+        html.push_str(&format!("<p>{}</p>", sentences.join(" ")));
+    }
+    if rng.chance(50) {
+        html.push_str("<ul data-type=\"taskList\">");
+        for _ in 0..2 + rng.below(4) {
+            let done = rng.chance(40);
+            html.push_str(&format!(
+                "<li data-type=\"taskItem\" data-checked=\"{}\"><label><input type=\"checkbox\"{}><span></span></label><div><p>{}</p></div></li>",
+                done,
+                if done { " checked" } else { "" },
+                rng.pick(TASKS)
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html
+}
+
+// Generates folders, notes, events and one large brain map. `scale` multiplies
+// the base amounts (6 folders, 60 notes, 50 events, ~150 map nodes).
+pub fn seed(conn: &mut Connection, scale: u32) -> Result<SeedSummary, String> {
+    let scale = scale.clamp(1, 100) as usize;
+    let mut rng = Rng(0x5EED_DA7A_u64 ^ scale as u64);
+    let mut summary = SeedSummary::default();
+    let now = Utc::now();
+    let stamp = timestamps::format(&now);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Folders
+    let mut folder_ids = Vec::new();
+    for round in 0..scale {
+        for (name, color) in FOLDERS {
+            let id = format!("folder_{}", Uuid::new_v4());
+            let name = if round == 0 { name.to_string() } else { format!("{} {}", name, round + 1) };
+            tx.execute(
+                "INSERT INTO folders (id, name, parent_id, color, icon, created_at, updated_at)
+                 VALUES (?1, ?2, NULL, ?3, NULL, ?4, ?4)",
+                params![id, name, color, stamp],
+            )
+            .map_err(|e| e.to_string())?;
+            folder_ids.push(id);
+            summary.folders += 1;
+        }
+    }
+
+    // Notes, with creation times spread over the last 90 days
+    let mut note_ids = Vec::new();
+    for i in 0..60 * scale {
+        let id = format!("note_{}", Uuid::new_v4());
+        let created =
+            timestamps::format(&(now - Duration::minutes(rng.below(90 * 24 * 60) as i64)));
+        let folder_id = if rng.chance(80) { Some(rng.pick(&folder_ids).clone()) } else { None };
+        let tags: Vec<&str> = ["demo", "idea", "todo", "draft", "reference"]
+            .into_iter()
+            .filter(|_| rng.chance(25))
+            .collect();
+
+        tx.execute(
+            "INSERT INTO notes (id, title, content, folder_id, tags, is_pinned, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![
+                id,
+                format!("{} #{}", rng.pick(TOPICS), i + 1),
+                note_content(&mut rng),
+                folder_id,
+                serde_json::to_string(&tags).unwrap_or_default(),
+                rng.chance(5) as i32,
+                created,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        note_ids.push(id);
+        summary.notes += 1;
+    }
+    for id in &note_ids {
+        search_index::note_changed(&tx, id);
+    }
+
+    // Events from two weeks back to six weeks ahead, some recurring
+    let today = now
+        .duration_trunc(Duration::days(1))
+        .map_err(|e| e.to_string())?;
+    let mut event_ids = Vec::new();
+    for _ in 0..50 * scale {
+        let id = format!("event_{}", Uuid::new_v4());
+        let day = today + Duration::days(rng.below(56) as i64 - 14);
+
+        let (time_mode, start, end, all_day) = match rng.below(10) {
+            0 => (TimeMode::Todo, None, None, false),
+            1 => (TimeMode::AllDay, Some(day), None, true),
+            2 => (TimeMode::Morning, Some(day), None, false),
+            _ => {
+                let start = day + Duration::minutes((7 * 60 + rng.below(24) * 30) as i64);
+                let end = start + Duration::minutes((1 + rng.below(4)) as i64 * 30);
+                (TimeMode::AtTime, Some(start), Some(end), false)
+            }
+        };
+        let recurring = if rng.chance(15) { Some(*rng.pick(&["daily", "weekly", "monthly"])) } else { None };
+        let status = if start.is_some_and(|s| s < now) && rng.chance(70) {
+            EventStatus::Completed
+        } else {
+            EventStatus::Pending
+        };
+
+        tx.execute(
+            "INSERT INTO events (id, title, description, start_time, end_time, has_scheduled_time,
+                                 time_mode, duration_minutes, category, priority, tags,
+                                 show_on_calendar, is_all_day, is_recurring, recurring_pattern,
+                                 status, reminders, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, '[]', 1, ?11, ?12, ?13, ?14, '[]', ?15, ?15)",
+            params![
+                id,
+                rng.pick(EVENT_TITLES),
+                rng.chance(30).then(|| *rng.pick(SENTENCES)),
+                start.as_ref().map(timestamps::format),
+                end.as_ref().map(timestamps::format),
+                start.is_some() as i32,
+                time_mode,
+                start.zip(end).map(|(s, e)| (e - s).num_minutes() as i32),
+                rng.pick(&["work", "meeting", "personal", "todo"]),
+                rng.pick(&[Priority::Low, Priority::Medium, Priority::High]),
+                all_day as i32,
+                recurring.is_some() as i32,
+                recurring,
+                status,
+                stamp,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        event_ids.push(id);
+        summary.events += 1;
+    }
+
+    // One large brain map: a radial tree three levels deep, plus cross links
+    let map_id = format!("brainmap_{}", Uuid::new_v4());
+    let center_id = format!("node_{}", Uuid::new_v4());
+    tx.execute(
+        "INSERT INTO brain_maps (id, title, description, center_node_id, center_node_text, created_at, updated_at)
+         VALUES (?1, 'Demo map', 'Generated demo data', ?2, 'Life', ?3, ?3)",
+        params![map_id, center_id, stamp],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut insert_node = |rng: &mut Rng,
+                           id: &str,
+                           parent: Option<&str>,
+                           label: String,
+                           (x, y): (f64, f64),
+                           layer: i32|
+     -> Result<(), String> {
+        let (shape, size) = match layer {
+            0 => (NodeShape::Circle, NodeSize::Xl),
+            1 => (NodeShape::Pill, NodeSize::Large),
+            2 => (NodeShape::Rectangle, NodeSize::Medium),
+            _ => (NodeShape::Circle, NodeSize::Small),
+        };
+        let linked_note = (layer == 3 && rng.chance(20)).then(|| rng.pick(&note_ids).clone());
+        let linked_event = (layer == 3 && rng.chance(10)).then(|| rng.pick(&event_ids).clone());
+
+        tx.execute(
+            "INSERT INTO brain_map_nodes (id, brain_map_id, parent_node_id, label, x, y, color, shape,
+                                          size, linked_note_id, linked_event_id, layer,
+                                          created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?13)",
+            params![
+                id,
+                map_id,
+                parent,
+                label,
+                x,
+                y,
+                rng.pick(FOLDERS).1,
+                shape,
+                size,
+                linked_note,
+                linked_event,
+                layer,
+                stamp,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        summary.nodes += 1;
+        Ok(())
+    };
+
+    insert_node(&mut rng, &center_id, None, "Life".to_string(), (0.0, 0.0), 0)?;
+
+    let branches = (MAP_TOPICS.len() * scale).min(60);
+    let mut leaf_ids = Vec::new();
+    for b in 0..branches {
+        let angle = b as f64 / branches as f64 * std::f64::consts::TAU;
+        let branch_id = format!("node_{}", Uuid::new_v4());
+        let topic = MAP_TOPICS[b % MAP_TOPICS.len()];
+        insert_node(
+            &mut rng,
+            &branch_id,
+            Some(&center_id),
+            topic.to_string(),
+            (angle.cos() * 300.0, angle.sin() * 300.0),
+            1,
+        )?;
+
+        for s in 0..3 {
+            let sub_angle = angle + (s as f64 - 1.0) * 0.2;
+            let sub_id = format!("node_{}", Uuid::new_v4());
+            insert_node(
+                &mut rng,
+                &sub_id,
+                Some(&branch_id),
+                format!("{} {}", topic, s + 1),
+                (sub_angle.cos() * 600.0, sub_angle.sin() * 600.0),
+                2,
+            )?;
+
+            for l in 0..2 + rng.below(3) {
+                let leaf_angle = sub_angle + (l as f64 - 1.5) * 0.05;
+                let leaf_id = format!("node_{}", Uuid::new_v4());
+                let label = rng.pick(TASKS).to_string();
+                insert_node(
+                    &mut rng,
+                    &leaf_id,
+                    Some(&sub_id),
+                    label,
+                    (leaf_angle.cos() * 900.0, leaf_angle.sin() * 900.0),
+                    3,
+                )?;
+                leaf_ids.push(leaf_id);
+            }
+        }
+    }
+
+    for _ in 0..leaf_ids.len() / 10 {
+        let source = rng.pick(&leaf_ids).clone();
+        let target = rng.pick(&leaf_ids).clone();
+        if source == target {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO brain_map_connections (id, brain_map_id, source_node_id, target_node_id,
+                                                style, animated, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)",
+            params![
+                format!("conn_{}", Uuid::new_v4()),
+                map_id,
+                source,
+                target,
+                rng.pick(&["dashed", "dotted", "curved"]),
+                stamp,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        summary.connections += 1;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(summary)
+}
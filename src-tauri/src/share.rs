@@ -0,0 +1,105 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use regex::{Captures, Regex};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+// Must match the iteration count the unlock script passes to WebCrypto
+const PBKDF2_ITERATIONS: u32 = 250_000;
+
+fn attachment_url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?:attachment://localhost|https?://attachment\.localhost)/(att_[0-9A-Za-z-]+)")
+            .expect("valid attachment url regex")
+    })
+}
+
+// Replaces each `attachment://` reference with whatever `resolve` returns for
+// its attachment id; references it can't resolve are left alone.
+pub fn replace_attachment_urls<F>(html: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    attachment_url_regex()
+        .replace_all(html, |caps: &Captures| resolve(&caps[1]).unwrap_or_else(|| caps[0].to_string()))
+        .into_owned()
+}
+
+// Replaces `attachment://` references with data URIs so the page works offline.
+// `resolve` returns (mime type, bytes) for an attachment id.
+pub fn inline_attachments<F>(html: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str) -> Option<(String, Vec<u8>)>,
+{
+    replace_attachment_urls(html, |id| {
+        let (mime_type, bytes) = resolve(id)?;
+        Some(format!("data:{};base64,{}", mime_type, BASE64.encode(bytes)))
+    })
+}
+
+// Encrypts the full page with a password-derived key (PBKDF2-SHA256 -> AES-256-GCM)
+// and returns a small page that decrypts it in the browser via WebCrypto.
+pub fn password_protect(title: &str, page: &str, password: &str) -> Result<String, String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, PBKDF2_ITERATIONS, &mut key);
+
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let data = cipher
+        .encrypt(&nonce, page.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+  body {{ font: 16px/1.5 -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; display: flex; justify-content: center; margin-top: 20vh; }}
+  input, button {{ font-size: 16px; padding: 6px 10px; }}
+  #err {{ color: #dc2626; }}
+</style>
+</head>
+<body>
+<form id="unlock">
+  <p>This note is password protected.</p>
+  <input type="password" id="pw" placeholder="Password" autofocus>
+  <button type="submit">Open</button>
+  <p id="err"></p>
+</form>
+<script>
+const payload = {{ salt: "{salt}", iv: "{iv}", data: "{data}", iterations: {iterations} }};
+const b64 = (s) => Uint8Array.from(atob(s), (c) => c.charCodeAt(0));
+document.getElementById("unlock").addEventListener("submit", async (e) => {{
+  e.preventDefault();
+  try {{
+    const material = await crypto.subtle.importKey("raw", new TextEncoder().encode(document.getElementById("pw").value), "PBKDF2", false, ["deriveKey"]);
+    const key = await crypto.subtle.deriveKey({{ name: "PBKDF2", salt: b64(payload.salt), iterations: payload.iterations, hash: "SHA-256" }}, material, {{ name: "AES-GCM", length: 256 }}, false, ["decrypt"]);
+    const plain = await crypto.subtle.decrypt({{ name: "AES-GCM", iv: b64(payload.iv) }}, key, b64(payload.data));
+    document.open();
+    document.write(new TextDecoder().decode(plain));
+    document.close();
+  }} catch (_) {{
+    document.getElementById("err").textContent = "Wrong password";
+  }}
+}});
+</script>
+</body>
+</html>
+"#,
+        title = crate::render::escape_html(title),
+        salt = BASE64.encode(salt),
+        iv = BASE64.encode(nonce),
+        data = BASE64.encode(data),
+        iterations = PBKDF2_ITERATIONS,
+    ))
+}
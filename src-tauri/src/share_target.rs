@@ -0,0 +1,317 @@
+use crate::attachments;
+use crate::commands::{insert_inbox_item, insert_note, store_attachment};
+use crate::db::Database;
+use crate::links;
+use crate::models::{
+    Attachment, InboxItem, NoteCreate, PendingShare, ShareDestination, ShareResult, SharedContent,
+};
+use crate::timestamps;
+use crate::validation;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+use uuid::Uuid;
+
+pub const SHARE_EVENT: &str = "share:received";
+pub const FILES_EVENT: &str = "share:files";
+
+// Files handed over on the command line, e.g. by the Explorer menu entry
+const SHARE_ARG: &str = "--share";
+
+// Titles made from shared text are cut to this many characters
+const TITLE_LEN: usize = 80;
+
+#[cfg(windows)]
+const MENU_KEY: &str = r"HKCU\Software\Classes\*\shell\SendToVoyena";
+
+// Files shared from the OS, waiting for receive_shared_content, by token.
+// Managed as app state; the paths never reach the webview, which only gets
+// the token and the file names.
+#[derive(Default)]
+pub struct PendingFiles(Mutex<HashMap<String, Vec<PathBuf>>>);
+
+impl PendingFiles {
+    fn add(&self, paths: Vec<PathBuf>) -> Result<PendingShare, String> {
+        let share = PendingShare {
+            token: format!("share_{}", Uuid::new_v4()),
+            file_names: paths.iter().map(|path| file_name(path)).collect(),
+        };
+        self.0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(share.token.clone(), paths);
+        Ok(share)
+    }
+
+    pub fn take(&self, token: &str) -> Result<Vec<PathBuf>, String> {
+        self.0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(token)
+            .ok_or_else(|| "Shared files not found".to_string())
+    }
+
+    pub fn list(&self) -> Result<Vec<PendingShare>, String> {
+        let pending = self.0.lock().map_err(|e| e.to_string())?;
+        Ok(pending
+            .iter()
+            .map(|(token, paths)| PendingShare {
+                token: token.clone(),
+                file_names: paths.iter().map(|path| file_name(path)).collect(),
+            })
+            .collect())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+pub struct SharedFile {
+    name: String,
+    mime_type: &'static str,
+    bytes: Vec<u8>,
+}
+
+// Read before the database is locked
+pub fn read_files(paths: &[PathBuf]) -> Result<Vec<SharedFile>, String> {
+    paths
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(SharedFile {
+                name: file_name(path),
+                mime_type: attachments::mime_for_extension(
+                    path.extension()
+                        .and_then(|e| e.to_str())
+                        .unwrap_or_default(),
+                ),
+                bytes,
+            })
+        })
+        .collect()
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+// The title given, else the first line of the text, the first file's name
+// or the link
+fn title(content: &SharedContent, files: &[SharedFile]) -> String {
+    if let Some(title) = non_empty(&content.title) {
+        return title.to_string();
+    }
+    let first_line = non_empty(&content.text)
+        .and_then(|text| text.lines().map(str::trim).find(|l| !l.is_empty()));
+    match first_line {
+        Some(line) if line.chars().count() > TITLE_LEN => {
+            format!("{}…", line.chars().take(TITLE_LEN).collect::<String>())
+        }
+        Some(line) => line.to_string(),
+        None => files
+            .first()
+            .map(|f| f.name.clone())
+            .or_else(|| non_empty(&content.url).map(String::from))
+            .unwrap_or_else(|| "Shared".to_string()),
+    }
+}
+
+fn attachment_link(attachment: &Attachment) -> String {
+    let url = format!("attachment://localhost/{}", attachment.id);
+    let name = attachment.file_name.replace(['[', ']'], "");
+    if attachment.kind == "image" {
+        format!("![{}]({})", name, url)
+    } else {
+        format!("[{}]({})", name, url)
+    }
+}
+
+// Files always make a note; otherwise the inbox unless a note was asked for
+pub fn receive(
+    conn: &Connection,
+    data_dir: &Path,
+    content: &SharedContent,
+    files: Vec<SharedFile>,
+) -> Result<ShareResult, String> {
+    let title = title(content, &files);
+    let destination = match content.destination {
+        Some(destination) if files.is_empty() => destination,
+        _ if files.is_empty() => ShareDestination::Inbox,
+        _ => ShareDestination::Note,
+    };
+
+    if destination == ShareDestination::Inbox {
+        let text: Vec<&str> = [non_empty(&content.text), non_empty(&content.url)]
+            .into_iter()
+            .flatten()
+            .collect();
+        let item = InboxItem {
+            id: format!("inbox_{}", Uuid::new_v4()),
+            text: text.join("\n"),
+            title,
+            tags: Vec::new(),
+            due_date: None,
+            priority: None,
+            folder_path: None,
+            created_at: timestamps::now(),
+        };
+        insert_inbox_item(conn, &item)?;
+        return Ok(ShareResult {
+            note: None,
+            inbox_item: Some(item),
+        });
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    if let Some(url) = non_empty(&content.url) {
+        body.push(format!("> Source: <{}>", url));
+    }
+    if let Some(text) = non_empty(&content.text) {
+        body.push(text.to_string());
+    }
+    let mut note = insert_note(
+        conn,
+        NoteCreate {
+            title: Some(title),
+            content: Some(body.join("\n\n")),
+            folder_id: None,
+            tags: None,
+            aliases: None,
+        },
+    )?;
+
+    if !files.is_empty() {
+        for file in files {
+            let attachment = store_attachment(
+                conn,
+                data_dir,
+                note.id.clone(),
+                attachments::kind_for_mime(file.mime_type),
+                file.mime_type.to_string(),
+                &file.bytes,
+                None,
+            )?;
+            body.push(attachment_link(&attachment));
+        }
+        note.updated_at = timestamps::now();
+        note.content = links::save_content(
+            conn,
+            &note.id,
+            &body.join("\n\n"),
+            &timestamps::format(&note.updated_at),
+        )?;
+    }
+
+    Ok(ShareResult {
+        note: Some(note),
+        inbox_item: None,
+    })
+}
+
+// From a `voyena://share?title=…&text=…&url=…` link, which Shortcuts and
+// Services workflows can open
+pub fn from_url(url: &Url) -> SharedContent {
+    let mut content = SharedContent {
+        title: None,
+        text: None,
+        url: None,
+        files: None,
+        destination: None,
+    };
+    for (key, value) in url.query_pairs() {
+        let value = Some(value.into_owned());
+        match key.as_ref() {
+            "title" => content.title = value,
+            "text" => content.text = value,
+            "url" => content.url = value,
+            "destination" => content.destination = value.and_then(|v| v.parse().ok()),
+            _ => {}
+        }
+    }
+    content
+}
+
+// Everything after `--share` is a file
+pub fn files_from_args(args: &[String]) -> Option<Vec<PathBuf>> {
+    let index = args.iter().position(|arg| arg == SHARE_ARG)?;
+    Some(args[index + 1..].iter().map(PathBuf::from).collect())
+}
+
+// Links arriving from outside the frontend are saved straight away and
+// announced with the share:received event
+pub fn handle(app: &AppHandle, content: SharedContent) {
+    let result = validation::shared_content(&content)
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            let db = app.state::<Database>();
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            receive(&conn, &db.data_dir, &content, Vec::new())
+        });
+    match result {
+        Ok(result) => {
+            if let Err(e) = app.emit(SHARE_EVENT, result) {
+                log::warn!("Failed to emit {}: {}", SHARE_EVENT, e);
+            }
+        }
+        Err(e) => log::warn!("Failed to receive shared content: {}", e),
+    }
+}
+
+// Files are held here and offered to the frontend with the share:files
+// event, for receive_shared_content to save by token. Shares that arrive
+// before the frontend listens are there in get_pending_shares.
+pub fn offer_files(app: &AppHandle, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    match app.state::<PendingFiles>().add(paths) {
+        Ok(share) => {
+            if let Err(e) = app.emit(FILES_EVENT, share) {
+                log::warn!("Failed to emit {}: {}", FILES_EVENT, e);
+            }
+        }
+        Err(e) => log::warn!("Failed to hold shared files: {}", e),
+    }
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<(), String> {
+    let status = std::process::Command::new("reg")
+        .args(args)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("reg {} failed", args.join(" ")));
+    }
+    Ok(())
+}
+
+// Adds or removes "Send to Voyena" on the Explorer context menu for files,
+// for the current user only
+#[cfg(windows)]
+pub fn set_menu(enabled: bool) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe = exe.display().to_string();
+    if !enabled {
+        // Already gone is fine
+        let _ = reg(&["delete", MENU_KEY, "/f"]);
+        return Ok(());
+    }
+
+    let command = format!("\"{}\" {} \"%1\"", exe, SHARE_ARG);
+    let command_key = format!(r"{}\command", MENU_KEY);
+    reg(&["add", MENU_KEY, "/ve", "/d", "Send to Voyena", "/f"])?;
+    reg(&["add", MENU_KEY, "/v", "Icon", "/d", &exe, "/f"])?;
+    reg(&["add", &command_key, "/ve", "/d", &command, "/f"])
+}
+
+#[cfg(not(windows))]
+pub fn set_menu(_enabled: bool) -> Result<(), String> {
+    Err("The Send to Voyena menu is only available on Windows".into())
+}
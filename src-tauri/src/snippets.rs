@@ -0,0 +1,137 @@
+use crate::locale;
+use crate::models::Snippet;
+use crate::timestamps;
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const COLUMNS: &str = "id, trigger, name, content, created_at, updated_at, deleted_at";
+
+fn row_to_snippet(row: &rusqlite::Row) -> rusqlite::Result<Snippet> {
+    Ok(Snippet {
+        id: row.get("id")?,
+        trigger: row.get("trigger")?,
+        name: row.get("name")?,
+        content: row.get("content")?,
+        created_at: timestamps::get(row, "created_at")?,
+        updated_at: timestamps::get(row, "updated_at")?,
+        deleted_at: timestamps::get_opt(row, "deleted_at")?,
+    })
+}
+
+// Snippets not deleted, by trigger
+pub fn list(conn: &Connection) -> Result<Vec<Snippet>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM snippets WHERE deleted_at IS NULL ORDER BY trigger COLLATE NOCASE",
+            COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_snippet)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// Deleted ones too, for sync
+pub fn load(conn: &Connection, id: &str) -> Result<Option<Snippet>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM snippets WHERE id = ?1", COLUMNS),
+        params![id],
+        row_to_snippet,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// The live snippet other than `except_id` already typed by `trigger`
+pub fn trigger_owner(
+    conn: &Connection,
+    trigger: &str,
+    except_id: Option<&str>,
+) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM snippets
+         WHERE trigger = ?1 COLLATE NOCASE AND deleted_at IS NULL AND id != ?2",
+        params![trigger, except_id.unwrap_or("")],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Stores the snippet as given, replacing any with the same id
+pub fn write(conn: &Connection, snippet: &Snippet) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO snippets
+             (id, trigger, name, content, created_at, updated_at, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            snippet.id,
+            snippet.trigger,
+            snippet.name,
+            snippet.content,
+            timestamps::format(&snippet.created_at),
+            timestamps::format(&snippet.updated_at),
+            snippet.deleted_at.as_ref().map(timestamps::format),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Keeps the row, so the deletion reaches other devices. False when there's
+// no such snippet.
+pub fn delete(conn: &Connection, id: &str, now: &str) -> Result<bool, String> {
+    let deleted = conn
+        .execute(
+            "UPDATE snippets SET deleted_at = ?1, updated_at = ?1
+             WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(deleted > 0)
+}
+
+// The text `trigger` types, ignoring case, with {{date}} and {{time}} filled
+// in as the locale writes them. Two devices can sync in the same trigger
+// before either is renamed; the one edited last wins.
+pub fn expand(conn: &Connection, trigger: &str) -> Result<Option<String>, String> {
+    let content: Option<String> = conn
+        .query_row(
+            "SELECT content FROM snippets
+             WHERE trigger = ?1 COLLATE NOCASE AND deleted_at IS NULL
+             ORDER BY updated_at DESC LIMIT 1",
+            params![trigger.trim()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(content) = content else {
+        return Ok(None);
+    };
+
+    let settings = locale::load(conn);
+    let now = Local::now();
+    Ok(Some(
+        content
+            .replace(
+                "{{date}}",
+                &locale::format_date(now.date_naive(), settings.date_order),
+            )
+            .replace("{{time}}", &locale::format_time(now.time(), settings.clock)),
+    ))
+}
+
+// Changed or deleted after `since`, for sending to another device
+pub fn changed_since(conn: &Connection, since: &str) -> Result<Vec<Snippet>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM snippets WHERE updated_at > ?1 OR deleted_at > ?1",
+            COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![since], row_to_snippet)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
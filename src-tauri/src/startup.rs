@@ -0,0 +1,168 @@
+use crate::db::{Database, DB_FILE};
+use crate::models::{BackupFile, StartupProblem, StartupProblemKind};
+use crate::storage;
+use chrono::Utc;
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const RECOVERY_WINDOW: &str = "recovery";
+
+// Why this start couldn't open the database; None when it opened normally
+#[derive(Default)]
+pub struct Recovery {
+    problem: Mutex<Option<StartupProblem>>,
+}
+
+impl Recovery {
+    pub fn problem(&self) -> Option<StartupProblem> {
+        self.problem.lock().ok()?.clone()
+    }
+
+    fn set(&self, problem: StartupProblem) {
+        if let Ok(mut current) = self.problem.lock() {
+            *current = Some(problem);
+        }
+    }
+
+    fn set_read_only(&self) {
+        if let Ok(mut current) = self.problem.lock() {
+            if let Some(problem) = current.as_mut() {
+                problem.read_only = true;
+            }
+        }
+    }
+}
+
+fn kind(error: &rusqlite::Error) -> StartupProblemKind {
+    match error.sqlite_error_code() {
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => StartupProblemKind::Corrupt,
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => StartupProblemKind::Locked,
+        _ => StartupProblemKind::Unavailable,
+    }
+}
+
+// Whatever can still be read of the backups table
+fn known_backups(data_dir: &Path) -> Vec<BackupFile> {
+    Database::open_read_only(data_dir.to_path_buf())
+        .ok()
+        .and_then(|db| {
+            let conn = db.conn.lock().ok()?;
+            storage::backups(&conn).ok()
+        })
+        .unwrap_or_default()
+}
+
+pub fn open(app: &AppHandle) -> Result<Database, StartupProblem> {
+    let data_dir = Database::data_dir(app).map_err(|message| StartupProblem {
+        kind: StartupProblemKind::Unavailable,
+        message,
+        database_path: String::new(),
+        backups: Vec::new(),
+        read_only: false,
+    })?;
+
+    Database::open(data_dir.clone()).map_err(|e| StartupProblem {
+        kind: kind(&e),
+        message: e.to_string(),
+        database_path: data_dir.join(DB_FILE).to_string_lossy().into_owned(),
+        backups: known_backups(&data_dir),
+        read_only: false,
+    })
+}
+
+// Hides the main window and opens the recovery window in its place, so a
+// start that can't open the database explains itself instead of crashing
+pub fn show(app: &AppHandle, problem: StartupProblem) -> tauri::Result<()> {
+    log::error!("Failed to open the database: {}", problem.message);
+    app.state::<Recovery>().set(problem);
+
+    WebviewWindowBuilder::new(app, RECOVERY_WINDOW, WebviewUrl::App("index.html".into()))
+        .title("Voyena")
+        .inner_size(520.0, 560.0)
+        .resizable(false)
+        .center()
+        .build()?;
+    if let Some(main) = app.get_webview_window("main") {
+        main.hide()?;
+    }
+    Ok(())
+}
+
+// Safe mode: the database is opened read-only and the main window comes
+// back. Background services stay off since they'd only fail to write.
+pub fn open_read_only(app: &AppHandle) -> Result<(), String> {
+    let recovery = app.state::<Recovery>();
+    if recovery.problem().is_none() {
+        return Err("The database is already open".into());
+    }
+
+    let data_dir = Database::data_dir(app)?;
+    let db = Database::open_read_only(data_dir).map_err(|e| e.to_string())?;
+    app.manage(db);
+    recovery.set_read_only();
+
+    if let Some(main) = app.get_webview_window("main") {
+        // It loaded before there was a database to read from
+        main.reload().map_err(|e| e.to_string())?;
+        main.show().map_err(|e| e.to_string())?;
+        let _ = main.set_focus();
+    }
+    if let Some(window) = app.get_webview_window(RECOVERY_WINDOW) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// A backup is only used if SQLite finds nothing wrong with it and it has
+// notes to restore
+fn check_backup(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check(1)", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result != "ok" {
+        return Err(format!("The backup is damaged too: {}", result));
+    }
+    conn.query_row("SELECT COUNT(*) FROM notes", [], |row| row.get::<_, i64>(0))
+        .map_err(|_| "That file isn't a Voyena backup".to_string())?;
+    Ok(())
+}
+
+// `path` as one of the backups offered for the startup problem; any other
+// path is refused, so the webview can't restore from a file of its choosing
+pub fn listed_backup(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let problem = app
+        .state::<Recovery>()
+        .problem()
+        .ok_or("The database opened normally")?;
+    if !problem.backups.iter().any(|backup| backup.path == path) {
+        return Err("That file isn't one of the listed backups".into());
+    }
+    Ok(PathBuf::from(path))
+}
+
+// Puts a copy of `backup` in place of the database. The files it replaces
+// are renamed rather than deleted, so nothing is lost if the backup turns out
+// to be the wrong one. The app needs restarting afterwards.
+pub fn restore(app: &AppHandle, backup: &Path) -> Result<(), String> {
+    if app.state::<Recovery>().problem().is_none() {
+        return Err("The database opened normally".into());
+    }
+    check_backup(backup)?;
+
+    let data_dir = Database::data_dir(app)?;
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    for suffix in ["", "-wal", "-shm"] {
+        let file = data_dir.join(format!("{}{}", DB_FILE, suffix));
+        if file.exists() {
+            let kept = data_dir.join(format!("{}{}.before-restore-{}", DB_FILE, suffix, stamp));
+            fs::rename(&file, kept).map_err(|e| e.to_string())?;
+        }
+    }
+    fs::copy(backup, data_dir.join(DB_FILE)).map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use crate::attachments;
+use crate::db::DB_FILE;
+use crate::models::{BackupFile, FolderStorage, NoteStorage, StorageSettings, StorageUsage};
+use crate::timestamps;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+
+pub const STORAGE_SETTING: &str = "storage";
+const DEFAULT_ATTACHMENT_WARNING_BYTES: i64 = 50 * 1024 * 1024;
+const TOP_NOTES: i64 = 50;
+
+// Until the user picks a threshold, attachments over 50 MB need confirming
+pub fn load_settings(conn: &Connection) -> Result<StorageSettings, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![STORAGE_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        None => Ok(StorageSettings {
+            attachment_warning_bytes: Some(DEFAULT_ATTACHMENT_WARNING_BYTES),
+        }),
+    }
+}
+
+pub fn save_settings(conn: &Connection, settings: &StorageSettings) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            STORAGE_SETTING,
+            serde_json::to_string(settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn format_megabytes(bytes: i64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn file_size(path: &Path) -> i64 {
+    fs::metadata(path).map(|m| m.len() as i64).unwrap_or(0)
+}
+
+fn dir_size(dir: &Path) -> i64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len() as i64)
+        .sum()
+}
+
+fn database_bytes(data_dir: &Path) -> i64 {
+    ["", "-wal", "-shm"]
+        .iter()
+        .map(|suffix| file_size(&data_dir.join(format!("{}{}", DB_FILE, suffix))))
+        .sum()
+}
+
+pub fn record_backup(conn: &Connection, path: &Path, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO backups (path, created_at) VALUES (?1, ?2)",
+        params![path.to_string_lossy(), now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Backups that were moved or deleted since are left out
+pub fn backups(conn: &Connection) -> Result<Vec<BackupFile>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, created_at FROM backups ORDER BY created_at DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, timestamps::get(row, 1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .filter_map(|r| r.ok())
+        .filter_map(|(path, created_at)| {
+            let meta = fs::metadata(&path).ok()?;
+            Some(BackupFile {
+                path,
+                size_bytes: meta.len() as i64,
+                created_at,
+            })
+        })
+        .collect())
+}
+
+fn notes(conn: &Connection) -> Result<Vec<NoteStorage>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.title, n.folder_id, COUNT(*), SUM(a.size_bytes),
+                    n.deleted_at IS NOT NULL
+             FROM attachments a
+             JOIN notes n ON n.id = a.note_id
+             GROUP BY n.id
+             ORDER BY SUM(a.size_bytes) DESC
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![TOP_NOTES], |row| {
+            Ok(NoteStorage {
+                note_id: row.get(0)?,
+                title: row.get(1)?,
+                folder_id: row.get(2)?,
+                attachments: row.get(3)?,
+                bytes: row.get(4)?,
+                in_trash: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+fn folders(conn: &Connection) -> Result<Vec<FolderStorage>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.folder_id, COALESCE(f.name, 'No folder'), COUNT(DISTINCT n.id),
+                    SUM(a.size_bytes)
+             FROM attachments a
+             JOIN notes n ON n.id = a.note_id
+             LEFT JOIN folders f ON f.id = n.folder_id
+             GROUP BY n.folder_id
+             ORDER BY SUM(a.size_bytes) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(FolderStorage {
+                folder_id: row.get(0)?,
+                name: row.get(1)?,
+                notes: row.get(2)?,
+                bytes: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+pub fn usage(conn: &Connection, data_dir: &Path) -> Result<StorageUsage, String> {
+    let backups = backups(conn)?;
+    Ok(StorageUsage {
+        database_bytes: database_bytes(data_dir),
+        attachments_bytes: dir_size(&attachments::attachments_dir(data_dir)),
+        backups_bytes: backups.iter().map(|b| b.size_bytes).sum(),
+        backups,
+        notes: notes(conn)?,
+        folders: folders(conn)?,
+        settings: load_settings(conn)?,
+    })
+}
@@ -0,0 +1,535 @@
+use crate::commands::{insert_event, insert_note, row_to_event, row_to_note};
+use crate::crdt;
+use crate::models::{
+    ConflictCopy, ConflictResolution, EntityKind, Event, Note, NoteCreate, NoteDoc,
+    RemoteChanges, SyncReport,
+};
+use crate::queries::{EVENT_COLUMNS, NOTE_COLUMNS};
+use crate::sanitize;
+use crate::search_index;
+use crate::snippets;
+use crate::timestamps;
+use crate::trash;
+use chrono::{DateTime, Local, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn load_note(conn: &Connection, id: &str) -> Result<Option<Note>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM notes WHERE id = ?1", NOTE_COLUMNS),
+        params![id],
+        row_to_note,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn load_event(conn: &Connection, id: &str) -> Result<Option<Event>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM events WHERE id = ?1", EVENT_COLUMNS),
+        params![id],
+        row_to_event,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// The updated_at both devices agreed on when the item was last synced
+fn base(conn: &Connection, kind: EntityKind, id: &str) -> Result<Option<DateTime<Utc>>, String> {
+    conn.query_row(
+        "SELECT base_updated_at FROM sync_state WHERE entity_type = ?1 AND entity_id = ?2",
+        params![kind, id],
+        |row| timestamps::get(row, 0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn set_base(
+    conn: &Connection,
+    kind: EntityKind,
+    id: &str,
+    updated_at: &DateTime<Utc>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_state (entity_type, entity_id, base_updated_at)
+         VALUES (?1, ?2, ?3)",
+        params![kind, id, timestamps::format(updated_at)],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Equal apart from when they were saved and the `ignored` fields
+fn same_except<T: Serialize>(a: &T, b: &T, ignored: &[&str]) -> bool {
+    let strip = |item: &T| {
+        let mut value = serde_json::to_value(item).unwrap_or_default();
+        if let Some(fields) = value.as_object_mut() {
+            for field in ["updated_at", "leave_by"].iter().chain(ignored) {
+                fields.remove(*field);
+            }
+        }
+        value
+    };
+    strip(a) == strip(b)
+}
+
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    same_except(a, b, &[])
+}
+
+enum Merge {
+    // Only the other device changed it, or it's new here
+    Take,
+    // Nothing new on the other device
+    Keep,
+    // Both changed it since the last sync
+    Conflict,
+}
+
+fn decide<T: Serialize>(
+    base: Option<DateTime<Utc>>,
+    local: Option<(&T, DateTime<Utc>)>,
+    remote: (&T, DateTime<Utc>),
+) -> Merge {
+    let Some((local, local_updated_at)) = local else {
+        return Merge::Take;
+    };
+    if same(local, remote.0) || base == Some(remote.1) {
+        Merge::Keep
+    } else if base == Some(local_updated_at) {
+        Merge::Take
+    } else {
+        Merge::Conflict
+    }
+}
+
+fn write_note(conn: &Connection, note: &Note) -> Result<(), String> {
+    let content = sanitize::sanitize_content(&note.content);
+    let tags = serde_json::to_string(&note.tags).unwrap_or_default();
+    let aliases = serde_json::to_string(&note.aliases).unwrap_or_default();
+    let updated = conn
+        .execute(
+            "UPDATE notes SET title = ?1, content = ?2, folder_id = ?3, tags = ?4, is_pinned = ?5,
+                    updated_at = ?6, deleted_at = ?7, aliases = ?8
+             WHERE id = ?9",
+            params![
+                note.title,
+                content,
+                note.folder_id,
+                tags,
+                note.is_pinned as i32,
+                timestamps::format(&note.updated_at),
+                note.deleted_at.as_ref().map(timestamps::format),
+                aliases,
+                note.id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO notes (id, title, content, folder_id, tags, is_pinned, created_at,
+                                updated_at, deleted_at, aliases)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                note.id,
+                note.title,
+                content,
+                note.folder_id,
+                tags,
+                note.is_pinned as i32,
+                timestamps::format(&note.created_at),
+                timestamps::format(&note.updated_at),
+                note.deleted_at.as_ref().map(timestamps::format),
+                aliases
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    search_index::note_changed(conn, &note.id);
+    Ok(())
+}
+
+fn write_event(conn: &Connection, event: &Event) -> Result<(), String> {
+    conn.execute("DELETE FROM events WHERE id = ?1", params![event.id])
+        .map_err(|e| e.to_string())?;
+    insert_event(conn, event)?;
+    conn.execute(
+        "UPDATE events SET deleted_at = ?1 WHERE id = ?2",
+        params![event.deleted_at.as_ref().map(timestamps::format), event.id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn conflict_title(title: &str, device: &str) -> String {
+    format!(
+        "{} (Conflicted copy, {}, {})",
+        title,
+        device,
+        Local::now().format("%Y-%m-%d %H:%M")
+    )
+}
+
+fn record_copy(conn: &Connection, copy: &ConflictCopy) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO conflict_copies (id, entity_type, original_id, title, device, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            copy.id,
+            copy.kind,
+            copy.original_id,
+            copy.title,
+            copy.device,
+            timestamps::format(&copy.created_at)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn note_copy(
+    conn: &Connection,
+    remote: &Note,
+    device: &str,
+    now: &DateTime<Utc>,
+) -> Result<ConflictCopy, String> {
+    let copy = insert_note(
+        conn,
+        NoteCreate {
+            title: Some(conflict_title(&remote.title, device)),
+            content: Some(remote.content.clone()),
+            folder_id: remote.folder_id.clone(),
+            tags: Some(remote.tags.clone()),
+            aliases: Some(remote.aliases.clone()),
+        },
+    )?;
+    let copy = ConflictCopy {
+        id: copy.id,
+        kind: EntityKind::Note,
+        original_id: remote.id.clone(),
+        title: remote.title.clone(),
+        device: device.to_string(),
+        created_at: *now,
+    };
+    record_copy(conn, &copy)?;
+    Ok(copy)
+}
+
+fn event_copy(
+    conn: &Connection,
+    remote: &Event,
+    device: &str,
+    now: &DateTime<Utc>,
+) -> Result<ConflictCopy, String> {
+    let copy = Event {
+        id: format!("event_{}", Uuid::new_v4()),
+        title: conflict_title(&remote.title, device),
+        created_at: *now,
+        updated_at: *now,
+        deleted_at: None,
+        ..remote.clone()
+    };
+    insert_event(conn, &copy)?;
+    let copy = ConflictCopy {
+        id: copy.id,
+        kind: EntityKind::Event,
+        original_id: remote.id.clone(),
+        title: remote.title.clone(),
+        device: device.to_string(),
+        created_at: *now,
+    };
+    record_copy(conn, &copy)?;
+    Ok(copy)
+}
+
+// Merges another device's changes. Items only it changed replace the local
+// ones; items both devices changed keep the local version and get a
+// conflict copy holding the other, so neither edit is lost. Notes sent with
+// their content document merge their text instead, and only get a copy if
+// other fields clash too.
+pub fn apply(
+    conn: &Connection,
+    changes: RemoteChanges,
+    now: &DateTime<Utc>,
+) -> Result<SyncReport, String> {
+    let stamp = timestamps::format(now);
+    let mut report = SyncReport {
+        applied: 0,
+        unchanged: 0,
+        conflicts: Vec::new(),
+    };
+    let docs: HashMap<&str, &str> = changes
+        .note_docs
+        .iter()
+        .map(|d| (d.note_id.as_str(), d.doc.as_str()))
+        .collect();
+
+    for remote in &changes.notes {
+        let local = load_note(conn, &remote.id)?;
+        let base = base(conn, EntityKind::Note, &remote.id)?;
+        let decision = decide(
+            base,
+            local.as_ref().map(|n| (n, n.updated_at)),
+            (remote, remote.updated_at),
+        );
+        let doc = docs
+            .get(remote.id.as_str())
+            .filter(|_| !matches!(decision, Merge::Keep));
+        let merged = match doc {
+            Some(doc) => {
+                let content = local.as_ref().map(|n| n.content.as_str());
+                crdt::merge(conn, &remote.id, content, doc, &stamp)?
+            }
+            None => None,
+        };
+
+        match (decision, local) {
+            (Merge::Take, _) | (Merge::Conflict, None) => {
+                let content = merged.unwrap_or_else(|| remote.content.clone());
+                write_note(
+                    conn,
+                    &Note {
+                        content,
+                        ..remote.clone()
+                    },
+                )?;
+                report.applied += 1;
+            }
+            (Merge::Keep, _) => report.unchanged += 1,
+            (Merge::Conflict, Some(local)) => {
+                if merged.is_none() || !same_except(&local, remote, &["content"]) {
+                    report
+                        .conflicts
+                        .push(note_copy(conn, remote, &changes.device, now)?);
+                }
+                if let Some(content) = merged {
+                    write_note(
+                        conn,
+                        &Note {
+                            content,
+                            updated_at: *now,
+                            ..local
+                        },
+                    )?;
+                    report.applied += 1;
+                }
+            }
+        }
+        // Keeps the document in step with the text the note ended up with
+        if doc.is_some() {
+            if let Some(note) = load_note(conn, &remote.id)? {
+                crdt::record_edit(conn, &note.id, &note.content, &stamp)?;
+            }
+        }
+        set_base(conn, EntityKind::Note, &remote.id, &remote.updated_at)?;
+    }
+
+    for remote in &changes.events {
+        let local = load_event(conn, &remote.id)?;
+        let base = base(conn, EntityKind::Event, &remote.id)?;
+        let local = local.as_ref().map(|e| (e, e.updated_at));
+        match decide(base, local, (remote, remote.updated_at)) {
+            Merge::Take => {
+                write_event(conn, remote)?;
+                report.applied += 1;
+            }
+            Merge::Keep => report.unchanged += 1,
+            Merge::Conflict => {
+                report
+                    .conflicts
+                    .push(event_copy(conn, remote, &changes.device, now)?)
+            }
+        }
+        set_base(conn, EntityKind::Event, &remote.id, &remote.updated_at)?;
+    }
+
+    // Snippets are short enough that a copy wouldn't help; the later edit wins
+    for remote in &changes.snippets {
+        let local = snippets::load(conn, &remote.id)?;
+        let base = base(conn, EntityKind::Snippet, &remote.id)?;
+        let local_version = local.as_ref().map(|s| (s, s.updated_at));
+        match decide(base, local_version, (remote, remote.updated_at)) {
+            Merge::Take => {
+                snippets::write(conn, remote)?;
+                report.applied += 1;
+            }
+            Merge::Conflict if local.as_ref().is_some_and(|s| s.updated_at < remote.updated_at) => {
+                snippets::write(conn, remote)?;
+                report.applied += 1;
+            }
+            Merge::Keep | Merge::Conflict => report.unchanged += 1,
+        }
+        set_base(conn, EntityKind::Snippet, &remote.id, &remote.updated_at)?;
+    }
+
+    Ok(report)
+}
+
+// This device's notes, events and snippets changed or trashed after
+// `since` (all of them when None), with each note's content document, for
+// sending to another device. Items deleted for good leave nothing to send.
+pub fn changes_since(
+    conn: &Connection,
+    device: &str,
+    since: Option<&str>,
+    now: &str,
+) -> Result<RemoteChanges, String> {
+    let since = since.unwrap_or("");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM notes WHERE updated_at > ?1 OR deleted_at > ?1",
+            NOTE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let notes = stmt
+        .query_map(params![since], row_to_note)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM events WHERE updated_at > ?1 OR deleted_at > ?1",
+            EVENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let events = stmt
+        .query_map(params![since], row_to_event)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let note_docs = notes
+        .iter()
+        .map(|note| {
+            Ok(NoteDoc {
+                note_id: note.id.clone(),
+                doc: crdt::export(conn, &note.id, &note.content, now)?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(RemoteChanges {
+        device: device.to_string(),
+        notes,
+        events,
+        note_docs,
+        snippets: snippets::changed_since(conn, since)?,
+    })
+}
+
+fn row_to_copy(row: &rusqlite::Row) -> rusqlite::Result<ConflictCopy> {
+    Ok(ConflictCopy {
+        id: row.get("id")?,
+        kind: row.get("entity_type")?,
+        original_id: row.get("original_id")?,
+        title: row.get("title")?,
+        device: row.get("device")?,
+        created_at: timestamps::get(row, "created_at")?,
+    })
+}
+
+// Unresolved copies, newest first
+pub fn conflict_copies(conn: &Connection) -> Result<Vec<ConflictCopy>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, entity_type, original_id, title, device, created_at
+             FROM conflict_copies ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_copy).map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn trash(conn: &Connection, copy: &ConflictCopy, now: &DateTime<Utc>) -> Result<(), String> {
+    if copy.kind == EntityKind::Event {
+        conn.execute(
+            "UPDATE events SET deleted_at = ?1 WHERE id = ?2",
+            params![timestamps::format(now), copy.id],
+        )
+        .map_err(|e| e.to_string())?;
+    } else {
+        trash::trash_note(conn, &copy.id, &timestamps::format(now))?;
+        search_index::note_removed(conn, &copy.id);
+    }
+    Ok(())
+}
+
+// The copy's version goes into the original under its own title, keeping
+// the original's id and creation time
+fn take_copy(conn: &Connection, copy: &ConflictCopy, now: &DateTime<Utc>) -> Result<(), String> {
+    match copy.kind {
+        EntityKind::Event => {
+            let (Some(version), Some(original)) = (
+                load_event(conn, &copy.id)?,
+                load_event(conn, &copy.original_id)?,
+            ) else {
+                return Err("Conflicted event not found".into());
+            };
+            write_event(
+                conn,
+                &Event {
+                    id: original.id,
+                    title: copy.title.clone(),
+                    created_at: original.created_at,
+                    updated_at: *now,
+                    deleted_at: None,
+                    ..version
+                },
+            )
+        }
+        _ => {
+            let (Some(version), Some(original)) = (
+                load_note(conn, &copy.id)?,
+                load_note(conn, &copy.original_id)?,
+            ) else {
+                return Err("Conflicted note not found".into());
+            };
+            write_note(
+                conn,
+                &Note {
+                    id: original.id,
+                    title: copy.title.clone(),
+                    created_at: original.created_at,
+                    updated_at: *now,
+                    deleted_at: None,
+                    published_url: original.published_url,
+                    ..version
+                },
+            )
+        }
+    }
+}
+
+pub fn resolve(
+    conn: &Connection,
+    id: &str,
+    resolution: ConflictResolution,
+    now: &DateTime<Utc>,
+) -> Result<(), String> {
+    let copy = conn
+        .query_row(
+            "SELECT id, entity_type, original_id, title, device, created_at
+             FROM conflict_copies WHERE id = ?1",
+            params![id],
+            row_to_copy,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Conflict copy {} not found", id))?;
+
+    match resolution {
+        ConflictResolution::Original => trash(conn, &copy, now)?,
+        ConflictResolution::Copy => {
+            take_copy(conn, &copy, now)?;
+            trash(conn, &copy, now)?;
+        }
+        ConflictResolution::Both => {}
+    }
+    conn.execute("DELETE FROM conflict_copies WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
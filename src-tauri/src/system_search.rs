@@ -0,0 +1,255 @@
+use crate::background;
+use crate::db::Database;
+use crate::deep_links;
+use crate::export;
+use crate::models::{BackgroundSubsystem, SystemSearchSettings};
+use crate::render;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+pub const SYSTEM_SEARCH_SETTING: &str = "system_search";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Notes written per pass, so a first index of a large vault doesn't hold the
+// database for long
+const BATCH: i64 = 200;
+
+pub fn load_settings(conn: &Connection) -> Result<SystemSearchSettings, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![SYSTEM_SEARCH_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        None => Ok(SystemSearchSettings { enabled: false }),
+    }
+}
+
+pub fn save_settings(conn: &Connection, settings: &SystemSearchSettings) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            SYSTEM_SEARCH_SETTING,
+            serde_json::to_string(settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Somewhere the system indexer already looks: the metadata cache Spotlight
+// reads on macOS, and Documents, which Windows Search indexes by default.
+// The directory belongs to the app and is removed when indexing is turned off.
+pub fn directory(app: &AppHandle) -> Result<PathBuf, String> {
+    let path = app.path();
+    let directory = if cfg!(target_os = "macos") {
+        path.home_dir()
+            .map(|home| home.join("Library/Caches/Metadata/com.voyena.app"))
+    } else if cfg!(windows) {
+        path.document_dir()
+            .map(|documents| documents.join("Voyena Search"))
+    } else {
+        path.app_data_dir().map(|data| data.join("search"))
+    };
+    directory.map_err(|e| e.to_string())
+}
+
+// A page per note that the indexer reads for its title, tags and text and
+// that opens the note when chosen from the results. The text is escaped
+// rather than rendered so nothing in a note runs when the page is opened.
+fn page(note_id: &str, title: &str, content: &str, tags: &[String]) -> String {
+    let url = deep_links::note_url(note_id);
+    let body: String = render::content_to_plain_text(content)
+        .split("\n\n")
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| format!("<p>{}</p>\n", render::escape_html(p.trim())))
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta http-equiv=\"refresh\" content=\"0; url={url}\">\n<meta name=\"keywords\" content=\"{tags}\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}<p><a href=\"{url}\">Open in Voyena</a></p>\n</body>\n</html>\n",
+        url = url,
+        tags = render::escape_html(&tags.join(", ")),
+        title = render::escape_html(title),
+        body = body,
+    )
+}
+
+// A note whose page is out of date; `page` is None when it should go
+struct Change {
+    note_id: String,
+    updated_at: String,
+    file_name: String,
+    page: Option<String>,
+}
+
+fn pending(conn: &Connection) -> Result<Vec<Change>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.title, n.content, n.tags, n.updated_at, n.deleted_at IS NOT NULL
+             FROM notes n LEFT JOIN system_search_files f ON f.note_id = n.id
+             WHERE (n.deleted_at IS NULL AND (f.updated_at IS NULL OR f.updated_at != n.updated_at))
+                OR (n.deleted_at IS NOT NULL AND f.note_id IS NOT NULL)
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut changes: Vec<Change> = stmt
+        .query_map(params![BATCH], |row| {
+            let note_id: String = row.get(0)?;
+            let title: String = row.get(1)?;
+            let content: String = row.get(2)?;
+            let tags: String = row.get(3)?;
+            let trashed: bool = row.get(5)?;
+            let tags: Vec<String> = serde_json::from_str(&tags).unwrap_or_default();
+            Ok(Change {
+                file_name: export::file_name(&title, "html"),
+                page: (!trashed).then(|| page(&note_id, &title, &content, &tags)),
+                updated_at: row.get(4)?,
+                note_id,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Notes deleted for good
+    let mut stmt = conn
+        .prepare(
+            "SELECT note_id FROM system_search_files WHERE note_id NOT IN (SELECT id FROM notes)",
+        )
+        .map_err(|e| e.to_string())?;
+    let gone = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok());
+    changes.extend(gone.map(|note_id| Change {
+        note_id,
+        updated_at: String::new(),
+        file_name: String::new(),
+        page: None,
+    }));
+
+    Ok(changes)
+}
+
+// Each note gets a directory named by its id holding one page named by its
+// title, so a rename replaces the page rather than leaving the old one
+fn write(directory: &Path, change: &Change) -> Result<(), String> {
+    let note_dir = directory.join(&change.note_id);
+    match fs::remove_dir_all(&note_dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.to_string()),
+        _ => {}
+    }
+    if let Some(page) = &change.page {
+        fs::create_dir_all(&note_dir).map_err(|e| e.to_string())?;
+        export::write_atomically(&note_dir.join(&change.file_name), |mut file| {
+            file.write_all(page.as_bytes()).map_err(|e| e.to_string())
+        })?;
+    }
+    Ok(())
+}
+
+fn record(conn: &Connection, change: &Change) -> Result<(), String> {
+    match change.page {
+        Some(_) => conn.execute(
+            "INSERT OR REPLACE INTO system_search_files (note_id, updated_at) VALUES (?1, ?2)",
+            params![change.note_id, change.updated_at],
+        ),
+        None => conn.execute(
+            "DELETE FROM system_search_files WHERE note_id = ?1",
+            params![change.note_id],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Brings one batch of pages up to date; returns how many it wrote
+fn pass(app: &AppHandle, directory: &Path) -> Result<usize, String> {
+    let db = app.state::<Database>();
+    let changes = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        pending(&conn)?
+    };
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    // A page that fails to write is left unrecorded and retried next pass
+    let written: Vec<&Change> = changes
+        .iter()
+        .filter(|change| match write(directory, change) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Failed to index note {}: {}", change.note_id, e);
+                false
+            }
+        })
+        .collect();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    for change in &written {
+        record(&conn, change)?;
+    }
+    Ok(written.len())
+}
+
+// Takes every page out of the index once indexing is turned off
+fn clear(app: &AppHandle, directory: &Path) -> Result<(), String> {
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let indexed: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM system_search_files)",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !indexed {
+        return Ok(());
+    }
+
+    match fs::remove_dir_all(directory) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.to_string()),
+        _ => {}
+    }
+    conn.execute("DELETE FROM system_search_files", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn run(app: &AppHandle) -> Result<(), String> {
+    let directory = directory(app)?;
+    let settings = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        load_settings(&conn)?
+    };
+    if !settings.enabled {
+        return clear(app, &directory);
+    }
+
+    // Keep going while there's a backlog, e.g. the first index
+    while pass(app, &directory)? as i64 >= BATCH {}
+    Ok(())
+}
+
+pub fn spawn_indexer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            background::wait_while_paused(&app, BackgroundSubsystem::SystemSearch).await;
+            if let Err(e) = run(&app) {
+                log::warn!("System search indexing failed: {}", e);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
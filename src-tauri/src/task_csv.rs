@@ -0,0 +1,687 @@
+use crate::commands::{insert_event, new_event};
+use crate::date_parse;
+use crate::db::Database;
+use crate::formats::{Format, Importer};
+use crate::jobs::JobContext;
+use crate::locale;
+use crate::models::{
+    DateOrder, EventCreate, EventStatus, EventType, FormatTarget, Priority, ProjectCreate,
+    ProjectItemKind, RecurrenceRule, TimeMode,
+};
+use crate::projects;
+use crate::recurrence;
+use crate::timestamps;
+use crate::today;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+// Todoist backup: a zip with one CSV per project, named after the project,
+// or any of those CSVs on their own. Sections become tags, labels written
+// into the task (`@errands`) become tags too, and comments are added to the
+// task's notes. Due strings are English as typed in Todoist; recurring ones
+// are translated to a rule, and anything that can't be is kept in the notes.
+pub struct Todoist;
+
+impl Importer for Todoist {
+    fn format(&self) -> Format {
+        Format {
+            id: "todoist",
+            name: "Todoist backup",
+            target: FormatTarget::File,
+            extensions: &["zip", "csv"],
+            preview: false,
+        }
+    }
+
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        _folder_id: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        let day_first = day_first(ctx)?;
+        let today = Local::now().date_naive();
+        let mut tasks = Vec::new();
+        for path in paths {
+            match read_todoist(path, today, day_first) {
+                Ok(read) => tasks.extend(read),
+                Err(e) => ctx.item_failed(&file_name(path), &e),
+            }
+        }
+        import_tasks(ctx, &tasks)?;
+        Ok(None)
+    }
+}
+
+// TickTick backup: one CSV of every task, after a few lines about the
+// export. Lists become projects; tags, priorities, start and due dates,
+// repeat rules and completion are kept. Notes (kind NOTE) aren't tasks and
+// are left out.
+pub struct TickTick;
+
+impl Importer for TickTick {
+    fn format(&self) -> Format {
+        Format {
+            id: "ticktick",
+            name: "TickTick backup",
+            target: FormatTarget::File,
+            extensions: &["csv"],
+            preview: false,
+        }
+    }
+
+    fn import(
+        &self,
+        ctx: &JobContext,
+        paths: &[PathBuf],
+        _folder_id: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        let mut tasks = Vec::new();
+        for path in paths {
+            let read = File::open(path)
+                .map_err(|e| e.to_string())
+                .and_then(read_ticktick);
+            match read {
+                Ok(read) => tasks.extend(read),
+                Err(e) => ctx.item_failed(&file_name(path), &e),
+            }
+        }
+        import_tasks(ctx, &tasks)?;
+        Ok(None)
+    }
+}
+
+// A task as read from a backup, before it's stored
+#[derive(Default)]
+struct Task {
+    title: String,
+    notes: Vec<String>,
+    project: Option<String>,
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    due: Option<Due>,
+    end: Option<DateTime<Utc>>,
+    rule: Option<RecurrenceRule>,
+    completed: bool,
+    created_at: Option<DateTime<Utc>>,
+}
+
+struct Due {
+    start: DateTime<Utc>,
+    all_day: bool,
+}
+
+impl Due {
+    fn local(date: NaiveDate, time: Option<NaiveTime>) -> Option<Due> {
+        let Some(time) = time else {
+            return Some(Due {
+                start: locale::day_start_utc(date),
+                all_day: true,
+            });
+        };
+        let start = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+        Some(Due {
+            start: start.with_timezone(&Utc),
+            all_day: false,
+        })
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn day_first(ctx: &JobContext) -> Result<bool, String> {
+    let db = ctx.app().state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(locale::load(&conn).date_order == DateOrder::Dmy)
+}
+
+// ============ Todoist ============
+
+fn read_todoist(path: &Path, today: NaiveDate, day_first: bool) -> Result<Vec<Task>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let is_zip = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    if !is_zip {
+        return read_todoist_csv(file, &project_name(path), today, day_first);
+    }
+
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut tasks = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        let Some(entry_path) = entry.enclosed_name() else {
+            continue;
+        };
+        let is_csv = entry_path.extension().is_some_and(|e| e == "csv");
+        if !entry.is_file() || !is_csv {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        let project = project_name(&entry_path);
+        tasks.extend(read_todoist_csv(
+            bytes.as_slice(),
+            &project,
+            today,
+            day_first,
+        )?);
+    }
+    if tasks.is_empty() {
+        return Err("No Todoist projects in this backup".to_string());
+    }
+    Ok(tasks)
+}
+
+// "Groceries [2203306141].csv" holds the Groceries project
+fn project_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match stem.rsplit_once(" [") {
+        Some((name, id)) if id.ends_with(']') => name,
+        _ => stem.as_str(),
+    };
+    match name.trim() {
+        "" => "Todoist".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// `@label` in a task's content
+fn label() -> &'static Regex {
+    static LABEL: OnceLock<Regex> = OnceLock::new();
+    LABEL.get_or_init(|| Regex::new(r"(?:^|\s)@([\w\-]+)").expect("valid label regex"))
+}
+
+// Rows are tasks, sections and comments in the order Todoist shows them; a
+// section applies to the tasks below it, a comment to the task above it
+fn read_todoist_csv(
+    reader: impl Read,
+    project: &str,
+    today: NaiveDate,
+    day_first: bool,
+) -> Result<Vec<Task>, String> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv.headers().map_err(|e| e.to_string())?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let (Some(kind), Some(content)) = (column("TYPE"), column("CONTENT")) else {
+        return Err("Not a Todoist CSV file".to_string());
+    };
+    let description = column("DESCRIPTION");
+    let priority = column("PRIORITY");
+    let date = column("DATE");
+
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut section: Option<String> = None;
+    for record in csv.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        let text = field(Some(content));
+        match field(Some(kind)) {
+            "section" => section = Some(text.to_string()).filter(|s| !s.is_empty()),
+            "note" if !text.is_empty() => {
+                if let Some(task) = tasks.last_mut() {
+                    task.notes.push(text.to_string());
+                }
+            }
+            "task" => {
+                let mut tags: Vec<String> = section.iter().cloned().collect();
+                for found in label().captures_iter(text) {
+                    tags.push(found[1].to_string());
+                }
+                let title = label().replace_all(text, "").trim().to_string();
+                let mut task = Task {
+                    title: if title.is_empty() {
+                        "Untitled".to_string()
+                    } else {
+                        title
+                    },
+                    notes: Some(field(description).to_string())
+                        .filter(|d| !d.is_empty())
+                        .into_iter()
+                        .collect(),
+                    project: Some(project.to_string()),
+                    tags,
+                    // 4 is p1, the most urgent; 1 is no priority
+                    priority: match field(priority) {
+                        "4" => Some(Priority::High),
+                        "3" => Some(Priority::Medium),
+                        "2" => Some(Priority::Low),
+                        _ => None,
+                    },
+                    ..Task::default()
+                };
+                todoist_due(&mut task, field(date), today, day_first);
+                tasks.push(task);
+            }
+            _ => {}
+        }
+    }
+    Ok(tasks)
+}
+
+// A due string becomes the task's due date and, when it repeats, its rule
+fn todoist_due(task: &mut Task, text: &str, today: NaiveDate, day_first: bool) {
+    if text.is_empty() {
+        return;
+    }
+    let reference = today.and_time(NaiveTime::MIN);
+    let repeat = todoist_repeat(text, reference, day_first)
+        .and_then(|repeat| Some((recurrence::from_rrule(&repeat.rrule)?, repeat)));
+    if let Some((rule, repeat)) = repeat {
+        task.due = Due::local(repeat.start.unwrap_or(today), repeat.time);
+        task.rule = Some(rule);
+    } else if let Some(resolved) = date_parse::resolve(text, reference, day_first) {
+        task.due = Due::local(resolved.date, resolved.time);
+    } else {
+        task.notes.push(format!("Due: {}", text));
+    }
+}
+
+// A recurring due string as an RRULE, with the first date and the time of
+// day when it gives them
+struct Repeat {
+    rrule: String,
+    start: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+}
+
+const WEEKDAY_CODES: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+const WEEKDAYS: &str = "MO,TU,WE,TH,FR";
+const WEEKEND: &str = "SA,SU";
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// "every monday", "every other week", "every 3 days starting jan 5", "every
+// weekday at 9am", "every 15th until dec 31", "every! 2 months", "daily".
+// None when the string doesn't repeat, or repeats in a way this doesn't
+// know.
+fn todoist_repeat(text: &str, reference: NaiveDateTime, day_first: bool) -> Option<Repeat> {
+    let lowered = text.to_lowercase().replace(',', " ");
+    let words: Vec<&str> = lowered.split_whitespace().collect();
+    let resolve = |words: &[&str]| date_parse::resolve(&words.join(" "), reference, day_first);
+
+    // "... starting jan 5 until jun 1"
+    let clause = |keys: &[&str]| words.iter().position(|w| keys.contains(w));
+    let starting = clause(&["starting", "from"]);
+    let until = clause(&["until", "ending"]);
+    let body_end = starting
+        .into_iter()
+        .chain(until)
+        .min()
+        .unwrap_or(words.len());
+    let clause_words = |at: Option<usize>| {
+        let from = at? + 1;
+        let to = [starting, until]
+            .into_iter()
+            .flatten()
+            .filter(|i| *i >= from)
+            .min()
+            .unwrap_or(words.len());
+        Some(&words[from..to])
+    };
+
+    let mut body = &words[..body_end];
+    let mut interval = 1;
+    match body.first().copied()? {
+        "every" | "every!" | "ev" | "ev!" | "after" => body = &body[1..],
+        "daily" | "weekly" | "monthly" | "yearly" => {}
+        _ => return None,
+    }
+
+    // A time of day at the end: "at 9am", "9am"
+    let mut time = None;
+    if let Some(at) = body.iter().position(|w| *w == "at") {
+        time = Some(resolve(&body[at..])?.time?);
+        body = &body[..at];
+    } else if let Some(last) = body.last() {
+        if let Some(t) = resolve(&[*last]).and_then(|r| r.time) {
+            time = Some(t);
+            body = &body[..body.len() - 1];
+        }
+    }
+
+    let mut start = None;
+    if let Some(first) = body.first() {
+        if *first == "other" {
+            interval = 2;
+            body = &body[1..];
+        } else if body.len() > 1 {
+            if let Ok(n) = first.parse::<u32>() {
+                interval = n;
+                body = &body[1..];
+            }
+        }
+    }
+
+    let (frequency, by_day) = match body {
+        [] => return None,
+        [unit] if unit_frequency(unit).is_some() => (unit_frequency(unit)?, None),
+        [unit] if unit.trim_end_matches('s') == "weekday" || unit.starts_with("workday") => {
+            ("WEEKLY", Some(WEEKDAYS.to_string()))
+        }
+        [unit] if unit.trim_end_matches('s') == "weekend" => ("WEEKLY", Some(WEEKEND.to_string())),
+        [day] if day_of_month(day).is_some() => {
+            let day = day_of_month(day)?;
+            start = (0..366)
+                .map(|n| reference.date() + Duration::days(n))
+                .find(|date| date.day() == day);
+            ("MONTHLY", None)
+        }
+        days if days
+            .iter()
+            .all(|w| *w == "and" || weekday_code(w).is_some()) =>
+        {
+            let codes: Vec<&str> = days.iter().filter_map(|w| weekday_code(w)).collect();
+            start = (0..7)
+                .map(|n| reference.date() + Duration::days(n))
+                .find(|date| {
+                    codes.contains(&WEEKDAY_CODES[date.weekday().num_days_from_monday() as usize])
+                });
+            ("WEEKLY", Some(codes.join(",")))
+        }
+        // A date in the year: "every jan 5"
+        date if date.iter().any(|w| MONTHS.iter().any(|m| w.starts_with(m))) => {
+            start = Some(resolve(date).filter(|r| r.time.is_none())?.date);
+            ("YEARLY", None)
+        }
+        _ => return None,
+    };
+
+    let mut rrule = format!("FREQ={};INTERVAL={}", frequency, interval);
+    if let Some(by_day) = by_day {
+        rrule.push_str(&format!(";BYDAY={}", by_day));
+    }
+    if let Some(words) = clause_words(until) {
+        let until = resolve(words)?.date;
+        rrule.push_str(&format!(";UNTIL={}", until.format("%Y%m%d")));
+    }
+    if let Some(words) = clause_words(starting) {
+        start = Some(resolve(words)?.date);
+    }
+    Some(Repeat { rrule, start, time })
+}
+
+fn unit_frequency(word: &str) -> Option<&'static str> {
+    match word.trim_end_matches('s') {
+        "day" | "daily" => Some("DAILY"),
+        "week" | "weekly" => Some("WEEKLY"),
+        "month" | "monthly" => Some("MONTHLY"),
+        "year" | "yearly" | "annually" => Some("YEARLY"),
+        _ => None,
+    }
+}
+
+// "mon", "tuesday", "thurs", also plural ("mondays")
+fn weekday_code(word: &str) -> Option<&'static str> {
+    let index = match word.trim_end_matches('s') {
+        "mon" | "monday" => 0,
+        "tue" | "tuesday" => 1,
+        "wed" | "wednesday" => 2,
+        "thu" | "thur" | "thursday" => 3,
+        "fri" | "friday" => 4,
+        "sat" | "saturday" => 5,
+        "sun" | "sunday" => 6,
+        _ => return None,
+    };
+    Some(WEEKDAY_CODES[index])
+}
+
+// "15", "15th", "1st"
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+// ============ TickTick ============
+
+fn read_ticktick(reader: impl Read) -> Result<Vec<Task>, String> {
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = csv.records();
+
+    // The export's date, version and a legend for Status come first
+    let headers = loop {
+        let Some(record) = records.next() else {
+            return Err("Not a TickTick backup".to_string());
+        };
+        let record = record.map_err(|e| e.to_string())?;
+        if record.get(0).map(str::trim) == Some("Folder Name") {
+            break record;
+        }
+    };
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let Some(title) = column("Title") else {
+        return Err("Not a TickTick backup".to_string());
+    };
+    let list = column("List Name");
+    let kind = column("Kind");
+    let tags = column("Tags");
+    let content = column("Content");
+    let start_date = column("Start Date");
+    let due_date = column("Due Date");
+    let repeat = column("Repeat");
+    let priority = column("Priority");
+    let status = column("Status");
+    let created = column("Created Time");
+    let all_day = column("Is All Day");
+
+    let mut tasks = Vec::new();
+    for record in records {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        if field(kind).eq_ignore_ascii_case("note") {
+            continue;
+        }
+
+        let mut task = Task {
+            title: match field(Some(title)) {
+                "" => "Untitled".to_string(),
+                title => title.to_string(),
+            },
+            notes: Some(field(content).to_string())
+                .filter(|c| !c.is_empty())
+                .into_iter()
+                .collect(),
+            project: Some(field(list).to_string()).filter(|l| !l.is_empty()),
+            tags: field(tags)
+                .split(',')
+                .map(|t| t.trim().trim_start_matches('#').to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            priority: match field(priority) {
+                "5" => Some(Priority::High),
+                "3" => Some(Priority::Medium),
+                "1" => Some(Priority::Low),
+                _ => None,
+            },
+            // 1 is completed, 2 completed and archived
+            completed: matches!(field(status), "1" | "2"),
+            created_at: ticktick_time(field(created)),
+            ..Task::default()
+        };
+
+        let start = ticktick_time(field(start_date));
+        let due = ticktick_time(field(due_date));
+        if let Some(at) = start.or(due) {
+            if field(all_day).eq_ignore_ascii_case("true") {
+                task.due = Due::local(at.with_timezone(&Local).date_naive(), None);
+            } else {
+                task.due = Some(Due {
+                    start: at,
+                    all_day: false,
+                });
+                task.end = due.filter(|due| start.is_some_and(|start| *due > start));
+            }
+        }
+        let rule = field(repeat);
+        if !rule.is_empty() {
+            match recurrence::from_rrule(rule) {
+                Some(rule) => task.rule = Some(rule),
+                None => task.notes.push(format!("Repeats: {}", rule)),
+            }
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+// "2024-01-05T09:00:00+0000"
+fn ticktick_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%z")
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+// ============ Importing ============
+
+fn import_tasks(ctx: &JobContext, tasks: &[Task]) -> Result<(), String> {
+    let db = ctx.app().state::<Database>();
+    let total = tasks.len();
+    for (index, task) in tasks.iter().enumerate() {
+        ctx.checkpoint()?;
+        let imported = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            import_task(&tx, task)
+                .and_then(|created| tx.commit().map(|_| created).map_err(|e| e.to_string()))
+        };
+        match imported {
+            Ok(true) => ctx.created(),
+            Ok(false) => ctx.skipped(),
+            Err(e) => ctx.item_failed(&task.title, &e),
+        }
+        ctx.progress(index + 1, total, &task.title);
+    }
+    Ok(())
+}
+
+// Returns false when the task is already there from an earlier import: a
+// task with the same title in the same project, created at the same time
+// when the backup says when
+fn import_task(conn: &Connection, task: &Task) -> Result<bool, String> {
+    let now = timestamps::now();
+    let project_id = match &task.project {
+        Some(name) => Some(match projects::named(conn, name)? {
+            Some(project) => project.id,
+            None => {
+                let data = ProjectCreate {
+                    name: name.clone(),
+                    description: None,
+                    color: None,
+                    due_date: None,
+                };
+                projects::insert(conn, data, &now)?.id
+            }
+        }),
+        None => None,
+    };
+
+    let exists: bool = conn
+        .query_row(
+            &format!(
+                "SELECT EXISTS(SELECT 1 FROM events
+                 WHERE deleted_at IS NULL AND {} AND title = ?2
+                   AND (?1 IS NULL OR {})
+                   AND (?3 IS NULL OR created_at = ?3))",
+                today::IS_TASK,
+                projects::linked(ProjectItemKind::Event)
+            ),
+            params![
+                project_id,
+                task.title,
+                task.created_at.as_ref().map(timestamps::format)
+            ],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Ok(false);
+    }
+
+    let due = task.due.as_ref();
+    let all_day = due.is_some_and(|due| due.all_day);
+    let notes = task.notes.join("\n\n");
+    let mut event = new_event(EventCreate {
+        title: task.title.clone(),
+        description: None,
+        event_type: Some(EventType::Task),
+        start_time: due.map(|due| due.start),
+        end_time: task.end,
+        time_mode: Some(match due {
+            None => TimeMode::Todo,
+            Some(_) if all_day => TimeMode::AllDay,
+            Some(_) => TimeMode::AtTime,
+        }),
+        duration_minutes: None,
+        location: None,
+        category: None,
+        color: None,
+        priority: task.priority,
+        tags: Some(task.tags.clone()),
+        show_on_calendar: Some(due.is_some()),
+        is_all_day: Some(all_day),
+        is_recurring: None,
+        recurring_pattern: None,
+        reminders: None,
+        notes: Some(notes).filter(|notes| !notes.is_empty()),
+    });
+    if task.completed {
+        event.status = Some(EventStatus::Completed);
+    }
+    if let Some(created_at) = task.created_at {
+        event.created_at = created_at;
+        event.updated_at = created_at;
+    }
+    insert_event(conn, &event)?;
+
+    if let Some(rule) = &task.rule {
+        recurrence::save_rule(
+            conn,
+            &event.id,
+            Some(rule),
+            &timestamps::format(&event.updated_at),
+        )?;
+    }
+    if let Some(project_id) = project_id {
+        projects::link(
+            conn,
+            &project_id,
+            ProjectItemKind::Event,
+            &event.id,
+            &timestamps::format(&now),
+        )?;
+    }
+    Ok(true)
+}
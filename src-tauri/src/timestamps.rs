@@ -0,0 +1,67 @@
+use crate::validation;
+use chrono::{
+    DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, SubsecRound, TimeZone, Utc,
+};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ValueRef};
+use rusqlite::{Row, RowIndex};
+
+// Times are stored as RFC 3339 in UTC to the millisecond with a Z suffix,
+// e.g. 2024-05-01T07:30:00.000Z, so they order correctly as text
+pub fn format(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// The current time at the precision it's stored with, so what a command
+// returns matches what a later read gives back
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(3)
+}
+
+// RFC 3339 with any offset, or the offset-less local form older versions of
+// the calendar editor stored
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let naive = validation::parse_timestamp(value)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+// Reads a stored column, treating anything unparseable as unset
+pub fn column(value: Option<String>) -> Option<DateTime<Utc>> {
+    value.as_deref().and_then(parse)
+}
+
+// Record timestamps (created_at and the like) are only ever written by
+// `format`, so one that doesn't parse is an error rather than unset
+struct Stored(DateTime<Utc>);
+
+impl FromSql for Stored {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        parse(text)
+            .map(Stored)
+            .ok_or_else(|| FromSqlError::Other(format!("invalid timestamp {:?}", text).into()))
+    }
+}
+
+pub fn get<I: RowIndex>(row: &Row, index: I) -> rusqlite::Result<DateTime<Utc>> {
+    row.get::<_, Stored>(index).map(|stored| stored.0)
+}
+
+pub fn get_opt<I: RowIndex>(row: &Row, index: I) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<Stored>>(index)
+        .map(|stored| stored.map(|stored| stored.0))
+}
+
+// The user's wall-clock time at `time`
+pub fn local(time: &DateTime<Utc>) -> NaiveDateTime {
+    time.with_timezone(&Local).naive_local()
+}
+
+pub fn local_date(time: &DateTime<Utc>) -> NaiveDate {
+    local(time).date()
+}
@@ -0,0 +1,115 @@
+use crate::commands::{row_to_event, row_to_note};
+use crate::locale;
+use crate::models::{Event, Note, TodayView};
+use crate::queries::{EVENT_COLUMNS, NOTE_COLUMNS};
+use crate::recurrence;
+use crate::reminders;
+use crate::timestamps;
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params_from_iter, Connection};
+
+// NULL-safe, so their negations hold for events without a type or pattern
+pub const IS_TASK: &str = "(COALESCE(event_type, '') = 'task' OR time_mode = 'todo')";
+pub const IS_OPEN: &str = "(status IS NULL OR status IN ('pending', 'in_progress'))";
+const IS_RECURRING_TASK: &str =
+    "(is_recurring = 1 AND (COALESCE(event_type, '') = 'task' OR time_mode = 'todo'))";
+
+// Start times are stored with different offsets, or none, so SQL narrows
+// events down by the stored date a day either side and the local day is
+// checked here
+pub fn query_events(conn: &Connection, condition: &str, args: &[&str]) -> Result<Vec<Event>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM events WHERE deleted_at IS NULL AND {} ORDER BY start_time ASC",
+            EVENT_COLUMNS, condition
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(args), row_to_event)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn local_day(event: &Event) -> Option<NaiveDate> {
+    event.start_time.as_ref().map(timestamps::local_date)
+}
+
+fn notes_changed_since(conn: &Connection, since: &str) -> Result<Vec<Note>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM notes
+             WHERE deleted_at IS NULL AND updated_at >= ?1
+             ORDER BY updated_at DESC",
+            NOTE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([since], row_to_note)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub fn build(conn: &Connection, today: NaiveDate) -> Result<TodayView, String> {
+    let day = |offset: i64| {
+        (today + Duration::days(offset))
+            .format("%Y-%m-%d")
+            .to_string()
+    };
+    let (yesterday, tomorrow) = (day(-1), day(1));
+    let day_start = locale::day_start_utc(today);
+    let day_end = locale::day_start_utc(today + Duration::days(1));
+    let since = timestamps::format(&day_start);
+
+    let events = query_events(
+        conn,
+        &format!(
+            "show_on_calendar = 1 AND NOT {} AND substr(start_time, 1, 10) BETWEEN ?1 AND ?2",
+            IS_TASK
+        ),
+        &[&yesterday, &tomorrow],
+    )?
+    .into_iter()
+    .filter(|e| local_day(e) == Some(today))
+    .collect();
+
+    let tasks = query_events(
+        conn,
+        &format!(
+            "{} AND {} AND NOT {} AND substr(start_time, 1, 10) <= ?1",
+            IS_TASK, IS_OPEN, IS_RECURRING_TASK
+        ),
+        &[&tomorrow],
+    )?
+    .into_iter()
+    .filter(|e| local_day(e).is_some_and(|d| d <= today))
+    .collect();
+
+    // Recurring tasks with an occurrence today that hasn't been completed
+    let mut habits = Vec::new();
+    for event in query_events(
+        conn,
+        &format!(
+            "{} AND COALESCE(status, '') != 'cancelled'",
+            IS_RECURRING_TASK
+        ),
+        &[],
+    )? {
+        let due = match recurrence::rule(conn, &event)? {
+            Some(rule) => recurrence::occurs_on(&rule, recurrence::anchor(&event), today),
+            None => false,
+        };
+        if due && !recurrence::is_completed(conn, &event.id, today)? {
+            habits.push(event);
+        }
+    }
+
+    Ok(TodayView {
+        date: day(0),
+        events,
+        tasks,
+        habits,
+        notes: notes_changed_since(conn, &since)?,
+        reminders: reminders::firing_between(conn, day_start, day_end)?,
+    })
+}
@@ -0,0 +1,299 @@
+use crate::attachments;
+use crate::commands::{purge_brain_map, purge_event, purge_note, row_to_note};
+use crate::folders;
+use crate::models::{
+    DeletedNotePreview, Note, NoteRestore, PurgeReport, RestoreFolderAction, RestoreStatus,
+    TrashSettings,
+};
+use crate::queries;
+use crate::render;
+use crate::search_index;
+use crate::timestamps;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+pub const TRASH_SETTING: &str = "trash";
+
+pub fn load_settings(conn: &Connection) -> Result<TrashSettings, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![TRASH_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string()),
+        None => Ok(TrashSettings {
+            retention_days: None,
+        }),
+    }
+}
+
+pub fn save_settings(conn: &Connection, settings: &TrashSettings) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![
+            TRASH_SETTING,
+            serde_json::to_string(settings).map_err(|e| e.to_string())?,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn trashed_before(conn: &Connection, table: &str, cutoff: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id FROM {} WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            table
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![cutoff], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// "Work/Projects" for a folder nested in Work; None if it doesn't exist
+fn folder_path(conn: &Connection, folder_id: &str) -> Result<Option<String>, String> {
+    let names: Vec<String> = folders::ancestors(conn, folder_id)?
+        .into_iter()
+        .map(|folder| folder.name)
+        .collect();
+    Ok((!names.is_empty()).then(|| names.join("/")))
+}
+
+// Moves a note to the trash, noting which folder it was in
+pub fn trash_note(conn: &Connection, id: &str, now: &str) -> Result<(), String> {
+    let folder_id: Option<String> = conn
+        .query_row(
+            "SELECT folder_id FROM notes WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    let path = match folder_id {
+        Some(folder_id) => folder_path(conn, &folder_id)?,
+        None => None,
+    };
+    conn.execute(
+        "UPDATE notes SET deleted_at = ?1, deleted_folder_path = ?2 WHERE id = ?3",
+        params![now, path, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn load_trashed(conn: &Connection, id: &str) -> Result<(Note, Option<String>), String> {
+    conn.query_row(
+        &format!(
+            "SELECT {}, deleted_folder_path FROM notes WHERE id = ?1",
+            queries::NOTE_COLUMNS
+        ),
+        params![id],
+        |row| Ok((row_to_note(row)?, row.get("deleted_folder_path")?)),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Note not found".to_string())
+}
+
+// Characters of a trashed note's text shown in the trash. Only the start of
+// the content is read, with room for the markup around that much text.
+const SNIPPET_LEN: usize = 200;
+const SNIPPET_SOURCE_LEN: usize = 4000;
+
+pub fn preview_note(conn: &Connection, id: &str) -> Result<DeletedNotePreview, String> {
+    let (title, head, deleted_at, folder_id, recorded_path): (
+        String,
+        String,
+        Option<DateTime<Utc>>,
+        Option<String>,
+        Option<String>,
+    ) = conn
+        .query_row(
+            "SELECT title, substr(content, 1, ?2) AS head, deleted_at, folder_id,
+                    deleted_folder_path
+             FROM notes WHERE id = ?1",
+            params![id, SNIPPET_SOURCE_LEN as i64],
+            |row| {
+                Ok((
+                    row.get("title")?,
+                    row.get("head")?,
+                    timestamps::get_opt(row, "deleted_at")?,
+                    row.get("folder_id")?,
+                    row.get("deleted_folder_path")?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Note not found")?;
+    let deleted_at = deleted_at.ok_or("Note isn't in the trash")?;
+
+    // Notes trashed before the path was recorded still name their folder
+    let folder_path = match (recorded_path, folder_id) {
+        (Some(path), _) => Some(path),
+        (None, Some(folder_id)) => folder_path(conn, &folder_id)?,
+        (None, None) => None,
+    };
+
+    let text = render::content_to_plain_text(&head);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let snippet = if text.chars().count() > SNIPPET_LEN {
+        format!(
+            "{}…",
+            text.chars()
+                .take(SNIPPET_LEN)
+                .collect::<String>()
+                .trim_end()
+        )
+    } else {
+        text
+    };
+
+    Ok(DeletedNotePreview {
+        id: id.to_string(),
+        title,
+        snippet,
+        deleted_at,
+        folder_path,
+    })
+}
+
+// Takes a note out of the trash, back into the folder it was in. If that
+// folder has been deleted since, a folder at the same path is used when there
+// is one; otherwise nothing changes until `action` says what to do, so the
+// user is asked rather than finding the note at the top level.
+pub fn restore_note(
+    conn: &Connection,
+    id: &str,
+    action: Option<RestoreFolderAction>,
+    folder_id: Option<&str>,
+) -> Result<NoteRestore, String> {
+    let (mut note, folder_path) = load_trashed(conn, id)?;
+    if note.deleted_at.is_none() {
+        return Err("Note isn't in the trash".into());
+    }
+
+    let original = match note.folder_id.as_deref() {
+        Some(folder_id) if !folders::ancestors(conn, folder_id)?.is_empty() => {
+            Some(folder_id.to_string())
+        }
+        _ => match folder_path.as_deref() {
+            Some(path) => folders::resolve_path(conn, path, false)?.map(|f| f.id),
+            None => None,
+        },
+    };
+
+    let target = match action {
+        Some(RestoreFolderAction::Recreate) => match folder_path.as_deref() {
+            Some(path) => folders::resolve_path(conn, path, true)?.map(|f| f.id),
+            None => return Err("The note's original folder isn't known".into()),
+        },
+        Some(RestoreFolderAction::Root) => None,
+        Some(RestoreFolderAction::Folder) => {
+            let folder_id = folder_id.ok_or("Choose a folder to restore into")?;
+            if folders::ancestors(conn, folder_id)?.is_empty() {
+                return Err("Folder not found".into());
+            }
+            Some(folder_id.to_string())
+        }
+        None if original.is_none() && folder_path.is_some() => {
+            return Ok(NoteRestore {
+                status: RestoreStatus::FolderMissing,
+                note,
+                folder_path,
+            });
+        }
+        None => original,
+    };
+
+    note.folder_id = target;
+    note.deleted_at = None;
+    note.updated_at = timestamps::now();
+    conn.execute(
+        "UPDATE notes SET deleted_at = NULL, deleted_folder_path = NULL, folder_id = ?1,
+                          updated_at = ?2
+         WHERE id = ?3",
+        params![note.folder_id, timestamps::format(&note.updated_at), id],
+    )
+    .map_err(|e| e.to_string())?;
+    search_index::note_changed(conn, id);
+
+    Ok(NoteRestore {
+        status: RestoreStatus::Restored,
+        note,
+        folder_path,
+    })
+}
+
+// Revisions and attachments of a note, and the bytes of text it and its
+// revisions hold
+fn note_size(conn: &Connection, id: &str) -> Result<(i64, i64, i64), String> {
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM note_revisions WHERE note_id = ?1),
+                (SELECT COUNT(*) FROM attachments WHERE note_id = ?1),
+                (SELECT length(CAST(title AS BLOB)) + length(CAST(content AS BLOB))
+                 FROM notes WHERE id = ?1)
+                + (SELECT TOTAL(length(CAST(title AS BLOB)) + length(CAST(content AS BLOB)))
+                   FROM note_revisions WHERE note_id = ?1)",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, f64>(2)? as i64)),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Deletes for good whatever has been in the trash longer than
+// `older_than_days`. A brain map's nodes and connections trashed with it go
+// too. Each item is removed on its own, so a failure part way leaves the
+// earlier ones deleted and the rest still in the trash.
+pub fn purge(
+    conn: &mut Connection,
+    data_dir: &Path,
+    older_than_days: u32,
+) -> Result<PurgeReport, String> {
+    let cutoff = timestamps::format(&(Utc::now() - Duration::days(older_than_days as i64)));
+    let mut report = PurgeReport {
+        notes: 0,
+        events: 0,
+        brain_maps: 0,
+        revisions: 0,
+        attachments: 0,
+        bytes_reclaimed: 0,
+    };
+
+    for id in trashed_before(conn, "notes", &cutoff)? {
+        let (revisions, attachments, text_bytes) = note_size(conn, &id)?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let file_names = purge_note(&tx, &id)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        let file_bytes = attachments::release_blobs(conn, data_dir, &file_names)?;
+        report.notes += 1;
+        report.revisions += revisions;
+        report.attachments += attachments;
+        report.bytes_reclaimed += text_bytes + file_bytes;
+    }
+
+    for id in trashed_before(conn, "events", &cutoff)? {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        purge_event(&tx, &id)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        report.events += 1;
+    }
+
+    for id in trashed_before(conn, "brain_maps", &cutoff)? {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        purge_brain_map(&tx, &id)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        report.brain_maps += 1;
+    }
+
+    Ok(report)
+}
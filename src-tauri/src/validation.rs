@@ -0,0 +1,567 @@
+use crate::error::AppError;
+use crate::folders;
+use crate::models::*;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+const MAX_TITLE_LEN: usize = 500;
+const MAX_NAME_LEN: usize = 255;
+const MAX_LABEL_LEN: usize = 1000;
+const MAX_TAG_LEN: usize = 100;
+const MAX_ICON_ID_LEN: usize = 64;
+const MAX_GLYPH_LEN: usize = 16;
+const MAX_METADATA_VALUE_LEN: usize = 64 * 1024;
+const MAX_RECURRENCE_INTERVAL: u32 = 365;
+const MAX_COMMENT_LEN: usize = 10_000;
+const MAX_TRAVEL_MINUTES: i32 = 24 * 60;
+const MAX_RANKING_WEIGHT: f64 = 10.0;
+const MAX_TRIGGER_LEN: usize = 32;
+const MAX_SNIPPET_LEN: usize = 64 * 1024;
+const MAX_SCRIPT_LEN: usize = 64 * 1024;
+
+pub const RECURRING_PATTERNS: &[&str] = &["daily", "weekly", "monthly", "yearly"];
+pub const REMINDER_TYPES: &[&str] = &["notification", "email"];
+pub const CONNECTION_STYLES: &[&str] = &["solid", "dashed", "dotted", "curved"];
+pub const MAP_THEMES: &[&str] = &["default", "dark", "colorful", "minimal", "neon"];
+
+type Result = std::result::Result<(), AppError>;
+
+// ============ Field rules ============
+
+fn required(field: &str, value: &str, max_len: usize) -> Result {
+    if value.trim().is_empty() {
+        return Err(AppError::validation(field, "must not be empty"));
+    }
+    max_length(field, value, max_len)
+}
+
+fn max_length(field: &str, value: &str, max_len: usize) -> Result {
+    if value.chars().count() > max_len {
+        return Err(AppError::validation(
+            field,
+            format!("must be at most {} characters", max_len),
+        ));
+    }
+    Ok(())
+}
+
+fn optional_max_length(field: &str, value: Option<&String>, max_len: usize) -> Result {
+    value.map_or(Ok(()), |v| max_length(field, v, max_len))
+}
+
+fn one_of(field: &str, value: Option<&String>, allowed: &[&str]) -> Result {
+    match value {
+        Some(v) if !allowed.contains(&v.as_str()) => Err(AppError::validation(
+            field,
+            format!("must be one of: {}", allowed.join(", ")),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// #rgb, #rrggbb or #rrggbbaa
+fn hex_color(field: &str, value: Option<&String>) -> Result {
+    let Some(color) = value else {
+        return Ok(());
+    };
+    let valid = color
+        .strip_prefix('#')
+        .filter(|hex| matches!(hex.len(), 3 | 6 | 8))
+        .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(AppError::validation(
+            field,
+            "must be a hex color like #3b82f6",
+        ));
+    }
+    Ok(())
+}
+
+// Catalog ids like "briefcase" or "my-icon": lowercase letters, digits and
+// dashes, starting with a letter
+fn icon_id(field: &str, value: Option<&String>) -> Result {
+    let Some(id) = value else {
+        return Ok(());
+    };
+    let valid = id.starts_with(|c: char| c.is_ascii_lowercase())
+        && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(AppError::validation(
+            field,
+            "must be an icon id like \"briefcase\"",
+        ));
+    }
+    max_length(field, id, MAX_ICON_ID_LEN)
+}
+
+fn tags(field: &str, value: Option<&Vec<String>>) -> Result {
+    for tag in value.into_iter().flatten() {
+        required(field, tag, MAX_TAG_LEN)?;
+    }
+    Ok(())
+}
+
+// RFC 3339, or the offset-less local form the calendar editor sends
+// ("2024-05-01T09:30:00").
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .ok()
+}
+
+fn date(field: &str, value: Option<&String>) -> Result {
+    match value {
+        Some(v) if NaiveDate::parse_from_str(v, "%Y-%m-%d").is_err() => {
+            Err(AppError::validation(field, "must be a date like 2024-05-01"))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Event times arrive as RFC 3339 and are parsed by serde, so only the
+// order is left to check
+fn time_range(start: Option<&DateTime<Utc>>, end: Option<&DateTime<Utc>>) -> Result {
+    let (Some(start), Some(end)) = (start, end) else {
+        return Ok(());
+    };
+    if end < start {
+        return Err(AppError::validation(
+            "end_time",
+            "must not be before start_time",
+        ));
+    }
+    Ok(())
+}
+
+fn duration(value: Option<i32>) -> Result {
+    match value {
+        Some(minutes) if minutes < 0 => Err(AppError::validation(
+            "duration_minutes",
+            "must not be negative",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn reminders(value: Option<&Vec<EventReminder>>) -> Result {
+    for reminder in value.into_iter().flatten() {
+        if reminder.minutes_before < 0 {
+            return Err(AppError::validation(
+                "reminders",
+                "minutes_before must not be negative",
+            ));
+        }
+        one_of("reminders", Some(&reminder.reminder_type), REMINDER_TYPES)?;
+    }
+    Ok(())
+}
+
+fn zoom(value: Option<f64>) -> Result {
+    match value {
+        Some(z) if !(z.is_finite() && z > 0.0) => Err(AppError::validation(
+            "viewport_zoom",
+            "must be a positive number",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn coordinate(field: &str, value: Option<f64>) -> Result {
+    match value {
+        Some(v) if !v.is_finite() => Err(AppError::validation(field, "must be a finite number")),
+        _ => Ok(()),
+    }
+}
+
+// ============ Notes & Folders ============
+
+pub fn note_create(data: &NoteCreate) -> Result {
+    optional_max_length("title", data.title.as_ref(), MAX_TITLE_LEN)?;
+    tags("tags", data.tags.as_ref())?;
+    aliases(data.aliases.as_ref())
+}
+
+pub fn note_update(data: &NoteUpdate) -> Result {
+    optional_max_length("title", data.title.as_ref(), MAX_TITLE_LEN)?;
+    tags("tags", data.tags.as_ref())?;
+    aliases(data.aliases.as_ref())
+}
+
+// Aliases are link targets, so they can't hold what ends or splits a wikilink
+fn aliases(value: Option<&Vec<String>>) -> Result {
+    for alias in value.into_iter().flatten() {
+        required("aliases", alias, MAX_TITLE_LEN)?;
+        if alias.contains(['[', ']', '|', '#']) {
+            return Err(AppError::validation(
+                "aliases",
+                "can't contain brackets, \"|\" or \"#\"",
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn folder_create(data: &FolderCreate) -> Result {
+    required("name", &data.name, MAX_NAME_LEN)?;
+    hex_color("color", data.color.as_ref())?;
+    icon_id("icon", data.icon.as_ref())
+}
+
+pub fn folder_update(data: &FolderUpdate) -> Result {
+    if let Some(name) = &data.name {
+        required("name", name, MAX_NAME_LEN)?;
+    }
+    hex_color("color", data.color.value())?;
+    icon_id("icon", data.icon.value())
+}
+
+// Every segment of "Work/Projects/Alpha" must be a valid folder name
+pub fn folder_path(path: &str) -> Result {
+    let segments = folders::path_segments(path);
+    if segments.is_empty() {
+        return Err(AppError::validation("path", "must name at least one folder"));
+    }
+    for segment in segments {
+        max_length("path", segment, MAX_NAME_LEN)?;
+    }
+    Ok(())
+}
+
+pub fn project_create(data: &ProjectCreate) -> Result {
+    required("name", &data.name, MAX_NAME_LEN)?;
+    hex_color("color", data.color.as_ref())?;
+    date("due_date", data.due_date.as_ref())
+}
+
+pub fn project_update(data: &ProjectUpdate) -> Result {
+    if let Some(name) = &data.name {
+        required("name", name, MAX_NAME_LEN)?;
+    }
+    hex_color("color", data.color.as_ref())?;
+    date("due_date", data.due_date.as_ref())
+}
+
+fn goal_target(metric: Option<GoalMetric>, target: Option<u32>) -> Result {
+    if target == Some(0) {
+        return Err(AppError::validation("target", "must be at least 1"));
+    }
+    if metric == Some(GoalMetric::Count) && target.is_none() {
+        return Err(AppError::validation("target", "is required for count goals"));
+    }
+    Ok(())
+}
+
+pub fn goal_create(data: &GoalCreate) -> Result {
+    required("title", &data.title, MAX_TITLE_LEN)?;
+    goal_target(data.metric, data.target)?;
+    date("due_date", data.due_date.as_ref())
+}
+
+// `current` is checked so a goal can't be switched to `count` without a target
+pub fn goal_update(current: &Goal, data: &GoalUpdate) -> Result {
+    if let Some(title) = &data.title {
+        required("title", title, MAX_TITLE_LEN)?;
+    }
+    goal_target(
+        Some(data.metric.unwrap_or(current.metric)),
+        data.target.or(current.target),
+    )?;
+    date("due_date", data.due_date.as_ref())
+}
+
+pub fn note_comment_body(body: &str) -> Result {
+    required("body", body, MAX_COMMENT_LEN)
+}
+
+pub fn palette_color(name: &str, hex: &str) -> Result {
+    required("name", name, MAX_NAME_LEN)?;
+    hex_color("hex", Some(&hex.to_string()))
+}
+
+pub fn catalog_icon(id: &str, label: &str, glyph: &str) -> Result {
+    icon_id("id", Some(&id.to_string()))?;
+    required("label", label, MAX_NAME_LEN)?;
+    required("glyph", glyph, MAX_GLYPH_LEN)
+}
+
+pub fn smart_folder_create(data: &SmartFolderCreate) -> Result {
+    required("name", &data.name, MAX_NAME_LEN)?;
+    required("query", &data.query, MAX_LABEL_LEN)
+}
+
+pub fn smart_folder_update(data: &SmartFolderUpdate) -> Result {
+    if let Some(name) = &data.name {
+        required("name", name, MAX_NAME_LEN)?;
+    }
+    if let Some(query) = &data.query {
+        required("query", query, MAX_LABEL_LEN)?;
+    }
+    Ok(())
+}
+
+// Typed as one word, so it can't contain spaces
+fn snippet_trigger(trigger: &str) -> Result {
+    required("trigger", trigger, MAX_TRIGGER_LEN)?;
+    if trigger.trim().contains(char::is_whitespace) {
+        return Err(AppError::validation("trigger", "must not contain spaces"));
+    }
+    Ok(())
+}
+
+pub fn snippet_create(data: &SnippetCreate) -> Result {
+    snippet_trigger(&data.trigger)?;
+    optional_max_length("name", data.name.as_ref(), MAX_NAME_LEN)?;
+    required("content", &data.content, MAX_SNIPPET_LEN)
+}
+
+pub fn snippet_update(data: &SnippetUpdate) -> Result {
+    if let Some(trigger) = &data.trigger {
+        snippet_trigger(trigger)?;
+    }
+    optional_max_length("name", data.name.value(), MAX_NAME_LEN)?;
+    if let Some(content) = &data.content {
+        required("content", content, MAX_SNIPPET_LEN)?;
+    }
+    Ok(())
+}
+
+pub fn script_create(data: &ScriptCreate) -> Result {
+    required("name", &data.name, MAX_NAME_LEN)?;
+    required("source", &data.source, MAX_SCRIPT_LEN)
+}
+
+pub fn script_update(data: &ScriptUpdate) -> Result {
+    if let Some(name) = &data.name {
+        required("name", name, MAX_NAME_LEN)?;
+    }
+    if let Some(source) = &data.source {
+        required("source", source, MAX_SCRIPT_LEN)?;
+    }
+    Ok(())
+}
+
+// `value` is JSON text; None clears the key
+pub fn metadata(key: &str, value: Option<&str>) -> Result {
+    required("key", key, MAX_NAME_LEN)?;
+    let Some(value) = value else {
+        return Ok(());
+    };
+    if value.len() > MAX_METADATA_VALUE_LEN {
+        return Err(AppError::validation(
+            "value",
+            format!("must be at most {} bytes", MAX_METADATA_VALUE_LEN),
+        ));
+    }
+    if serde_json::from_str::<serde_json::Value>(value).is_err() {
+        return Err(AppError::validation("value", "must be valid JSON"));
+    }
+    Ok(())
+}
+
+pub fn recurrence_rule(rule: &RecurrenceRule) -> Result {
+    if !(1..=MAX_RECURRENCE_INTERVAL).contains(&rule.interval) {
+        return Err(AppError::validation(
+            "interval",
+            format!("must be between 1 and {}", MAX_RECURRENCE_INTERVAL),
+        ));
+    }
+    if !rule.weekdays.is_empty() && rule.frequency != RecurrenceFrequency::Weekly {
+        return Err(AppError::validation("weekdays", "only apply to weekly rules"));
+    }
+    date("until", rule.until.as_ref())
+}
+
+// A front matter key and its value as JSON text: a string, number, boolean
+// or null, or a list of those. Nested objects can't be written.
+pub fn note_property(key: &str, value: Option<&str>) -> Result {
+    required("key", key, MAX_NAME_LEN)?;
+    if key.starts_with(['-', '#', '"', '\'']) || key.contains([':', '\n', '\r']) {
+        return Err(AppError::validation(
+            "key",
+            "must not contain ':' or line breaks, or start with '-', '#' or a quote",
+        ));
+    }
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let parsed = serde_json::from_str::<serde_json::Value>(value)
+        .map_err(|_| AppError::validation("value", "must be valid JSON"))?;
+    let scalar = |v: &serde_json::Value| !v.is_object() && !v.is_array();
+    let valid = match &parsed {
+        serde_json::Value::Array(items) => items.iter().all(scalar),
+        other => scalar(other),
+    };
+    if !valid {
+        return Err(AppError::validation(
+            "value",
+            "must be a string, number, boolean, null or a list of those",
+        ));
+    }
+    Ok(())
+}
+
+pub fn search_ranking(data: &SearchRanking) -> Result {
+    let weights = [
+        ("relevance", data.relevance),
+        ("recency", data.recency),
+        ("pinned", data.pinned),
+        ("frequency", data.frequency),
+    ];
+    for (field, weight) in weights {
+        if !weight.is_finite() || !(0.0..=MAX_RANKING_WEIGHT).contains(&weight) {
+            return Err(AppError::validation(
+                field,
+                format!("must be between 0 and {}", MAX_RANKING_WEIGHT),
+            ));
+        }
+    }
+    if !data.recency_half_life_days.is_finite() || data.recency_half_life_days <= 0.0 {
+        return Err(AppError::validation(
+            "recency_half_life_days",
+            "must be greater than 0",
+        ));
+    }
+    Ok(())
+}
+
+// ============ Events ============
+
+pub fn event_create(data: &EventCreate) -> Result {
+    required("title", &data.title, MAX_TITLE_LEN)?;
+    time_range(data.start_time.as_ref(), data.end_time.as_ref())?;
+    one_of(
+        "recurring_pattern",
+        data.recurring_pattern.as_ref(),
+        RECURRING_PATTERNS,
+    )?;
+    hex_color("color", data.color.as_ref())?;
+    duration(data.duration_minutes)?;
+    tags("tags", data.tags.as_ref())?;
+    reminders(data.reminders.as_ref())
+}
+
+pub fn quick_add(title: &str, tags: &[String]) -> Result {
+    required("title", title, MAX_TITLE_LEN)?;
+    for tag in tags {
+        required("tags", tag, MAX_TAG_LEN)?;
+    }
+    Ok(())
+}
+
+pub fn shared_content(content: &SharedContent) -> Result {
+    let blank = |value: &Option<String>| value.as_deref().unwrap_or_default().trim().is_empty();
+    if blank(&content.text) && blank(&content.url) && content.files.is_none() {
+        return Err(AppError::validation("payload", "nothing was shared"));
+    }
+    optional_max_length("title", content.title.as_ref(), MAX_TITLE_LEN)?;
+    if content.files.is_some() && content.destination == Some(ShareDestination::Inbox) {
+        return Err(AppError::validation("files", "can only be shared as a note"));
+    }
+    Ok(())
+}
+
+pub fn travel_minutes(value: Option<i32>) -> Result {
+    match value {
+        Some(minutes) if !(0..=MAX_TRAVEL_MINUTES).contains(&minutes) => {
+            Err(AppError::validation(
+                "travel_minutes",
+                format!("must be between 0 and {}", MAX_TRAVEL_MINUTES),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn event_schedule(start: &DateTime<Utc>, end: Option<&DateTime<Utc>>) -> Result {
+    time_range(Some(start), end)
+}
+
+pub fn event_update(data: &EventUpdate) -> Result {
+    if let Some(title) = &data.title {
+        required("title", title, MAX_TITLE_LEN)?;
+    }
+    time_range(data.start_time.value(), data.end_time.value())?;
+    one_of(
+        "recurring_pattern",
+        data.recurring_pattern.value(),
+        RECURRING_PATTERNS,
+    )?;
+    hex_color("color", data.color.value())?;
+    duration(data.duration_minutes.value().copied())?;
+    tags("tags", data.tags.as_ref())?;
+    reminders(data.reminders.as_ref())
+}
+
+// ============ Brain Maps ============
+
+pub fn brain_map_create(data: &BrainMapCreate) -> Result {
+    optional_max_length("title", data.title.as_ref(), MAX_TITLE_LEN)?;
+    optional_max_length(
+        "center_node_text",
+        data.center_node_text.as_ref(),
+        MAX_LABEL_LEN,
+    )?;
+    one_of("theme", data.theme.as_ref(), MAP_THEMES)
+}
+
+pub fn brain_map_update(data: &BrainMapUpdate) -> Result {
+    optional_max_length("title", data.title.as_ref(), MAX_TITLE_LEN)?;
+    optional_max_length(
+        "center_node_text",
+        data.center_node_text.as_ref(),
+        MAX_LABEL_LEN,
+    )?;
+    one_of("theme", data.theme.value(), MAP_THEMES)?;
+    coordinate("viewport_x", data.viewport_x)?;
+    coordinate("viewport_y", data.viewport_y)?;
+    zoom(data.viewport_zoom)
+}
+
+pub fn viewport(data: &BrainMapViewport) -> Result {
+    coordinate("x", Some(data.x))?;
+    coordinate("y", Some(data.y))?;
+    zoom(Some(data.zoom))
+}
+
+pub fn node_create(data: &BrainMapNodeCreate) -> Result {
+    max_length("label", &data.label, MAX_LABEL_LEN)?;
+    coordinate("x", data.x)?;
+    coordinate("y", data.y)?;
+    hex_color("color", data.color.as_ref())?;
+    icon_id("icon", data.icon.as_ref())
+}
+
+pub fn node_update(data: &BrainMapNodeUpdate) -> Result {
+    optional_max_length("label", data.label.as_ref(), MAX_LABEL_LEN)?;
+    coordinate("x", data.x)?;
+    coordinate("y", data.y)?;
+    hex_color("color", data.color.value())?;
+    icon_id("icon", data.icon.value())
+}
+
+pub fn node_style(style: &NodeStyle) -> Result {
+    if style.color.is_none() && style.shape.is_none() && style.size.is_none() && style.icon.is_none() {
+        return Err(AppError::validation("style", "must set at least one property"));
+    }
+    hex_color("color", style.color.as_ref())?;
+    icon_id("icon", style.icon.as_ref())
+}
+
+pub fn style_preset_create(data: &StylePresetCreate) -> Result {
+    required("name", &data.name, MAX_NAME_LEN)?;
+    hex_color("color", data.color.as_ref())?;
+    icon_id("icon", data.icon.as_ref())
+}
+
+pub fn connection_create(data: &BrainMapConnectionCreate) -> Result {
+    if data.source_node_id == data.target_node_id {
+        return Err(AppError::validation(
+            "target_node_id",
+            "must differ from source_node_id",
+        ));
+    }
+    optional_max_length("label", data.label.as_ref(), MAX_LABEL_LEN)?;
+    hex_color("color", data.color.as_ref())?;
+    one_of("style", data.style.as_ref(), CONNECTION_STYLES)
+}
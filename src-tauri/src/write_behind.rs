@@ -0,0 +1,252 @@
+use crate::db::Database;
+use crate::history;
+use crate::models::BrainMapViewport;
+use crate::timestamps;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// Positions are written once no update has arrived for this long, or this
+// long after the first, so a long drag is still saved as it goes
+const POSITION_SETTLE: Duration = Duration::from_millis(250);
+const POSITION_MAX_DELAY: Duration = Duration::from_secs(2);
+
+// Nothing else depends on the viewport being current on disk, so panning
+// and zooming are saved less eagerly
+const VIEWPORT_SETTLE: Duration = Duration::from_secs(1);
+const VIEWPORT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+// The latest value per id since the last write
+struct Pending<T> {
+    values: HashMap<String, T>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+    // A flush task is waiting for the updates to settle
+    scheduled: bool,
+    settle: Duration,
+    max_delay: Duration,
+}
+
+impl<T> Pending<T> {
+    fn new(settle: Duration, max_delay: Duration) -> Self {
+        Pending {
+            values: HashMap::new(),
+            first: None,
+            last: None,
+            scheduled: false,
+            settle,
+            max_delay,
+        }
+    }
+
+    // True when the caller should schedule a flush
+    fn hold(&mut self, id: String, value: T) -> bool {
+        let now = Instant::now();
+        self.values.insert(id, value);
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+        !std::mem::replace(&mut self.scheduled, true)
+    }
+
+    fn take(&mut self) -> HashMap<String, T> {
+        self.first = None;
+        self.last = None;
+        std::mem::take(&mut self.values)
+    }
+
+    // For the flush task; ends the schedule when it returns values
+    fn take_if_settled(&mut self) -> Option<HashMap<String, T>> {
+        let settled = match (self.first, self.last) {
+            (Some(first), Some(last)) => {
+                last.elapsed() >= self.settle || first.elapsed() >= self.max_delay
+            }
+            // Already written by a command that needed it
+            _ => true,
+        };
+        if !settled {
+            return None;
+        }
+        self.scheduled = false;
+        Some(self.take())
+    }
+}
+
+// Updates that arrive far faster than they're worth saving, like node
+// positions on every frame of a drag, are held here and only the last value
+// for each is written. Commands that read or change what's held call the
+// matching flush first so they never see or overwrite a stale row.
+pub struct WriteBehind {
+    positions: Mutex<Pending<(f64, f64)>>,
+    viewports: Mutex<Pending<BrainMapViewport>>,
+}
+
+impl Default for WriteBehind {
+    fn default() -> Self {
+        WriteBehind {
+            positions: Mutex::new(Pending::new(POSITION_SETTLE, POSITION_MAX_DELAY)),
+            viewports: Mutex::new(Pending::new(VIEWPORT_SETTLE, VIEWPORT_MAX_DELAY)),
+        }
+    }
+}
+
+impl WriteBehind {
+    pub fn hold_positions(
+        &self,
+        app: &AppHandle,
+        updates: Vec<(String, f64, f64)>,
+    ) -> Result<(), String> {
+        let mut pending = self.positions.lock().map_err(|e| e.to_string())?;
+        let mut schedule = false;
+        for (id, x, y) in updates {
+            schedule |= pending.hold(id, (x, y));
+        }
+        if schedule {
+            spawn_flush(
+                app.clone(),
+                |w| &w.positions,
+                write_positions,
+                "node positions",
+            );
+        }
+        Ok(())
+    }
+
+    pub fn hold_viewport(
+        &self,
+        app: &AppHandle,
+        brain_map_id: String,
+        viewport: BrainMapViewport,
+    ) -> Result<(), String> {
+        let mut pending = self.viewports.lock().map_err(|e| e.to_string())?;
+        if pending.hold(brain_map_id, viewport) {
+            spawn_flush(
+                app.clone(),
+                |w| &w.viewports,
+                write_viewports,
+                "map viewports",
+            );
+        }
+        Ok(())
+    }
+
+    pub fn flush_positions(&self, conn: &Connection) -> Result<(), String> {
+        let positions = self.positions.lock().map_err(|e| e.to_string())?.take();
+        write_positions(conn, positions)
+    }
+
+    pub fn flush_viewports(&self, conn: &Connection) -> Result<(), String> {
+        let viewports = self.viewports.lock().map_err(|e| e.to_string())?.take();
+        write_viewports(conn, viewports)
+    }
+
+    // Everything still held, e.g. as the app exits
+    pub fn flush(&self, conn: &Connection) -> Result<(), String> {
+        self.flush_positions(conn)?;
+        self.flush_viewports(conn)
+    }
+}
+
+// Each map's moves are logged as one undoable step
+fn write_positions(
+    conn: &Connection,
+    positions: HashMap<String, (f64, f64)>,
+) -> Result<(), String> {
+    if positions.is_empty() {
+        return Ok(());
+    }
+
+    let mut by_map: HashMap<String, Vec<String>> = HashMap::new();
+    for id in positions.keys() {
+        let brain_map_id: Option<String> = conn
+            .query_row(
+                "SELECT brain_map_id FROM brain_map_nodes WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        // Deleted while its position was held
+        if let Some(brain_map_id) = brain_map_id {
+            by_map.entry(brain_map_id).or_default().push(id.clone());
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = timestamps::format(&Utc::now());
+    for (brain_map_id, ids) in by_map {
+        let op = history::record(&tx, &brain_map_id, "move_nodes", ids.clone(), Vec::new())?;
+        for id in ids {
+            let (x, y) = positions[&id];
+            tx.execute(
+                "UPDATE brain_map_nodes SET x = ?1, y = ?2, updated_at = ?3 WHERE id = ?4",
+                params![x, y, now, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        op.finish(&tx)?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Viewports aren't content, so updated_at is left alone
+fn write_viewports(
+    conn: &Connection,
+    viewports: HashMap<String, BrainMapViewport>,
+) -> Result<(), String> {
+    if viewports.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (id, viewport) in viewports {
+        tx.execute(
+            "UPDATE brain_maps SET viewport_x = ?1, viewport_y = ?2, viewport_zoom = ?3
+             WHERE id = ?4",
+            params![viewport.x, viewport.y, viewport.zoom, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+type Write<T> = fn(&Connection, HashMap<String, T>) -> Result<(), String>;
+
+// Waits for the held updates to settle, then writes them
+fn spawn_flush<T: Send + 'static>(
+    app: AppHandle,
+    pending: fn(&WriteBehind) -> &Mutex<Pending<T>>,
+    write: Write<T>,
+    what: &'static str,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settle = match pending(&app.state::<WriteBehind>()).lock() {
+                Ok(pending) => pending.settle,
+                Err(_) => return,
+            };
+            tokio::time::sleep(settle).await;
+
+            // The connection is locked before the values are taken, so a
+            // command can't read the rows between the two
+            let db = app.state::<Database>();
+            let Ok(conn) = db.conn.lock() else {
+                return;
+            };
+            let values = match pending(&app.state::<WriteBehind>()).lock() {
+                Ok(mut pending) => pending.take_if_settled(),
+                Err(_) => return,
+            };
+            let Some(values) = values else {
+                continue;
+            };
+
+            if let Err(e) = write(&conn, values) {
+                log::warn!("Failed to save {}: {}", what, e);
+            }
+            return;
+        }
+    });
+}
@@ -0,0 +1,1922 @@
+
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+async getNotes(folderId: string | null, recursive: boolean | null, sort: NoteSortChoice | null) : Promise<Result<Note[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_notes", { folderId, recursive, sort }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteSort(folderId: string | null) : Promise<Result<NoteSortChoice, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_sort", { folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNote(id: string) : Promise<Result<Note | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createNote(data: NoteCreate) : Promise<Result<Note, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_note", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateNote(id: string, data: NoteUpdate) : Promise<Result<Note, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_note", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteNote(id: string, hard: boolean | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_note", { id, hard }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async restoreNote(id: string, action: RestoreFolderAction | null, folderId: string | null) : Promise<Result<NoteRestore, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_note", { id, action, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getDeletedNotePreview(id: string) : Promise<Result<DeletedNotePreview, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_deleted_note_preview", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async moveNotesToFolder(noteIds: string[], folderId: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("move_notes_to_folder", { noteIds, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async reorderNotes(noteIds: string[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reorder_notes", { noteIds }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteProperties(id: string) : Promise<Result<NoteProperty[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_properties", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setNoteProperty(id: string, key: string, value: string | null) : Promise<Result<NoteProperty[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_note_property", { id, key, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteComments(noteId: string) : Promise<Result<NoteComment[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_comments", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addNoteComment(noteId: string, data: NoteCommentCreate) : Promise<Result<NoteComment, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_note_comment", { noteId, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateNoteComment(id: string, data: NoteCommentUpdate) : Promise<Result<NoteComment, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_note_comment", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteNoteComment(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_note_comment", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async searchNotes(query: string, limit: number | null) : Promise<Result<SearchHit[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("search_notes", { query, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBacklinks(noteId: string) : Promise<Result<Note[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_backlinks", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async checkNoteLinks(noteId: string | null, repair: boolean | null) : Promise<Result<BrokenLink[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("check_note_links", { noteId, repair }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resolveNoteByTitleOrAlias(text: string, limit: number | null) : Promise<Result<LinkTarget[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_note_by_title_or_alias", { text, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resolveEmbeds(noteId: string) : Promise<Result<Embed[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_embeds", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteOutline(id: string) : Promise<Result<OutlineHeading[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_outline", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLinkSettings() : Promise<Result<LinkSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_link_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLinkSettings(settings: LinkSettings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_link_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteRevisions(noteId: string) : Promise<Result<NoteRevision[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_revisions", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteBookmarks(noteId: string) : Promise<Result<NoteBookmark[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_bookmarks", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async rebuildSearchIndex() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rebuild_search_index") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async recordNoteOpen(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("record_note_open", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSearchRanking() : Promise<Result<SearchRanking, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_search_ranking") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSearchRanking(weights: SearchRanking) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_search_ranking", { weights }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSmartFolders() : Promise<Result<SmartFolder[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_smart_folders") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createSmartFolder(data: SmartFolderCreate) : Promise<Result<SmartFolder, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_smart_folder", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateSmartFolder(id: string, data: SmartFolderUpdate) : Promise<Result<SmartFolder, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_smart_folder", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteSmartFolder(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_smart_folder", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSmartFolderNotes(id: string, limit: number | null) : Promise<Result<SearchHit[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_smart_folder_notes", { id, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFolders(query: ListQuery | null) : Promise<Result<Page<Folder>, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_folders", { query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createFolder(data: FolderCreate) : Promise<Result<Folder, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_folder", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateFolder(id: string, data: FolderUpdate) : Promise<Result<Folder, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_folder", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async moveFolder(id: string, newParentId: string | null, merge: boolean | null) : Promise<Result<Folder, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("move_folder", { id, newParentId, merge }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteFolder(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_folder", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFolderPath(id: string) : Promise<Result<Folder[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_folder_path", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resolveFolderPath(path: string, create: boolean | null) : Promise<Result<Folder | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_folder_path", { path, create }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getEvents() : Promise<Result<Event[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_events") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getEvent(id: string) : Promise<Result<Event | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_event", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getEventsByType(eventType: EventType) : Promise<Result<Event[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_events_by_type", { eventType }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createEvent(data: EventCreate) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_event", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateEvent(id: string, data: EventUpdate) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_event", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteEvent(id: string, hard: boolean | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_event", { id, hard }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async duplicateEvent(id: string, newStart: string) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("duplicate_event", { id, newStart }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createEventLikeLast(category: string) : Promise<Result<Event | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_event_like_last", { category }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async shiftEvents(ids: string[], deltaMinutes: number) : Promise<Result<Event[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("shift_events", { ids, deltaMinutes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async moveEventsToDate(ids: string[], date: string) : Promise<Result<Event[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("move_events_to_date", { ids, date }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getUnscheduledEvents(filters: UnscheduledFilter | null) : Promise<Result<Event[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_unscheduled_events", { filters }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async scheduleEvent(id: string, start: string, end: string | null) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("schedule_event", { id, start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setEventTravelTime(id: string, travelMinutes: number | null) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_event_travel_time", { id, travelMinutes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLocaleSettings() : Promise<Result<LocaleSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_locale_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLocaleSettings(settings: LocaleSettings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_locale_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getWeekRange(date: string) : Promise<Result<WeekRange, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_week_range", { date }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAgenda(start: string, end: string) : Promise<Result<AgendaDay[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_agenda", { start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTodayView() : Promise<Result<TodayView, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_today_view") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setTaskRecurrence(id: string, rule: RecurrenceRule | null) : Promise<Result<Event, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_task_recurrence", { id, rule }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTaskHistory(id: string, start: string | null, end: string | null) : Promise<Result<TaskHistory, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_task_history", { id, start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async completeTaskOccurrence(id: string, date: string) : Promise<Result<TaskOccurrence, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("complete_task_occurrence", { id, date }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async reopenTaskOccurrence(id: string, date: string) : Promise<Result<TaskOccurrence, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reopen_task_occurrence", { id, date }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addTaskDependency(taskId: string, dependsOnId: string) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_task_dependency", { taskId, dependsOnId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removeTaskDependency(taskId: string, dependsOnId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("remove_task_dependency", { taskId, dependsOnId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTaskPlan(project: string | null) : Promise<Result<TaskPlan, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_task_plan", { project }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getProjects() : Promise<Result<Project[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_projects") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createProject(data: ProjectCreate) : Promise<Result<Project, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_project", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateProject(id: string, data: ProjectUpdate) : Promise<Result<Project, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_project", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteProject(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_project", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async linkProjectItem(projectId: string, itemType: ProjectItemKind, itemId: string) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("link_project_item", { projectId, itemType, itemId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unlinkProjectItem(projectId: string, itemType: ProjectItemKind, itemId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unlink_project_item", { projectId, itemType, itemId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getProjectOverview(id: string) : Promise<Result<ProjectOverview, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_project_overview", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGoals() : Promise<Result<Goal[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_goals") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createGoal(data: GoalCreate) : Promise<Result<Goal, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_goal", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateGoal(id: string, data: GoalUpdate) : Promise<Result<Goal, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_goal", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteGoal(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_goal", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async linkGoalItem(goalId: string, eventId: string) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("link_goal_item", { goalId, eventId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unlinkGoalItem(goalId: string, eventId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unlink_goal_item", { goalId, eventId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGoalProgress(id: string) : Promise<Result<GoalProgress, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_goal_progress", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportEventsIcs(start: string | null, end: string | null) : Promise<Result<string | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_events_ics", { start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportFolderSite(folderId: string) : Promise<Result<string | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_folder_site", { folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async parseDateExpression(text: string, reference: string | null, locale: string | null) : Promise<Result<ParsedDate | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("parse_date_expression", { text, reference, locale }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async quickAdd(text: string) : Promise<Result<QuickAddResult, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("quick_add", { text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getInboxItems() : Promise<Result<InboxItem[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_inbox_items") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async triageInboxItem(id: string, action: TriageAction) : Promise<Result<TriageResult, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("triage_inbox_item", { id, action }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getProductivityMetrics(start: string, end: string) : Promise<Result<ProductivityMetrics, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_productivity_metrics", { start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getHolidayRegions() : Promise<Result<HolidayRegion[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_holiday_regions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setHolidayRegions(codes: string[]) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_holiday_regions", { codes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getHolidays(start: string, end: string) : Promise<Result<Holiday[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_holidays", { start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async generateWeeklyReview(week: string) : Promise<Result<Note, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("generate_weekly_review", { week }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBrainMaps(query: ListQuery | null) : Promise<Result<Page<BrainMapSummary>, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_brain_maps", { query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBrainMap(id: string) : Promise<Result<BrainMapWithData | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_brain_map", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBrainMapVisible(id: string) : Promise<Result<BrainMapVisibleData | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_brain_map_visible", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBrainMapStats(id: string) : Promise<Result<BrainMapStats | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_brain_map_stats", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createBrainMap(data: BrainMapCreate) : Promise<Result<BrainMapWithData, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_brain_map", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateBrainMap(id: string, data: BrainMapUpdate) : Promise<Result<BrainMap, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_brain_map", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateBrainMapViewport(id: string, viewport: BrainMapViewport) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_brain_map_viewport", { id, viewport }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteBrainMap(id: string, hard: boolean | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_brain_map", { id, hard }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async restoreBrainMap(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_brain_map", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportBrainMapImage(id: string, format: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_brain_map_image", { id, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createBrainMapNode(data: BrainMapNodeCreate) : Promise<Result<BrainMapNode, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_brain_map_node", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateBrainMapNode(id: string, data: BrainMapNodeUpdate) : Promise<Result<BrainMapNode, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_brain_map_node", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteBrainMapNode(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_brain_map_node", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateNodePositions(updates: ([string, number, number])[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_node_positions", { updates }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNodesLinkedToNote(noteId: string) : Promise<Result<NoteNodeLink[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_nodes_linked_to_note", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async linkNoteToNode(noteId: string, nodeId: string) : Promise<Result<BrainMapNode, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("link_note_to_node", { noteId, nodeId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createBrainMapConnection(data: BrainMapConnectionCreate) : Promise<Result<BrainMapConnection, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_brain_map_connection", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getConnectionsBetween(a: string, b: string) : Promise<Result<BrainMapConnection[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_connections_between", { a, b }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteBrainMapConnection(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_brain_map_connection", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async undoBrainMapOp(mapId: string) : Promise<Result<BrainMapOpApplied | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("undo_brain_map_op", { mapId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async redoBrainMapOp(mapId: string) : Promise<Result<BrainMapOpApplied | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("redo_brain_map_op", { mapId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getStylePresets() : Promise<Result<StylePreset[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_style_presets") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createStylePreset(data: StylePresetCreate) : Promise<Result<StylePreset, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_style_preset", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteStylePreset(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_style_preset", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async applyStyleToSubtree(nodeId: string, style: NodeStyle) : Promise<Result<BrainMapNode[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_style_to_subtree", { nodeId, style }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getMetadata(entityType: EntityKind, entityId: string) : Promise<Result<MetadataEntry[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_metadata", { entityType, entityId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setMetadata(entityType: EntityKind, entityId: string, key: string, value: string | null) : Promise<Result<MetadataEntry[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_metadata", { entityType, entityId, key, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getColorPalette() : Promise<Result<PaletteColor[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_color_palette") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addPaletteColor(name: string, hex: string) : Promise<Result<PaletteColor[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_palette_color", { name, hex }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removePaletteColor(hex: string) : Promise<Result<PaletteColor[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("remove_palette_color", { hex }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getIconCatalog() : Promise<Result<CatalogIcon[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_icon_catalog") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addCatalogIcon(id: string, label: string, glyph: string) : Promise<Result<CatalogIcon[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_catalog_icon", { id, label, glyph }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removeCatalogIcon(id: string) : Promise<Result<CatalogIcon[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("remove_catalog_icon", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSetting(key: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_setting", { key }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSetting(key: string, value: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_setting", { key, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addAttachment(noteId: string) : Promise<Result<Attachment | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_attachment", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAttachments(noteId: string) : Promise<Result<Attachment[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_attachments", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async saveAudioMemo(noteId: string, bytes: number[], mimeType: string | null, durationMs: number | null) : Promise<Result<Attachment, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_audio_memo", { noteId, bytes, mimeType, durationMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAudioMemos(noteId: string) : Promise<Result<Attachment[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_audio_memos", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteAttachment(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_attachment", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAttachmentDedupStats() : Promise<Result<AttachmentDedupStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_attachment_dedup_stats") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getStorageUsage() : Promise<Result<StorageUsage, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_storage_usage") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setStorageSettings(settings: StorageSettings) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_storage_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async shareNoteBundle(id: string, password: string | null) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("share_note_bundle", { id, password }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async publishNote(id: string) : Promise<Result<Note, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("publish_note", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unpublishNote(id: string) : Promise<Result<Note, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unpublish_note", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPublishTarget() : Promise<Result<PublishTarget | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_publish_target") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setPublishTarget(target: PublishTarget, secret: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_publish_target", { target, secret }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getEmailCaptureConfig() : Promise<Result<EmailCaptureConfig | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_email_capture_config") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setEmailCaptureConfig(config: EmailCaptureConfig, password: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_email_capture_config", { config, password }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async pollEmailInbox() : Promise<Result<EmailPollResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("poll_email_inbox") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFeeds() : Promise<Result<Feed[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_feeds") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addFeed(url: string) : Promise<Result<Feed, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_feed", { url }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteFeed(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_feed", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFeedItems(feedId: string | null, unreadOnly: boolean | null) : Promise<Result<FeedItem[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_feed_items", { feedId, unreadOnly }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async markFeedItemRead(id: string, isRead: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("mark_feed_item_read", { id, isRead }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async refreshFeeds() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("refresh_feeds") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async saveFeedItemAsNote(id: string, folderId: string | null) : Promise<Result<Note, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_feed_item_as_note", { id, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async captureUrl(url: string, tags: string[] | null, folderId: string | null) : Promise<Result<Note, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("capture_url", { url, tags, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async convertHtmlToMarkdown(html: string) : Promise<string> {
+    return await TAURI_INVOKE("convert_html_to_markdown", { html });
+},
+async exportNoteToFile(id: string, format: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_note_to_file", { id, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async importNotesFromFiles(folderId: string | null) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_notes_from_files", { folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async backupDatabase() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("backup_database") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportVaultArchive() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_vault_archive") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportMarkdownFolder() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_markdown_folder") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportEnex() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_enex") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async importFormat(format: string, folderId: string | null) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_format", { format, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async previewImport(format: string, folderId: string | null) : Promise<Result<ImportPreview | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_import", { format, folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async confirmImport(id: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("confirm_import", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportFormat(format: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_format", { format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listSupportedFormats() : Promise<SupportedFormat[]> {
+    return await TAURI_INVOKE("list_supported_formats");
+},
+async listJobs(limit: number | null) : Promise<Result<Job[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_jobs", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelJob(id: string) : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_job", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async rotateRecoveryCodes() : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rotate_recovery_codes") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async verifyRecoveryCode(code: string) : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("verify_recovery_code", { code }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getRecoveryCodeStatus() : Promise<Result<RecoveryCodeStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_recovery_code_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async mergeRemoteChanges(changes: RemoteChanges) : Promise<Result<SyncReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("merge_remote_changes", { changes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listConflictCopies() : Promise<Result<ConflictCopy[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_conflict_copies") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNoteCrdt(noteId: string) : Promise<Result<NoteDoc, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_note_crdt", { noteId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resolveConflictCopy(id: string, resolution: ConflictResolution) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_conflict_copy", { id, resolution }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLanSyncConfig() : Promise<Result<LanSyncConfig | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_lan_sync_config") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLanSyncConfig(config: LanSyncConfig, passphrase: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_lan_sync_config", { config, passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLanPeers() : Promise<Result<LanPeer[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_lan_peers") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async syncWithLanPeer(deviceId: string) : Promise<Result<LanSyncResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("sync_with_lan_peer", { deviceId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getMirrorConfig() : Promise<Result<MirrorConfig | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_mirror_config") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setMirrorConfig(enabled: boolean) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_mirror_config", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async pickMirrorDirectory() : Promise<Result<MirrorConfig | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pick_mirror_directory") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getWatchedFolders() : Promise<Result<WatchedFolder[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_watched_folders") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async watchFolder(folderId: string) : Promise<Result<WatchedFolder | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("watch_folder", { folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unwatchFolder(folderId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unwatch_folder", { folderId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSystemSearchSettings() : Promise<Result<SystemSearchSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_system_search_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSystemSearchSettings(settings: SystemSearchSettings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_system_search_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async takeLaunchNoteLink() : Promise<string | null> {
+    return await TAURI_INVOKE("take_launch_note_link");
+},
+async receiveSharedContent(payload: SharedContent) : Promise<Result<ShareResult, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("receive_shared_content", { payload }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPendingShares() : Promise<Result<PendingShare[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_pending_shares") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setShareMenu(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_share_menu", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getClipboardCaptureSettings() : Promise<Result<ClipboardCaptureSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_clipboard_capture_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setClipboardCaptureSettings(settings: ClipboardCaptureSettings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_clipboard_capture_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getDailyNote(date: string | null) : Promise<Result<Note, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_daily_note", { date }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSnippets() : Promise<Result<Snippet[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_snippets") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createSnippet(data: SnippetCreate) : Promise<Result<Snippet, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_snippet", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateSnippet(id: string, data: SnippetUpdate) : Promise<Result<Snippet, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_snippet", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteSnippet(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_snippet", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async expandSnippet(trigger: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("expand_snippet", { trigger }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getScripts() : Promise<Result<Script[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_scripts") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createScript(data: ScriptCreate) : Promise<Result<Script, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_script", { data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateScript(id: string, data: ScriptUpdate) : Promise<Result<Script, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_script", { id, data }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteScript(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_script", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listActions() : Promise<Action[]> {
+    return await TAURI_INVOKE("list_actions");
+},
+async getPlatformCapabilities() : Promise<PlatformCapabilities> {
+    return await TAURI_INVOKE("get_platform_capabilities");
+},
+async getBackgroundActivity() : Promise<BackgroundActivity> {
+    return await TAURI_INVOKE("get_background_activity");
+},
+async setBackgroundActivity(level: ActivityLevel) : Promise<BackgroundActivity> {
+    return await TAURI_INVOKE("set_background_activity", { level });
+},
+async getStartupProblem() : Promise<StartupProblem | null> {
+    return await TAURI_INVOKE("get_startup_problem");
+},
+async openDatabaseReadOnly() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_database_read_only") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async restoreDatabaseBackup(path: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_database_backup", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async restartApp() : Promise<void> {
+    await TAURI_INVOKE("restart_app");
+},
+async repairBrainMaps(dryRun: boolean | null) : Promise<Result<BrainMapRepairReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("repair_brain_maps", { dryRun }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async purgeDeletedItems(olderThanDays: number) : Promise<Result<PurgeReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("purge_deleted_items", { olderThanDays }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTrashSettings() : Promise<Result<TrashSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_trash_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setTrashSettings(settings: TrashSettings) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_trash_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async seedDemoData(scale: number | null) : Promise<Result<SeedSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("seed_demo_data", { scale }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setProfilingEnabled(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_profiling_enabled", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPerformanceReport(limit: number | null) : Promise<PerformanceReport> {
+    return await TAURI_INVOKE("get_performance_report", { limit });
+}
+}
+
+/** user-defined events **/
+
+
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type Action = { id: string; title: string; params: ActionParam[] }
+export type ActionParam = { name: string; required: boolean; schema: JsonValue }
+export type ActivityLevel = "full" | "reduced" | "paused"
+export type AgendaDay = { date: string; label: string; week_number: number; entries: AgendaEntry[] }
+export type AgendaEntry = { event: Event; time_label: string | null }
+export type AppError = { kind: "validation"; field: string; message: string } | { kind: "conflict"; message: string; existing_id: string } | { kind: "internal"; message: string }
+export type Attachment = { id: string; note_id: string; kind: string; file_name: string; mime_type: string; size_bytes: number; duration_ms: number | null; created_at: string }
+export type AttachmentDedupStats = { attachments: number; blobs: number; logical_bytes: number; stored_bytes: number; saved_bytes: number }
+export type BackgroundActivity = { level: ActivityLevel; paused: BackgroundSubsystem[] }
+export type BackgroundSubsystem = "feeds" | "email" | "lan_sync" | "system_search" | "mirror" | "watched_folders" | "reminders" | "maintenance"
+export type BacklogSort = "priority" | "created"
+export type BackupFile = { path: string; size_bytes: number; created_at: string }
+export type BrainMap = { id: string; title: string; description: string | null; center_node_id: string | null; center_node_text: string; viewport_x: number; viewport_y: number; viewport_zoom: number; theme: string | null; created_at: string; updated_at: string; deleted_at: string | null }
+export type BrainMapConnection = { id: string; brain_map_id: string; source_node_id: string; target_node_id: string; label: string | null; color: string | null; style: string | null; animated: boolean; created_at: string }
+export type BrainMapConnectionCreate = { brain_map_id: string; source_node_id: string; target_node_id: string; label: string | null; color: string | null; style: string | null; animated: boolean | null }
+export type BrainMapCreate = { title: string | null; description: string | null; center_node_text: string | null; theme: string | null }
+export type BrainMapNode = { id: string; brain_map_id: string; parent_node_id: string | null; label: string; description: string | null; x: number; y: number; color: string | null; shape: NodeShape | null; size: NodeSize | null; icon: string | null; linked_note_id: string | null; linked_folder_id: string | null; linked_event_id: string | null; is_collapsed: boolean; layer: number; created_at: string; updated_at: string }
+export type BrainMapNodeCreate = { brain_map_id: string; parent_node_id: string | null; label: string; description: string | null; x: number | null; y: number | null; color: string | null; shape: NodeShape | null; size: NodeSize | null; icon: string | null; linked_note_id: string | null; linked_folder_id: string | null; linked_event_id: string | null }
+export type BrainMapNodeUpdate = { parent_node_id?: string | null; label: string | null; description?: string | null; x: number | null; y: number | null; color?: string | null; shape?: NodeShape | null; size?: NodeSize | null; icon?: string | null; linked_note_id?: string | null; linked_folder_id?: string | null; linked_event_id?: string | null; is_collapsed: boolean | null }
+export type BrainMapOpApplied = { label: string; nodes: BrainMapNode[]; connections: BrainMapConnection[]; removed_node_ids: string[]; removed_connection_ids: string[] }
+export type BrainMapRepairReport = { orphaned_nodes: number; missing_parents: number; dangling_connections: number; broken_note_links: number; broken_folder_links: number; broken_event_links: number; repaired: boolean }
+export type BrainMapStats = { node_count: number; connection_count: number; max_depth: number; orphan_nodes: NodeDegree[]; most_connected: NodeDegree[] }
+export type BrainMapSummary = { brain_map: BrainMap; node_count: number; connection_count: number }
+export type BrainMapUpdate = { title: string | null; description?: string | null; center_node_id?: string | null; center_node_text: string | null; viewport_x: number | null; viewport_y: number | null; viewport_zoom: number | null; theme?: string | null }
+export type BrainMapViewport = { x: number; y: number; zoom: number }
+export type BrainMapVisibleData = { brain_map: BrainMap; nodes: BrainMapNode[]; connections: BrainMapConnection[]; hidden_descendants: Partial<{ [key in string]: number }> }
+export type BrainMapWithData = { brain_map: BrainMap; nodes: BrainMapNode[]; connections: BrainMapConnection[] }
+export type BrokenLink = { source_note_id: string; source_title: string; target: string; reason: BrokenLinkReason; target_id: string | null; target_title: string | null; repaired: boolean }
+export type BrokenLinkReason = "missing" | "trashed" | "renamed"
+export type BusyDay = { date: string; event_count: number; scheduled_hours: number }
+export type CatalogIcon = { id: string; label: string; glyph: string; custom?: boolean }
+export type ClipboardCaptureSettings = { enabled: boolean; shortcut: string }
+export type ClipboardCaptured = { note: Note; source_app: string | null }
+export type ClockFormat = "twenty_four_hour" | "twelve_hour"
+export type CommandStats = { name: string; calls: number; avg_ms: number; max_ms: number; avg_rows: number | null }
+export type ConflictCopy = { id: string; kind: EntityKind; original_id: string; title: string; device: string; created_at: string }
+export type ConflictResolution = "original" | "copy" | "both"
+export type DateOrder = "ymd" | "dmy" | "mdy"
+export type DayOfWeek = "monday" | "tuesday" | "wednesday" | "thursday" | "friday" | "saturday" | "sunday"
+export type DeletedNotePreview = { id: string; title: string; snippet: string; deleted_at: string; folder_path: string | null }
+export type EmailCaptureConfig = { enabled: boolean; host: string; port: number | null; username: string; mailbox: string; folder_id: string | null; poll_interval_minutes: number | null }
+export type EmailPollResult = { imported: number; skipped: number; failed: number }
+export type Embed = { source_note_id: string; target: string; note_id: string | null; heading: string | null; html: string | null; cyclic: boolean }
+export type EntityKind = "note" | "event" | "folder" | "snippet"
+export type Event = { id: string; title: string; description: string | null; event_type: EventType | null; start_time: string | null; end_time: string | null; has_scheduled_time: boolean; time_mode: TimeMode; duration_minutes: number | null; location: string | null; category: string | null; color: string | null; priority: Priority | null; tags: string[]; show_on_calendar: boolean; is_all_day: boolean; is_recurring: boolean; recurring_pattern: string | null; status: EventStatus | null; reminders: EventReminder[]; notes: string | null; created_at: string; updated_at: string; deleted_at: string | null; travel_minutes: number | null; leave_by: string | null }
+export type EventCreate = { title: string; description: string | null; event_type: EventType | null; start_time: string | null; end_time: string | null; time_mode: TimeMode | null; duration_minutes: number | null; location: string | null; category: string | null; color: string | null; priority: Priority | null; tags: string[] | null; show_on_calendar: boolean | null; is_all_day: boolean | null; is_recurring: boolean | null; recurring_pattern: string | null; reminders: EventReminder[] | null; notes: string | null }
+export type EventReminder = { id: string; minutes_before: number; type: string }
+export type EventStatus = "pending" | "in_progress" | "completed" | "cancelled" | "missed" | "skipped"
+export type EventType = "event" | "task" | "meeting" | "reminder" | "deadline"
+export type EventUpdate = { title: string | null; description?: string | null; event_type: EventType | null; start_time?: string | null; end_time?: string | null; time_mode: TimeMode | null; duration_minutes?: number | null; location?: string | null; category?: string | null; color?: string | null; priority: Priority | null; tags: string[] | null; show_on_calendar: boolean | null; is_all_day: boolean | null; is_recurring: boolean | null; recurring_pattern?: string | null; status: EventStatus | null; reminders: EventReminder[] | null; notes?: string | null }
+export type Feed = { id: string; url: string; title: string | null; site_url: string | null; last_fetched_at: string | null; created_at: string }
+export type FeedItem = { id: string; feed_id: string; guid: string; title: string; url: string | null; summary: string | null; content: string | null; published_at: string | null; is_read: boolean; note_id: string | null; created_at: string }
+export type Folder = { id: string; name: string; parent_id: string | null; color: string | null; icon: string | null; created_at: string; updated_at: string }
+export type FolderCreate = { name: string; parent_id: string | null; color: string | null; icon: string | null }
+export type FolderStorage = { folder_id: string | null; name: string; notes: number; bytes: number }
+export type FolderUpdate = { name: string | null; parent_id?: string | null; color?: string | null; icon?: string | null }
+export type FormatDirection = "import" | "export"
+export type FormatTarget = "file" | "folder"
+export type Goal = { id: string; title: string; description: string | null; metric: GoalMetric; target: number | null; due_date: string | null; created_at: string; updated_at: string }
+export type GoalCreate = { title: string; description: string | null; metric: GoalMetric | null; target: number | null; due_date: string | null }
+export type GoalItemProgress = { task: Event; recurring: boolean; done: number; due: number }
+export type GoalMetric = "completion" | "count"
+export type GoalProgress = { goal: Goal; items: GoalItemProgress[]; current: number; target: number; percent: number; days_left: number | null }
+export type GoalUpdate = { title: string | null; description: string | null; metric: GoalMetric | null; target: number | null; due_date: string | null }
+export type Holiday = { date: string; name: string; region: string }
+export type HolidayRegion = { code: string; name: string; selected: boolean }
+export type ImportPreview = { id: string; format: string; summary: ImportSummary }
+export type ImportSummary = { folders: number; notes: number; attachments: number; duplicates: number; problems: string[] }
+export type InboxItem = { id: string; text: string; title: string; tags: string[]; due_date: string | null; priority: Priority | null; folder_path: string | null; created_at: string }
+export type Job = { id: string; kind: string; state: JobState; attempts: number; done: number; total: number; message: string | null; output: string | null; error: string | null; report: JobReport | null; created_at: string; updated_at: string }
+export type JobProgress = { id: string; kind: string; state: JobState; done: number; total: number; message: string | null; output: string | null; error: string | null; report: JobReport | null }
+export type JobReport = { created: number; updated: number; skipped: number; errors: string[] }
+export type JobState = "queued" | "running" | "completed" | "failed" | "cancelled"
+export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+export type LanPeer = { device_id: string; name: string; address: string; last_synced_at: string | null }
+export type LanSyncConfig = { enabled: boolean; device_name: string; port: number | null }
+export type LanSyncResult = { peer: string; sent: number; received: SyncReport }
+export type LinkSettings = { update_on_rename: boolean }
+export type LinkTarget = { note_id: string; title: string; alias: string | null; exact: boolean }
+export type ListQuery = { search: string | null; sort: ListSort | null; descending: boolean | null; limit: number | null; offset: number | null }
+export type ListSort = "name" | "created" | "updated"
+export type LocaleSettings = { week_start: WeekStart; clock: ClockFormat; date_order: DateOrder }
+export type MetadataEntry = { key: string; value: string; updated_at: string }
+export type MirrorConfig = { enabled: boolean; directory: string }
+export type NodeDegree = { node_id: string; label: string; degree: number }
+export type NodeShape = "circle" | "rectangle" | "diamond" | "hexagon" | "pill"
+export type NodeSize = "small" | "medium" | "large" | "xl"
+export type NodeStyle = { color: string | null; shape: NodeShape | null; size: NodeSize | null; icon: string | null }
+export type Note = { id: string; title: string; content: string; folder_id: string | null; tags: string[]; aliases?: string[]; is_pinned: boolean; created_at: string; updated_at: string; deleted_at: string | null; published_url: string | null; language?: string | null }
+export type NoteBookmark = { note_id: string; url: string; label: string | null }
+export type NoteComment = { id: string; note_id: string; offset: number; length: number; quote: string; body: string; resolved: boolean; detached: boolean; created_at: string; updated_at: string }
+export type NoteCommentCreate = { offset: number; length: number; body: string }
+export type NoteCommentUpdate = { body: string | null; resolved: boolean | null }
+export type NoteCreate = { title: string | null; content: string | null; folder_id: string | null; tags: string[] | null; aliases: string[] | null }
+export type NoteDoc = { note_id: string; doc: string }
+export type NoteLinksChanged = { note_id: string; node_id: string; brain_map_id: string }
+export type NoteNodeLink = { node_id: string; node_label: string; brain_map_id: string; brain_map_title: string }
+export type NoteProperty = { key: string; kind: PropertyKind; value: string }
+export type NoteRestore = { status: RestoreStatus; note: Note; folder_path: string | null }
+export type NoteRevision = { seq: number; note_id: string; title: string; content: string; reason: string; created_at: string }
+export type NoteSort = "updated" | "created" | "title" | "size" | "manual"
+export type NoteSortChoice = { sort: NoteSort; descending: boolean | null }
+export type NoteStorage = { note_id: string; title: string; folder_id: string | null; attachments: number; bytes: number; in_trash: boolean }
+export type NoteUpdate = { title: string | null; content: string | null; folder_id?: string | null; tags: string[] | null; aliases: string[] | null; is_pinned: boolean | null }
+export type OccurrenceStatus = "completed" | "missed" | "pending"
+export type OutlineHeading = { level: number; text: string; slug: string; offset: number; end: number; children: OutlineHeading[] }
+export type Page<T> = { items: T[]; total: number }
+export type PaletteColor = { name: string; hex: string; custom?: boolean }
+export type PanicReport = { message: string; location: string | null; thread: string | null; occurred_at: string }
+export type ParsedDate = { date: string; time: string | null; timestamp: string }
+export type PendingShare = { token: string; file_names: string[] }
+export type PerformanceReport = { enabled: boolean; commands: CommandStats[]; queries: QueryStats[] }
+export type PlannedTask = { task: Event; depends_on: string[]; blocked_by: string[]; step: number | null; in_cycle: boolean }
+export type PlatformCapabilities = { mobile: boolean; markdown_mirror: boolean; watched_folders: boolean; system_search: boolean; lan_sync: boolean; share_menu: boolean; clipboard_capture: boolean; scheduled_notifications: boolean }
+export type Priority = "low" | "medium" | "high"
+export type ProductivityMetrics = { events_total: number; events_completed: number; events_cancelled: number; events_missed: number; busiest_days: BusyDay[]; average_scheduled_hours: number; notes_created: number }
+export type Project = { id: string; name: string; description: string | null; color: string | null; status: ProjectStatus; due_date: string | null; created_at: string; updated_at: string }
+export type ProjectActivity = { kind: ProjectItemKind; id: string; title: string; updated_at: string }
+export type ProjectCreate = { name: string; description: string | null; color: string | null; due_date: string | null }
+export type ProjectItemKind = "note" | "event" | "brain_map"
+export type ProjectOverview = { project: Project; notes: Note[]; events: Event[]; tasks: Event[]; brain_maps: BrainMap[]; stats: ProjectStats; recent_activity: ProjectActivity[] }
+export type ProjectStats = { tasks_total: number; tasks_completed: number; tasks_overdue: number; upcoming_events: number; notes: number; brain_maps: number; progress: number }
+export type ProjectStatus = "active" | "on_hold" | "completed" | "archived"
+export type ProjectUpdate = { name: string | null; description: string | null; color: string | null; status: ProjectStatus | null; due_date: string | null }
+export type PropertyKind = "text" | "number" | "checkbox" | "date" | "date_time" | "list"
+export type PublishTarget = { kind: "post"; endpoint: string } | { kind: "s3"; endpoint: string; bucket: string; region: string; access_key_id: string; public_base_url: string | null }
+export type PurgeReport = { notes: number; events: number; brain_maps: number; revisions: number; attachments: number; bytes_reclaimed: number }
+export type QueryStats = { sql: string; calls: number; avg_ms: number; max_ms: number }
+export type QuickAddResult = { inbox_item: InboxItem | null; event: Event | null }
+export type RecoveryCodeStatus = { total: number; remaining: number; created_at: string | null }
+export type RecurrenceFrequency = "daily" | "weekly" | "monthly" | "yearly"
+export type RecurrenceRule = { frequency: RecurrenceFrequency; interval?: number; weekdays?: DayOfWeek[]; until: string | null }
+export type ReminderDue = { event_id: string; title: string; kind: ReminderKind; fire_at: string; start_time: string; location: string | null }
+export type ReminderKind = "reminder" | "leave"
+export type RemoteChanges = { device: string; notes?: Note[]; events?: Event[]; note_docs?: NoteDoc[]; snippets?: Snippet[] }
+export type RestoreFolderAction = "recreate" | "root" | "folder"
+export type RestoreStatus = "restored" | "folder_missing"
+export type Script = { id: string; name: string; source: string; enabled: boolean; last_run_at: string | null; last_error: string | null; created_at: string; updated_at: string }
+export type ScriptCreate = { name: string; source: string; enabled: boolean | null }
+export type ScriptUpdate = { name: string | null; source: string | null; enabled: boolean | null }
+export type SearchHit = { note: Note; snippet: string; score: number }
+export type SearchRanking = { relevance: number; recency: number; pinned: number; frequency: number; recency_half_life_days: number }
+export type SeedSummary = { folders: number; notes: number; events: number; nodes: number; connections: number }
+export type ShareDestination = "note" | "inbox"
+export type ShareResult = { note: Note | null; inbox_item: InboxItem | null }
+export type SharedContent = { title: string | null; text: string | null; url: string | null; files: string | null; destination: ShareDestination | null }
+export type SmartFolder = { id: string; name: string; query: string; created_at: string; updated_at: string }
+export type SmartFolderCreate = { name: string; query: string }
+export type SmartFolderUpdate = { name: string | null; query: string | null }
+export type Snippet = { id: string; trigger: string; name: string | null; content: string; created_at: string; updated_at: string; deleted_at: string | null }
+export type SnippetCreate = { trigger: string; name: string | null; content: string }
+export type SnippetUpdate = { trigger: string | null; name?: string | null; content: string | null }
+export type StartupProblem = { kind: StartupProblemKind; message: string; database_path: string; backups: BackupFile[]; read_only: boolean }
+export type StartupProblemKind = "corrupt" | "locked" | "unavailable"
+export type StorageSettings = { attachment_warning_bytes: number | null }
+export type StorageUsage = { database_bytes: number; attachments_bytes: number; backups_bytes: number; backups: BackupFile[]; notes: NoteStorage[]; folders: FolderStorage[]; settings: StorageSettings }
+export type StylePreset = { id: string; name: string; color: string | null; shape: NodeShape | null; size: NodeSize | null; icon: string | null; created_at: string }
+export type StylePresetCreate = { name: string; color: string | null; shape: NodeShape | null; size: NodeSize | null; icon: string | null }
+export type SupportedFormat = { id: string; name: string; direction: FormatDirection; target: FormatTarget; extensions: string[]; preview: boolean }
+export type SyncReport = { applied: number; unchanged: number; conflicts: ConflictCopy[] }
+export type SystemSearchSettings = { enabled: boolean }
+export type TaskHistory = { event_id: string; rule: RecurrenceRule; occurrences: TaskOccurrence[]; current_streak: number; longest_streak: number; completed: number; missed: number }
+export type TaskOccurrence = { date: string; status: OccurrenceStatus; completed_at: string | null }
+export type TaskPlan = { tasks: PlannedTask[]; cycles: string[][] }
+export type TimeMode = "todo" | "at_time" | "all_day" | "morning" | "day" | "evening" | "anytime"
+export type TodayView = { date: string; events: Event[]; tasks: Event[]; habits: Event[]; notes: Note[]; reminders: ReminderDue[] }
+export type TrashSettings = { retention_days: number | null }
+export type TriageAction = "note" | "task" | "dismiss"
+export type TriageResult = { note: Note | null; event: Event | null }
+export type UnscheduledFilter = { category: string | null; priority: Priority | null; search: string | null; include_finished: boolean | null; sort: BacklogSort | null }
+export type WatchedFolder = { folder_id: string; directory: string; created_at: string }
+export type WeekRange = { start: string; end: string; year: number; week_number: number }
+export type WeekStart = "monday" | "sunday" | "saturday"
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
+
+function __makeEvents__<T extends Record<string, any>>(
+	mappings: Record<keyof T, string>,
+) {
+	return new Proxy(
+		{} as unknown as {
+			[K in keyof T]: __EventObj__<T[K]> & {
+				(handle: __WebviewWindow__): __EventObj__<T[K]>;
+			};
+		},
+		{
+			get: (_, event) => {
+				const name = mappings[event as keyof T];
+
+				return new Proxy((() => {}) as any, {
+					apply: (_, __, [window]: [__WebviewWindow__]) => ({
+						listen: (arg: any) => window.listen(name, arg),
+						once: (arg: any) => window.once(name, arg),
+						emit: (arg: any) => window.emit(name, arg),
+					}),
+					get: (_, command: keyof __EventObj__<any>) => {
+						switch (command) {
+							case "listen":
+								return (arg: any) => TAURI_API_EVENT.listen(name, arg);
+							case "once":
+								return (arg: any) => TAURI_API_EVENT.once(name, arg);
+							case "emit":
+								return (arg: any) => TAURI_API_EVENT.emit(name, arg);
+						}
+					},
+				});
+			},
+		},
+	);
+}
@@ -1,4 +1,5 @@
 import { useState } from 'react';
+import { format, parseISO } from 'date-fns';
 import { useCreateEvent, useUpdateEvent } from '../queries';
 import type { Event, EventCreate, EventCategory, Priority, TimeMode, RecurringPattern, EventReminder } from '../types';
 
@@ -40,10 +41,10 @@ export default function EventModal({ event, onClose }: EventModalProps) {
   const [title, setTitle] = useState(event?.title || '');
   const [description, setDescription] = useState(event?.description || '');
   const [startDate, setStartDate] = useState(
-    event?.start_time ? event.start_time.split('T')[0] : ''
+    event?.start_time ? format(parseISO(event.start_time), 'yyyy-MM-dd') : ''
   );
   const [startTime, setStartTime] = useState(
-    event?.start_time ? event.start_time.split('T')[1]?.slice(0, 5) : ''
+    event?.start_time ? format(parseISO(event.start_time), 'HH:mm') : ''
   );
   const [timeMode, setTimeMode] = useState<TimeMode>(event?.time_mode || 'at_time');
   const [category, setCategory] = useState<EventCategory>(event?.category || 'personal');
@@ -75,12 +76,13 @@ export default function EventModal({ event, onClose }: EventModalProps) {
     const isAllDay = timeMode === 'all_day';
     const needsSpecificTime = timeMode === 'at_time';
 
+    // Entered as local time, sent as UTC
     const startDateTime = startDate
-      ? isAllDay
-        ? `${startDate}T00:00:00`
-        : needsSpecificTime && startTime
-          ? `${startDate}T${startTime}:00`
-          : `${startDate}T00:00:00`
+      ? new Date(
+          !isAllDay && needsSpecificTime && startTime
+            ? `${startDate}T${startTime}:00`
+            : `${startDate}T00:00:00`
+        ).toISOString()
       : null;
 
     const data: EventCreate = {
@@ -17,17 +17,32 @@ import TableCell from '@tiptap/extension-table-cell';
 import TableHeader from '@tiptap/extension-table-header';
 import Typography from '@tiptap/extension-typography';
 import CharacterCount from '@tiptap/extension-character-count';
-import { save } from '@tauri-apps/plugin-dialog';
-import { writeTextFile } from '@tauri-apps/plugin-fs';
-import { useUpdateNote, useCreateNote, useDeleteNote, useMoveNotesToFolder, useFolders } from '../queries';
+import {
+  useUpdateNote,
+  useCreateNote,
+  useDeleteNote,
+  useMoveNotesToFolder,
+  useFolders,
+  useNoteProperties,
+  useSetNoteProperty,
+} from '../queries';
+import { filesCommands, searchCommands } from '../services/tauriCommands';
+import { splitFrontMatter } from '../lib/frontMatter';
 import { format } from 'date-fns';
-import type { Note } from '../types';
+import type { Note, NoteProperty } from '../types';
 
 interface NoteEditorProps {
   note: Note;
   onNoteDeleted?: () => void;
 }
 
+function formatProperty(property: NoteProperty): string {
+  const value: unknown = JSON.parse(property.value);
+  if (value === null) return '—';
+  if (Array.isArray(value)) return value.map(String).join(', ');
+  return String(value);
+}
+
 interface ToolbarButton {
   label: string;
   title?: string;
@@ -44,6 +59,8 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
   const deleteNote = useDeleteNote();
   const moveNote = useMoveNotesToFolder();
   const { data: folders = [] } = useFolders();
+  const { data: properties = [] } = useNoteProperties(note.id);
+  const setProperty = useSetNoteProperty();
 
   const [title, setTitle] = useState(note.title);
   const [showMenu, setShowMenu] = useState(false);
@@ -53,10 +70,12 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
   const [showDetails, setShowDetails] = useState(false);
 
   const menuRef = useRef<HTMLDivElement>(null);
+  // Front matter isn't editable as rich text; it's kept aside and written back
+  const frontMatterRef = useRef(splitFrontMatter(note.content).header);
 
   const saveContent = useCallback(
     (content: string) => {
-      updateNote.mutate({ id: note.id, data: { content } });
+      updateNote.mutate({ id: note.id, data: { content: frontMatterRef.current + content } });
     },
     [note.id, updateNote]
   );
@@ -95,7 +114,7 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
       Typography,
       CharacterCount,
     ],
-    content: note.content,
+    content: splitFrontMatter(note.content).body,
     editorProps: {
       attributes: {
         class: 'tiptap',
@@ -106,13 +125,33 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
     },
   });
 
+  // The webview's spellchecker picks its dictionary from the lang attribute
   useEffect(() => {
-    if (editor && note.content !== editor.getHTML()) {
-      editor.commands.setContent(note.content);
+    const dom = editor?.view.dom;
+    if (!dom) return;
+    if (note.language) {
+      dom.setAttribute('lang', note.language);
+    } else {
+      dom.removeAttribute('lang');
+    }
+  }, [editor, note.language]);
+
+  useEffect(() => {
+    const { header, body } = splitFrontMatter(note.content);
+    frontMatterRef.current = header;
+    if (editor && body !== editor.getHTML()) {
+      editor.commands.setContent(body);
     }
     setTitle(note.title);
   }, [note.id, note.content, note.title, editor]);
 
+  // Feeds the "frequently opened" boost in search ranking
+  useEffect(() => {
+    searchCommands.recordOpen(note.id).catch((error) => {
+      console.error('[NoteEditor] Failed to record note open:', error);
+    });
+  }, [note.id]);
+
   useEffect(() => {
     const handleClick = (e: MouseEvent) => {
       if (menuRef.current && !menuRef.current.contains(e.target as Node)) {
@@ -166,18 +205,8 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
   };
 
   const handleExport = async (fmt: 'txt' | 'md' | 'html') => {
-    const plainText = editor?.getText() || '';
-    let content = '';
-    if (fmt === 'txt') content = `${note.title}\n\n${plainText}`;
-    else if (fmt === 'md') content = `# ${note.title}\n\n${plainText}`;
-    else content = `<!DOCTYPE html><html><head><title>${note.title}</title></head><body><h1>${note.title}</h1>${note.content}</body></html>`;
-
     try {
-      const filePath = await save({
-        defaultPath: `${note.title || 'Untitled'}.${fmt}`,
-        filters: [{ name: fmt.toUpperCase(), extensions: [fmt] }],
-      });
-      if (filePath) await writeTextFile(filePath, content);
+      await filesCommands.exportNote(note.id, fmt);
     } catch (err) {
       console.error('[Export]', err);
     }
@@ -400,6 +429,30 @@ export default function NoteEditor({ note, onNoteDeleted }: NoteEditorProps) {
             </div>
           </div>
 
+          {properties.length > 0 && (
+            <div className="details-section">
+              <h4>Properties</h4>
+              <div className="details-info">
+                {properties.map((property) => (
+                  <div className="info-row" key={property.key}>
+                    <span className="info-label">{property.key}</span>
+                    {property.kind === 'checkbox' ? (
+                      <input
+                        type="checkbox"
+                        checked={JSON.parse(property.value) === true}
+                        onChange={(e) =>
+                          setProperty.mutate({ id: note.id, key: property.key, value: e.target.checked })
+                        }
+                      />
+                    ) : (
+                      <span className="info-value">{formatProperty(property)}</span>
+                    )}
+                  </div>
+                ))}
+              </div>
+            </div>
+          )}
+
           <div className="details-section">
             <h4>Info</h4>
             <div className="details-info">
@@ -0,0 +1,8 @@
+// Splits a leading YAML front matter block (delimiters included) from the
+// rest of the note, matching front_matter::split on the Rust side. The editor
+// only sees the body; the block is put back when saving.
+export function splitFrontMatter(content: string): { header: string; body: string } {
+  const match = /^---\r?\n(?:[\s\S]*?\r?\n)?(?:---|\.\.\.)[ \t]*(?:\r?\n|$)/.exec(content);
+  if (!match) return { header: '', body: content };
+  return { header: match[0], body: content.slice(match[0].length) };
+}
@@ -3,10 +3,12 @@ import ReactDOM from 'react-dom/client';
 import { QueryClientProvider } from '@tanstack/react-query';
 import { ReactQueryDevtools } from '@tanstack/react-query-devtools';
 import { RouterProvider } from '@tanstack/react-router';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { queryClient } from './lib/queryClient';
 import { ThemeProvider } from './contexts/ThemeContext';
 import { AuthProvider } from './contexts/AuthContext';
 import { router } from './router';
+import RecoveryPage from './pages/RecoveryPage';
 import '@fontsource/space-grotesk/300.css';
 import '@fontsource/space-grotesk/400.css';
 import '@fontsource/space-grotesk/500.css';
@@ -15,15 +17,25 @@ import './styles/global.css';
 import './styles/app.css';
 import './styles/brainmap.css';
 
+// The recovery window opens instead of the app when the database can't be
+// opened, so it renders on its own without the router or auth
+const isRecoveryWindow = getCurrentWebviewWindow().label === 'recovery';
+
 ReactDOM.createRoot(document.getElementById('root')!).render(
   <React.StrictMode>
-    <QueryClientProvider client={queryClient}>
+    {isRecoveryWindow ? (
       <ThemeProvider>
-        <AuthProvider>
-          <RouterProvider router={router} />
-        </AuthProvider>
+        <RecoveryPage />
       </ThemeProvider>
-      <ReactQueryDevtools initialIsOpen={false} position="bottom" />
-    </QueryClientProvider>
+    ) : (
+      <QueryClientProvider client={queryClient}>
+        <ThemeProvider>
+          <AuthProvider>
+            <RouterProvider router={router} />
+          </AuthProvider>
+        </ThemeProvider>
+        <ReactQueryDevtools initialIsOpen={false} position="bottom" />
+      </QueryClientProvider>
+    )}
   </React.StrictMode>
 );
@@ -0,0 +1,76 @@
+import { useEffect, useState } from 'react';
+import { recoveryCommands } from '../services/tauriCommands';
+import type { StartupProblem } from '../types';
+
+const SUMMARY: Record<StartupProblem['kind'], string> = {
+  corrupt: 'The notes database is damaged',
+  locked: 'The notes database is in use by another program',
+  unavailable: "The notes database couldn't be opened",
+};
+
+// Shown in its own window instead of the app when the database fails to open
+export default function RecoveryPage() {
+  const [problem, setProblem] = useState<StartupProblem | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    recoveryCommands.getProblem().then(setProblem).catch(() => setProblem(null));
+  }, []);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    setError('');
+    try {
+      await action();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  if (!problem) {
+    return <div className="auth-page" />;
+  }
+
+  return (
+    <div className="auth-page">
+      <div className="auth-container">
+        <h1 className="auth-logo">VOYENA</h1>
+        <p className="auth-subtitle">{SUMMARY[problem.kind]}</p>
+
+        <div className="auth-form">
+          <div className="auth-error">{problem.message}</div>
+          {error && <div className="auth-error">{error}</div>}
+
+          {problem.kind === 'locked' && (
+            <button className="auth-button" disabled={busy} onClick={() => run(recoveryCommands.restart)}>
+              Try again
+            </button>
+          )}
+          {problem.backups.map((backup) => (
+            <button
+              key={backup.path}
+              className="auth-button"
+              disabled={busy}
+              onClick={() => run(() => recoveryCommands.restoreBackup(backup.path))}
+            >
+              Restore backup from {new Date(backup.created_at).toLocaleString()}
+            </button>
+          ))}
+          <button className="auth-button" disabled={busy} onClick={() => run(() => recoveryCommands.restoreBackup())}>
+            Restore from a backup file…
+          </button>
+        </div>
+
+        <div className="auth-links">
+          <button disabled={busy} onClick={() => run(recoveryCommands.openReadOnly)}>
+            Open read-only
+          </button>
+        </div>
+        <p className="auth-subtitle">{problem.database_path}</p>
+      </div>
+    </div>
+  );
+}
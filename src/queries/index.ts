@@ -3,10 +3,12 @@ export {
   useNotes,
   useNote,
   useSearchNotes,
+  useNoteProperties,
   useCreateNote,
   useUpdateNote,
   useDeleteNote,
   useMoveNotesToFolder,
+  useSetNoteProperty,
 } from './useNotes';
 
 // Folders
@@ -15,6 +17,7 @@ export {
   useFolder,
   useCreateFolder,
   useUpdateFolder,
+  useMoveFolder,
   useDeleteFolder,
 } from './useFolders';
 
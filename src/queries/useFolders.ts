@@ -82,6 +82,34 @@ export function useUpdateFolder() {
   });
 }
 
+export function useMoveFolder() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({
+      id,
+      newParentId,
+      merge,
+    }: {
+      id: string;
+      newParentId: string | null;
+      merge?: boolean;
+    }) => foldersCommands.move(id, newParentId, merge),
+    onSuccess: (folder, { id }) => {
+      // After a merge the moved folder is gone
+      if (folder.id !== id) {
+        queryClient.removeQueries({ queryKey: queryKeys.folders.detail(id) });
+      }
+    },
+    onSettled: () => {
+      // Notes below the folder may now belong to a different one after a merge,
+      // and folder-filtered searches cover a different subtree
+      queryClient.invalidateQueries({ queryKey: queryKeys.folders.all });
+      queryClient.invalidateQueries({ queryKey: queryKeys.notes.all });
+    },
+  });
+}
+
 export function useDeleteFolder() {
   const queryClient = useQueryClient();
 
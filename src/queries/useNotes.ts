@@ -37,6 +37,16 @@ export function useSearchNotes(query: string) {
   });
 }
 
+// Front matter properties. Kept apart from the note's detail key so saving
+// the body doesn't refetch them.
+export function useNoteProperties(id: string | null) {
+  return useQuery({
+    queryKey: ['notes', 'properties', id],
+    queryFn: () => (id ? notesCommands.getProperties(id) : []),
+    enabled: !!id,
+  });
+}
+
 // ============ Mutations ============
 
 export function useCreateNote() {
@@ -147,3 +157,19 @@ export function useMoveNotesToFolder() {
     },
   });
 }
+
+export function useSetNoteProperty() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({ id, key, value }: { id: string; key: string; value: unknown }) =>
+      notesCommands.setProperty(id, key, value),
+    onSuccess: (properties, { id }) => {
+      queryClient.setQueryData(['notes', 'properties', id], properties);
+    },
+    onSettled: () => {
+      // The note's content now has a different front matter block
+      queryClient.invalidateQueries({ queryKey: queryKeys.notes.all });
+    },
+  });
+}
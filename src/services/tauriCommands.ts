@@ -1,18 +1,61 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { supabase, isSupabaseConfigured } from './supabase';
 import type {
   Note, NoteCreate, NoteUpdate,
   Folder, FolderCreate, FolderUpdate,
-  Event, EventCreate, EventUpdate,
+  Event, EventCreate, EventUpdate, EventType,
   BrainMap, BrainMapCreate, BrainMapUpdate,
   BrainMapNode, BrainMapNodeCreate, BrainMapNodeUpdate,
-  BrainMapConnection, BrainMapConnectionCreate, BrainMapWithData
+  BrainMapConnection, BrainMapConnectionCreate, BrainMapWithData,
+  BrainMapVisibleData, BrainMapOpApplied, NoteNodeLink, NoteLinksChanged, NodeStyle, StylePreset, StylePresetCreate,
+  Job, JobProgress, ListQuery, Page, BrainMapSummary, BrainMapStats,
+  UnscheduledFilter, ReminderDue, Holiday, HolidayRegion, ProductivityMetrics,
+  InboxItem, TriageAction, QuickAddResult, TriageResult, ParsedDate,
+  LocaleSettings, WeekRange, AgendaDay, AttachmentDedupStats, SearchHit, NoteBookmark,
+  SmartFolder, SmartFolderCreate, SmartFolderUpdate, SearchRanking, PaletteColor, CatalogIcon,
+  EntityKind, MetadataEntry, NoteProperty, TodayView,
+  RecurrenceRule, TaskHistory, TaskOccurrence, TaskPlan,
+  Project, ProjectCreate, ProjectUpdate, ProjectItemKind, ProjectOverview,
+  Goal, GoalCreate, GoalUpdate, GoalProgress,
+  NoteComment, NoteCommentCreate, NoteCommentUpdate,
+  RemoteChanges, SyncReport, ConflictCopy, ConflictResolution, NoteDoc,
+  LanSyncConfig, LanPeer, LanSyncResult,
+  Attachment, StorageUsage, StorageSettings, BrokenLink,
+  LinkSettings, NoteRevision, LinkTarget, Embed,
+  OutlineHeading, MirrorConfig, WatchedFolder, SystemSearchSettings,
+  SharedContent, ShareResult, PendingShare, PlatformCapabilities,
+  BackgroundActivity, ActivityLevel,
+  StartupProblem, PanicReport, TrashSettings, PurgeReport,
+  NoteRestore, RestoreFolderAction, BrainMapViewport, NoteSortChoice,
+  DeletedNotePreview, ClipboardCaptureSettings, ClipboardCaptured,
+  Snippet, SnippetCreate, SnippetUpdate, Script, ScriptCreate, ScriptUpdate, Action,
+  SupportedFormat, ImportPreview
 } from '../types';
 
+// Fills in the fields a list query leaves out
+function toListQuery(query?: Partial<ListQuery>): ListQuery | null {
+  if (!query) return null;
+  return {
+    search: query.search ?? null,
+    sort: query.sort ?? null,
+    descending: query.descending ?? null,
+    limit: query.limit ?? null,
+    offset: query.offset ?? null,
+  };
+}
+
 // ============ Notes Commands ============
 
 export const notesCommands = {
-  async getAll(userId?: string, folderId?: string | null): Promise<Note[]> {
+  // With `recursive`, notes in subfolders of `folderId` are included too.
+  // Locally, a `sort` is remembered for the folder; without one its last choice is used.
+  async getAll(
+    userId?: string,
+    folderId?: string | null,
+    recursive = false,
+    sort?: NoteSortChoice
+  ): Promise<Note[]> {
     if (isSupabaseConfigured && userId) {
       let query = supabase
         .from('notes')
@@ -20,7 +63,26 @@ export const notesCommands = {
         .eq('user_id', userId)
         .is('deleted_at', null);
 
-      if (folderId) {
+      if (folderId && recursive) {
+        const { data: folders, error: foldersError } = await supabase
+          .from('folders')
+          .select('id, parent_id')
+          .eq('user_id', userId);
+        if (foldersError) throw foldersError;
+
+        const ids = new Set([folderId]);
+        let grew = true;
+        while (grew) {
+          grew = false;
+          for (const folder of folders ?? []) {
+            if (folder.parent_id && ids.has(folder.parent_id) && !ids.has(folder.id)) {
+              ids.add(folder.id);
+              grew = true;
+            }
+          }
+        }
+        query = query.in('folder_id', [...ids]);
+      } else if (folderId) {
         query = query.eq('folder_id', folderId);
       }
 
@@ -30,7 +92,15 @@ export const notesCommands = {
     }
 
     // Fallback to local SQLite
-    return invoke<Note[]>('get_notes', { folderId: folderId ?? null });
+    return invoke<Note[]>('get_notes', {
+      folderId: folderId ?? null,
+      recursive,
+      sort: sort ?? null,
+    });
+  },
+
+  async getSort(folderId: string | null): Promise<NoteSortChoice> {
+    return invoke<NoteSortChoice>('get_note_sort', { folderId });
   },
 
   async getById(id: string): Promise<Note | null> {
@@ -114,6 +184,37 @@ export const notesCommands = {
     return invoke<void>('delete_note', { id, hard });
   },
 
+  // Status folder_missing means the note is still in the trash; call again
+  // with an action, and a folderId for 'folder'
+  async restore(
+    id: string,
+    action?: RestoreFolderAction,
+    folderId?: string
+  ): Promise<NoteRestore> {
+    if (isSupabaseConfigured) {
+      const { data: note, error } = await supabase
+        .from('notes')
+        .update({ deleted_at: null, updated_at: new Date().toISOString() })
+        .eq('id', id)
+        .select()
+        .single();
+
+      if (error) throw error;
+      return { status: 'restored', note, folder_path: null };
+    }
+
+    return invoke<NoteRestore>('restore_note', {
+      id,
+      action: action ?? null,
+      folderId: folderId ?? null,
+    });
+  },
+
+  // Title, start of the text and original folder of a trashed note
+  async getDeletedPreview(id: string): Promise<DeletedNotePreview> {
+    return invoke<DeletedNotePreview>('get_deleted_note_preview', { id });
+  },
+
   async moveToFolder(noteIds: string[], folderId: string | null): Promise<void> {
     if (isSupabaseConfigured) {
       await supabase
@@ -126,6 +227,11 @@ export const notesCommands = {
     return invoke<void>('move_notes_to_folder', { noteIds, folderId });
   },
 
+  // The order notes show in when a folder is sorted manually
+  async reorder(noteIds: string[]): Promise<void> {
+    return invoke<void>('reorder_notes', { noteIds });
+  },
+
   async search(userId: string, query: string): Promise<Note[]> {
     if (isSupabaseConfigured && userId) {
       const { data, error } = await supabase
@@ -140,15 +246,145 @@ export const notesCommands = {
       return data ?? [];
     }
 
-    // Fallback: client-side search
-    const notes = await this.getAll();
-    const lowerQuery = query.toLowerCase();
-    return notes.filter(
-      (note) =>
-        note.title.toLowerCase().includes(lowerQuery) ||
-        note.content.toLowerCase().includes(lowerQuery) ||
-        note.tags.some((tag) => tag.toLowerCase().includes(lowerQuery))
-    );
+    const hits = await searchCommands.search(query);
+    return hits.map((hit) => hit.note);
+  },
+
+  // Typed entries of the note's YAML front matter; local database only
+  async getProperties(id: string): Promise<NoteProperty[]> {
+    if (isSupabaseConfigured) return [];
+    return invoke<NoteProperty[]>('get_note_properties', { id });
+  },
+
+  // Rewrites the front matter with `key` set to `value`; `undefined` removes
+  // it. Local database only.
+  async setProperty(id: string, key: string, value: unknown): Promise<NoteProperty[]> {
+    return invoke<NoteProperty[]>('set_note_property', {
+      id,
+      key,
+      value: value === undefined ? null : JSON.stringify(value),
+    });
+  },
+};
+
+// ============ Note Comment Commands ============
+
+// Margin comments that don't touch the note's content. Offsets are JS string
+// indices into the content as stored. Local database only.
+export const noteCommentCommands = {
+  async getAll(noteId: string): Promise<NoteComment[]> {
+    return invoke<NoteComment[]>('get_note_comments', { noteId });
+  },
+
+  async add(noteId: string, data: NoteCommentCreate): Promise<NoteComment> {
+    return invoke<NoteComment>('add_note_comment', { noteId, data });
+  },
+
+  async update(id: string, data: NoteCommentUpdate): Promise<NoteComment> {
+    return invoke<NoteComment>('update_note_comment', { id, data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_note_comment', { id });
+  },
+};
+
+// ============ Search Commands ============
+// Local database only; the index is kept current on every note write
+
+export const searchCommands = {
+  // Ranked matches with a highlighted snippet of the note text. Besides plain
+  // words and "exact phrases" the query takes tag:, folder:, before:, after:,
+  // lang: and is:pinned / is:published filters; a leading '-' excludes.
+  async search(query: string, limit?: number): Promise<SearchHit[]> {
+    return invoke<SearchHit[]>('search_notes', { query, limit: limit ?? null });
+  },
+
+  async getBacklinks(noteId: string): Promise<Note[]> {
+    return invoke<Note[]>('get_backlinks', { noteId });
+  },
+
+  // Broken wikilinks in one note, or all notes when noteId is omitted;
+  // `repair` points links at renamed notes' current titles
+  async checkLinks(noteId?: string, repair?: boolean): Promise<BrokenLink[]> {
+    return invoke<BrokenLink[]>('check_note_links', { noteId: noteId ?? null, repair: repair ?? null });
+  },
+
+  // Heading tree with offsets into the content, for the table of contents
+  async getOutline(id: string): Promise<OutlineHeading[]> {
+    return invoke<OutlineHeading[]>('get_note_outline', { id });
+  },
+
+  // Everything the note's ![[note#heading]] embeds show, nested embeds included
+  async resolveEmbeds(noteId: string): Promise<Embed[]> {
+    return invoke<Embed[]>('resolve_embeds', { noteId });
+  },
+
+  // Link autocomplete: notes whose title or alias matches what's been typed
+  async resolveLinkTarget(text: string, limit?: number): Promise<LinkTarget[]> {
+    return invoke<LinkTarget[]>('resolve_note_by_title_or_alias', { text, limit: limit ?? null });
+  },
+
+  // Whether renaming a note rewrites [[wikilinks]] to it in other notes
+  async getLinkSettings(): Promise<LinkSettings> {
+    return invoke<LinkSettings>('get_link_settings');
+  },
+
+  async setLinkSettings(settings: LinkSettings): Promise<void> {
+    return invoke<void>('set_link_settings', { settings });
+  },
+
+  // Snapshots taken before automatic edits such as link updates, newest first
+  async getRevisions(noteId: string): Promise<NoteRevision[]> {
+    return invoke<NoteRevision[]>('get_note_revisions', { noteId });
+  },
+
+  async getBookmarks(noteId: string): Promise<NoteBookmark[]> {
+    return invoke<NoteBookmark[]>('get_note_bookmarks', { noteId });
+  },
+
+  // Resolves to a job id; follow it with jobsCommands.onJobProgress
+  async rebuildIndex(): Promise<string> {
+    return invoke<string>('rebuild_search_index');
+  },
+
+  // Frequently opened notes rank higher
+  async recordOpen(noteId: string): Promise<void> {
+    if (isSupabaseConfigured) return;
+    return invoke<void>('record_note_open', { id: noteId });
+  },
+
+  async getRanking(): Promise<SearchRanking> {
+    return invoke<SearchRanking>('get_search_ranking');
+  },
+
+  async setRanking(weights: SearchRanking): Promise<void> {
+    return invoke<void>('set_search_ranking', { weights });
+  },
+};
+
+// ============ Smart Folder Commands ============
+// Saved searches; local database only
+
+export const smartFoldersCommands = {
+  async getAll(): Promise<SmartFolder[]> {
+    return invoke<SmartFolder[]>('get_smart_folders');
+  },
+
+  async create(data: SmartFolderCreate): Promise<SmartFolder> {
+    return invoke<SmartFolder>('create_smart_folder', { data });
+  },
+
+  async update(id: string, data: SmartFolderUpdate): Promise<SmartFolder> {
+    return invoke<SmartFolder>('update_smart_folder', { id, data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_smart_folder', { id });
+  },
+
+  async getNotes(id: string, limit?: number): Promise<SearchHit[]> {
+    return invoke<SearchHit[]>('get_smart_folder_notes', { id, limit: limit ?? null });
   },
 };
 
@@ -167,7 +403,13 @@ export const foldersCommands = {
       return data ?? [];
     }
 
-    return invoke<Folder[]>('get_folders');
+    const page = await this.getPage();
+    return page.items;
+  },
+
+  // Local database only
+  async getPage(query?: Partial<ListQuery>): Promise<Page<Folder>> {
+    return invoke<Page<Folder>>('get_folders', { query: toListQuery(query) });
   },
 
   async getById(id: string): Promise<Folder | null> {
@@ -253,6 +495,24 @@ export const foldersCommands = {
 
     return invoke<void>('delete_folder', { id });
   },
+
+  // Breadcrumbs from the top level down to the folder; local database only
+  async getPath(id: string): Promise<Folder[]> {
+    return invoke<Folder[]>('get_folder_path', { id });
+  },
+
+  // "Work/Projects/Alpha", creating missing folders unless `create` is false;
+  // local database only
+  async resolvePath(path: string, create = true): Promise<Folder | null> {
+    return invoke<Folder | null>('resolve_folder_path', { path, create });
+  },
+
+  // Moves a folder under another (null for the top level). With `merge`, a
+  // same-named folder already there absorbs it and is returned; otherwise
+  // that's a conflict. Local database only.
+  async move(id: string, newParentId: string | null, merge = false): Promise<Folder> {
+    return invoke<Folder>('move_folder', { id, newParentId, merge });
+  },
 };
 
 // ============ Events Commands ============
@@ -292,6 +552,23 @@ export const eventsCommands = {
     return invoke<Event | null>('get_event', { id });
   },
 
+  async getByType(userId: string | undefined, eventType: EventType): Promise<Event[]> {
+    if (isSupabaseConfigured && userId) {
+      const { data, error } = await supabase
+        .from('events')
+        .select('*')
+        .eq('user_id', userId)
+        .eq('event_type', eventType)
+        .is('deleted_at', null)
+        .order('start_time', { ascending: true });
+
+      if (error) throw error;
+      return data ?? [];
+    }
+
+    return invoke<Event[]>('get_events_by_type', { eventType });
+  },
+
   async create(userId: string, data: EventCreate): Promise<Event> {
     if (isSupabaseConfigured && userId) {
       const now = new Date().toISOString();
@@ -301,6 +578,7 @@ export const eventsCommands = {
           user_id: userId,
           title: data.title,
           description: data.description ?? null,
+          event_type: data.event_type ?? 'event',
           start_time: data.start_time ?? null,
           end_time: data.end_time ?? null,
           time_mode: data.time_mode ?? 'at_time',
@@ -315,6 +593,7 @@ export const eventsCommands = {
           is_recurring: data.is_recurring ?? false,
           recurring_pattern: data.recurring_pattern ?? null,
           reminders: data.reminders ?? [],
+          notes: data.notes ?? null,
           status: 'pending',
           has_scheduled_time: data.start_time != null,
           created_at: now,
@@ -337,6 +616,11 @@ export const eventsCommands = {
     };
 
     if (isSupabaseConfigured) {
+      // Giving an event a start time puts it on the calendar
+      if (data.start_time != null) {
+        Object.assign(updates, { has_scheduled_time: true });
+      }
+
       const { data: updated, error } = await supabase
         .from('events')
         .update(updates)
@@ -367,6 +651,76 @@ export const eventsCommands = {
     return invoke<void>('delete_event', { id, hard });
   },
 
+  // Keeps duration, reminders, tags and category; local database only
+  async duplicate(id: string, newStart: string): Promise<Event> {
+    return invoke<Event>('duplicate_event', { id, newStart });
+  },
+
+  // Repeats the latest event in the category starting now; null if there is none
+  async createLikeLast(category: string): Promise<Event | null> {
+    return invoke<Event | null>('create_event_like_last', { category });
+  },
+
+  // Both run in one transaction and return the events that moved
+  async shift(ids: string[], deltaMinutes: number): Promise<Event[]> {
+    return invoke<Event[]>('shift_events', { ids, deltaMinutes });
+  },
+
+  // `date` is YYYY-MM-DD; times of day and durations are kept
+  async moveToDate(ids: string[], date: string): Promise<Event[]> {
+    return invoke<Event[]>('move_events_to_date', { ids, date });
+  },
+
+  // Planner backlog: events with no time yet (local database only)
+  async getUnscheduled(filters?: Partial<UnscheduledFilter>): Promise<Event[]> {
+    return invoke<Event[]>('get_unscheduled_events', {
+      filters: filters
+        ? {
+            category: filters.category ?? null,
+            priority: filters.priority ?? null,
+            search: filters.search ?? null,
+            include_finished: filters.include_finished ?? null,
+            sort: filters.sort ?? null,
+          }
+        : null,
+    });
+  },
+
+  async schedule(id: string, start: string, end?: string | null): Promise<Event> {
+    return invoke<Event>('schedule_event', { id, start, end: end ?? null });
+  },
+
+  // The rest of this block is local database only and follows the locale
+  // settings. Dates are YYYY-MM-DD; ranges include both ends.
+  async getWeekRange(date: string): Promise<WeekRange> {
+    return invoke<WeekRange>('get_week_range', { date });
+  },
+
+  async getAgenda(start: string, end: string): Promise<AgendaDay[]> {
+    return invoke<AgendaDay[]>('get_agenda', { start, end });
+  },
+
+  // Today's events, due tasks, habits, edited notes and reminders; local
+  // database only
+  async getToday(): Promise<TodayView> {
+    return invoke<TodayView>('get_today_view');
+  },
+
+  // Local database only; `start` and `end` are YYYY-MM-DD, both inclusive
+  async getProductivityMetrics(start: string, end: string): Promise<ProductivityMetrics> {
+    return invoke<ProductivityMetrics>('get_productivity_metrics', { start, end });
+  },
+
+  // Local database only; null clears the travel buffer
+  async setTravelTime(id: string, travelMinutes: number | null): Promise<Event> {
+    return invoke<Event>('set_event_travel_time', { id, travelMinutes });
+  },
+
+  // Fires for reminders and "time to leave" alerts as they come due (local database only)
+  onReminderDue(handler: (reminder: ReminderDue) => void): Promise<UnlistenFn> {
+    return listen<ReminderDue>('reminder:due', (event) => handler(event.payload));
+  },
+
   async getByDateRange(userId: string, startDate: string, endDate: string): Promise<Event[]> {
     if (isSupabaseConfigured && userId) {
       const { data, error } = await supabase
@@ -391,6 +745,255 @@ export const eventsCommands = {
   },
 };
 
+// ============ Recurring Task Commands ============
+
+// Completions are tracked per occurrence; the task itself stays open. Local
+// database only.
+export const recurringTaskCommands = {
+  // null turns the task back into a one-off
+  async setRecurrence(id: string, rule: RecurrenceRule | null): Promise<Event> {
+    return invoke<Event>('set_task_recurrence', { id, rule });
+  },
+
+  // `start` and `end` are YYYY-MM-DD, both inclusive; defaults to the last 90 days
+  async getHistory(id: string, start?: string, end?: string): Promise<TaskHistory> {
+    return invoke<TaskHistory>('get_task_history', {
+      id,
+      start: start ?? null,
+      end: end ?? null,
+    });
+  },
+
+  async completeOccurrence(id: string, date: string): Promise<TaskOccurrence> {
+    return invoke<TaskOccurrence>('complete_task_occurrence', { id, date });
+  },
+
+  async reopenOccurrence(id: string, date: string): Promise<TaskOccurrence> {
+    return invoke<TaskOccurrence>('reopen_task_occurrence', { id, date });
+  },
+};
+
+// ============ Task Dependency Commands ============
+
+// Local database only
+export const taskDependencyCommands = {
+  // `taskId` waits on `dependsOnId`; rejected if that would close a loop
+  async add(taskId: string, dependsOnId: string): Promise<void> {
+    return invoke<void>('add_task_dependency', { taskId, dependsOnId });
+  },
+
+  async remove(taskId: string, dependsOnId: string): Promise<void> {
+    return invoke<void>('remove_task_dependency', { taskId, dependsOnId });
+  },
+
+  // Open tasks in dependency order, limited to a project's tasks when `projectId` is given
+  async getPlan(projectId?: string): Promise<TaskPlan> {
+    return invoke<TaskPlan>('get_task_plan', { project: projectId ?? null });
+  },
+};
+
+// ============ Project Commands ============
+
+// Local database only
+export const projectCommands = {
+  async getAll(): Promise<Project[]> {
+    return invoke<Project[]>('get_projects');
+  },
+
+  async create(data: ProjectCreate): Promise<Project> {
+    return invoke<Project>('create_project', { data });
+  },
+
+  async update(id: string, data: ProjectUpdate): Promise<Project> {
+    return invoke<Project>('update_project', { id, data });
+  },
+
+  // Linked notes, events and maps are kept
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_project', { id });
+  },
+
+  // Tasks are linked as 'event'
+  async link(projectId: string, itemType: ProjectItemKind, itemId: string): Promise<void> {
+    return invoke<void>('link_project_item', { projectId, itemType, itemId });
+  },
+
+  async unlink(projectId: string, itemType: ProjectItemKind, itemId: string): Promise<void> {
+    return invoke<void>('unlink_project_item', { projectId, itemType, itemId });
+  },
+
+  async getOverview(id: string): Promise<ProjectOverview> {
+    return invoke<ProjectOverview>('get_project_overview', { id });
+  },
+};
+
+// ============ Goal Commands ============
+
+// Local database only
+export const goalCommands = {
+  async getAll(): Promise<Goal[]> {
+    return invoke<Goal[]>('get_goals');
+  },
+
+  // Count goals need a `target`
+  async create(data: GoalCreate): Promise<Goal> {
+    return invoke<Goal>('create_goal', { data });
+  },
+
+  async update(id: string, data: GoalUpdate): Promise<Goal> {
+    return invoke<Goal>('update_goal', { id, data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_goal', { id });
+  },
+
+  // Links a task or recurring task (habit) to the goal
+  async link(goalId: string, eventId: string): Promise<void> {
+    return invoke<void>('link_goal_item', { goalId, eventId });
+  },
+
+  async unlink(goalId: string, eventId: string): Promise<void> {
+    return invoke<void>('unlink_goal_item', { goalId, eventId });
+  },
+
+  async getProgress(id: string): Promise<GoalProgress> {
+    return invoke<GoalProgress>('get_goal_progress', { id });
+  },
+};
+
+// ============ Date Parsing Commands ============
+
+// Shared parser for quick-add, due dates and reminder dialogs; runs locally
+// even with Supabase configured
+export const dateCommands = {
+  // "next friday 3pm", "in 2 weeks", "5/6"; null when not understood
+  async parse(
+    text: string,
+    reference?: string,
+    locale: string = navigator.language,
+  ): Promise<ParsedDate | null> {
+    return invoke<ParsedDate | null>('parse_date_expression', {
+      text,
+      reference: reference ?? null,
+      locale,
+    });
+  },
+};
+
+// ============ Inbox Commands ============
+
+// Local database only
+export const inboxCommands = {
+  // Entries with an @date become tasks right away; the rest wait in the inbox.
+  // A /Folder/Path token picks the folder used when filing as a note.
+  async quickAdd(text: string): Promise<QuickAddResult> {
+    return invoke<QuickAddResult>('quick_add', { text });
+  },
+
+  async getAll(): Promise<InboxItem[]> {
+    return invoke<InboxItem[]>('get_inbox_items');
+  },
+
+  async triage(id: string, action: TriageAction): Promise<TriageResult> {
+    return invoke<TriageResult>('triage_inbox_item', { id, action });
+  },
+};
+
+// ============ Holiday Commands ============
+
+// Local database only; the calendars are bundled with the app
+export const holidayCommands = {
+  async getRegions(): Promise<HolidayRegion[]> {
+    return invoke<HolidayRegion[]>('get_holiday_regions');
+  },
+
+  async setRegions(codes: string[]): Promise<void> {
+    return invoke<void>('set_holiday_regions', { codes });
+  },
+
+  // `start` and `end` are YYYY-MM-DD, both inclusive
+  async getInRange(start: string, end: string): Promise<Holiday[]> {
+    return invoke<Holiday[]>('get_holidays', { start, end });
+  },
+};
+
+// ============ Review Commands ============
+
+// Local database only
+export const reviewCommands = {
+  // `week` is an ISO week (2024-W18) or any date in it; regenerating replaces
+  // that week's note in the Reviews folder
+  async generateWeekly(week: string): Promise<Note> {
+    return invoke<Note>('generate_weekly_review', { week });
+  },
+};
+
+// ============ Metadata Commands ============
+
+// Arbitrary fields on notes, events and folders, stored as JSON. Local
+// database only.
+export const metadataCommands = {
+  async get(entityType: EntityKind, entityId: string): Promise<MetadataEntry[]> {
+    return invoke<MetadataEntry[]>('get_metadata', { entityType, entityId });
+  },
+
+  // Fields as an object with their values parsed
+  async getValues(entityType: EntityKind, entityId: string): Promise<Record<string, unknown>> {
+    const entries = await metadataCommands.get(entityType, entityId);
+    return Object.fromEntries(entries.map((entry) => [entry.key, JSON.parse(entry.value)]));
+  },
+
+  // `undefined` removes the field
+  async set(
+    entityType: EntityKind,
+    entityId: string,
+    key: string,
+    value: unknown
+  ): Promise<MetadataEntry[]> {
+    return invoke<MetadataEntry[]>('set_metadata', {
+      entityType,
+      entityId,
+      key,
+      value: value === undefined ? null : JSON.stringify(value),
+    });
+  },
+
+  async remove(entityType: EntityKind, entityId: string, key: string): Promise<MetadataEntry[]> {
+    return metadataCommands.set(entityType, entityId, key, undefined);
+  },
+};
+
+// ============ Palette Commands ============
+
+// Colors and icons offered by the pickers; built-ins first, then the user's
+// own additions. Local database only.
+export const paletteCommands = {
+  async getColors(): Promise<PaletteColor[]> {
+    return invoke<PaletteColor[]>('get_color_palette');
+  },
+
+  async addColor(name: string, hex: string): Promise<PaletteColor[]> {
+    return invoke<PaletteColor[]>('add_palette_color', { name, hex });
+  },
+
+  async removeColor(hex: string): Promise<PaletteColor[]> {
+    return invoke<PaletteColor[]>('remove_palette_color', { hex });
+  },
+
+  async getIcons(): Promise<CatalogIcon[]> {
+    return invoke<CatalogIcon[]>('get_icon_catalog');
+  },
+
+  async addIcon(id: string, label: string, glyph: string): Promise<CatalogIcon[]> {
+    return invoke<CatalogIcon[]>('add_catalog_icon', { id, label, glyph });
+  },
+
+  async removeIcon(id: string): Promise<CatalogIcon[]> {
+    return invoke<CatalogIcon[]>('remove_catalog_icon', { id });
+  },
+};
+
 // ============ Settings Commands ============
 
 export const settingsCommands = {
@@ -401,6 +1004,15 @@ export const settingsCommands = {
   async set(key: string, value: string): Promise<void> {
     return invoke<void>('set_setting', { key, value });
   },
+
+  // First day of week, 12/24h clock and date order (local database only)
+  async getLocale(): Promise<LocaleSettings> {
+    return invoke<LocaleSettings>('get_locale_settings');
+  },
+
+  async setLocale(settings: LocaleSettings): Promise<void> {
+    return invoke<void>('set_locale_settings', { settings });
+  },
 };
 
 // ============ Brain Map Commands ============
@@ -419,7 +1031,13 @@ export const brainMapCommands = {
       return data ?? [];
     }
 
-    return invoke<BrainMap[]>('get_brain_maps');
+    const page = await this.getPage();
+    return page.items.map((summary) => summary.brain_map);
+  },
+
+  // Local database only
+  async getPage(query?: Partial<ListQuery>): Promise<Page<BrainMapSummary>> {
+    return invoke<Page<BrainMapSummary>>('get_brain_maps', { query: toListQuery(query) });
   },
 
   async getById(id: string): Promise<BrainMapWithData | null> {
@@ -464,6 +1082,22 @@ export const brainMapCommands = {
     return invoke<BrainMapWithData | null>('get_brain_map', { id });
   },
 
+  // Local database only
+  async getStats(id: string): Promise<BrainMapStats | null> {
+    return invoke<BrainMapStats | null>('get_brain_map_stats', { id });
+  },
+
+  // Collapsed branches are folded away on the Rust side. Supabase has no
+  // equivalent, so there the full map comes back with nothing hidden.
+  async getVisibleById(id: string): Promise<BrainMapVisibleData | null> {
+    if (isSupabaseConfigured) {
+      const data = await this.getById(id);
+      return data && { ...data, hidden_descendants: {} };
+    }
+
+    return invoke<BrainMapVisibleData | null>('get_brain_map_visible', { id });
+  },
+
   async create(userId: string, data: BrainMapCreate): Promise<BrainMapWithData> {
     if (isSupabaseConfigured && userId) {
       const now = new Date().toISOString();
@@ -527,10 +1161,12 @@ export const brainMapCommands = {
   },
 
   async update(id: string, data: BrainMapUpdate): Promise<BrainMap> {
-    const updates = {
-      ...data,
-      updated_at: new Date().toISOString(),
-    };
+    // Viewport fields alone don't count as an edit, matching the backend
+    const { viewport_x, viewport_y, viewport_zoom, ...content } = data;
+    const viewportOnly = Object.values(content).every((value) => value == null);
+    const updates = viewportOnly
+      ? { viewport_x, viewport_y, viewport_zoom }
+      : { ...data, updated_at: new Date().toISOString() };
 
     if (isSupabaseConfigured) {
       const { data: updated, error } = await supabase
@@ -547,6 +1183,20 @@ export const brainMapCommands = {
     return invoke<BrainMap>('update_brain_map', { id, data });
   },
 
+  // Safe to call on every pan/zoom frame; the backend only writes once the
+  // viewport settles, and the map's updated_at isn't touched
+  async updateViewport(id: string, viewport: BrainMapViewport): Promise<void> {
+    if (isSupabaseConfigured) {
+      await supabase
+        .from('brain_maps')
+        .update({ viewport_x: viewport.x, viewport_y: viewport.y, viewport_zoom: viewport.zoom })
+        .eq('id', id);
+      return;
+    }
+
+    return invoke<void>('update_brain_map_viewport', { id, viewport });
+  },
+
   async delete(id: string, hard: boolean = false): Promise<void> {
     if (isSupabaseConfigured) {
       if (hard) {
@@ -565,6 +1215,38 @@ export const brainMapCommands = {
 
     return invoke<void>('delete_brain_map', { id, hard });
   },
+
+  async restore(id: string): Promise<void> {
+    if (isSupabaseConfigured) {
+      await supabase
+        .from('brain_maps')
+        .update({ deleted_at: null, updated_at: new Date().toISOString() })
+        .eq('id', id);
+      return;
+    }
+
+    return invoke<void>('restore_brain_map', { id });
+  },
+
+  // Renders from the local database, whatever the canvas zoom is. Returns the
+  // saved path, or null if the save dialog was cancelled.
+  async exportToFile(
+    id: string,
+    format: 'svg' | 'png' | 'graphml' | 'xmind'
+  ): Promise<string | null> {
+    return invoke<string | null>('export_brain_map_image', { id, format });
+  },
+
+  // The edit log only exists in the local database; null means nothing to undo/redo
+  async undo(mapId: string): Promise<BrainMapOpApplied | null> {
+    if (isSupabaseConfigured) return null;
+    return invoke<BrainMapOpApplied | null>('undo_brain_map_op', { mapId });
+  },
+
+  async redo(mapId: string): Promise<BrainMapOpApplied | null> {
+    if (isSupabaseConfigured) return null;
+    return invoke<BrainMapOpApplied | null>('redo_brain_map_op', { mapId });
+  },
 };
 
 // ============ Brain Map Node Commands ============
@@ -688,6 +1370,46 @@ export const brainMapNodeCommands = {
 
     return invoke<void>('update_node_positions', { updates });
   },
+
+  async getLinkedToNote(noteId: string): Promise<NoteNodeLink[]> {
+    if (isSupabaseConfigured) {
+      const { data, error } = await supabase
+        .from('brain_map_nodes')
+        .select('id, label, brain_map_id, brain_maps!inner(title, deleted_at)')
+        .eq('linked_note_id', noteId)
+        .is('brain_maps.deleted_at', null);
+      if (error) throw error;
+      type Row = { id: string; label: string; brain_map_id: string; brain_maps: { title: string } };
+      return ((data ?? []) as unknown as Row[]).map((row) => ({
+        node_id: row.id,
+        node_label: row.label,
+        brain_map_id: row.brain_map_id,
+        brain_map_title: row.brain_maps.title,
+      }));
+    }
+
+    return invoke<NoteNodeLink[]>('get_nodes_linked_to_note', { noteId });
+  },
+
+  async linkNote(noteId: string, nodeId: string): Promise<BrainMapNode> {
+    if (isSupabaseConfigured) {
+      const { data: node, error } = await supabase
+        .from('brain_map_nodes')
+        .update({ linked_note_id: noteId, updated_at: new Date().toISOString() })
+        .eq('id', nodeId)
+        .select()
+        .single();
+      if (error) throw error;
+      return node;
+    }
+
+    return invoke<BrainMapNode>('link_note_to_node', { noteId, nodeId });
+  },
+
+  // Fires for each note a node gets linked to or unlinked from (local database only)
+  onNoteLinksChanged(handler: (change: NoteLinksChanged) => void): Promise<UnlistenFn> {
+    return listen<NoteLinksChanged>('note:links-changed', (event) => handler(event.payload));
+  },
 };
 
 // ============ Brain Map Connection Commands ============
@@ -726,6 +1448,21 @@ export const brainMapConnectionCommands = {
     return invoke<BrainMapConnection>('create_brain_map_connection', { data });
   },
 
+  async getBetween(a: string, b: string): Promise<BrainMapConnection[]> {
+    if (isSupabaseConfigured) {
+      const { data, error } = await supabase
+        .from('brain_map_connections')
+        .select('*')
+        .or(`and(source_node_id.eq.${a},target_node_id.eq.${b}),and(source_node_id.eq.${b},target_node_id.eq.${a})`)
+        .order('created_at', { ascending: true });
+
+      if (error) throw error;
+      return data ?? [];
+    }
+
+    return invoke<BrainMapConnection[]>('get_connections_between', { a, b });
+  },
+
   async delete(id: string): Promise<void> {
     if (isSupabaseConfigured) {
       await supabase.from('brain_map_connections').delete().eq('id', id);
@@ -735,3 +1472,454 @@ export const brainMapConnectionCommands = {
     return invoke<void>('delete_brain_map_connection', { id });
   },
 };
+
+// ============ Style Preset Commands ============
+
+export const stylePresetCommands = {
+  async getAll(): Promise<StylePreset[]> {
+    return invoke<StylePreset[]>('get_style_presets');
+  },
+
+  async create(data: StylePresetCreate): Promise<StylePreset> {
+    return invoke<StylePreset>('create_style_preset', { data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_style_preset', { id });
+  },
+
+  // One call for a whole branch instead of one update per node
+  async applyToSubtree(nodeId: string, style: NodeStyle): Promise<BrainMapNode[]> {
+    return invoke<BrainMapNode[]>('apply_style_to_subtree', { nodeId, style });
+  },
+};
+
+// ============ Attachment Commands ============
+// Local database only
+
+export const attachmentsCommands = {
+  // Identical files attached to several notes are stored once
+  async getDedupStats(): Promise<AttachmentDedupStats> {
+    return invoke<AttachmentDedupStats>('get_attachment_dedup_stats');
+  },
+
+  // Picks the file in a native dialog, confirming files over the size
+  // warning; null if the user cancelled either
+  async add(noteId: string): Promise<Attachment | null> {
+    return invoke<Attachment | null>('add_attachment', { noteId });
+  },
+
+  async getStorageUsage(): Promise<StorageUsage> {
+    return invoke<StorageUsage>('get_storage_usage');
+  },
+
+  async setStorageSettings(settings: StorageSettings): Promise<void> {
+    return invoke<void>('set_storage_settings', { settings });
+  },
+};
+
+// ============ File Commands ============
+// Native dialogs are opened on the Rust side; each call resolves to the
+// chosen path, or null if the user cancelled.
+
+export const filesCommands = {
+  async exportNote(id: string, format: 'txt' | 'md' | 'html'): Promise<string | null> {
+    return invoke<string | null>('export_note_to_file', { id, format });
+  },
+
+  async backupDatabase(): Promise<string | null> {
+    return invoke<string | null>('backup_database');
+  },
+};
+
+// ============ Job Commands ============
+// Imports and exports are queued and run in the background, surviving an app
+// restart. Each call resolves to a job id (null if the dialog was cancelled);
+// follow it with onJobProgress until the job ends with its report.
+
+export const jobsCommands = {
+  async exportVaultArchive(): Promise<string | null> {
+    return invoke<string | null>('export_vault_archive');
+  },
+
+  async exportMarkdownFolder(): Promise<string | null> {
+    return invoke<string | null>('export_markdown_folder');
+  },
+
+  async exportEnex(): Promise<string | null> {
+    return invoke<string | null>('export_enex');
+  },
+
+  // `start` and `end` are YYYY-MM-DD, both inclusive
+  async exportEventsIcs(start?: string, end?: string): Promise<string | null> {
+    return invoke<string | null>('export_events_ics', { start: start ?? null, end: end ?? null });
+  },
+
+  // The folder and its subfolders as linked HTML pages with an index.html.
+  // Returns the job id, or null if the folder dialog was cancelled.
+  async exportFolderSite(folderId: string): Promise<string | null> {
+    return invoke<string | null>('export_folder_site', { folderId });
+  },
+
+  async importNotes(folderId?: string | null): Promise<string | null> {
+    return invoke<string | null>('import_notes_from_files', { folderId: folderId ?? null });
+  },
+
+  // Formats from listSupportedFormats with direction "import"; opens a file
+  // or folder picker depending on the format's target
+  async importFormat(format: string, folderId?: string | null): Promise<string | null> {
+    return invoke<string | null>('import_format', { format, folderId: folderId ?? null });
+  },
+
+  // For formats listed with `preview`: picks what to import like importFormat,
+  // then resolves to what the import would create (null if the dialog was
+  // cancelled). Nothing is imported until confirmImport is called with its id.
+  async previewImport(format: string, folderId?: string | null): Promise<ImportPreview | null> {
+    return invoke<ImportPreview | null>('preview_import', { format, folderId: folderId ?? null });
+  },
+
+  // Queues a previewed import; resolves to the job id
+  async confirmImport(id: string): Promise<string> {
+    return invoke<string>('confirm_import', { id });
+  },
+
+  // Formats from listSupportedFormats with direction "export"
+  async exportFormat(format: string): Promise<string | null> {
+    return invoke<string | null>('export_format', { format });
+  },
+
+  // A format that works both ways is listed for each direction under one id
+  async listSupportedFormats(): Promise<SupportedFormat[]> {
+    return invoke<SupportedFormat[]>('list_supported_formats');
+  },
+
+  async list(limit?: number): Promise<Job[]> {
+    return invoke<Job[]>('list_jobs', { limit: limit ?? null });
+  },
+
+  async cancel(id: string): Promise<boolean> {
+    return invoke<boolean>('cancel_job', { id });
+  },
+
+  onJobProgress(handler: (progress: JobProgress) => void): Promise<UnlistenFn> {
+    return listen<JobProgress>('job:progress', (event) => handler(event.payload));
+  },
+};
+
+// ============ Sync Commands ============
+
+// Local database only
+export const syncCommands = {
+  // Applies another device's changed notes and events; concurrent edits that
+  // can't both win come back as conflict copies
+  async mergeRemoteChanges(changes: RemoteChanges): Promise<SyncReport> {
+    return invoke<SyncReport>('merge_remote_changes', { changes });
+  },
+
+  // The note's content document, to send in RemoteChanges.note_docs so
+  // edits from both devices merge within the text
+  async getNoteCrdt(noteId: string): Promise<NoteDoc> {
+    return invoke<NoteDoc>('get_note_crdt', { noteId });
+  },
+
+  async listConflictCopies(): Promise<ConflictCopy[]> {
+    return invoke<ConflictCopy[]>('list_conflict_copies');
+  },
+
+  async resolveConflictCopy(id: string, resolution: ConflictResolution): Promise<void> {
+    return invoke<void>('resolve_conflict_copy', { id, resolution });
+  },
+};
+
+// ============ LAN Sync Commands ============
+
+// Local database only
+export const lanSyncCommands = {
+  async getConfig(): Promise<LanSyncConfig | null> {
+    return invoke<LanSyncConfig | null>('get_lan_sync_config');
+  },
+
+  // The passphrase goes to the OS keychain; leave it out to keep the current one
+  async setConfig(config: LanSyncConfig, passphrase?: string): Promise<void> {
+    return invoke<void>('set_lan_sync_config', { config, passphrase: passphrase ?? null });
+  },
+
+  async getPeers(): Promise<LanPeer[]> {
+    return invoke<LanPeer[]>('get_lan_peers');
+  },
+
+  async syncWith(deviceId: string): Promise<LanSyncResult> {
+    return invoke<LanSyncResult>('sync_with_lan_peer', { deviceId });
+  },
+
+  // Fires when a peer syncs with this device
+  onReceived(handler: (result: LanSyncResult) => void): Promise<UnlistenFn> {
+    return listen<LanSyncResult>('lan-sync:received', (event) => handler(event.payload));
+  },
+};
+
+// ============ Markdown Mirror Commands ============
+
+export const mirrorCommands = {
+  async getConfig(): Promise<MirrorConfig | null> {
+    return invoke<MirrorConfig | null>('get_mirror_config');
+  },
+
+  // Enabling needs a directory picked with pickDirectory first
+  async setEnabled(enabled: boolean): Promise<void> {
+    return invoke<void>('set_mirror_config', { enabled });
+  },
+
+  // Picks the directory in a native dialog; null if cancelled
+  async pickDirectory(): Promise<MirrorConfig | null> {
+    return invoke<MirrorConfig | null>('pick_mirror_directory');
+  },
+};
+
+// ============ Watched Folder Commands ============
+
+// Two-way sync between an app folder and a directory of .md files
+export const watchedFolderCommands = {
+  async getAll(): Promise<WatchedFolder[]> {
+    return invoke<WatchedFolder[]>('get_watched_folders');
+  },
+
+  // Picks the directory in a native dialog; null if cancelled
+  async watch(folderId: string): Promise<WatchedFolder | null> {
+    return invoke<WatchedFolder | null>('watch_folder', { folderId });
+  },
+
+  // Notes and files both stay where they are
+  async unwatch(folderId: string): Promise<void> {
+    return invoke<void>('unwatch_folder', { folderId });
+  },
+};
+
+// ============ System Search Commands ============
+
+// Spotlight / Windows Search results open notes through voyena://note/{id}
+export const systemSearchCommands = {
+  async getSettings(): Promise<SystemSearchSettings> {
+    return invoke<SystemSearchSettings>('get_system_search_settings');
+  },
+
+  async setSettings(settings: SystemSearchSettings): Promise<void> {
+    return invoke<void>('set_system_search_settings', { settings });
+  },
+
+  // The note the app was launched to open, if any; only returned once
+  async takeLaunchNote(): Promise<string | null> {
+    return invoke<string | null>('take_launch_note_link');
+  },
+
+  // Fires with the note id when a voyena://note link is opened while running
+  onOpenNote(handler: (noteId: string) => void): Promise<UnlistenFn> {
+    return listen<string>('note:open', (event) => handler(event.payload));
+  },
+};
+
+// ============ Share Target Commands ============
+
+// Content shared from other apps becomes a note or an inbox item
+export const shareCommands = {
+  // `payload.files` is the token from onFiles or getPending
+  async receive(payload: SharedContent): Promise<ShareResult> {
+    return invoke<ShareResult>('receive_shared_content', { payload });
+  },
+
+  // Shared files not saved yet, e.g. ones that arrived at launch
+  async getPending(): Promise<PendingShare[]> {
+    return invoke<PendingShare[]>('get_pending_shares');
+  },
+
+  // Windows only: "Send to Voyena" on the Explorer menu for files
+  async setMenu(enabled: boolean): Promise<void> {
+    return invoke<void>('set_share_menu', { enabled });
+  },
+
+  // Fires when content arrives through a voyena://share link
+  onReceived(handler: (result: ShareResult) => void): Promise<UnlistenFn> {
+    return listen<ShareResult>('share:received', (event) => handler(event.payload));
+  },
+
+  // Fires when files arrive through the menu, to save with receive
+  onFiles(handler: (share: PendingShare) => void): Promise<UnlistenFn> {
+    return listen<PendingShare>('share:files', (event) => handler(event.payload));
+  },
+};
+
+// ============ Clipboard Capture Commands ============
+
+// Desktop only: a global shortcut appends the clipboard to today's daily note
+export const clipboardCaptureCommands = {
+  async getSettings(): Promise<ClipboardCaptureSettings> {
+    return invoke<ClipboardCaptureSettings>('get_clipboard_capture_settings');
+  },
+
+  // Rejects a shortcut that doesn't parse or that another app holds
+  async setSettings(settings: ClipboardCaptureSettings): Promise<void> {
+    return invoke<void>('set_clipboard_capture_settings', { settings });
+  },
+
+  // The day's note (YYYY-MM-DD), created if missing; today by default
+  async getDailyNote(date?: string): Promise<Note> {
+    return invoke<Note>('get_daily_note', { date: date ?? null });
+  },
+
+  // Fires after each capture, for the confirming notification
+  onCaptured(handler: (captured: ClipboardCaptured) => void): Promise<UnlistenFn> {
+    return listen<ClipboardCaptured>('clipboard:captured', (event) => handler(event.payload));
+  },
+};
+
+// ============ Paste Commands ============
+
+// Converted in Rust so rich text pastes the same on every platform
+export const pasteCommands = {
+  async htmlToMarkdown(html: string): Promise<string> {
+    return invoke<string>('convert_html_to_markdown', { html });
+  },
+};
+
+// ============ Snippet Commands ============
+// Text typed by a short trigger; synced with notes and events
+
+export const snippetCommands = {
+  async getAll(): Promise<Snippet[]> {
+    return invoke<Snippet[]>('get_snippets');
+  },
+
+  // Rejects a trigger another snippet already uses
+  async create(data: SnippetCreate): Promise<Snippet> {
+    return invoke<Snippet>('create_snippet', { data });
+  },
+
+  async update(id: string, data: SnippetUpdate): Promise<Snippet> {
+    return invoke<Snippet>('update_snippet', { id, data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_snippet', { id });
+  },
+
+  // The text to insert, with {{date}} and {{time}} filled in; null when no
+  // snippet has the trigger
+  async expand(trigger: string): Promise<string | null> {
+    return invoke<string | null>('expand_snippet', { trigger });
+  },
+};
+
+// ============ Script Commands ============
+// User rhai scripts, run in a sandbox when notes and events change. A script
+// subscribes by defining on_note_created(note), on_note_updated(note),
+// on_note_deleted(id), on_event_created(event) or on_event_updated(event),
+// and can call create_note(title, content), tag_note(id, tag) and
+// schedule_event(title, when). A failed run is kept as last_error.
+
+export const scriptCommands = {
+  async getAll(): Promise<Script[]> {
+    return invoke<Script[]>('get_scripts');
+  },
+
+  // Rejects source that doesn't parse
+  async create(data: ScriptCreate): Promise<Script> {
+    return invoke<Script>('create_script', { data });
+  },
+
+  async update(id: string, data: ScriptUpdate): Promise<Script> {
+    return invoke<Script>('update_script', { id, data });
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke<void>('delete_script', { id });
+  },
+};
+
+// ============ Action Registry Commands ============
+
+// Every backend command with a JSON Schema for each argument; commands are
+// listed as soon as they're registered
+export const actionCommands = {
+  async list(): Promise<Action[]> {
+    return invoke<Action[]>('list_actions');
+  },
+
+  // Runs an action with arguments keyed by ActionParam.name
+  async run<T = unknown>(id: string, args: Record<string, unknown> = {}): Promise<T> {
+    return invoke<T>(id, args);
+  },
+};
+
+// ============ Platform Commands ============
+
+// Which subsystems this build runs; mobile builds leave the directory and
+// network ones out and hand reminders to the OS
+export const platformCommands = {
+  async getCapabilities(): Promise<PlatformCapabilities> {
+    return invoke<PlatformCapabilities>('get_platform_capabilities');
+  },
+};
+
+// ============ Background Activity Commands ============
+
+// Turning activity down pauses feeds, email, LAN sync and system search
+// ('reduced'), or everything but work the user starts ('paused')
+export const backgroundActivityCommands = {
+  async get(): Promise<BackgroundActivity> {
+    return invoke<BackgroundActivity>('get_background_activity');
+  },
+
+  async set(level: ActivityLevel): Promise<BackgroundActivity> {
+    return invoke<BackgroundActivity>('set_background_activity', { level });
+  },
+};
+
+// ============ Startup Recovery Commands ============
+
+// For the recovery window shown when the database can't be opened
+export const recoveryCommands = {
+  async getProblem(): Promise<StartupProblem | null> {
+    return invoke<StartupProblem | null>('get_startup_problem');
+  },
+
+  // Safe mode: the main window comes back with nothing saved
+  async openReadOnly(): Promise<void> {
+    return invoke<void>('open_database_read_only');
+  },
+
+  // Restarts on the backup; without a path a file picker opens first
+  async restoreBackup(path?: string): Promise<void> {
+    return invoke<void>('restore_database_backup', { path: path ?? null });
+  },
+
+  async restart(): Promise<void> {
+    return invoke<void>('restart_app');
+  },
+};
+
+// ============ Diagnostics ============
+
+export const diagnosticsEvents = {
+  // Fires when the backend panics, e.g. inside a command whose call then fails
+  onPanic(handler: (report: PanicReport) => void): Promise<UnlistenFn> {
+    return listen<PanicReport>('app:panic', (event) => handler(event.payload));
+  },
+};
+
+// ============ Trash ============
+
+export const trashCommands = {
+  // Deletes for good whatever was trashed more than `olderThanDays` ago
+  async purge(olderThanDays: number): Promise<PurgeReport> {
+    return invoke<PurgeReport>('purge_deleted_items', { olderThanDays });
+  },
+
+  async getSettings(): Promise<TrashSettings> {
+    return invoke<TrashSettings>('get_trash_settings');
+  },
+
+  // A retention period has the trash purged automatically in the background
+  async setSettings(settings: TrashSettings): Promise<void> {
+    return invoke<void>('set_trash_settings', { settings });
+  },
+};
@@ -6,11 +6,15 @@ export interface Note {
   content: string;
   folder_id: string | null;
   tags: string[];
+  // Other names the note goes by in [[wikilinks]] and search
+  aliases?: string[];
   is_pinned: boolean;
   is_favorite?: boolean;
   created_at: string;
   updated_at: string;
   deleted_at: string | null;
+  // ISO 639-1 code detected from the text (local database only)
+  language?: string | null;
 }
 
 export interface NoteCreate {
@@ -18,6 +22,7 @@ export interface NoteCreate {
   content?: string;
   folder_id?: string | null;
   tags?: string[];
+  aliases?: string[];
 }
 
 export interface NoteUpdate {
@@ -25,6 +30,7 @@ export interface NoteUpdate {
   content?: string;
   folder_id?: string | null;
   tags?: string[];
+  aliases?: string[];
   is_pinned?: boolean;
 }
 
@@ -51,8 +57,8 @@ export interface FolderCreate {
 export interface FolderUpdate {
   name?: string;
   parent_id?: string | null;
-  color?: string;
-  icon?: string;
+  color?: string | null;
+  icon?: string | null;
 }
 
 // ============ UI Types ============
@@ -68,6 +74,7 @@ export interface ViewOptions {
 }
 
 // ============ Event Types ============
+export type EventType = 'event' | 'task' | 'meeting' | 'reminder' | 'deadline';
 export type TimeMode = 'todo' | 'at_time' | 'all_day' | 'morning' | 'day' | 'evening' | 'anytime';
 export type EventCategory = 'work' | 'meeting' | 'personal' | 'todo';
 export type Priority = 'low' | 'medium' | 'high';
@@ -85,7 +92,8 @@ export interface Event {
   user_id?: string;
   title: string;
   description?: string;
-  event_type?: string;
+  event_type?: EventType;
+  // RFC 3339 in UTC, e.g. 2024-05-01T07:30:00.000Z
   start_time: string | null;
   end_time: string | null;
   has_scheduled_time: boolean;
@@ -106,11 +114,15 @@ export interface Event {
   created_at: string;
   updated_at: string;
   deleted_at?: string | null;
+  // Minutes needed to get there; leave_by is start_time minus that
+  travel_minutes?: number | null;
+  leave_by?: string | null;
 }
 
 export interface EventCreate {
   title: string;
   description?: string;
+  event_type?: EventType;
   start_time?: string | null;
   end_time?: string | null;
   time_mode?: TimeMode;
@@ -125,26 +137,30 @@ export interface EventCreate {
   is_recurring?: boolean;
   recurring_pattern?: RecurringPattern;
   reminders?: EventReminder[];
+  notes?: string;
 }
 
+// In updates a missing field is left alone and null clears it
 export interface EventUpdate {
   title?: string;
-  description?: string;
+  description?: string | null;
+  event_type?: EventType;
   start_time?: string | null;
   end_time?: string | null;
   time_mode?: TimeMode;
-  duration_minutes?: number;
-  location?: string;
-  category?: EventCategory;
-  color?: string;
+  duration_minutes?: number | null;
+  location?: string | null;
+  category?: EventCategory | null;
+  color?: string | null;
   priority?: Priority;
   tags?: string[];
   show_on_calendar?: boolean;
   is_all_day?: boolean;
   is_recurring?: boolean;
-  recurring_pattern?: RecurringPattern;
+  recurring_pattern?: RecurringPattern | null;
   status?: EventStatus;
   reminders?: EventReminder[];
+  notes?: string | null;
 }
 
 // ============ AI Types ============
@@ -191,13 +207,13 @@ export interface BrainMapCreate {
 
 export interface BrainMapUpdate {
   title?: string;
-  description?: string;
-  center_node_id?: string;
+  description?: string | null;
+  center_node_id?: string | null;
   center_node_text?: string;
   viewport_x?: number;
   viewport_y?: number;
   viewport_zoom?: number;
-  theme?: BrainMapTheme;
+  theme?: BrainMapTheme | null;
 }
 
 export interface BrainMapNode {
@@ -296,3 +312,179 @@ export interface RenderedConnection extends BrainMapConnection {
   sourceNode: RenderedNode;
   targetNode: RenderedNode;
 }
+
+// Brain map with collapsed branches folded away (see get_brain_map_visible)
+export type { BrainMapVisibleData } from '../bindings';
+
+// Rows put back by undo_brain_map_op / redo_brain_map_op
+export type { BrainMapOpApplied } from '../bindings';
+
+// Note <-> node links; NoteLinksChanged is the payload of `note:links-changed`
+export type { NoteNodeLink, NoteLinksChanged } from '../bindings';
+
+// Structure overview from get_brain_map_stats
+export type { BrainMapStats, NodeDegree } from '../bindings';
+
+// Node styling shared by presets and apply_style_to_subtree
+export type { NodeStyle, StylePreset, StylePresetCreate } from '../bindings';
+
+// ============ List Types ============
+// Paging, search and sort for get_folders / get_brain_maps
+export type { ListQuery, ListSort, Page, BrainMapSummary } from '../bindings';
+
+// ============ Planner Types ============
+// Filters for the unscheduled backlog (get_unscheduled_events)
+export type { UnscheduledFilter, BacklogSort } from '../bindings';
+
+// Payload of `reminder:due`; kind 'leave' is the "time to leave" alert
+export type { ReminderDue, ReminderKind } from '../bindings';
+
+// Typed locale settings and the week/agenda views built on them
+export type {
+  LocaleSettings, WeekStart, ClockFormat, DateOrder, WeekRange, AgendaDay, AgendaEntry,
+} from '../bindings';
+
+// Everything on the "today" screen in one payload (get_today_view)
+export type { TodayView } from '../bindings';
+
+// Recurrence rules for tasks and their per-occurrence completion history
+export type {
+  RecurrenceRule, RecurrenceFrequency, DayOfWeek, TaskHistory, TaskOccurrence, OccurrenceStatus,
+} from '../bindings';
+
+// Open tasks ordered by their dependencies (get_task_plan)
+export type { TaskPlan, PlannedTask } from '../bindings';
+
+// Projects group notes, events, tasks and brain maps (get_project_overview)
+export type {
+  Project, ProjectCreate, ProjectUpdate, ProjectStatus, ProjectItemKind, ProjectOverview,
+  ProjectStats, ProjectActivity,
+} from '../bindings';
+
+// Goals measured by linked tasks and habits (get_goal_progress)
+export type {
+  Goal, GoalCreate, GoalUpdate, GoalMetric, GoalProgress, GoalItemProgress,
+} from '../bindings';
+
+// Margin comments anchored to a range of a note's content
+export type { NoteComment, NoteCommentCreate, NoteCommentUpdate } from '../bindings';
+
+// Merging another device's changes and the conflict copies it can leave
+export type { RemoteChanges, NoteDoc, SyncReport, ConflictCopy, ConflictResolution } from '../bindings';
+
+// Peer-to-peer sync over the local network; LanSyncResult is also the
+// payload of `lan-sync:received`
+export type { LanSyncConfig, LanPeer, LanSyncResult } from '../bindings';
+
+// Result of parse_date_expression
+export type { ParsedDate } from '../bindings';
+
+// Quick-add inbox (quick_add / triage_inbox_item)
+export type { InboxItem, TriageAction, QuickAddResult, TriageResult } from '../bindings';
+
+// Dashboard totals from get_productivity_metrics
+export type { ProductivityMetrics, BusyDay } from '../bindings';
+
+// Bundled public holiday calendars (get_holidays / get_holiday_regions)
+export type { Holiday, HolidayRegion } from '../bindings';
+
+// Full-text search results and the link indexes (search_notes, get_note_bookmarks)
+export type { SearchHit, NoteBookmark } from '../bindings';
+
+// Wikilinks whose target is gone or was renamed (check_note_links)
+export type { BrokenLink, BrokenLinkReason, LinkSettings, LinkTarget } from '../bindings';
+
+// Heading tree for the table of contents (get_note_outline)
+export type { OutlineHeading } from '../bindings';
+
+// Fragments shown for ![[note#heading]] embeds (resolve_embeds)
+export type { Embed } from '../bindings';
+
+// Notes as they were before automatic edits (get_note_revisions)
+export type { NoteRevision } from '../bindings';
+
+// Weights for ordering search results (get_search_ranking)
+export type { SearchRanking } from '../bindings';
+
+// Saved searches listed with folders
+export type { SmartFolder, SmartFolderCreate, SmartFolderUpdate } from '../bindings';
+
+// Space saved by storing identical attachments once
+export type { Attachment, AttachmentDedupStats } from '../bindings';
+
+// Disk usage by note and folder, and the attachment size warning (get_storage_usage)
+export type {
+  StorageUsage, StorageSettings, NoteStorage, FolderStorage, BackupFile,
+} from '../bindings';
+
+// Typed YAML front matter entries (get_note_properties)
+export type { NoteProperty, PropertyKind } from '../bindings';
+
+// Custom fields on notes, events and folders (get_metadata / set_metadata)
+export type { EntityKind, MetadataEntry } from '../bindings';
+
+// Shared colors and icons for folders, events and map nodes (get_color_palette / get_icon_catalog)
+export type { PaletteColor, CatalogIcon } from '../bindings';
+
+// ============ Error Types ============
+// Rejection payload of create/update commands
+export type { AppError } from '../bindings';
+
+// ============ Job Types ============
+// JobProgress is the payload of the `job:progress` event
+export type { Job, JobProgress, JobReport, JobState } from '../bindings';
+// Import and export formats for the dialogs (list_supported_formats)
+export type { SupportedFormat, FormatDirection, FormatTarget } from '../bindings';
+// Dry run of an import, before confirm_import (preview_import)
+export type { ImportPreview, ImportSummary } from '../bindings';
+
+// Where notes are continuously copied as Markdown (get_mirror_config)
+export type { MirrorConfig } from '../bindings';
+
+// Folders kept in two-way sync with a directory (get_watched_folders)
+export type { WatchedFolder } from '../bindings';
+
+// Spotlight / Windows Search indexing (get_system_search_settings)
+export type { SystemSearchSettings } from '../bindings';
+
+// Content shared from other apps (receive_shared_content)
+export type { SharedContent, ShareResult, ShareDestination, PendingShare } from '../bindings';
+
+// What this build can run (get_platform_capabilities)
+export type { PlatformCapabilities } from '../bindings';
+
+// Background work turned down for battery (get_background_activity)
+export type { BackgroundActivity, ActivityLevel, BackgroundSubsystem } from '../bindings';
+
+// Why the database failed to open at startup (get_startup_problem)
+export type { StartupProblem, StartupProblemKind } from '../bindings';
+
+// Payload of the app:panic event
+export type { PanicReport } from '../bindings';
+
+// Trash retention and what a purge removed (purge_deleted_items)
+export type { TrashSettings, PurgeReport } from '../bindings';
+
+// Outcome of restore_note, which may ask where a note should go
+export type { NoteRestore, RestoreStatus, RestoreFolderAction } from '../bindings';
+
+// Pan and zoom of a map canvas (update_brain_map_viewport)
+export type { BrainMapViewport } from '../bindings';
+
+// How a folder's notes are ordered (get_notes, get_note_sort)
+export type { NoteSort, NoteSortChoice } from '../bindings';
+
+// A trashed note as the trash list shows it (get_deleted_note_preview)
+export type { DeletedNotePreview } from '../bindings';
+
+// Clipboard capture shortcut and the clipboard:captured payload
+export type { ClipboardCaptureSettings, ClipboardCaptured } from '../bindings';
+
+// Text snippets typed by a trigger (get_snippets, expand_snippet)
+export type { Snippet, SnippetCreate, SnippetUpdate } from '../bindings';
+
+// User scripts run on note and event changes (get_scripts)
+export type { Script, ScriptCreate, ScriptUpdate } from '../bindings';
+
+// Backend commands for the command palette and automation rules (list_actions)
+export type { Action, ActionParam, JsonValue } from '../bindings';